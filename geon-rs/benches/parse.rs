@@ -0,0 +1,73 @@
+//! Parser and generator throughput, run with `cargo bench`; name
+//! benchmarks to run only those, e.g. `cargo bench -- corpus`.
+
+use geon_rs::generator::format;
+use geon_rs::parser::parse_many;
+use geon_rs::{generate, parse};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SMALL: &str = "PLACE: Old Market Square\nTYPE: public_space\nLOCATION: 52.9533, -1.1500\n";
+// The full example document from Appendix A of the specification
+const APPENDIX_A: &str = include_str!("appendix_a.geon");
+const CORPUS_SIZE: usize = 10_000;
+
+// Appendix A under `count` different names and locations, as one multi-document text
+fn corpus(count: usize) -> String {
+    let body = APPENDIX_A.split_once("LOCATION: 52.4777, -1.8933\n").map_or(APPENDIX_A, |(_, rest)| rest);
+    (0..count)
+        .map(|i| format!("PLACE: Market {}\nLOCATION: {:.4}, -1.8933\n{}", i, 52.0 + i as f64 / 1e4, body))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Time `f` over enough calls to take about a second, in ten samples, and
+// print the median time per call and the rate over `bytes` of input
+fn bench(name: &str, filters: &[String], bytes: usize, mut f: impl FnMut()) {
+    if !filters.is_empty() && !filters.iter().any(|filter| name.contains(filter.as_str())) {
+        return;
+    }
+    let mut calls = 1;
+    loop {
+        let start = Instant::now();
+        (0..calls).for_each(|_| f());
+        if start.elapsed() > Duration::from_millis(100) {
+            break;
+        }
+        calls *= 2;
+    }
+    let mut samples: Vec<Duration> = (0..10)
+        .map(|_| {
+            let start = Instant::now();
+            (0..calls).for_each(|_| f());
+            start.elapsed() / calls
+        })
+        .collect();
+    samples.sort();
+    let median = samples[samples.len() / 2];
+    let rate = bytes as f64 / median.as_secs_f64() / 1e6;
+    println!("{:<24} {:>12.3?} {:>10.1} MB/s", name, median, rate);
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; anything else names benchmarks to run
+    let filters: Vec<String> = std::env::args().skip(1).filter(|a| !a.starts_with("--")).collect();
+    let corpus = corpus(CORPUS_SIZE);
+    let place = parse(APPENDIX_A);
+
+    bench("parse/small", &filters, SMALL.len(), || {
+        black_box(parse(black_box(SMALL)));
+    });
+    bench("parse/appendix_a", &filters, APPENDIX_A.len(), || {
+        black_box(parse(black_box(APPENDIX_A)));
+    });
+    bench("parse_many/corpus_10k", &filters, corpus.len(), || {
+        black_box(parse_many(black_box(&corpus)));
+    });
+    bench("generate/appendix_a", &filters, APPENDIX_A.len(), || {
+        black_box(generate(black_box(&place)));
+    });
+    bench("format/appendix_a", &filters, APPENDIX_A.len(), || {
+        black_box(format(black_box(APPENDIX_A)).unwrap());
+    });
+}
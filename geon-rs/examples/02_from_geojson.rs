@@ -1,4 +1,4 @@
-use geon_rs::{GeonPlace, generate, from_geojson};
+use geon_rs::{generate, from_geojson};
 use serde_json::json;
 
 fn main() {
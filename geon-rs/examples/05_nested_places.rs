@@ -1,5 +1,5 @@
 use geon_rs::{GeonPlace, Coordinate, generate, parse};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 fn main() {
     // 1. Build a nested place hierarchy
@@ -14,7 +14,7 @@ fn main() {
         "food and beverage".to_string(),
     ];
     
-    let mut experience = HashMap::new();
+    let mut experience = BTreeMap::new();
     experience.insert("activity_density".to_string(), "very_high".to_string());
     experience.insert("noise_level".to_string(), "loud".to_string());
     experience.insert("legibility".to_string(), "medium".to_string());
@@ -26,7 +26,7 @@ fn main() {
         "Victoria Square (300m north)".to_string(),
     ];
 
-    let mut connectivity = HashMap::new();
+    let mut connectivity = BTreeMap::new();
     connectivity.insert("pedestrian_entries".to_string(), "6".to_string());
     connectivity.insert("rail".to_string(), "New Street Station (direct access)".to_string());
     connectivity.insert("public_transport".to_string(), "tram (Corporation Street, 200m)".to_string());
@@ -48,7 +48,7 @@ fn main() {
     child2.area = Some("50000 sqm".to_string());
     child2.purpose = vec!["retail (mixed)".to_string(), "food and beverage".to_string()];
     
-    let mut temp = HashMap::new();
+    let mut temp = BTreeMap::new();
     temp.insert("trading_hours".to_string(), "09:00-20:00 Mon-Sat, 11:00-17:00 Sun".to_string());
     child2.temporal = temp;
     
@@ -120,11 +120,11 @@ UPDATED: 2025-01-20T09:00:00Z
     println!("=== Parsed Appendix A ===");
     println!("Place:       {}", place.place);
     println!("Type:        {}", place.type_);
-    if let Some(id) = place.id { println!("ID:          {}", id); }
-    if let Some(loc) = place.location { println!("Location:    {}", loc); }
+    if let Some(id) = &place.id { println!("ID:          {}", id); }
+    if let Some(loc) = &place.location { println!("Location:    {}", loc); }
     println!("Boundary:    {} points", place.boundary.len());
-    if let Some(area) = place.area { println!("Area:        {}", area); }
-    if let Some(el) = place.elevation { println!("Elevation:   {}", el); }
+    if let Some(area) = &place.area { println!("Area:        {}", area); }
+    if let Some(el) = &place.elevation { println!("Elevation:   {}", el); }
     
     println!("Purposes:    {} items", place.purpose.len());
     println!("Experience:  {} qualities", place.experience.len());
@@ -134,14 +134,14 @@ UPDATED: 2025-01-20T09:00:00Z
     for child in &place.contains {
         println!("  - {} ({})", child.place, child.type_);
     }
-    if let Some(po) = place.part_of { println!("Part of:     {}", po); }
+    if let Some(po) = &place.part_of { println!("Part of:     {}", po); }
     
     // Viewsheds - handled as generic Value in Rust currently if complex
     // Or string list if implemented that way? In models.rs it's just `serde_json::Value`.
     // Let's print recursively format it or just count?
-    if let Some(arr) = place.viewsheds.as_array() {
+    if let Some(arr) = &place.viewsheds.as_array() {
         println!("Viewsheds:   {} views", arr.len());
-    } else if let Some(obj) = place.viewsheds.as_object() {
+    } else if let Some(obj) = &place.viewsheds.as_object() {
         println!("Viewsheds:   {} items", obj.len());
     }
     
@@ -149,7 +149,7 @@ UPDATED: 2025-01-20T09:00:00Z
     println!("Built form:  {} attributes", place.built_form.len());
     println!("Sources:     {} sources", place.source.len());
     println!("Confidence:  {} fields", place.confidence.len());
-    if let Some(upd) = place.updated { println!("Updated:     {}", upd); }
+    if let Some(upd) = &place.updated { println!("Updated:     {}", upd); }
     println!();
     
     // Re-generate
@@ -1,7 +1,7 @@
 use geon_rs::{GeonPlace, Coordinate, generate, parse};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Write;
 
 fn main() -> std::io::Result<()> {
     // 1. Create a sample .geon file in memory
@@ -12,7 +12,7 @@ fn main() -> std::io::Result<()> {
     place.area = Some("14 hectares".to_string());
     place.purpose = vec!["recreation".to_string(), "sport".to_string(), "ecology".to_string(), "events".to_string()];
     
-    let mut exp = HashMap::new();
+    let mut exp = BTreeMap::new();
     exp.insert("openness".to_string(), "high".to_string());
     exp.insert("enclosure".to_string(), "low".to_string());
     exp.insert("activity_density".to_string(), "moderate".to_string());
@@ -47,7 +47,7 @@ fn main() -> std::io::Result<()> {
     println!();
     
     // 3. Modify and save
-    let mut temp = HashMap::new();
+    let mut temp = BTreeMap::new();
     temp.insert("summer_events".to_string(), "concerts every Friday July-August".to_string());
     temp.insert("parkrun".to_string(), "every Saturday 09:00".to_string());
     loaded.temporal = temp;
@@ -0,0 +1,25 @@
+//! Serve the `.geon` files under a directory over HTTP:
+//!
+//! ```sh
+//! cargo run --example 08_serve --features server -- ../geon-vscode 127.0.0.1:8080
+//! curl 'http://127.0.0.1:8080/places?bbox=-1.2,52.9,-1.1,53.0' -H 'Accept: application/geo+json'
+//! ```
+
+use geon_rs::collection::GeonCollection;
+use geon_rs::server::{serve, GeonService};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let dir = args.next().unwrap_or_else(|| ".".to_string());
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+
+    let (collection, errors) = GeonCollection::from_dir(&dir)?;
+    for (path, e) in errors {
+        eprintln!("skipping {}: {}", path.display(), e);
+    }
+    println!("serving {} places on http://{}/places", collection.len(), addr);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    serve(listener, GeonService::new(collection)).await?;
+    Ok(())
+}
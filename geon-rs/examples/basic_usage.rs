@@ -1,5 +1,5 @@
 use geon_rs::{GeonPlace, Coordinate, generate, parse};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 fn main() {
     // 1. Build a GeonPlace programmatically
@@ -25,7 +25,7 @@ fn main() {
         "circulation".to_string(),
     ];
     
-    let mut experience = HashMap::new();
+    let mut experience = BTreeMap::new();
     experience.insert("openness".to_string(), "high".to_string());
     experience.insert("enclosure".to_string(), "medium".to_string());
     experience.insert("accessibility".to_string(), "high".to_string());
@@ -38,13 +38,13 @@ fn main() {
         "Exchange Arcade (southeast corner)".to_string(),
     ];
 
-    let mut connectivity = HashMap::new();
+    let mut connectivity = BTreeMap::new();
     connectivity.insert("pedestrian_entries".to_string(), "6".to_string());
     connectivity.insert("vehicular_access".to_string(), "restricted".to_string());
     connectivity.insert("public_transport".to_string(), "tram (adjacent)".to_string());
     place.connectivity = connectivity;
 
-    let mut temporal = HashMap::new();
+    let mut temporal = BTreeMap::new();
     temporal.insert("weekday_footfall".to_string(), "2000-3000 people/hour".to_string());
     temporal.insert("weekend_events".to_string(), "2-3 per month".to_string());
     temporal.insert("evening_activity".to_string(), "low (20% of daytime)".to_string());
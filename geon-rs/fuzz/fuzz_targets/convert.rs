@@ -0,0 +1,35 @@
+//! Any bytes through the importers that read text or JSON, and a place
+//! built from the same bytes through the exporters and back.
+
+#![no_main]
+
+use geon_rs::converter::csv::{from_csv, CsvMapping};
+use geon_rs::converter::inspire::from_inspire_gml;
+use geon_rs::converter::osm::from_overpass;
+use geon_rs::converter::overture::{from_overture, CategoryMapping};
+use geon_rs::converter::{from_geojson, from_osm_xml, to_geojson};
+use geon_rs::generator::generate;
+use geon_rs::parser::parse;
+use geon_rs::testing::Unstructured;
+use geon_rs::GeonPlace;
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let _ = from_csv(&text, &CsvMapping::default());
+    let _ = from_osm_xml(&text);
+    let _ = from_inspire_gml(&text);
+    if let Ok(value) = serde_json::from_slice::<Value>(data) {
+        from_overpass(&value);
+        from_overture(&value, &CategoryMapping::default());
+        for place in from_geojson(value) {
+            to_geojson(&place);
+            generate(&place);
+        }
+    }
+
+    let place: GeonPlace = Unstructured::new(data).arbitrary();
+    assert_eq!(parse(&generate(&place)), place);
+    from_geojson(to_geojson(&place));
+});
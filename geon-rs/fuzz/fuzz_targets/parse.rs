@@ -0,0 +1,21 @@
+//! Any bytes through the parser, the formatter and the lexer, and the
+//! parsed place back out as text and in again.
+
+#![no_main]
+
+use geon_rs::generator::{format, generate};
+use geon_rs::lexer::tokens;
+use geon_rs::parser::{parse, parse_bytes, parse_many};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    tokens(&text).for_each(drop);
+    parse_many(&text);
+    let formatted = format(&text);
+    parse(&formatted);
+
+    if let Ok(place) = parse_bytes(data) {
+        parse(&generate(&place));
+    }
+});
@@ -0,0 +1,603 @@
+use crate::collection::{Cell, GeonCollection, Grid, Level, SpatialMatch};
+use crate::enrich::elevation::DemSource;
+use crate::geometry::{area_m2, centroid, compass_direction, haversine_m, ring_contains};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::quantity::parse_length;
+use crate::units::Elevation;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "walkshed")]
+pub mod walkshed;
+
+/// Density-based clustering parameters: a place with at least `min_pts`
+/// places (itself included) within `eps_m` metres is a cluster core.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dbscan {
+    pub eps_m: f64,
+    pub min_pts: usize,
+}
+
+/// Cluster labels for the top-level places of a collection, by index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Clustering {
+    /// `None` for noise and for places without LOCATION.
+    pub labels: Vec<Option<usize>>,
+    /// Number of clusters; labels run from 0 to `clusters - 1`.
+    pub clusters: usize,
+}
+
+impl Clustering {
+    /// Indices of the places in one cluster.
+    pub fn members(&self, cluster: usize) -> Vec<usize> {
+        (0..self.labels.len()).filter(|i| self.labels[*i] == Some(cluster)).collect()
+    }
+
+    /// A candidate parent for a cluster, e.g. a market quarter for a dense
+    /// group of stalls: a `district` at the members' mean LOCATION, with their
+    /// combined EXTENT, the PURPOSEs shared by at least half of them, and the
+    /// member names under `extra["members"]`.
+    pub fn proposed_parent(&self, collection: &GeonCollection, cluster: usize, name: &str) -> GeonPlace {
+        let members: Vec<&GeonPlace> = self.members(cluster).into_iter().map(|i| &collection.places[i]).collect();
+        let points: Vec<&Coordinate> = members.iter().filter_map(|p| p.location.as_ref()).collect();
+
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.type_ = "district".to_string();
+        if !points.is_empty() {
+            let n = points.len() as f64;
+            p.location = Some(Coordinate::new(
+                points.iter().map(|c| c.lat).sum::<f64>() / n,
+                points.iter().map(|c| c.lon).sum::<f64>() / n,
+            ));
+            p.extent = Some(Extent {
+                north: points.iter().map(|c| c.lat).fold(f64::MIN, f64::max),
+                south: points.iter().map(|c| c.lat).fold(f64::MAX, f64::min),
+                east: points.iter().map(|c| c.lon).fold(f64::MIN, f64::max),
+                west: points.iter().map(|c| c.lon).fold(f64::MAX, f64::min),
+            });
+        }
+
+        let mut purposes: Vec<(String, usize)> = Vec::new();
+        for purpose in members.iter().flat_map(|m| &m.purpose) {
+            match purposes.iter_mut().find(|(p, _)| p.eq_ignore_ascii_case(purpose)) {
+                Some((_, n)) => *n += 1,
+                None => purposes.push((purpose.clone(), 1)),
+            }
+        }
+        p.purpose = purposes.into_iter().filter(|(_, n)| n * 2 >= members.len()).map(|(p, _)| p).collect();
+        let names = members.iter().map(|m| serde_json::Value::String(m.place.clone())).collect();
+        p.extra.insert("members".to_string(), serde_json::Value::Array(names));
+        p
+    }
+}
+
+/// DBSCAN over the LOCATIONs of the collection's top-level places, using
+/// its spatial index for neighbourhood queries. Clusters are numbered in
+/// order of their first member.
+pub fn cluster(collection: &GeonCollection, params: Dbscan) -> Clustering {
+    let places = &collection.places;
+    let neighbours = |i: usize| -> Vec<usize> {
+        match &places[i].location {
+            Some(at) => collection.radius_indices(at, params.eps_m, SpatialMatch::Location),
+            None => vec![],
+        }
+    };
+
+    let min_pts = params.min_pts.max(1);
+    let mut labels: Vec<Option<usize>> = vec![None; places.len()];
+    let mut visited = vec![false; places.len()];
+    let mut clusters = 0;
+    for i in 0..places.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+        let seeds = neighbours(i);
+        if seeds.len() < min_pts {
+            continue;
+        }
+
+        labels[i] = Some(clusters);
+        let mut queue: VecDeque<usize> = seeds.into();
+        while let Some(j) = queue.pop_front() {
+            // Border points join the first cluster to reach them
+            if labels[j].is_none() {
+                labels[j] = Some(clusters);
+            }
+            if visited[j] {
+                continue;
+            }
+            visited[j] = true;
+            let reach = neighbours(j);
+            if reach.len() >= min_pts {
+                queue.extend(reach.into_iter().filter(|k| !visited[*k] || labels[*k].is_none()));
+            }
+        }
+        clusters += 1;
+    }
+    Clustering { labels, clusters }
+}
+
+/// Eye height in metres of an observer standing at a place.
+pub const OBSERVER_HEIGHT_M: f64 = 1.6;
+
+// Earth radius for line-of-sight drop, lengthened for standard atmospheric
+// refraction (k = 0.13)
+const SIGHT_RADIUS_M: f64 = 6_371_008.8 / (1.0 - 0.13);
+
+/// For each of `targets` with a LOCATION on the DEM, whether it can be seen
+/// from this place's LOCATION over the terrain between them, taking the
+/// target's `BUILT_FORM` `height` as standing above the ground. Visible
+/// targets are written into VIEWSHEDS keyed by name, with `distance_m`,
+/// `direction` (`"northeast"` ...) and `prominence_deg`, the angle by which
+/// the target's top clears the skyline in front of it; targets that turn out
+/// hidden are removed. VIEWSHEDS in any other shape than this map is
+/// replaced. Returns the number of visible targets.
+pub fn compute_viewsheds(place: &mut GeonPlace, dem: &impl DemSource, targets: &[GeonPlace]) -> usize {
+    let Some(from) = place.location.clone() else { return 0 };
+    let Some(ground) = dem.elevation_at(&from) else { return 0 };
+    let eye = ground + OBSERVER_HEIGHT_M;
+    let mut entries = match std::mem::take(&mut place.viewsheds) {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+
+    let mut visible = 0;
+    for target in targets {
+        let Some(to) = &target.location else { continue };
+        let distance = haversine_m(&from, to);
+        if target.place == place.place || distance == 0.0 {
+            continue;
+        }
+        let Some(base) = dem.elevation_at(to) else { continue };
+        let height = target.built_form.get("height").and_then(|h| parse_length(h)).unwrap_or(0.0);
+        // Angle above horizontal from the eye to a height at a distance
+        let angle = |h: f64, d: f64| ((h - d * d / (2.0 * SIGHT_RADIUS_M) - eye) / d).atan().to_degrees();
+        let top = angle(base + height, distance);
+
+        let steps = ((distance / dem.resolution_m().max(1.0)).ceil() as usize).clamp(2, 10_000);
+        let skyline = (1..steps)
+            .filter_map(|i| {
+                let t = i as f64 / steps as f64;
+                let at = Coordinate::new(from.lat + (to.lat - from.lat) * t, from.lon + (to.lon - from.lon) * t);
+                dem.elevation_at(&at).map(|h| angle(h, distance * t))
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        if top >= skyline {
+            visible += 1;
+            let prominence = if skyline.is_finite() { top - skyline } else { top };
+            entries.insert(
+                target.place.clone(),
+                json!({
+                    "distance_m": distance.round(),
+                    "direction": compass_direction(&from, to),
+                    "prominence_deg": (prominence * 100.0).round() / 100.0,
+                }),
+            );
+        } else {
+            entries.remove(&target.place);
+        }
+    }
+    place.viewsheds = Value::Object(entries);
+    visible
+}
+
+/// Gradient statistics along a linear place such as a street, from
+/// [`elevation_profile`]. Gradients are rises over run, in percent, either
+/// way along the line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationProfile {
+    pub length_m: f64,
+    pub total_climb_m: f64,
+    pub total_descent_m: f64,
+    pub max_gradient: f64,
+    /// Over the whole line, climbs and descents alike.
+    pub mean_gradient: f64,
+    pub lowest_m: f64,
+    pub highest_m: f64,
+}
+
+impl ElevationProfile {
+    /// Write the profile into VERTICAL_PROFILE, e.g. `max_gradient: 8.2%`,
+    /// `total_climb: 14 m`, keeping any other keys.
+    pub fn apply(&self, place: &mut GeonPlace) {
+        let metres = |v: f64| Elevation(v).to_string();
+        let percent = |v: f64| format!("{}%", (v * 10.0).round() / 10.0);
+        for (key, value) in [
+            ("length", metres(self.length_m)),
+            ("total_climb", metres(self.total_climb_m)),
+            ("total_descent", metres(self.total_descent_m)),
+            ("max_gradient", percent(self.max_gradient)),
+            ("mean_gradient", percent(self.mean_gradient)),
+            ("lowest", metres(self.lowest_m)),
+            ("highest", metres(self.highest_m)),
+        ] {
+            place.vertical_profile.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Sample the DEM along a place's PATH, or its BOUNDARY points in order
+/// where it has none, at the DEM's resolution. Gradients are measured between samples
+/// at least that far apart, so a single noisy cell does not read as a
+/// cliff. `None` for fewer than two points or where the line leaves the DEM.
+pub fn elevation_profile(place: &GeonPlace, dem: &impl DemSource) -> Option<ElevationProfile> {
+    let line = if place.path.is_empty() { &place.boundary } else { &place.path };
+    if line.len() < 2 {
+        return None;
+    }
+    let step = dem.resolution_m().max(1.0);
+    // (distance along the line, elevation)
+    let mut samples: Vec<(f64, f64)> = vec![(0.0, dem.elevation_at(&line[0])?)];
+    let mut along = 0.0;
+    for w in line.windows(2) {
+        let length = haversine_m(&w[0], &w[1]);
+        let n = (length / step).ceil().max(1.0) as usize;
+        for i in 1..=n {
+            let t = i as f64 / n as f64;
+            let at = Coordinate::new(w[0].lat + (w[1].lat - w[0].lat) * t, w[0].lon + (w[1].lon - w[0].lon) * t);
+            samples.push((along + length * t, dem.elevation_at(&at)?));
+        }
+        along += length;
+    }
+
+    let (mut climb, mut descent, mut max_gradient) = (0.0, 0.0, 0.0f64);
+    // Latest sample at least `step` behind the current one
+    let mut back = 0;
+    for j in 1..samples.len() {
+        let (d, h) = samples[j];
+        let rise = h - samples[j - 1].1;
+        if rise > 0.0 {
+            climb += rise;
+        } else {
+            descent -= rise;
+        }
+        while back + 1 < j && d - samples[back + 1].0 >= step * 0.999 {
+            back += 1;
+        }
+        let run = d - samples[back].0;
+        if run >= step * 0.999 {
+            max_gradient = max_gradient.max((h - samples[back].1).abs() / run * 100.0);
+        }
+    }
+    let heights = samples.iter().map(|s| s.1);
+    Some(ElevationProfile {
+        length_m: along,
+        total_climb_m: climb,
+        total_descent_m: descent,
+        max_gradient,
+        mean_gradient: if along > 0.0 { (climb + descent) / along * 100.0 } else { 0.0 },
+        lowest_m: heights.clone().fold(f64::INFINITY, f64::min),
+        highest_m: heights.fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+/// The mean level of one EXPERIENCE quality in one grid cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceCell {
+    pub cell: Cell,
+    pub extent: Extent,
+    /// From 1 (the lowest step of the quality's scale) to 5 (the highest).
+    pub mean: f64,
+    /// Places in the cell with a readable value.
+    pub places: usize,
+}
+
+/// An EXPERIENCE quality summarised over a grid by
+/// [`aggregate_experience`], one entry per occupied cell in cell order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperienceSurface {
+    pub quality: String,
+    pub cells: Vec<SurfaceCell>,
+}
+
+impl ExperienceSurface {
+    /// A GeoJSON FeatureCollection of cell polygons with `cell`, `quality`,
+    /// `mean` and `places` properties, ready to style as a choropleth.
+    pub fn to_geojson(&self) -> Value {
+        let features: Vec<Value> = self
+            .cells
+            .iter()
+            .map(|c| {
+                let e = &c.extent;
+                let ring = [[e.west, e.south], [e.east, e.south], [e.east, e.north], [e.west, e.north], [e.west, e.south]];
+                json!({
+                    "type": "Feature",
+                    "geometry": {"type": "Polygon", "coordinates": [ring]},
+                    "properties": {
+                        "cell": c.cell.to_string(),
+                        "quality": self.quality,
+                        "mean": (c.mean * 100.0).round() / 100.0,
+                        "places": c.places,
+                    }
+                })
+            })
+            .collect();
+        json!({"type": "FeatureCollection", "features": features})
+    }
+}
+
+/// Average the level of the EXPERIENCE `quality` (such as
+/// `activity_density`) over every place in the collection, nested ones
+/// included, by the grid cell of its LOCATION or BOUNDARY centroid. Values
+/// are read with [`Level::from_value`], so `busy (weekends)` counts as the
+/// fourth step; places without a position or a readable value are skipped.
+pub fn aggregate_experience(collection: &GeonCollection, quality: &str, grid: Grid) -> ExperienceSurface {
+    fn visit(places: &[GeonPlace], quality: &str, grid: Grid, sums: &mut BTreeMap<Cell, (f64, usize)>) {
+        for p in places {
+            let level = p.experience.get(quality).and_then(|v| Level::from_value(v));
+            let at = p.location.clone().or_else(|| centroid(&p.boundary));
+            if let (Some(level), Some(at)) = (level, at) {
+                let sum = sums.entry(grid.cell(&at)).or_default();
+                sum.0 += level as u8 as f64 + 1.0;
+                sum.1 += 1;
+            }
+            visit(&p.contains, quality, grid, sums);
+        }
+    }
+    let mut sums = BTreeMap::new();
+    visit(&collection.places, quality, grid, &mut sums);
+    let cells = sums
+        .into_iter()
+        .filter_map(|(cell, (total, n))| {
+            Some(SurfaceCell { cell, extent: grid.bounds(&cell)?, mean: total / n as f64, places: n })
+        })
+        .collect();
+    ExperienceSurface { quality: quality.to_string(), cells }
+}
+
+/// Set the PART_OF of each top-level place to the name of the smallest of
+/// `boundaries` (wards, neighbourhoods) whose BOUNDARY contains its
+/// LOCATION, or its BOUNDARY's centroid where it has no LOCATION. Places
+/// outside every boundary are left alone. With `nest`, contained places
+/// also move into the CONTAINS of a copy of their boundary, which takes the
+/// place of the first of them in the collection; they no longer have files
+/// of their own. Returns the number of places assigned.
+pub fn assign_part_of(collection: &mut GeonCollection, boundaries: &GeonCollection, nest: bool) -> usize {
+    let assignments: Vec<Option<usize>> = collection
+        .places
+        .iter()
+        .map(|p| {
+            let at = p.location.clone().or_else(|| centroid(&p.boundary))?;
+            boundaries
+                .radius_indices(&at, 0.0, SpatialMatch::Intersects)
+                .into_iter()
+                .filter(|b| boundaries.places[*b].boundary.len() >= 3 && ring_contains(&boundaries.places[*b].boundary, &at))
+                .min_by(|a, b| area_m2(&boundaries.places[*a].boundary).total_cmp(&area_m2(&boundaries.places[*b].boundary)))
+        })
+        .collect();
+    let assigned = assignments.iter().flatten().count();
+    for (place, b) in collection.places.iter_mut().zip(&assignments) {
+        if let Some(b) = b {
+            place.part_of = Some(boundaries.places[*b].place.clone());
+        }
+    }
+    if !nest || assigned == 0 {
+        collection.reindex();
+        return assigned;
+    }
+
+    // Output places with their files, and where each boundary's copy went
+    let mut out: Vec<(GeonPlace, Option<PathBuf>)> = Vec::new();
+    let mut parents: HashMap<usize, usize> = HashMap::new();
+    let old = std::mem::take(collection);
+    for (i, place) in old.places.iter().enumerate() {
+        match assignments[i] {
+            Some(b) => {
+                let slot = *parents.entry(b).or_insert_with(|| {
+                    let mut parent = boundaries.places[b].clone();
+                    parent.contains.clear();
+                    out.push((parent, boundaries.path(b).map(Path::to_path_buf)));
+                    out.len() - 1
+                });
+                out[slot].0.contains.push(place.clone());
+            }
+            None => out.push((place.clone(), old.path(i).map(Path::to_path_buf))),
+        }
+    }
+    for (place, path) in out {
+        match path {
+            Some(path) => collection.push_from(place, path),
+            None => collection.push(place),
+        }
+    }
+    collection.reindex();
+    assigned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrich::elevation::Dem;
+
+    #[test]
+    fn test_dbscan() {
+        let place = |name: &str, lat: f64, lon: f64, purpose: &str| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.type_ = "building".to_string();
+            p.location = Some(Coordinate::new(lat, lon));
+            p.purpose = vec![purpose.to_string()];
+            p
+        };
+        let collection = GeonCollection::new(vec![
+            place("Fish Stall", 52.95530, -1.14480, "market trading"),
+            place("Cheese Stall", 52.95535, -1.14470, "market trading"),
+            place("Bakery Stall", 52.95525, -1.14460, "market trading"),
+            place("Flower Stall", 52.95540, -1.14490, "retail"),
+            place("Lone Kiosk", 52.95800, -1.14000, "retail"),
+            place("Bus Shelter", 52.96200, -1.13000, "waiting"),
+            place("Bus Stop", 52.96205, -1.13005, "waiting"),
+        ]);
+
+        let clustering = cluster(&collection, Dbscan { eps_m: 30.0, min_pts: 3 });
+        assert_eq!(clustering.clusters, 1);
+        assert_eq!(clustering.labels, [Some(0), Some(0), Some(0), Some(0), None, None, None]);
+
+        let pairs = cluster(&collection, Dbscan { eps_m: 30.0, min_pts: 2 });
+        assert_eq!(pairs.clusters, 2);
+        assert_eq!(pairs.members(1), [5, 6]);
+
+        let quarter = clustering.proposed_parent(&collection, 0, "Market Quarter");
+        assert_eq!(quarter.type_, "district");
+        assert_eq!(quarter.purpose, ["market trading"]);
+        assert_eq!(quarter.extent.as_ref().unwrap().north, 52.9554);
+        assert_eq!(quarter.extra["members"][3], "Flower Stall");
+    }
+
+    #[test]
+    fn test_compute_viewsheds() {
+        // A valley running east-west: flat at 10 m with a 60 m ridge at
+        // column 5, on a 0.001° (~111 m) grid
+        let cols = 11;
+        let values = (0..3).flat_map(|_| (0..cols).map(|c| if c == 5 { 60.0 } else { 10.0 })).collect();
+        let dem = Dem {
+            name: "valley".to_string(),
+            origin: Coordinate::new(0.002, 0.0),
+            step: (0.001, 0.001),
+            cols,
+            rows: 3,
+            nodata: None,
+            values,
+        };
+        let landmark = |name: &str, lon: f64, height: &str| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = Some(Coordinate::new(0.001, lon));
+            if !height.is_empty() {
+                p.built_form.insert("height".to_string(), height.to_string());
+            }
+            p
+        };
+        let targets = [
+            landmark("Mill", 0.003, ""),
+            landmark("Cottage", 0.009, ""),
+            landmark("Church Spire", 0.009, "200 m"),
+            landmark("Offshore", 0.5, ""),
+        ];
+
+        let mut viewpoint = landmark("Viewpoint", 0.001, "");
+        viewpoint.viewsheds = json!({"Cottage": "east", "Castle": "north"});
+        assert_eq!(compute_viewsheds(&mut viewpoint, &dem, &targets), 2);
+        let views = viewpoint.viewsheds.as_object().unwrap();
+        assert_eq!(views["Mill"]["direction"], "east");
+        assert_eq!(views["Mill"]["distance_m"], 222.0);
+        assert!(views["Church Spire"]["prominence_deg"].as_f64().unwrap() > 0.0);
+        assert!(!views.contains_key("Cottage"));
+        assert_eq!(views["Castle"], "north");
+
+        let mut nowhere = GeonPlace::default();
+        assert_eq!(compute_viewsheds(&mut nowhere, &dem, &targets), 0);
+    }
+
+    #[test]
+    fn test_elevation_profile() {
+        // Ground rising 5 m every 0.001° (~111 m) eastwards, then level
+        let cols = 8;
+        let row: Vec<f64> = (0..cols).map(|c| 20.0 + 5.0 * (c.min(4) as f64)).collect();
+        let dem = Dem {
+            name: "slope".to_string(),
+            origin: Coordinate::new(0.001, 0.0),
+            step: (0.001, 0.001),
+            cols,
+            rows: 2,
+            nodata: None,
+            values: row.iter().chain(&row).copied().collect(),
+        };
+        let mut street = GeonPlace::default();
+        street.type_ = "street".to_string();
+        street.path = vec![Coordinate::new(0.0005, 0.0), Coordinate::new(0.0005, 0.003), Coordinate::new(0.0005, 0.007)];
+
+        let profile = elevation_profile(&street, &dem).unwrap();
+        assert!((profile.length_m - 778.4).abs() < 0.5, "{}", profile.length_m);
+        assert!((profile.total_climb_m - 20.0).abs() < 1e-6);
+        assert_eq!(profile.total_descent_m, 0.0);
+        assert!((profile.max_gradient - 4.5).abs() < 0.05, "{}", profile.max_gradient);
+        assert_eq!((profile.lowest_m, profile.highest_m), (20.0, 40.0));
+
+        profile.apply(&mut street);
+        assert_eq!(street.vertical_profile["total_climb"], "20 m");
+        assert_eq!(street.vertical_profile["max_gradient"], "4.5%");
+
+        street.path.push(Coordinate::new(0.0005, 0.02));
+        assert_eq!(elevation_profile(&street, &dem), None);
+    }
+
+    #[test]
+    fn test_aggregate_experience() {
+        let place = |lat: f64, lon: f64, activity: &str| {
+            let mut p = GeonPlace::default();
+            p.location = Some(Coordinate::new(lat, lon));
+            p.experience.insert("activity_density".to_string(), activity.to_string());
+            p
+        };
+        let mut market = place(52.953, -1.149, "very_high");
+        market.contains.push(place(52.954, -1.148, "busy (weekends)"));
+        let collection = GeonCollection::new(vec![
+            market,
+            place(52.955, -1.145, "medium"),
+            place(52.93, -1.12, "low"),
+            place(52.93, -1.12, "unknown"),
+        ]);
+
+        let surface = aggregate_experience(&collection, "activity_density", Grid::degrees(0.01));
+        assert_eq!(surface.cells.len(), 2);
+        let centre = surface.cells.iter().find(|c| c.places == 3).unwrap();
+        assert!((centre.mean - 4.0).abs() < 1e-9);
+        assert!(centre.extent.south <= 52.953 && centre.extent.north >= 52.955);
+
+        let geojson = surface.to_geojson();
+        assert_eq!(geojson["features"].as_array().unwrap().len(), 2);
+        assert_eq!(geojson["features"][0]["geometry"]["type"], "Polygon");
+        assert_eq!(geojson["features"][0]["properties"]["quality"], "activity_density");
+
+        let tiles = aggregate_experience(&collection, "activity_density", Grid::tiles(14));
+        assert!(tiles.cells.iter().all(|c| c.extent.north > c.extent.south && c.extent.east > c.extent.west));
+        assert!(aggregate_experience(&collection, "noise", Grid::tiles(14)).cells.is_empty());
+    }
+
+    #[test]
+    fn test_assign_part_of() {
+        let square = |name: &str, west: f64, south: f64, size: f64| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.boundary = vec![
+                Coordinate::new(south, west),
+                Coordinate::new(south, west + size),
+                Coordinate::new(south + size, west + size),
+                Coordinate::new(south + size, west),
+            ];
+            p
+        };
+        let point = |name: &str, lat: f64, lon: f64| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = Some(Coordinate::new(lat, lon));
+            p
+        };
+        let mut boundaries = GeonCollection::default();
+        boundaries.push_from(square("City", 0.0, 0.0, 0.1), "city.geon");
+        boundaries.push_from(square("Castle Ward", 0.0, 0.0, 0.02), "wards/castle.geon");
+        let mut places = GeonCollection::default();
+        places.push_from(point("Castle", 0.01, 0.01), "castle.geon");
+        places.push_from(point("Arboretum", 0.05, 0.05), "arboretum.geon");
+        places.push_from(point("Elsewhere", 1.0, 1.0), "elsewhere.geon");
+        places.push_from(square("Gatehouse", 0.005, 0.005, 0.001), "gatehouse.geon");
+
+        let mut flat = places.clone();
+        assert_eq!(assign_part_of(&mut flat, &boundaries, false), 3);
+        let part_of: Vec<Option<&str>> = flat.iter().map(|p| p.part_of.as_deref()).collect();
+        assert_eq!(part_of, [Some("Castle Ward"), Some("City"), None, Some("Castle Ward")]);
+
+        assert_eq!(assign_part_of(&mut places, &boundaries, true), 3);
+        let names: Vec<&str> = places.iter().map(|p| p.place.as_str()).collect();
+        assert_eq!(names, ["Castle Ward", "City", "Elsewhere"]);
+        assert_eq!(places.places[0].contains.len(), 2);
+        assert_eq!(places.places[0].contains[1].place, "Gatehouse");
+        assert_eq!(places.path(0), Some(Path::new("wards/castle.geon")));
+        assert_eq!(places.path(2), Some(Path::new("elsewhere.geon")));
+    }
+}
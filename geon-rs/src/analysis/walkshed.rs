@@ -0,0 +1,239 @@
+//! How far a person can walk from a place in a few minutes.
+//!
+//! Reach is measured along a pedestrian [`Network`] built from path lines,
+//! or read from isochrones already computed by a routing service
+//! (OpenRouteService, Valhalla). Either way it is summarised in CONNECTIVITY
+//! as `walk_<n>min: <area> ha`, with the isochrone rings optionally kept in
+//! `extra["isochrones"]` as GeoJSON polygons keyed by minutes.
+
+use crate::converter::positions;
+use crate::geometry::{area_m2, haversine_m, hull, Hull};
+use crate::models::{Coordinate, GeonPlace};
+use crate::units::{Area, AreaUnit};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Typical adult walking pace, 4.8 km/h.
+pub const WALKING_SPEED_M_PER_MIN: f64 = 80.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkshedOptions {
+    pub speed_m_per_min: f64,
+    /// Walking times to report, in minutes.
+    pub minutes: Vec<u32>,
+    /// Keep each isochrone ring in `extra["isochrones"]`.
+    pub isochrones: bool,
+}
+
+impl Default for WalkshedOptions {
+    fn default() -> Self {
+        Self { speed_m_per_min: WALKING_SPEED_M_PER_MIN, minutes: vec![5, 10, 15], isochrones: false }
+    }
+}
+
+/// A pedestrian path network. Lines join wherever they share a vertex
+/// exactly, as OSM ways do at junctions.
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    pub nodes: Vec<Coordinate>,
+    // Neighbours of each node with the path length to them in metres
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+// Frontier entry for Dijkstra, ordered nearest first
+#[derive(PartialEq)]
+struct Step(f64, usize);
+
+impl Eq for Step {}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Network {
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a [Coordinate]>) -> Self {
+        let mut network = Network::default();
+        let mut ids: HashMap<(u64, u64), usize> = HashMap::new();
+        for line in lines {
+            let mut previous: Option<usize> = None;
+            for c in line {
+                let id = *ids.entry((c.lat.to_bits(), c.lon.to_bits())).or_insert_with(|| {
+                    network.nodes.push(c.clone());
+                    network.adjacency.push(Vec::new());
+                    network.nodes.len() - 1
+                });
+                if let Some(p) = previous.filter(|p| *p != id) {
+                    let length = haversine_m(&network.nodes[p], c);
+                    network.adjacency[p].push((id, length));
+                    network.adjacency[id].push((p, length));
+                }
+                previous = Some(id);
+            }
+        }
+        network
+    }
+
+    /// The LineStrings and MultiLineStrings of a GeoJSON FeatureCollection,
+    /// e.g. footways exported from OSM.
+    pub fn from_geojson(collection: &Value) -> Self {
+        let mut lines: Vec<Vec<Coordinate>> = Vec::new();
+        for feature in collection.get("features").and_then(|v| v.as_array()).into_iter().flatten() {
+            let Some(geom) = feature.get("geometry") else { continue };
+            let coords = geom.get("coordinates").unwrap_or(&Value::Null);
+            match geom.get("type").and_then(|v| v.as_str()) {
+                Some("LineString") => lines.push(positions(coords)),
+                Some("MultiLineString") => lines.extend(coords.as_array().into_iter().flatten().map(positions)),
+                _ => {}
+            }
+        }
+        Self::from_lines(lines.iter().map(Vec::as_slice))
+    }
+
+    /// Walking distance in metres to every node within `max_m` of `from`,
+    /// starting with a straight walk to the nearest node.
+    pub fn reach(&self, from: &Coordinate, max_m: f64) -> Vec<(usize, f64)> {
+        let Some((start, offset)) =
+            self.nodes.iter().map(|n| haversine_m(from, n)).enumerate().min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            return vec![];
+        };
+        let mut best = vec![f64::INFINITY; self.nodes.len()];
+        let mut frontier = BinaryHeap::from([Step(offset, start)]);
+        best[start] = offset;
+        while let Some(Step(cost, node)) = frontier.pop() {
+            if cost > best[node] || cost > max_m {
+                continue;
+            }
+            for &(next, length) in &self.adjacency[node] {
+                if cost + length < best[next] {
+                    best[next] = cost + length;
+                    frontier.push(Step(cost + length, next));
+                }
+            }
+        }
+        best.into_iter().enumerate().filter(|(_, d)| *d <= max_m).collect()
+    }
+}
+
+// Write one walking time's summary, and ring if wanted
+fn record(place: &mut GeonPlace, minutes: u32, ring: &[Coordinate], keep_ring: bool) {
+    let area = Area(area_m2(ring)).format(AreaUnit::Hectares, 1);
+    place.connectivity.insert(format!("walk_{}min", minutes), area);
+    if keep_ring && ring.len() >= 3 {
+        let closing = ring.first().filter(|_| ring.first() != ring.last());
+        let coords: Vec<Value> = ring.iter().chain(closing).map(|c| json!([c.lon, c.lat])).collect();
+        let isochrones = place.extra.entry("isochrones".to_string()).or_insert_with(|| Value::Object(Map::new()));
+        if !isochrones.is_object() {
+            *isochrones = Value::Object(Map::new());
+        }
+        isochrones[minutes.to_string()] = json!({"type": "Polygon", "coordinates": [coords]});
+    }
+}
+
+/// Walk `network` from the place's LOCATION for each of the option's
+/// walking times, and record the area of a concave hull around the nodes
+/// reached. Returns `false`, changing nothing, if the place has no LOCATION
+/// or the network is empty.
+pub fn walkshed(place: &mut GeonPlace, network: &Network, options: &WalkshedOptions) -> bool {
+    let Some(from) = place.location.clone() else { return false };
+    let longest = options.minutes.iter().max().copied().unwrap_or(0) as f64 * options.speed_m_per_min;
+    let reached = network.reach(&from, longest);
+    if reached.is_empty() {
+        return false;
+    }
+    for &minutes in &options.minutes {
+        let budget = minutes as f64 * options.speed_m_per_min;
+        let mut points: Vec<Coordinate> = vec![from.clone()];
+        points.extend(reached.iter().filter(|(_, d)| *d <= budget).map(|(i, _)| network.nodes[*i].clone()));
+        // Dig into gaps wider than a tenth of the walk, so the hull follows
+        // the paths rather than bridging blocks they don't reach
+        let ring = hull(&points, Hull::Concave((budget / 10.0).max(50.0)));
+        record(place, minutes, &ring, options.isochrones);
+    }
+    true
+}
+
+/// Record isochrones from a routing service's GeoJSON FeatureCollection:
+/// OpenRouteService (`value` in seconds) or Valhalla (`contour` in
+/// minutes). Each polygon's outer ring is used. Returns how many were
+/// recorded.
+pub fn apply_isochrones(place: &mut GeonPlace, collection: &Value, keep_rings: bool) -> usize {
+    let mut recorded = 0;
+    for feature in collection.get("features").and_then(|v| v.as_array()).into_iter().flatten() {
+        let props = feature.get("properties").unwrap_or(&Value::Null);
+        let minutes = match (props.get("value").and_then(|v| v.as_f64()), props.get("contour").and_then(|v| v.as_f64())) {
+            (Some(seconds), _) => seconds / 60.0,
+            (None, Some(minutes)) => minutes,
+            (None, None) => continue,
+        };
+        let Some(geom) = feature.get("geometry") else { continue };
+        let ring = match geom.get("type").and_then(|v| v.as_str()) {
+            Some("Polygon") => geom.pointer("/coordinates/0").map(positions),
+            Some("LineString") => geom.get("coordinates").map(positions),
+            _ => None,
+        };
+        if let Some(ring) = ring.filter(|r| r.len() >= 3) {
+            record(place, minutes.round() as u32, &ring, keep_rings);
+            recorded += 1;
+        }
+    }
+    recorded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walkshed_on_grid() {
+        // Streets every 0.002° (~220 m) on a 5 x 5 grid, plus a long spur
+        let step = 0.002;
+        let mut lines: Vec<Vec<Coordinate>> = Vec::new();
+        for i in 0..5 {
+            let v = i as f64 * step;
+            lines.push((0..5).map(|j| Coordinate::new(v, j as f64 * step)).collect());
+            lines.push((0..5).map(|j| Coordinate::new(j as f64 * step, v)).collect());
+        }
+        lines.push(vec![Coordinate::new(0.008, 0.008), Coordinate::new(0.05, 0.008)]);
+        let network = Network::from_lines(lines.iter().map(Vec::as_slice));
+        assert_eq!(network.nodes.len(), 26);
+
+        let mut square = GeonPlace::default();
+        square.location = Some(Coordinate::new(0.004, 0.004));
+        let options = WalkshedOptions { isochrones: true, ..Default::default() };
+        assert!(walkshed(&mut square, &network, &options));
+
+        let hectares = |key: &str| square.connectivity[key].trim_end_matches(" ha").parse::<f64>().unwrap();
+        // 400 m reaches the four neighbouring junctions
+        assert!((hectares("walk_5min") - 9.9).abs() < 0.2, "{}", square.connectivity["walk_5min"]);
+        assert!(hectares("walk_5min") < hectares("walk_10min"));
+        assert!(hectares("walk_10min") <= hectares("walk_15min"));
+        assert_eq!(square.extra["isochrones"]["5"]["type"], "Polygon");
+
+        let mut lost = GeonPlace::default();
+        assert!(!walkshed(&mut lost, &network, &options));
+        assert!(lost.connectivity.is_empty());
+    }
+
+    #[test]
+    fn test_apply_isochrones() {
+        let ring = json!([[0.0, 0.0], [0.01, 0.0], [0.01, 0.01], [0.0, 0.01], [0.0, 0.0]]);
+        let ors = json!({"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"value": 600.0}, "geometry": {"type": "Polygon", "coordinates": [ring]}},
+            {"type": "Feature", "properties": {}, "geometry": {"type": "Polygon", "coordinates": [ring]}}
+        ]});
+        let mut place = GeonPlace::default();
+        assert_eq!(apply_isochrones(&mut place, &ors, false), 1);
+        assert_eq!(place.connectivity["walk_10min"], "123.6 ha");
+        assert!(!place.extra.contains_key("isochrones"));
+    }
+}
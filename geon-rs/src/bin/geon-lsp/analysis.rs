@@ -0,0 +1,402 @@
+//! What the server knows about a document: diagnostics, completions and
+//! hover text. Positions are zero-based lines and UTF-16 columns, as LSP
+//! counts them.
+
+use geon_rs::lexer::{tokens, TokenKind};
+use geon_rs::parser::parse_many;
+use geon_rs::validate::{lint_collection, LintConfig, Severity};
+use geon_rs::vocabulary::{find_section, scale_for, scales_with, SectionKind, EXPERIENCE_KEYS, SECTIONS, TYPES};
+use geon_rs::{GeonCollection, Level};
+use serde_json::{json, Value};
+
+/// A problem on one line, from `start` to `end` (UTF-16 columns).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub severity: Severity,
+    pub message: String,
+    /// Lint rule, for problems the validator found.
+    pub rule: Option<&'static str>,
+}
+
+impl Diagnostic {
+    pub fn to_lsp(&self) -> Value {
+        let severity = match self.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+            Severity::Info => 3,
+        };
+        let mut value = json!({
+            "range": {"start": {"line": self.line, "character": self.start}, "end": {"line": self.line, "character": self.end}},
+            "severity": severity,
+            "source": "geon",
+            "message": self.message,
+        });
+        if let Some(rule) = self.rule {
+            value["code"] = json!(rule);
+        }
+        value
+    }
+}
+
+/// A completion candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub detail: String,
+    pub insert: String,
+    /// LSP CompletionItemKind: 14 for sections, 12 for values, 10 for keys.
+    pub kind: u32,
+}
+
+impl Completion {
+    pub fn to_lsp(&self) -> Value {
+        json!({"label": self.label, "detail": self.detail, "insertText": self.insert, "kind": self.kind})
+    }
+}
+
+// A content line: not blank, not a comment
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    content: &'a str,
+}
+
+impl<'a> Line<'a> {
+    // The text after a list marker
+    fn body(&self) -> &'a str {
+        self.content.strip_prefix("- ").unwrap_or(self.content)
+    }
+
+    fn key_value(&self) -> Option<(&'a str, &'a str)> {
+        self.body().split_once(':').map(|(k, v)| (k.trim(), v.trim()))
+    }
+
+    // The GEON section this line opens, matched exactly (sections are upper case)
+    fn section(&self) -> Option<(&'static geon_rs::vocabulary::Section, &'a str)> {
+        let (key, value) = self.key_value()?;
+        SECTIONS.iter().find(|s| s.key == key).map(|s| (s, value))
+    }
+}
+
+fn content_lines(text: &str) -> Vec<Line<'_>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|(number, l)| Line { number, indent: l.len() - l.trim_start().len(), content: l.trim() })
+        .collect()
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+// Byte offset in `line` of UTF-16 column `character`
+fn byte_index(line: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= character {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+fn at(line: &Line, severity: Severity, message: String) -> Diagnostic {
+    let start = line.indent;
+    Diagnostic { line: line.number, start, end: start + utf16_len(line.content), severity, message, rule: None }
+}
+
+fn coordinate(text: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = text.split_once(',')?;
+    let (lat, lon): (f64, f64) = (lat.trim().parse().ok()?, lon.trim().parse().ok()?);
+    Some((lat, lon)).filter(|_| (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon))
+}
+
+// The nearest earlier line indented less than `lines[i]`
+fn parent<'l, 'a>(lines: &'l [Line<'a>], i: usize) -> Option<&'l Line<'a>> {
+    lines[..i].iter().rev().find(|l| l.indent < lines[i].indent)
+}
+
+// Lines directly inside `lines[i]`
+fn children<'l, 'a>(lines: &'l [Line<'a>], i: usize) -> impl Iterator<Item = &'l Line<'a>> {
+    let indent = lines[i].indent;
+    lines[i + 1..].iter().take_while(move |l| l.indent > indent).filter(move |l| l.indent == indent + 2)
+}
+
+// Problems the parser would silently skip over, line by line
+fn line_diagnostics(lines: &[Line]) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.indent > 0 {
+            let expected = parent(lines, i).map_or(0, |p| p.indent + 2);
+            if line.indent != expected {
+                let message = format!("indented {} spaces where {} are expected; the parser skips this line", line.indent, expected);
+                out.push(at(line, Severity::Warning, message));
+                continue;
+            }
+        } else {
+            match line.key_value() {
+                None => out.push(at(line, Severity::Error, "expected `KEY: value`".to_string())),
+                Some((key, _)) if find_section(key).is_none() => {
+                    out.push(at(line, Severity::Warning, format!("'{}' is not a GEON section; the parser keeps it as an extra field", key)))
+                }
+                _ => {}
+            }
+        }
+
+        let Some((section, value)) = line.section() else { continue };
+        match section.kind {
+            SectionKind::Text if value.is_empty() => {
+                out.push(at(line, Severity::Warning, format!("{} needs a value on the same line", section.key)))
+            }
+            SectionKind::Coordinate if coordinate(value).is_none() => {
+                out.push(at(line, Severity::Error, format!("{} should be `lat, lon` in degrees, not '{}'", section.key, value)))
+            }
+            SectionKind::Coordinates => {
+                for point in children(lines, i).filter(|p| coordinate(p.body()).is_none()) {
+                    out.push(at(point, Severity::Error, format!("'{}' is not a `lat, lon` point", point.body())));
+                }
+            }
+            _ if section.key == "EXPERIENCE" => {
+                for quality in children(lines, i) {
+                    let Some((key, level)) = quality.key_value() else { continue };
+                    if !level.is_empty() && Level::from_value(level).is_none() {
+                        let message = format!("'{}' is not on a GEON scale; {} takes {}", level, key, scale_for(key).join(", "));
+                        out.push(at(quality, Severity::Info, message));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+// The line an issue about `place`'s `field` belongs on: the field inside
+// the place's block, else the line naming the place
+fn issue_line<'l, 'a>(lines: &'l [Line<'a>], place: &str, field: &str) -> Option<&'l Line<'a>> {
+    let names = |l: &Line| matches!(l.key_value(), Some(("PLACE" | "ID", value)) if value == place);
+    let anchor = lines.iter().position(names)?;
+    // Keys of a list item start at its marker's indent plus two
+    let depth = lines[anchor].indent + if lines[anchor].content.starts_with("- ") { 2 } else { 0 };
+    let next_document = |l: &&Line| l.indent == 0 && l.content.starts_with("PLACE:");
+    let mut block = std::iter::once(&lines[anchor])
+        .chain(lines[anchor + 1..].iter().take_while(|l| l.indent >= depth && !next_document(l)));
+    let found = block.find(|l| l.key_value().is_some_and(|(k, _)| k == field) && (l.indent == depth || l.number == lines[anchor].number));
+    Some(found.unwrap_or(&lines[anchor]))
+}
+
+/// Everything wrong with a document: lines the parser would skip or
+/// misread, then what [`lint_collection`] finds in the places it reads.
+pub fn diagnostics(text: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let lines = content_lines(text);
+    let mut out = line_diagnostics(&lines);
+    let collection = GeonCollection::new(parse_many(text));
+    for issue in lint_collection(&collection, config) {
+        let line = issue_line(&lines, &issue.place, &issue.field);
+        let (number, start, end) = match line {
+            Some(l) => (l.number, l.indent, l.indent + utf16_len(l.content)),
+            None => (0, 0, 0),
+        };
+        let message = format!("{}: {}", issue.field, issue.message);
+        out.push(Diagnostic { line: number, start, end, severity: issue.severity, message, rule: Some(issue.rule) });
+    }
+    out
+}
+
+/// Semantic token types, in the order [`semantic_tokens`] indexes them.
+pub const TOKEN_TYPES: &[&str] = &["property", "string", "number", "operator", "comment"];
+
+/// The document's [`tokens`] in LSP's relative encoding: five numbers per
+/// token (line delta, start delta, length, type, modifiers). Indents are
+/// left out.
+pub fn semantic_tokens(text: &str) -> Vec<u32> {
+    let line_starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let mut data = Vec::new();
+    let (mut last_line, mut last_start) = (0, 0);
+    for token in tokens(text) {
+        let kind = match token.kind {
+            TokenKind::Indent => continue,
+            TokenKind::Key => 0,
+            TokenKind::Value => 1,
+            TokenKind::Coordinate => 2,
+            TokenKind::ListMarker => 3,
+            TokenKind::Comment => 4,
+        };
+        let start = utf16_len(&text[line_starts[token.line]..token.span.start]);
+        let delta_start = if token.line == last_line { start - last_start } else { start };
+        data.extend([(token.line - last_line) as u32, delta_start as u32, utf16_len(token.text) as u32, kind, 0]);
+        (last_line, last_start) = (token.line, start);
+    }
+    data
+}
+
+fn starts_with_ignoring_case(word: &str, prefix: &str) -> bool {
+    word.len() >= prefix.len() && word[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+/// Completions at `line`, `character`: section keys at the start of a
+/// line, EXPERIENCE qualities inside EXPERIENCE, TYPE values and scale
+/// words after the colon.
+pub fn complete(text: &str, line: usize, character: usize) -> Vec<Completion> {
+    let current = text.lines().nth(line).unwrap_or("");
+    let prefix = &current[..byte_index(current, character)];
+    let indent = prefix.len() - prefix.trim_start().len();
+    let typed = prefix.trim_start();
+    let body = typed.strip_prefix("- ").unwrap_or(typed);
+
+    // The line this one sits inside, from the lines above it
+    let above = content_lines(text).into_iter().filter(|l| l.number < line).collect::<Vec<_>>();
+    let parent = above.iter().rev().find(|l| l.indent < indent);
+    let parent_key = parent.and_then(|p| p.key_value()).map(|(k, _)| k);
+
+    let value = |word: &str, detail: String| Completion { label: word.to_string(), detail, insert: word.to_string(), kind: 12 };
+    if let Some((key, partial)) = body.split_once(':') {
+        let (key, partial) = (key.trim(), partial.trim_start());
+        let words: Vec<Completion> = match (parent_key, key) {
+            (_, "TYPE") if indent == 0 || typed.starts_with("- ") || parent.is_some_and(|p| p.content.starts_with("- ")) => {
+                TYPES.iter().map(|t| value(t, "GEON place type".to_string())).collect()
+            }
+            (Some("EXPERIENCE"), quality) => {
+                let scale = scale_for(quality);
+                scale.iter().enumerate().map(|(step, w)| value(w, format!("step {} of 5", step + 1))).collect()
+            }
+            (Some("CONFIDENCE"), _) => ["high", "medium", "low"].iter().map(|w| value(w, "confidence".to_string())).collect(),
+            _ => Vec::new(),
+        };
+        return words.into_iter().filter(|c| starts_with_ignoring_case(&c.label, partial)).collect();
+    }
+
+    let candidates: Vec<Completion> = match parent {
+        // Top level, or the keys of a nested place
+        None => sections(),
+        Some(p) if p.content.starts_with("- ") && p.section().is_some() => sections(),
+        Some(_) if parent_key == Some("CONTAINS") => {
+            sections().into_iter().filter(|c| c.label == "PLACE").collect()
+        }
+        Some(_) if parent_key == Some("EXPERIENCE") => EXPERIENCE_KEYS
+            .iter()
+            .map(|(key, scale)| Completion {
+                label: key.to_string(),
+                detail: geon_rs::vocabulary::SCALES[*scale].join(" · "),
+                insert: format!("{}: ", key),
+                kind: 10,
+            })
+            .collect(),
+        Some(_) => Vec::new(),
+    };
+    candidates.into_iter().filter(|c| starts_with_ignoring_case(&c.label, body)).collect()
+}
+
+fn sections() -> Vec<Completion> {
+    SECTIONS
+        .iter()
+        .map(|s| {
+            let insert = match s.kind {
+                SectionKind::Text | SectionKind::Coordinate => format!("{}: ", s.key),
+                _ => format!("{}:", s.key),
+            };
+            Completion { label: s.key.to_string(), detail: s.summary.to_string(), insert, kind: 14 }
+        })
+        .collect()
+}
+
+/// Markdown describing the word at `line`, `character`: a section, a
+/// scale word or a place type.
+pub fn hover(text: &str, line: usize, character: usize) -> Option<String> {
+    let current = text.lines().nth(line)?;
+    let at = byte_index(current, character);
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let start = current[..at].rfind(|c: char| !is_word(c)).map_or(0, |i| i + 1);
+    let end = current[at..].find(|c: char| !is_word(c)).map_or(current.len(), |i| at + i);
+    let word = &current[start..end];
+    if word.is_empty() {
+        return None;
+    }
+
+    if let Some(section) = SECTIONS.iter().find(|s| s.key == word) {
+        return Some(format!("**{}**\n\n{}", section.key, section.summary));
+    }
+    let scales = scales_with(word);
+    if !scales.is_empty() {
+        let lines: Vec<String> =
+            scales.iter().map(|(scale, step)| format!("step {} of 5 on {}", step + 1, scale.join(" · "))).collect();
+        return Some(format!("`{}`: {}", word, lines.join("; ")));
+    }
+    if TYPES.contains(&word) {
+        return Some(format!("`{}`: GEON place type", word));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "\
+PLACE: Arboretum
+TYPE: public_space
+LOCATION: 52.96, 200
+SMELL: cut grass
+EXPERIENCE:
+  noise: loud
+  crowding: heaving
+BOUNDARY:
+  - 52.96, -1.16
+  - north gate
+CONTAINS:
+  - PLACE: Bandstand
+     TYPE: landmark
+";
+
+    #[test]
+    fn test_diagnostics() {
+        let found: Vec<(usize, Severity, String)> =
+            diagnostics(DOC, &LintConfig::default()).into_iter().map(|d| (d.line, d.severity, d.message)).collect();
+        assert!(found.contains(&(2, Severity::Error, "LOCATION should be `lat, lon` in degrees, not '52.96, 200'".to_string())));
+        assert!(found.contains(&(3, Severity::Warning, "'SMELL' is not a GEON section; the parser keeps it as an extra field".to_string())));
+        assert!(found.iter().any(|(line, severity, m)| *line == 6 && *severity == Severity::Info && m.contains("deserted, sparse")));
+        assert!(found.contains(&(9, Severity::Error, "'north gate' is not a `lat, lon` point".to_string())));
+        assert!(found.iter().any(|(line, _, m)| *line == 12 && m.starts_with("indented 5 spaces where 4")));
+        // The validator's issues land on the place's own lines
+        assert!(found.contains(&(7, Severity::Error, "BOUNDARY: ring has only 1 distinct points".to_string())));
+        assert!(diagnostics("PLACE: Kiosk\nTYPE: building\nLOCATION: 52.9, -1.1\n", &LintConfig::default()).is_empty());
+        let hall = "PLACE: Hall\nLOCATION: 52.9, -1.1\nCONTAINS:\n  - PLACE: Room\n    TYPE: room\n";
+        let missing_type = diagnostics(hall, &LintConfig::default());
+        assert_eq!((missing_type[0].line, missing_type[0].rule), (0, Some("missing-type")));
+    }
+
+    #[test]
+    fn test_complete() {
+        let labels = |text: &str, line: usize, character: usize| -> Vec<String> {
+            complete(text, line, character).into_iter().map(|c| c.label).collect()
+        };
+        assert_eq!(labels("PLACE: A\nEXP", 1, 3), ["EXPERIENCE"]);
+        assert_eq!(labels("PLACE: A\nTYPE: bu", 1, 8), ["building"]);
+        assert_eq!(labels("PLACE: A\nEXPERIENCE:\n  noise: ", 2, 9), ["very_quiet", "quiet", "moderate", "loud", "very_loud"]);
+        assert_eq!(labels("PLACE: A\nEXPERIENCE:\n  crowd", 2, 7), ["crowding"]);
+        assert_eq!(labels("PLACE: A\nCONTAINS:\n  - PLACE: B\n    LOC", 3, 7), ["LOCATION"]);
+        assert!(labels("PLACE: A\nPURPOSE:\n  - ", 2, 4).is_empty());
+    }
+
+    #[test]
+    fn test_hover() {
+        assert!(hover(DOC, 4, 3).unwrap().starts_with("**EXPERIENCE**"));
+        assert_eq!(hover(DOC, 5, 10).unwrap(), "`loud`: step 4 of 5 on very_quiet · quiet · moderate · loud · very_loud");
+        assert_eq!(hover(DOC, 1, 8).unwrap(), "`public_space`: GEON place type");
+        assert_eq!(hover(DOC, 0, 9), None);
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        let data = semantic_tokens("PLACE: Café\n# note\nBOUNDARY:\n  - 52.9, -1.1\n");
+        assert_eq!(
+            data.chunks(5).collect::<Vec<_>>(),
+            [[0, 0, 5, 0, 0], [0, 7, 4, 1, 0], [1, 0, 6, 4, 0], [1, 0, 8, 0, 0], [1, 2, 1, 3, 0], [0, 2, 10, 2, 0]]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! `geon-lsp`: a Language Server Protocol server for `.geon` files.
+//!
+//! Speaks LSP over standard input and output with whole-document sync.
+//! It publishes diagnostics as documents change (lines the parser would
+//! skip or misread, then the validator's issues under the workspace's
+//! `.geonlint.toml`), completes section keys, EXPERIENCE qualities, TYPE
+//! values and scale words, describes them on hover, highlights them with
+//! [`geon_rs::lexer`]'s tokens and formats documents with
+//! [`geon_rs::generator::format`].
+
+mod analysis;
+mod rpc;
+
+use geon_rs::validate::LintConfig;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+// JSON-RPC error for requests the server doesn't handle
+const METHOD_NOT_FOUND: i64 = -32601;
+// LSP error for a request that was understood but couldn't be carried out
+const REQUEST_FAILED: i64 = -32803;
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, String>,
+    config: LintConfig,
+    shutdown: bool,
+}
+
+// The local path of a `file://` URI
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.bytes();
+    while let Some(b) = rest.next() {
+        match b {
+            b'%' => {
+                let hex = [rest.next()?, rest.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+impl Server {
+    // Lint settings from the workspace root, falling back to the defaults
+    fn load_config(&mut self, params: &Value) {
+        let root = params["rootUri"].as_str().and_then(uri_path).or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+        let Some(path) = root.map(|r| r.join(".geonlint.toml")) else { return };
+        match std::fs::read_to_string(&path).map(|text| LintConfig::from_toml(&text)) {
+            Ok(Ok(config)) => self.config = config,
+            Ok(Err(e)) => eprintln!("geon-lsp: {}: {}", path.display(), e),
+            Err(_) => {}
+        }
+    }
+
+    fn publish(&self, uri: &str) -> Value {
+        let diagnostics: Vec<Value> = match self.documents.get(uri) {
+            Some(text) => analysis::diagnostics(text, &self.config).iter().map(|d| d.to_lsp()).collect(),
+            None => Vec::new(),
+        };
+        json!({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {"uri": uri, "diagnostics": diagnostics}})
+    }
+
+    // The result of a request, or an error code and message
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        let document = || {
+            let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+            self.documents.get(uri).map(String::as_str).unwrap_or_default()
+        };
+        let position = || {
+            let p = &params["position"];
+            (p["line"].as_u64().unwrap_or(0) as usize, p["character"].as_u64().unwrap_or(0) as usize)
+        };
+        match method {
+            "initialize" => {
+                self.load_config(params);
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "completionProvider": {"triggerCharacters": [":", " "]},
+                        "hoverProvider": true,
+                        "documentFormattingProvider": true,
+                        "semanticTokensProvider": {
+                            "legend": {"tokenTypes": analysis::TOKEN_TYPES, "tokenModifiers": []},
+                            "full": true,
+                        },
+                    },
+                    "serverInfo": {"name": "geon-lsp", "version": env!("CARGO_PKG_VERSION")},
+                }))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/completion" => {
+                let (line, character) = position();
+                Ok(analysis::complete(document(), line, character).iter().map(|c| c.to_lsp()).collect())
+            }
+            "textDocument/hover" => {
+                let (line, character) = position();
+                Ok(match analysis::hover(document(), line, character) {
+                    Some(text) => json!({"contents": {"kind": "markdown", "value": text}}),
+                    None => Value::Null,
+                })
+            }
+            "textDocument/semanticTokens/full" => Ok(json!({"data": analysis::semantic_tokens(document())})),
+            "textDocument/formatting" => {
+                let text = document();
+                let formatted = geon_rs::generator::format(text).map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+                if formatted == text {
+                    return Ok(json!([]));
+                }
+                // Replace everything: end past the last line covers any trailing text
+                let end = json!({"line": text.lines().count() + 1, "character": 0});
+                Ok(json!([{"range": {"start": {"line": 0, "character": 0}, "end": end}, "newText": formatted}]))
+            }
+            other => Err((METHOD_NOT_FOUND, format!("unhandled method {}", other))),
+        }
+    }
+
+    // Messages to send in reply to a notification
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                vec![self.publish(&uri)]
+            }
+            // Whole-document sync: the last change holds the full text
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array().map(Vec::as_slice).unwrap_or_default();
+                match changes.last().and_then(|c| c["text"].as_str()) {
+                    Some(text) => {
+                        self.documents.insert(uri.clone(), text.to_string());
+                        vec![self.publish(&uri)]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![self.publish(&uri)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut input = BufReader::new(io::stdin().lock());
+    let mut output = io::stdout().lock();
+    let mut server = Server::default();
+    loop {
+        let message = match rpc::read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return ExitCode::from(1),
+            Err(e) => {
+                eprintln!("geon-lsp: {}", e);
+                return ExitCode::from(1);
+            }
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        if method == "exit" {
+            return if server.shutdown { ExitCode::SUCCESS } else { ExitCode::from(1) };
+        }
+        let replies = match message.get("id") {
+            // Responses to requests the server never sends are ignored
+            Some(_) if method.is_empty() => Vec::new(),
+            Some(id) => vec![match server.request(method, params) {
+                Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                Err((code, text)) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": text}}),
+            }],
+            None => server.notify(method, params),
+        };
+        for reply in replies {
+            if let Err(e) = rpc::write_message(&mut output, &reply) {
+                eprintln!("geon-lsp: {}", e);
+                return ExitCode::from(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! JSON-RPC messages framed with `Content-Length` headers, as the Language
+//! Server Protocol sends them over standard input and output.
+
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// The next message, or `None` at end of input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "message without Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_framing() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &json!({"jsonrpc": "2.0", "method": "exit", "params": {"name": "Café"}})).unwrap();
+        write_message(&mut buf, &json!({"jsonrpc": "2.0", "id": 1, "result": null})).unwrap();
+        let mut input = io::Cursor::new(buf);
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["params"]["name"], "Café");
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["id"], 1);
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+}
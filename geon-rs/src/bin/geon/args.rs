@@ -0,0 +1,92 @@
+//! Command-line arguments: positionals, `--name value`, `--name=value`,
+//! bare `--flag`s and a few one-letter aliases.
+
+// One-letter aliases for long options
+const SHORT: &[(&str, &str)] = &[("o", "output"), ("f", "from"), ("t", "to"), ("q", "quiet")];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// Split `raw` into positionals and options; options named in `valued`
+    /// take the next argument as their value when not given with `=`.
+    /// Everything after `--` is positional.
+    pub fn parse(raw: impl IntoIterator<Item = String>, valued: &[&str]) -> Result<Self, String> {
+        let mut args = Args::default();
+        let mut raw = raw.into_iter();
+        while let Some(arg) = raw.next() {
+            let name = if arg == "--" {
+                args.positional.extend(raw.by_ref());
+                break;
+            } else if let Some(long) = arg.strip_prefix("--") {
+                long.to_string()
+            } else if let Some(short) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+                let (letter, rest) = short.split_at(short.chars().next().map_or(0, char::len_utf8));
+                let long = SHORT.iter().find(|(s, _)| *s == letter).map(|(_, l)| *l).ok_or(format!("unknown option -{}", letter))?;
+                if rest.is_empty() { long.to_string() } else { format!("{}={}", long, rest) }
+            } else {
+                args.positional.push(arg);
+                continue;
+            };
+            match name.split_once('=') {
+                Some((name, value)) => args.options.push((name.to_string(), Some(value.to_string()))),
+                None if valued.contains(&name.as_str()) => {
+                    let value = raw.next().ok_or(format!("--{} needs a value", name))?;
+                    args.options.push((name, Some(value)));
+                }
+                None => args.options.push((name, None)),
+            }
+        }
+        Ok(args)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    /// The last value given for `name`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values(name).pop()
+    }
+
+    /// Every value given for a repeatable option, in order.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|(n, _)| n == name).filter_map(|(_, v)| v.as_deref()).collect()
+    }
+
+    /// Fail on any option not in `known`, so typos don't pass silently.
+    pub fn only(&self, known: &[&str]) -> Result<(), String> {
+        match self.options.iter().find(|(n, _)| !known.contains(&n.as_str())) {
+            Some((name, _)) => Err(format!("unknown option --{}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|s| s.to_string()), &["to", "output"])
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&["in.geon", "--to", "geojson", "-oout.json", "--pretty", "--tag=a", "--tag=b"]).unwrap();
+        assert_eq!(args.positional, ["in.geon"]);
+        assert_eq!(args.value("to"), Some("geojson"));
+        assert_eq!(args.value("output"), Some("out.json"));
+        assert!(args.flag("pretty") && !args.flag("quiet"));
+        assert_eq!(args.values("tag"), ["a", "b"]);
+        assert!(args.only(&["to", "output", "pretty"]).is_err());
+
+        let args = parse(&["-", "--", "--odd-name.geon"]).unwrap();
+        assert_eq!(args.positional, ["-", "--odd-name.geon"]);
+        assert_eq!(parse(&["--to"]).unwrap_err(), "--to needs a value");
+        assert_eq!(parse(&["-x"]).unwrap_err(), "unknown option -x");
+    }
+}
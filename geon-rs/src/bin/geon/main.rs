@@ -0,0 +1,535 @@
+//! `geon`: work with GEON files from the shell.
+//!
+//! Every command reads the files named on the command line, or standard
+//! input when there are none (or for `-`), and writes to standard output
+//! unless given `-o FILE`. Exit status is 0 on success, 1 when a check
+//! finds problems and 2 when the command itself fails.
+
+mod args;
+mod sarif;
+
+use args::Args;
+use geon_rs::parser::parse_many;
+use geon_rs::validate::{lint_collection, validate_collection, LintConfig, Profile, Severity};
+use geon_rs::converter::csv::{from_csv, CsvMapping};
+use geon_rs::converter::osm::{ElementType, OverpassQuery};
+use geon_rs::converter::overture::{from_overture, CategoryMapping};
+use geon_rs::converter::{to_gpx, to_jsonld, to_kml};
+use geon_rs::{from_geojson_with, generate, to_geojson_collection, Coordinate, GeonCollection, GeonError, GeonPlace, TypeMapping};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: geon <command> [options] [files...]
+
+commands:
+  parse [FILE]                 GEON text to JSON
+  generate [FILE]              JSON places to GEON text
+  validate [PATH...]           check files or directories of .geon files
+  lint [PATH...]               validate, plus the rules of a profile
+       [--config FILE]         (default: .geonlint.toml, if present)
+       [--profile PROFILE]     minimal, recommended or strict
+       [--format FORMAT]       text, json or sarif
+  convert [PATH] --to FORMAT   convert places from geon, geojson, json, csv
+          [--from FORMAT]      or overture to geon, geojson, json, kml, gpx or
+                               jsonld (default: from the file extensions)
+          [--mapping FILE]     type mapping for geojson or overture input
+                               (JSON, or TOML by extension)
+          [--collection]       write one .geon file per place under -o DIR;
+                               PATH may be a directory of .geon files
+  stats [PATH...] [--json]     summary of a corpus: type counts, field
+                               coverage, confidence and bounding box
+        [--coverage]           per-field values and section keys instead
+  fetch osm --around LAT,LON   query OpenStreetMap through Overpass; with
+            [--radius METRES]  -o DIR, write one .geon file per result
+            [--bbox S,W,N,E]   (instead of --around)
+            [--tag KEY[=VALUE]...] [--element node|way|relation...]
+            [--timeout SECONDS] [--endpoint URL]
+            [--dry-run]        print the Overpass QL instead of sending it
+  diff OLD NEW                 field-level changes between two files
+  fmt [PATH...] [--check]      rewrite files in canonical style; with
+                               --check, list unformatted files instead
+
+options:
+  -o, --output FILE            write to FILE instead of standard output
+  -q, --quiet                  validate: no summary line
+  -h, --help                   show this help";
+
+// Options taking a value, across all commands
+const VALUED: &[&str] = &[
+    "output", "from", "to", "config", "profile", "format", "mapping", "around", "radius", "bbox", "tag", "element",
+    "timeout", "endpoint",
+];
+
+// Lint settings read when no --config is given
+const LINT_CONFIG: &str = ".geonlint.toml";
+
+type Result<T> = std::result::Result<T, String>;
+type GeonResult<T> = std::result::Result<T, GeonError>;
+
+fn main() -> ExitCode {
+    let mut raw = std::env::args().skip(1);
+    let Some(command) = raw.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let result = Args::parse(raw, VALUED).and_then(|args| match command.as_str() {
+        "parse" => parse(&args),
+        "generate" => generate_cmd(&args),
+        "validate" => validate(&args),
+        "lint" => lint(&args),
+        "convert" => convert(&args),
+        "stats" => stats(&args),
+        "diff" => diff(&args),
+        "fetch" => fetch(&args),
+        "fmt" => fmt(&args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(true)
+        }
+        other => Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+    });
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("geon: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+// The text of `path`, or of standard input for `None` or `-`
+fn read_input(path: Option<&str>) -> Result<String> {
+    match path {
+        None | Some("-") => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|e| format!("reading standard input: {}", e))?;
+            Ok(text)
+        }
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e)),
+    }
+}
+
+// At most one input file
+fn single_input(args: &Args) -> Result<Option<&str>> {
+    match args.positional.as_slice() {
+        [] => Ok(None),
+        [one] => Ok(Some(one)),
+        _ => Err("expected at most one input file".to_string()),
+    }
+}
+
+fn write_output(args: &Args, text: &str) -> Result<()> {
+    let text = if text.ends_with('\n') { text.to_string() } else { format!("{}\n", text) };
+    match args.value("output") {
+        Some(path) => std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+// A JSON place, or an array of them
+fn places_from_json(text: &str) -> Result<Vec<GeonPlace>> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let one = |v: serde_json::Value| serde_json::from_value::<GeonPlace>(v).map_err(|e| format!("invalid place: {}", e));
+    match value {
+        serde_json::Value::Array(items) => items.into_iter().map(one).collect(),
+        other => Ok(vec![one(other)?]),
+    }
+}
+
+fn generate_all(places: &[GeonPlace]) -> String {
+    places.iter().map(generate).collect::<Vec<_>>().join("\n")
+}
+
+// `.geon` files under `dir`, recursively and in path order
+fn geon_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            geon_files(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "geon") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Places from files and directories of .geon files, or standard input
+fn load(paths: &[String]) -> Result<GeonCollection> {
+    if paths.is_empty() {
+        return Ok(GeonCollection::new(parse_many(&read_input(None)?)));
+    }
+    let mut collection = GeonCollection::default();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            let (dir, errors) = GeonCollection::from_dir(path).map_err(|e| format!("{}: {}", path, e))?;
+            for (file, e) in errors {
+                eprintln!("geon: skipping {}: {}", file.display(), e);
+            }
+            for (i, place) in dir.places.iter().enumerate() {
+                match dir.path(i) {
+                    Some(file) => collection.push_from(place.clone(), Path::new(path).join(file)),
+                    None => collection.push(place.clone()),
+                }
+            }
+        } else {
+            for place in parse_many(&read_input(Some(path))?) {
+                collection.push_from(place, path);
+            }
+        }
+    }
+    Ok(collection)
+}
+
+fn parse(args: &Args) -> Result<bool> {
+    args.only(&["output"])?;
+    let places = parse_many(&read_input(single_input(args)?)?);
+    let json = match places.as_slice() {
+        [one] => to_json(one)?,
+        many => to_json(&many)?,
+    };
+    write_output(args, &json)?;
+    Ok(true)
+}
+
+fn generate_cmd(args: &Args) -> Result<bool> {
+    args.only(&["output"])?;
+    let places = places_from_json(&read_input(single_input(args)?)?)?;
+    write_output(args, &generate_all(&places))?;
+    Ok(true)
+}
+
+fn validate(args: &Args) -> Result<bool> {
+    args.only(&["quiet"])?;
+    let collection = load(&args.positional)?;
+    let issues = validate_collection(&collection);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+    if !args.flag("quiet") {
+        eprintln!("{} place(s), {} error(s), {} other issue(s)", collection.len(), errors, issues.len() - errors);
+    }
+    Ok(errors == 0)
+}
+
+fn lint_config(args: &Args) -> Result<LintConfig> {
+    let path = match args.value("config") {
+        Some(path) => Some(path),
+        None => Some(LINT_CONFIG).filter(|p| Path::new(p).is_file()),
+    };
+    let mut config = match path {
+        Some(path) => LintConfig::from_toml(&read_input(Some(path))?).map_err(|e| format!("{}: {}", path, e))?,
+        None => LintConfig::default(),
+    };
+    if let Some(profile) = args.value("profile") {
+        config.profile = serde_json::from_value::<Profile>(profile.into()).map_err(|_| format!("unknown profile '{}'", profile))?;
+    }
+    Ok(config)
+}
+
+fn lint(args: &Args) -> Result<bool> {
+    args.only(&["output", "config", "profile", "format"])?;
+    let config = lint_config(args)?;
+    let collection = load(&args.positional)?;
+
+    // Issues name places by ID or name; find the file each came from
+    let mut files: HashMap<&str, String> = HashMap::new();
+    for (i, place) in collection.places.iter().enumerate() {
+        if let Some(path) = collection.path(i) {
+            for key in place.id.iter().chain([&place.place]) {
+                files.entry(key).or_insert_with(|| path.display().to_string());
+            }
+        }
+    }
+    let issues: Vec<_> = lint_collection(&collection, &config)
+        .into_iter()
+        .map(|issue| {
+            let file = files.get(issue.place.as_str()).cloned();
+            (issue, file)
+        })
+        .collect();
+
+    let out = match args.value("format").unwrap_or("text") {
+        "text" => issues
+            .iter()
+            .map(|(issue, file)| match file {
+                Some(file) => format!("{}: {} ({})\n", file, issue, issue.rule),
+                None => format!("{} ({})\n", issue, issue.rule),
+            })
+            .collect(),
+        "json" => {
+            let records: Vec<_> = issues
+                .iter()
+                .map(|(issue, file)| {
+                    let mut record = serde_json::to_value(issue).expect("issues serialise");
+                    record["file"] = file.as_deref().into();
+                    record
+                })
+                .collect();
+            to_json(&records)?
+        }
+        "sarif" => to_json(&sarif::report(&issues))?,
+        other => return Err(format!("unknown format '{}'", other)),
+    };
+    if !out.is_empty() {
+        write_output(args, &out)?;
+    }
+    Ok(issues.iter().all(|(issue, _)| issue.severity != Severity::Error))
+}
+
+// A format named on the command line or implied by a file extension
+fn format_of(name: Option<&str>, path: Option<&str>) -> Option<String> {
+    let name = name.or_else(|| Path::new(path?).extension()?.to_str())?;
+    Some(name.to_lowercase())
+}
+
+// A mapping file, JSON or TOML by extension
+fn read_mapping<T>(path: &str, json: fn(&str) -> GeonResult<T>, toml: fn(&str) -> GeonResult<T>) -> Result<T> {
+    let text = read_input(Some(path))?;
+    let read = if path.ends_with(".toml") { toml } else { json };
+    read(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn read_json(text: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))
+}
+
+fn convert(args: &Args) -> Result<bool> {
+    args.only(&["output", "from", "to", "mapping", "collection"])?;
+    let input = single_input(args)?;
+    let from = format_of(args.value("from"), input).unwrap_or_else(|| "geon".to_string());
+    let to = match args.flag("collection") {
+        true => "geon".to_string(),
+        false => format_of(args.value("to"), args.value("output")).ok_or("--to is required")?,
+    };
+    let mapping = args.value("mapping");
+    if mapping.is_some() && !matches!(from.as_str(), "geojson" | "overture") {
+        return Err("--mapping applies to geojson and overture input".to_string());
+    }
+
+    let collection = match from.as_str() {
+        "geon" => load(&args.positional)?,
+        other => {
+            let text = read_input(input)?;
+            let places = match other {
+                "geojson" => {
+                    let mapping = match mapping {
+                        Some(path) => read_mapping(path, TypeMapping::from_json, TypeMapping::from_toml)?,
+                        None => TypeMapping::default(),
+                    };
+                    from_geojson_with(read_json(&text)?, &mapping)
+                }
+                "overture" => {
+                    let mapping = match mapping {
+                        Some(path) => read_mapping(path, CategoryMapping::from_json, CategoryMapping::from_toml)?,
+                        None => CategoryMapping::default(),
+                    };
+                    from_overture(&read_json(&text)?, &mapping)
+                }
+                "json" => places_from_json(&text)?,
+                "csv" => from_csv(&text, &CsvMapping::default()).map_err(|e| e.to_string())?,
+                other => return Err(format!("cannot read '{}'", other)),
+            };
+            GeonCollection::new(places)
+        }
+    };
+
+    if args.flag("collection") {
+        let dir = args.value("output").ok_or("--collection needs -o DIR")?;
+        let written = collection.save_to_dir(dir).map_err(|e| format!("{}: {}", dir, e))?;
+        eprintln!("wrote {} file(s) to {}", written.len(), dir);
+        return Ok(true);
+    }
+    let places = &collection.places;
+    let out = match to.as_str() {
+        "geon" => generate_all(places),
+        "geojson" => to_json(&to_geojson_collection(places, &Default::default()))?,
+        "json" => to_json(places)?,
+        "kml" => to_kml(places),
+        "gpx" => to_gpx(places),
+        "jsonld" => to_json(&to_jsonld(places))?,
+        other => return Err(format!("cannot write '{}'", other)),
+    };
+    write_output(args, &out)?;
+    Ok(true)
+}
+
+fn stats(args: &Args) -> Result<bool> {
+    args.only(&["output", "json", "coverage"])?;
+    let collection = load(&args.positional)?;
+    let out = match (args.flag("coverage"), args.flag("json")) {
+        (false, false) => collection.stats().to_string(),
+        (false, true) => to_json(&collection.stats())?,
+        (true, false) => collection.coverage_report().to_string(),
+        (true, true) => to_json(&collection.coverage_report())?,
+    };
+    write_output(args, &out)?;
+    Ok(true)
+}
+
+fn diff(args: &Args) -> Result<bool> {
+    args.only(&["output"])?;
+    let [old, new] = args.positional.as_slice() else {
+        return Err("diff needs two files".to_string());
+    };
+    let report = geon_rs::collection::diff_text(&read_input(Some(old))?, &read_input(Some(new))?);
+    if !report.is_empty() {
+        write_output(args, &report)?;
+    }
+    // Like diff(1): status 1 when the files differ
+    Ok(report.is_empty())
+}
+
+fn fmt(args: &Args) -> Result<bool> {
+    args.only(&["check"])?;
+    let check = args.flag("check");
+    if args.positional.is_empty() || args.positional == ["-"] {
+        let text = read_input(None)?;
+        let formatted = geon_rs::generator::format(&text).map_err(|e| e.to_string())?;
+        if !check {
+            print!("{}", formatted);
+        }
+        return Ok(!check || formatted == text);
+    }
+
+    let mut files = Vec::new();
+    for path in &args.positional {
+        let path = PathBuf::from(path);
+        if path.is_dir() { geon_files(&path, &mut files)? } else { files.push(path) }
+    }
+    let mut clean = true;
+    for file in files {
+        let text = std::fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        let formatted = geon_rs::generator::format(&text).map_err(|e| format!("{}: {}", file.display(), e))?;
+        if formatted == text {
+            continue;
+        }
+        clean = false;
+        if check {
+            println!("{}", file.display());
+        } else {
+            std::fs::write(&file, formatted).map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+    }
+    // Rewriting succeeds whatever it changed; checking fails on any change
+    Ok(clean || !check)
+}
+
+// `n` comma-separated numbers, as in `--around 52.95,-1.16`
+fn numbers(option: &str, text: &str, n: usize) -> Result<Vec<f64>> {
+    let values: Vec<f64> = text.split(',').filter_map(|s| s.trim().parse().ok()).collect();
+    match values.len() == n && text.split(',').count() == n {
+        true => Ok(values),
+        false => Err(format!("--{}: expected {} comma-separated numbers, got '{}'", option, n, text)),
+    }
+}
+
+fn overpass_query(args: &Args) -> Result<OverpassQuery> {
+    let mut query = OverpassQuery::new();
+    match (args.value("around"), args.value("bbox")) {
+        (Some(_), Some(_)) => return Err("give --around or --bbox, not both".to_string()),
+        (Some(around), None) => {
+            let at = numbers("around", around, 2)?;
+            let radius = numbers("radius", args.value("radius").unwrap_or("500"), 1)?;
+            query = query.around(Coordinate::new(at[0], at[1]), radius[0]);
+        }
+        (None, Some(bbox)) => {
+            let b = numbers("bbox", bbox, 4)?;
+            query = query.bbox(b[0], b[1], b[2], b[3]);
+        }
+        (None, None) => return Err("fetch osm needs --around or --bbox".to_string()),
+    }
+    for tag in args.values("tag") {
+        query = match tag.split_once('=') {
+            Some((key, value)) => query.tag(key, value),
+            None => query.has_tag(tag),
+        };
+    }
+    let elements = args.values("element");
+    if !elements.is_empty() {
+        let elements = elements
+            .iter()
+            .map(|e| match *e {
+                "node" => Ok(ElementType::Node),
+                "way" => Ok(ElementType::Way),
+                "relation" => Ok(ElementType::Relation),
+                other => Err(format!("unknown element type '{}'", other)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        query = query.elements(&elements);
+    }
+    if let Some(timeout) = args.value("timeout") {
+        query = query.timeout(timeout.parse().map_err(|_| format!("--timeout: expected seconds, got '{}'", timeout))?);
+    }
+    if let Some(endpoint) = args.value("endpoint") {
+        query = query.endpoint(endpoint);
+    }
+    Ok(query)
+}
+
+fn fetch(args: &Args) -> Result<bool> {
+    args.only(&["output", "around", "radius", "bbox", "tag", "element", "timeout", "endpoint", "dry-run"])?;
+    match args.positional.as_slice() {
+        [source] if source == "osm" => {}
+        [source] => return Err(format!("cannot fetch from '{}'; try 'osm'", source)),
+        _ => return Err("usage: geon fetch osm --around LAT,LON [options]".to_string()),
+    }
+    let query = overpass_query(args)?;
+    if args.flag("dry-run") {
+        print!("{}", query.to_ql());
+        return Ok(true);
+    }
+    let places = fetch_places(&query)?;
+    match args.value("output") {
+        Some(dir) => {
+            let written = GeonCollection::new(places).save_to_dir(dir).map_err(|e| format!("{}: {}", dir, e))?;
+            eprintln!("wrote {} file(s) to {}", written.len(), dir);
+        }
+        None if places.is_empty() => eprintln!("no results"),
+        None => print!("{}", generate_all(&places)),
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "http")]
+fn fetch_places(query: &OverpassQuery) -> Result<Vec<GeonPlace>> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(query.fetch(&reqwest::Client::new())).map_err(|e| format!("Overpass: {}", e))
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch_places(_: &OverpassQuery) -> Result<Vec<GeonPlace>> {
+    Err("this geon was built without the http feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Args {
+        Args::parse(raw.iter().map(|s| s.to_string()), VALUED).unwrap()
+    }
+
+    #[test]
+    fn test_overpass_query_from_args() {
+        let query = overpass_query(&args(&["osm", "--around", "52.95,-1.16", "--tag", "leisure=park", "--element", "way"])).unwrap();
+        assert_eq!(query.to_ql(), "[out:json][timeout:25];\n(\n  way[\"leisure\"=\"park\"](around:500,52.95,-1.16);\n);\nout body geom;\n");
+        let query = overpass_query(&args(&["osm", "--bbox", "52.9,-1.2,53,-1.1", "--tag", "amenity"])).unwrap();
+        assert!(query.to_ql().contains("node[\"amenity\"](52.9,-1.2,53,-1.1);"));
+        assert!(overpass_query(&args(&["osm", "--tag", "amenity"])).is_err());
+        assert!(overpass_query(&args(&["osm", "--around", "52.95,-1.16", "--element", "area"])).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! SARIF 2.1.0 output for `geon lint`, the format code-scanning services
+//! read from CI.
+
+use geon_rs::validate::{Issue, Severity, RULES};
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+/// A SARIF log of `issues`, each with the file its place was read from.
+pub fn report(issues: &[(Issue, Option<String>)]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|r| {
+            json!({
+                "id": r.name,
+                "shortDescription": {"text": r.description},
+                "defaultConfiguration": {"level": level(r.severity)},
+            })
+        })
+        .collect();
+    let results: Vec<Value> = issues
+        .iter()
+        .map(|(issue, file)| {
+            let mut result = json!({
+                "ruleId": issue.rule,
+                "ruleIndex": RULES.iter().position(|r| r.name == issue.rule),
+                "level": level(issue.severity),
+                "message": {"text": format!("{}: {}: {}", issue.place, issue.field, issue.message)},
+            });
+            if let Some(file) = file {
+                result["locations"] = json!([{"physicalLocation": {"artifactLocation": {"uri": file}}}]);
+            }
+            result
+        })
+        .collect();
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {"driver": {"name": "geon", "version": env!("CARGO_PKG_VERSION"), "rules": rules}},
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sarif_report() {
+        let issue = Issue {
+            rule: "missing-type",
+            severity: Severity::Info,
+            place: "Kiosk".to_string(),
+            field: "TYPE".to_string(),
+            message: "place has no TYPE".to_string(),
+        };
+        let log = report(&[(issue, Some("corpus/kiosk.geon".to_string()))]);
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["level"], "note");
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"][result["ruleIndex"].as_u64().unwrap() as usize]["id"], "missing-type");
+        assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "corpus/kiosk.geon");
+    }
+}
@@ -0,0 +1,228 @@
+//! Hierarchical cell grids for aggregating places, as in urban analytics.
+//!
+//! S2 cells are computed here; with the `h3` feature, [`H3`] cells come
+//! from the `h3o` crate. Any other function from a coordinate and
+//! resolution to a cell is a [`CellGrid`] too, so a closure over another
+//! grid's library plugs straight into [`GeonCollection::group_by_cell`].
+
+use crate::collection::GeonCollection;
+use crate::models::{Coordinate, GeonPlace};
+use std::collections::BTreeMap;
+
+/// A grid assigning each coordinate a 64-bit cell ID at a resolution.
+pub trait CellGrid {
+    /// The cell containing `at`, or `None` if it or `resolution` is out of
+    /// the grid's range.
+    fn cell(&self, at: &Coordinate, resolution: u8) -> Option<u64>;
+}
+
+impl<F: Fn(&Coordinate, u8) -> Option<u64>> CellGrid for F {
+    fn cell(&self, at: &Coordinate, resolution: u8) -> Option<u64> {
+        self(at, resolution)
+    }
+}
+
+/// The S2 grid, levels 0 (a cube face) to 30 (about 1 cm).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct S2;
+
+impl CellGrid for S2 {
+    fn cell(&self, at: &Coordinate, resolution: u8) -> Option<u64> {
+        (resolution <= S2_MAX_LEVEL && at.lat.is_finite() && at.lon.is_finite()).then(|| s2_cell(at, resolution))
+    }
+}
+
+const S2_MAX_LEVEL: u8 = 30;
+
+/// The H3 grid, resolutions 0 (about 4.4 million km² hexagons) to 15
+/// (about 1 m²), with cell IDs as `h3o` and the H3 library give them.
+#[cfg(feature = "h3")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct H3;
+
+#[cfg(feature = "h3")]
+impl CellGrid for H3 {
+    fn cell(&self, at: &Coordinate, resolution: u8) -> Option<u64> {
+        let resolution = h3o::Resolution::try_from(resolution).ok()?;
+        let at = h3o::LatLng::new(at.lat, at.lon).ok()?;
+        Some(at.to_cell(resolution).into())
+    }
+}
+
+// Hilbert curve position of each (i, j) quadrant by orientation, and how
+// each position changes the orientation of the next level down
+const IJ_TO_POS: [[u64; 4]; 4] = [[0, 1, 3, 2], [0, 3, 1, 2], [2, 3, 1, 0], [2, 1, 3, 0]];
+const POS_TO_ORIENTATION: [usize; 4] = [1, 0, 0, 3];
+
+// Cube face and face coordinates (u, v) of a point on the sphere
+fn face_uv(c: &Coordinate) -> (u64, f64, f64) {
+    let (lat, lon) = (c.lat.to_radians(), c.lon.to_radians());
+    let (x, y, z) = (lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin());
+    let face = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+        if x < 0.0 { 3 } else { 0 }
+    } else if y.abs() >= z.abs() {
+        if y < 0.0 { 4 } else { 1 }
+    } else if z < 0.0 {
+        5
+    } else {
+        2
+    };
+    let (u, v) = match face {
+        0 => (y / x, z / x),
+        1 => (-x / y, z / y),
+        2 => (-x / z, -y / z),
+        3 => (z / x, y / x),
+        4 => (z / y, -x / y),
+        _ => (-y / z, -x / z),
+    };
+    (face, u, v)
+}
+
+// S2's quadratic projection from u or v to s or t in [0, 1], then to a
+// leaf-cell row or column
+fn leaf_index(u: f64) -> u64 {
+    let s = if u >= 0.0 { 0.5 * (1.0 + 3.0 * u).sqrt() } else { 1.0 - 0.5 * (1.0 - 3.0 * u).sqrt() };
+    let max = (1u64 << S2_MAX_LEVEL) - 1;
+    ((s * (1u64 << S2_MAX_LEVEL) as f64).floor().max(0.0) as u64).min(max)
+}
+
+/// The S2 cell ID containing `c` at `level` (clamped to 30).
+pub fn s2_cell(c: &Coordinate, level: u8) -> u64 {
+    let (face, u, v) = face_uv(c);
+    let (i, j) = (leaf_index(u), leaf_index(v));
+    let mut orientation = (face & 1) as usize;
+    let mut pos = 0u64;
+    for bit in (0..S2_MAX_LEVEL).rev() {
+        let ij = ((i >> bit & 1) << 1 | (j >> bit & 1)) as usize;
+        let quadrant = IJ_TO_POS[orientation][ij];
+        pos = pos << 2 | quadrant;
+        orientation ^= POS_TO_ORIENTATION[quadrant as usize];
+    }
+    let leaf = face << 61 | pos << 1 | 1;
+    let lsb = 1u64 << (2 * (S2_MAX_LEVEL - level.min(S2_MAX_LEVEL)));
+    (leaf & lsb.wrapping_neg()) | lsb
+}
+
+/// The level of an S2 cell ID.
+pub fn s2_level(id: u64) -> u8 {
+    S2_MAX_LEVEL - (id.trailing_zeros() / 2) as u8
+}
+
+/// Whether S2 cell `cell` lies within (or is) cell `ancestor`.
+pub fn s2_contains(ancestor: u64, cell: u64) -> bool {
+    let lsb = ancestor & ancestor.wrapping_neg();
+    (ancestor - (lsb - 1)..=ancestor + (lsb - 1)).contains(&cell)
+}
+
+/// The short hex form of an S2 cell ID used by S2 tools, e.g. `"89c25"`.
+pub fn s2_token(id: u64) -> String {
+    if id == 0 {
+        return "X".to_string();
+    }
+    let hex = format!("{:016x}", id);
+    hex.trim_end_matches('0').to_string()
+}
+
+impl Coordinate {
+    /// See [`s2_cell`].
+    pub fn s2_cell(&self, level: u8) -> u64 {
+        s2_cell(self, level)
+    }
+
+    /// The H3 cell containing this point at `resolution`, or `None` if the
+    /// resolution is above 15 or the point isn't a finite coordinate.
+    #[cfg(feature = "h3")]
+    pub fn h3_cell(&self, resolution: u8) -> Option<u64> {
+        H3.cell(self, resolution)
+    }
+}
+
+impl GeonCollection {
+    /// Places with a LOCATION grouped by their cell in `grid` at
+    /// `resolution`; places the grid has no cell for are left out.
+    pub fn group_by_cell(&self, grid: &impl CellGrid, resolution: u8) -> BTreeMap<u64, Vec<&GeonPlace>> {
+        let mut groups: BTreeMap<u64, Vec<&GeonPlace>> = BTreeMap::new();
+        for place in &self.places {
+            if let Some(cell) = place.location.as_ref().and_then(|at| grid.cell(at, resolution)) {
+                groups.entry(cell).or_default().push(place);
+            }
+        }
+        groups
+    }
+
+    /// [`group_by_cell`](Self::group_by_cell) on the S2 grid.
+    pub fn group_by_s2(&self, level: u8) -> BTreeMap<u64, Vec<&GeonPlace>> {
+        self.group_by_cell(&S2, level)
+    }
+
+    /// [`group_by_cell`](Self::group_by_cell) on the H3 grid.
+    #[cfg(feature = "h3")]
+    pub fn group_by_h3(&self, resolution: u8) -> BTreeMap<u64, Vec<&GeonPlace>> {
+        self.group_by_cell(&H3, resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_s2_cells() {
+        // Cube faces: +x, +y, +z, -x, -y, -z
+        let faces: Vec<String> = [(0.0, 0.0), (0.0, 90.0), (90.0, 0.0), (0.0, 180.0), (0.0, -90.0), (-90.0, 0.0)]
+            .iter()
+            .map(|&(lat, lon)| s2_token(Coordinate::new(lat, lon).s2_cell(0)))
+            .collect();
+        assert_eq!(faces, ["1", "3", "5", "7", "9", "b"]);
+
+        let square = Coordinate::new(52.9533, -1.1505);
+        let leaf = square.s2_cell(30);
+        assert_eq!(leaf & 1, 1);
+        for level in 0..30 {
+            let cell = square.s2_cell(level);
+            assert_eq!(s2_level(cell), level);
+            assert!(s2_contains(cell, leaf) && s2_contains(cell, square.s2_cell(level + 1)));
+        }
+        assert!(!s2_contains(square.s2_cell(12), Coordinate::new(51.5007, -0.1246).s2_cell(30)));
+        assert_eq!(s2_token(Coordinate::new(40.7128, -74.0060).s2_cell(8)), "89c25");
+
+        let place = |name: &str, lat: f64, lon: f64| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = Some(Coordinate::new(lat, lon));
+            p
+        };
+        let collection = GeonCollection::new(vec![
+            place("Old Market Square", 52.9533, -1.1505),
+            place("Council House", 52.9536, -1.1503),
+            place("Big Ben", 51.5007, -0.1246),
+        ]);
+        let groups = collection.group_by_s2(10);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&square.s2_cell(10)].len(), 2);
+        assert!(S2.cell(&square, 31).is_none());
+
+        let bands = |c: &Coordinate, _: u8| Some(c.lat.floor() as u64);
+        assert_eq!(collection.group_by_cell(&bands, 0).keys().collect::<Vec<_>>(), [&51, &52]);
+    }
+
+    #[cfg(feature = "h3")]
+    #[test]
+    fn test_h3_cells() {
+        assert_eq!(Coordinate::new(0.0, 0.0).h3_cell(0), Some(0x8075fffffffffff));
+        let square = Coordinate::new(52.9533, -1.1505);
+        let cell = square.h3_cell(9).unwrap();
+        // The resolution sits in bits 52 to 55 of an H3 cell ID
+        assert_eq!((cell >> 52) & 0xf, 9);
+        assert!(square.h3_cell(16).is_none() && Coordinate::new(f64::NAN, 0.0).h3_cell(9).is_none());
+
+        let mut places = vec![GeonPlace::default(), GeonPlace::default(), GeonPlace::default()];
+        places[0].location = Some(square.clone());
+        places[1].location = Some(Coordinate::new(52.9536, -1.1503));
+        places[2].location = Some(Coordinate::new(51.5007, -0.1246));
+        let collection = GeonCollection::new(places);
+        let groups = collection.group_by_h3(7);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&square.h3_cell(7).unwrap()].len(), 2);
+    }
+}
@@ -0,0 +1,161 @@
+//! Who changed what and when: field-level edit records for curated
+//! corpora, kept alongside places and written out as HISTORY entries or a
+//! JSON sidecar file.
+
+use crate::collection::{field_changes, FieldChange, GeonCollection};
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// One field edit to one place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edit {
+    /// The place edited, by ID or name.
+    pub place: String,
+    pub author: String,
+    /// When, as given by the caller; ISO 8601 keeps entries in order.
+    pub at: String,
+    pub change: FieldChange,
+}
+
+/// Edits in the order they were recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangeLog {
+    pub edits: Vec<Edit>,
+}
+
+fn key(place: &GeonPlace) -> String {
+    place.id.clone().unwrap_or_else(|| place.place.clone())
+}
+
+impl ChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Record every field changed from `before` to `after`, returning how
+    /// many there were.
+    pub fn record(&mut self, author: &str, at: &str, before: &GeonPlace, after: &GeonPlace) -> usize {
+        let changes = field_changes(before, after);
+        let count = changes.len();
+        for change in changes {
+            self.edits.push(Edit { place: key(before), author: author.to_string(), at: at.to_string(), change });
+        }
+        count
+    }
+
+    /// Run `edit` on `place` and record what it changed.
+    pub fn edit<R>(&mut self, author: &str, at: &str, place: &mut GeonPlace, edit: impl FnOnce(&mut GeonPlace) -> R) -> R {
+        let before = place.clone();
+        let result = edit(place);
+        self.record(author, at, &before, place);
+        result
+    }
+
+    /// Record the changes between two versions of a collection (see
+    /// [`GeonCollection::diff`]). Added and removed places are a PLACE
+    /// edit from or to nothing.
+    pub fn record_collection(&mut self, author: &str, at: &str, before: &GeonCollection, after: &GeonCollection) -> usize {
+        let start = self.edits.len();
+        let diff = before.diff(after);
+        let whole = |place: &GeonPlace, added: bool| {
+            let name = Some(place.place.clone());
+            let (before, after) = if added { (None, name) } else { (name, None) };
+            Edit { place: key(place), author: author.to_string(), at: at.to_string(), change: FieldChange { field: "PLACE".to_string(), before, after } }
+        };
+        self.edits.extend(diff.added.iter().map(|p| whole(p, true)));
+        self.edits.extend(diff.removed.iter().map(|p| whole(p, false)));
+        for change in &diff.changed {
+            self.record(author, at, change.before, change.after);
+        }
+        self.edits.len() - start
+    }
+
+    /// Edits to the place with this ID or name.
+    pub fn for_place<'a>(&'a self, place: &'a str) -> impl Iterator<Item = &'a Edit> + 'a {
+        self.edits.iter().filter(move |e| e.place == place)
+    }
+
+    /// Append this log's edits to `place` as HISTORY entries, one per
+    /// author and time, with `date`, `author` and `event` keys. The event
+    /// lists the changes, e.g. `TYPE: plaza -> square; PURPOSE: + events`.
+    pub fn write_history(&self, place: &mut GeonPlace) {
+        let key = key(place);
+        let mut sessions: Vec<(&str, &str, Vec<String>)> = Vec::new();
+        for edit in self.for_place(&key) {
+            match sessions.last_mut() {
+                Some((author, at, changes)) if *author == edit.author && *at == edit.at => changes.push(edit.change.to_string()),
+                _ => sessions.push((&edit.author, &edit.at, vec![edit.change.to_string()])),
+            }
+        }
+        for (author, at, changes) in sessions {
+            place.history.push(BTreeMap::from([
+                ("date".to_string(), at.to_string()),
+                ("author".to_string(), author.to_string()),
+                ("event".to_string(), changes.join("; ")),
+            ]));
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("change log serialises")
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, GeonError> {
+        serde_json::from_str(text).map_err(|e| GeonError::InvalidStructure(format!("change log: {}", e)))
+    }
+
+    /// Write the log as a JSON sidecar, e.g. `market.geon.changes.json`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), GeonError> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, GeonError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_log() {
+        let mut square = GeonPlace::default();
+        square.place = "Old Market Square".to_string();
+        square.id = Some("osm:way/1".to_string());
+        square.type_ = "plaza".to_string();
+
+        let mut log = ChangeLog::new();
+        log.edit("jo", "2024-05-01T10:00:00Z", &mut square, |p| {
+            p.type_ = "square".to_string();
+            p.purpose.push("events".to_string());
+        });
+        log.edit("sam", "2024-05-02T09:30:00Z", &mut square, |p| p.area = Some("11000 sqm".to_string()));
+        assert_eq!(log.for_place("osm:way/1").count(), 3);
+        assert_eq!(log.edits[2].author, "sam");
+
+        log.write_history(&mut square);
+        assert_eq!(square.history.len(), 2);
+        assert_eq!(square.history[0]["event"], "TYPE: plaza -> square; PURPOSE: + events");
+        assert_eq!(square.history[1]["author"], "sam");
+        let text = crate::generate(&square);
+        assert!(text.contains("HISTORY:\n  - author: jo\n    date: 2024-05-01T10:00:00Z\n"));
+        assert_eq!(crate::parse(&text), square);
+
+        let before = GeonCollection::new(vec![square.clone()]);
+        let mut cafe = GeonPlace::default();
+        cafe.place = "Cafe".to_string();
+        let after = GeonCollection::new(vec![square, cafe]);
+        assert_eq!(log.record_collection("jo", "2024-06-01", &before, &after), 1);
+
+        assert_eq!(ChangeLog::from_json(&log.to_json()).unwrap(), log);
+    }
+}
@@ -0,0 +1,690 @@
+use crate::generator::generate;
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::{parse, GeonError};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+mod coverage;
+mod dedupe;
+mod diff;
+mod index;
+mod lazy;
+mod order;
+mod partition;
+mod query;
+mod registry;
+mod search;
+mod stats;
+mod stream;
+#[cfg(feature = "watch")]
+mod watch;
+
+pub use coverage::{CoverageReport, Distribution, FieldCoverage, KeyCoverage, TOP_VALUES};
+pub use dedupe::{duplicate_groups, merge_into, MergeStrategy, Merged, Survivor};
+pub use diff::{diff_text, field_changes, CollectionDiff, FieldChange, PlaceChange};
+pub(crate) use diff::{pair_children, LIST_FIELDS};
+use index::SpatialIndex;
+pub(crate) use index::place_bounds;
+use search::TextIndex;
+pub use index::SpatialMatch;
+pub use lazy::{LazyCollection, PlaceHeader};
+pub use order::Order;
+pub use partition::{Cell, Grid, Partition, ShardBy};
+pub use query::{Level, Query};
+pub use registry::{PlaceHandle, Reference, Registry, Resolution};
+pub use stats::{AreaStats, CollectionStats, TemporalCoverage};
+pub use stream::PlaceStream;
+#[cfg(feature = "watch")]
+pub use watch::{watch, WatchEvent, Watcher, POLL_INTERVAL};
+
+/// A set of places, typically a corpus of `.geon` files on disk.
+#[derive(Debug, Clone, Default)]
+pub struct GeonCollection {
+    pub places: Vec<GeonPlace>,
+    // File each place was read from, relative to the loaded directory,
+    // by index into `places`
+    origins: Vec<Option<PathBuf>>,
+    // Built on the first spatial query and text search respectively
+    index: OnceLock<SpatialIndex>,
+    text_index: OnceLock<TextIndex>,
+}
+
+impl PartialEq for GeonCollection {
+    fn eq(&self, other: &Self) -> bool {
+        self.places == other.places && self.origins == other.origins
+    }
+}
+
+fn find_geon_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), GeonError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_geon_files(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "geon") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_place(path: &Path) -> Result<GeonPlace, GeonError> {
+    let place = parse(&std::fs::read_to_string(path)?);
+    if place.place.is_empty() {
+        return Err(GeonError::InvalidStructure("missing PLACE".to_string()));
+    }
+    Ok(place)
+}
+
+// File name for a place without provenance, e.g. `Old Market Square` ->
+// `old-market-square.geon`
+fn file_name(place: &GeonPlace) -> String {
+    let slug: Vec<String> = place
+        .place
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if slug.is_empty() { "place.geon".to_string() } else { format!("{}.geon", slug.join("-")) }
+}
+
+// Write each file, creating directories as needed, and return the paths.
+// When `atomic`, everything is first written to hidden temporary files
+// beside its destination and only then renamed into place, so readers never
+// see a partly written file, and a failed write leaves no file changed.
+fn write_files(files: Vec<(PathBuf, String)>, atomic: bool) -> Result<Vec<PathBuf>, GeonError> {
+    for (path, _) in &files {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    if !atomic {
+        for (path, text) in &files {
+            std::fs::write(path, text)?;
+        }
+        return Ok(files.into_iter().map(|(path, _)| path).collect());
+    }
+
+    let temporary = |path: &Path| {
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        path.with_file_name(format!(".{}.tmp", name))
+    };
+    for (i, (path, text)) in files.iter().enumerate() {
+        if let Err(e) = std::fs::write(temporary(path), text) {
+            for (written, _) in &files[..=i] {
+                let _ = std::fs::remove_file(temporary(written));
+            }
+            return Err(e.into());
+        }
+    }
+    for (path, _) in &files {
+        std::fs::rename(temporary(path), path)?;
+    }
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+impl GeonCollection {
+    pub fn new(places: Vec<GeonPlace>) -> Self {
+        Self { places, ..Default::default() }
+    }
+
+    /// Load every `*.geon` file under `dir`, recursively and in path order.
+    ///
+    /// Files that cannot be read, or that have no PLACE, are skipped and
+    /// returned alongside the collection with their errors; only failing to
+    /// walk the directory itself is an `Err`. With the `parallel` feature,
+    /// files are read and parsed on every core.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<(Self, Vec<(PathBuf, GeonError)>), GeonError> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        find_geon_files(dir, &mut files)?;
+        files.sort();
+
+        #[cfg(feature = "parallel")]
+        let places = crate::parser::par_map(&files, |f| read_place(f));
+        #[cfg(not(feature = "parallel"))]
+        let places: Vec<_> = files.iter().map(|f| read_place(f)).collect();
+
+        let mut collection = Self::default();
+        let mut errors = Vec::new();
+        for (file, place) in files.into_iter().zip(places) {
+            match place {
+                Ok(place) => {
+                    let relative = file.strip_prefix(dir).map(Path::to_path_buf).unwrap_or(file);
+                    collection.push_from(place, relative);
+                }
+                Err(e) => errors.push((file, e)),
+            }
+        }
+        Ok((collection, errors))
+    }
+
+    /// Write each place back to `dir` at the path it was loaded from, and
+    /// places without one to a file named after PLACE. Returns the paths
+    /// written.
+    pub fn save_to_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, GeonError> {
+        write_files(self.files(dir.as_ref()), false)
+    }
+
+    // Where `save_to_dir` puts each place under `dir`, with its text
+    fn files(&self, dir: &Path) -> Vec<(PathBuf, String)> {
+        let mut files: Vec<(PathBuf, String)> = Vec::new();
+        for (i, place) in self.places.iter().enumerate() {
+            let mut path = match self.path(i) {
+                Some(relative) => dir.join(relative),
+                None => dir.join(file_name(place)),
+            };
+            // Keep unsourced places with the same name from overwriting each other
+            let mut n = 1;
+            while files.iter().any(|(p, _)| *p == path) {
+                n += 1;
+                path = dir.join(file_name(place).replace(".geon", &format!("-{}.geon", n)));
+            }
+            files.push((path, generate(place)));
+        }
+        files
+    }
+
+    pub fn push(&mut self, place: GeonPlace) {
+        self.places.push(place);
+        self.indexed(self.places.len() - 1);
+    }
+
+    /// Add a place read from `path`, kept for [`save_to_dir`](Self::save_to_dir).
+    pub fn push_from(&mut self, place: GeonPlace, path: impl Into<PathBuf>) {
+        self.origins.resize(self.places.len(), None);
+        self.places.push(place);
+        self.origins.push(Some(path.into()));
+        self.indexed(self.places.len() - 1);
+    }
+
+    /// Put `place` in the place of the `i`th, keeping its file, and return
+    /// the old one.
+    pub fn replace(&mut self, i: usize, place: GeonPlace) -> GeonPlace {
+        let old = std::mem::replace(&mut self.places[i], place);
+        self.indexed(i);
+        old
+    }
+
+    /// Remove and return the `i`th place; later places move down one.
+    pub fn remove(&mut self, i: usize) -> GeonPlace {
+        let place = self.places.remove(i);
+        if i < self.origins.len() {
+            self.origins.remove(i);
+        }
+        match self.index.get_mut() {
+            Some(index) if index.len == self.places.len() + 1 => index.remove(i),
+            Some(_) => self.index = OnceLock::new(),
+            None => {}
+        }
+        self.text_index = OnceLock::new();
+        place
+    }
+
+    // Bring the spatial index, if built, up to date with the `i`th place
+    // in place, and drop the text index
+    fn indexed(&mut self, i: usize) {
+        match self.index.get_mut() {
+            // Missing more than this place; see `spatial_index`
+            Some(index) if index.len + 1 < self.places.len() => self.index = OnceLock::new(),
+            Some(index) => index.set(i, &self.places[i]),
+            None => {}
+        }
+        self.text_index = OnceLock::new();
+    }
+
+    /// The file the `i`th place was loaded from, relative to its directory.
+    pub fn path(&self, i: usize) -> Option<&Path> {
+        self.origins.get(i)?.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.places.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.places.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, GeonPlace> {
+        self.places.iter()
+    }
+
+    /// Index of every ID and name, for resolving references.
+    pub fn registry(&self) -> Registry {
+        Registry::new(self)
+    }
+
+    /// Collection-wide checks, such as containment cycles (see
+    /// [`validate_collection`](crate::validate::validate_collection)).
+    pub fn validate(&self) -> Vec<crate::validate::Issue> {
+        crate::validate::validate_collection(self)
+    }
+
+    /// Give every place, nested ones included, that has no EXTENT one
+    /// derived from its geometry (see [`GeonPlace::derive_extent`]), so
+    /// bounding-box indexes and exports can rely on it. Returns how many
+    /// were filled in.
+    pub fn fill_extents(&mut self) -> usize {
+        fn fill(place: &mut GeonPlace) -> usize {
+            // Children first, so parents build on their extents
+            let filled = place.contains.iter_mut().map(fill).sum::<usize>();
+            filled + usize::from(place.extent.is_none() && place.derive_extent())
+        }
+        let filled = self.places.iter_mut().map(fill).sum();
+        self.reindex();
+        filled
+    }
+
+    /// Places matching `query`, in collection order.
+    pub fn filter(&self, query: impl std::borrow::Borrow<Query>) -> Vec<&GeonPlace> {
+        let query = query.borrow();
+        self.places.iter().filter(|p| query.matches(p)).collect()
+    }
+
+    /// Rebuild the spatial and text indexes. Needed after editing places
+    /// through `places` directly; [`push`](Self::push),
+    /// [`replace`](Self::replace) and [`remove`](Self::remove) keep them
+    /// up to date.
+    pub fn reindex(&mut self) {
+        self.index = OnceLock::new();
+        self.text_index = OnceLock::new();
+    }
+
+    fn spatial_index(&self) -> Cow<'_, SpatialIndex> {
+        let index = self.index.get_or_init(|| SpatialIndex::build(&self.places));
+        // Places added or removed through `places` since it was built
+        if index.len == self.places.len() { Cow::Borrowed(index) } else { Cow::Owned(SpatialIndex::build(&self.places)) }
+    }
+
+    /// Places whose PLACE, PURPOSE, CHARACTER, ADJACENCIES or EXPERIENCE
+    /// values share words with `query`, with their relevance scores, best
+    /// first. Matching ignores case and plural `s`; names weigh most,
+    /// then purposes, then character.
+    pub fn search(&self, query: &str) -> Vec<(&GeonPlace, f64)> {
+        let index = self.text_index.get_or_init(|| TextIndex::build(&self.places));
+        let ranked = if index.len == self.places.len() {
+            index.search(query)
+        } else {
+            TextIndex::build(&self.places).search(query)
+        };
+        ranked.into_iter().map(|(i, score)| (&self.places[i], score)).collect()
+    }
+
+    /// Places whose LOCATION lies within `extent` (which may cross the
+    /// antimeridian, `west > east`), in collection order.
+    pub fn query_bbox(&self, extent: &Extent) -> Vec<&GeonPlace> {
+        self.query_bbox_with(extent, SpatialMatch::Location)
+    }
+
+    pub fn query_bbox_with(&self, extent: &Extent, mode: SpatialMatch) -> Vec<&GeonPlace> {
+        let found = self.spatial_index().query_bbox(&self.places, extent, mode);
+        found.into_iter().map(|i| &self.places[i]).collect()
+    }
+
+    /// The `k` places with a LOCATION nearest `center`, nearest first, with
+    /// their great-circle distances in metres.
+    pub fn nearest(&self, center: &Coordinate, k: usize) -> Vec<(&GeonPlace, f64)> {
+        self.nearest_matching(center, &Query::All, k)
+    }
+
+    /// As [`nearest`](Self::nearest), among places of one TYPE.
+    pub fn nearest_of_type(&self, center: &Coordinate, type_: &str, k: usize) -> Vec<(&GeonPlace, f64)> {
+        self.nearest_matching(center, &Query::type_is(type_), k)
+    }
+
+    /// As [`nearest`](Self::nearest), among places matching `query`.
+    pub fn nearest_matching(&self, center: &Coordinate, query: &Query, k: usize) -> Vec<(&GeonPlace, f64)> {
+        let found = self.spatial_index().nearest(&self.places, center, k, |p| query.matches(p));
+        found.into_iter().map(|(i, d)| (&self.places[i], d)).collect()
+    }
+
+    /// Places whose LOCATION lies within `metres` of `center`, in collection
+    /// order.
+    pub fn query_radius(&self, center: &Coordinate, metres: f64) -> Vec<&GeonPlace> {
+        self.query_radius_with(center, metres, SpatialMatch::Location)
+    }
+
+    pub fn query_radius_with(&self, center: &Coordinate, metres: f64, mode: SpatialMatch) -> Vec<&GeonPlace> {
+        let found = self.radius_indices(center, metres, mode);
+        found.into_iter().map(|i| &self.places[i]).collect()
+    }
+
+    /// Places whose LOCATION has a geohash starting with `prefix`, in
+    /// collection order.
+    pub fn query_geohash(&self, prefix: &str) -> Vec<&GeonPlace> {
+        let Some(cell) = crate::geometry::geohash_extent(prefix) else { return Vec::new() };
+        let prefix = prefix.to_ascii_lowercase();
+        self.query_bbox(&cell)
+            .into_iter()
+            .filter(|p| p.location.as_ref().is_some_and(|c| c.geohash(prefix.len()) == prefix))
+            .collect()
+    }
+
+    /// Places with a LOCATION bucketed by its geohash at `precision`.
+    pub fn group_by_geohash(&self, precision: usize) -> BTreeMap<String, Vec<&GeonPlace>> {
+        let mut groups: BTreeMap<String, Vec<&GeonPlace>> = BTreeMap::new();
+        for place in &self.places {
+            if let Some(at) = &place.location {
+                groups.entry(at.geohash(precision)).or_default().push(place);
+            }
+        }
+        groups
+    }
+
+    pub(crate) fn radius_indices(&self, center: &Coordinate, metres: f64, mode: SpatialMatch) -> Vec<usize> {
+        self.spatial_index().query_radius(&self.places, center, metres, mode)
+    }
+}
+
+impl From<Vec<GeonPlace>> for GeonCollection {
+    fn from(places: Vec<GeonPlace>) -> Self {
+        Self::new(places)
+    }
+}
+
+impl<'a> IntoIterator for &'a GeonCollection {
+    type Item = &'a GeonPlace;
+    type IntoIter = std::slice::Iter<'a, GeonPlace>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.places.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_round_trip() {
+        let dir = std::env::temp_dir().join(format!("geon-collection-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("parks")).unwrap();
+        std::fs::write(dir.join("market.geon"), "PLACE: Old Market Square\nTYPE: public_space\n").unwrap();
+        std::fs::write(dir.join("parks/arboretum.geon"), "PLACE: Arboretum\nTYPE: public_space\n").unwrap();
+        std::fs::write(dir.join("parks/broken.geon"), "TYPE: public_space\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "PLACE: Not a place file\n").unwrap();
+
+        let (mut collection, errors) = GeonCollection::from_dir(&dir).unwrap();
+        assert_eq!(collection.len(), 2);
+        assert_eq!(collection.places[0].place, "Old Market Square");
+        assert_eq!(collection.path(1), Some(Path::new("parks/arboretum.geon")));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.ends_with("parks/broken.geon"));
+
+        collection.places[1].type_ = "natural_feature".to_string();
+        let mut new = GeonPlace::default();
+        new.place = "Castle Green".to_string();
+        collection.push(new);
+        let out = dir.join("out");
+        let written = collection.save_to_dir(&out).unwrap();
+        assert_eq!(written[2], out.join("castle-green.geon"));
+
+        let (reloaded, errors) = GeonCollection::from_dir(&out).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reloaded.places[0].place, "Castle Green");
+        assert_eq!(reloaded.places[2].type_, "natural_feature");
+        assert_eq!(reloaded.path(2), Some(Path::new("parks/arboretum.geon")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dir_round_trip_keeps_every_field() {
+        let text = r#"PLACE: Arboretum
+TYPE: park
+ID: osm:way/1
+LOCATION: 52.96, -1.16
+BOUNDARY:
+  - 52.96, -1.17
+  - 52.97, -1.16
+  - 52.95, -1.15
+PATH:
+  - 52.96, -1.16
+  - 52.97, -1.16
+EXTENT: 52.97, 52.95, -1.15, -1.17
+ELEVATION: 60m
+AREA: 7 ha
+PURPOSE: recreation
+EXPERIENCE:
+  openness: high
+CHARACTER:
+  - Victorian
+  -
+ADJACENCIES:
+  - Waverley Street (west)
+CONNECTIVITY:
+  pedestrian_entries: 3
+CONTAINS:
+  - PLACE: Bandstand
+    TYPE: structure
+    LOCATION: 52.961, -1.161
+    SOURCE:
+      - survey
+    CONFIDENCE:
+      geometry: medium
+    HISTORY:
+      - date: 1880
+        event: built
+    CONTAINS:
+      - PLACE: Stage
+        BUILT_FORM:
+          material: cast iron
+    RATING: 4
+PART_OF: Nottingham
+VIEWSHEDS:
+  castle: partial
+TEMPORAL:
+  opening: dawn to dusk
+LIFESPAN:
+  opened: 1852
+SOURCE:
+  - OpenStreetMap
+  - survey 2024
+CONFIDENCE:
+  geometry: high
+UPDATED: 2025-05-01
+BUILT_FORM:
+  bandstand: cast iron
+ECOLOGY:
+  trees: 800
+INFRASTRUCTURE:
+  lighting: none
+DEMOGRAPHICS:
+  visitors: families
+ECONOMY:
+  entry: free
+VISUAL:
+  palette: green
+HISTORY:
+  - date: 1852
+    event: opened
+  - date: 2002
+VERTICAL_PROFILE:
+  canopy_height: 25m
+WHEELCHAIR: yes
+addr:street: Waverley Street
+RATING: 4.5
+FACILITIES:
+  - toilets
+"#;
+        let dir = std::env::temp_dir().join(format!("geon-collection-full-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("arboretum.geon"), text).unwrap();
+
+        let (collection, errors) = GeonCollection::from_dir(&dir).unwrap();
+        assert!(errors.is_empty());
+        let bandstand = &collection.places[0].contains[0];
+        assert_eq!((bandstand.history.len(), bandstand.extra.len()), (1, 1));
+        assert_eq!(bandstand.contains[0].built_form["material"], "cast iron");
+        let written = collection.save_to_dir(dir.join("out")).unwrap();
+        let (reloaded, errors) = GeonCollection::from_dir(dir.join("out")).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reloaded.places, collection.places);
+
+        let bytes = std::fs::read(&written[0]).unwrap();
+        reloaded.save_to_dir(dir.join("again")).unwrap();
+        assert_eq!(std::fs::read(dir.join("again/arboretum.geon")).unwrap(), bytes);
+
+        let sharded = collection.save_sharded(dir.join("sharded"), ShardBy::Type, false).unwrap();
+        assert_eq!(parse(&std::fs::read_to_string(&sharded[0]).unwrap()), collection.places[0]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_geohash() {
+        let jutland = Coordinate::new(57.64911, 10.40744);
+        assert_eq!(jutland.geohash(11), "u4pruydqqvj");
+        let cell = crate::geometry::geohash_extent("U4PRUY").unwrap();
+        assert!(cell.south <= jutland.lat && jutland.lat <= cell.north && cell.west <= jutland.lon && jutland.lon <= cell.east);
+        assert!(crate::geometry::geohash_extent("u4pa").is_none());
+
+        let place = |name: &str, lat: f64, lon: f64| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = Some(Coordinate::new(lat, lon));
+            p
+        };
+        let mut square = place("Old Market Square", 52.9533, -1.1505);
+        assert_eq!(square.set_geohash(7).as_deref(), Some("gcrjjzf"));
+        assert_eq!(serde_json::to_value(&square).unwrap()["geohash"], "gcrjjzf");
+        let collection = GeonCollection::new(vec![square, place("Castle", 52.9497, -1.1543), place("Big Ben", 51.5007, -0.1246)]);
+        let names = |found: Vec<&GeonPlace>| found.iter().map(|p| p.place.clone()).collect::<Vec<_>>();
+        assert_eq!(names(collection.query_geohash("gcrj")), ["Old Market Square", "Castle"]);
+        let groups = collection.group_by_geohash(3);
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["gcp", "gcr"]);
+        assert_eq!(groups["gcr"].len(), 2);
+    }
+
+    #[test]
+    fn test_spatial_queries() {
+        let place = |name: &str, lat: f64, lon: f64| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = Some(Coordinate::new(lat, lon));
+            p
+        };
+        let mut park = place("Arboretum", 52.9602, -1.1562);
+        park.boundary = vec![
+            Coordinate::new(52.958, -1.160),
+            Coordinate::new(52.958, -1.150),
+            Coordinate::new(52.962, -1.150),
+            Coordinate::new(52.962, -1.160),
+        ];
+        let mut collection = GeonCollection::new(vec![
+            place("Old Market Square", 52.9533, -1.1505),
+            park,
+            place("Castle", 52.9497, -1.1543),
+        ]);
+
+        let window = Extent { north: 52.957, south: 52.949, east: -1.149, west: -1.152 };
+        let names = |found: Vec<&GeonPlace>| found.iter().map(|p| p.place.clone()).collect::<Vec<_>>();
+        assert_eq!(names(collection.query_bbox(&window)), ["Old Market Square"]);
+        let edge = Extent { north: 52.959, south: 52.955, east: -1.149, west: -1.152 };
+        assert!(collection.query_bbox(&edge).is_empty());
+        assert_eq!(names(collection.query_bbox_with(&edge, SpatialMatch::Intersects)), ["Arboretum"]);
+
+        let market = Coordinate::new(52.9533, -1.1505);
+        assert_eq!(names(collection.query_radius(&market, 500.0)), ["Old Market Square", "Castle"]);
+        assert_eq!(
+            names(collection.query_radius_with(&market, 600.0, SpatialMatch::Intersects)),
+            ["Old Market Square", "Arboretum", "Castle"]
+        );
+
+        collection.push(place("Ice Arena", 52.9509, -1.1425));
+        assert_eq!(collection.query_radius(&market, 800.0).len(), 3);
+    }
+
+    #[test]
+    fn test_nearest() {
+        // A grid of stops, with tram stops every fifth
+        let mut places = Vec::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                let mut p = GeonPlace::default();
+                p.place = format!("Stop {}-{}", i, j);
+                p.type_ = if (i * 20 + j) % 5 == 0 { "transport_hub" } else { "street" }.to_string();
+                p.location = Some(Coordinate::new(52.9 + i as f64 * 0.005, -1.2 + j as f64 * 0.005));
+                places.push(p);
+            }
+        }
+        let collection = GeonCollection::new(places);
+        let at = Coordinate::new(52.9501, -1.1499);
+
+        // Checked against a full scan
+        let mut all: Vec<(String, f64)> = collection
+            .iter()
+            .map(|p| (p.place.clone(), crate::geometry::haversine_m(&at, p.location.as_ref().unwrap())))
+            .collect();
+        all.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let nearest = collection.nearest(&at, 5);
+        assert_eq!(nearest.iter().map(|(p, _)| p.place.clone()).collect::<Vec<_>>(), all[..5].iter().map(|a| a.0.clone()).collect::<Vec<_>>());
+        assert_eq!(nearest[0].0.place, "Stop 10-10");
+        assert!(nearest[0].1 < 20.0);
+
+        let trams = collection.nearest_of_type(&at, "transport_hub", 2);
+        assert!(trams.iter().all(|(p, _)| p.type_ == "transport_hub"));
+        assert_eq!(trams[0].0.place, "Stop 10-10");
+        assert!(trams[1].1 >= nearest[1].1);
+
+        // From far outside the data
+        let far = collection.nearest(&Coordinate::new(51.5, -0.12), 1);
+        assert_eq!(far[0].0.place, "Stop 0-19");
+        assert_eq!(collection.nearest(&at, 0).len(), 0);
+    }
+
+    #[test]
+    fn test_filter_query() {
+        let place = |name: &str, type_: &str, purpose: &str, activity: &str| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.type_ = type_.to_string();
+            p.purpose = vec![purpose.to_string()];
+            p.experience.insert("activity_density".to_string(), activity.to_string());
+            p
+        };
+        let collection = GeonCollection::new(vec![
+            place("Old Market Square", "public_space", "Market trading", "high (weekdays), very_high (weekends)"),
+            place("Sneinton Market", "public_space", "market", "sparse"),
+            place("Victoria Centre", "building", "retail market", "crowded"),
+        ]);
+        assert_eq!(Level::from_value("busy"), Some(Level::High));
+        assert_eq!(Level::from_value("variable"), None);
+
+        let busy_markets = Query::type_is("public_space")
+            .and(Query::purpose_contains("market"))
+            .and(Query::experience_at_least("activity_density", Level::High));
+        let found = collection.filter(&busy_markets);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].place, "Old Market Square");
+
+        let quiet_or_indoor = Query::experience_at_most("activity_density", Level::Low).or(!Query::type_is("public_space"));
+        let names: Vec<&str> = collection.filter(quiet_or_indoor).iter().map(|p| p.place.as_str()).collect();
+        assert_eq!(names, ["Sneinton Market", "Victoria Centre"]);
+        assert_eq!(collection.filter(Query::has_field("experience")).len(), 3);
+    }
+
+    #[test]
+    fn test_search() {
+        let place = |name: &str, purpose: &str, character: &str| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.purpose = vec![purpose.to_string()];
+            p.character = vec![character.to_string()];
+            p
+        };
+        let mut collection = GeonCollection::new(vec![
+            place("Victoria Centre", "Retail", "1970s shopping centre with an indoor market"),
+            place("Old Market Square", "Markets and civic events", "Historic heart of the city"),
+            place("Castle Green", "Leisure", "Historic parkland below the castle"),
+        ]);
+        let names = |found: Vec<(&GeonPlace, f64)>| found.iter().map(|(p, _)| p.place.clone()).collect::<Vec<_>>();
+        assert_eq!(names(collection.search("historic market")), ["Old Market Square", "Victoria Centre", "Castle Green"]);
+        assert!(collection.search("railway").is_empty());
+
+        collection.push(place("Nottingham Station", "Railway interchange", "Edwardian"));
+        assert_eq!(names(collection.search("Railways")), ["Nottingham Station"]);
+    }
+}
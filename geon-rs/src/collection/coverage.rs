@@ -0,0 +1,246 @@
+use super::diff::sections;
+use super::query::{has_field, FIELDS};
+use super::stats::visit;
+use super::GeonCollection;
+use crate::models::GeonPlace;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// How many of the most common values a [`Distribution`] keeps.
+pub const TOP_VALUES: usize = 5;
+
+/// How the values of a field or key are spread.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub distinct: usize,
+    /// The [`TOP_VALUES`] most common values with their counts, most common
+    /// first and ties in value order.
+    pub top: Vec<(String, usize)>,
+}
+
+impl Distribution {
+    fn from_counts(counts: BTreeMap<String, usize>) -> Self {
+        let distinct = counts.len();
+        let mut top: Vec<(String, usize)> = counts.into_iter().collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(TOP_VALUES);
+        Self { distinct, top }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let top: Vec<String> = self.top.iter().map(|(value, n)| format!("{} {}", value, n)).collect();
+        write!(f, "{}", top.join(", "))?;
+        if self.distinct > self.top.len() {
+            write!(f, ", ... ({} distinct)", self.distinct)?;
+        }
+        Ok(())
+    }
+}
+
+/// One key inside a map section (or HISTORY entries).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KeyCoverage {
+    pub key: String,
+    /// Places with the key.
+    pub places: usize,
+    pub values: Distribution,
+}
+
+/// One GEON field, or an unknown key kept in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FieldCoverage {
+    pub field: String,
+    /// Places with the field filled in.
+    pub places: usize,
+    pub percent: f64,
+    /// Values of text fields, and entries of list fields; `None` for
+    /// geometry, nested places and sections.
+    pub values: Option<Distribution>,
+    /// Keys of map sections and HISTORY entries, most used first.
+    pub keys: Vec<KeyCoverage>,
+}
+
+/// Which fields a corpus fills in and with what, for judging data of
+/// unknown quality. Serialises to JSON; `Display` gives a plain-text table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CoverageReport {
+    /// Every place, nested CONTAINS included.
+    pub places: usize,
+    /// GEON fields in specification order, then unknown keys by name.
+    pub fields: Vec<FieldCoverage>,
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} places", self.places)?;
+        for field in &self.fields {
+            write!(f, "{:<18} {:>7} {:>6.1}%", field.field, field.places, field.percent)?;
+            match &field.values {
+                Some(values) if field.places > 0 => writeln!(f, "  {}", values)?,
+                _ => writeln!(f)?,
+            }
+            for key in &field.keys {
+                writeln!(f, "  {:<16} {:>7}          {}", key.key, key.places, key.values)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Text values of a field, or `None` for fields without a distribution
+fn field_values(p: &GeonPlace, field: &str) -> Option<Vec<String>> {
+    let one = |v: &Option<String>| v.iter().cloned().collect();
+    let text = |v: &String| if v.is_empty() { Vec::new() } else { vec![v.clone()] };
+    Some(match field {
+        "PLACE" => text(&p.place),
+        "TYPE" => text(&p.type_),
+        "ID" => one(&p.id),
+        "ELEVATION" => one(&p.elevation),
+        "AREA" => one(&p.area),
+        "PART_OF" => one(&p.part_of),
+        "UPDATED" => one(&p.updated),
+        "PURPOSE" => p.purpose.clone(),
+        "CHARACTER" => p.character.clone(),
+        "ADJACENCIES" => p.adjacencies.clone(),
+        "SOURCE" => p.source.clone(),
+        _ => return None,
+    })
+}
+
+fn extra_value(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn count<'a>(counts: &mut BTreeMap<String, usize>, values: impl IntoIterator<Item = &'a String>) {
+    for value in values {
+        *counts.entry(value.trim().to_string()).or_default() += 1;
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    places: usize,
+    values: Option<BTreeMap<String, usize>>,
+    // Per key: places with it, and its values
+    keys: BTreeMap<String, (usize, BTreeMap<String, usize>)>,
+}
+
+impl GeonCollection {
+    /// Per field, how many places fill it in and with which values, and
+    /// per key of each map section, how often it is used and with which
+    /// values. Covers every place, nested ones included.
+    pub fn coverage_report(&self) -> CoverageReport {
+        let mut all = Vec::new();
+        visit(&self.places, &mut all);
+
+        let mut tallies: BTreeMap<&str, Tally> = FIELDS.iter().map(|f| (*f, Tally::default())).collect();
+        let mut unknown: BTreeMap<String, Tally> = BTreeMap::new();
+        for p in &all {
+            for field in FIELDS {
+                let tally = tallies.get_mut(field).expect("every field has a tally");
+                tally.places += usize::from(has_field(p, field));
+                if let Some(values) = field_values(p, field) {
+                    count(tally.values.get_or_insert_with(BTreeMap::new), &values);
+                }
+            }
+            for (field, map) in sections(p) {
+                let keys = &mut tallies.get_mut(field).expect("every section is a field").keys;
+                for (key, value) in map {
+                    let (places, values) = keys.entry(key.clone()).or_default();
+                    *places += 1;
+                    count(values, [value]);
+                }
+            }
+            let history = &mut tallies.get_mut("HISTORY").expect("HISTORY is a field").keys;
+            let keys: BTreeSet<&String> = p.history.iter().flat_map(|e| e.keys()).collect();
+            for key in keys {
+                let (places, values) = history.entry(key.clone()).or_default();
+                *places += 1;
+                count(values, p.history.iter().filter_map(|e| e.get(key)));
+            }
+            for (key, value) in p.extra.iter().filter(|(k, _)| has_field(p, k)) {
+                let tally = unknown.entry(key.clone()).or_default();
+                tally.places += 1;
+                count(tally.values.get_or_insert_with(BTreeMap::new), [&extra_value(value)]);
+            }
+        }
+
+        let percent = |n: usize| if all.is_empty() { 0.0 } else { (n as f64 * 1000.0 / all.len() as f64).round() / 10.0 };
+        let fields = FIELDS
+            .iter()
+            .map(|f| (f.to_string(), tallies.remove(f).unwrap_or_default()))
+            .chain(unknown)
+            .map(|(field, tally)| {
+                let mut keys: Vec<KeyCoverage> = tally
+                    .keys
+                    .into_iter()
+                    .map(|(key, (places, values))| KeyCoverage { key, places, values: Distribution::from_counts(values) })
+                    .collect();
+                keys.sort_by(|a, b| b.places.cmp(&a.places).then_with(|| a.key.cmp(&b.key)));
+                FieldCoverage {
+                    field,
+                    places: tally.places,
+                    percent: percent(tally.places),
+                    values: tally.values.map(Distribution::from_counts),
+                    keys,
+                }
+            })
+            .collect();
+        CoverageReport { places: all.len(), fields }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage_report() {
+        let place = |name: &str, type_: &str, noise: Option<&str>| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.type_ = type_.to_string();
+            if let Some(noise) = noise {
+                p.experience.insert("noise".to_string(), noise.to_string());
+            }
+            p
+        };
+        let mut square = place("Old Market Square", "public_space", Some("loud"));
+        square.purpose = vec!["markets".to_string(), "events".to_string()];
+        square.contains = vec![place("Fountain", "landmark", None)];
+        square.extra.insert("wheelchair".to_string(), Value::String("yes".to_string()));
+        let mut park = place("Arboretum", "public_space", Some("quiet"));
+        park.purpose = vec!["leisure".to_string(), "events".to_string()];
+        park.experience.insert("shade".to_string(), "high".to_string());
+        park.history = vec![[("date", "1852"), ("event", "opened")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()];
+        let collection = GeonCollection::new(vec![square, park, place("Castle", "", Some("Loud"))]);
+
+        let report = collection.coverage_report();
+        assert_eq!(report.places, 4);
+        let field = |name: &str| report.fields.iter().find(|f| f.field == name).unwrap();
+        assert_eq!(field("TYPE").places, 3);
+        assert_eq!(field("TYPE").percent, 75.0);
+        assert_eq!(field("TYPE").values.as_ref().unwrap().top, [("public_space".to_string(), 2), ("landmark".to_string(), 1)]);
+        assert_eq!(field("PURPOSE").values.as_ref().unwrap().top[0], ("events".to_string(), 2));
+        assert!(field("LOCATION").values.is_none());
+
+        let experience = &field("EXPERIENCE").keys;
+        assert_eq!((experience[0].key.as_str(), experience[0].places), ("noise", 3));
+        assert_eq!(experience[0].values.distinct, 3);
+        assert_eq!(experience[1].key, "shade");
+        assert_eq!(field("HISTORY").keys[1].values.top, [("opened".to_string(), 1)]);
+        assert_eq!(report.fields.last().unwrap().field, "wheelchair");
+
+        let text = report.to_string();
+        assert!(text.starts_with("4 places\nPLACE"));
+        assert!(text.contains("TYPE                     3   75.0%  public_space 2, landmark 1\n"));
+        assert!(text.contains("  noise                  3          Loud 1, loud 1, quiet 1\n"));
+    }
+}
@@ -0,0 +1,414 @@
+use super::{GeonCollection, Level};
+use crate::geometry::{haversine_m, ring_area};
+use crate::graph::split_note;
+use crate::models::{Extent, GeonPlace};
+use std::collections::{BTreeMap, HashMap};
+
+/// Which record of a duplicate group the others are merged into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Survivor {
+    /// The first in collection order.
+    First,
+    /// The one with the most fields filled in.
+    #[default]
+    MostComplete,
+    /// The one with the highest mean CONFIDENCE.
+    MostConfident,
+}
+
+/// How [`GeonCollection::dedupe`] finds and merges duplicates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeStrategy {
+    /// Places sharing a name further apart than this are distinct.
+    pub max_distance_m: f64,
+    pub survivor: Survivor,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        Self { max_distance_m: 100.0, survivor: Survivor::default() }
+    }
+}
+
+/// One merged group: the surviving record and those folded into it, by ID
+/// or name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub into: String,
+    pub from: Vec<String>,
+}
+
+// `The Old Market Square` and `old market square` match
+pub(super) fn normalise(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    match words.split_first() {
+        Some((first, rest)) if first == "the" && !rest.is_empty() => rest.join(" "),
+        _ => words.join(" "),
+    }
+}
+
+fn key(place: &GeonPlace) -> String {
+    place.id.clone().unwrap_or_else(|| place.place.clone())
+}
+
+fn is_duplicate(a: &GeonPlace, b: &GeonPlace, max_distance_m: f64) -> bool {
+    if a.id.is_some() && a.id == b.id {
+        return true;
+    }
+    let types_agree = a.type_.is_empty() || b.type_.is_empty() || a.type_ == b.type_;
+    let near = match (&a.location, &b.location) {
+        (Some(x), Some(y)) => haversine_m(x, y) <= max_distance_m,
+        _ => false,
+    };
+    // Distinct IDs from the same scheme are distinct records
+    let ids_agree = match (&a.id, &b.id) {
+        (Some(x), Some(y)) => x.split(':').next() != y.split(':').next(),
+        _ => true,
+    };
+    types_agree && near && ids_agree && normalise(&a.place) == normalise(&b.place)
+}
+
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Groups of two or more top-level places that look like the same place:
+/// the same ID, or the same name (ignoring case, punctuation and a leading
+/// "The") with agreeing TYPEs within `max_distance_m`. Indices into
+/// `places`, each group and the list in collection order.
+pub fn duplicate_groups(places: &[GeonPlace], max_distance_m: f64) -> Vec<Vec<usize>> {
+    // Only places sharing an ID or name can match, so compare within those
+    let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, p) in places.iter().enumerate() {
+        buckets.entry(normalise(&p.place)).or_default().push(i);
+        if let Some(id) = &p.id {
+            buckets.entry(format!("\u{0}{}", id)).or_default().push(i);
+        }
+    }
+    let mut parent: Vec<usize> = (0..places.len()).collect();
+    for bucket in buckets.values().filter(|b| b.len() > 1) {
+        for (n, &i) in bucket.iter().enumerate() {
+            for &j in &bucket[n + 1..] {
+                if is_duplicate(&places[i], &places[j], max_distance_m) {
+                    let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[ri.max(rj)] = ri.min(rj);
+                }
+            }
+        }
+    }
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..places.len() {
+        let r = root(&mut parent, i);
+        groups.entry(r).or_default().push(i);
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort();
+    groups
+}
+
+fn completeness(p: &GeonPlace) -> usize {
+    let optional = [p.id.is_some(), p.location.is_some(), p.extent.is_some(), p.elevation.is_some(), p.area.is_some(), p.part_of.is_some(), p.updated.is_some()];
+    let lists = [p.boundary.len(), p.purpose.len(), p.character.len(), p.adjacencies.len(), p.source.len(), p.contains.len(), p.history.len()];
+    let maps = [&p.experience, &p.connectivity, &p.temporal, &p.lifespan, &p.confidence, &p.built_form, &p.ecology, &p.infrastructure, &p.demographics, &p.economy, &p.visual, &p.vertical_profile];
+    optional.iter().filter(|x| **x).count()
+        + lists.iter().filter(|n| **n > 0).count()
+        + maps.iter().filter(|m| !m.is_empty()).count()
+        + p.extra.len()
+}
+
+fn mean_confidence(p: &GeonPlace) -> f64 {
+    let levels: Vec<f64> = p.confidence.values().filter_map(|v| Level::from_value(v)).map(|l| l as u8 as f64).collect();
+    if levels.is_empty() { -1.0 } else { levels.iter().sum::<f64>() / levels.len() as f64 }
+}
+
+fn union(into: &mut Vec<String>, from: Vec<String>) {
+    for v in from {
+        if !into.contains(&v) {
+            into.push(v);
+        }
+    }
+}
+
+fn fill<V>(into: &mut BTreeMap<String, V>, from: BTreeMap<String, V>) {
+    for (k, v) in from {
+        into.entry(k).or_insert(v);
+    }
+}
+
+/// Fold `other` into `base`: `base` keeps its values, gaps are filled from
+/// `other`, lists and SOURCE are unioned, the larger BOUNDARY, the longer PATH and the union
+/// of EXTENTs are kept, and each CONFIDENCE key keeps the higher rating.
+/// `other`'s ID, if different, is recorded in `extra["same_as"]`.
+pub fn merge_into(base: &mut GeonPlace, other: GeonPlace) {
+    if base.place.is_empty() {
+        base.place = other.place;
+    }
+    if base.type_.is_empty() {
+        base.type_ = other.type_;
+    }
+    match (&base.id, other.id) {
+        (None, id) => base.id = id,
+        (Some(own), Some(id)) if *own != id => {
+            let same_as = base.extra.entry("same_as".to_string()).or_insert_with(|| serde_json::json!([]));
+            if let Some(list) = same_as.as_array_mut() {
+                list.push(serde_json::Value::String(id));
+            }
+        }
+        _ => {}
+    }
+
+    base.location = base.location.take().or(other.location);
+    if ring_area(&other.boundary) > ring_area(&base.boundary) {
+        base.boundary = other.boundary;
+    }
+    if other.path.len() > base.path.len() {
+        base.path = other.path;
+    }
+    base.extent = match (base.extent.take(), other.extent) {
+        (Some(a), Some(b)) => Some(Extent {
+            north: a.north.max(b.north),
+            south: a.south.min(b.south),
+            east: a.east.max(b.east),
+            west: a.west.min(b.west),
+        }),
+        (a, b) => a.or(b),
+    };
+    base.elevation = base.elevation.take().or(other.elevation);
+    base.area = base.area.take().or(other.area);
+    base.part_of = base.part_of.take().or(other.part_of);
+    base.updated = base.updated.take().max(other.updated);
+    if crate::models::is_empty_json_value(&base.viewsheds) {
+        base.viewsheds = other.viewsheds;
+    }
+
+    union(&mut base.purpose, other.purpose);
+    union(&mut base.character, other.character);
+    union(&mut base.adjacencies, other.adjacencies);
+    union(&mut base.source, other.source);
+    for child in other.contains {
+        if !base.contains.iter().any(|c| normalise(&c.place) == normalise(&child.place)) {
+            base.contains.push(child);
+        }
+    }
+    for entry in other.history {
+        if !base.history.contains(&entry) {
+            base.history.push(entry);
+        }
+    }
+
+    for (k, v) in other.confidence {
+        let rank = |v: &str| Level::from_value(v);
+        match base.confidence.get(&k) {
+            Some(own) if rank(own) >= rank(&v) => {}
+            _ => {
+                base.confidence.insert(k, v);
+            }
+        }
+    }
+    fill(&mut base.experience, other.experience);
+    fill(&mut base.connectivity, other.connectivity);
+    fill(&mut base.temporal, other.temporal);
+    fill(&mut base.lifespan, other.lifespan);
+    fill(&mut base.built_form, other.built_form);
+    fill(&mut base.ecology, other.ecology);
+    fill(&mut base.infrastructure, other.infrastructure);
+    fill(&mut base.demographics, other.demographics);
+    fill(&mut base.economy, other.economy);
+    fill(&mut base.visual, other.visual);
+    fill(&mut base.vertical_profile, other.vertical_profile);
+    fill(&mut base.extra, other.extra);
+}
+
+// Point PART_OF and ADJACENCIES entries naming a merged-away ID at the
+// survivor instead, keeping any `(note)`
+fn rewrite_references(place: &mut GeonPlace, renamed: &HashMap<String, String>) {
+    if let Some(chain) = &place.part_of {
+        let names: Vec<&str> = chain.split(',').map(str::trim).map(|n| renamed.get(n).map_or(n, String::as_str)).collect();
+        place.part_of = Some(names.join(", "));
+    }
+    for adjacency in &mut place.adjacencies {
+        let (name, note) = split_note(adjacency);
+        if let Some(new) = renamed.get(name) {
+            *adjacency = match note {
+                Some(note) => format!("{} ({})", new, note),
+                None => new.clone(),
+            };
+        }
+    }
+    for child in &mut place.contains {
+        rewrite_references(child, renamed);
+    }
+}
+
+impl GeonCollection {
+    /// Duplicate groups among the top-level places (see [`duplicate_groups`]).
+    pub fn duplicates(&self, max_distance_m: f64) -> Vec<Vec<usize>> {
+        duplicate_groups(&self.places, max_distance_m)
+    }
+
+    /// Merge each group of duplicates into one record chosen by
+    /// `strategy.survivor` (see [`merge_into`]), which keeps its position
+    /// and file. References to the IDs of merged-away records are rewritten
+    /// to the survivor's ID, or its name if it has none.
+    pub fn dedupe(&mut self, strategy: MergeStrategy) -> Vec<Merged> {
+        let groups = self.duplicates(strategy.max_distance_m);
+        let mut merged = Vec::new();
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        let mut removed = vec![false; self.places.len()];
+
+        for group in groups {
+            let survivor = match strategy.survivor {
+                Survivor::First => group[0],
+                Survivor::MostComplete => *group.iter().rev().max_by_key(|i| completeness(&self.places[**i])).unwrap(),
+                Survivor::MostConfident => *group
+                    .iter()
+                    .rev()
+                    .max_by(|a, b| mean_confidence(&self.places[**a]).total_cmp(&mean_confidence(&self.places[**b])))
+                    .unwrap(),
+            };
+            let mut base = std::mem::take(&mut self.places[survivor]);
+            let mut from = Vec::new();
+            for &i in group.iter().filter(|i| **i != survivor) {
+                let other = std::mem::take(&mut self.places[i]);
+                from.push(key(&other));
+                if let Some(id) = &other.id {
+                    renamed.insert(id.clone(), String::new());
+                }
+                merge_into(&mut base, other);
+                removed[i] = true;
+            }
+            let into = key(&base);
+            for target in renamed.values_mut().filter(|t| t.is_empty()) {
+                *target = into.clone();
+            }
+            self.places[survivor] = base;
+            merged.push(Merged { into, from });
+        }
+
+        // The survivor may have taken over a merged-away ID
+        renamed.retain(|from, to| from != to);
+        let mut i = 0;
+        self.places.retain(|_| {
+            i += 1;
+            !removed[i - 1]
+        });
+        let mut i = 0;
+        self.origins.retain(|_| {
+            i += 1;
+            !removed.get(i - 1).copied().unwrap_or(false)
+        });
+        for place in &mut self.places {
+            rewrite_references(place, &renamed);
+        }
+        self.reindex();
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    fn place(name: &str, id: Option<&str>, lat: f64, lon: f64) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.type_ = "public_space".to_string();
+        p.id = id.map(str::to_string);
+        p.location = Some(Coordinate::new(lat, lon));
+        p
+    }
+
+    #[test]
+    fn test_duplicate_groups() {
+        let places = vec![
+            place("Old Market Square", Some("osm:way/1"), 52.9533, -1.1505),
+            place("The Old Market Square", Some("wd:Q1"), 52.9534, -1.1504),
+            place("Market Square", None, 52.9533, -1.1505),
+            place("Old Market Square", Some("osm:way/2"), 52.9533, -1.1506),
+            place("old market square", None, 53.5, -1.1),
+        ];
+        assert_eq!(duplicate_groups(&places, 100.0), [vec![0, 1, 3]]);
+        assert!(duplicate_groups(&places, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_dedupe_merges_and_rewrites() {
+        let mut a = place("Old Market Square", Some("osm:way/1"), 52.9533, -1.1505);
+        a.source = vec!["OpenStreetMap".to_string()];
+        a.confidence.insert("location".to_string(), "medium".to_string());
+        let mut b = place("old market square", Some("wd:Q1"), 52.9534, -1.1504);
+        b.source = vec!["Wikidata".to_string()];
+        b.purpose = vec!["Civic events".to_string()];
+        b.confidence.insert("location".to_string(), "high".to_string());
+        b.experience.insert("openness".to_string(), "high".to_string());
+        let mut hall = place("Council House", None, 52.9536, -1.1497);
+        hall.adjacencies = vec!["wd:Q1 (west)".to_string()];
+
+        let mut collection = GeonCollection::new(vec![a, hall, b]);
+        let merged = collection.dedupe(MergeStrategy { survivor: Survivor::First, ..Default::default() });
+        assert_eq!(merged, [Merged { into: "osm:way/1".to_string(), from: vec!["wd:Q1".to_string()] }]);
+        assert_eq!(collection.len(), 2);
+
+        let square = &collection.places[0];
+        assert_eq!(square.source, ["OpenStreetMap", "Wikidata"]);
+        assert_eq!(square.purpose, ["Civic events"]);
+        assert_eq!(square.confidence["location"], "high");
+        assert_eq!(square.experience["openness"], "high");
+        assert_eq!(square.extra["same_as"], serde_json::json!(["wd:Q1"]));
+        assert_eq!(collection.places[1].adjacencies, ["osm:way/1 (west)"]);
+    }
+
+    #[test]
+    fn test_dedupe_parsed_text() {
+        let text = "\
+PLACE: Old Market Square
+TYPE: public_space
+ID: osm:way/1
+LOCATION: 52.9533, -1.1505
+SOURCE: OpenStreetMap
+CONFIDENCE:
+  location: medium
+HISTORY:
+  - date: 1928
+    event: Council House opened
+PLACE: old market square
+TYPE: public_space
+ID: wd:Q1
+LOCATION: 52.9534, -1.1504
+PURPOSE:
+  - Civic events
+SOURCE: Wikidata
+CONFIDENCE:
+  location: high
+VISUAL:
+  paving: granite
+PLACE: Council House
+TYPE: building
+LOCATION: 52.9536, -1.1497
+ADJACENCIES:
+  - wd:Q1 (west)
+";
+        let mut collection = GeonCollection::new(crate::parser::parse_many(text));
+        assert_eq!(collection.len(), 3);
+        let merged = collection.dedupe(MergeStrategy { survivor: Survivor::First, ..Default::default() });
+        assert_eq!(merged, [Merged { into: "osm:way/1".to_string(), from: vec!["wd:Q1".to_string()] }]);
+
+        let square = &collection.places[0];
+        assert_eq!(square.source, ["OpenStreetMap", "Wikidata"]);
+        assert_eq!(square.purpose, ["Civic events"]);
+        assert_eq!(square.confidence["location"], "high");
+        assert_eq!(square.visual["paving"], "granite");
+        assert_eq!(square.history[0]["event"], "Council House opened");
+        assert_eq!(collection.places[1].adjacencies, ["osm:way/1 (west)"]);
+        assert_eq!(crate::parse(&crate::generate(square)), *square);
+    }
+}
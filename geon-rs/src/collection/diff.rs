@@ -0,0 +1,392 @@
+use super::dedupe::normalise;
+use super::query::FIELDS;
+use super::GeonCollection;
+use crate::geometry::haversine_m;
+use crate::models::GeonPlace;
+use crate::parser::parse_many;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Places further apart than this are different places even if they share
+/// a name.
+const MATCH_DISTANCE_M: f64 = 100.0;
+
+/// GEON keys whose entries change one at a time.
+pub(crate) const LIST_FIELDS: &[&str] = &["PURPOSE", "CHARACTER", "ADJACENCIES", "SOURCE", "HISTORY", "CONTAINS"];
+
+/// One field-level difference between two versions of a place.
+///
+/// `field` is a GEON key, a key within a section (`EXPERIENCE.noise`), an
+/// `extra` field by its own name, or any of these on a nested place, by
+/// its position in the earlier version (`CONTAINS[2].TYPE`). List entries
+/// are added or removed one at a time, so an added entry has no `before`
+/// and a removed one no `after`.
+/// Nested places, HISTORY entries, VIEWSHEDS and `extra` values are JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "{}: {} -> {}", self.field, before, after),
+            (None, Some(after)) => write!(f, "{}: + {}", self.field, after),
+            (Some(before), None) => write!(f, "{}: - {}", self.field, before),
+            (None, None) => write!(f, "{}", self.field),
+        }
+    }
+}
+
+/// A place in both collections that differs between them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaceChange<'a> {
+    pub before: &'a GeonPlace,
+    pub after: &'a GeonPlace,
+    pub fields: Vec<FieldChange>,
+}
+
+/// What changed from one collection to another, e.g. between two monthly
+/// imports. Displays as a change report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CollectionDiff<'a> {
+    pub added: Vec<&'a GeonPlace>,
+    pub removed: Vec<&'a GeonPlace>,
+    pub changed: Vec<PlaceChange<'a>>,
+}
+
+impl CollectionDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn label(place: &GeonPlace) -> String {
+    match &place.id {
+        Some(id) => format!("{} ({})", place.place, id),
+        None => place.place.clone(),
+    }
+}
+
+impl fmt::Display for CollectionDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for place in &self.added {
+            writeln!(f, "+ {}", label(place))?;
+        }
+        for place in &self.removed {
+            writeln!(f, "- {}", label(place))?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", label(change.after))?;
+            for field in &change.fields {
+                writeln!(f, "    {}", field)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Values by (spec position, field path), with whether the field is a list
+type Flat = BTreeMap<(usize, String), (bool, Vec<String>)>;
+
+fn flatten(place: &GeonPlace) -> Flat {
+    let mut out = Flat::new();
+    let mut put = |key: &str, sub: Option<&str>, value: String| {
+        let rank = FIELDS.iter().position(|f| *f == key).unwrap_or(FIELDS.len());
+        let path = match sub {
+            Some(sub) => format!("{}.{}", key, sub),
+            None => key.to_string(),
+        };
+        let entry = out.entry((rank, path)).or_insert_with(|| (LIST_FIELDS.contains(&key), Vec::new()));
+        entry.1.push(value);
+    };
+
+    let scalars = [
+        ("PLACE", Some(place.place.clone()).filter(|p| !p.is_empty())),
+        ("TYPE", Some(place.type_.clone()).filter(|t| !t.is_empty())),
+        ("ID", place.id.clone()),
+        ("LOCATION", place.location.as_ref().map(|c| c.to_string())),
+        ("EXTENT", place.extent.as_ref().map(|e| e.to_string())),
+        ("ELEVATION", place.elevation.clone()),
+        ("AREA", place.area.clone()),
+        ("PART_OF", place.part_of.clone()),
+        ("UPDATED", place.updated.clone()),
+    ];
+    for (key, value) in scalars {
+        if let Some(value) = value {
+            put(key, None, value);
+        }
+    }
+    if !place.boundary.is_empty() {
+        let points: Vec<String> = place.boundary.iter().map(|c| c.to_string()).collect();
+        put("BOUNDARY", None, points.join("; "));
+    }
+    if !place.path.is_empty() {
+        let points: Vec<String> = place.path.iter().map(|c| c.to_string()).collect();
+        put("PATH", None, points.join("; "));
+    }
+    if !crate::models::is_empty_json_value(&place.viewsheds) {
+        put("VIEWSHEDS", None, place.viewsheds.to_string());
+    }
+
+    let lists = [
+        ("PURPOSE", &place.purpose),
+        ("CHARACTER", &place.character),
+        ("ADJACENCIES", &place.adjacencies),
+        ("SOURCE", &place.source),
+    ];
+    for (key, items) in lists {
+        for item in items {
+            put(key, None, item.clone());
+        }
+    }
+    for entry in &place.history {
+        put("HISTORY", None, serde_json::to_string(entry).unwrap_or_default());
+    }
+
+    for (key, map) in sections(place) {
+        for (k, v) in map {
+            put(key, Some(k), v.clone());
+        }
+    }
+    for (key, value) in &place.extra {
+        put(key, None, value.to_string());
+    }
+    out
+}
+
+/// The key-value sections of a place, by GEON key.
+pub(crate) fn sections(place: &GeonPlace) -> [(&'static str, &BTreeMap<String, String>); 12] {
+    [
+        ("EXPERIENCE", &place.experience),
+        ("CONNECTIVITY", &place.connectivity),
+        ("TEMPORAL", &place.temporal),
+        ("LIFESPAN", &place.lifespan),
+        ("CONFIDENCE", &place.confidence),
+        ("BUILT_FORM", &place.built_form),
+        ("ECOLOGY", &place.ecology),
+        ("INFRASTRUCTURE", &place.infrastructure),
+        ("DEMOGRAPHICS", &place.demographics),
+        ("ECONOMY", &place.economy),
+        ("VISUAL", &place.visual),
+        ("VERTICAL_PROFILE", &place.vertical_profile),
+    ]
+}
+
+fn changes_into(a: &GeonPlace, b: &GeonPlace, prefix: &str, out: &mut Vec<FieldChange>) {
+    let (before, after) = (flatten(a), flatten(b));
+    let mut paths: Vec<&(usize, String)> = before.keys().chain(after.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let empty = (false, Vec::new());
+    for path in paths {
+        let (is_list, old) = before.get(path).unwrap_or(&empty);
+        let (_, new) = after.get(path).unwrap_or(&empty);
+        let field = format!("{}{}", prefix, path.1);
+        if *is_list || after.get(path).is_some_and(|(l, _)| *l) {
+            // Entries in `old` but not `new` were removed, and the rest of
+            // `new` added
+            let mut rest: Vec<&String> = new.iter().collect();
+            for item in old {
+                match rest.iter().position(|x| *x == item) {
+                    Some(i) => {
+                        rest.remove(i);
+                    }
+                    None => out.push(FieldChange { field: field.clone(), before: Some(item.clone()), after: None }),
+                }
+            }
+            for item in rest {
+                out.push(FieldChange { field: field.clone(), before: None, after: Some(item.clone()) });
+            }
+        } else if old.first() != new.first() {
+            out.push(FieldChange { field, before: old.first().cloned(), after: new.first().cloned() });
+        }
+    }
+
+    // Nested places pair up by name and are compared field by field, by
+    // their position in `a`. Removals and additions come after every edit
+    // so a patch can apply them in order without the positions moving.
+    let field = format!("{}CONTAINS", prefix);
+    let pairs = pair_children(&a.contains, &b.contains);
+    for (i, pair) in pairs.iter().enumerate() {
+        if let Some(j) = pair {
+            changes_into(&a.contains[i], &b.contains[*j], &format!("{}[{}].", field, i), out);
+        }
+    }
+    for (child, _) in a.contains.iter().zip(&pairs).filter(|(_, pair)| pair.is_none()) {
+        out.push(FieldChange { field: field.clone(), before: serde_json::to_string(child).ok(), after: None });
+    }
+    for (j, child) in b.contains.iter().enumerate() {
+        if !pairs.contains(&Some(j)) {
+            out.push(FieldChange { field: field.clone(), before: None, after: serde_json::to_string(child).ok() });
+        }
+    }
+}
+
+/// For each place nested in `a`, the index of the same place in `b`:
+/// the first not yet taken with the same name (see
+/// [`GeonCollection::dedupe`] for how names compare).
+pub(crate) fn pair_children(a: &[GeonPlace], b: &[GeonPlace]) -> Vec<Option<usize>> {
+    let mut taken = vec![false; b.len()];
+    a.iter()
+        .map(|child| {
+            let j = (0..b.len()).find(|&j| !taken[j] && normalise(&b[j].place) == normalise(&child.place))?;
+            taken[j] = true;
+            Some(j)
+        })
+        .collect()
+}
+
+/// Every field-level difference from `a` to `b`, in spec order with nested
+/// places last.
+pub fn field_changes(a: &GeonPlace, b: &GeonPlace) -> Vec<FieldChange> {
+    let mut out = Vec::new();
+    changes_into(a, b, "", &mut out);
+    out
+}
+
+/// A change report (see [`CollectionDiff`]) between two GEON documents,
+/// empty when they describe the same places. Layout, key order and comments
+/// don't count as changes, since both are compared as parsed.
+pub fn diff_text(a: &str, b: &str) -> String {
+    let read = |text: &str| GeonCollection::new(parse_many(text));
+    let (a, b) = (read(a), read(b));
+    a.diff(&b).to_string()
+}
+
+fn same_place(a: &GeonPlace, b: &GeonPlace) -> bool {
+    // Both have IDs and they differ: already ruled out by ID matching
+    if a.id.is_some() && b.id.is_some() {
+        return false;
+    }
+    let near = match (&a.location, &b.location) {
+        (Some(x), Some(y)) => haversine_m(x, y) <= MATCH_DISTANCE_M,
+        _ => true,
+    };
+    near && normalise(&a.place) == normalise(&b.place)
+}
+
+impl GeonCollection {
+    /// Places added, removed and changed from this collection to `other`.
+    ///
+    /// Places pair up by ID, then, where either has no ID, by name (see
+    /// [`GeonCollection::dedupe`] for how names compare) within 100 m of
+    /// each other.
+    pub fn diff<'a>(&'a self, other: &'a GeonCollection) -> CollectionDiff<'a> {
+        let mut by_id: HashMap<&str, usize> = HashMap::new();
+        for (j, place) in other.places.iter().enumerate() {
+            if let Some(id) = &place.id {
+                by_id.entry(id).or_insert(j);
+            }
+        }
+
+        let mut pairs: Vec<Option<usize>> = vec![None; self.places.len()];
+        let mut taken = vec![false; other.places.len()];
+        for (i, place) in self.places.iter().enumerate() {
+            if let Some(&j) = place.id.as_deref().and_then(|id| by_id.get(id)) {
+                if !taken[j] {
+                    pairs[i] = Some(j);
+                    taken[j] = true;
+                }
+            }
+        }
+        for (i, place) in self.places.iter().enumerate() {
+            if pairs[i].is_some() {
+                continue;
+            }
+            let found = other.places.iter().enumerate().position(|(j, o)| !taken[j] && same_place(place, o));
+            if let Some(j) = found {
+                pairs[i] = Some(j);
+                taken[j] = true;
+            }
+        }
+
+        let mut diff = CollectionDiff::default();
+        for (place, pair) in self.places.iter().zip(&pairs) {
+            match pair {
+                Some(j) => {
+                    let after = &other.places[*j];
+                    let fields = field_changes(place, after);
+                    if !fields.is_empty() {
+                        diff.changed.push(PlaceChange { before: place, after, fields });
+                    }
+                }
+                None => diff.removed.push(place),
+            }
+        }
+        diff.added = other.places.iter().zip(&taken).filter(|(_, t)| !**t).map(|(p, _)| p).collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::format;
+    use crate::models::Coordinate;
+
+    fn place(name: &str, id: Option<&str>) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.id = id.map(str::to_string);
+        p
+    }
+
+    #[test]
+    fn test_collection_diff() {
+        let mut square = place("Old Market Square", Some("osm:way/1"));
+        square.type_ = "plaza".to_string();
+        square.purpose = vec!["market".to_string(), "parking".to_string()];
+        let mut hall = place("Council House", None);
+        hall.location = Some(Coordinate::new(52.9531, -1.1497));
+        hall.contains = vec![place("Ballroom", None)];
+        let before = GeonCollection::new(vec![square.clone(), hall.clone(), place("Kiosk", None)]);
+
+        square.type_ = "square".to_string();
+        square.purpose = vec!["market".to_string(), "events".to_string()];
+        square.place = "Market Square".to_string();
+        hall.location = Some(Coordinate::new(52.9532, -1.1497));
+        hall.experience.insert("noise".to_string(), "quiet".to_string());
+        hall.contains[0].type_ = "room".to_string();
+        let after = GeonCollection::new(vec![place("Cafe", None), hall, square]);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added[0].place, "Cafe");
+        assert_eq!(diff.removed[0].place, "Kiosk");
+        let report: Vec<String> = diff.changed.iter().flat_map(|c| &c.fields).map(|f| f.to_string()).collect();
+        assert_eq!(
+            report,
+            [
+                "PLACE: Old Market Square -> Market Square",
+                "TYPE: plaza -> square",
+                "PURPOSE: - parking",
+                "PURPOSE: + events",
+                "LOCATION: 52.9531, -1.1497 -> 52.9532, -1.1497",
+                "EXPERIENCE.noise: + quiet",
+                "CONTAINS[0].TYPE: + room",
+            ]
+        );
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_diff_text() {
+        let a = "PLACE: Arboretum\nEXPERIENCE:\n  noise_level: loud\n  light: dappled\n";
+        let b = "# reformatted\nPLACE:   Arboretum\n\nEXPERIENCE:\n  light:   dappled\n  noise_level: moderate\n";
+        assert_eq!(diff_text(a, b), "~ Arboretum\n    EXPERIENCE.noise_level: loud -> moderate\n");
+        assert_eq!(diff_text(a, &format(a).unwrap()), "");
+
+        let a = "PLACE: Arboretum\nCONFIDENCE:\n  geometry: high\nHISTORY:\n  - date: 1852\n    event: opened\n";
+        let b = "PLACE: Arboretum\nCONFIDENCE:\n  geometry: medium\nHISTORY:\n  - date: 1852\n    event: opened to the public\n";
+        assert_eq!(
+            diff_text(a, b),
+            "~ Arboretum\n    CONFIDENCE.geometry: high -> medium\n    \
+             HISTORY: - {\"date\":\"1852\",\"event\":\"opened\"}\n    \
+             HISTORY: + {\"date\":\"1852\",\"event\":\"opened to the public\"}\n"
+        );
+    }
+}
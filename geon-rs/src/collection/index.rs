@@ -0,0 +1,280 @@
+use crate::geometry::{haversine_m, ring_contains, METRES_PER_DEGREE};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use std::collections::{HashMap, HashSet};
+
+/// What a place must do to match a spatial query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpatialMatch {
+    /// LOCATION falls inside the query area; places without one never match.
+    #[default]
+    Location,
+    /// The place's geometry touches the query area: BOUNDARY where present,
+    /// else PATH, else EXTENT, else LOCATION.
+    Intersects,
+}
+
+// (west, south, east, north)
+pub(crate) type Bounds = (f64, f64, f64, f64);
+
+pub(crate) fn place_bounds(place: &GeonPlace) -> Option<Bounds> {
+    let mut points: Vec<(f64, f64)> = place.boundary.iter().chain(&place.path).map(|c| (c.lon, c.lat)).collect();
+    points.extend(place.location.iter().map(|c| (c.lon, c.lat)));
+    if let Some(e) = &place.extent {
+        points.extend([(e.west, e.south), (e.east, e.north)]);
+    }
+    let (first, rest) = points.split_first()?;
+    Some(rest.iter().fold((first.0, first.1, first.0, first.1), |(w, s, e, n), (x, y)| {
+        (w.min(*x), s.min(*y), e.max(*x), n.max(*y))
+    }))
+}
+
+// Grid cells covered by `bounds`
+fn cell_keys(cell: f64, (w, s, e, n): Bounds) -> impl Iterator<Item = (i64, i64)> {
+    let key = |v: f64| (v / cell).floor() as i64;
+    let (x0, x1, y0, y1) = (key(w), key(e), key(s), key(n));
+    (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+}
+
+/// A uniform grid over place bounding boxes, sized to the data so that each
+/// cell holds a handful of places. Queries return candidates to be checked
+/// exactly against the places themselves.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SpatialIndex {
+    cell: f64,
+    extent: Option<Bounds>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    pub(crate) len: usize,
+}
+
+impl SpatialIndex {
+    pub(crate) fn build(places: &[GeonPlace]) -> Self {
+        let bounds: Vec<Option<Bounds>> = places.iter().map(place_bounds).collect();
+        let extent = bounds.iter().flatten().copied().reduce(|a, b| {
+            (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))
+        });
+        let Some((w, s, e, n)) = extent else {
+            return Self { cell: 1.0, len: places.len(), ..Default::default() };
+        };
+        // Roughly sqrt(n) cells a side, no finer than about 10 m
+        let side = (bounds.iter().flatten().count() as f64).sqrt().ceil();
+        let cell = ((e - w).max(n - s) / side).max(1e-4);
+
+        let mut index = Self { cell, extent, cells: HashMap::new(), len: places.len() };
+        for (i, b) in bounds.iter().enumerate() {
+            if let Some(b) = b {
+                for key in cell_keys(cell, *b) {
+                    index.cells.entry(key).or_default().push(i);
+                }
+            }
+        }
+        index
+    }
+
+    /// Re-file the `i`th place, new or edited, without rebuilding. The
+    /// cell size stays as built, so an index that has grown far past its
+    /// original size is better rebuilt.
+    pub(crate) fn set(&mut self, i: usize, place: &GeonPlace) {
+        self.unset(i);
+        if let Some(b) = place_bounds(place) {
+            self.extent = Some(match self.extent {
+                Some(e) => (e.0.min(b.0), e.1.min(b.1), e.2.max(b.2), e.3.max(b.3)),
+                None => b,
+            });
+            for key in cell_keys(self.cell, b) {
+                self.cells.entry(key).or_default().push(i);
+            }
+        }
+        self.len = self.len.max(i + 1);
+    }
+
+    /// Drop the `i`th place, shifting later indices down as `Vec::remove` does.
+    pub(crate) fn remove(&mut self, i: usize) {
+        self.unset(i);
+        for indices in self.cells.values_mut() {
+            indices.iter_mut().filter(|j| **j > i).for_each(|j| *j -= 1);
+        }
+        self.len -= 1;
+    }
+
+    fn unset(&mut self, i: usize) {
+        self.cells.retain(|_, indices| {
+            indices.retain(|j| *j != i);
+            !indices.is_empty()
+        });
+    }
+
+    /// Indices of places whose bounding box may overlap `bounds`, in order.
+    fn candidates(&self, (w, s, e, n): Bounds) -> Vec<usize> {
+        let Some((iw, is, ie, inn)) = self.extent else { return vec![] };
+        if w > ie || e < iw || s > inn || n < is {
+            return vec![];
+        }
+        // Clamp to the indexed area so huge windows stay cheap
+        let clamped = (w.max(iw), s.max(is), e.min(ie), n.min(inn));
+        let mut found: Vec<usize> = cell_keys(self.cell, clamped).filter_map(|k| self.cells.get(&k)).flatten().copied().collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    pub(crate) fn query_bbox(&self, places: &[GeonPlace], extent: &Extent, mode: SpatialMatch) -> Vec<usize> {
+        // An extent crossing the antimeridian is two windows
+        let windows = if extent.west > extent.east {
+            vec![(extent.west, extent.south, 180.0, extent.north), (-180.0, extent.south, extent.east, extent.north)]
+        } else {
+            vec![(extent.west, extent.south, extent.east, extent.north)]
+        };
+        let mut found: Vec<usize> = windows
+            .into_iter()
+            .flat_map(|window| {
+                self.candidates(window).into_iter().filter(move |i| match mode {
+                    SpatialMatch::Location => places[*i].location.as_ref().is_some_and(|c| in_bounds(c, window)),
+                    SpatialMatch::Intersects => intersects_bounds(&places[*i], window),
+                })
+            })
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// The `k` places nearest `center` by LOCATION among those `accept`ed,
+    /// nearest first, with their distances in metres. Searches outwards a
+    /// ring of cells at a time until nothing unsearched could be closer.
+    pub(crate) fn nearest(
+        &self,
+        places: &[GeonPlace],
+        center: &Coordinate,
+        k: usize,
+        accept: impl Fn(&GeonPlace) -> bool,
+    ) -> Vec<(usize, f64)> {
+        let Some((w, s, e, n)) = self.extent else { return vec![] };
+        if k == 0 {
+            return vec![];
+        }
+        let key = |v: f64| (v / self.cell).floor() as i64;
+        let (cx, cy) = (key(center.lon), key(center.lat));
+        let (x0, x1, y0, y1) = (key(w), key(e), key(s), key(n));
+        // Lower bound on metres per cell anywhere between the centre and the data
+        let max_lat = center.lat.abs().max(s.abs()).max(n.abs()).min(90.0);
+        let cell_m = self.cell * METRES_PER_DEGREE * max_lat.to_radians().cos().max(1e-6);
+        let first_ring = (x0 - cx).max(cx - x1).max(y0 - cy).max(cy - y1).max(0);
+        let last_ring = (x1 - cx).max(cx - x0).max(y1 - cy).max(cy - y0).max(0);
+
+        let mut seen = HashSet::new();
+        let mut found: Vec<(usize, f64)> = Vec::new();
+        for r in first_ring..=last_ring {
+            // Cells of ring r that fall within the indexed area
+            for y in (cy - r).max(y0)..=(cy + r).min(y1) {
+                let xs: Vec<i64> = if y == cy - r || y == cy + r {
+                    ((cx - r).max(x0)..=(cx + r).min(x1)).collect()
+                } else {
+                    [cx - r, cx + r].into_iter().filter(|x| (x0..=x1).contains(x)).collect()
+                };
+                for i in xs.into_iter().filter_map(|x| self.cells.get(&(x, y))).flatten() {
+                    if !seen.insert(*i) || !accept(&places[*i]) {
+                        continue;
+                    }
+                    if let Some(at) = &places[*i].location {
+                        found.push((*i, haversine_m(center, at)));
+                    }
+                }
+            }
+            found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            // Anything beyond this ring is at least r cells away
+            if found.len() >= k && found[k - 1].1 <= r as f64 * cell_m {
+                break;
+            }
+        }
+        found.truncate(k);
+        found
+    }
+
+    pub(crate) fn query_radius(
+        &self,
+        places: &[GeonPlace],
+        center: &Coordinate,
+        metres: f64,
+        mode: SpatialMatch,
+    ) -> Vec<usize> {
+        let dlat = metres / METRES_PER_DEGREE;
+        let dlon = dlat / center.lat.to_radians().cos().max(1e-6);
+        let window = (center.lon - dlon, center.lat - dlat, center.lon + dlon, center.lat + dlat);
+        self.candidates(window)
+            .into_iter()
+            .filter(|i| match mode {
+                SpatialMatch::Location => places[*i].location.as_ref().is_some_and(|c| haversine_m(center, c) <= metres),
+                SpatialMatch::Intersects => distance_m(&places[*i], center).is_some_and(|d| d <= metres),
+            })
+            .collect()
+    }
+}
+
+fn in_bounds(c: &Coordinate, (w, s, e, n): Bounds) -> bool {
+    (w..=e).contains(&c.lon) && (s..=n).contains(&c.lat)
+}
+
+// Whether segments ab and cd cross or touch, in lon/lat
+fn segments_intersect(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let cross = |o: (f64, f64), p: (f64, f64), q: (f64, f64)| (p.0 - o.0) * (q.1 - o.1) - (p.1 - o.1) * (q.0 - o.0);
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    d1 * d2 <= 0.0 && d3 * d4 <= 0.0
+}
+
+fn intersects_bounds(place: &GeonPlace, window: Bounds) -> bool {
+    let (w, s, e, n) = window;
+    if place.boundary.len() >= 3 {
+        let ring = &place.boundary;
+        let corners = [(w, s), (e, s), (e, n), (w, n)];
+        return ring.iter().any(|c| in_bounds(c, window))
+            || corners.iter().any(|(x, y)| ring_contains(ring, &Coordinate::new(*y, *x)))
+            || (0..ring.len()).any(|i| {
+                let (a, b) = (&ring[i], &ring[(i + 1) % ring.len()]);
+                (0..4).any(|j| segments_intersect((a.lon, a.lat), (b.lon, b.lat), corners[j], corners[(j + 1) % 4]))
+            });
+    }
+    if place.path.len() >= 2 {
+        let corners = [(w, s), (e, s), (e, n), (w, n)];
+        return place.path.iter().any(|c| in_bounds(c, window))
+            || place.path.windows(2).any(|p| {
+                (0..4).any(|j| segments_intersect((p[0].lon, p[0].lat), (p[1].lon, p[1].lat), corners[j], corners[(j + 1) % 4]))
+            });
+    }
+    if let Some(x) = &place.extent {
+        return x.west <= e && x.east >= w && x.south <= n && x.north >= s;
+    }
+    place.location.as_ref().is_some_and(|c| in_bounds(c, window))
+}
+
+/// Distance in metres from `center` to the nearest part of a place's
+/// geometry (BOUNDARY, else PATH, else EXTENT, else LOCATION); zero inside it.
+pub(crate) fn distance_m(place: &GeonPlace, center: &Coordinate) -> Option<f64> {
+    // Local planar metres around the centre, accurate at query scales
+    let scale = center.lat.to_radians().cos();
+    let project = |c: &Coordinate| ((c.lon - center.lon) * scale * METRES_PER_DEGREE, (c.lat - center.lat) * METRES_PER_DEGREE);
+    let to_segment = |a: (f64, f64), b: (f64, f64)| {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 > 0.0 { (-(a.0 * dx + a.1 * dy) / len2).clamp(0.0, 1.0) } else { 0.0 };
+        (a.0 + t * dx).hypot(a.1 + t * dy)
+    };
+
+    if place.boundary.len() >= 3 {
+        let ring = &place.boundary;
+        if ring_contains(ring, center) {
+            return Some(0.0);
+        }
+        let points: Vec<(f64, f64)> = ring.iter().map(project).collect();
+        return (0..points.len()).map(|i| to_segment(points[i], points[(i + 1) % points.len()])).reduce(f64::min);
+    }
+    if place.path.len() >= 2 {
+        let points: Vec<(f64, f64)> = place.path.iter().map(project).collect();
+        return points.windows(2).map(|p| to_segment(p[0], p[1])).reduce(f64::min);
+    }
+    if let Some(e) = &place.extent {
+        let nearest = Coordinate::new(center.lat.clamp(e.south, e.north), center.lon.clamp(e.west, e.east));
+        return Some(haversine_m(center, &nearest));
+    }
+    place.location.as_ref().map(|c| haversine_m(center, c))
+}
@@ -0,0 +1,201 @@
+use super::{find_geon_files, GeonCollection};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::{documents, parse, parse_coordinate, GeonError};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The top-level identity and position of a place, read without parsing
+/// the rest of its document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaceHeader {
+    pub place: String,
+    pub type_: String,
+    pub id: Option<String>,
+    pub location: Option<Coordinate>,
+}
+
+impl PlaceHeader {
+    /// Read the unindented PLACE, TYPE, ID and LOCATION lines of a document.
+    pub fn scan(text: &str) -> Self {
+        let mut header = Self::default();
+        for line in text.lines().filter(|l| !l.starts_with(char::is_whitespace)) {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let value = value.trim();
+            match key {
+                "PLACE" => header.place = value.to_string(),
+                "TYPE" => header.type_ = value.to_string(),
+                "ID" => header.id = Some(value.to_string()),
+                "LOCATION" => header.location = parse_coordinate(value),
+                _ => {}
+            }
+        }
+        header
+    }
+}
+
+// Parsed places by index, and the order they were last used in
+#[derive(Debug, Default)]
+struct Lru {
+    places: HashMap<usize, (Arc<GeonPlace>, u64)>,
+    by_use: BTreeMap<u64, usize>,
+    clock: u64,
+}
+
+/// A collection that keeps each place as GEON text with its
+/// [`PlaceHeader`], and parses a place in full only when it is asked for.
+/// The most recently used `capacity` parsed places are kept.
+///
+/// Loading only scans a few lines per document, so a corpus of millions of
+/// places is ready to search by name, ID or position almost at once.
+#[derive(Debug)]
+pub struct LazyCollection {
+    texts: Vec<String>,
+    headers: Vec<PlaceHeader>,
+    ids: HashMap<String, usize>,
+    capacity: usize,
+    cache: Mutex<Lru>,
+}
+
+impl LazyCollection {
+    pub fn new(capacity: usize) -> Self {
+        Self { texts: Vec::new(), headers: Vec::new(), ids: HashMap::new(), capacity: capacity.max(1), cache: Mutex::default() }
+    }
+
+    /// Add every document in a multi-document GEON text. Documents without
+    /// a PLACE are skipped.
+    pub fn push_text(&mut self, text: &str) {
+        for doc in documents(text) {
+            let header = PlaceHeader::scan(doc);
+            if header.place.is_empty() {
+                continue;
+            }
+            if let Some(id) = &header.id {
+                self.ids.entry(id.clone()).or_insert(self.texts.len());
+            }
+            self.texts.push(doc.to_string());
+            self.headers.push(header);
+        }
+    }
+
+    /// Load every `*.geon` file under `dir`, in path order, as
+    /// [`GeonCollection::from_dir`] does, but without parsing.
+    pub fn from_dir(dir: impl AsRef<Path>, capacity: usize) -> Result<Self, GeonError> {
+        let mut files = Vec::new();
+        find_geon_files(dir.as_ref(), &mut files)?;
+        files.sort();
+        let mut collection = Self::new(capacity);
+        for file in files {
+            collection.push_text(&std::fs::read_to_string(file)?);
+        }
+        Ok(collection)
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    pub fn headers(&self) -> &[PlaceHeader] {
+        &self.headers
+    }
+
+    /// The GEON text of the `i`th place.
+    pub fn text(&self, i: usize) -> Option<&str> {
+        self.texts.get(i).map(String::as_str)
+    }
+
+    /// The `i`th place, parsed now unless it was used recently.
+    pub fn get(&self, i: usize) -> Option<Arc<GeonPlace>> {
+        let text = self.texts.get(i)?;
+        let mut lru = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        lru.clock += 1;
+        let now = lru.clock;
+        if let Some((place, used)) = lru.places.get_mut(&i) {
+            let (place, last) = (place.clone(), std::mem::replace(used, now));
+            lru.by_use.remove(&last);
+            lru.by_use.insert(now, i);
+            return Some(place);
+        }
+
+        let place = Arc::new(parse(text));
+        if lru.places.len() >= self.capacity {
+            if let Some((_, oldest)) = lru.by_use.pop_first() {
+                lru.places.remove(&oldest);
+            }
+        }
+        lru.places.insert(i, (place.clone(), now));
+        lru.by_use.insert(now, i);
+        Some(place)
+    }
+
+    /// How many parsed places are held.
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).places.len()
+    }
+
+    /// The place with this ID (the first, if the ID is duplicated).
+    pub fn by_id(&self, id: &str) -> Option<Arc<GeonPlace>> {
+        self.get(*self.ids.get(id)?)
+    }
+
+    /// Indices of places with this PLACE name, ignoring case.
+    pub fn find(&self, name: &str) -> Vec<usize> {
+        (0..self.len()).filter(|i| self.headers[*i].place.eq_ignore_ascii_case(name.trim())).collect()
+    }
+
+    /// Indices of places whose LOCATION lies within `extent` (which may
+    /// cross the antimeridian), read from headers alone.
+    pub fn within(&self, extent: &Extent) -> Vec<usize> {
+        let inside = |c: &Coordinate| {
+            let lon = if extent.west > extent.east {
+                c.lon >= extent.west || c.lon <= extent.east
+            } else {
+                (extent.west..=extent.east).contains(&c.lon)
+            };
+            lon && (extent.south..=extent.north).contains(&c.lat)
+        };
+        (0..self.len()).filter(|i| self.headers[*i].location.as_ref().is_some_and(inside)).collect()
+    }
+
+    /// Parse everything into a [`GeonCollection`].
+    pub fn to_collection(&self) -> GeonCollection {
+        GeonCollection::new(self.texts.iter().map(|t| parse(t)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy_collection() {
+        let text = "# Nottingham\n\
+                    PLACE: Old Market Square\nID: osm:way/1\nLOCATION: 52.9529, -1.1505\nCONTAINS:\n  - PLACE: Fountain\n    TYPE: landmark\n\
+                    PLACE: Council House\nTYPE: building\nLOCATION: 52.9531, -1.1497\n\
+                    PLACE: Trafalgar Square\nLOCATION: 51.5080, -0.1281\n";
+        let mut lazy = LazyCollection::new(2);
+        lazy.push_text(text);
+        assert_eq!(lazy.len(), 3);
+        assert_eq!(lazy.headers()[1].type_, "building");
+        assert!(lazy.text(0).unwrap().starts_with("# Nottingham\nPLACE: Old Market Square"));
+        assert_eq!(lazy.cached(), 0);
+
+        let nottingham = Extent { north: 52.96, south: 52.95, east: -1.14, west: -1.16 };
+        assert_eq!(lazy.within(&nottingham), [0, 1]);
+        assert_eq!(lazy.by_id("osm:way/1").unwrap().contains[0].place, "Fountain");
+        assert_eq!(lazy.get(1).unwrap().type_, "building");
+        // Reading the square again keeps it; the hall is evicted next
+        lazy.get(0);
+        assert_eq!(lazy.get(lazy.find("trafalgar square")[0]).unwrap().place, "Trafalgar Square");
+        assert_eq!(lazy.cached(), 2);
+        let lru = lazy.cache.lock().unwrap();
+        assert!(lru.places.contains_key(&0) && !lru.places.contains_key(&1));
+        drop(lru);
+
+        assert_eq!(lazy.to_collection().places[1], parse(lazy.text(1).unwrap()));
+    }
+}
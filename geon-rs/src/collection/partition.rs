@@ -0,0 +1,237 @@
+use super::{write_files, GeonCollection};
+use crate::geometry::centroid;
+use crate::io::mvt::{tile_for, TileId};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How to cut a collection into spatial shards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grid {
+    /// Square cells of this many degrees, aligned to 0°, 0°.
+    Degrees(f64),
+    /// XYZ slippy-map tiles at this zoom level.
+    Tiles(u8),
+}
+
+impl Grid {
+    pub fn degrees(size: f64) -> Self {
+        Self::Degrees(size)
+    }
+
+    pub fn tiles(zoom: u8) -> Self {
+        Self::Tiles(zoom)
+    }
+
+    /// The cell containing `c`.
+    pub fn cell(&self, c: &Coordinate) -> Cell {
+        match *self {
+            Self::Degrees(size) => Cell::Grid((c.lon / size).floor() as i64, (c.lat / size).floor() as i64),
+            Self::Tiles(zoom) => Cell::Tile(tile_for(c, zoom)),
+        }
+    }
+
+    /// The area covered by `cell`, or `None` if it belongs to a different
+    /// kind of grid.
+    pub fn bounds(&self, cell: &Cell) -> Option<Extent> {
+        match (*self, *cell) {
+            (Self::Degrees(size), Cell::Grid(x, y)) => Some(Extent {
+                north: (y + 1) as f64 * size,
+                south: y as f64 * size,
+                east: (x + 1) as f64 * size,
+                west: x as f64 * size,
+            }),
+            (Self::Tiles(zoom), Cell::Tile((z, x, y))) if z == zoom => {
+                let n = (1u64 << z) as f64;
+                let lat = |y: u32| (std::f64::consts::PI * (1.0 - 2.0 * y as f64 / n)).sinh().atan().to_degrees();
+                Some(Extent {
+                    north: lat(y),
+                    south: lat(y + 1),
+                    east: (x + 1) as f64 / n * 360.0 - 180.0,
+                    west: x as f64 / n * 360.0 - 180.0,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One shard of a [`Grid`]: a degree cell by column and row, or a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cell {
+    Grid(i64, i64),
+    Tile(TileId),
+}
+
+impl Cell {
+    /// Relative directory for the shard's files: `x<col>_y<row>` or `z/x/y`.
+    pub fn path(&self) -> PathBuf {
+        match self {
+            Self::Grid(x, y) => PathBuf::from(format!("x{}_y{}", x, y)),
+            Self::Tile((z, x, y)) => [z.to_string(), x.to_string(), y.to_string()].iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grid(x, y) => write!(f, "x{}_y{}", x, y),
+            Self::Tile((z, x, y)) => write!(f, "{}/{}/{}", z, x, y),
+        }
+    }
+}
+
+/// A collection split into shards, each a collection of its own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Partition {
+    pub shards: BTreeMap<Cell, GeonCollection>,
+    /// Places with no LOCATION, BOUNDARY or EXTENT.
+    pub unplaced: GeonCollection,
+}
+
+// The point that decides a place's shard
+pub(super) fn anchor(place: &GeonPlace) -> Option<Coordinate> {
+    if let Some(at) = &place.location {
+        return Some(at.clone());
+    }
+    if let Some(at) = centroid(&place.boundary) {
+        return Some(at);
+    }
+    place.extent.as_ref().map(Extent::center)
+}
+
+impl Partition {
+    /// Write each shard under `dir` in its cell's directory (see
+    /// [`Cell::path`]) and unplaced places under `dir/unplaced`, keeping
+    /// each place's own relative path. Returns the paths written.
+    pub fn save_to_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, GeonError> {
+        write_files(self.files(dir.as_ref()), false)
+    }
+
+    fn files(&self, dir: &Path) -> Vec<(PathBuf, String)> {
+        let mut files = Vec::new();
+        for (cell, shard) in &self.shards {
+            files.extend(shard.files(&dir.join(cell.path())));
+        }
+        files.extend(self.unplaced.files(&dir.join("unplaced")));
+        files
+    }
+}
+
+/// How [`GeonCollection::save_sharded`] splits a collection into
+/// directories.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShardBy {
+    /// XYZ tiles at this zoom, as [`Grid::Tiles`]; places with nowhere to
+    /// anchor them go under `unplaced`.
+    Tile(u8),
+    /// TYPE, one directory per type; places without one go under `untyped`.
+    Type,
+}
+
+// Directory name for a TYPE, e.g. `public_space` or `natural/peak` ->
+// `natural_peak`
+fn type_dir(type_: &str) -> String {
+    let name: String = type_
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if name.is_empty() { "untyped".to_string() } else { name }
+}
+
+impl GeonCollection {
+    /// Split the top-level places into grid shards by LOCATION, or by the
+    /// middle of BOUNDARY or EXTENT where there is none. Each place goes to
+    /// exactly one shard, with its nested places and file provenance.
+    pub fn partition(&self, grid: Grid) -> Partition {
+        let mut partition = Partition::default();
+        for (i, place) in self.places.iter().enumerate() {
+            let shard = match anchor(place) {
+                Some(at) => partition.shards.entry(grid.cell(&at)).or_default(),
+                None => &mut partition.unplaced,
+            };
+            match self.path(i) {
+                Some(path) => shard.push_from(place.clone(), path),
+                None => shard.push(place.clone()),
+            }
+        }
+        partition
+    }
+
+    /// Write the collection under `dir` split into shard directories, each
+    /// place at its own relative path within its shard. Returns the paths
+    /// written.
+    ///
+    /// With `atomic`, every file is staged beside its destination and
+    /// renamed into place once all are written, so concurrent readers never
+    /// see a half-written `.geon` file and a failed save changes nothing.
+    pub fn save_sharded(&self, dir: impl AsRef<Path>, by: ShardBy, atomic: bool) -> Result<Vec<PathBuf>, GeonError> {
+        let dir = dir.as_ref();
+        let files = match by {
+            ShardBy::Tile(zoom) => self.partition(Grid::tiles(zoom)).files(dir),
+            ShardBy::Type => {
+                let mut shards: BTreeMap<String, GeonCollection> = BTreeMap::new();
+                for (i, place) in self.places.iter().enumerate() {
+                    let shard = shards.entry(type_dir(&place.type_)).or_default();
+                    match self.path(i) {
+                        Some(path) => shard.push_from(place.clone(), path),
+                        None => shard.push(place.clone()),
+                    }
+                }
+                shards.iter().flat_map(|(name, shard)| shard.files(&dir.join(name))).collect()
+            }
+        };
+        write_files(files, atomic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition() {
+        let place = |name: &str, at: Option<(f64, f64)>| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.location = at.map(|(lat, lon)| Coordinate::new(lat, lon));
+            p
+        };
+        let mut collection = GeonCollection::default();
+        collection.push_from(place("Old Market Square", Some((52.9533, -1.1505))), "square.geon");
+        collection.push(place("Council House", Some((52.9536, -1.1503))));
+        collection.push(place("Arboretum", Some((52.9602, -1.1562))));
+        collection.push(place("Somewhere", None));
+
+        let partition = collection.partition(Grid::degrees(0.01));
+        let cells: Vec<String> = partition.shards.keys().map(Cell::to_string).collect();
+        assert_eq!(cells, ["x-116_y5295", "x-116_y5296"]);
+        let centre = &partition.shards[&Cell::Grid(-116, 5295)];
+        assert_eq!(centre.len(), 2);
+        assert_eq!(centre.path(0), Some(Path::new("square.geon")));
+        assert_eq!(partition.unplaced.places[0].place, "Somewhere");
+
+        let tiles = collection.partition(Grid::tiles(14));
+        assert_eq!(tiles.shards.keys().next(), Some(&Cell::Tile((14, 8139, 5340))));
+        assert_eq!(Cell::Tile((14, 8139, 5340)).path(), Path::new("14/8139/5340"));
+
+        collection.places[0].type_ = "public_space".to_string();
+        let dir = std::env::temp_dir().join(format!("geon-sharded-{}", std::process::id()));
+        let written = collection.save_sharded(&dir, ShardBy::Type, true).unwrap();
+        assert_eq!(written[0], dir.join("public_space/square.geon"));
+        assert_eq!(written[1], dir.join("untyped/council-house.geon"));
+        let written = collection.save_sharded(&dir, ShardBy::Tile(14), true).unwrap();
+        assert_eq!(written[0], dir.join("14/8139/5340/square.geon"));
+        assert!(written.last().unwrap().starts_with(dir.join("unplaced")));
+        let (reloaded, errors) = GeonCollection::from_dir(&dir).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reloaded.len(), 8);
+        let leftover = std::fs::read_dir(dir.join("untyped")).unwrap().flatten().any(|e| e.file_name().to_string_lossy().ends_with(".tmp"));
+        assert!(!leftover);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
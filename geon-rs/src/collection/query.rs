@@ -0,0 +1,178 @@
+use crate::models::GeonPlace;
+use crate::vocabulary::SCALES;
+
+/// Position on the five-step scales used by EXPERIENCE qualities. Each
+/// quality has its own words (`quiet`..`very_loud`, `deserted`..`crowded`),
+/// but every scale runs from the same lowest to highest step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    VeryLow,
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
+const LEVELS: [Level; 5] = [Level::VeryLow, Level::Low, Level::Medium, Level::High, Level::VeryHigh];
+
+impl Level {
+    /// Read the level of an EXPERIENCE value from its leading word, so
+    /// `high (weekdays), very_high (weekends)` is [`Level::High`].
+    pub fn from_value(value: &str) -> Option<Self> {
+        let word = value
+            .trim()
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ';')
+            .next()?
+            .to_lowercase()
+            .replace('-', "_");
+        SCALES.iter().find_map(|scale| scale.iter().position(|w| *w == word)).map(|i| LEVELS[i])
+    }
+}
+
+/// A predicate over places, built from the constructors below and combined
+/// with [`and`](Query::and), [`or`](Query::or) and `!`.
+///
+/// Text matches ignore case; `*_contains` queries match a substring of any
+/// entry in the field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    All,
+    TypeIs(String),
+    NameContains(String),
+    PurposeContains(String),
+    CharacterContains(String),
+    AdjacentTo(String),
+    PartOf(String),
+    HasField(String),
+    ExperienceAtLeast(String, Level),
+    ExperienceAtMost(String, Level),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl Query {
+    pub fn type_is(type_: &str) -> Self {
+        Self::TypeIs(type_.to_string())
+    }
+
+    pub fn name_contains(text: &str) -> Self {
+        Self::NameContains(text.to_string())
+    }
+
+    pub fn purpose_contains(text: &str) -> Self {
+        Self::PurposeContains(text.to_string())
+    }
+
+    pub fn character_contains(text: &str) -> Self {
+        Self::CharacterContains(text.to_string())
+    }
+
+    /// An ADJACENCIES entry mentions `name`.
+    pub fn adjacent_to(name: &str) -> Self {
+        Self::AdjacentTo(name.to_string())
+    }
+
+    /// PART_OF mentions `name`.
+    pub fn part_of(name: &str) -> Self {
+        Self::PartOf(name.to_string())
+    }
+
+    /// The place has a non-empty value for a GEON key such as `BOUNDARY`
+    /// or `ECOLOGY` (any case), or for an `extra` field by its own name.
+    pub fn has_field(key: &str) -> Self {
+        Self::HasField(key.to_string())
+    }
+
+    /// EXPERIENCE `key` reads as `level` or higher (see [`Level::from_value`]).
+    pub fn experience_at_least(key: &str, level: Level) -> Self {
+        Self::ExperienceAtLeast(key.to_string(), level)
+    }
+
+    pub fn experience_at_most(key: &str, level: Level) -> Self {
+        Self::ExperienceAtMost(key.to_string(), level)
+    }
+
+    pub fn and(self, other: Query) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Query) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, place: &GeonPlace) -> bool {
+        let experience = |key: &str| place.experience.get(key).and_then(|v| Level::from_value(v));
+        match self {
+            Self::All => true,
+            Self::TypeIs(t) => place.type_.eq_ignore_ascii_case(t),
+            Self::NameContains(text) => contains(&place.place, text),
+            Self::PurposeContains(text) => place.purpose.iter().any(|p| contains(p, text)),
+            Self::CharacterContains(text) => place.character.iter().any(|c| contains(c, text)),
+            Self::AdjacentTo(name) => place.adjacencies.iter().any(|a| contains(a, name)),
+            Self::PartOf(name) => place.part_of.as_deref().is_some_and(|p| contains(p, name)),
+            Self::HasField(key) => has_field(place, key),
+            Self::ExperienceAtLeast(key, level) => experience(key).is_some_and(|l| l >= *level),
+            Self::ExperienceAtMost(key, level) => experience(key).is_some_and(|l| l <= *level),
+            Self::And(a, b) => a.matches(place) && b.matches(place),
+            Self::Or(a, b) => a.matches(place) || b.matches(place),
+            Self::Not(q) => !q.matches(place),
+        }
+    }
+}
+
+impl std::ops::Not for Query {
+    type Output = Query;
+
+    fn not(self) -> Query {
+        Query::Not(Box::new(self))
+    }
+}
+
+// GEON keys understood by `has_field`, in spec order
+pub(crate) const FIELDS: &[&str] = &[
+    "PLACE", "TYPE", "ID", "LOCATION", "BOUNDARY", "PATH", "EXTENT", "ELEVATION", "AREA", "PURPOSE", "EXPERIENCE",
+    "CHARACTER", "ADJACENCIES", "CONNECTIVITY", "CONTAINS", "PART_OF", "VIEWSHEDS", "TEMPORAL", "LIFESPAN",
+    "SOURCE", "CONFIDENCE", "UPDATED", "BUILT_FORM", "ECOLOGY", "INFRASTRUCTURE", "DEMOGRAPHICS", "ECONOMY",
+    "VISUAL", "HISTORY", "VERTICAL_PROFILE",
+];
+
+pub(crate) fn has_field(p: &GeonPlace, key: &str) -> bool {
+    match key.to_uppercase().as_str() {
+        "PLACE" => !p.place.is_empty(),
+        "TYPE" => !p.type_.is_empty(),
+        "ID" => p.id.is_some(),
+        "LOCATION" => p.location.is_some(),
+        "BOUNDARY" => !p.boundary.is_empty(),
+        "PATH" => !p.path.is_empty(),
+        "EXTENT" => p.extent.is_some(),
+        "ELEVATION" => p.elevation.is_some(),
+        "AREA" => p.area.is_some(),
+        "PURPOSE" => !p.purpose.is_empty(),
+        "EXPERIENCE" => !p.experience.is_empty(),
+        "CHARACTER" => !p.character.is_empty(),
+        "ADJACENCIES" => !p.adjacencies.is_empty(),
+        "CONNECTIVITY" => !p.connectivity.is_empty(),
+        "CONTAINS" => !p.contains.is_empty(),
+        "PART_OF" => p.part_of.is_some(),
+        "VIEWSHEDS" => !crate::models::is_empty_json_value(&p.viewsheds),
+        "TEMPORAL" => !p.temporal.is_empty(),
+        "LIFESPAN" => !p.lifespan.is_empty(),
+        "SOURCE" => !p.source.is_empty(),
+        "CONFIDENCE" => !p.confidence.is_empty(),
+        "UPDATED" => p.updated.is_some(),
+        "BUILT_FORM" => !p.built_form.is_empty(),
+        "ECOLOGY" => !p.ecology.is_empty(),
+        "INFRASTRUCTURE" => !p.infrastructure.is_empty(),
+        "DEMOGRAPHICS" => !p.demographics.is_empty(),
+        "ECONOMY" => !p.economy.is_empty(),
+        "VISUAL" => !p.visual.is_empty(),
+        "HISTORY" => !p.history.is_empty(),
+        "VERTICAL_PROFILE" => !p.vertical_profile.is_empty(),
+        _ => p.extra.get(key).is_some_and(|v| !crate::models::is_empty_json_value(v)),
+    }
+}
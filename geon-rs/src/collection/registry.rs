@@ -0,0 +1,182 @@
+use super::GeonCollection;
+use crate::graph::split_note;
+use crate::models::GeonPlace;
+use std::collections::HashMap;
+
+/// Where a place sits in a collection: its index in `places`, then indices
+/// into successive CONTAINS lists.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlaceHandle(pub Vec<usize>);
+
+impl PlaceHandle {
+    pub fn get<'a>(&self, collection: &'a GeonCollection) -> Option<&'a GeonPlace> {
+        let (first, rest) = self.0.split_first()?;
+        rest.iter().try_fold(collection.places.get(*first)?, |p, i| p.contains.get(*i))
+    }
+
+    pub fn get_mut<'a>(&self, collection: &'a mut GeonCollection) -> Option<&'a mut GeonPlace> {
+        let (first, rest) = self.0.split_first()?;
+        rest.iter().try_fold(collection.places.get_mut(*first)?, |p, i| p.contains.get_mut(*i))
+    }
+}
+
+/// What a reference to a place by ID or name comes to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Resolved(PlaceHandle),
+    /// Several places share the name and none has it as an ID.
+    Ambiguous(Vec<PlaceHandle>),
+    Unresolved,
+}
+
+/// A PART_OF or ADJACENCIES entry naming another place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub from: PlaceHandle,
+    /// `PART_OF` or `ADJACENCIES`.
+    pub field: &'static str,
+    /// The name referred to, without any `(note)`.
+    pub name: String,
+    pub target: Resolution,
+}
+
+/// Every ID and PLACE name in a collection, nested places included, for
+/// turning references into places.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Registry {
+    ids: HashMap<String, Vec<PlaceHandle>>,
+    // Keyed by lowercased name
+    names: HashMap<String, Vec<PlaceHandle>>,
+}
+
+impl Registry {
+    pub fn new(collection: &GeonCollection) -> Self {
+        let mut registry = Self::default();
+        for (i, place) in collection.places.iter().enumerate() {
+            registry.add(place, vec![i]);
+        }
+        registry
+    }
+
+    /// Register `place` as the `i`th top-level place of the collection, and
+    /// the places nested in it, as after [`GeonCollection::push`].
+    pub fn insert(&mut self, i: usize, place: &GeonPlace) {
+        self.add(place, vec![i]);
+    }
+
+    fn add(&mut self, place: &GeonPlace, path: Vec<usize>) {
+        if let Some(id) = &place.id {
+            self.ids.entry(id.clone()).or_default().push(PlaceHandle(path.clone()));
+        }
+        if !place.place.is_empty() {
+            self.names.entry(place.place.to_lowercase()).or_default().push(PlaceHandle(path.clone()));
+        }
+        for (j, child) in place.contains.iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(j);
+            self.add(child, child_path);
+        }
+    }
+
+    /// The place with this ID (the first, if the ID is duplicated).
+    pub fn by_id(&self, id: &str) -> Option<&PlaceHandle> {
+        self.ids.get(id)?.first()
+    }
+
+    /// Places with this PLACE name, ignoring case.
+    pub fn by_name(&self, name: &str) -> &[PlaceHandle] {
+        self.names.get(&name.trim().to_lowercase()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Resolve an ID or name. IDs win over names.
+    pub fn resolve(&self, reference: &str) -> Resolution {
+        if let Some(handle) = self.by_id(reference.trim()) {
+            return Resolution::Resolved(handle.clone());
+        }
+        match self.by_name(reference) {
+            [] => Resolution::Unresolved,
+            [one] => Resolution::Resolved(one.clone()),
+            many => Resolution::Ambiguous(many.to_vec()),
+        }
+    }
+
+    /// Every PART_OF and ADJACENCIES reference in the collection, resolved,
+    /// in collection order.
+    pub fn references(&self, collection: &GeonCollection) -> Vec<Reference> {
+        let mut handles: Vec<&PlaceHandle> = self.names.values().chain(self.ids.values()).flatten().collect();
+        handles.sort();
+        handles.dedup();
+
+        let mut refs = Vec::new();
+        for handle in handles {
+            let Some(place) = handle.get(collection) else { continue };
+            let part_of = place.part_of.iter().flat_map(|chain| chain.split(',')).map(|n| ("PART_OF", n.trim()));
+            let adjacent = place.adjacencies.iter().map(|a| ("ADJACENCIES", split_note(a).0));
+            for (field, name) in part_of.chain(adjacent).filter(|(_, n)| !n.is_empty()) {
+                refs.push(Reference { from: handle.clone(), field, name: name.to_string(), target: self.resolve(name) });
+            }
+        }
+        refs
+    }
+
+    /// Names carried by more than one place, with those places, by name.
+    pub fn ambiguous_names(&self) -> Vec<(&str, &[PlaceHandle])> {
+        let mut found: Vec<(&str, &[PlaceHandle])> =
+            self.names.iter().filter(|(_, h)| h.len() > 1).map(|(n, h)| (n.as_str(), h.as_slice())).collect();
+        found.sort();
+        found
+    }
+
+    /// IDs carried by more than one place, by ID.
+    pub fn duplicate_ids(&self) -> Vec<(&str, &[PlaceHandle])> {
+        let mut found: Vec<(&str, &[PlaceHandle])> =
+            self.ids.iter().filter(|(_, h)| h.len() > 1).map(|(n, h)| (n.as_str(), h.as_slice())).collect();
+        found.sort();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_references() {
+        let place = |name: &str, id: Option<&str>| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.id = id.map(str::to_string);
+            p
+        };
+        let mut square = place("Old Market Square", Some("osm:way/1"));
+        square.part_of = Some("City Centre, Nottingham".to_string());
+        square.adjacencies = vec!["High Street (north)".to_string(), "Council House (east)".to_string()];
+        square.contains = vec![place("council house", None)];
+        let collection = GeonCollection::new(vec![
+            square,
+            place("High Street", Some("osm:way/2")),
+            place("High Street", Some("osm:way/3")),
+            place("City Centre", Some("osm:way/2")),
+        ]);
+
+        let registry = collection.registry();
+        assert_eq!(registry.by_id("osm:way/1"), Some(&PlaceHandle(vec![0])));
+        assert_eq!(registry.resolve("osm:way/3"), Resolution::Resolved(PlaceHandle(vec![2])));
+        let council = registry.by_name("Council House")[0].get(&collection).unwrap();
+        assert_eq!(council.place, "council house");
+
+        let refs = registry.references(&collection);
+        let targets: Vec<(&str, &str, &Resolution)> = refs.iter().map(|r| (r.field, r.name.as_str(), &r.target)).collect();
+        assert_eq!(
+            targets,
+            [
+                ("PART_OF", "City Centre", &Resolution::Resolved(PlaceHandle(vec![3]))),
+                ("PART_OF", "Nottingham", &Resolution::Unresolved),
+                ("ADJACENCIES", "High Street", &Resolution::Ambiguous(vec![PlaceHandle(vec![1]), PlaceHandle(vec![2])])),
+                ("ADJACENCIES", "Council House", &Resolution::Resolved(PlaceHandle(vec![0, 0]))),
+            ]
+        );
+        assert_eq!(registry.ambiguous_names().len(), 1);
+        assert_eq!(registry.duplicate_ids()[0].0, "osm:way/2");
+    }
+}
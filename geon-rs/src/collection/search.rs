@@ -0,0 +1,72 @@
+use crate::models::GeonPlace;
+use std::collections::HashMap;
+
+// Field weights: a word in the name says more than one in the character
+const PLACE_WEIGHT: f64 = 3.0;
+const PURPOSE_WEIGHT: f64 = 2.0;
+const CHARACTER_WEIGHT: f64 = 1.5;
+const OTHER_WEIGHT: f64 = 1.0;
+
+/// Lowercase words with a plural `s` dropped, so `Markets` finds `market`.
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(|w| {
+        let w = w.to_lowercase();
+        match w.strip_suffix('s') {
+            Some(stem) if stem.chars().count() > 2 && !stem.ends_with('s') => stem.to_string(),
+            _ => w,
+        }
+    })
+}
+
+/// Inverted index from words in PLACE, PURPOSE, CHARACTER, ADJACENCIES and
+/// EXPERIENCE values to the places using them, with weighted counts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TextIndex {
+    postings: HashMap<String, Vec<(usize, f64)>>,
+    pub(crate) len: usize,
+}
+
+impl TextIndex {
+    pub(crate) fn build(places: &[GeonPlace]) -> Self {
+        let mut index = Self { len: places.len(), ..Default::default() };
+        for (i, p) in places.iter().enumerate() {
+            let mut counts: HashMap<String, f64> = HashMap::new();
+            let mut add = |text: &str, weight: f64| {
+                for token in tokens(text) {
+                    *counts.entry(token).or_default() += weight;
+                }
+            };
+            add(&p.place, PLACE_WEIGHT);
+            p.purpose.iter().for_each(|v| add(v, PURPOSE_WEIGHT));
+            p.character.iter().for_each(|v| add(v, CHARACTER_WEIGHT));
+            p.adjacencies.iter().for_each(|v| add(v, OTHER_WEIGHT));
+            p.experience.values().for_each(|v| add(v, OTHER_WEIGHT));
+            for (token, count) in counts {
+                index.postings.entry(token).or_default().push((i, count));
+            }
+        }
+        index
+    }
+
+    /// Indices of places matching any word of `query` with their scores,
+    /// best first. Each word scores its weighted count damped by how common
+    /// it is across the collection (tf-idf), so rarer words rank higher.
+    pub(crate) fn search(&self, query: &str) -> Vec<(usize, f64)> {
+        let mut words: Vec<String> = tokens(query).collect();
+        words.sort();
+        words.dedup();
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for word in &words {
+            let Some(postings) = self.postings.get(word) else { continue };
+            let idf = (1.0 + self.len as f64 / postings.len() as f64).ln();
+            for (i, count) in postings {
+                // Sub-linear in repetition, so one field can't dominate
+                *scores.entry(*i).or_default() += (1.0 + count.ln_1p()) * idf;
+            }
+        }
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+}
@@ -0,0 +1,200 @@
+use super::index::place_bounds;
+use super::query::{has_field, FIELDS};
+use super::GeonCollection;
+use crate::models::{Extent, GeonPlace};
+use crate::quantity::parse_area;
+use crate::units::Area;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// AREA totals for one TYPE, over the places whose AREA could be read.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AreaStats {
+    pub count: usize,
+    pub total_sqm: f64,
+    pub mean_sqm: f64,
+}
+
+/// How much of the collection says anything about time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TemporalCoverage {
+    /// Earliest and latest UPDATED values (ISO 8601 compares as text).
+    pub updated_earliest: Option<String>,
+    pub updated_latest: Option<String>,
+    pub with_temporal: usize,
+    pub with_lifespan: usize,
+    /// How many places have each TEMPORAL key.
+    pub temporal_keys: BTreeMap<String, usize>,
+}
+
+/// Summary of a collection, for reports and dashboards. Serialises to JSON
+/// with `serde_json::to_string(&stats)`; `Display` gives a plain-text
+/// report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CollectionStats {
+    /// Every place, nested CONTAINS included.
+    pub places: usize,
+    pub by_type: BTreeMap<String, usize>,
+    pub area_by_type: BTreeMap<String, AreaStats>,
+    /// Percentage of places with each GEON key filled in.
+    pub field_coverage: BTreeMap<String, f64>,
+    /// For each CONFIDENCE key, how many places rate it each way.
+    pub confidence: BTreeMap<String, BTreeMap<String, usize>>,
+    pub temporal: TemporalCoverage,
+    /// Box around every place's geometry; `None` if none has any.
+    pub bounds: Option<Extent>,
+}
+
+impl fmt::Display for CollectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} places", self.places)?;
+        if let Some(b) = &self.bounds {
+            writeln!(f, "bounds: {}, {} to {}, {} (south, west to north, east)", b.south, b.west, b.north, b.east)?;
+        }
+        let t = &self.temporal;
+        if let (Some(earliest), Some(latest)) = (&t.updated_earliest, &t.updated_latest) {
+            writeln!(f, "updated: {} to {}", earliest, latest)?;
+        }
+
+        writeln!(f, "\ntypes:")?;
+        for (type_, n) in &self.by_type {
+            write!(f, "  {:<18} {:>7}", type_, n)?;
+            match self.area_by_type.get(type_) {
+                Some(area) => writeln!(f, "  mean area {} over {}", Area(area.mean_sqm), area.count)?,
+                None => writeln!(f)?,
+            }
+        }
+        writeln!(f, "\nfield coverage:")?;
+        for (field, percent) in FIELDS.iter().filter_map(|k| Some((k, self.field_coverage.get(*k)?))) {
+            writeln!(f, "  {:<18} {:>6.1}%", field, percent)?;
+        }
+        if !self.confidence.is_empty() {
+            writeln!(f, "\nconfidence:")?;
+            for (key, levels) in &self.confidence {
+                let levels: Vec<String> = levels.iter().map(|(level, n)| format!("{} {}", level, n)).collect();
+                writeln!(f, "  {:<18} {}", key, levels.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn visit<'a>(places: &'a [GeonPlace], out: &mut Vec<&'a GeonPlace>) {
+    for p in places {
+        out.push(p);
+        visit(&p.contains, out);
+    }
+}
+
+impl GeonCollection {
+    /// Counts, AREA totals and field, confidence and temporal coverage over
+    /// every place, nested ones included. Places without a TYPE count as
+    /// `unknown`.
+    pub fn stats(&self) -> CollectionStats {
+        let mut all = Vec::new();
+        visit(&self.places, &mut all);
+        let mut stats = CollectionStats { places: all.len(), ..Default::default() };
+
+        let mut field_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut bounds: Option<(f64, f64, f64, f64)> = None;
+        for p in &all {
+            if let Some((w, s, e, n)) = place_bounds(p) {
+                bounds = Some(match bounds {
+                    Some((bw, bs, be, bn)) => (bw.min(w), bs.min(s), be.max(e), bn.max(n)),
+                    None => (w, s, e, n),
+                });
+            }
+            let type_ = if p.type_.is_empty() { "unknown" } else { p.type_.as_str() };
+            *stats.by_type.entry(type_.to_string()).or_default() += 1;
+            if let Some(sqm) = p.area.as_deref().and_then(parse_area) {
+                let area = stats.area_by_type.entry(type_.to_string()).or_default();
+                area.count += 1;
+                area.total_sqm += sqm;
+            }
+            for field in FIELDS.iter().filter(|f| has_field(p, f)) {
+                *field_counts.entry(field).or_default() += 1;
+            }
+            for (key, value) in &p.confidence {
+                *stats.confidence.entry(key.clone()).or_default().entry(value.trim().to_lowercase()).or_default() += 1;
+            }
+
+            let t = &mut stats.temporal;
+            if let Some(updated) = &p.updated {
+                if t.updated_earliest.as_ref().is_none_or(|e| updated < e) {
+                    t.updated_earliest = Some(updated.clone());
+                }
+                if t.updated_latest.as_ref().is_none_or(|l| updated > l) {
+                    t.updated_latest = Some(updated.clone());
+                }
+            }
+            t.with_temporal += usize::from(!p.temporal.is_empty());
+            t.with_lifespan += usize::from(!p.lifespan.is_empty());
+            for key in p.temporal.keys() {
+                *t.temporal_keys.entry(key.clone()).or_default() += 1;
+            }
+        }
+
+        stats.bounds = bounds.map(|(west, south, east, north)| Extent { north, south, east, west });
+        for area in stats.area_by_type.values_mut() {
+            area.mean_sqm = area.total_sqm / area.count as f64;
+        }
+        if !all.is_empty() {
+            for field in FIELDS {
+                let n = field_counts.get(field).copied().unwrap_or_default();
+                stats.field_coverage.insert(field.to_string(), (n as f64 * 1000.0 / all.len() as f64).round() / 10.0);
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let place = |name: &str, type_: &str, area: Option<&str>| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.type_ = type_.to_string();
+            p.area = area.map(str::to_string);
+            p
+        };
+        let mut square = place("Old Market Square", "public_space", Some("1.2 ha"));
+        square.confidence.insert("location".to_string(), "High".to_string());
+        square.updated = Some("2024-05-01".to_string());
+        square.temporal.insert("weekday_footfall".to_string(), "2000/h".to_string());
+        square.contains = vec![place("Fountain", "landmark", None)];
+        let mut park = place("Arboretum", "public_space", Some("8000 sqm"));
+        park.confidence.insert("location".to_string(), "medium".to_string());
+        park.updated = Some("2023-11-20".to_string());
+
+        square.location = Some(crate::models::Coordinate::new(52.953, -1.15));
+        square.contains[0].location = Some(crate::models::Coordinate::new(52.9531, -1.1499));
+        park.location = Some(crate::models::Coordinate::new(52.963, -1.16));
+
+        let stats = GeonCollection::new(vec![square, park, place("Castle", "", Some("big"))]).stats();
+        assert_eq!(stats.places, 4);
+        assert_eq!(stats.by_type["public_space"], 2);
+        assert_eq!(stats.by_type["unknown"], 1);
+        assert_eq!(stats.area_by_type["public_space"], AreaStats { count: 2, total_sqm: 20_000.0, mean_sqm: 10_000.0 });
+        assert!(!stats.area_by_type.contains_key("unknown"));
+        assert_eq!(stats.field_coverage["AREA"], 75.0);
+        assert_eq!(stats.field_coverage["PLACE"], 100.0);
+        assert_eq!(stats.confidence["location"]["high"], 1);
+        assert_eq!(stats.temporal.updated_earliest.as_deref(), Some("2023-11-20"));
+        assert_eq!(stats.temporal.temporal_keys["weekday_footfall"], 1);
+
+        assert_eq!(stats.bounds, Some(Extent { north: 52.963, south: 52.953, east: -1.1499, west: -1.16 }));
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["area_by_type"]["public_space"]["mean_sqm"], 10_000.0);
+        let report = stats.to_string();
+        assert!(report.starts_with("4 places\nbounds: 52.953, -1.16 to 52.963, -1.1499"), "{}", report);
+        assert!(report.contains("  public_space             2  mean area 1 ha over 2\n"), "{}", report);
+        assert!(report.contains("  AREA                 75.0%\n"));
+        assert!(report.contains("  location           high 1, medium 1\n"));
+    }
+}
@@ -0,0 +1,135 @@
+use super::{find_geon_files, GeonCollection};
+use crate::models::GeonPlace;
+use crate::parser::{parse, GeonError};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Places read one at a time from a multi-document GEON source, split as
+/// [`documents`](crate::parser::documents) splits text. Only the document
+/// being read is held in memory.
+///
+/// Reading stops after the first error.
+#[derive(Debug)]
+pub struct PlaceStream<R> {
+    reader: R,
+    // The `PLACE:` line that ended the previous document
+    pending: String,
+    failed: bool,
+}
+
+impl<R: BufRead> PlaceStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, pending: String::new(), failed: false }
+    }
+}
+
+impl<R: BufRead> Iterator for PlaceStream<R> {
+    type Item = Result<GeonPlace, GeonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mut doc = std::mem::take(&mut self.pending);
+        let mut seen_place = !doc.is_empty();
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e.into()));
+                }
+            }
+            if line.starts_with("PLACE:") {
+                if seen_place {
+                    self.pending = line;
+                    break;
+                }
+                seen_place = true;
+            }
+            doc.push_str(&line);
+        }
+        if doc.trim().is_empty() {
+            return None;
+        }
+        if !seen_place {
+            self.failed = true;
+            return Some(Err(GeonError::InvalidStructure("missing PLACE".to_string())));
+        }
+        Some(Ok(parse(&doc)))
+    }
+}
+
+impl GeonCollection {
+    /// Stream the places in a multi-document `.geon` file without loading
+    /// the whole file.
+    pub fn stream_file(path: impl AsRef<Path>) -> Result<PlaceStream<BufReader<File>>, GeonError> {
+        Ok(PlaceStream::new(BufReader::new(File::open(path)?)))
+    }
+
+    /// Call `f` with each place in a `.geon` file, or in every `*.geon` file
+    /// under a directory in path order, until it breaks. Places are
+    /// streamed, so memory use doesn't grow with the corpus. Returns how
+    /// many places were visited.
+    pub fn for_each_place<F>(path: impl AsRef<Path>, mut f: F) -> Result<usize, GeonError>
+    where
+        F: FnMut(GeonPlace) -> ControlFlow<()>,
+    {
+        let path = path.as_ref();
+        let mut files = Vec::new();
+        if path.is_dir() {
+            find_geon_files(path, &mut files)?;
+            files.sort();
+        } else {
+            files.push(path.to_path_buf());
+        }
+
+        let mut visited = 0;
+        for file in files {
+            for place in Self::stream_file(file)? {
+                visited += 1;
+                if f(place?).is_break() {
+                    return Ok(visited);
+                }
+            }
+        }
+        Ok(visited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_places() {
+        let text = "# Nottingham\nPLACE: Old Market Square\nCONTAINS:\n  - PLACE: Fountain\nPLACE: Council House\n\nPLACE: Arboretum\n";
+        let names: Vec<String> = PlaceStream::new(text.as_bytes()).map(|p| p.unwrap().place).collect();
+        assert_eq!(names, ["Old Market Square", "Council House", "Arboretum"]);
+        assert!(PlaceStream::new("# nothing here\n".as_bytes()).next().unwrap().is_err());
+
+        let dir = std::env::temp_dir().join(format!("geon-stream-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join("a.geon"), text).unwrap();
+        std::fs::write(dir.join("b/c.geon"), "PLACE: Castle Green\n").unwrap();
+
+        let mut seen = Vec::new();
+        let visited = GeonCollection::for_each_place(&dir, |place| {
+            seen.push(place.place);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(visited, 4);
+        assert_eq!(seen[3], "Castle Green");
+        let visited = GeonCollection::for_each_place(dir.join("a.geon"), |place| {
+            if place.place == "Council House" { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        })
+        .unwrap();
+        assert_eq!(visited, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use super::{find_geon_files, read_place, GeonCollection};
+use crate::parser::GeonError;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often [`watch`] looks for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change to a watched directory. Paths are relative to it, as in
+/// [`GeonCollection::path`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    Added(PathBuf),
+    Changed(PathBuf),
+    Removed(PathBuf),
+    /// A new or changed file that could not be read, e.g. one still being
+    /// written. Any earlier version stays in the collection.
+    Failed(PathBuf, String),
+}
+
+/// A collection kept in step with a directory of `.geon` files by polling
+/// file modification times and sizes. Only files that change are parsed
+/// again, and the spatial index is updated in place.
+#[derive(Debug)]
+pub struct Watcher {
+    dir: PathBuf,
+    collection: GeonCollection,
+    seen: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl Watcher {
+    /// Watch `dir`, starting from an empty collection: the first
+    /// [`poll`](Self::poll) reports every file as added.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf(), collection: GeonCollection::default(), seen: HashMap::new() }
+    }
+
+    pub fn collection(&self) -> &GeonCollection {
+        &self.collection
+    }
+
+    pub fn into_collection(self) -> GeonCollection {
+        self.collection
+    }
+
+    fn position(&self, relative: &Path) -> Option<usize> {
+        self.collection.origins.iter().position(|o| o.as_deref() == Some(relative))
+    }
+
+    /// Bring the collection up to date, returning what changed: removals
+    /// first, then additions and changes in path order.
+    pub fn poll(&mut self) -> Result<Vec<WatchEvent>, GeonError> {
+        let mut files = Vec::new();
+        find_geon_files(&self.dir, &mut files)?;
+        files.sort();
+
+        let mut events = Vec::new();
+        let mut gone: Vec<PathBuf> = self.seen.keys().filter(|p| files.binary_search(p).is_err()).cloned().collect();
+        gone.sort();
+        for file in gone {
+            self.seen.remove(&file);
+            let relative = file.strip_prefix(&self.dir).unwrap_or(&file).to_path_buf();
+            if let Some(i) = self.position(&relative) {
+                self.collection.remove(i);
+                events.push(WatchEvent::Removed(relative));
+            }
+        }
+
+        for file in files {
+            // Gone again since the directory was read
+            let Ok(meta) = std::fs::metadata(&file) else { continue };
+            let stamp = (meta.modified()?, meta.len());
+            if self.seen.insert(file.clone(), stamp) == Some(stamp) {
+                continue;
+            }
+            let relative = file.strip_prefix(&self.dir).unwrap_or(&file).to_path_buf();
+            match (read_place(&file), self.position(&relative)) {
+                (Ok(place), Some(i)) => {
+                    self.collection.replace(i, place);
+                    events.push(WatchEvent::Changed(relative));
+                }
+                (Ok(place), None) => {
+                    self.collection.push_from(place, relative.clone());
+                    events.push(WatchEvent::Added(relative));
+                }
+                (Err(e), _) => events.push(WatchEvent::Failed(relative, e.to_string())),
+            }
+        }
+        Ok(events)
+    }
+}
+
+/// Load `dir` and keep it loaded, calling `callback` with the collection
+/// and what changed: once with everything on start, then after each
+/// [`POLL_INTERVAL`] in which something did. Runs until the callback
+/// returns [`ControlFlow::Break`], then returns the collection.
+pub fn watch(
+    dir: impl AsRef<Path>,
+    mut callback: impl FnMut(&GeonCollection, &[WatchEvent]) -> ControlFlow<()>,
+) -> Result<GeonCollection, GeonError> {
+    let mut watcher = Watcher::new(dir);
+    loop {
+        let events = watcher.poll()?;
+        if !events.is_empty() && callback(watcher.collection(), &events).is_break() {
+            return Ok(watcher.into_collection());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Coordinate, Extent};
+
+    #[test]
+    fn test_watcher_poll() {
+        let dir = std::env::temp_dir().join(format!("geon-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hall.geon"), "PLACE: Council House\nLOCATION: 52.9531, -1.1497\n").unwrap();
+        std::fs::write(dir.join("square.geon"), "PLACE: Old Market Square\nLOCATION: 52.9529, -1.1505\n").unwrap();
+
+        let mut watcher = Watcher::new(&dir);
+        assert_eq!(watcher.poll().unwrap().len(), 2);
+        assert!(watcher.poll().unwrap().is_empty());
+        let near = Extent { north: 52.96, south: 52.95, east: -1.14, west: -1.16 };
+        assert_eq!(watcher.collection().query_bbox(&near).len(), 2);
+
+        // The hall moves out of town, the square goes and a half-written file appears
+        std::fs::write(dir.join("hall.geon"), "PLACE: Council House\nLOCATION: 51.50740, -0.1278\n").unwrap();
+        std::fs::remove_file(dir.join("square.geon")).unwrap();
+        std::fs::write(dir.join("new.geon"), "TYPE: building\n").unwrap();
+        let events = watcher.poll().unwrap();
+        assert_eq!(events[0], WatchEvent::Removed(PathBuf::from("square.geon")));
+        assert_eq!(events[1], WatchEvent::Changed(PathBuf::from("hall.geon")));
+        assert!(matches!(&events[2], WatchEvent::Failed(path, _) if path == Path::new("new.geon")));
+
+        let collection = watcher.collection();
+        assert_eq!(collection.len(), 1);
+        assert!(collection.query_bbox(&near).is_empty());
+        assert_eq!(collection.nearest(&Coordinate::new(51.5, -0.13), 1)[0].0.place, "Council House");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,135 +1,504 @@
+use crate::collection::{GeonCollection, Registry, Resolution};
+use crate::geometry::{centroid, haversine_m, ring_area};
 use crate::models::{GeonPlace, Coordinate};
-use serde_json::{Value, Map};
-use std::collections::HashMap;
-
-// Type mapping from common OSM/GeoJSON keys to GEON types
-fn get_type_mapping() -> HashMap<&'static str, &'static str> {
-    let mut m = HashMap::new();
-    m.insert("park", "public_space");
-    m.insert("garden", "public_space");
-    m.insert("playground", "public_space");
-    m.insert("plaza", "public_space");
-    m.insert("square", "public_space");
-    m.insert("marketplace", "public_space");
-    // ... extensive mapping for simplicity doing subset or full? 
-    // Let's do a decent subset matching the python file
-    m.insert("yes", "building");
-    m.insert("house", "building");
-    m.insert("apartments", "building");
-    m.insert("commercial", "building");
-    m.insert("retail", "building");
-    m.insert("school", "building");
-    m.insert("hospital", "building");
-    m.insert("railway_station", "transport_hub");
-    m.insert("station", "transport_hub");
-    m
-}
-
-fn infer_type(props: &Map<String, Value>) -> String {
-    if let Some(Val) = props.get("geon_type") {
-        if let Some(s) = Val.as_str() {
-            return s.to_string();
+use crate::parser::GeonError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value, Map};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+pub mod cityjson;
+pub mod csv;
+pub mod geonames;
+pub mod google_places;
+mod gpx;
+mod jsonld;
+pub mod gtfs;
+#[cfg(feature = "inspire")]
+pub mod inspire;
+mod kml;
+pub mod osm;
+mod osm_xml;
+pub mod os;
+pub mod overture;
+pub mod poi;
+pub mod shapefile;
+pub mod whosonfirst;
+mod xml;
+
+pub use gpx::to_gpx;
+pub use jsonld::to_jsonld;
+pub use kml::to_kml;
+pub use osm_xml::from_osm_xml;
+
+// Type mapping from common OSM/GeoJSON tag values to GEON types
+const TYPE_TABLE: &[(&str, &[&str])] = &[
+    ("public_space", &[
+        "park", "garden", "playground", "plaza", "square", "common", "pitch", "marketplace",
+        "recreation_ground", "village_green", "sports_centre", "stadium", "dog_park", "picnic_site",
+    ]),
+    ("street", &[
+        "road", "residential", "primary", "secondary", "tertiary", "unclassified", "service",
+        "living_street", "footway", "cycleway", "path", "pedestrian", "steps", "motorway", "trunk",
+    ]),
+    ("transport_hub", &[
+        "railway_station", "station", "bus_station", "airport", "aerodrome", "halt", "tram_stop",
+        "subway_entrance", "ferry_terminal", "platform", "stop_position",
+    ]),
+    ("building", &[
+        "yes", "house", "apartments", "terrace", "detached", "commercial", "retail", "industrial",
+        "warehouse", "office", "church", "cathedral", "mosque", "temple", "synagogue", "school",
+        "college", "hospital", "university", "library", "theatre", "cinema", "restaurant", "cafe",
+        "pub", "bar", "hotel", "supermarket",
+    ]),
+    ("landmark", &[
+        "monument", "memorial", "statue", "tower", "castle", "ruins", "archaeological_site",
+        "attraction", "viewpoint", "museum",
+    ]),
+    ("threshold", &["bridge", "gate", "city_gate", "tunnel"]),
+    ("infrastructure", &[
+        "parking", "fuel", "substation", "water_tower", "wastewater_plant", "railway", "rail",
+        "canal", "dam", "power",
+    ]),
+    ("natural_feature", &[
+        "river", "stream", "lake", "water", "wood", "forest", "peak", "cliff", "beach", "wetland",
+        "heath", "grassland", "scrub", "meadow", "nature_reserve",
+    ]),
+    ("district", &[
+        "suburb", "neighbourhood", "quarter", "borough", "city", "town", "village", "hamlet",
+    ]),
+];
+
+// Tag keys checked in priority order when inferring a type
+const TYPE_KEYS: &[&str] = &[
+    "type", "building", "highway", "railway", "public_transport", "aeroway", "leisure", "amenity",
+    "tourism", "historic", "man_made", "natural", "waterway", "landuse", "place", "boundary",
+];
+
+// Type implied by a key alone when none of its values are in the table
+const KEY_FALLBACK: &[(&str, &str)] = &[
+    ("highway", "street"),
+    ("building", "building"),
+    ("leisure", "public_space"),
+    ("amenity", "building"),
+    ("shop", "building"),
+    ("historic", "landmark"),
+    ("natural", "natural_feature"),
+    ("waterway", "natural_feature"),
+    ("place", "district"),
+];
+
+// Property keys tried in order for a place's name
+const NAME_KEYS: &[&str] = &["name", "name:en", "official_name", "title", "label"];
+
+/// Tables used to infer TYPE and PLACE from GeoJSON properties or OSM tags.
+///
+/// The default reproduces the built-in inference. A custom mapping can be
+/// loaded from JSON (or TOML, with the `toml` feature) and passed to
+/// [`from_geojson_with`]; fields present replace the defaults, missing ones
+/// keep them:
+///
+/// ```json
+/// {"types": {"public_space": ["park", "allotments"]}, "type_keys": ["landuse", "leisure"]}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypeMapping {
+    /// GEON type -> property values that imply it.
+    pub types: BTreeMap<String, Vec<String>>,
+    /// Property keys whose values are looked up in `types`, highest priority first.
+    pub type_keys: Vec<String>,
+    /// `(key, type)` pairs applied, in order, when a key is present but none
+    /// of the values matched.
+    pub key_fallback: Vec<(String, String)>,
+    /// Property keys tried in order for the name.
+    pub name_keys: Vec<String>,
+    /// Type of places nothing matched.
+    pub default_type: String,
+}
+
+impl Default for TypeMapping {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            types: TYPE_TABLE.iter().map(|(t, values)| (t.to_string(), strings(values))).collect(),
+            type_keys: strings(TYPE_KEYS),
+            key_fallback: KEY_FALLBACK.iter().map(|(k, t)| (k.to_string(), t.to_string())).collect(),
+            name_keys: strings(NAME_KEYS),
+            default_type: "hybrid".to_string(),
         }
     }
-    
-    let mapping = get_type_mapping();
-    let keys = ["type", "building", "highway", "railway", "leisure", "amenity", "natural", "landuse"];
-    
-    for k in keys {
-        if let Some(val) = props.get(k) {
-            if let Some(s) = val.as_str() {
-                 if let Some(mapped) = mapping.get(s) {
-                     return mapped.to_string();
-                 }
+}
+
+impl TypeMapping {
+    /// Read a mapping from JSON.
+    pub fn from_json(text: &str) -> Result<Self, GeonError> {
+        serde_json::from_str(text).map_err(|e| GeonError::InvalidStructure(format!("type mapping: {}", e)))
+    }
+
+    /// Read a mapping from TOML, e.g. `type_keys = ["landuse"]` and a
+    /// `[types]` table of arrays.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, GeonError> {
+        let value = crate::io::toml::parse_value(text)?;
+        serde_json::from_value(value).map_err(|e| GeonError::InvalidStructure(format!("type mapping: {}", e)))
+    }
+
+    /// GEON type for a property map; an explicit `geon_type` always wins.
+    pub fn infer_type(&self, props: &Map<String, Value>) -> String {
+        if let Some(s) = props.get("geon_type").and_then(|v| v.as_str()) {
+            return s.to_string();
+        }
+        for key in &self.type_keys {
+            if let Some(value) = props.get(key).and_then(|v| v.as_str()) {
+                if let Some((type_, _)) = self.types.iter().find(|(_, values)| values.iter().any(|v| v == value)) {
+                    return type_.clone();
+                }
             }
         }
+        self.key_fallback
+            .iter()
+            .find(|(key, _)| props.contains_key(key))
+            .map(|(_, type_)| type_.clone())
+            .unwrap_or_else(|| self.default_type.clone())
     }
-    
-    "hybrid".to_string()
+
+    /// First non-empty name property, or `Unnamed`.
+    pub fn infer_name(&self, props: &Map<String, Value>) -> String {
+        self.name_keys
+            .iter()
+            .find_map(|k| props.get(k).and_then(|v| v.as_str()).filter(|s| !s.is_empty()))
+            .unwrap_or("Unnamed")
+            .to_string()
+    }
+}
+
+static DEFAULT_MAPPING: LazyLock<TypeMapping> = LazyLock::new(TypeMapping::default);
+
+pub(crate) fn infer_type(props: &Map<String, Value>) -> String {
+    DEFAULT_MAPPING.infer_type(props)
+}
+
+// Tags whose values describe what a place is for
+const PURPOSE_KEYS: &[&str] = &["amenity", "leisure", "shop", "tourism", "sport", "craft", "office"];
+
+// Tags copied verbatim into `extra`
+const EXTRA_KEYS: &[&str] = &[
+    "opening_hours", "website", "phone", "cuisine", "operator", "wheelchair", "access",
+    "heritage", "wikidata", "wikipedia",
+];
+
+fn extract_purposes(props: &Map<String, Value>) -> Vec<String> {
+    match props.get("purpose") {
+        Some(Value::Array(list)) => {
+            return list.iter().filter_map(|v| v.as_str().map(str::to_string)).collect();
+        }
+        Some(Value::String(s)) => return vec![s.clone()],
+        _ => {}
+    }
+    let mut purposes = Vec::new();
+    for key in PURPOSE_KEYS {
+        if let Some(val) = props.get(*key).and_then(|v| v.as_str()) {
+            purposes.push(format!("{}: {}", key, val));
+        }
+    }
+    purposes
+}
+
+fn extract_extra(props: &Map<String, Value>) -> BTreeMap<String, Value> {
+    EXTRA_KEYS
+        .iter()
+        .filter_map(|k| props.get(*k).map(|v| (k.to_string(), v.clone())))
+        .collect()
 }
 
 fn infer_name(props: &Map<String, Value>) -> String {
-    let keys = ["name", "name:en", "official_name", "title", "label"];
-    for k in keys {
-        if let Some(val) = props.get(k) {
-            if let Some(s) = val.as_str() {
-                if !s.is_empty() {
-                    return s.to_string();
-                }
-            }
+    DEFAULT_MAPPING.infer_name(props)
+}
+
+pub(crate) fn positions(v: &Value) -> Vec<Coordinate> {
+    v.as_array()
+        .map(|pts| {
+            pts.iter()
+                .filter_map(|pt| {
+                    let pair = pt.as_array()?;
+                    Some(Coordinate::new(pair.get(1)?.as_f64()?, pair.first()?.as_f64()?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// A GeoJSON ring without the closing position GEON leaves implicit
+fn open_ring(mut ring: Vec<Coordinate>) -> Vec<Coordinate> {
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+// Exterior rings of every polygon in a geometry, including collections,
+// without their closing positions
+fn exterior_rings(geom: &Map<String, Value>) -> Vec<Vec<Coordinate>> {
+    let coords = geom.get("coordinates").and_then(|v| v.as_array());
+    match geom.get("type").and_then(|v| v.as_str()) {
+        Some("Polygon") => coords.and_then(|rings| rings.first()).map(|r| open_ring(positions(r))).into_iter().collect(),
+        Some("MultiPolygon") => coords
+            .map(|polys| polys.iter().filter_map(|p| p.get(0)).map(|r| open_ring(positions(r))).collect())
+            .unwrap_or_default(),
+        Some("GeometryCollection") => member_geometries(geom).flat_map(exterior_rings).collect(),
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|ring| !ring.is_empty())
+    .collect()
+}
+
+fn member_geometries(geom: &Map<String, Value>) -> impl Iterator<Item = &Map<String, Value>> {
+    geom.get("geometries").and_then(|v| v.as_array()).into_iter().flatten().filter_map(|g| g.as_object())
+}
+
+fn line_length(line: &[Coordinate]) -> f64 {
+    line.windows(2).map(|w| haversine_m(&w[0], &w[1])).sum()
+}
+
+// Point halfway along a line
+fn line_midpoint(line: &[Coordinate]) -> Option<Coordinate> {
+    let mut remaining = line_length(line) / 2.0;
+    for w in line.windows(2) {
+        let step = haversine_m(&w[0], &w[1]);
+        if step > 0.0 && remaining <= step {
+            let t = remaining / step;
+            return Some(Coordinate::new(w[0].lat + (w[1].lat - w[0].lat) * t, w[0].lon + (w[1].lon - w[0].lon) * t));
         }
+        remaining -= step;
+    }
+    line.first().cloned()
+}
+
+// Lines of a LineString or MultiLineString, including those in collections
+fn lines(geom: &Map<String, Value>) -> Vec<Vec<Coordinate>> {
+    let coords = geom.get("coordinates");
+    match geom.get("type").and_then(|v| v.as_str()) {
+        Some("LineString") => coords.map(positions).into_iter().collect(),
+        Some("MultiLineString") => coords
+            .and_then(|v| v.as_array())
+            .map(|ls| ls.iter().map(positions).collect())
+            .unwrap_or_default(),
+        Some("GeometryCollection") => member_geometries(geom).flat_map(lines).collect(),
+        _ => vec![],
     }
-    "Unnamed".to_string()
 }
 
+/// Representative point of any GeoJSON geometry: the point itself, the mean
+/// of a MultiPoint, the midpoint along the longest line, or the area-weighted
+/// centroid of the largest polygon's exterior ring. GeometryCollections prefer polygons,
+/// then their first member with a location.
 fn extract_centroid(geom: &Map<String, Value>) -> Option<Coordinate> {
-    let type_ = geom.get("type")?.as_str()?;
-    let coords = geom.get("coordinates")?;
-    
-    if type_ == "Point" {
-        let arr = coords.as_array()?;
-        if arr.len() >= 2 {
-            return Some(Coordinate::new(arr[1].as_f64()?, arr[0].as_f64()?));
-        }
-    } else if type_ == "Polygon" {
-        // Simple average of first ring
-        let rings = coords.as_array()?;
-        if let Some(first_ring) = rings.get(0)?.as_array() {
-            if first_ring.is_empty() { return None; }
-            let mut sum_lat = 0.0;
-            let mut sum_lon = 0.0;
-            let count = first_ring.len() as f64;
-            
-            for pt in first_ring {
-                let pair = pt.as_array()?;
-                if pair.len() >= 2 {
-                    sum_lon += pair[0].as_f64()?;
-                    sum_lat += pair[1].as_f64()?;
-                }
+    let coords = geom.get("coordinates");
+    match geom.get("type")?.as_str()? {
+        "Point" => {
+            let pair = coords?.as_array()?;
+            Some(Coordinate::new(pair.get(1)?.as_f64()?, pair.first()?.as_f64()?))
+        }
+        "MultiPoint" => osm::average(&positions(coords?)),
+        "LineString" | "MultiLineString" => {
+            let lines = lines(geom);
+            let longest = lines.iter().max_by(|a, b| line_length(a).total_cmp(&line_length(b)))?;
+            line_midpoint(longest)
+        }
+        "Polygon" | "MultiPolygon" => centroid(&extract_boundary(geom)),
+        "GeometryCollection" => {
+            let boundary = extract_boundary(geom);
+            if boundary.is_empty() {
+                member_geometries(geom).find_map(extract_centroid)
+            } else {
+                centroid(&boundary)
             }
-            return Some(Coordinate::new(sum_lat / count, sum_lon / count));
         }
+        _ => None,
+    }
+}
+
+/// Exterior ring of a Polygon, or of the largest polygon in a MultiPolygon
+/// or GeometryCollection.
+fn extract_boundary(geom: &Map<String, Value>) -> Vec<Coordinate> {
+    exterior_rings(geom)
+        .into_iter()
+        .max_by(|a, b| ring_area(a).total_cmp(&ring_area(b)))
+        .unwrap_or_default()
+}
+
+fn is_linear(geom: &Map<String, Value>) -> bool {
+    match geom.get("type").and_then(|v| v.as_str()) {
+        Some("LineString" | "MultiLineString") => true,
+        Some("GeometryCollection") => {
+            let mut members = member_geometries(geom).peekable();
+            members.peek().is_some() && members.all(is_linear)
+        }
+        _ => false,
     }
-    // Implement others if needed for examples
-    None
 }
 
 fn feature_to_geon(feature: &Map<String, Value>) -> GeonPlace {
+    feature_to_geon_with(feature, &DEFAULT_MAPPING)
+}
+
+fn feature_to_geon_with(feature: &Map<String, Value>, mapping: &TypeMapping) -> GeonPlace {
     let empty_map = Map::new();
     let props = feature.get("properties").and_then(|v| v.as_object()).unwrap_or(&empty_map);
     let geom = feature.get("geometry").and_then(|v| v.as_object()).unwrap_or(&empty_map);
     
     let mut p = GeonPlace::default();
-    p.place = infer_name(props);
-    p.type_ = infer_type(props);
+    p.place = mapping.infer_name(props);
+    p.type_ = mapping.infer_type(props);
+    // Untyped lines are most often streets and paths
+    if p.type_ == mapping.default_type && !props.contains_key("geon_type") && is_linear(geom) {
+        p.type_ = "street".to_string();
+    }
     p.location = extract_centroid(geom);
     
-    // Boundary from Polygon
-    if let Some(type_) = geom.get("type").and_then(|v| v.as_str()) {
-        if type_ == "Polygon" {
-            if let Some(coords) = geom.get("coordinates").and_then(|v| v.as_array()) {
-                if let Some(ring) = coords.get(0).and_then(|v| v.as_array()) {
-                    for pt in ring {
-                        if let Some(pair) = pt.as_array() {
-                            if pair.len() >= 2 {
-                                if let (Some(lon), Some(lat)) = (pair[0].as_f64(), pair[1].as_f64()) {
-                                    p.boundary.push(Coordinate::new(lat, lon));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    p.boundary = extract_boundary(geom);
+    p.path = lines(geom).into_iter().max_by(|a, b| line_length(a).total_cmp(&line_length(b))).unwrap_or_default();
+    p.purpose = extract_purposes(props);
+    p.id = match feature.get("id") {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    capture_properties(&mut p, props, mapping);
+    p
+}
+
+// Properties written by `place_properties` that map back onto GEON sections
+const SECTION_KEYS: &[&str] = &[
+    "name", "geon_type", "id", "location", "extent", "elevation", "area", "purpose", "character",
+    "adjacencies", "source", "experience", "connectivity", "temporal", "lifespan", "confidence",
+    "built_form", "ecology", "infrastructure", "demographics", "economy", "visual", "vertical_profile",
+    "part_of", "viewsheds", "history", "updated", "geon:adjacent_ids",
+];
+
+fn as_text(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn text_list(v: &Value) -> Vec<String> {
+    match v {
+        Value::Array(items) => items.iter().filter_map(as_text).collect(),
+        other => as_text(other).into_iter().collect(),
+    }
+}
+
+fn text_map(v: &Value) -> BTreeMap<String, String> {
+    v.as_object()
+        .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), as_text(v)?))).collect())
+        .unwrap_or_default()
+}
+
+// Nested `contains` Features, rebuilt into a tree from their `parent`
+// references (as written by `to_geojson`) or taken as-is when embedded
+fn contained_places(features: &[Value], own_ref: &str, mapping: &TypeMapping) -> Vec<GeonPlace> {
+    let mut items: Vec<(GeonPlace, Option<String>)> = features
+        .iter()
+        .filter_map(|f| f.as_object())
+        .map(|f| {
+            let mut child = feature_to_geon_with(f, mapping);
+            let parent = child.extra.remove("parent").and_then(|v| as_text(&v));
+            (child, parent)
+        })
+        .collect();
+    let mut roots = Vec::new();
+    // Children follow their parents, so attach from the end backwards
+    while let Some((child, parent)) = items.pop() {
+        let container = parent
+            .filter(|p| p != own_ref)
+            .and_then(|p| items.iter().rposition(|(c, _)| parent_ref(c) == p));
+        match container {
+            Some(i) => items[i].0.contains.insert(0, child),
+            None => roots.insert(0, child),
         }
     }
-    
-    // Copy arbitrary properties logic simplified
-    // ...
-    
-    p
+    roots
+}
+
+/// Map GEON section properties (as written by `to_geojson`) onto the place
+/// and keep every other property in `extra`, so no attribute is lost.
+fn capture_properties(p: &mut GeonPlace, props: &Map<String, Value>, mapping: &TypeMapping) {
+    let text = |key: &str| props.get(key).and_then(as_text);
+    if let Some(id) = text("id") {
+        p.id = Some(id);
+    }
+    if let Some(loc) = props.get("location").and_then(|v| v.as_array()) {
+        let position: Vec<f64> = loc.iter().filter_map(|v| v.as_f64()).collect();
+        if let Some(c) = Coordinate::from_geojson_position(&position) {
+            p.location = Some(c);
+        }
+    }
+    p.extent = props.get("extent").and_then(|v| serde_json::from_value(v.clone()).ok());
+    p.elevation = text("elevation");
+    p.area = text("area");
+    p.part_of = text("part_of");
+    p.updated = text("updated");
+    if let Some(v) = props.get("viewsheds") {
+        p.viewsheds = v.clone();
+    }
+
+    let lists = [
+        ("character", &mut p.character),
+        ("adjacencies", &mut p.adjacencies),
+        ("source", &mut p.source),
+    ];
+    for (key, list) in lists {
+        if let Some(v) = props.get(key) {
+            *list = text_list(v);
+        }
+    }
+    let maps = [
+        ("experience", &mut p.experience),
+        ("connectivity", &mut p.connectivity),
+        ("temporal", &mut p.temporal),
+        ("lifespan", &mut p.lifespan),
+        ("confidence", &mut p.confidence),
+        ("built_form", &mut p.built_form),
+        ("ecology", &mut p.ecology),
+        ("infrastructure", &mut p.infrastructure),
+        ("demographics", &mut p.demographics),
+        ("economy", &mut p.economy),
+        ("visual", &mut p.visual),
+        ("vertical_profile", &mut p.vertical_profile),
+    ];
+    for (key, map) in maps {
+        if let Some(v) = props.get(key) {
+            *map = text_map(v);
+        }
+    }
+    if let Some(Value::Array(entries)) = props.get("history") {
+        p.history = entries.iter().map(text_map).filter(|e| !e.is_empty()).collect();
+    }
+    if let Some(Value::Array(children)) = props.get("contains") {
+        p.contains = contained_places(children, &parent_ref(p), mapping);
+    }
+
+    p.extra = props
+        .iter()
+        .filter(|(k, _)| !SECTION_KEYS.contains(&k.as_str()))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    if !p.contains.is_empty() {
+        p.extra.remove("contains");
+    }
 }
 
 pub fn from_geojson(value: Value) -> Vec<GeonPlace> {
+    from_geojson_with(value, &DEFAULT_MAPPING)
+}
+
+/// As [`from_geojson`], inferring TYPE and PLACE with a custom mapping.
+pub fn from_geojson_with(value: Value, mapping: &TypeMapping) -> Vec<GeonPlace> {
     match value {
         Value::Object(map) => {
             if let Some(type_) = map.get("type").and_then(|v| v.as_str()) {
@@ -137,14 +506,14 @@ pub fn from_geojson(value: Value) -> Vec<GeonPlace> {
                     if let Some(features) = map.get("features").and_then(|v| v.as_array()) {
                         return features.iter().filter_map(|v| {
                             if let Value::Object(f) = v {
-                                Some(feature_to_geon(f))
+                                Some(feature_to_geon_with(f, mapping))
                             } else {
                                 None
                             }
                         }).collect();
                     }
                 } else if type_ == "Feature" {
-                    return vec![feature_to_geon(&map)];
+                    return vec![feature_to_geon_with(&map, mapping)];
                 }
             }
             vec![]
@@ -152,3 +521,289 @@ pub fn from_geojson(value: Value) -> Vec<GeonPlace> {
         _ => vec![],
     }
 }
+
+// GEON -> GeoJSON
+
+fn parent_ref(place: &GeonPlace) -> String {
+    place.id.clone().unwrap_or_else(|| place.place.clone())
+}
+
+fn place_geometry(place: &GeonPlace) -> Value {
+    let polygon = (place.boundary.len() >= 3).then(|| {
+        let mut ring: Vec<Vec<f64>> = place.boundary.iter().map(|c| c.to_geojson_position()).collect();
+        // GeoJSON rings must be closed
+        if place.boundary.first() != place.boundary.last() {
+            ring.push(place.boundary[0].to_geojson_position());
+        }
+        json!({ "type": "Polygon", "coordinates": [ring] })
+    });
+    let line = (place.path.len() >= 2).then(|| {
+        let line: Vec<Vec<f64>> = place.path.iter().map(|c| c.to_geojson_position()).collect();
+        json!({ "type": "LineString", "coordinates": line })
+    });
+    match (polygon, line) {
+        (Some(polygon), Some(line)) => json!({ "type": "GeometryCollection", "geometries": [polygon, line] }),
+        (Some(geometry), None) | (None, Some(geometry)) => geometry,
+        (None, None) => match &place.location {
+            Some(loc) => json!({ "type": "Point", "coordinates": loc.to_geojson_position() }),
+            None => Value::Null,
+        },
+    }
+}
+
+fn place_properties(place: &GeonPlace) -> Map<String, Value> {
+    let mut props = Map::new();
+    props.insert("name".to_string(), json!(place.place));
+    props.insert("geon_type".to_string(), json!(place.type_));
+    if let Some(id) = &place.id { props.insert("id".to_string(), json!(id)); }
+
+    if let Some(loc) = &place.location {
+        if place.boundary.len() >= 3 || place.path.len() >= 2 {
+            props.insert("location".to_string(), json!(loc.to_geojson_position()));
+        }
+    }
+    if let Some(ext) = &place.extent { props.insert("extent".to_string(), json!(ext)); }
+    if let Some(el) = &place.elevation { props.insert("elevation".to_string(), json!(el)); }
+    if let Some(area) = &place.area { props.insert("area".to_string(), json!(area)); }
+
+    let lists = [
+        ("purpose", &place.purpose),
+        ("character", &place.character),
+        ("adjacencies", &place.adjacencies),
+        ("source", &place.source),
+    ];
+    for (key, list) in lists {
+        if !list.is_empty() { props.insert(key.to_string(), json!(list)); }
+    }
+
+    let maps = [
+        ("experience", &place.experience),
+        ("connectivity", &place.connectivity),
+        ("temporal", &place.temporal),
+        ("lifespan", &place.lifespan),
+        ("confidence", &place.confidence),
+        ("built_form", &place.built_form),
+        ("ecology", &place.ecology),
+        ("infrastructure", &place.infrastructure),
+        ("demographics", &place.demographics),
+        ("economy", &place.economy),
+        ("visual", &place.visual),
+        ("vertical_profile", &place.vertical_profile),
+    ];
+    for (key, map) in maps {
+        if !map.is_empty() { props.insert(key.to_string(), json!(map)); }
+    }
+
+    if let Some(part_of) = &place.part_of { props.insert("part_of".to_string(), json!(part_of)); }
+    if !place.viewsheds.is_null() { props.insert("viewsheds".to_string(), place.viewsheds.clone()); }
+    if !place.history.is_empty() { props.insert("history".to_string(), json!(place.history)); }
+    if let Some(updated) = &place.updated { props.insert("updated".to_string(), json!(updated)); }
+
+    // Unknown fields never shadow the GEON sections above
+    for (k, v) in &place.extra {
+        props.entry(k.clone()).or_insert_with(|| v.clone());
+    }
+
+    props
+}
+
+fn place_feature(place: &GeonPlace, props: Map<String, Value>) -> Value {
+    let mut feature = Map::new();
+    feature.insert("type".to_string(), json!("Feature"));
+    if let Some(id) = &place.id { feature.insert("id".to_string(), json!(id)); }
+    feature.insert("geometry".to_string(), place_geometry(place));
+    feature.insert("properties".to_string(), Value::Object(props));
+    Value::Object(feature)
+}
+
+fn flatten_children(parent: &GeonPlace, out: &mut Vec<Value>) {
+    for child in &parent.contains {
+        let mut props = place_properties(child);
+        props.insert("parent".to_string(), json!(parent_ref(parent)));
+        out.push(place_feature(child, props));
+        flatten_children(child, out);
+    }
+}
+
+/// Convert a place into a GeoJSON Feature.
+///
+/// Geometry is a Polygon when BOUNDARY has at least three points and a
+/// LineString when PATH has at least two, or a GeometryCollection of the
+/// Polygon and LineString when a place has both; otherwise it is a Point
+/// from LOCATION (or `null`). GEON sections become lower-cased
+/// properties. Nested places are flattened into a `contains` array of
+/// Features, each carrying a `parent` property (the ID, or name, of the
+/// place directly containing it).
+pub fn to_geojson(place: &GeonPlace) -> Value {
+    let mut props = place_properties(place);
+    if !place.contains.is_empty() {
+        let mut children = Vec::new();
+        flatten_children(place, &mut children);
+        props.insert("contains".to_string(), Value::Array(children));
+    }
+    place_feature(place, props)
+}
+
+/// As [`from_geojson`] on the Feature's JSON.
+#[cfg(feature = "geojson")]
+impl TryFrom<geojson::Feature> for GeonPlace {
+    type Error = GeonError;
+
+    fn try_from(feature: geojson::Feature) -> Result<Self, GeonError> {
+        match serde_json::to_value(&feature) {
+            Ok(Value::Object(map)) => Ok(feature_to_geon(&map)),
+            Ok(_) => Err(GeonError::InvalidStructure("GeoJSON feature is not an object".to_string())),
+            Err(e) => Err(GeonError::InvalidStructure(format!("GeoJSON feature: {}", e))),
+        }
+    }
+}
+
+/// As [`to_geojson`]. A geometry with coordinates that aren't finite
+/// numbers is left out.
+#[cfg(feature = "geojson")]
+impl From<&GeonPlace> for geojson::Feature {
+    fn from(place: &GeonPlace) -> Self {
+        let props = match to_geojson(place).get_mut("properties").map(Value::take) {
+            Some(Value::Object(props)) => Some(props),
+            _ => None,
+        };
+        geojson::Feature {
+            bbox: None,
+            geometry: serde_json::from_value(place_geometry(place)).ok(),
+            id: place.id.clone().map(geojson::feature::Id::String),
+            properties: props,
+            foreign_members: None,
+        }
+    }
+}
+
+/// How CONTAINS hierarchies are represented in a FeatureCollection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HierarchyMode {
+    /// Every nested place becomes its own top-level Feature, linked to its
+    /// container through `parent_id` and `part_of` properties, with its
+    /// `depth` below the top-level place (0 for the place itself).
+    #[default]
+    Flatten,
+    /// Only the given places become Features; children are embedded
+    /// recursively as a `contains` array of Features in their properties.
+    Embed,
+}
+
+/// Options for [`to_geojson_collection`].
+#[derive(Debug, Clone, Default)]
+pub struct CollectionOptions {
+    pub hierarchy: HierarchyMode,
+}
+
+// The collection being exported, for resolving ADJACENCIES
+type Links<'a> = Option<(&'a GeonCollection, &'a Registry)>;
+
+// ADJACENCIES that resolve to a single place, as that place's ID (or name)
+fn adjacent_ids(place: &GeonPlace, links: Links) -> Option<Value> {
+    let (collection, registry) = links?;
+    let ids: Vec<String> = place
+        .adjacencies
+        .iter()
+        .filter_map(|reference| match registry.resolve(reference) {
+            Resolution::Resolved(handle) => handle.get(collection).map(parent_ref),
+            _ => None,
+        })
+        .collect();
+    (!ids.is_empty()).then(|| json!(ids))
+}
+
+fn linked_properties(place: &GeonPlace, links: Links) -> Map<String, Value> {
+    let mut props = place_properties(place);
+    if let Some(ids) = adjacent_ids(place, links) {
+        props.insert("geon:adjacent_ids".to_string(), ids);
+    }
+    props
+}
+
+fn embedded_feature(place: &GeonPlace, links: Links) -> Value {
+    let mut props = linked_properties(place, links);
+    if !place.contains.is_empty() {
+        let children = place.contains.iter().map(|c| embedded_feature(c, links)).collect();
+        props.insert("contains".to_string(), Value::Array(children));
+    }
+    place_feature(place, props)
+}
+
+fn flatten_into(place: &GeonPlace, parent: Option<&GeonPlace>, depth: usize, links: Links, out: &mut Vec<Value>) {
+    let mut props = linked_properties(place, links);
+    if let Some(parent) = parent {
+        props.insert("parent_id".to_string(), json!(parent_ref(parent)));
+        if place.part_of.is_none() {
+            props.insert("part_of".to_string(), json!(parent.place));
+        }
+    }
+    props.insert("depth".to_string(), json!(depth));
+    out.push(place_feature(place, props));
+    for child in &place.contains {
+        flatten_into(child, Some(place), depth + 1, links, out);
+    }
+}
+
+fn feature_collection(places: &[GeonPlace], opts: &CollectionOptions, links: Links) -> Value {
+    let mut features = Vec::new();
+    for place in places {
+        match opts.hierarchy {
+            HierarchyMode::Flatten => flatten_into(place, None, 0, links, &mut features),
+            HierarchyMode::Embed => features.push(embedded_feature(place, links)),
+        }
+    }
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Convert places into a GeoJSON FeatureCollection, suitable for loading
+/// into desktop and web GIS tools.
+pub fn to_geojson_collection(places: &[GeonPlace], opts: &CollectionOptions) -> Value {
+    feature_collection(places, opts, None)
+}
+
+impl GeonCollection {
+    /// As [`to_geojson_collection`], also resolving each place's
+    /// ADJACENCIES against the whole collection into a `geon:adjacent_ids`
+    /// array of IDs (or names, for places without one). References that
+    /// are unresolved or ambiguous are left out of it.
+    pub fn to_geojson(&self, opts: &CollectionOptions) -> Value {
+        let registry = self.registry();
+        feature_collection(&self.places, opts, Some((self, &registry)))
+    }
+}
+
+// Shared helpers for the XML exporters
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn sorted_pairs(map: &BTreeMap<String, String>) -> String {
+    map.iter().map(|(k, v)| format!("{}: {}", k, v)).collect::<Vec<_>>().join("; ")
+}
+
+// Plain-text summary of the semantic sections, one "Section: ..." per line
+fn describe_place(place: &GeonPlace) -> String {
+    let mut lines = Vec::new();
+    if !place.type_.is_empty() { lines.push(format!("Type: {}", place.type_)); }
+    if !place.purpose.is_empty() { lines.push(format!("Purpose: {}", place.purpose.join(", "))); }
+    if !place.experience.is_empty() { lines.push(format!("Experience: {}", sorted_pairs(&place.experience))); }
+    if !place.character.is_empty() { lines.push(format!("Character: {}", place.character.join(", "))); }
+    if !place.adjacencies.is_empty() { lines.push(format!("Adjacencies: {}", place.adjacencies.join(", "))); }
+    if !place.connectivity.is_empty() { lines.push(format!("Connectivity: {}", sorted_pairs(&place.connectivity))); }
+    if !place.temporal.is_empty() { lines.push(format!("Temporal: {}", sorted_pairs(&place.temporal))); }
+    if let Some(area) = &place.area { lines.push(format!("Area: {}", area)); }
+    lines.join("\n")
+}
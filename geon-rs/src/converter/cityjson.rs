@@ -0,0 +1,341 @@
+use super::osm::average;
+use crate::geometry::centroid;
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// CityJSON city object type -> GEON type
+const OBJECT_TYPES: &[(&str, &str)] = &[
+    ("Building", "building"),
+    ("BuildingPart", "building"),
+    ("BuildingInstallation", "building"),
+    ("BuildingStorey", "building"),
+    ("BuildingRoom", "building"),
+    ("BuildingUnit", "building"),
+    ("Road", "street"),
+    ("TransportSquare", "public_space"),
+    ("Railway", "infrastructure"),
+    ("Waterway", "infrastructure"),
+    ("Bridge", "infrastructure"),
+    ("BridgePart", "infrastructure"),
+    ("Tunnel", "infrastructure"),
+    ("TunnelPart", "infrastructure"),
+    ("CityFurniture", "infrastructure"),
+    ("OtherConstruction", "infrastructure"),
+    ("WaterBody", "natural_feature"),
+    ("PlantCover", "natural_feature"),
+    ("SolitaryVegetationObject", "natural_feature"),
+    ("TINRelief", "natural_feature"),
+    ("LandUse", "district"),
+];
+
+// Attributes mapped onto BUILT_FORM / VERTICAL_PROFILE keys
+const BUILT_FORM_ATTRS: &[(&str, &str)] = &[
+    ("measuredHeight", "height"),
+    ("roofType", "roof_type"),
+    ("yearOfConstruction", "year_built"),
+    ("yearOfDemolition", "year_demolished"),
+    ("class", "class"),
+];
+const VERTICAL_ATTRS: &[(&str, &str)] = &[
+    ("storeysAboveGround", "storeys_above_ground"),
+    ("storeysBelowGround", "storeys_below_ground"),
+    ("storeyHeightsAboveGround", "storey_height_above_ground"),
+    ("storeyHeightsBelowGround", "storey_height_below_ground"),
+];
+const NAME_ATTRS: &[&str] = &["name", "Name", "naam"];
+const PURPOSE_ATTRS: &[&str] = &["function", "usage"];
+
+fn invalid(msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("CityJSON: {}", msg))
+}
+
+fn text(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn metres(z: f64) -> String {
+    format!("{} m", (z * 10.0).round() / 10.0)
+}
+
+/// EPSG code of the document's `metadata.referenceSystem`, e.g. 7415 for
+/// `https://www.opengis.net/def/crs/EPSG/0/7415`.
+pub fn epsg_code(doc: &Value) -> Option<u32> {
+    let crs = doc.pointer("/metadata/referenceSystem")?.as_str()?;
+    crs.rsplit(['/', ':']).find(|s| !s.is_empty())?.parse().ok()
+}
+
+struct Vertices {
+    points: Vec<[f64; 3]>,
+}
+
+impl Vertices {
+    fn read(doc: &Value) -> Result<Self, GeonError> {
+        let scale = doc.pointer("/transform/scale").and_then(xyz).unwrap_or([1.0; 3]);
+        let translate = doc.pointer("/transform/translate").and_then(xyz).unwrap_or([0.0; 3]);
+        let raw = doc.get("vertices").and_then(|v| v.as_array()).ok_or_else(|| invalid("missing vertices"))?;
+        let points = raw
+            .iter()
+            .map(|v| {
+                xyz(v)
+                    .map(|p| [0, 1, 2].map(|i| p[i] * scale[i] + translate[i]))
+                    .ok_or_else(|| invalid("bad vertex"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Vertices { points })
+    }
+
+    fn get(&self, index: &Value) -> Option<[f64; 3]> {
+        self.points.get(index.as_u64()? as usize).copied()
+    }
+}
+
+fn xyz(v: &Value) -> Option<[f64; 3]> {
+    let a = v.as_array()?;
+    Some([a.first()?.as_f64()?, a.get(1)?.as_f64()?, a.get(2).and_then(|z| z.as_f64()).unwrap_or(0.0)])
+}
+
+// Outer rings of every surface in a geometry, as vertex positions. Boundaries
+// nest as solid > shell > surface > ring > index (MultiSolid adds a level);
+// a ring is the innermost array of indices and a surface's first ring is its
+// exterior.
+fn surfaces(boundaries: &Value, vertices: &Vertices, out: &mut Vec<Vec<[f64; 3]>>) {
+    let Some(items) = boundaries.as_array() else { return };
+    let is_surface = items
+        .first()
+        .and_then(|ring| ring.as_array())
+        .and_then(|ring| ring.first())
+        .is_some_and(|i| i.is_u64());
+    if is_surface {
+        if let Some(exterior) = items.first().and_then(|r| r.as_array()) {
+            out.push(exterior.iter().filter_map(|i| vertices.get(i)).collect());
+        }
+    } else {
+        for item in items {
+            surfaces(item, vertices, out);
+        }
+    }
+}
+
+// Highest LoD geometry of an object (LoD may be a number or a string such as "2.2")
+fn best_geometry(object: &Value) -> Option<&Value> {
+    let lod = |g: &&Value| g.get("lod").and_then(text).and_then(|l| l.parse::<f64>().ok()).unwrap_or(0.0);
+    object.get("geometry")?.as_array()?.iter().max_by(|a, b| lod(a).total_cmp(&lod(b)))
+}
+
+fn to_coord(p: &[f64; 3], reproject: &dyn Fn(f64, f64) -> Coordinate) -> Coordinate {
+    reproject(p[0], p[1])
+}
+
+fn object_to_place(
+    id: &str,
+    object: &Value,
+    vertices: &Vertices,
+    reproject: Option<&dyn Fn(f64, f64) -> Coordinate>,
+) -> GeonPlace {
+    let empty = Map::new();
+    let attrs = object.get("attributes").and_then(|a| a.as_object()).unwrap_or(&empty);
+    let kind = object.get("type").and_then(|t| t.as_str()).unwrap_or("");
+
+    let mut p = GeonPlace::default();
+    p.place = NAME_ATTRS
+        .iter()
+        .find_map(|k| attrs.get(*k).and_then(text))
+        .unwrap_or_else(|| "Unnamed".to_string());
+    p.type_ = OBJECT_TYPES
+        .iter()
+        .find(|(t, _)| kind.strip_prefix('+').unwrap_or(kind) == *t)
+        .map(|(_, g)| g.to_string())
+        .unwrap_or_else(|| "hybrid".to_string());
+    p.id = Some(format!("cityjson:{}", id));
+    p.source = vec![format!("CityJSON ({})", id)];
+    p.purpose = PURPOSE_ATTRS.iter().filter_map(|k| attrs.get(*k).and_then(text)).collect();
+
+    for (attr, key) in BUILT_FORM_ATTRS {
+        if let Some(v) = attrs.get(*attr).and_then(text) {
+            p.built_form.insert(key.to_string(), v);
+        }
+    }
+    if let Some(year) = p.built_form.get("year_built") {
+        p.lifespan.insert("built".to_string(), year.clone());
+    }
+    for (attr, key) in VERTICAL_ATTRS {
+        if let Some(v) = attrs.get(*attr).and_then(text) {
+            p.vertical_profile.insert(key.to_string(), v);
+        }
+    }
+    let rest: Map<String, Value> = attrs
+        .iter()
+        .filter(|(k, _)| {
+            let k = k.as_str();
+            !NAME_ATTRS.contains(&k)
+                && !PURPOSE_ATTRS.contains(&k)
+                && !BUILT_FORM_ATTRS.iter().chain(VERTICAL_ATTRS).any(|(a, _)| *a == k)
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    if !rest.is_empty() {
+        p.extra.insert("attributes".to_string(), Value::Object(rest));
+    }
+    p.extra.insert("cityjson_type".to_string(), Value::String(kind.to_string()));
+
+    if let Some(geometry) = best_geometry(object) {
+        if let Some(lod) = geometry.get("lod").and_then(text) {
+            p.vertical_profile.insert("lod".to_string(), lod);
+        }
+        let mut rings = Vec::new();
+        if let Some(b) = geometry.get("boundaries") {
+            surfaces(b, vertices, &mut rings);
+        }
+        let all: Vec<&[f64; 3]> = rings.iter().flatten().collect();
+        if !all.is_empty() {
+            let min_z = all.iter().map(|v| v[2]).fold(f64::INFINITY, f64::min);
+            let max_z = all.iter().map(|v| v[2]).fold(f64::NEG_INFINITY, f64::max);
+            p.elevation = Some(metres(min_z));
+            if max_z > min_z {
+                p.vertical_profile.insert("height".to_string(), metres(max_z - min_z));
+                p.built_form.entry("height".to_string()).or_insert_with(|| metres(max_z - min_z));
+            }
+            if let Some(reproject) = reproject {
+                // Footprint: the largest surface lying flat at the lowest level
+                let footprint = rings
+                    .iter()
+                    .filter(|r| r.len() >= 3 && r.iter().all(|v| (v[2] - min_z).abs() < 1e-6))
+                    .max_by_key(|r| r.len());
+                if let Some(ring) = footprint {
+                    p.boundary = ring.iter().map(|v| to_coord(v, reproject)).collect();
+                }
+                let points: Vec<Coordinate> = all.iter().map(|v| to_coord(v, reproject)).collect();
+                p.location = if p.boundary.is_empty() { average(&points) } else { centroid(&p.boundary) };
+            }
+        }
+    }
+    p
+}
+
+fn is_geographic(doc: &Value) -> bool {
+    matches!(epsg_code(doc), Some(4326 | 4979 | 4258 | 4937))
+}
+
+/// Convert a CityJSON document. Buildings, roads, water bodies and other
+/// city objects become places (see the type table above); child objects
+/// such as `BuildingPart`s are nested under CONTAINS with PART_OF set.
+///
+/// Attributes fill BUILT_FORM (`measuredHeight`, `roofType`,
+/// `yearOfConstruction`, ...) and VERTICAL_PROFILE (storeys and the LoD of
+/// the geometry used); ELEVATION and height come from the geometry itself.
+///
+/// Coordinates are only used when the reference system is geographic
+/// (EPSG:4326, 4979, 4258 or 4937); for projected models use
+/// [`from_cityjson_with`] and supply the reprojection.
+pub fn from_cityjson(doc: &Value) -> Result<Vec<GeonPlace>, GeonError> {
+    if is_geographic(doc) {
+        from_cityjson_with(doc, &|x, y| Coordinate::new(y, x))
+    } else {
+        convert(doc, None)
+    }
+}
+
+/// As [`from_cityjson`], mapping each vertex's real-world `(x, y)` to a
+/// WGS84 coordinate with `reproject`.
+pub fn from_cityjson_with(doc: &Value, reproject: &dyn Fn(f64, f64) -> Coordinate) -> Result<Vec<GeonPlace>, GeonError> {
+    convert(doc, Some(reproject))
+}
+
+fn convert(doc: &Value, reproject: Option<&dyn Fn(f64, f64) -> Coordinate>) -> Result<Vec<GeonPlace>, GeonError> {
+    if doc.get("type").and_then(|t| t.as_str()) != Some("CityJSON") {
+        return Err(invalid("not a CityJSON document"));
+    }
+    let vertices = Vertices::read(doc)?;
+    let objects = doc.get("CityObjects").and_then(|o| o.as_object()).ok_or_else(|| invalid("missing CityObjects"))?;
+
+    let mut places: HashMap<&str, GeonPlace> =
+        objects.iter().map(|(id, obj)| (id.as_str(), object_to_place(id, obj, &vertices, reproject))).collect();
+
+    // Attach children depth-first so grandchildren are nested before their parent moves
+    fn attach(id: &str, objects: &Map<String, Value>, places: &mut HashMap<&str, GeonPlace>) -> Option<GeonPlace> {
+        let mut place = places.remove(id)?;
+        let children = objects[id].get("children").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+        for child in children.iter().filter_map(|c| c.as_str()) {
+            if let Some(mut child) = attach(child, objects, places) {
+                child.part_of = place.id.clone();
+                place.contains.push(child);
+            }
+        }
+        Some(place)
+    }
+
+    let mut out = Vec::new();
+    for (id, obj) in objects {
+        let has_parent = obj.get("parents").and_then(|p| p.as_array()).is_some_and(|p| !p.is_empty());
+        if !has_parent {
+            out.extend(attach(id, objects, &mut places));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cube_doc(crs: &str) -> Value {
+        // Unit cube at 10 m elevation, vertices scaled by 0.001
+        json!({
+            "type": "CityJSON",
+            "version": "2.0",
+            "metadata": {"referenceSystem": crs},
+            "transform": {"scale": [0.001, 0.001, 0.001], "translate": [-1.15, 52.95, 10.0]},
+            "vertices": [[0,0,0],[1,0,0],[1,1,0],[0,1,0],[0,0,12000],[1,0,12000],[1,1,12000],[0,1,12000]],
+            "CityObjects": {
+                "b1": {
+                    "type": "Building",
+                    "attributes": {"name": "Council House", "roofType": "dome", "storeysAboveGround": 4, "architect": "T. Cecil Howitt"},
+                    "geometry": [{"type": "Solid", "lod": "2.2", "boundaries": [[
+                        [[0,3,2,1]], [[4,5,6,7]], [[0,1,5,4]], [[1,2,6,5]], [[2,3,7,6]], [[3,0,4,7]]
+                    ]]}],
+                    "children": ["b1-part"]
+                },
+                "b1-part": {"type": "BuildingPart", "parents": ["b1"], "attributes": {"function": "office"}}
+            }
+        })
+    }
+
+    #[test]
+    fn test_building_semantics() {
+        let places = from_cityjson(&cube_doc("https://www.opengis.net/def/crs/EPSG/0/4979")).unwrap();
+        assert_eq!(places.len(), 1);
+        let b = &places[0];
+        assert_eq!(b.place, "Council House");
+        assert_eq!(b.type_, "building");
+        assert_eq!(b.built_form["roof_type"], "dome");
+        assert_eq!(b.built_form["height"], "12 m");
+        assert_eq!(b.vertical_profile["storeys_above_ground"], "4");
+        assert_eq!(b.vertical_profile["lod"], "2.2");
+        assert_eq!(b.elevation.as_deref(), Some("10 m"));
+        assert_eq!(b.boundary.len(), 4);
+        assert_eq!(b.extra["attributes"]["architect"], "T. Cecil Howitt");
+        assert_eq!(b.contains[0].purpose, vec!["office"]);
+        assert_eq!(b.contains[0].part_of.as_deref(), Some("cityjson:b1"));
+        assert_eq!(crate::parse(&crate::generate(b)), *b);
+    }
+
+    #[test]
+    fn test_projected_without_reprojection() {
+        let doc = cube_doc("https://www.opengis.net/def/crs/EPSG/0/7415");
+        assert_eq!(epsg_code(&doc), Some(7415));
+        let places = from_cityjson(&doc).unwrap();
+        assert!(places[0].location.is_none());
+        assert_eq!(places[0].built_form["height"], "12 m");
+
+        let shifted = from_cityjson_with(&doc, &|x, y| Coordinate::new(y + 1.0, x)).unwrap();
+        assert!(shifted[0].location.as_ref().unwrap().lat > 53.0);
+    }
+}
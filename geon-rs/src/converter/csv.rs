@@ -0,0 +1,211 @@
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+
+/// Column names used when reading and writing flat CSV.
+///
+/// The core columns map onto PLACE, TYPE, ID, LOCATION, AREA and PURPOSE;
+/// `extra` lists further `(column, key)` pairs carried in `GeonPlace::extra`.
+#[derive(Debug, Clone)]
+pub struct CsvMapping {
+    pub name: String,
+    pub type_: String,
+    pub id: String,
+    pub lat: String,
+    pub lon: String,
+    pub area: String,
+    pub purpose: String,
+    /// Separator used to join PURPOSE entries into one cell.
+    pub purpose_separator: char,
+    pub extra: Vec<(String, String)>,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self {
+            name: "name".to_string(),
+            type_: "type".to_string(),
+            id: "id".to_string(),
+            lat: "lat".to_string(),
+            lon: "lon".to_string(),
+            area: "area".to_string(),
+            purpose: "purpose".to_string(),
+            purpose_separator: ';',
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl CsvMapping {
+    /// Map an additional CSV column to an `extra` key of the same name.
+    pub fn with_extra(mut self, column: &str) -> Self {
+        self.extra.push((column.to_string(), column.to_string()));
+        self
+    }
+
+    fn core_columns(&self) -> [&str; 7] {
+        [&self.name, &self.type_, &self.id, &self.lat, &self.lon, &self.area, &self.purpose]
+    }
+}
+
+// RFC 4180 records: quoted fields may contain commas, quotes and newlines
+pub(crate) fn read_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+    records
+}
+
+fn write_field(buf: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        buf.push('"');
+        buf.push_str(&field.replace('"', "\"\""));
+        buf.push('"');
+    } else {
+        buf.push_str(field);
+    }
+}
+
+fn write_record(buf: &mut String, fields: &[String]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            buf.push(',');
+        }
+        write_field(buf, field);
+    }
+    buf.push('\n');
+}
+
+/// Read places from CSV with a header row. Missing columns and empty cells
+/// are left unset; a row with only one of lat/lon has no LOCATION.
+pub fn from_csv(text: &str, mapping: &CsvMapping) -> Result<Vec<GeonPlace>, GeonError> {
+    let mut records = read_records(text).into_iter();
+    let header = match records.next() {
+        Some(h) => h,
+        None => return Ok(vec![]),
+    };
+    let col = |name: &str| header.iter().position(|h| h.trim() == name);
+    let [name_i, type_i, id_i, lat_i, lon_i, area_i, purpose_i] = mapping.core_columns().map(col);
+    let extra_cols: Vec<(usize, &String)> = mapping
+        .extra
+        .iter()
+        .filter_map(|(column, key)| col(column).map(|i| (i, key)))
+        .collect();
+
+    let mut places = Vec::new();
+    for record in records {
+        let cell = |i: Option<usize>| {
+            i.and_then(|i| record.get(i)).map(|s| s.trim()).filter(|s| !s.is_empty())
+        };
+
+        let mut p = GeonPlace::default();
+        p.place = cell(name_i).unwrap_or("Unnamed").to_string();
+        p.type_ = cell(type_i).unwrap_or_default().to_string();
+        p.id = cell(id_i).map(str::to_string);
+        if let (Some(lat), Some(lon)) = (cell(lat_i), cell(lon_i)) {
+            p.location = Some(Coordinate::new(lat.parse()?, lon.parse()?));
+        }
+        p.area = cell(area_i).map(str::to_string);
+        if let Some(purpose) = cell(purpose_i) {
+            p.purpose = purpose
+                .split(mapping.purpose_separator)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        for (i, key) in &extra_cols {
+            if let Some(v) = cell(Some(*i)) {
+                p.extra.insert((*key).clone(), Value::String(v.to_string()));
+            }
+        }
+        places.push(p);
+    }
+    Ok(places)
+}
+
+/// Write top-level places as CSV with a header row. Nested CONTAINS places
+/// and sections without a column are not represented.
+pub fn to_csv(places: &[GeonPlace], mapping: &CsvMapping) -> String {
+    let mut buf = String::new();
+    let mut header: Vec<String> = mapping.core_columns().iter().map(|c| c.to_string()).collect();
+    header.extend(mapping.extra.iter().map(|(column, _)| column.clone()));
+    write_record(&mut buf, &header);
+
+    for p in places {
+        let mut row = vec![
+            p.place.clone(),
+            p.type_.clone(),
+            p.id.clone().unwrap_or_default(),
+            p.location.as_ref().map(|c| c.lat.to_string()).unwrap_or_default(),
+            p.location.as_ref().map(|c| c.lon.to_string()).unwrap_or_default(),
+            p.area.clone().unwrap_or_default(),
+            p.purpose.join(&mapping.purpose_separator.to_string()),
+        ];
+        for (_, key) in &mapping.extra {
+            row.push(match p.extra.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(v) => v.to_string(),
+            });
+        }
+        write_record(&mut buf, &row);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let text = "name,type,lat,lon,purpose,opening_hours\n\"Market, Old\",public_space,52.95,-1.15,trade; events,\"Mo-Sa \"\"08:00\"\"\"\nStall,,,,,\n";
+        let mapping = CsvMapping::default().with_extra("opening_hours");
+        let places = from_csv(text, &mapping).unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].place, "Market, Old");
+        assert_eq!(places[0].location, Some(Coordinate::new(52.95, -1.15)));
+        assert_eq!(places[0].purpose, vec!["trade", "events"]);
+        assert_eq!(places[0].extra["opening_hours"], "Mo-Sa \"08:00\"");
+        assert_eq!(places[1].location, None);
+
+        let again = from_csv(&to_csv(&places, &mapping), &mapping).unwrap();
+        assert_eq!(again, places);
+    }
+
+    #[test]
+    fn test_csv_bad_coordinate() {
+        assert!(from_csv("name,lat,lon\nX,north,1\n", &CsvMapping::default()).is_err());
+    }
+}
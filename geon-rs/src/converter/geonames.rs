@@ -0,0 +1,219 @@
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Feature codes with a more specific type than their class
+const CODE_TABLE: &[(&str, &[&str])] = &[
+    ("transport_hub", &["AIRP", "AIRF", "RSTN", "RSTP", "BUSTN", "BUSTP", "MTRO", "FY", "PRT"]),
+    ("landmark", &["MNMT", "CSTL", "HSTS", "RUIN", "TOWR", "ANS", "PAL", "PYR", "MUS"]),
+    ("threshold", &["BDG", "TNL", "GATE", "PASS"]),
+    ("public_space", &["PRK", "SQR", "GDN", "PLAY", "ATHF", "STDM", "CMN", "RES"]),
+    ("street", &["RD", "ST", "TRL", "RDJCT"]),
+    ("infrastructure", &["RR", "DAM", "PS", "PSH", "CNL", "PRKGT", "RSV"]),
+];
+
+// Fallback by feature class
+fn class_type(class: &str) -> &'static str {
+    match class {
+        "A" | "P" => "district",
+        "H" | "T" | "V" | "U" => "natural_feature",
+        "L" => "public_space",
+        "R" => "street",
+        "S" => "building",
+        _ => "hybrid",
+    }
+}
+
+/// GEON type for a GeoNames feature class and code (e.g. `S`, `RSTN`).
+pub fn type_for(class: &str, code: &str) -> String {
+    CODE_TABLE
+        .iter()
+        .find(|(_, codes)| codes.contains(&code))
+        .map(|(t, _)| *t)
+        .unwrap_or_else(|| class_type(class))
+        .to_string()
+}
+
+/// Read an `admin1CodesASCII.txt` / `admin2Codes.txt` (or `countryInfo.txt`
+/// reduced to `code<TAB>name`) file into a code -> name table such as
+/// `GB.ENG -> England`, for resolving PART_OF in [`from_dump`].
+pub fn admin_names(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut cols = l.split('\t');
+            Some((cols.next()?.to_string(), cols.next()?.to_string()))
+        })
+        .collect()
+}
+
+fn elevation(metres: &str) -> Option<String> {
+    let m: i64 = metres.trim().parse().ok()?;
+    (m != -9999).then(|| format!("{} m", m))
+}
+
+fn common(p: &mut GeonPlace, id: &str, class: &str, code: &str, population: Option<u64>) {
+    p.type_ = type_for(class, code);
+    p.id = Some(format!("geonames:{}", id));
+    p.source = vec![format!("GeoNames ({})", id)];
+    if let Some(population) = population.filter(|n| *n > 0) {
+        p.demographics.insert("population".to_string(), population.to_string());
+    }
+    p.extra.insert("feature_code".to_string(), Value::String(format!("{}.{}", class, code)));
+}
+
+/// Read rows of a GeoNames dump (`allCountries.txt`, `GB.txt`, `cities500.txt`
+/// and so on): 19 tab-separated columns, no header.
+///
+/// PART_OF lists the admin divisions smallest first (`admin2, admin1,
+/// country`), named through `admins` (see [`admin_names`]) where possible
+/// and by code otherwise. Alternate names, the time zone and the feature
+/// code go to `extra`.
+pub fn from_dump(text: &str, admins: &HashMap<String, String>) -> Result<Vec<GeonPlace>, GeonError> {
+    let mut places = Vec::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < 19 {
+            return Err(GeonError::InvalidStructure(format!(
+                "GeoNames: line {} has {} columns, expected 19",
+                n + 1,
+                cols.len()
+            )));
+        }
+
+        let mut p = GeonPlace::default();
+        p.place = cols[1].to_string();
+        p.location = Some(Coordinate::new(cols[4].parse()?, cols[5].parse()?));
+        common(&mut p, cols[0], cols[6], cols[7], cols[14].parse().ok());
+        p.elevation = elevation(cols[15]).or_else(|| elevation(cols[16]));
+        p.updated = Some(cols[18].to_string()).filter(|s| !s.is_empty());
+
+        // Admin codes are keyed cumulatively: GB, GB.ENG, GB.ENG.J9
+        let country = cols[8];
+        let mut keys = vec![country.to_string()];
+        for code in cols[10..12].iter().take_while(|c| !c.is_empty() && **c != "00") {
+            keys.push(format!("{}.{}", keys.last().unwrap(), code));
+        }
+        if !country.is_empty() {
+            let chain: Vec<String> = keys
+                .iter()
+                .rev()
+                .map(|key| admins.get(key).cloned().unwrap_or_else(|| key.rsplit('.').next().unwrap().to_string()))
+                .filter(|name| *name != p.place)
+                .collect();
+            p.part_of = Some(chain.join(", ")).filter(|s| !s.is_empty());
+        }
+
+        let alternates: Vec<Value> = cols[3]
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| Value::String(s.to_string()))
+            .collect();
+        if !alternates.is_empty() {
+            p.extra.insert("alternate_names".to_string(), Value::Array(alternates));
+        }
+        if !cols[17].is_empty() {
+            p.extra.insert("timezone".to_string(), Value::String(cols[17].to_string()));
+        }
+        places.push(p);
+    }
+    Ok(places)
+}
+
+// API numbers arrive as strings (lat, lng) or numbers (population)
+fn number(v: &Value, key: &str) -> Option<f64> {
+    match v.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Convert one record from the GeoNames web services (`getJSON`, or an
+/// entry of `searchJSON`'s `geonames` array).
+///
+/// PART_OF is built from `adminName1`..`adminName4` and `countryName`,
+/// smallest first; `bbox`, where requested, sets EXTENT.
+pub fn from_api_record(record: &Value) -> GeonPlace {
+    let text = |key: &str| record.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+    let id = match record.get("geonameId") {
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::String(s)) => s.clone(),
+        _ => String::new(),
+    };
+
+    let mut p = GeonPlace::default();
+    p.place = text("name").or(text("toponymName")).unwrap_or("Unnamed").to_string();
+    if let (Some(lat), Some(lon)) = (number(record, "lat"), number(record, "lng")) {
+        p.location = Some(Coordinate::new(lat, lon));
+    }
+    common(
+        &mut p,
+        &id,
+        text("fcl").unwrap_or_default(),
+        text("fcode").unwrap_or_default(),
+        number(record, "population").map(|n| n as u64),
+    );
+    if id.is_empty() {
+        p.id = None;
+        p.source = vec!["GeoNames".to_string()];
+    }
+    p.elevation = number(record, "elevation").map(|m| format!("{} m", m));
+
+    let chain: Vec<&str> = ["adminName4", "adminName3", "adminName2", "adminName1", "countryName"]
+        .iter()
+        .filter_map(|key| text(key))
+        .filter(|name| *name != p.place)
+        .collect();
+    p.part_of = Some(chain.join(", ")).filter(|s| !s.is_empty());
+
+    if let Some(bbox) = record.get("bbox") {
+        if let (Some(north), Some(south), Some(east), Some(west)) =
+            (number(bbox, "north"), number(bbox, "south"), number(bbox, "east"), number(bbox, "west"))
+        {
+            p.extent = Some(Extent { north, south, east, west });
+        }
+    }
+    if let Some(tz) = record.pointer("/timezone/timeZoneId").and_then(|v| v.as_str()) {
+        p.extra.insert("timezone".to_string(), Value::String(tz.to_string()));
+    }
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dump_row() {
+        let row = "2641170\tNottingham\tNottingham\tNottingem,Snotingaham\t52.9536\t-1.15047\tP\tPPLA2\tGB\t\tENG\tJ9\t\t\t289301\t\t56\tEurope/London\t2023-06-01\n";
+        let mut admins = admin_names("GB.ENG\tEngland\tEngland\t6269131\n");
+        admins.insert("GB".to_string(), "United Kingdom".to_string());
+        let places = from_dump(row, &admins).unwrap();
+        let p = &places[0];
+        assert_eq!(p.place, "Nottingham");
+        assert_eq!(p.type_, "district");
+        assert_eq!(p.id.as_deref(), Some("geonames:2641170"));
+        assert_eq!(p.demographics["population"], "289301");
+        assert_eq!(p.elevation.as_deref(), Some("56 m"));
+        assert_eq!(p.part_of.as_deref(), Some("J9, England, United Kingdom"));
+        assert_eq!(p.extra["alternate_names"], json!(["Nottingem", "Snotingaham"]));
+        assert!(from_dump("1\tshort\n", &admins).is_err());
+    }
+
+    #[test]
+    fn test_api_record() {
+        let record = json!({
+            "geonameId": 6954829, "name": "Nottingham Station", "lat": "52.94713", "lng": "-1.14622",
+            "fcl": "S", "fcode": "RSTN", "population": 0, "countryName": "United Kingdom",
+            "adminName1": "England", "adminName2": "Nottingham"
+        });
+        let p = from_api_record(&record);
+        assert_eq!(p.type_, "transport_hub");
+        assert_eq!(p.location, Some(Coordinate::new(52.94713, -1.14622)));
+        assert_eq!(p.part_of.as_deref(), Some("Nottingham, England, United Kingdom"));
+        assert!(p.demographics.is_empty());
+    }
+}
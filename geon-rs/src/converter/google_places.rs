@@ -0,0 +1,144 @@
+use crate::models::{Coordinate, Extent, GeonPlace};
+use serde_json::Value;
+
+// Google place types -> GEON type; the first matching type wins
+const TYPE_TABLE: &[(&str, &[&str])] = &[
+    ("transport_hub", &[
+        "airport", "bus_station", "bus_stop", "ferry_terminal", "light_rail_station", "subway_station",
+        "train_station", "transit_station", "taxi_stand",
+    ]),
+    ("public_space", &[
+        "park", "dog_park", "playground", "plaza", "national_park", "garden", "campground", "stadium",
+        "sports_complex", "athletic_field", "picnic_ground", "marina",
+    ]),
+    ("landmark", &["tourist_attraction", "historical_landmark", "monument", "sculpture", "cultural_landmark"]),
+    ("natural_feature", &["natural_feature", "beach", "hiking_area", "lake", "river", "mountain_peak"]),
+    ("infrastructure", &["parking", "gas_station", "electric_vehicle_charging_station", "rest_stop", "bridge"]),
+    ("street", &["route", "street_address", "intersection"]),
+    ("district", &[
+        "neighborhood", "sublocality", "locality", "political", "administrative_area_level_1",
+        "administrative_area_level_2", "postal_code", "colloquial_area",
+    ]),
+    ("building", &[
+        "restaurant", "cafe", "bar", "pub", "bakery", "museum", "art_gallery", "library", "school",
+        "university", "hospital", "church", "mosque", "synagogue", "hindu_temple", "lodging", "hotel",
+        "store", "supermarket", "shopping_mall", "department_store", "movie_theater", "performing_arts_theater",
+        "city_hall", "courthouse", "post_office", "police", "fire_station", "bank", "gym", "pharmacy",
+    ]),
+];
+
+// Types too general to be a PURPOSE
+const GENERIC_TYPES: &[&str] = &["point_of_interest", "establishment", "political", "premise", "food"];
+
+/// GEON type for a list of Google place types (`primaryType` first).
+pub fn type_for(types: &[&str]) -> String {
+    types
+        .iter()
+        .find_map(|t| TYPE_TABLE.iter().find(|(_, values)| values.contains(t)).map(|(geon, _)| geon.to_string()))
+        .unwrap_or_else(|| "hybrid".to_string())
+}
+
+fn lat_lng(v: &Value) -> Option<Coordinate> {
+    Some(Coordinate::new(v.get("latitude")?.as_f64()?, v.get("longitude")?.as_f64()?))
+}
+
+/// Convert a Places API (New) Place Details response.
+///
+/// `types` map to TYPE (see the table above) and, apart from generic ones
+/// such as `point_of_interest`, to PURPOSE. `regularOpeningHours` fills
+/// TEMPORAL with one entry per weekday (`monday: 8:00 AM – 6:00 PM`), and
+/// rating, review count, price level, business status and contact details
+/// go to `extra`.
+pub fn from_place_details(details: &Value) -> GeonPlace {
+    let mut p = GeonPlace::default();
+    let text = |key: &str| details.get(key).and_then(|v| v.as_str());
+
+    p.place = details
+        .pointer("/displayName/text")
+        .and_then(|v| v.as_str())
+        .or_else(|| details.get("displayName").and_then(|v| v.as_str()))
+        .unwrap_or("Unnamed")
+        .to_string();
+
+    let mut types: Vec<&str> = text("primaryType").into_iter().collect();
+    if let Some(all) = details.get("types").and_then(|v| v.as_array()) {
+        types.extend(all.iter().filter_map(|t| t.as_str()).filter(|t| Some(*t) != text("primaryType")));
+    }
+    p.type_ = type_for(&types);
+    p.purpose = types.iter().filter(|t| !GENERIC_TYPES.contains(t)).map(|t| t.replace('_', " ")).collect();
+
+    if let Some(id) = text("id") {
+        p.id = Some(format!("google:{}", id));
+        p.source = vec![format!("Google Places ({})", id)];
+    } else {
+        p.source = vec!["Google Places".to_string()];
+    }
+    p.location = details.get("location").and_then(lat_lng);
+    if let (Some(low), Some(high)) = (
+        details.pointer("/viewport/low").and_then(lat_lng),
+        details.pointer("/viewport/high").and_then(lat_lng),
+    ) {
+        p.extent = Some(Extent { north: high.lat, south: low.lat, east: high.lon, west: low.lon });
+    }
+
+    if let Some(days) = details.pointer("/regularOpeningHours/weekdayDescriptions").and_then(|v| v.as_array()) {
+        for day in days.iter().filter_map(|d| d.as_str()) {
+            if let Some((name, hours)) = day.split_once(": ") {
+                p.temporal.insert(name.trim().to_lowercase(), hours.trim().to_string());
+            }
+        }
+    }
+
+    let extras = [
+        ("rating", "rating"),
+        ("userRatingCount", "user_rating_count"),
+        ("priceLevel", "price_level"),
+        ("businessStatus", "business_status"),
+        ("formattedAddress", "address"),
+        ("websiteUri", "website"),
+        ("internationalPhoneNumber", "phone"),
+        ("googleMapsUri", "google_maps_uri"),
+    ];
+    for (key, extra_key) in extras {
+        if let Some(v) = details.get(key).filter(|v| !v.is_null()) {
+            p.extra.insert(extra_key.to_string(), v.clone());
+        }
+    }
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_place_details() {
+        let details = json!({
+            "id": "ChIJ2Qw1Yd3BeUgR",
+            "displayName": {"text": "Nottingham Castle", "languageCode": "en"},
+            "primaryType": "tourist_attraction",
+            "types": ["tourist_attraction", "museum", "point_of_interest", "establishment"],
+            "location": {"latitude": 52.9497, "longitude": -1.1543},
+            "viewport": {"low": {"latitude": 52.948, "longitude": -1.156}, "high": {"latitude": 52.951, "longitude": -1.152}},
+            "rating": 4.3,
+            "userRatingCount": 9876,
+            "regularOpeningHours": {
+                "openNow": true,
+                "weekdayDescriptions": ["Monday: Closed", "Tuesday: 10:00 AM – 5:00 PM"]
+            },
+            "websiteUri": "https://www.nottinghamcastle.org.uk/"
+        });
+        let place = from_place_details(&details);
+        assert_eq!(place.place, "Nottingham Castle");
+        assert_eq!(place.type_, "landmark");
+        assert_eq!(place.purpose, vec!["tourist attraction", "museum"]);
+        assert_eq!(place.id.as_deref(), Some("google:ChIJ2Qw1Yd3BeUgR"));
+        assert_eq!(place.location, Some(Coordinate::new(52.9497, -1.1543)));
+        assert_eq!(place.temporal["monday"], "Closed");
+        assert_eq!(place.temporal["tuesday"], "10:00 AM – 5:00 PM");
+        assert_eq!(place.extra["rating"], 4.3);
+        assert_eq!(place.extra["user_rating_count"], 9876);
+        assert_eq!(place.extent.as_ref().unwrap().north, 52.951);
+    }
+}
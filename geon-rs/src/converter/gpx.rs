@@ -0,0 +1,52 @@
+use super::{describe_place, xml_escape};
+use crate::models::GeonPlace;
+use std::fmt::Write;
+
+// Leading number of an ELEVATION value in metres ("142m above sea level", "30 ft")
+fn elevation_metres(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(text.len());
+    let value: f64 = text[..end].parse().ok()?;
+    let unit = text[end..].trim_start();
+    if unit.starts_with("ft") || unit.starts_with("feet") {
+        Some(value * 0.3048)
+    } else {
+        Some(value)
+    }
+}
+
+fn write_waypoint(buf: &mut String, place: &GeonPlace) {
+    if let Some(loc) = &place.location {
+        writeln!(buf, "  <wpt lat=\"{}\" lon=\"{}\">", loc.lat, loc.lon).unwrap();
+        if let Some(ele) = place.elevation.as_deref().and_then(elevation_metres) {
+            writeln!(buf, "    <ele>{}</ele>", ele).unwrap();
+        }
+        writeln!(buf, "    <name>{}</name>", xml_escape(&place.place)).unwrap();
+        let description = describe_place(place);
+        if !description.is_empty() {
+            writeln!(buf, "    <desc>{}</desc>", xml_escape(&description)).unwrap();
+        }
+        if !place.type_.is_empty() {
+            writeln!(buf, "    <type>{}</type>", xml_escape(&place.type_)).unwrap();
+        }
+        writeln!(buf, "  </wpt>").unwrap();
+    }
+    for child in &place.contains {
+        write_waypoint(buf, child);
+    }
+}
+
+/// Export place locations, including nested places, as GPX 1.1 waypoints.
+/// Places without a LOCATION are skipped.
+pub fn to_gpx(places: &[GeonPlace]) -> String {
+    let mut buf = String::new();
+    writeln!(buf, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(buf, "<gpx version=\"1.1\" creator=\"geon-rs\" xmlns=\"http://www.topografix.com/GPX/1/1\">").unwrap();
+    for place in places {
+        write_waypoint(&mut buf, place);
+    }
+    writeln!(buf, "</gpx>").unwrap();
+    buf
+}
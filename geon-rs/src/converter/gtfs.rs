@@ -0,0 +1,314 @@
+use super::csv::read_records;
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Contents of the GTFS files used for conversion. Only `stops` is
+/// required; routes, trips and stop_times together give CONNECTIVITY, and
+/// calendar adds service days and dates to TEMPORAL.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GtfsFeed<'a> {
+    pub stops: &'a str,
+    pub routes: Option<&'a str>,
+    pub trips: Option<&'a str>,
+    pub stop_times: Option<&'a str>,
+    pub calendar: Option<&'a str>,
+}
+
+type Row = HashMap<String, String>;
+
+fn table(text: &str) -> Vec<Row> {
+    let mut records = read_records(text).into_iter();
+    let Some(header) = records.next() else { return vec![] };
+    let header: Vec<String> = header.iter().map(|h| h.trim_start_matches('\u{feff}').trim().to_string()).collect();
+    records
+        .map(|r| header.iter().cloned().zip(r.into_iter().map(|v| v.trim().to_string())).collect())
+        .collect()
+}
+
+fn field<'a>(row: &'a Row, key: &str) -> &'a str {
+    row.get(key).map(String::as_str).unwrap_or("")
+}
+
+// Basic and extended (HVT) route types
+fn route_mode(route_type: &str) -> &'static str {
+    match route_type.parse::<u32>().unwrap_or(3) {
+        0 | 900..=999 => "tram",
+        1 | 400..=499 => "metro",
+        2 | 100..=199 => "rail",
+        4 | 1000..=1099 | 1200..=1299 => "ferry",
+        5 | 6 | 7 | 1300..=1499 => "cable",
+        11 | 800..=899 => "trolleybus",
+        12 => "monorail",
+        _ => "bus",
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct StopService {
+    routes: BTreeMap<&'static str, BTreeSet<String>>,
+    services: BTreeSet<String>,
+    first: Option<String>,
+    last: Option<String>,
+}
+
+impl StopService {
+    fn merge(&mut self, other: &StopService) {
+        for (mode, names) in &other.routes {
+            self.routes.entry(mode).or_default().extend(names.iter().cloned());
+        }
+        self.services.extend(other.services.iter().cloned());
+        if let Some(f) = &other.first {
+            if self.first.as_ref().is_none_or(|cur| f < cur) {
+                self.first = Some(f.clone());
+            }
+        }
+        if let Some(l) = &other.last {
+            if self.last.as_ref().is_none_or(|cur| l > cur) {
+                self.last = Some(l.clone());
+            }
+        }
+    }
+}
+
+// GTFS times may exceed 24:00 and omit the leading zero ("5:30:00")
+fn normalise_time(t: &str) -> Option<String> {
+    let mut parts = t.split(':');
+    let h: u32 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    Some(format!("{:02}:{:02}", h, m))
+}
+
+fn service_by_stop(feed: &GtfsFeed) -> HashMap<String, StopService> {
+    let mut by_stop: HashMap<String, StopService> = HashMap::new();
+    let (Some(routes), Some(trips), Some(stop_times)) = (feed.routes, feed.trips, feed.stop_times) else {
+        return by_stop;
+    };
+
+    let routes: HashMap<String, (&'static str, String)> = table(routes)
+        .into_iter()
+        .map(|r| {
+            let name = match field(&r, "route_short_name") {
+                "" => field(&r, "route_long_name").to_string(),
+                short => short.to_string(),
+            };
+            (field(&r, "route_id").to_string(), (route_mode(field(&r, "route_type")), name))
+        })
+        .collect();
+    let trips: HashMap<String, (String, String)> = table(trips)
+        .into_iter()
+        .map(|r| {
+            let ids = (field(&r, "route_id").to_string(), field(&r, "service_id").to_string());
+            (field(&r, "trip_id").to_string(), ids)
+        })
+        .collect();
+
+    for st in table(stop_times) {
+        let Some((route_id, service_id)) = trips.get(field(&st, "trip_id")) else { continue };
+        let entry = by_stop.entry(field(&st, "stop_id").to_string()).or_default();
+        if let Some((mode, name)) = routes.get(route_id) {
+            entry.routes.entry(mode).or_default().insert(name.clone());
+        }
+        entry.services.insert(service_id.clone());
+
+        let time = match field(&st, "departure_time") {
+            "" => field(&st, "arrival_time"),
+            t => t,
+        };
+        if let Some(t) = normalise_time(time) {
+            let single = StopService { first: Some(t.clone()), last: Some(t), ..Default::default() };
+            entry.merge(&single);
+        }
+    }
+    by_stop
+}
+
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("monday", "Mon"),
+    ("tuesday", "Tue"),
+    ("wednesday", "Wed"),
+    ("thursday", "Thu"),
+    ("friday", "Fri"),
+    ("saturday", "Sat"),
+    ("sunday", "Sun"),
+];
+
+// "20240101" -> "2024-01-01"
+fn gtfs_date(d: &str) -> String {
+    if d.len() == 8 { format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..]) } else { d.to_string() }
+}
+
+// Collapse runs of consecutive days: [Mon..Fri, Sun] -> "Mon-Fri, Sun"
+fn day_ranges(days: &[bool; 7]) -> String {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < 7 {
+        if !days[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < 7 && days[i + 1] {
+            i += 1;
+        }
+        out.push(match i - start {
+            0 => WEEKDAYS[start].1.to_string(),
+            1 => format!("{}, {}", WEEKDAYS[start].1, WEEKDAYS[i].1),
+            _ => format!("{}-{}", WEEKDAYS[start].1, WEEKDAYS[i].1),
+        });
+        i += 1;
+    }
+    out.join(", ")
+}
+
+fn apply_service(p: &mut GeonPlace, service: &StopService, calendar: &HashMap<String, Row>) {
+    for (mode, names) in &service.routes {
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        p.connectivity.insert(mode.to_string(), names.join(", "));
+    }
+    if let (Some(first), Some(last)) = (&service.first, &service.last) {
+        p.temporal.insert("service_hours".to_string(), format!("{}-{}", first, last));
+    }
+
+    let rows: Vec<&Row> = service.services.iter().filter_map(|s| calendar.get(s)).collect();
+    if rows.is_empty() {
+        return;
+    }
+    let mut days = [false; 7];
+    for row in &rows {
+        for (i, (key, _)) in WEEKDAYS.iter().enumerate() {
+            days[i] |= field(row, key) == "1";
+        }
+    }
+    if days.iter().any(|d| *d) {
+        p.temporal.insert("service_days".to_string(), day_ranges(&days));
+    }
+    let start = rows.iter().map(|r| field(r, "start_date")).filter(|d| !d.is_empty()).min();
+    let end = rows.iter().map(|r| field(r, "end_date")).filter(|d| !d.is_empty()).max();
+    if let (Some(start), Some(end)) = (start, end) {
+        p.temporal.insert("service_period".to_string(), format!("{} to {}", gtfs_date(start), gtfs_date(end)));
+    }
+}
+
+/// Convert a GTFS feed's stops into `transport_hub` places.
+///
+/// Stations (`location_type` 1) contain their platforms and entrances as
+/// CONTAINS children, and aggregate their children's service. CONNECTIVITY
+/// lists serving routes by mode (`tram: 1, 2`); TEMPORAL records the first
+/// and last departure, service days and calendar period.
+pub fn from_gtfs(feed: &GtfsFeed) -> Result<Vec<GeonPlace>, GeonError> {
+    let service = service_by_stop(feed);
+    let calendar: HashMap<String, Row> = feed
+        .calendar
+        .map(table)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (field(&r, "service_id").to_string(), r))
+        .collect();
+
+    let stops = table(feed.stops);
+    let mut places: Vec<(String, String, GeonPlace, StopService)> = Vec::new();
+    for row in &stops {
+        let location_type = field(row, "location_type");
+        // Generic nodes and boarding areas are not places in their own right
+        if matches!(location_type, "3" | "4") {
+            continue;
+        }
+        let stop_id = field(row, "stop_id").to_string();
+        let mut p = GeonPlace::default();
+        p.place = match field(row, "stop_name") {
+            "" => "Unnamed".to_string(),
+            name => name.to_string(),
+        };
+        p.type_ = if location_type == "2" { "threshold" } else { "transport_hub" }.to_string();
+        p.id = Some(format!("gtfs:{}", stop_id));
+        let (lat, lon) = (field(row, "stop_lat"), field(row, "stop_lon"));
+        if !lat.is_empty() && !lon.is_empty() {
+            p.location = Some(Coordinate::new(lat.parse()?, lon.parse()?));
+        }
+        for key in ["stop_code", "wheelchair_boarding", "platform_code", "zone_id"] {
+            if !field(row, key).is_empty() {
+                p.extra.insert(key.to_string(), Value::String(field(row, key).to_string()));
+            }
+        }
+        p.source = vec!["GTFS".to_string()];
+        let own = service.get(&stop_id).cloned().unwrap_or_default();
+        places.push((stop_id, field(row, "parent_station").to_string(), p, own));
+    }
+
+    // Roll children's service up into their station before nesting them
+    let index: HashMap<String, usize> = places.iter().enumerate().map(|(i, (id, ..))| (id.clone(), i)).collect();
+    let mut rolled: Vec<StopService> = places.iter().map(|_| StopService::default()).collect();
+    for (i, (_, parent, _, own)) in places.iter().enumerate() {
+        rolled[i].merge(own);
+        if let Some(&pi) = index.get(parent) {
+            rolled[pi].merge(own);
+        }
+    }
+    for (i, (.., p, _)) in places.iter_mut().enumerate() {
+        apply_service(p, &rolled[i], &calendar);
+    }
+
+    let mut children: HashMap<usize, Vec<GeonPlace>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, (_, parent, p, _)) in places.iter().enumerate() {
+        match index.get(parent) {
+            Some(&pi) if pi != i => children.entry(pi).or_default().push(p.clone()),
+            _ => roots.push(i),
+        }
+    }
+    Ok(roots
+        .into_iter()
+        .map(|i| {
+            let mut p = places[i].2.clone();
+            p.contains = children.remove(&i).unwrap_or_default();
+            for child in &mut p.contains {
+                child.part_of = Some(p.place.clone());
+            }
+            p
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gtfs_stops_and_service() {
+        let feed = GtfsFeed {
+            stops: "\u{feff}stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+                    OMS,Old Market Square,52.9533,-1.1505,1,\n\
+                    OMS1,Old Market Square (Platform 1),52.9534,-1.1506,0,OMS\n\
+                    OMS2,Old Market Square (Platform 2),52.9532,-1.1504,0,OMS\n\
+                    LB,Lace Market,52.9530,-1.1440,,\n",
+            routes: Some("route_id,route_short_name,route_type\nT1,1,0\nT2,2,0\nB58,58,3\n"),
+            trips: Some("route_id,service_id,trip_id\nT1,WK,t1\nT2,WE,t2\nB58,WK,b1\n"),
+            stop_times: Some(
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 t1,05:30:00,05:31:00,OMS1,1\nt2,24:10:00,24:10:00,OMS2,1\nb1,7:00:00,7:00:00,LB,1\n",
+            ),
+            calendar: Some(
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 WK,1,1,1,1,1,0,0,20240101,20241231\nWE,0,0,0,0,0,1,1,20240106,20241229\n",
+            ),
+        };
+        let places = from_gtfs(&feed).unwrap();
+        assert_eq!(places.len(), 2);
+
+        let station = &places[0];
+        assert_eq!(station.type_, "transport_hub");
+        assert_eq!(station.contains.len(), 2);
+        assert_eq!(station.connectivity["tram"], "1, 2");
+        assert_eq!(station.temporal["service_hours"], "05:31-24:10");
+        assert_eq!(station.temporal["service_days"], "Mon-Sun");
+        assert_eq!(station.temporal["service_period"], "2024-01-01 to 2024-12-31");
+        assert_eq!(station.contains[0].connectivity["tram"], "1");
+        assert_eq!(station.contains[0].temporal["service_days"], "Mon-Fri");
+        assert_eq!(station.contains[0].part_of.as_deref(), Some("Old Market Square"));
+
+        assert_eq!(places[1].connectivity["bus"], "58");
+        assert_eq!(places[1].temporal["service_hours"], "07:00-07:00");
+    }
+}
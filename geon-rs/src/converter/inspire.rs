@@ -0,0 +1,381 @@
+use super::xml::{local_name, XmlEvent, XmlReader};
+use crate::geometry::{centroid, ring_area};
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Just enough of a DOM to navigate INSPIRE feature members
+#[derive(Debug, Default)]
+struct Elem {
+    name: String,
+    attrs: Vec<(String, String)>,
+    text: String,
+    children: Vec<Elem>,
+}
+
+impl Elem {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == key || local_name(k) == key).map(|(_, v)| v.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Elem> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Elem> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    // Depth-first search for descendants with this local name
+    fn descendants<'a>(&'a self, name: &str, out: &mut Vec<&'a Elem>) {
+        for c in &self.children {
+            if c.name == name {
+                out.push(c);
+            } else {
+                c.descendants(name, out);
+            }
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&Elem> {
+        let mut found = Vec::new();
+        self.descendants(name, &mut found);
+        found.into_iter().next()
+    }
+
+    // gml:id of the parent unit, for same-document `#id` links
+    fn upper_level(&self) -> Option<&str> {
+        self.child("upperLevelUnit").and_then(|l| l.attr("href")).map(|h| h.trim_start_matches('#'))
+    }
+
+    // Last path segment of an xlink:href code list value
+    fn code(&self) -> Option<String> {
+        let href = self.attr("href").filter(|h| !h.is_empty());
+        let value = href.map(|h| h.rsplit(['/', '#']).next().unwrap_or(h)).unwrap_or(self.text.as_str());
+        Some(value.trim().to_string()).filter(|s| !s.is_empty())
+    }
+}
+
+fn read_tree(text: &str) -> Result<Elem, GeonError> {
+    let mut stack = vec![Elem::default()];
+    for event in XmlReader::new(text) {
+        match event? {
+            XmlEvent::Start { name, attrs, self_closing } => {
+                let elem = Elem {
+                    name: local_name(name).to_string(),
+                    attrs: attrs.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+                    ..Default::default()
+                };
+                if self_closing {
+                    stack.last_mut().unwrap().children.push(elem);
+                } else {
+                    stack.push(elem);
+                }
+            }
+            XmlEvent::End { .. } => {
+                if stack.len() > 1 {
+                    let elem = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(elem);
+                }
+            }
+            XmlEvent::Text(t) => stack.last_mut().unwrap().text.push_str(&t),
+        }
+    }
+    if stack.len() != 1 {
+        return Err(GeonError::InvalidStructure("GML: unclosed element".to_string()));
+    }
+    Ok(stack.pop().unwrap())
+}
+
+/// Axis handling for a GML `srsName`.
+#[derive(Clone, Copy)]
+enum Axes<'a> {
+    // EPSG:4258/4326 as URN or http URI: latitude first
+    LatLon,
+    // Legacy "EPSG:4326" strings: longitude first
+    LonLat,
+    Projected(&'a dyn Fn(f64, f64) -> Coordinate),
+    Unknown,
+}
+
+fn axes<'a>(srs: &str, reproject: Option<&'a dyn Fn(f64, f64) -> Coordinate>) -> Axes<'a> {
+    let code = srs.rsplit(['/', ':', '#']).find(|s| !s.is_empty()).unwrap_or_default();
+    let geographic = matches!(code, "4258" | "4326" | "4937" | "4979");
+    match (geographic, reproject) {
+        (true, _) if srs.to_ascii_uppercase().starts_with("EPSG:") => Axes::LonLat,
+        (true, _) => Axes::LatLon,
+        (false, Some(f)) => Axes::Projected(f),
+        (false, None) => Axes::Unknown,
+    }
+}
+
+// First srsName on the geometry or any of its parts
+fn srs_name(elem: &Elem) -> Option<&str> {
+    elem.attr("srsName").or_else(|| elem.children.iter().find_map(srs_name))
+}
+
+fn to_coordinate(c: &[f64], axes: Axes) -> Option<Coordinate> {
+    match axes {
+        Axes::LatLon => Some(Coordinate::new(c[0], c[1])),
+        Axes::LonLat => Some(Coordinate::new(c[1], c[0])),
+        Axes::Projected(f) => Some(f(c[0], c[1])),
+        Axes::Unknown => None,
+    }
+}
+
+// Every polygon exterior ring, from posList or pos in `srsDimension` tuples
+fn exterior_rings(geometry: &Elem, axes: Axes) -> Vec<Vec<Coordinate>> {
+    let mut exteriors = Vec::new();
+    geometry.descendants("exterior", &mut exteriors);
+    let mut rings = Vec::new();
+    for exterior in exteriors {
+        let (values, dim) = match exterior.find("posList") {
+            Some(list) => (list.text.clone(), list.attr("srsDimension").and_then(|d| d.parse().ok()).unwrap_or(2)),
+            None => {
+                let mut pos = Vec::new();
+                exterior.descendants("pos", &mut pos);
+                let dim = pos.first().map(|p| p.text.split_whitespace().count()).unwrap_or(2);
+                (pos.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join(" "), dim)
+            }
+        };
+        let numbers: Vec<f64> = values.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        let ring: Vec<Coordinate> = numbers.chunks_exact(dim.max(2)).filter_map(|c| to_coordinate(c, axes)).collect();
+        if !ring.is_empty() {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+fn set_geometry(p: &mut GeonPlace, feature: &Elem, reproject: Option<&dyn Fn(f64, f64) -> Coordinate>) {
+    let Some(geometry) = feature.child("geometry") else { return };
+    let axes = axes(srs_name(geometry).unwrap_or_default(), reproject);
+    let rings = exterior_rings(geometry, axes);
+    if let Some(ring) = rings.into_iter().max_by(|a, b| ring_area(a).total_cmp(&ring_area(b))) {
+        p.location = centroid(&ring);
+        p.boundary = ring;
+    } else if let Some(pos) = geometry.find("pos") {
+        let c: Vec<f64> = pos.text.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        if c.len() >= 2 {
+            p.location = to_coordinate(&c, axes);
+        }
+    }
+}
+
+fn geographical_name(elem: Option<&Elem>) -> Option<String> {
+    let text = elem?.find("text")?.text.trim().to_string();
+    Some(text).filter(|s| !s.is_empty())
+}
+
+fn inspire_id(feature: &Elem) -> Option<String> {
+    let identifier = feature.child("inspireId").or(feature.child("inspireID"))?;
+    let local = identifier.find("localId")?.text.trim().to_string();
+    match identifier.find("namespace").map(|n| n.text.trim()) {
+        Some(ns) if !ns.is_empty() => Some(format!("inspire:{}:{}", ns, local)),
+        _ => Some(format!("inspire:{}", local)),
+    }
+}
+
+fn text_of(feature: &Elem, name: &str) -> Option<String> {
+    let t = feature.child(name)?.text.trim().to_string();
+    Some(t).filter(|s| !s.is_empty())
+}
+
+// "natureConservation" -> "nature conservation"
+fn words(camel: &str) -> String {
+    let mut out = String::new();
+    for ch in camel.chars() {
+        if ch.is_uppercase() && !out.is_empty() {
+            out.push(' ');
+        }
+        out.extend(ch.to_lowercase());
+    }
+    out
+}
+
+fn protected_site(feature: &Elem, reproject: Option<&dyn Fn(f64, f64) -> Coordinate>) -> GeonPlace {
+    let mut p = GeonPlace::default();
+    p.place = geographical_name(feature.child("siteName")).unwrap_or_else(|| "Unnamed".to_string());
+    p.id = inspire_id(feature);
+    set_geometry(&mut p, feature, reproject);
+
+    let classes: Vec<String> = feature.children_named("siteProtectionClassification").filter_map(Elem::code).collect();
+    p.type_ = if classes.iter().any(|c| c == "cultural" || c == "archaeological") {
+        "landmark"
+    } else {
+        "natural_feature"
+    }
+    .to_string();
+    p.purpose = classes.iter().map(|c| words(c)).collect();
+
+    if let Some(designation) = feature.child("siteDesignation") {
+        if let Some(d) = designation.find("designation").and_then(Elem::code) {
+            p.extra.insert("designation".to_string(), Value::String(d));
+        }
+        if let Some(s) = designation.find("designationScheme").and_then(Elem::code) {
+            p.extra.insert("designation_scheme".to_string(), Value::String(s));
+        }
+    }
+    if let Some(date) = text_of(feature, "legalFoundationDate") {
+        p.lifespan.insert("established".to_string(), date.split('T').next().unwrap_or_default().to_string());
+    }
+    p.source = vec!["INSPIRE Protected Sites".to_string()];
+    p
+}
+
+fn admin_unit(feature: &Elem, reproject: Option<&dyn Fn(f64, f64) -> Coordinate>) -> GeonPlace {
+    let mut p = GeonPlace::default();
+    p.type_ = "district".to_string();
+    p.place = geographical_name(feature.child("name")).unwrap_or_else(|| "Unnamed".to_string());
+    p.id = inspire_id(feature);
+    set_geometry(&mut p, feature, reproject);
+
+    if let Some(code) = text_of(feature, "nationalCode") {
+        p.extra.insert("national_code".to_string(), Value::String(code));
+    }
+    if let Some(level) = feature.child("nationalLevel").and_then(Elem::code) {
+        p.extra.insert("national_level".to_string(), Value::String(level));
+    }
+    if let Some(name) = feature.child("nationalLevelName").and_then(|n| n.find("LocalisedCharacterString")) {
+        p.extra.insert("national_level_name".to_string(), Value::String(name.text.trim().to_string()));
+    }
+    if let Some(country) = feature.child("country").and_then(|c| c.find("CountryCode")) {
+        let code = country.attr("codeListValue").map(str::to_string).unwrap_or_else(|| country.text.trim().to_string());
+        p.extra.insert("country".to_string(), Value::String(code));
+    }
+    p.updated = text_of(feature, "beginLifespanVersion");
+    p.source = vec!["INSPIRE Administrative Units".to_string()];
+    p
+}
+
+fn convert(text: &str, reproject: Option<&dyn Fn(f64, f64) -> Coordinate>) -> Result<Vec<GeonPlace>, GeonError> {
+    let root = read_tree(text)?;
+    let mut sites = Vec::new();
+    root.descendants("ProtectedSite", &mut sites);
+    let mut units = Vec::new();
+    root.descendants("AdministrativeUnit", &mut units);
+
+    let mut places: Vec<GeonPlace> = sites.iter().map(|f| protected_site(f, reproject)).collect();
+
+    // Resolve upperLevelUnit links within the document into PART_OF chains
+    let names: HashMap<&str, (&Elem, String)> = units
+        .iter()
+        .filter_map(|u| Some((u.attr("id")?, (*u, geographical_name(u.child("name"))?))))
+        .collect();
+    for unit in &units {
+        let mut p = admin_unit(unit, reproject);
+        let mut chain = Vec::new();
+        let mut next = unit.upper_level();
+        while let Some((parent, name)) = next.and_then(|id| names.get(id)) {
+            if chain.len() > names.len() {
+                break;
+            }
+            chain.push(name.clone());
+            next = parent.upper_level();
+        }
+        if chain.is_empty() {
+            if let Some(href) = unit.upper_level() {
+                p.extra.insert("upper_level_unit".to_string(), Value::String(href.to_string()));
+            }
+        }
+        p.part_of = Some(chain.join(", ")).filter(|s| !s.is_empty());
+        places.push(p);
+    }
+    Ok(places)
+}
+
+/// Read INSPIRE Protected Sites (`ps:ProtectedSite`) and Administrative
+/// Units (`au:AdministrativeUnit`) from a GML document or WFS response.
+///
+/// Protected sites are typed `natural_feature` (or `landmark` when their
+/// protection is cultural or archaeological), with the protection
+/// classification as PURPOSE, the designation in `extra` and the legal
+/// foundation date as `LIFESPAN.established`. Administrative units are
+/// `district`s whose `upperLevelUnit` links become PART_OF chains.
+///
+/// Polygons take the exterior ring of their largest surface. Coordinates are
+/// only used in geographic reference systems (EPSG:4258, 4326, ...); for
+/// projected ones use [`from_inspire_gml_with`].
+pub fn from_inspire_gml(text: &str) -> Result<Vec<GeonPlace>, GeonError> {
+    convert(text, None)
+}
+
+/// As [`from_inspire_gml`], mapping projected `(x, y)` positions to WGS84
+/// with `reproject`.
+pub fn from_inspire_gml_with(text: &str, reproject: &dyn Fn(f64, f64) -> Coordinate) -> Result<Vec<GeonPlace>, GeonError> {
+    convert(text, Some(reproject))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GML: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:FeatureCollection xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:gml="http://www.opengis.net/gml/3.2"
+    xmlns:ps="http://inspire.ec.europa.eu/schemas/ps/4.0" xmlns:au="http://inspire.ec.europa.eu/schemas/au/4.0"
+    xmlns:xlink="http://www.w3.org/1999/xlink">
+  <wfs:member>
+    <ps:ProtectedSite gml:id="PS.1">
+      <ps:geometry>
+        <gml:MultiSurface srsName="http://www.opengis.net/def/crs/EPSG/0/4258">
+          <gml:surfaceMember><gml:Polygon><gml:exterior><gml:LinearRing>
+            <gml:posList srsDimension="2">53.30 -1.80 53.30 -1.70 53.40 -1.70 53.30 -1.80</gml:posList>
+          </gml:LinearRing></gml:exterior></gml:Polygon></gml:surfaceMember>
+        </gml:MultiSurface>
+      </ps:geometry>
+      <ps:inspireID><base:Identifier><base:localId>UK0030269</base:localId><base:namespace>UK.JNCC</base:namespace></base:Identifier></ps:inspireID>
+      <ps:legalFoundationDate>2005-04-01T00:00:00</ps:legalFoundationDate>
+      <ps:siteDesignation><ps:DesignationType>
+        <ps:designationScheme xlink:href="http://inspire.ec.europa.eu/codelist/DesignationSchemeValue/natura2000"/>
+        <ps:designation xlink:href="http://inspire.ec.europa.eu/codelist/Natura2000DesignationValue/specialAreaOfConservation"/>
+      </ps:DesignationType></ps:siteDesignation>
+      <ps:siteName><gn:GeographicalName><gn:spelling><gn:SpellingOfName><gn:text>Peak District Dales</gn:text></gn:SpellingOfName></gn:spelling></gn:GeographicalName></ps:siteName>
+      <ps:siteProtectionClassification>natureConservation</ps:siteProtectionClassification>
+    </ps:ProtectedSite>
+  </wfs:member>
+  <wfs:member>
+    <au:AdministrativeUnit gml:id="AU.ENG">
+      <au:name><gn:GeographicalName><gn:spelling><gn:SpellingOfName><gn:text>England</gn:text></gn:SpellingOfName></gn:spelling></gn:GeographicalName></au:name>
+    </au:AdministrativeUnit>
+  </wfs:member>
+  <wfs:member>
+    <au:AdministrativeUnit gml:id="AU.NOT">
+      <au:geometry><gml:Point srsName="EPSG:4326"><gml:pos>-1.15 52.95</gml:pos></gml:Point></au:geometry>
+      <au:nationalCode>E06000018</au:nationalCode>
+      <au:nationalLevel xlink:href="http://inspire.ec.europa.eu/codelist/AdministrativeHierarchyLevel/3rdOrder"/>
+      <au:name><gn:GeographicalName><gn:spelling><gn:SpellingOfName><gn:text>Nottingham</gn:text></gn:SpellingOfName></gn:spelling></gn:GeographicalName></au:name>
+      <au:upperLevelUnit xlink:href="#AU.ENG"/>
+    </au:AdministrativeUnit>
+  </wfs:member>
+</wfs:FeatureCollection>"##;
+
+    #[test]
+    fn test_inspire_gml() {
+        let places = from_inspire_gml(GML).unwrap();
+        assert_eq!(places.len(), 3);
+
+        let site = &places[0];
+        assert_eq!(site.place, "Peak District Dales");
+        assert_eq!(site.type_, "natural_feature");
+        assert_eq!(site.id.as_deref(), Some("inspire:UK.JNCC:UK0030269"));
+        assert_eq!(site.boundary[1], Coordinate::new(53.30, -1.70));
+        assert_eq!(site.purpose, vec!["nature conservation"]);
+        assert_eq!(site.extra["designation"], "specialAreaOfConservation");
+        assert_eq!(site.lifespan["established"], "2005-04-01");
+
+        let unit = &places[2];
+        assert_eq!(unit.type_, "district");
+        assert_eq!(unit.location, Some(Coordinate::new(52.95, -1.15)));
+        assert_eq!(unit.part_of.as_deref(), Some("England"));
+        assert_eq!(unit.extra["national_level"], "3rdOrder");
+    }
+
+    #[test]
+    fn test_projected_needs_reprojection() {
+        let gml = GML.replace("EPSG/0/4258", "EPSG/0/27700");
+        assert!(from_inspire_gml(&gml).unwrap()[0].boundary.is_empty());
+        let places = from_inspire_gml_with(&gml, &|x, y| Coordinate::new(y, x)).unwrap();
+        assert_eq!(places[0].boundary[0], Coordinate::new(-1.80, 53.30));
+    }
+}
@@ -0,0 +1,53 @@
+use super::describe_place;
+use crate::models::GeonPlace;
+use serde_json::{json, Map, Value};
+
+// schema.org's space-separated "lat lon" points, closing the ring
+fn polygon(place: &GeonPlace) -> String {
+    let closing = place.boundary.first().filter(|_| place.boundary.first() != place.boundary.last());
+    let points: Vec<String> = place.boundary.iter().chain(closing).map(|c| format!("{} {}", c.lat, c.lon)).collect();
+    points.join(" ")
+}
+
+fn place_node(place: &GeonPlace) -> Value {
+    let mut node = Map::new();
+    node.insert("@type".to_string(), json!("Place"));
+    if let Some(id) = &place.id {
+        node.insert("@id".to_string(), json!(id));
+    }
+    node.insert("name".to_string(), json!(place.place));
+    if !place.type_.is_empty() {
+        node.insert("additionalType".to_string(), json!(place.type_));
+    }
+    let description = describe_place(place);
+    if !description.is_empty() {
+        node.insert("description".to_string(), json!(description));
+    }
+    if let Some(loc) = &place.location {
+        node.insert("geo".to_string(), json!({"@type": "GeoCoordinates", "latitude": loc.lat, "longitude": loc.lon}));
+    } else if place.boundary.len() >= 3 {
+        node.insert("geo".to_string(), json!({"@type": "GeoShape", "polygon": polygon(place)}));
+    }
+    if let Some(parent) = &place.part_of {
+        node.insert("containedInPlace".to_string(), json!({"@type": "Place", "name": parent}));
+    }
+    if !place.contains.is_empty() {
+        node.insert("containsPlace".to_string(), place.contains.iter().map(place_node).collect());
+    }
+    let links: Vec<&String> = place.source.iter().filter(|s| s.starts_with("http://") || s.starts_with("https://")).collect();
+    if !links.is_empty() {
+        node.insert("sameAs".to_string(), json!(links));
+    }
+    Value::Object(node)
+}
+
+/// Export places as a schema.org JSON-LD graph for embedding in web pages.
+/// Each place is a `Place` with TYPE as its `additionalType`, LOCATION (or
+/// else BOUNDARY) as `geo`, CONTAINS children as `containsPlace` and URL
+/// SOURCE entries as `sameAs`.
+pub fn to_jsonld(places: &[GeonPlace]) -> Value {
+    json!({
+        "@context": "https://schema.org",
+        "@graph": places.iter().map(place_node).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,81 @@
+use super::{describe_place, xml_escape};
+use crate::models::GeonPlace;
+use std::fmt::Write;
+
+fn write_indent(buf: &mut String, depth: usize) {
+    for _ in 0..depth {
+        buf.push_str("  ");
+    }
+}
+
+fn write_geometry(buf: &mut String, place: &GeonPlace, depth: usize) {
+    if place.boundary.len() >= 3 {
+        let mut coords: Vec<String> = place.boundary.iter().map(|c| format!("{},{}", c.lon, c.lat)).collect();
+        // KML rings must be closed
+        if place.boundary.first() != place.boundary.last() {
+            coords.push(coords[0].clone());
+        }
+        write_indent(buf, depth);
+        writeln!(
+            buf,
+            "<Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon>",
+            coords.join(" ")
+        )
+        .unwrap();
+    } else if let Some(loc) = &place.location {
+        write_indent(buf, depth);
+        writeln!(buf, "<Point><coordinates>{},{}</coordinates></Point>", loc.lon, loc.lat).unwrap();
+    }
+}
+
+fn write_placemark(buf: &mut String, place: &GeonPlace, depth: usize) {
+    write_indent(buf, depth);
+    match &place.id {
+        Some(id) => writeln!(buf, "<Placemark id=\"{}\">", xml_escape(id)).unwrap(),
+        None => writeln!(buf, "<Placemark>").unwrap(),
+    }
+    write_indent(buf, depth + 1);
+    writeln!(buf, "<name>{}</name>", xml_escape(&place.place)).unwrap();
+    let description = describe_place(place);
+    if !description.is_empty() {
+        write_indent(buf, depth + 1);
+        writeln!(buf, "<description>{}</description>", xml_escape(&description)).unwrap();
+    }
+    write_geometry(buf, place, depth + 1);
+    write_indent(buf, depth);
+    writeln!(buf, "</Placemark>").unwrap();
+}
+
+// Places with children become a Folder holding their own Placemark first
+fn write_place(buf: &mut String, place: &GeonPlace, depth: usize) {
+    if place.contains.is_empty() {
+        write_placemark(buf, place, depth);
+        return;
+    }
+    write_indent(buf, depth);
+    writeln!(buf, "<Folder>").unwrap();
+    write_indent(buf, depth + 1);
+    writeln!(buf, "<name>{}</name>", xml_escape(&place.place)).unwrap();
+    write_placemark(buf, place, depth + 1);
+    for child in &place.contains {
+        write_place(buf, child, depth + 1);
+    }
+    write_indent(buf, depth);
+    writeln!(buf, "</Folder>").unwrap();
+}
+
+/// Export places as a KML document. Each place becomes a Placemark whose
+/// description summarises its semantic sections; places with CONTAINS
+/// children are wrapped in a Folder alongside their children.
+pub fn to_kml(places: &[GeonPlace]) -> String {
+    let mut buf = String::new();
+    writeln!(buf, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(buf, "<kml xmlns=\"http://www.opengis.net/kml/2.2\">").unwrap();
+    writeln!(buf, "<Document>").unwrap();
+    for place in places {
+        write_place(&mut buf, place, 1);
+    }
+    writeln!(buf, "</Document>").unwrap();
+    writeln!(buf, "</kml>").unwrap();
+    buf
+}
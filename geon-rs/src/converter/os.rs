@@ -0,0 +1,349 @@
+//! Ordnance Survey inputs: OS Open UPRN and OS MasterMap Topography Layer.
+//!
+//! Identifiers are namespaced as `osgb:<TOID digits>` for topographic
+//! features and `osgb:uprn:<UPRN>` for addressable locations, and every
+//! place records the OS product and release it came from in SOURCE.
+//! British National Grid coordinates are converted to WGS84 with
+//! [`bng_to_wgs84`].
+
+use super::csv::read_records;
+use crate::geometry::centroid;
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+
+/// An OS product and release, e.g. `OS Open UPRN` / `2024-10`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsRelease {
+    pub product: String,
+    pub version: String,
+}
+
+impl OsRelease {
+    pub fn new(product: &str, version: &str) -> Self {
+        Self { product: product.to_string(), version: version.to_string() }
+    }
+
+    fn source(&self) -> String {
+        format!("Ordnance Survey {} ({})", self.product, self.version)
+    }
+}
+
+/// `osgb:` ID for a TOID, with or without its `osgb` prefix.
+pub fn toid_id(toid: &str) -> String {
+    format!("osgb:{}", toid.trim().trim_start_matches("osgb"))
+}
+
+/// `osgb:uprn:` ID for a UPRN.
+pub fn uprn_id(uprn: &str) -> String {
+    format!("osgb:uprn:{}", uprn.trim())
+}
+
+// Airy 1830 ellipsoid and the National Grid projection
+const AIRY_A: f64 = 6_377_563.396;
+const AIRY_B: f64 = 6_356_256.909;
+const F0: f64 = 0.999_601_271_7;
+const LAT0: f64 = 49.0;
+const LON0: f64 = -2.0;
+const E0: f64 = 400_000.0;
+const N0: f64 = -100_000.0;
+
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_B: f64 = 6_356_752.314_245;
+
+// OSGB36 -> WGS84 Helmert parameters: metres, ppm, arc-seconds
+const HELMERT_T: [f64; 3] = [446.448, -125.157, 542.060];
+const HELMERT_S: f64 = -20.4894;
+const HELMERT_R: [f64; 3] = [0.1502, 0.2470, 0.8421];
+
+// Inverse transverse Mercator: grid easting/northing -> OSGB36 lat/lon in radians
+fn grid_to_osgb36(easting: f64, northing: f64) -> (f64, f64) {
+    let (a, b) = (AIRY_A, AIRY_B);
+    let e2 = 1.0 - (b * b) / (a * a);
+    let n = (a - b) / (a + b);
+    let (n2, n3) = (n * n, n * n * n);
+    let lat0 = LAT0.to_radians();
+
+    let meridional = |lat: f64| {
+        let (d, s) = (lat - lat0, lat + lat0);
+        b * F0
+            * ((1.0 + n + 1.25 * n2 + 1.25 * n3) * d
+                - (3.0 * n + 3.0 * n2 + 21.0 / 8.0 * n3) * d.sin() * s.cos()
+                + (15.0 / 8.0 * n2 + 15.0 / 8.0 * n3) * (2.0 * d).sin() * (2.0 * s).cos()
+                - 35.0 / 24.0 * n3 * (3.0 * d).sin() * (3.0 * s).cos())
+    };
+
+    let mut lat = lat0;
+    let mut m = 0.0;
+    while (northing - N0 - m).abs() >= 1e-5 {
+        lat += (northing - N0 - m) / (a * F0);
+        m = meridional(lat);
+    }
+
+    let (sin, cos, tan) = (lat.sin(), lat.cos(), lat.tan());
+    let nu = a * F0 / (1.0 - e2 * sin * sin).sqrt();
+    let rho = a * F0 * (1.0 - e2) / (1.0 - e2 * sin * sin).powf(1.5);
+    let eta2 = nu / rho - 1.0;
+    let (t2, t4, t6) = (tan * tan, tan.powi(4), tan.powi(6));
+    let sec = 1.0 / cos;
+
+    let vii = tan / (2.0 * rho * nu);
+    let viii = tan / (24.0 * rho * nu.powi(3)) * (5.0 + 3.0 * t2 + eta2 - 9.0 * t2 * eta2);
+    let ix = tan / (720.0 * rho * nu.powi(5)) * (61.0 + 90.0 * t2 + 45.0 * t4);
+    let x = sec / nu;
+    let xi = sec / (6.0 * nu.powi(3)) * (nu / rho + 2.0 * t2);
+    let xii = sec / (120.0 * nu.powi(5)) * (5.0 + 28.0 * t2 + 24.0 * t4);
+    let xiia = sec / (5040.0 * nu.powi(7)) * (61.0 + 662.0 * t2 + 1320.0 * t4 + 720.0 * t6);
+
+    let de = easting - E0;
+    let lat = lat - vii * de.powi(2) + viii * de.powi(4) - ix * de.powi(6);
+    let lon = LON0.to_radians() + x * de - xi * de.powi(3) + xii * de.powi(5) - xiia * de.powi(7);
+    (lat, lon)
+}
+
+fn to_cartesian(lat: f64, lon: f64, a: f64, b: f64) -> [f64; 3] {
+    let e2 = 1.0 - (b * b) / (a * a);
+    let nu = a / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    [nu * lat.cos() * lon.cos(), nu * lat.cos() * lon.sin(), (1.0 - e2) * nu * lat.sin()]
+}
+
+fn from_cartesian([x, y, z]: [f64; 3], a: f64, b: f64) -> (f64, f64) {
+    let e2 = 1.0 - (b * b) / (a * a);
+    let p = (x * x + y * y).sqrt();
+    let mut lat = z.atan2(p * (1.0 - e2));
+    for _ in 0..10 {
+        let nu = a / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        lat = (z + e2 * nu * lat.sin()).atan2(p);
+    }
+    (lat, y.atan2(x))
+}
+
+/// Convert a British National Grid (EPSG:27700) easting/northing to WGS84.
+///
+/// Uses the OS seven-parameter Helmert transformation, which is accurate
+/// to a few metres; OSTN15 is needed for survey-grade results.
+pub fn bng_to_wgs84(easting: f64, northing: f64) -> Coordinate {
+    let (lat, lon) = grid_to_osgb36(easting, northing);
+    let [x, y, z] = to_cartesian(lat, lon, AIRY_A, AIRY_B);
+    let s = 1.0 + HELMERT_S * 1e-6;
+    let [rx, ry, rz] = HELMERT_R.map(|r| (r / 3600.0).to_radians());
+    let [tx, ty, tz] = HELMERT_T;
+    let wgs = [
+        tx + s * x - rz * y + ry * z,
+        ty + rz * x + s * y - rx * z,
+        tz - ry * x + rx * y + s * z,
+    ];
+    let (lat, lon) = from_cartesian(wgs, WGS84_A, WGS84_B);
+    Coordinate::new(lat.to_degrees(), lon.to_degrees())
+}
+
+// MasterMap descriptive terms, checked before the broader groups
+const TERM_TABLE: &[(&str, &[&str])] = &[
+    ("threshold", &["Bridge", "Archway", "Tunnel", "Footbridge"]),
+    ("infrastructure", &["Track", "Electricity Sub Station", "Pylon", "Level Crossing", "Weir"]),
+    ("natural_feature", &[
+        "Coniferous Trees", "Nonconiferous Trees", "Scrub", "Rough Grassland", "Heath", "Marsh", "Reeds",
+        "Rock", "Scree", "Foreshore", "Watercourse", "Spring",
+    ]),
+    ("landmark", &["Monument", "Cross", "Tower"]),
+];
+
+const GROUP_TABLE: &[(&str, &[&str])] = &[
+    ("building", &["Building", "Glasshouse"]),
+    ("street", &["Road Or Track", "Roadside", "Path"]),
+    ("infrastructure", &["Rail", "Structure"]),
+    ("natural_feature", &["Inland Water", "Tidal Water", "Natural Environment", "Landform"]),
+    ("landmark", &["Historic Interest"]),
+    ("public_space", &["General Surface"]),
+];
+
+fn lookup(table: &[(&str, &[&str])], values: &[&str]) -> Option<String> {
+    values.iter().find_map(|v| table.iter().find(|(_, vs)| vs.contains(v)).map(|(t, _)| t.to_string()))
+}
+
+/// GEON type for a MasterMap feature's descriptive groups and terms.
+pub fn type_for(groups: &[&str], terms: &[&str]) -> String {
+    lookup(TERM_TABLE, terms)
+        .or_else(|| lookup(GROUP_TABLE, groups))
+        .unwrap_or_else(|| "hybrid".to_string())
+}
+
+// AddressBase classification codes by leading letter
+fn classification_type(code: &str) -> &'static str {
+    match code.chars().next() {
+        Some('R' | 'C' | 'X' | 'M' | 'P') => "building",
+        Some('L') => "public_space",
+        Some('Z') => "landmark",
+        Some('O') => "infrastructure",
+        _ => "hybrid",
+    }
+}
+
+/// Read an OS Open UPRN CSV (`UPRN,X_COORDINATE,Y_COORDINATE,LATITUDE,
+/// LONGITUDE`). LATITUDE/LONGITUDE are used when present and the grid
+/// coordinates otherwise. An AddressBase `CLASSIFICATION_CODE` column, if
+/// present, sets TYPE and is kept in `extra`; rows without one are typed
+/// `hybrid`, as a UPRN alone does not say what it identifies.
+pub fn from_open_uprn(text: &str, release: &OsRelease) -> Result<Vec<GeonPlace>, GeonError> {
+    let mut records = read_records(text).into_iter();
+    let Some(header) = records.next() else { return Ok(vec![]) };
+    let col = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let uprn_i = col("UPRN").ok_or_else(|| GeonError::InvalidStructure("OS Open UPRN: no UPRN column".to_string()))?;
+    let [x_i, y_i, lat_i, lon_i, class_i] =
+        ["X_COORDINATE", "Y_COORDINATE", "LATITUDE", "LONGITUDE", "CLASSIFICATION_CODE"].map(col);
+
+    let mut places = Vec::new();
+    for record in records {
+        let cell = |i: Option<usize>| i.and_then(|i| record.get(i)).map(|s| s.trim()).filter(|s| !s.is_empty());
+        let Some(uprn) = cell(Some(uprn_i)) else { continue };
+
+        let mut p = GeonPlace::default();
+        p.place = format!("UPRN {}", uprn);
+        p.id = Some(uprn_id(uprn));
+        p.location = match (cell(lat_i), cell(lon_i), cell(x_i), cell(y_i)) {
+            (Some(lat), Some(lon), _, _) => Some(Coordinate::new(lat.parse()?, lon.parse()?)),
+            (_, _, Some(x), Some(y)) => Some(bng_to_wgs84(x.parse()?, y.parse()?)),
+            _ => None,
+        };
+        p.type_ = cell(class_i).map(classification_type).unwrap_or("hybrid").to_string();
+        if let Some(code) = cell(class_i) {
+            p.extra.insert("classification_code".to_string(), Value::String(code.to_string()));
+        }
+        p.source = vec![release.source()];
+        places.push(p);
+    }
+    Ok(places)
+}
+
+fn strings(v: Option<&Value>) -> Vec<&str> {
+    match v {
+        Some(Value::Array(items)) => items.iter().filter_map(|i| i.as_str()).collect(),
+        Some(Value::String(s)) => s.split('|').map(str::trim).filter(|s| !s.is_empty()).collect(),
+        _ => vec![],
+    }
+}
+
+fn grid_point(v: &Value) -> Option<Coordinate> {
+    let pair = v.as_array()?;
+    Some(bng_to_wgs84(pair.first()?.as_f64()?, pair.get(1)?.as_f64()?))
+}
+
+/// Convert one OS MasterMap Topography Layer GeoJSON Feature with British
+/// National Grid coordinates.
+///
+/// `descriptiveGroup` and `descriptiveTerm` set TYPE (see [`type_for`]) and
+/// CHARACTER, `calculatedAreaValue` sets AREA, `versionDate` sets UPDATED,
+/// and the feature's own `version` goes to `extra["os_version"]`.
+pub fn from_mastermap_feature(feature: &Value, release: &OsRelease) -> GeonPlace {
+    let null = Value::Null;
+    let props = feature.get("properties").unwrap_or(&null);
+    let groups = strings(props.get("descriptiveGroup"));
+    let terms = strings(props.get("descriptiveTerm"));
+
+    let mut p = GeonPlace::default();
+    p.type_ = type_for(&groups, &terms);
+    p.place = props
+        .get("name")
+        .and_then(|v| v.as_str())
+        .or(terms.first().copied())
+        .or(groups.first().copied())
+        .unwrap_or("Unnamed")
+        .to_string();
+    p.character = groups.iter().chain(&terms).map(|s| s.to_lowercase()).collect();
+    p.character.dedup();
+
+    if let Some(toid) = props.get("TOID").or_else(|| props.get("toid")).and_then(|v| v.as_str()) {
+        p.id = Some(toid_id(toid));
+    }
+    if let Some(area) = props.get("calculatedAreaValue").and_then(|v| v.as_f64()) {
+        p.area = Some(format!("{} sqm", (area * 10.0).round() / 10.0));
+    }
+    p.updated = props.get("versionDate").and_then(|v| v.as_str()).map(str::to_string);
+    if let Some(version) = props.get("version").filter(|v| !v.is_null()) {
+        p.extra.insert("os_version".to_string(), version.clone());
+    }
+    p.source = vec![release.source()];
+
+    let geom = feature.get("geometry").unwrap_or(&null);
+    let coords = geom.get("coordinates").unwrap_or(&null);
+    match geom.get("type").and_then(|v| v.as_str()) {
+        Some("Point") => p.location = grid_point(coords),
+        Some("Polygon") => {
+            if let Some(ring) = coords.get(0).and_then(|r| r.as_array()) {
+                p.boundary = ring.iter().filter_map(grid_point).collect();
+            }
+        }
+        Some("LineString") => {
+            if let Some(line) = coords.as_array() {
+                p.location = line.get(line.len() / 2).and_then(grid_point);
+            }
+        }
+        _ => {}
+    }
+    if p.location.is_none() {
+        p.location = centroid(&p.boundary);
+    }
+    p
+}
+
+/// Convert every Feature in an OS MasterMap Topography Layer
+/// FeatureCollection.
+pub fn from_mastermap(collection: &Value, release: &OsRelease) -> Vec<GeonPlace> {
+    collection
+        .get("features")
+        .and_then(|v| v.as_array())
+        .map(|features| features.iter().map(|f| from_mastermap_feature(f, release)).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bng_to_wgs84() {
+        // Worked example from the OS guide: TG 51401 13455
+        let (lat, lon) = grid_to_osgb36(651_409.903, 313_177.270);
+        assert!((lat.to_degrees() - 52.657_570_3).abs() < 1e-6);
+        assert!((lon.to_degrees() - 1.717_921_5).abs() < 1e-6);
+
+        let wgs = bng_to_wgs84(651_409.903, 313_177.270);
+        assert!((wgs.lat - 52.657_98).abs() < 1e-4 && (wgs.lon - 1.716_05).abs() < 1e-4, "{:?}", wgs);
+    }
+
+    #[test]
+    fn test_open_uprn_and_mastermap() {
+        let release = OsRelease::new("OS Open UPRN", "2024-10");
+        let text = "UPRN,X_COORDINATE,Y_COORDINATE,LATITUDE,LONGITUDE\n100030311234,457180.00,339811.00,52.9495,-1.1529\n10001,457180.00,339811.00,,\n";
+        let places = from_open_uprn(text, &release).unwrap();
+        assert_eq!(places[0].id.as_deref(), Some("osgb:uprn:100030311234"));
+        assert_eq!(places[0].location, Some(Coordinate::new(52.9495, -1.1529)));
+        assert_eq!(places[0].source, vec!["Ordnance Survey OS Open UPRN (2024-10)"]);
+        let projected = places[1].location.clone().unwrap();
+        assert!((projected.lat - 52.95).abs() < 0.01 && (projected.lon + 1.15).abs() < 0.01);
+
+        let feature = json!({
+            "type": "Feature",
+            "geometry": {"type": "Polygon", "coordinates": [[
+                [457100.0, 339800.0], [457120.0, 339800.0], [457120.0, 339820.0], [457100.0, 339800.0]
+            ]]},
+            "properties": {
+                "TOID": "osgb1000002529080353",
+                "descriptiveGroup": ["Building"],
+                "descriptiveTerm": [],
+                "calculatedAreaValue": 200.04,
+                "version": 4,
+                "versionDate": "2019-03-12"
+            }
+        });
+        let release = OsRelease::new("OS MasterMap Topography Layer", "2024-09");
+        let place = from_mastermap_feature(&feature, &release);
+        assert_eq!(place.id.as_deref(), Some("osgb:1000002529080353"));
+        assert_eq!(place.type_, "building");
+        assert_eq!(place.area.as_deref(), Some("200 sqm"));
+        assert_eq!(place.boundary.len(), 4);
+        assert_eq!(place.extra["os_version"], 4);
+        assert_eq!(place.source, vec!["Ordnance Survey OS MasterMap Topography Layer (2024-09)"]);
+    }
+}
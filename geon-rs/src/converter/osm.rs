@@ -0,0 +1,333 @@
+use super::{extract_extra, extract_purposes, infer_name, infer_type};
+use crate::geometry::centroid;
+use crate::models::{Coordinate, Extent, GeonPlace};
+use serde_json::{Map, Value};
+
+pub(super) fn average(coords: &[Coordinate]) -> Option<Coordinate> {
+    if coords.is_empty() {
+        return None;
+    }
+    let n = coords.len() as f64;
+    let lat = coords.iter().map(|c| c.lat).sum::<f64>() / n;
+    let lon = coords.iter().map(|c| c.lon).sum::<f64>() / n;
+    Some(Coordinate::new(lat, lon))
+}
+
+fn lat_lon(v: &Value) -> Option<Coordinate> {
+    Some(Coordinate::new(v.get("lat")?.as_f64()?, v.get("lon")?.as_f64()?))
+}
+
+// A closed way or member ring; open lines are not boundaries
+pub(super) fn is_ring(coords: &[Coordinate]) -> bool {
+    coords.len() >= 4 && coords.first() == coords.last()
+}
+
+/// Build a place from an OSM element's kind (`node`/`way`/`relation`), id,
+/// tags and resolved geometry. Shared by the Overpass JSON and `.osm` XML
+/// importers.
+pub(super) fn tagged_place(
+    kind: &str,
+    id: i64,
+    tags: &Map<String, Value>,
+    location: Option<Coordinate>,
+    boundary: Vec<Coordinate>,
+) -> GeonPlace {
+    let mut p = GeonPlace::default();
+    p.place = infer_name(tags);
+    p.type_ = infer_type(tags);
+    p.id = Some(format!("osm:{}/{}", kind, id));
+    p.location = location.or_else(|| centroid(&boundary));
+    p.boundary = boundary;
+    p.purpose = extract_purposes(tags);
+    p.extra = extract_extra(tags);
+    p.source = vec![format!("OpenStreetMap ({}/{})", kind, id)];
+    p
+}
+
+/// Convert one element of an Overpass API JSON response.
+///
+/// LOCATION comes from a node's coordinates, the `center` of `out center`
+/// output, or the middle of `bounds`; a closed `geometry` from `out geom`
+/// becomes the BOUNDARY and an open one the PATH.
+pub fn from_overpass_element(element: &Value) -> GeonPlace {
+    let empty = Map::new();
+    let tags = element.get("tags").and_then(|t| t.as_object()).unwrap_or(&empty);
+    let kind = element.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let id = element.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+
+    let geometry: Vec<Coordinate> = element
+        .get("geometry")
+        .and_then(|v| v.as_array())
+        .map(|pts| pts.iter().filter_map(lat_lon).collect())
+        .unwrap_or_default();
+
+    let location = if kind == "node" {
+        lat_lon(element)
+    } else if let Some(center) = element.get("center") {
+        lat_lon(center)
+    } else if let Some(bounds) = element.get("bounds") {
+        let edge = |key: &str| bounds.get(key).and_then(|v| v.as_f64());
+        match (edge("maxlat"), edge("minlat"), edge("maxlon"), edge("minlon")) {
+            (Some(north), Some(south), Some(east), Some(west)) => Some(Extent { north, south, east, west }.center()),
+            _ => None,
+        }
+    } else if is_ring(&geometry) {
+        centroid(&geometry)
+    } else {
+        average(&geometry)
+    };
+
+    if is_ring(&geometry) {
+        tagged_place(kind, id, tags, location, geometry)
+    } else {
+        let mut p = tagged_place(kind, id, tags, location, vec![]);
+        if geometry.len() >= 2 {
+            p.path = geometry;
+        }
+        p
+    }
+}
+
+/// Convert every tagged element of an Overpass API JSON response.
+pub fn from_overpass(response: &Value) -> Vec<GeonPlace> {
+    response
+        .get("elements")
+        .and_then(|v| v.as_array())
+        .map(|elements| {
+            elements
+                .iter()
+                .filter(|el| el.get("tags").and_then(|t| t.as_object()).is_some_and(|t| !t.is_empty()))
+                .map(from_overpass_element)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Public Overpass API endpoint used by [`OverpassQuery::fetch`] unless
+/// another is set.
+pub const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
+
+/// OSM element type selected by a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    Node,
+    Way,
+    Relation,
+}
+
+impl ElementType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ElementType::Node => "node",
+            ElementType::Way => "way",
+            ElementType::Relation => "relation",
+        }
+    }
+}
+
+/// What `out` returns for ways and relations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutMode {
+    /// Tags only (plus coordinates for nodes).
+    Body,
+    /// A centre point per element: cheap, gives LOCATION only.
+    Center,
+    /// Full geometry, so closed ways become BOUNDARY rings.
+    #[default]
+    Geom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SearchArea {
+    BBox { south: f64, west: f64, north: f64, east: f64 },
+    Around { center: Coordinate, radius_m: f64 },
+}
+
+/// Builder for Overpass QL queries whose JSON output feeds
+/// [`from_overpass`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverpassQuery {
+    area: Option<SearchArea>,
+    filters: Vec<(String, Option<String>)>,
+    elements: Vec<ElementType>,
+    timeout_s: u32,
+    out: OutMode,
+    endpoint: String,
+}
+
+impl Default for OverpassQuery {
+    fn default() -> Self {
+        Self {
+            area: None,
+            filters: Vec::new(),
+            elements: vec![ElementType::Node, ElementType::Way, ElementType::Relation],
+            timeout_s: 25,
+            out: OutMode::default(),
+            endpoint: OVERPASS_URL.to_string(),
+        }
+    }
+}
+
+fn ql_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl OverpassQuery {
+    /// All element types, 25 s timeout, `out geom`, no area or filters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict to a bounding box.
+    pub fn bbox(mut self, south: f64, west: f64, north: f64, east: f64) -> Self {
+        self.area = Some(SearchArea::BBox { south, west, north, east });
+        self
+    }
+
+    /// Restrict to within `radius_m` metres of `center`.
+    pub fn around(mut self, center: Coordinate, radius_m: f64) -> Self {
+        self.area = Some(SearchArea::Around { center, radius_m });
+        self
+    }
+
+    /// Require `key=value`.
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.filters.push((key.to_string(), Some(value.to_string())));
+        self
+    }
+
+    /// Require that `key` is present with any value.
+    pub fn has_tag(mut self, key: &str) -> Self {
+        self.filters.push((key.to_string(), None));
+        self
+    }
+
+    /// Select only these element types.
+    pub fn elements(mut self, elements: &[ElementType]) -> Self {
+        self.elements = elements.to_vec();
+        self
+    }
+
+    pub fn timeout(mut self, seconds: u32) -> Self {
+        self.timeout_s = seconds;
+        self
+    }
+
+    pub fn out(mut self, mode: OutMode) -> Self {
+        self.out = mode;
+        self
+    }
+
+    /// Send [`fetch`](Self::fetch) requests to another Overpass instance.
+    pub fn endpoint(mut self, url: &str) -> Self {
+        self.endpoint = url.to_string();
+        self
+    }
+
+    /// Render the query as Overpass QL.
+    pub fn to_ql(&self) -> String {
+        let mut selector = String::new();
+        for (key, value) in &self.filters {
+            selector.push('[');
+            selector.push_str(&ql_string(key));
+            if let Some(v) = value {
+                selector.push('=');
+                selector.push_str(&ql_string(v));
+            }
+            selector.push(']');
+        }
+        match &self.area {
+            Some(SearchArea::BBox { south, west, north, east }) => {
+                selector.push_str(&format!("({},{},{},{})", south, west, north, east))
+            }
+            Some(SearchArea::Around { center, radius_m }) => {
+                selector.push_str(&format!("(around:{},{},{})", radius_m, center.lat, center.lon))
+            }
+            None => {}
+        }
+
+        let mut ql = format!("[out:json][timeout:{}];\n(\n", self.timeout_s);
+        for element in &self.elements {
+            ql.push_str(&format!("  {}{};\n", element.as_str(), selector));
+        }
+        ql.push_str(");\n");
+        ql.push_str(match self.out {
+            OutMode::Body => "out body;\n",
+            OutMode::Center => "out body center;\n",
+            OutMode::Geom => "out body geom;\n",
+        });
+        ql
+    }
+
+    /// Run the query and convert every tagged element with
+    /// [`from_overpass`].
+    #[cfg(feature = "http")]
+    pub async fn fetch(&self, client: &reqwest::Client) -> Result<Vec<GeonPlace>, crate::parser::GeonError> {
+        let url = reqwest::Url::parse_with_params(&self.endpoint, &[("data", self.to_ql())])
+            .map_err(|e| crate::parser::GeonError::InvalidStructure(e.to_string()))?;
+        let response: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+        Ok(from_overpass(&response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overpass_elements() {
+        let response = json!({
+            "elements": [
+                {"type": "node", "id": 1, "lat": 52.0, "lon": -1.0},
+                {
+                    "type": "node", "id": 2, "lat": 52.95, "lon": -1.15,
+                    "tags": {"name": "Nottingham Castle", "tourism": "attraction", "historic": "castle", "website": "https://example.org"}
+                },
+                {
+                    "type": "way", "id": 3,
+                    "bounds": {"minlat": 52.0, "minlon": -1.0, "maxlat": 52.2, "maxlon": -0.8},
+                    "geometry": [
+                        {"lat": 52.0, "lon": -1.0}, {"lat": 52.2, "lon": -1.0},
+                        {"lat": 52.2, "lon": -0.8}, {"lat": 52.0, "lon": -1.0}
+                    ],
+                    "tags": {"name": "Wollaton Park", "leisure": "park"}
+                },
+                {
+                    "type": "way", "id": 4,
+                    "geometry": [{"lat": 52.0, "lon": -1.0}, {"lat": 52.1, "lon": -1.1}],
+                    "tags": {"highway": "bridleway"}
+                }
+            ]
+        });
+        let places = from_overpass(&response);
+        assert_eq!(places.len(), 3);
+
+        assert_eq!(places[0].type_, "landmark");
+        assert_eq!(places[0].purpose, vec!["tourism: attraction"]);
+        assert_eq!(places[0].extra["website"], "https://example.org");
+        assert_eq!(places[0].source, vec!["OpenStreetMap (node/2)"]);
+
+        assert_eq!(places[1].type_, "public_space");
+        assert_eq!(places[1].id.as_deref(), Some("osm:way/3"));
+        assert_eq!(places[1].location, Some(Coordinate::new(52.1, -0.9)));
+        assert_eq!(places[1].boundary.len(), 4);
+
+        assert_eq!(places[2].type_, "street");
+        assert!(places[2].boundary.is_empty());
+        assert_eq!(places[2].path.len(), 2);
+        assert_eq!(places[2].location, Some(Coordinate::new(52.05, -1.05)));
+    }
+
+    #[test]
+    fn test_overpass_query_builder() {
+        let ql = OverpassQuery::new()
+            .bbox(52.9, -1.2, 53.0, -1.1)
+            .tag("name", "Say \"Cheese\"")
+            .elements(&[ElementType::Node])
+            .timeout(10)
+            .out(OutMode::Body)
+            .to_ql();
+        assert_eq!(ql, "[out:json][timeout:10];\n(\n  node[\"name\"=\"Say \\\"Cheese\\\"\"](52.9,-1.2,53,-1.1);\n);\nout body;\n");
+    }
+}
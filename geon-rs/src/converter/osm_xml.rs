@@ -0,0 +1,171 @@
+use super::osm::{average, is_ring, tagged_place};
+use super::xml::{attr, XmlEvent, XmlReader};
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct Element {
+    kind: String,
+    id: i64,
+    location: Option<Coordinate>,
+    refs: Vec<i64>,
+    members: Vec<(String, i64, String)>,
+    tags: Map<String, Value>,
+}
+
+fn read_elements(text: &str) -> Result<Vec<Element>, GeonError> {
+    let mut elements = Vec::new();
+    let mut current: Option<Element> = None;
+
+    for event in XmlReader::new(text) {
+        match event? {
+            XmlEvent::Start { name, attrs, self_closing } => match name {
+                "node" | "way" | "relation" => {
+                    let mut el = Element { kind: name.to_string(), ..Default::default() };
+                    el.id = attr(&attrs, "id").and_then(|v| v.parse().ok()).unwrap_or(0);
+                    if let (Some(lat), Some(lon)) = (attr(&attrs, "lat"), attr(&attrs, "lon")) {
+                        el.location = Some(Coordinate::new(lat.parse()?, lon.parse()?));
+                    }
+                    if self_closing {
+                        elements.push(el);
+                    } else {
+                        current = Some(el);
+                    }
+                }
+                "tag" => {
+                    if let (Some(el), Some(k), Some(v)) = (current.as_mut(), attr(&attrs, "k"), attr(&attrs, "v")) {
+                        el.tags.insert(k.to_string(), Value::String(v.to_string()));
+                    }
+                }
+                "nd" => {
+                    if let (Some(el), Some(r)) = (current.as_mut(), attr(&attrs, "ref")) {
+                        el.refs.push(r.parse().unwrap_or(0));
+                    }
+                }
+                "member" => {
+                    if let (Some(el), Some(kind), Some(r)) = (current.as_mut(), attr(&attrs, "type"), attr(&attrs, "ref")) {
+                        let role = attr(&attrs, "role").unwrap_or("").to_string();
+                        el.members.push((kind.to_string(), r.parse().unwrap_or(0), role));
+                    }
+                }
+                _ => {}
+            },
+            XmlEvent::End { name: "node" | "way" | "relation" } => {
+                if let Some(el) = current.take() {
+                    elements.push(el);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(elements)
+}
+
+/// Import an OSM XML (`.osm`) extract. Every tagged node, way and relation
+/// becomes a place; untagged elements only contribute geometry. Closed ways
+/// become BOUNDARY rings, open ways a PATH, and multipolygon relations take the ring of their
+/// largest `outer` way.
+pub fn from_osm_xml(text: &str) -> Result<Vec<GeonPlace>, GeonError> {
+    let elements = read_elements(text)?;
+
+    let nodes: HashMap<i64, Coordinate> = elements
+        .iter()
+        .filter(|el| el.kind == "node")
+        .filter_map(|el| el.location.clone().map(|c| (el.id, c)))
+        .collect();
+    let way_coords: HashMap<i64, Vec<Coordinate>> = elements
+        .iter()
+        .filter(|el| el.kind == "way")
+        .map(|el| (el.id, el.refs.iter().filter_map(|r| nodes.get(r).cloned()).collect()))
+        .collect();
+
+    let mut places = Vec::new();
+    for el in elements.iter().filter(|el| !el.tags.is_empty()) {
+        let place = match el.kind.as_str() {
+            "node" => tagged_place(&el.kind, el.id, &el.tags, el.location.clone(), vec![]),
+            "way" => {
+                let coords = &way_coords[&el.id];
+                let boundary = if is_ring(coords) { coords.clone() } else { vec![] };
+                let location = if boundary.is_empty() { average(coords) } else { None };
+                let mut p = tagged_place(&el.kind, el.id, &el.tags, location, boundary);
+                if p.boundary.is_empty() && coords.len() >= 2 {
+                    p.path = coords.clone();
+                }
+                p
+            }
+            _ => {
+                let mut member_coords = Vec::new();
+                let mut outer: Vec<Coordinate> = Vec::new();
+                for (kind, r, role) in &el.members {
+                    match kind.as_str() {
+                        "node" => member_coords.extend(nodes.get(r).cloned()),
+                        "way" => {
+                            if let Some(coords) = way_coords.get(r) {
+                                member_coords.extend(coords.iter().cloned());
+                                if role == "outer" && is_ring(coords) && coords.len() > outer.len() {
+                                    outer = coords.clone();
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let location = if outer.is_empty() { average(&member_coords) } else { None };
+                tagged_place(&el.kind, el.id, &el.tags, location, outer)
+            }
+        };
+        places.push(place);
+    }
+    Ok(places)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTRACT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="52.0" lon="-1.0"/>
+  <node id="2" lat="52.0" lon="-0.9"/>
+  <node id="3" lat="52.1" lon="-0.9"/>
+  <node id="4" lat="52.05" lon="-0.95">
+    <tag k="name" v="Fish &amp; Chips"/>
+    <tag k="amenity" v="restaurant"/>
+  </node>
+  <way id="10">
+    <nd ref="1"/><nd ref="2"/><nd ref="3"/><nd ref="1"/>
+    <tag k="leisure" v="park"/>
+    <tag k="name" v="Triangle Park"/>
+  </way>
+  <relation id="20">
+    <member type="way" ref="10" role="outer"/>
+    <tag k="type" v="multipolygon"/>
+    <tag k="landuse" v="square"/>
+  </relation>
+</osm>"#;
+
+    #[test]
+    fn test_osm_xml_import() {
+        let places = from_osm_xml(EXTRACT).unwrap();
+        assert_eq!(places.len(), 3);
+
+        assert_eq!(places[0].place, "Fish & Chips");
+        assert_eq!(places[0].id.as_deref(), Some("osm:node/4"));
+        assert_eq!(places[0].purpose, vec!["amenity: restaurant"]);
+
+        assert_eq!(places[1].type_, "public_space");
+        assert_eq!(places[1].boundary.len(), 4);
+        assert!(places[1].location.is_some());
+
+        assert_eq!(places[2].place, "Unnamed");
+        assert_eq!(places[2].boundary.len(), 4);
+    }
+
+    #[test]
+    fn test_osm_xml_malformed() {
+        assert!(from_osm_xml("<osm><node id=\"1\" lat=\"x\" lon=\"1\"/></osm>").is_err());
+        assert!(from_osm_xml("<osm><node id=\"1\"").is_err());
+    }
+}
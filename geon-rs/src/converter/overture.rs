@@ -0,0 +1,260 @@
+use super::{extract_boundary, extract_centroid};
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Ordered table mapping Overture place categories to GEON types.
+///
+/// A category matches a rule when it contains the rule's pattern
+/// (case-insensitively); the first matching rule wins. Categories matching
+/// no rule get `default_type`. Read from a file, the rules replace the
+/// built-in ones:
+///
+/// ```json
+/// {"rules": [["bakery", "building"], ["park", "public_space"]], "default_type": "hybrid"}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryMapping {
+    pub rules: Vec<(String, String)>,
+    pub default_type: String,
+}
+
+impl Default for CategoryMapping {
+    fn default() -> Self {
+        let table: &[(&str, &[&str])] = &[
+            ("transport_hub", &["station", "airport", "bus_stop", "ferry_terminal", "transit"]),
+            (
+                "landmark",
+                &["museum", "monument", "memorial", "church", "cathedral", "castle", "historic", "landmark"],
+            ),
+            (
+                "public_space",
+                &["park", "garden", "playground", "sports_centre", "stadium", "plaza", "square", "beach"],
+            ),
+            (
+                "building",
+                &[
+                    "restaurant", "cafe", "coffee", "bar", "pub", "hotel", "school", "college", "university",
+                    "hospital", "bank", "shop", "store", "supermarket", "library", "theatre", "cinema",
+                ],
+            ),
+        ];
+        let rules = table
+            .iter()
+            .flat_map(|(type_, patterns)| patterns.iter().map(move |p| (p.to_string(), type_.to_string())))
+            .collect();
+        Self { rules, default_type: "hybrid".to_string() }
+    }
+}
+
+impl CategoryMapping {
+    /// Read a mapping from JSON.
+    pub fn from_json(text: &str) -> Result<Self, GeonError> {
+        let value = serde_json::from_str(text).map_err(|e| GeonError::InvalidStructure(format!("category mapping: {}", e)))?;
+        Self::from_value(value)
+    }
+
+    /// Read a mapping from TOML, e.g. `rules = [["bakery", "building"]]`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, GeonError> {
+        Self::from_value(crate::io::toml::parse_value(text)?)
+    }
+
+    // Patterns match lowercased categories, so are stored lowercase
+    fn from_value(value: Value) -> Result<Self, GeonError> {
+        let mut mapping: Self =
+            serde_json::from_value(value).map_err(|e| GeonError::InvalidStructure(format!("category mapping: {}", e)))?;
+        for (pattern, _) in &mut mapping.rules {
+            *pattern = pattern.to_lowercase();
+        }
+        Ok(mapping)
+    }
+
+    /// Add a rule ahead of the existing ones, so it takes precedence.
+    pub fn prepend(&mut self, pattern: &str, type_: &str) {
+        self.rules.insert(0, (pattern.to_lowercase(), type_.to_string()));
+    }
+
+    pub fn type_for(&self, category: &str) -> String {
+        self.lookup(category).unwrap_or_else(|| self.default_type.clone())
+    }
+
+    /// Type of the first rule matching `category`, if any.
+    pub fn lookup(&self, category: &str) -> Option<String> {
+        let category = category.to_lowercase();
+        self.rules
+            .iter()
+            .find(|(pattern, _)| category.contains(pattern.as_str()))
+            .map(|(_, type_)| type_.clone())
+    }
+}
+
+fn str_at<'a>(v: &'a Value, path: &[&str]) -> Option<&'a str> {
+    path.iter().try_fold(v, |v, key| v.get(key))?.as_str()
+}
+
+/// Convert one Overture Maps place Feature using the default category table.
+pub fn from_overture_feature(feature: &Value) -> GeonPlace {
+    from_overture_feature_with(feature, &CategoryMapping::default())
+}
+
+/// Convert one Overture Maps place Feature using a custom category table.
+pub fn from_overture_feature_with(feature: &Value, mapping: &CategoryMapping) -> GeonPlace {
+    let empty = Value::Object(Map::new());
+    let props = feature.get("properties").unwrap_or(&empty);
+    let empty_map = Map::new();
+    let geom = feature.get("geometry").and_then(|v| v.as_object()).unwrap_or(&empty_map);
+
+    let mut p = GeonPlace::default();
+
+    // Name
+    p.place = str_at(props, &["names", "primary"])
+        .or_else(|| str_at(props, &["name"]))
+        .unwrap_or("Unnamed")
+        .to_string();
+
+    // ID
+    p.id = str_at(props, &["id"]).or_else(|| str_at(feature, &["id"])).map(str::to_string);
+
+    // Categories: older releases use `main`, current ones `primary`
+    p.type_ = match str_at(props, &["categories", "primary"]).or_else(|| str_at(props, &["categories", "main"])) {
+        Some(main) => mapping.type_for(main),
+        None => mapping.default_type.clone(),
+    };
+    if let Some(alt) = props.pointer("/categories/alternate").and_then(|v| v.as_array()) {
+        p.purpose = alt.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect();
+    }
+
+    // Geometry
+    p.location = extract_centroid(geom);
+    p.boundary = extract_boundary(geom);
+
+    // Confidence
+    if let Some(conf) = props.get("confidence").and_then(|v| v.as_f64()) {
+        p.confidence.insert("overall".to_string(), format!("{:.2}", conf));
+    }
+
+    // Sources
+    if let Some(sources) = props.get("sources").and_then(|v| v.as_array()) {
+        for src in sources {
+            if let Some(dataset) = src.get("dataset").and_then(|v| v.as_str()) {
+                let source = format!("Overture Maps ({})", dataset);
+                if !p.source.contains(&source) {
+                    p.source.push(source);
+                }
+            }
+        }
+    }
+    if p.source.is_empty() {
+        p.source.push("Overture Maps".to_string());
+    }
+
+    // Address, contact and brand details
+    if let Some(addr) = props.pointer("/addresses/0") {
+        let parts: Vec<&str> = ["freeform", "locality", "postcode", "region", "country"]
+            .iter()
+            .filter_map(|k| addr.get(k).and_then(|v| v.as_str()))
+            .collect();
+        if !parts.is_empty() {
+            p.extra.insert("address".to_string(), Value::String(parts.join(", ")));
+        }
+    }
+    for (key, list) in [("website", "websites"), ("phone", "phones"), ("social", "socials")] {
+        if let Some(first) = props.pointer(&format!("/{}/0", list)) {
+            p.extra.insert(key.to_string(), first.clone());
+        }
+    }
+    if let Some(brand) = str_at(props, &["brand", "names", "primary"]) {
+        p.extra.insert("brand".to_string(), Value::String(brand.to_string()));
+    }
+
+    p
+}
+
+/// Convert an Overture Feature or FeatureCollection.
+pub fn from_overture(value: &Value, mapping: &CategoryMapping) -> Vec<GeonPlace> {
+    match value.get("type").and_then(|v| v.as_str()) {
+        Some("FeatureCollection") => value
+            .get("features")
+            .and_then(|v| v.as_array())
+            .map(|fs| fs.iter().map(|f| from_overture_feature_with(f, mapping)).collect())
+            .unwrap_or_default(),
+        Some("Feature") => vec![from_overture_feature_with(value, mapping)],
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+    use serde_json::json;
+
+    // Shape of a record from the 2024 places theme release
+    fn record() -> Value {
+        json!({
+            "type": "Feature",
+            "id": "08f194ad32410744020054a1a98e6d95",
+            "geometry": {"type": "Point", "coordinates": [-1.1490, 52.9534]},
+            "properties": {
+                "id": "08f194ad32410744020054a1a98e6d95",
+                "version": 0,
+                "names": {"primary": "Ye Olde Trip to Jerusalem", "common": null, "rules": null},
+                "categories": {"primary": "pub", "alternate": ["bar", "historical_landmark"]},
+                "confidence": 0.9793990828827596,
+                "websites": ["http://www.triptojerusalem.com/"],
+                "socials": ["https://www.facebook.com/107412529291735"],
+                "phones": ["+441159473171"],
+                "brand": null,
+                "addresses": [{
+                    "freeform": "1 Brewhouse Yard",
+                    "locality": "Nottingham",
+                    "postcode": "NG1 6AD",
+                    "region": null,
+                    "country": "GB"
+                }],
+                "sources": [
+                    {"property": "", "dataset": "meta", "record_id": "107412529291735"},
+                    {"property": "/properties/confidence", "dataset": "meta", "record_id": "107412529291735"}
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_overture_place_record() {
+        let p = from_overture_feature(&record());
+        assert_eq!(p.place, "Ye Olde Trip to Jerusalem");
+        assert_eq!(p.type_, "building");
+        assert_eq!(p.id.as_deref(), Some("08f194ad32410744020054a1a98e6d95"));
+        assert_eq!(p.location, Some(Coordinate::new(52.9534, -1.1490)));
+        assert_eq!(p.purpose, vec!["bar", "historical_landmark"]);
+        assert_eq!(p.confidence["overall"], "0.98");
+        assert_eq!(p.source, vec!["Overture Maps (meta)"]);
+        assert_eq!(p.extra["address"], "1 Brewhouse Yard, Nottingham, NG1 6AD, GB");
+        assert_eq!(p.extra["phone"], "+441159473171");
+        assert!(!p.extra.contains_key("brand"));
+    }
+
+    #[test]
+    fn test_custom_category_mapping() {
+        let mut mapping = CategoryMapping::default();
+        mapping.prepend("pub", "landmark");
+        let collection = json!({"type": "FeatureCollection", "features": [record()]});
+        let places = from_overture(&collection, &mapping);
+        assert_eq!(places[0].type_, "landmark");
+        assert_eq!(mapping.type_for("vending_machine"), "hybrid");
+    }
+
+    #[test]
+    fn test_category_mapping_from_file() {
+        let mapping = CategoryMapping::from_json(r#"{"rules": [["Bakery", "building"]], "default_type": "landmark"}"#).unwrap();
+        assert_eq!(mapping.type_for("artisan_bakery"), "building");
+        assert_eq!(mapping.type_for("park"), "landmark");
+        assert!(CategoryMapping::from_json(r#"{"rules": "bakery"}"#).is_err());
+        #[cfg(feature = "toml")]
+        assert_eq!(CategoryMapping::from_toml("rules = [[\"bakery\", \"building\"]]\n").unwrap().rules, mapping.rules);
+    }
+}
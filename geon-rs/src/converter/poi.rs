@@ -0,0 +1,260 @@
+//! Pluggable point-of-interest feeds.
+//!
+//! A [`PoiSource`] turns one raw record from a commercial or open POI feed
+//! into a place. New feeds implement the trait outside the crate rather than
+//! adding another converter here.
+
+use super::csv::{read_records, CsvMapping};
+use super::google_places::from_place_details;
+use super::overture::{from_overture_feature_with, CategoryMapping};
+use crate::models::{Coordinate, GeonPlace};
+use serde_json::{Map, Value};
+
+/// A POI feed whose records convert to places one at a time.
+pub trait PoiSource {
+    fn to_geon(&self, raw: &Value) -> GeonPlace;
+
+    fn to_geon_all(&self, records: &[Value]) -> Vec<GeonPlace> {
+        records.iter().map(|r| self.to_geon(r)).collect()
+    }
+}
+
+/// Google Places API (New) Place Details responses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GooglePlaces;
+
+impl PoiSource for GooglePlaces {
+    fn to_geon(&self, raw: &Value) -> GeonPlace {
+        from_place_details(raw)
+    }
+}
+
+/// Overture Maps place Features, typed with this category table.
+impl PoiSource for CategoryMapping {
+    fn to_geon(&self, raw: &Value) -> GeonPlace {
+        from_overture_feature_with(raw, self)
+    }
+}
+
+/// Foursquare Open Source Places rows, as JSON objects keyed by column.
+///
+/// `fsq_category_labels` (`"Dining and Drinking > Restaurant > Pizzeria"`)
+/// are matched against `categories` from the most specific level up, and
+/// their leaves become PURPOSE.
+#[derive(Debug, Clone)]
+pub struct Foursquare {
+    pub categories: CategoryMapping,
+}
+
+impl Default for Foursquare {
+    fn default() -> Self {
+        let mut categories = CategoryMapping::default();
+        for (pattern, type_) in [
+            ("transport hub", "transport_hub"),
+            ("nature preserve", "natural_feature"),
+            ("mountain", "natural_feature"),
+            ("lake", "natural_feature"),
+            ("river", "natural_feature"),
+            ("neighborhood", "district"),
+            ("bridge", "threshold"),
+            ("parking", "infrastructure"),
+        ] {
+            categories.prepend(pattern, type_);
+        }
+        Self { categories }
+    }
+}
+
+fn category_labels(raw: &Value) -> Vec<&str> {
+    match raw.get("fsq_category_labels") {
+        Some(Value::Array(labels)) => labels.iter().filter_map(|l| l.as_str()).collect(),
+        Some(Value::String(label)) if !label.is_empty() => vec![label.as_str()],
+        _ => vec![],
+    }
+}
+
+// Non-empty text for a field, accepting numbers as written
+fn text(raw: &Value, key: &str) -> Option<String> {
+    match raw.get(key)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn number(raw: &Value, key: &str) -> Option<f64> {
+    match raw.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl PoiSource for Foursquare {
+    fn to_geon(&self, raw: &Value) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = text(raw, "name").unwrap_or_else(|| "Unnamed".to_string());
+
+        let labels = category_labels(raw);
+        p.type_ = labels
+            .iter()
+            .find_map(|label| label.rsplit('>').find_map(|level| self.categories.lookup(level.trim())))
+            .unwrap_or_else(|| self.categories.default_type.clone());
+        for label in &labels {
+            let leaf = label.rsplit('>').next().unwrap_or_default().trim().to_lowercase();
+            if !leaf.is_empty() && !p.purpose.contains(&leaf) {
+                p.purpose.push(leaf);
+            }
+        }
+
+        if let (Some(lat), Some(lon)) = (number(raw, "latitude"), number(raw, "longitude")) {
+            p.location = Some(Coordinate::new(lat, lon));
+        }
+        match text(raw, "fsq_place_id") {
+            Some(id) => {
+                p.id = Some(format!("fsq:{}", id));
+                p.source = vec![format!("Foursquare Open Source Places ({})", id)];
+            }
+            None => p.source = vec!["Foursquare Open Source Places".to_string()],
+        }
+        p.updated = text(raw, "date_refreshed");
+        if let Some(created) = text(raw, "date_created") {
+            p.lifespan.insert("established".to_string(), created);
+        }
+        if let Some(closed) = text(raw, "date_closed") {
+            p.lifespan.insert("closed".to_string(), closed);
+        }
+
+        let address: Vec<String> = ["address", "locality", "region", "postcode", "country"]
+            .iter()
+            .filter_map(|key| text(raw, key))
+            .collect();
+        if !address.is_empty() {
+            p.extra.insert("address".to_string(), Value::String(address.join(", ")));
+        }
+        for (key, extra_key) in [("tel", "phone"), ("website", "website"), ("email", "email")] {
+            if let Some(v) = text(raw, key) {
+                p.extra.insert(extra_key.to_string(), Value::String(v));
+            }
+        }
+        p
+    }
+}
+
+/// Generic POI CSV rows, as produced by [`csv_rows`].
+///
+/// Columns follow `mapping`; rows without a TYPE cell are typed from the
+/// `category_column` through `categories`.
+#[derive(Debug, Clone)]
+pub struct CsvPoi {
+    pub mapping: CsvMapping,
+    pub category_column: String,
+    pub categories: CategoryMapping,
+}
+
+impl Default for CsvPoi {
+    fn default() -> Self {
+        Self {
+            mapping: CsvMapping::default(),
+            category_column: "category".to_string(),
+            categories: CategoryMapping::default(),
+        }
+    }
+}
+
+impl PoiSource for CsvPoi {
+    fn to_geon(&self, raw: &Value) -> GeonPlace {
+        let m = &self.mapping;
+        let mut p = GeonPlace::default();
+        p.place = text(raw, &m.name).unwrap_or_else(|| "Unnamed".to_string());
+        let category = text(raw, &self.category_column);
+        p.type_ = text(raw, &m.type_).unwrap_or_else(|| match &category {
+            Some(c) => self.categories.type_for(c),
+            None => self.categories.default_type.clone(),
+        });
+        p.id = text(raw, &m.id);
+        if let (Some(lat), Some(lon)) = (number(raw, &m.lat), number(raw, &m.lon)) {
+            p.location = Some(Coordinate::new(lat, lon));
+        }
+        p.area = text(raw, &m.area);
+        if let Some(purpose) = text(raw, &m.purpose) {
+            p.purpose = purpose
+                .split(m.purpose_separator)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if p.purpose.is_empty() {
+            p.purpose.extend(category.map(|c| c.to_lowercase()));
+        }
+        for (column, key) in &m.extra {
+            if let Some(v) = text(raw, column) {
+                p.extra.insert(key.clone(), Value::String(v));
+            }
+        }
+        p
+    }
+}
+
+/// Split CSV text with a header row into one JSON object per row, keyed by
+/// column name, for use with a [`PoiSource`].
+pub fn csv_rows(text: &str) -> Vec<Value> {
+    let mut records = read_records(text).into_iter();
+    let Some(header) = records.next() else { return vec![] };
+    records
+        .map(|record| {
+            let row: Map<String, Value> = header
+                .iter()
+                .zip(record)
+                .map(|(column, cell)| (column.trim().to_string(), Value::String(cell)))
+                .collect();
+            Value::Object(row)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_foursquare_row() {
+        let row = json!({
+            "fsq_place_id": "4b5a3c2ef964a520",
+            "name": "Ye Olde Trip to Jerusalem",
+            "latitude": 52.9488,
+            "longitude": -1.1522,
+            "address": "1 Brewhouse Yard",
+            "locality": "Nottingham",
+            "postcode": "NG1 6AD",
+            "country": "GB",
+            "date_created": "2010-01-22",
+            "date_refreshed": "2024-11-03",
+            "fsq_category_labels": ["Dining and Drinking > Bar > Pub"]
+        });
+        let place = Foursquare::default().to_geon(&row);
+        assert_eq!(place.place, "Ye Olde Trip to Jerusalem");
+        assert_eq!(place.type_, "building");
+        assert_eq!(place.purpose, vec!["pub"]);
+        assert_eq!(place.id.as_deref(), Some("fsq:4b5a3c2ef964a520"));
+        assert_eq!(place.location, Some(Coordinate::new(52.9488, -1.1522)));
+        assert_eq!(place.lifespan["established"], "2010-01-22");
+        assert_eq!(place.updated.as_deref(), Some("2024-11-03"));
+        assert_eq!(place.extra["address"], "1 Brewhouse Yard, Nottingham, NG1 6AD, GB");
+    }
+
+    #[test]
+    fn test_csv_poi_source() {
+        let text = "name,category,lat,lon,phone\nArboretum,Park,52.961,-1.157,\nCity Library,library,52.953,-1.148,0115 915 2828\n";
+        let mut source = CsvPoi::default();
+        source.mapping = source.mapping.with_extra("phone");
+        let places = source.to_geon_all(&csv_rows(text));
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].type_, "public_space");
+        assert_eq!(places[0].purpose, vec!["park"]);
+        assert!(!places[0].extra.contains_key("phone"));
+        assert_eq!(places[1].type_, "building");
+        assert_eq!(places[1].extra["phone"], "0115 915 2828");
+    }
+}
@@ -0,0 +1,257 @@
+use super::feature_to_geon;
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde_json::{json, Map, Value};
+
+fn invalid(msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("shapefile: {}", msg))
+}
+
+// Bounds-checked little/big-endian readers over a byte slice
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn slice(&self, at: usize, len: usize) -> Result<&[u8], GeonError> {
+        self.0.get(at..at + len).ok_or_else(|| invalid("unexpected end of data"))
+    }
+    fn i32_be(&self, at: usize) -> Result<i32, GeonError> {
+        Ok(i32::from_be_bytes(self.slice(at, 4)?.try_into().unwrap()))
+    }
+    fn i32_le(&self, at: usize) -> Result<i32, GeonError> {
+        Ok(i32::from_le_bytes(self.slice(at, 4)?.try_into().unwrap()))
+    }
+    fn u32_le(&self, at: usize) -> Result<u32, GeonError> {
+        Ok(u32::from_le_bytes(self.slice(at, 4)?.try_into().unwrap()))
+    }
+    fn u16_le(&self, at: usize) -> Result<u16, GeonError> {
+        Ok(u16::from_le_bytes(self.slice(at, 2)?.try_into().unwrap()))
+    }
+    fn f64_le(&self, at: usize) -> Result<f64, GeonError> {
+        Ok(f64::from_le_bytes(self.slice(at, 8)?.try_into().unwrap()))
+    }
+    fn count(&self, at: usize) -> Result<usize, GeonError> {
+        usize::try_from(self.i32_le(at)?).map_err(|_| invalid("negative count"))
+    }
+}
+
+fn points(b: &Bytes, at: usize, n: usize) -> Result<Vec<Value>, GeonError> {
+    (0..n).map(|i| Ok(json!([b.f64_le(at + i * 16)?, b.f64_le(at + i * 16 + 8)?]))).collect()
+}
+
+// Shape record content -> GeoJSON geometry. Z and M variants share the XY
+// layout of their base type, with extra arrays after it that are ignored.
+fn shape_geometry(b: &Bytes) -> Result<Value, GeonError> {
+    match b.i32_le(0)? {
+        0 => Ok(Value::Null),
+        1 | 11 | 21 => Ok(json!({"type": "Point", "coordinates": [b.f64_le(4)?, b.f64_le(12)?]})),
+        8 | 18 | 28 => {
+            let n = b.count(36)?;
+            Ok(json!({"type": "MultiPoint", "coordinates": points(b, 40, n)?}))
+        }
+        kind @ (3 | 13 | 23 | 5 | 15 | 25) => {
+            let num_parts = b.count(36)?;
+            let num_points = b.count(40)?;
+            let pts_at = 44 + num_parts * 4;
+            let all = points(b, pts_at, num_points)?;
+            let mut parts = Vec::with_capacity(num_parts);
+            for i in 0..num_parts {
+                let start = b.count(44 + i * 4)?;
+                let end = if i + 1 < num_parts { b.count(44 + (i + 1) * 4)? } else { num_points };
+                parts.push(all.get(start..end).ok_or_else(|| invalid("bad part index"))?.to_vec());
+            }
+            let polygon = matches!(kind, 5 | 15 | 25);
+            Ok(match (polygon, parts.len()) {
+                (true, _) => json!({"type": "Polygon", "coordinates": parts}),
+                (false, 1) => json!({"type": "LineString", "coordinates": parts[0]}),
+                (false, _) => json!({"type": "MultiLineString", "coordinates": parts}),
+            })
+        }
+        other => Err(invalid(&format!("unsupported shape type {}", other))),
+    }
+}
+
+fn read_shp(shp: &[u8]) -> Result<Vec<Value>, GeonError> {
+    let b = Bytes(shp);
+    if b.i32_be(0)? != 9994 {
+        return Err(invalid("not a .shp file"));
+    }
+    let file_len = (b.i32_be(24)? as usize * 2).min(shp.len());
+    let mut geometries = Vec::new();
+    let mut at = 100;
+    while at + 8 <= file_len {
+        let content_len = usize::try_from(b.i32_be(at + 4)?).map_err(|_| invalid("negative length"))? * 2;
+        let content = Bytes(b.slice(at + 8, content_len)?);
+        geometries.push(shape_geometry(&content)?);
+        at += 8 + content_len;
+    }
+    Ok(geometries)
+}
+
+fn dbf_value(kind: u8, raw: &[u8]) -> Value {
+    let text = String::from_utf8_lossy(raw);
+    let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    if text.is_empty() {
+        return Value::Null;
+    }
+    match kind {
+        b'N' | b'F' => text
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| text.parse::<f64>().map(Value::from))
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        b'L' => match text {
+            "T" | "t" | "Y" | "y" => Value::Bool(true),
+            "F" | "f" | "N" | "n" => Value::Bool(false),
+            _ => Value::Null,
+        },
+        b'D' if text.len() == 8 => Value::String(format!("{}-{}-{}", &text[..4], &text[4..6], &text[6..])),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+fn read_dbf(dbf: &[u8]) -> Result<Vec<Map<String, Value>>, GeonError> {
+    let b = Bytes(dbf);
+    let num_records = b.u32_le(4)? as usize;
+    let header_len = b.u16_le(8)? as usize;
+    let record_len = b.u16_le(10)? as usize;
+
+    // (name, type, offset within record, width); offset 0 is the deletion flag
+    let mut fields = Vec::new();
+    let mut offset = 1;
+    let mut at = 32;
+    while at + 32 <= header_len && b.slice(at, 1)?[0] != 0x0D {
+        let desc = b.slice(at, 32)?;
+        let name_end = desc[..11].iter().position(|c| *c == 0).unwrap_or(11);
+        let name = String::from_utf8_lossy(&desc[..name_end]).trim().to_lowercase();
+        let width = desc[16] as usize;
+        fields.push((name, desc[11], offset, width));
+        offset += width;
+        at += 32;
+    }
+
+    let mut records = Vec::with_capacity(num_records);
+    for i in 0..num_records {
+        let record = b.slice(header_len + i * record_len, record_len)?;
+        let mut props = Map::new();
+        if record[0] != b'*' {
+            for (name, kind, off, width) in &fields {
+                let raw = record.get(*off..off + width).ok_or_else(|| invalid("bad field width"))?;
+                let value = dbf_value(*kind, raw);
+                if !value.is_null() {
+                    props.insert(name.clone(), value);
+                }
+            }
+        }
+        records.push(props);
+    }
+    Ok(records)
+}
+
+/// Read an ESRI Shapefile from the bytes of its `.shp` and (optionally)
+/// `.dbf` parts. Each shape and its DBF attributes pass through the same
+/// property mapping as GeoJSON features (with DBF field names lower-cased),
+/// so name/type inference and purpose and `extra` extraction behave
+/// identically. Coordinates are assumed to be WGS84 (check the `.prj`).
+pub fn from_shapefile(shp: &[u8], dbf: Option<&[u8]>) -> Result<Vec<GeonPlace>, GeonError> {
+    let geometries = read_shp(shp)?;
+    let attributes = match dbf {
+        Some(dbf) => read_dbf(dbf)?,
+        None => Vec::new(),
+    };
+
+    Ok(geometries
+        .into_iter()
+        .enumerate()
+        .map(|(i, geometry)| {
+            let mut feature = Map::new();
+            feature.insert("type".to_string(), json!("Feature"));
+            feature.insert("geometry".to_string(), geometry);
+            let props = attributes.get(i).cloned().unwrap_or_default();
+            feature.insert("properties".to_string(), Value::Object(props));
+            feature_to_geon(&feature)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    fn shp_file(records: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (i, content) in records.iter().enumerate() {
+            body.extend((i as i32 + 1).to_be_bytes());
+            body.extend((content.len() as i32 / 2).to_be_bytes());
+            body.extend(content);
+        }
+        let mut out = vec![0u8; 100];
+        out[..4].copy_from_slice(&9994i32.to_be_bytes());
+        out[24..28].copy_from_slice((((100 + body.len()) / 2) as i32).to_be_bytes().as_slice());
+        out[28..32].copy_from_slice(&1000i32.to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn f64s(values: &[f64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn dbf_file(fields: &[(&str, u8, u8)], rows: &[&[&str]]) -> Vec<u8> {
+        let header_len = 32 + fields.len() * 32 + 1;
+        let record_len = 1 + fields.iter().map(|f| f.2 as usize).sum::<usize>();
+        let mut out = vec![0u8; 32];
+        out[0] = 3;
+        out[4..8].copy_from_slice(&(rows.len() as u32).to_le_bytes());
+        out[8..10].copy_from_slice(&(header_len as u16).to_le_bytes());
+        out[10..12].copy_from_slice(&(record_len as u16).to_le_bytes());
+        for (name, kind, width) in fields {
+            let mut desc = [0u8; 32];
+            desc[..name.len()].copy_from_slice(name.as_bytes());
+            desc[11] = *kind;
+            desc[16] = *width;
+            out.extend(desc);
+        }
+        out.push(0x0D);
+        for row in rows {
+            out.push(b' ');
+            for (value, (_, _, width)) in row.iter().zip(fields) {
+                out.extend(format!("{:<w$}", value, w = *width as usize).bytes());
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_point_and_polygon_with_attributes() {
+        let mut point = 1i32.to_le_bytes().to_vec();
+        point.extend(f64s(&[-1.15, 52.95]));
+
+        let mut polygon = 5i32.to_le_bytes().to_vec();
+        polygon.extend(f64s(&[0.0, 0.0, 1.0, 1.0]));
+        polygon.extend(1i32.to_le_bytes());
+        polygon.extend(4i32.to_le_bytes());
+        polygon.extend(0i32.to_le_bytes());
+        polygon.extend(f64s(&[0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]));
+
+        let shp = shp_file(&[point, polygon]);
+        let dbf = dbf_file(
+            &[("NAME", b'C', 20), ("LEISURE", b'C', 10), ("AREA_HA", b'N', 8)],
+            &[&["Market Cross", "", ""], &["Green", "park", "1.5"]],
+        );
+
+        let places = from_shapefile(&shp, Some(&dbf)).unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].place, "Market Cross");
+        assert_eq!(places[0].location, Some(Coordinate::new(52.95, -1.15)));
+        assert_eq!(places[1].place, "Green");
+        assert_eq!(places[1].type_, "public_space");
+        // The closing vertex is implicit in BOUNDARY
+        assert_eq!(places[1].boundary.len(), 3);
+    }
+
+    #[test]
+    fn test_rejects_non_shapefile() {
+        assert!(from_shapefile(b"not a shapefile at all", None).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use super::{extract_boundary, extract_centroid};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// Placetypes from smallest to largest, for ordering PART_OF chains
+const PLACETYPES: &[&str] = &[
+    "venue", "building", "address", "intersection", "campus", "microhood", "neighbourhood", "macrohood",
+    "borough", "postalcode", "locality", "localadmin", "county", "macrocounty", "region", "macroregion",
+    "dependency", "disputed", "country", "empire", "marinearea", "ocean", "continent",
+];
+
+/// GEON type for a Who's On First placetype.
+pub fn type_for(placetype: &str) -> String {
+    match placetype {
+        "venue" | "building" | "address" => "building",
+        "intersection" => "street",
+        "campus" => "hybrid",
+        "marinearea" | "ocean" | "lake" => "natural_feature",
+        _ if PLACETYPES.contains(&placetype) => "district",
+        _ => "hybrid",
+    }
+    .to_string()
+}
+
+fn wof_id(v: &Value) -> Option<i64> {
+    match v {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+// EDTF "unknown" markers carry no date
+fn edtf(props: &Map<String, Value>, key: &str) -> Option<String> {
+    let v = props.get(key)?.as_str()?;
+    (!v.is_empty() && !matches!(v, "u" | "uuuu" | "..")).then(|| v.to_string())
+}
+
+/// Ancestor IDs from the first `wof:hierarchy`, smallest placetype first,
+/// excluding the record itself.
+pub fn hierarchy_ids(record: &Value) -> Vec<i64> {
+    let own = record.get("id").and_then(wof_id);
+    let Some(hierarchy) = record.pointer("/properties/wof:hierarchy/0").and_then(|v| v.as_object()) else {
+        return vec![];
+    };
+    let mut ancestors: Vec<(usize, i64)> = hierarchy
+        .iter()
+        .filter_map(|(key, id)| {
+            let rank = PLACETYPES.iter().position(|t| key.strip_suffix("_id") == Some(*t))?;
+            Some((rank, wof_id(id).filter(|id| *id > 0 && Some(*id) != own)?))
+        })
+        .collect();
+    ancestors.sort();
+    ancestors.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Convert one Who's On First GeoJSON record.
+///
+/// - `wof:placetype` sets TYPE and is kept as `extra["placetype"]`
+/// - the hierarchy becomes PART_OF, naming ancestors through `names` and
+///   falling back to `wof:<id>` (see [`from_wof_records`])
+/// - `name:*_x_preferred` labels go to `extra["labels"]` by language
+/// - `wof:concordances` go to `extra["concordances"]`, and a Wikidata
+///   concordance also to `extra["wikidata"]`
+/// - `edtf:inception`/`edtf:cessation` fill LIFESPAN
+pub fn from_wof_record(record: &Value, names: &HashMap<i64, String>) -> GeonPlace {
+    let empty = Map::new();
+    let props = record.get("properties").and_then(|v| v.as_object()).unwrap_or(&empty);
+    let geom = record.get("geometry").and_then(|v| v.as_object()).unwrap_or(&empty);
+    let text = |key: &str| props.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+    let num = |key: &str| props.get(key).and_then(|v| v.as_f64());
+
+    let mut p = GeonPlace::default();
+    p.place = text("wof:name").unwrap_or("Unnamed").to_string();
+    let placetype = text("wof:placetype").unwrap_or_default();
+    p.type_ = type_for(placetype);
+    if !placetype.is_empty() {
+        p.extra.insert("placetype".to_string(), Value::String(placetype.to_string()));
+    }
+
+    let id = record.get("id").or_else(|| props.get("wof:id")).and_then(wof_id);
+    match id {
+        Some(id) => {
+            p.id = Some(format!("wof:{}", id));
+            p.source = vec![format!("Who's On First ({})", id)];
+        }
+        None => p.source = vec!["Who's On First".to_string()],
+    }
+
+    p.location = match (num("lbl:latitude"), num("lbl:longitude")) {
+        (Some(lat), Some(lon)) => Some(Coordinate::new(lat, lon)),
+        _ => match (num("geom:latitude"), num("geom:longitude")) {
+            (Some(lat), Some(lon)) => Some(Coordinate::new(lat, lon)),
+            _ => extract_centroid(geom),
+        },
+    };
+    p.boundary = extract_boundary(geom);
+    let bbox: Vec<f64> = text("geom:bbox")
+        .map(|b| b.split(',').filter_map(|v| v.trim().parse().ok()).collect())
+        .unwrap_or_default();
+    if let [west, south, east, north] = bbox[..] {
+        p.extent = Some(Extent { north, south, east, west });
+    }
+    if let Some(area) = num("geom:area_square_m").filter(|a| *a > 0.0) {
+        p.area = Some(format!("{} sqm", area.round()));
+    }
+
+    let chain: Vec<String> = hierarchy_ids(record)
+        .into_iter()
+        .map(|id| names.get(&id).cloned().unwrap_or_else(|| format!("wof:{}", id)))
+        .collect();
+    p.part_of = Some(chain.join(", ")).filter(|s| !s.is_empty());
+
+    let mut labels = Map::new();
+    for (key, value) in props {
+        if let Some(lang) = key.strip_prefix("name:").and_then(|k| k.strip_suffix("_x_preferred")) {
+            if let Some(name) = value.get(0).filter(|v| v.is_string()) {
+                labels.insert(lang.to_string(), name.clone());
+            }
+        }
+    }
+    if !labels.is_empty() {
+        p.extra.insert("labels".to_string(), Value::Object(labels));
+    }
+
+    if let Some(concordances) = props.get("wof:concordances").and_then(|v| v.as_object()) {
+        if let Some(qid) = concordances.get("wd:id").and_then(|v| v.as_str()) {
+            p.extra.insert("wikidata".to_string(), Value::String(qid.to_string()));
+        }
+        if !concordances.is_empty() {
+            p.extra.insert("concordances".to_string(), Value::Object(concordances.clone()));
+        }
+    }
+
+    if let Some(inception) = edtf(props, "edtf:inception") {
+        p.lifespan.insert("established".to_string(), inception);
+    }
+    if let Some(cessation) = edtf(props, "edtf:cessation") {
+        p.lifespan.insert("ceased".to_string(), cessation);
+    }
+    p
+}
+
+/// Convert a batch of Who's On First records, naming PART_OF ancestors
+/// from the other records in the batch.
+pub fn from_wof_records(records: &[Value]) -> Vec<GeonPlace> {
+    let names: HashMap<i64, String> = records
+        .iter()
+        .filter_map(|r| {
+            let id = r.get("id").and_then(wof_id)?;
+            let name = r.pointer("/properties/wof:name")?.as_str()?;
+            Some((id, name.to_string()))
+        })
+        .collect();
+    records.iter().map(|r| from_wof_record(r, &names)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_wof_hierarchy() {
+        let records = vec![
+            json!({
+                "id": 85682555, "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": [-122.4151, 37.7599]},
+                "properties": {
+                    "wof:name": "Mission District",
+                    "wof:placetype": "neighbourhood",
+                    "wof:hierarchy": [{
+                        "neighbourhood_id": 85682555, "locality_id": 85922583,
+                        "region_id": 85688637, "country_id": 85633793, "continent_id": -1
+                    }],
+                    "name:eng_x_preferred": ["Mission District"],
+                    "name:spa_x_preferred": ["La Misión"],
+                    "wof:concordances": {"wd:id": "Q7586", "gp:id": 2452000},
+                    "geom:bbox": "-122.43,37.74,-122.40,37.77",
+                    "edtf:inception": "uuuu"
+                }
+            }),
+            json!({"id": 85922583, "properties": {"wof:name": "San Francisco", "wof:placetype": "locality"}}),
+            json!({"id": 85633793, "properties": {"wof:name": "United States", "wof:placetype": "country"}}),
+        ];
+        let places = from_wof_records(&records);
+        let p = &places[0];
+        assert_eq!(p.id.as_deref(), Some("wof:85682555"));
+        assert_eq!(p.type_, "district");
+        assert_eq!(p.part_of.as_deref(), Some("San Francisco, wof:85688637, United States"));
+        assert_eq!(p.location, Some(Coordinate::new(37.7599, -122.4151)));
+        assert_eq!(p.extent.as_ref().map(|e| e.north), Some(37.77));
+        assert_eq!(p.extra["labels"]["spa"], "La Misión");
+        assert_eq!(p.extra["wikidata"], "Q7586");
+        assert_eq!(p.extra["concordances"]["gp:id"], 2452000);
+        assert!(p.lifespan.is_empty());
+        assert_eq!(places[2].part_of, None);
+    }
+}
@@ -0,0 +1,222 @@
+// Minimal pull reader for the XML formats the converters import. It handles
+// elements, attributes, text, CDATA and the predefined/numeric entities;
+// comments, processing instructions and doctypes are skipped. Close tags
+// must match the element they close.
+
+use crate::parser::GeonError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum XmlEvent<'a> {
+    Start {
+        name: &'a str,
+        attrs: Vec<(&'a str, String)>,
+        self_closing: bool,
+    },
+    End {
+        name: &'a str,
+    },
+    Text(String),
+}
+
+/// Strip any namespace prefix: `gml:posList` -> `posList`.
+pub(super) fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+pub(super) fn attr<'b>(attrs: &'b [(&str, String)], key: &str) -> Option<&'b str> {
+    attrs.iter().find(|(k, _)| *k == key || local_name(k) == key).map(|(_, v)| v.as_str())
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match decoded {
+            Some(ch) => {
+                out.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub(super) struct XmlReader<'a> {
+    text: &'a str,
+    pos: usize,
+    // Names of the elements currently open, innermost last
+    open: Vec<&'a str>,
+}
+
+impl<'a> XmlReader<'a> {
+    pub(super) fn new(text: &'a str) -> Self {
+        Self { text, pos: 0, open: Vec::new() }
+    }
+
+    fn error(&self, msg: &str) -> GeonError {
+        GeonError::InvalidStructure(format!("XML {} at offset {}", msg, self.pos))
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, GeonError> {
+        match self.text[self.pos..].find(end) {
+            Some(idx) => {
+                let skipped = &self.text[self.pos..self.pos + idx];
+                self.pos += idx + end.len();
+                Ok(skipped)
+            }
+            None => Err(self.error(&format!("missing '{}'", end))),
+        }
+    }
+
+    // Body of the tag starting at `self.pos`, up to the first '>' outside a
+    // quoted attribute value
+    fn tag_body(&mut self) -> Result<&'a str, GeonError> {
+        let start = self.pos;
+        let mut quote = None;
+        for (i, b) in self.text.as_bytes()[start..].iter().enumerate() {
+            match (quote, b) {
+                (None, b'"' | b'\'') => quote = Some(*b),
+                (Some(q), _) if q == *b => quote = None,
+                (None, b'>') => {
+                    self.pos = start + i + 1;
+                    return Ok(&self.text[start..start + i]);
+                }
+                _ => {}
+            }
+        }
+        Err(self.error(if quote.is_some() { "unterminated attribute" } else { "missing '>'" }))
+    }
+
+    fn parse_tag(&mut self) -> Result<XmlEvent<'a>, GeonError> {
+        // `self.pos` is just past '<'
+        let body = self.tag_body()?;
+        if let Some(name) = body.strip_prefix('/') {
+            let name = name.trim();
+            return match self.open.pop() {
+                Some(open) if open == name => Ok(XmlEvent::End { name }),
+                Some(open) => Err(self.error(&format!("close tag </{}> does not match <{}>", name, open))),
+                None => Err(self.error(&format!("close tag </{}> without an open tag", name))),
+            };
+        }
+        let (body, self_closing) = match body.strip_suffix('/') {
+            Some(b) => (b, true),
+            None => (body, false),
+        };
+        let name_end = body.find(|c: char| c.is_whitespace()).unwrap_or(body.len());
+        let name = &body[..name_end];
+
+        let mut attrs = Vec::new();
+        let mut rest = body[name_end..].trim_start();
+        while !rest.is_empty() {
+            let eq = rest.find('=').ok_or_else(|| self.error("malformed attribute"))?;
+            let key = rest[..eq].trim();
+            let after = rest[eq + 1..].trim_start();
+            let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'');
+            let quote = quote.ok_or_else(|| self.error("unquoted attribute"))?;
+            let close = after[1..].find(quote).ok_or_else(|| self.error("unterminated attribute"))?;
+            attrs.push((key, unescape(&after[1..1 + close])));
+            rest = after[close + 2..].trim_start();
+        }
+        if !self_closing {
+            self.open.push(name);
+        }
+        Ok(XmlEvent::Start { name, attrs, self_closing })
+    }
+}
+
+impl<'a> Iterator for XmlReader<'a> {
+    type Item = Result<XmlEvent<'a>, GeonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos >= self.text.len() {
+                // Report an unclosed element once, then finish
+                let unclosed = self.open.drain(..).next_back()?;
+                return Some(Err(self.error(&format!("unclosed <{}>", unclosed))));
+            }
+            let rest = &self.text[self.pos..];
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                let text = rest[..end].trim();
+                if !text.is_empty() {
+                    return Some(Ok(XmlEvent::Text(unescape(text))));
+                }
+                continue;
+            }
+            if rest.starts_with("<![CDATA[") {
+                self.pos += 9;
+                return Some(self.skip_past("]]>").map(|t| XmlEvent::Text(t.to_string())));
+            }
+            let skip_to = if rest.starts_with("<!--") {
+                Some("-->")
+            } else if rest.starts_with("<?") {
+                Some("?>")
+            } else if rest.starts_with("<!") {
+                Some(">")
+            } else {
+                None
+            };
+            if let Some(end) = skip_to {
+                if let Err(e) = self.skip_past(end) {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+            self.pos += 1;
+            return Some(self.parse_tag());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(text: &str) -> Result<Vec<XmlEvent<'_>>, GeonError> {
+        XmlReader::new(text).collect()
+    }
+
+    #[test]
+    fn test_xml_reader_tags() {
+        let parsed = events(r#"<a title="x > y" note='say "hi"'><b/>text</a>"#).unwrap();
+        assert_eq!(
+            parsed[0],
+            XmlEvent::Start {
+                name: "a",
+                attrs: vec![("title", "x > y".to_string()), ("note", "say \"hi\"".to_string())],
+                self_closing: false,
+            }
+        );
+        assert_eq!(parsed[1], XmlEvent::Start { name: "b", attrs: vec![], self_closing: true });
+        assert_eq!(parsed[2], XmlEvent::Text("text".to_string()));
+        assert_eq!(parsed[3], XmlEvent::End { name: "a" });
+
+        assert!(events("<a><b></a></b>").unwrap_err().to_string().contains("</a> does not match <b>"));
+        assert!(events("<a></a></b>").is_err());
+        assert!(events("<a><b></b>").unwrap_err().to_string().contains("unclosed <a>"));
+        assert!(events(r#"<a title="x>"#).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! Enrichment of existing places from external knowledge sources.
+//!
+//! Each source splits into a pure mapping step, which applies an already
+//! fetched record to a place, and (behind the `http` feature) an async
+//! fetch step that looks the record up.
+
+pub mod admin;
+pub mod elevation;
+pub mod nominatim;
+pub mod sensors;
+pub mod wikidata;
+
+#[cfg(feature = "http")]
+pub use admin::reverse_part_of;
+#[cfg(feature = "http")]
+pub use elevation::elevation;
+#[cfg(feature = "http")]
+pub use nominatim::geocode;
+#[cfg(feature = "http")]
+pub use sensors::from_sensorthings;
@@ -0,0 +1,111 @@
+use crate::geometry::{ring_area, ring_contains};
+use crate::models::GeonPlace;
+use serde_json::{Map, Value};
+
+// Nominatim address components from the most local to the widest
+const ADDRESS_LEVELS: &[&str] = &[
+    "neighbourhood", "quarter", "suburb", "city_district", "hamlet", "village", "town", "city",
+    "municipality", "county", "state_district", "state", "region", "country",
+];
+
+/// The containing areas named in a structured address (as returned by
+/// Nominatim with `addressdetails=1`), most local first, skipping `own_name`.
+pub fn part_of_chain(address: &Map<String, Value>, own_name: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    for level in ADDRESS_LEVELS {
+        if let Some(name) = address.get(*level).and_then(|v| v.as_str()) {
+            if name != own_name && !chain.iter().any(|c| c == name) {
+                chain.push(name.to_string());
+            }
+        }
+    }
+    chain
+}
+
+/// Fill PART_OF from a local collection of boundary places (districts,
+/// cities, regions ...): every boundary containing the place's LOCATION,
+/// smallest first, joined as `"Lenton, Nottingham, England"`. Returns
+/// whether any containing boundary was found.
+pub fn part_of_from_boundaries(place: &mut GeonPlace, boundaries: &[GeonPlace]) -> bool {
+    let Some(location) = &place.location else { return false };
+    let mut containing: Vec<&GeonPlace> = boundaries
+        .iter()
+        .filter(|b| b.boundary.len() >= 3 && b.place != place.place && ring_contains(&b.boundary, location))
+        .collect();
+    if containing.is_empty() {
+        return false;
+    }
+    containing.sort_by(|a, b| ring_area(&a.boundary).total_cmp(&ring_area(&b.boundary)));
+    let chain: Vec<&str> = containing.iter().map(|b| b.place.as_str()).collect();
+    place.part_of = Some(chain.join(", "));
+    true
+}
+
+#[cfg(feature = "http")]
+mod fetch {
+    use super::part_of_chain;
+    use crate::models::GeonPlace;
+    use crate::parser::GeonError;
+    use serde_json::Value;
+
+    const REVERSE_URL: &str = "https://nominatim.openstreetmap.org/reverse";
+
+    /// Reverse-geocode the place's LOCATION with Nominatim and fill PART_OF
+    /// with the containing neighbourhood/city/region chain. Returns whether
+    /// PART_OF was set. See [`geocode`](crate::enrich::geocode) for the
+    /// public instance's usage policy.
+    pub async fn reverse_part_of(place: &mut GeonPlace, client: &reqwest::Client) -> Result<bool, GeonError> {
+        let Some(location) = &place.location else { return Ok(false) };
+        let (lat, lon) = (location.lat.to_string(), location.lon.to_string());
+        let params = [("lat", lat.as_str()), ("lon", lon.as_str()), ("format", "jsonv2"), ("addressdetails", "1")];
+        let url = reqwest::Url::parse_with_params(REVERSE_URL, &params)
+            .map_err(|e| GeonError::InvalidStructure(e.to_string()))?;
+        let result: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+
+        let Some(address) = result.get("address").and_then(|a| a.as_object()) else { return Ok(false) };
+        let chain = part_of_chain(address, &place.place);
+        if chain.is_empty() {
+            return Ok(false);
+        }
+        place.part_of = Some(chain.join(", "));
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "http")]
+pub use fetch::reverse_part_of;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+    use serde_json::json;
+
+    fn square(name: &str, half: f64) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.boundary = vec![
+            Coordinate::new(-half, -half),
+            Coordinate::new(-half, half),
+            Coordinate::new(half, half),
+            Coordinate::new(half, -half),
+        ];
+        p
+    }
+
+    #[test]
+    fn test_part_of_from_boundaries() {
+        let boundaries = vec![square("England", 10.0), square("Lenton", 1.0), square("Nottingham", 3.0), square("Elsewhere", 0.1)];
+        let mut place = GeonPlace::default();
+        place.location = Some(Coordinate::new(0.5, 0.5));
+        assert!(part_of_from_boundaries(&mut place, &boundaries));
+        assert_eq!(place.part_of.as_deref(), Some("Lenton, Nottingham, England"));
+    }
+
+    #[test]
+    fn test_part_of_chain() {
+        let address = json!({"park": "Wollaton Park", "suburb": "Wollaton", "city": "Nottingham", "state": "England", "country": "United Kingdom"});
+        let chain = part_of_chain(address.as_object().unwrap(), "Wollaton Park");
+        assert_eq!(chain, vec!["Wollaton", "Nottingham", "England", "United Kingdom"]);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::geometry::METRES_PER_DEGREE;
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+
+/// A geographic elevation grid in metres, sampled at regularly spaced
+/// points from `origin` (the north-west sample) southwards and eastwards.
+#[derive(Debug, Clone)]
+pub struct Dem {
+    /// Name recorded in SOURCE, e.g. `SRTM N52W002`.
+    pub name: String,
+    pub origin: Coordinate,
+    /// Degrees between rows (southwards) and columns (eastwards).
+    pub step: (f64, f64),
+    pub cols: usize,
+    pub rows: usize,
+    pub nodata: Option<f64>,
+    /// Row-major, north to south.
+    pub values: Vec<f64>,
+}
+
+/// Anything that gives the ground height in metres at a coordinate, such
+/// as a [`Dem`], for terrain analysis in [`analysis`](crate::analysis).
+pub trait DemSource {
+    /// `None` where there is no data.
+    fn elevation_at(&self, at: &Coordinate) -> Option<f64>;
+
+    /// Spacing in metres at which terrain is worth sampling along a line.
+    fn resolution_m(&self) -> f64 {
+        30.0
+    }
+}
+
+impl DemSource for Dem {
+    fn elevation_at(&self, at: &Coordinate) -> Option<f64> {
+        self.sample(at)
+    }
+
+    fn resolution_m(&self) -> f64 {
+        self.step.0 * METRES_PER_DEGREE
+    }
+}
+
+fn invalid(msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("DEM: {}", msg))
+}
+
+impl Dem {
+    /// Read an ESRI ASCII grid (`.asc`) in geographic coordinates.
+    pub fn from_ascii_grid(text: &str, name: &str) -> Result<Self, GeonError> {
+        let mut header = std::collections::HashMap::new();
+        let mut lines = text.lines().peekable();
+        while let Some(line) = lines.peek() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value), None) = (parts.next(), parts.next(), parts.next()) else { break };
+            if key.parse::<f64>().is_ok() {
+                break;
+            }
+            header.insert(key.to_ascii_lowercase(), value.parse::<f64>()?);
+            lines.next();
+        }
+        let get = |key: &str| header.get(key).copied().ok_or_else(|| invalid(&format!("missing {}", key)));
+        let (cols, rows, cell) = (get("ncols")? as usize, get("nrows")? as usize, get("cellsize")?);
+        // Corner registration is offset by half a cell from the first sample
+        let (west, south) = match (header.get("xllcenter"), header.get("yllcenter")) {
+            (Some(x), Some(y)) => (*x, *y),
+            _ => (get("xllcorner")? + cell / 2.0, get("yllcorner")? + cell / 2.0),
+        };
+        let values = lines
+            .flat_map(str::split_whitespace)
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() != cols * rows {
+            return Err(invalid(&format!("expected {} values, found {}", cols * rows, values.len())));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            origin: Coordinate::new(south + (rows - 1) as f64 * cell, west),
+            step: (cell, cell),
+            cols,
+            rows,
+            nodata: header.get("nodata_value").copied(),
+            values,
+        })
+    }
+
+    /// Read an SRTM `.hgt` tile (big-endian 16-bit, 1201² or 3601² samples)
+    /// named like `N52W002`, which gives its south-west corner.
+    pub fn from_hgt(bytes: &[u8], tile: &str) -> Result<Self, GeonError> {
+        let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+        if size < 2 || size * size * 2 != bytes.len() {
+            return Err(invalid("HGT tile is not a square grid of 16-bit samples"));
+        }
+        let tile = tile.trim_end_matches(".hgt");
+        let sign = |c: Option<char>, neg: char| if c == Some(neg) { -1.0 } else { 1.0 };
+        let (lat, lon) = match (tile.get(1..3).map(str::parse::<f64>), tile.get(4..7).map(str::parse::<f64>)) {
+            (Some(Ok(lat)), Some(Ok(lon))) => {
+                (lat * sign(tile.chars().next(), 'S'), lon * sign(tile.chars().nth(3), 'W'))
+            }
+            _ => return Err(invalid(&format!("bad HGT tile name '{}'", tile))),
+        };
+        let step = 1.0 / (size - 1) as f64;
+        Ok(Self {
+            name: format!("SRTM {}", tile),
+            origin: Coordinate::new(lat + 1.0, lon),
+            step: (step, step),
+            cols: size,
+            rows: size,
+            nodata: Some(-32768.0),
+            values: bytes.chunks_exact(2).map(|b| i16::from_be_bytes([b[0], b[1]]) as f64).collect(),
+        })
+    }
+
+    fn at(&self, row: usize, col: usize) -> Option<f64> {
+        let v = *self.values.get(row * self.cols + col)?;
+        (Some(v) != self.nodata).then_some(v)
+    }
+
+    /// Bilinearly interpolated elevation, or the nearest sample where a
+    /// neighbour is missing. `None` outside the grid.
+    pub fn sample(&self, at: &Coordinate) -> Option<f64> {
+        let r = (self.origin.lat - at.lat) / self.step.0;
+        let c = (at.lon - self.origin.lon) / self.step.1;
+        let (max_r, max_c) = ((self.rows - 1) as f64, (self.cols - 1) as f64);
+        if !(0.0..=max_r).contains(&r) || !(0.0..=max_c).contains(&c) {
+            return None;
+        }
+        let (r0, c0) = (r.floor() as usize, c.floor() as usize);
+        let (r1, c1) = ((r0 + 1).min(self.rows - 1), (c0 + 1).min(self.cols - 1));
+        let (fr, fc) = (r - r0 as f64, c - c0 as f64);
+        match (self.at(r0, c0), self.at(r0, c1), self.at(r1, c0), self.at(r1, c1)) {
+            (Some(a), Some(b), Some(d), Some(e)) => {
+                Some((a * (1.0 - fc) + b * fc) * (1.0 - fr) + (d * (1.0 - fc) + e * fc) * fr)
+            }
+            _ => self.at(r.round() as usize, c.round() as usize),
+        }
+    }
+}
+
+/// Set ELEVATION to `metres` (as `"<n> m"`) and record `source`.
+pub fn apply_elevation(place: &mut GeonPlace, metres: f64, source: &str) {
+    place.elevation = Some(format!("{} m", (metres * 10.0).round() / 10.0));
+    if !place.source.iter().any(|s| s == source) {
+        place.source.push(source.to_string());
+    }
+}
+
+/// Fill ELEVATION at LOCATION from a local DEM. Returns whether the place
+/// lies on the grid.
+pub fn elevation_from_dem(place: &mut GeonPlace, dem: &Dem) -> bool {
+    let Some(metres) = place.location.as_ref().and_then(|at| dem.sample(at)) else { return false };
+    apply_elevation(place, metres, &format!("{} (DEM)", dem.name));
+    true
+}
+
+#[cfg(feature = "http")]
+mod fetch {
+    use super::apply_elevation;
+    use crate::models::GeonPlace;
+    use crate::parser::GeonError;
+    use serde_json::Value;
+
+    /// Open-Meteo elevation API, backed by the Copernicus GLO-90 DEM.
+    pub const ELEVATION_URL: &str = "https://api.open-meteo.com/v1/elevation";
+
+    /// Fill ELEVATION at LOCATION from the Open-Meteo elevation API. Returns
+    /// whether an elevation was found; places without LOCATION are skipped.
+    pub async fn elevation(place: &mut GeonPlace, client: &reqwest::Client) -> Result<bool, GeonError> {
+        let Some(at) = &place.location else { return Ok(false) };
+        let url = reqwest::Url::parse_with_params(
+            ELEVATION_URL,
+            &[("latitude", at.lat.to_string()), ("longitude", at.lon.to_string())],
+        )
+        .map_err(|e| GeonError::InvalidStructure(e.to_string()))?;
+        let resp: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+        let Some(metres) = resp.pointer("/elevation/0").and_then(|v| v.as_f64()) else { return Ok(false) };
+        apply_elevation(place, metres, "Copernicus DEM GLO-90 (Open-Meteo)");
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "http")]
+pub use fetch::{elevation, ELEVATION_URL};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_grid_sample() {
+        let asc = "ncols 3\nnrows 2\nxllcorner -1.2\nyllcorner 52.9\ncellsize 0.1\nNODATA_value -9999\n50 60 70\n30 40 -9999\n";
+        let dem = Dem::from_ascii_grid(asc, "test grid").unwrap();
+        assert_eq!(dem.origin, Coordinate::new(53.05, -1.15));
+
+        // Halfway between the first two samples of the top row
+        assert!((dem.sample(&Coordinate::new(53.05, -1.10)).unwrap() - 55.0).abs() < 1e-9);
+        // Centre of the top-left four samples
+        assert!((dem.sample(&Coordinate::new(53.0, -1.10)).unwrap() - 45.0).abs() < 1e-9);
+        // Touches the no-data cell: nearest sample instead
+        assert_eq!(dem.sample(&Coordinate::new(52.96, -0.96)), None);
+        assert_eq!(dem.sample(&Coordinate::new(53.02, -0.97)), Some(70.0));
+
+        let mut place = GeonPlace::default();
+        place.location = Some(Coordinate::new(53.05, -1.10));
+        assert!(elevation_from_dem(&mut place, &dem));
+        assert_eq!(place.elevation.as_deref(), Some("55 m"));
+        assert_eq!(place.source, vec!["test grid (DEM)"]);
+    }
+
+    #[test]
+    fn test_hgt_tile() {
+        let samples: [i16; 4] = [100, 200, 300, 400];
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        let dem = Dem::from_hgt(&bytes, "N52W002.hgt").unwrap();
+        assert_eq!(dem.origin, Coordinate::new(53.0, -2.0));
+        assert_eq!(dem.sample(&Coordinate::new(53.0, -1.5)), Some(150.0));
+        assert_eq!(dem.sample(&Coordinate::new(52.5, -1.5)), Some(250.0));
+        assert_eq!(dem.sample(&Coordinate::new(51.9, -1.5)), None);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::converter::infer_type;
+use crate::models::{Coordinate, Extent, GeonPlace};
+use serde_json::{Map, Value};
+
+// Nominatim returns numbers as strings ("52.95")
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+/// Convert one Nominatim search result (`format=jsonv2` or `json`, ideally
+/// with `addressdetails=1`).
+///
+/// The result's class/type pair (`leisure`/`park`) goes through the same
+/// type mapping as OSM tags; LOCATION comes from `lat`/`lon`, EXTENT from
+/// `boundingbox`, and the structured address is kept in `extra["address"]`.
+pub fn from_nominatim_result(result: &Value) -> GeonPlace {
+    let mut p = GeonPlace::default();
+
+    let class = result.get("category").or_else(|| result.get("class")).and_then(|v| v.as_str());
+    let kind = result.get("type").and_then(|v| v.as_str());
+    if let (Some(class), Some(kind)) = (class, kind) {
+        let mut tags = Map::new();
+        tags.insert(class.to_string(), Value::String(kind.to_string()));
+        p.type_ = infer_type(&tags);
+        p.purpose = vec![format!("{}: {}", class, kind)];
+    } else {
+        p.type_ = "hybrid".to_string();
+    }
+
+    let display_name = result.get("display_name").and_then(|v| v.as_str()).unwrap_or("");
+    p.place = result
+        .get("name")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .or_else(|| display_name.split(',').next().map(str::trim).filter(|s| !s.is_empty()))
+        .unwrap_or("Unnamed")
+        .to_string();
+
+    if let (Some(lat), Some(lon)) = (result.get("lat").and_then(number), result.get("lon").and_then(number)) {
+        p.location = Some(Coordinate::new(lat, lon));
+    }
+    // boundingbox is [south, north, west, east]
+    if let Some(bbox) = result.get("boundingbox").and_then(|v| v.as_array()) {
+        let values: Vec<f64> = bbox.iter().filter_map(number).collect();
+        if let [south, north, west, east] = values[..] {
+            p.extent = Some(Extent { north, south, east, west });
+        }
+    }
+
+    let osm_type = result.get("osm_type").and_then(|v| v.as_str());
+    let osm_id = result.get("osm_id").and_then(|v| v.as_i64());
+    if let (Some(kind), Some(id)) = (osm_type, osm_id) {
+        p.id = Some(format!("osm:{}/{}", kind, id));
+        p.source = vec![format!("OpenStreetMap via Nominatim ({}/{})", kind, id)];
+    } else {
+        p.source = vec!["OpenStreetMap via Nominatim".to_string()];
+    }
+
+    if let Some(address) = result.get("address").filter(|a| a.is_object()) {
+        p.extra.insert("address".to_string(), address.clone());
+    }
+    if !display_name.is_empty() {
+        p.extra.insert("display_name".to_string(), Value::String(display_name.to_string()));
+    }
+    if let Some(importance) = result.get("importance").and_then(number) {
+        p.confidence.insert("geocode_importance".to_string(), format!("{:.2}", importance));
+    }
+    p
+}
+
+#[cfg(feature = "http")]
+mod fetch {
+    use super::from_nominatim_result;
+    use crate::models::GeonPlace;
+    use crate::parser::GeonError;
+    use serde_json::Value;
+
+    const SEARCH_URL: &str = "https://nominatim.openstreetmap.org/search";
+
+    /// Geocode a name or address with Nominatim, most relevant result first.
+    ///
+    /// The public instance requires an identifying User-Agent (set one on
+    /// the client) and at most one request per second.
+    pub async fn geocode(query: &str, client: &reqwest::Client) -> Result<Vec<GeonPlace>, GeonError> {
+        let params = [("q", query), ("format", "jsonv2"), ("addressdetails", "1"), ("limit", "10")];
+        let url = reqwest::Url::parse_with_params(SEARCH_URL, &params)
+            .map_err(|e| GeonError::InvalidStructure(e.to_string()))?;
+        let results: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+        Ok(results.as_array().map(|r| r.iter().map(from_nominatim_result).collect()).unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "http")]
+pub use fetch::geocode;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nominatim_result() {
+        let result = json!({
+            "osm_type": "way", "osm_id": 23975306,
+            "lat": "52.9483", "lon": "-1.2053",
+            "category": "leisure", "type": "park",
+            "importance": 0.4521,
+            "name": "Wollaton Park",
+            "display_name": "Wollaton Park, Wollaton, Nottingham, England, United Kingdom",
+            "address": {"park": "Wollaton Park", "city": "Nottingham", "country_code": "gb"},
+            "boundingbox": ["52.9389", "52.9555", "-1.2205", "-1.1894"]
+        });
+        let place = from_nominatim_result(&result);
+        assert_eq!(place.place, "Wollaton Park");
+        assert_eq!(place.type_, "public_space");
+        assert_eq!(place.id.as_deref(), Some("osm:way/23975306"));
+        assert_eq!(place.location, Some(Coordinate::new(52.9483, -1.2053)));
+        assert_eq!(place.extent, Some(Extent { north: 52.9555, south: 52.9389, east: -1.1894, west: -1.2205 }));
+        assert_eq!(place.extra["address"]["city"], "Nottingham");
+        assert_eq!(place.confidence["geocode_importance"], "0.45");
+    }
+}
@@ -0,0 +1,246 @@
+use crate::converter::csv::read_records;
+use crate::geometry::haversine_m;
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+
+/// A time series of numeric observations of one property by one sensor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    /// Observed property, e.g. `footfall` or `noise level`.
+    pub property: String,
+    pub unit: Option<String>,
+    /// Sensor name recorded in SOURCE.
+    pub sensor: String,
+    pub location: Option<Coordinate>,
+    /// `(ISO 8601 time, value)` pairs.
+    pub observations: Vec<(String, f64)>,
+}
+
+fn point(geometry: &Value) -> Option<Coordinate> {
+    let c = geometry.get("coordinates")?.as_array()?;
+    Some(Coordinate::new(c.get(1)?.as_f64()?, c.first()?.as_f64()?))
+}
+
+/// Read a SensorThings Datastream expanded with `Thing/Locations`,
+/// `ObservedProperty` and `Observations`. Observations whose result is not
+/// numeric are dropped; interval phenomenon times use their start.
+pub fn from_sensorthings_datastream(datastream: &Value) -> Option<Series> {
+    let text = |path: &str| datastream.pointer(path).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+    let property = text("/ObservedProperty/name").or(text("/name"))?.to_string();
+    let unit = text("/unitOfMeasurement/symbol").or(text("/unitOfMeasurement/name")).map(str::to_string);
+    let sensor = text("/Thing/name").or(text("/Sensor/name")).unwrap_or("SensorThings sensor").to_string();
+    let location = datastream
+        .pointer("/Thing/Locations")
+        .and_then(|v| v.as_array())
+        .and_then(|locations| locations.iter().find_map(|l| l.get("location").and_then(point)));
+
+    let observations = datastream
+        .get("Observations")
+        .and_then(|v| v.as_array())
+        .map(|obs| {
+            obs.iter()
+                .filter_map(|o| {
+                    let time = o.get("phenomenonTime")?.as_str()?.split('/').next()?.to_string();
+                    let value = match o.get("result")? {
+                        Value::Number(n) => n.as_f64()?,
+                        Value::String(s) => s.trim().parse().ok()?,
+                        _ => return None,
+                    };
+                    Some((time, value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(Series { property, unit, sensor, location, observations })
+}
+
+/// Read a two-column CSV time series (`time,value` with a header row).
+pub fn from_csv_series(text: &str, property: &str, sensor: &str) -> Result<Series, GeonError> {
+    let mut observations = Vec::new();
+    for record in read_records(text).into_iter().skip(1) {
+        let (Some(time), Some(value)) = (record.first(), record.get(1)) else { continue };
+        if value.trim().is_empty() {
+            continue;
+        }
+        observations.push((time.trim().to_string(), value.trim().parse()?));
+    }
+    Ok(Series { property: property.to_string(), sensor: sensor.to_string(), observations, ..Default::default() })
+}
+
+// Day of week for an ISO date prefix, 0 = Sunday
+fn weekday(time: &str) -> Option<u32> {
+    let mut parts = time.get(..10)?.split('-');
+    let (y, m, d): (i64, i64, i64) = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    // Days since 1970-01-01 (a Thursday), from Howard Hinnant's civil algorithm
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some((days + 4).rem_euclid(7) as u32)
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn confidence(n: usize) -> &'static str {
+    match n {
+        0..=9 => "low",
+        10..=99 => "medium",
+        _ => "high",
+    }
+}
+
+/// Summarise a series into TEMPORAL if its sensor is within `max_distance_m`
+/// of the place (series without a location are taken to be at the place).
+///
+/// Writes `<property>_measured` (the overall mean with unit, count and time
+/// span) and, where observations fall on both, `weekday_<property>` and
+/// `weekend_<property>` means. The sensor is added to SOURCE and a
+/// count-based rating to `CONFIDENCE.<property>_measured`. Returns whether
+/// anything was written.
+pub fn summarise(place: &mut GeonPlace, series: &Series, max_distance_m: f64) -> bool {
+    if let (Some(at), Some(sensor)) = (&place.location, &series.location) {
+        if haversine_m(at, sensor) > max_distance_m {
+            return false;
+        }
+    }
+    let values: Vec<f64> = series.observations.iter().map(|(_, v)| *v).collect();
+    let Some(overall) = mean(&values) else { return false };
+
+    let key = series.property.trim().to_lowercase().replace([' ', '-'], "_");
+    let unit = series.unit.as_deref().map(|u| format!(" {}", u)).unwrap_or_default();
+    let format = |v: f64| format!("{}{}", (v * 10.0).round() / 10.0, unit);
+
+    let mut times: Vec<&str> = series.observations.iter().map(|(t, _)| t.as_str()).collect();
+    times.sort();
+    let span = match (times.first(), times.last()) {
+        (Some(first), Some(last)) if first != last => format!(", {} to {}", first, last),
+        _ => String::new(),
+    };
+    let plural = if values.len() == 1 { "" } else { "s" };
+    let measured = format!("{}_measured", key);
+    let summary = format!("mean {} ({} observation{}{})", format(overall), values.len(), plural, span);
+    place.temporal.insert(measured.clone(), summary);
+
+    let (mut weekday_values, mut weekend_values) = (Vec::new(), Vec::new());
+    for (time, value) in &series.observations {
+        match weekday(time) {
+            Some(0 | 6) => weekend_values.push(*value),
+            Some(_) => weekday_values.push(*value),
+            None => {}
+        }
+    }
+    if let (Some(weekday), Some(weekend)) = (mean(&weekday_values), mean(&weekend_values)) {
+        place.temporal.insert(format!("weekday_{}", key), format(weekday));
+        place.temporal.insert(format!("weekend_{}", key), format(weekend));
+    }
+
+    place.confidence.insert(measured, confidence(values.len()).to_string());
+    let source = format!("{} (sensor)", series.sensor);
+    if !place.source.contains(&source) {
+        place.source.push(source);
+    }
+    true
+}
+
+#[cfg(feature = "http")]
+mod fetch {
+    use super::{from_sensorthings_datastream, summarise};
+    use crate::models::GeonPlace;
+    use crate::parser::GeonError;
+    use serde_json::Value;
+
+    /// Query a SensorThings endpoint (e.g. `https://example.org/FROST-Server/v1.1`)
+    /// for Datastreams whose Thing lies within `radius_m` of the place, and
+    /// [`summarise`] each into TEMPORAL. Returns how many were applied.
+    pub async fn from_sensorthings(
+        place: &mut GeonPlace,
+        endpoint: &str,
+        radius_m: f64,
+        client: &reqwest::Client,
+    ) -> Result<usize, GeonError> {
+        let Some(at) = place.location.clone() else { return Ok(0) };
+        let dlat = radius_m / crate::geometry::METRES_PER_DEGREE;
+        let dlon = dlat / at.lat.to_radians().cos().max(1e-6);
+        let (s, n, w, e) = (at.lat - dlat, at.lat + dlat, at.lon - dlon, at.lon + dlon);
+        let filter = format!(
+            "st_within(Thing/Locations/location, geography'POLYGON(({w} {s}, {e} {s}, {e} {n}, {w} {n}, {w} {s}))')"
+        );
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/Datastreams", endpoint.trim_end_matches('/')),
+            &[
+                ("$filter", filter.as_str()),
+                ("$expand", "Thing/Locations,ObservedProperty,Observations($orderby=phenomenonTime desc;$top=1000)"),
+            ],
+        )
+        .map_err(|e| GeonError::InvalidStructure(e.to_string()))?;
+        let resp: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+
+        let mut applied = 0;
+        for datastream in resp.get("value").and_then(|v| v.as_array()).into_iter().flatten() {
+            if let Some(series) = from_sensorthings_datastream(datastream) {
+                if summarise(place, &series, radius_m) {
+                    applied += 1;
+                }
+            }
+        }
+        Ok(applied)
+    }
+}
+
+#[cfg(feature = "http")]
+pub use fetch::from_sensorthings;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summarise_datastream() {
+        let datastream = json!({
+            "name": "Footfall counter 3",
+            "unitOfMeasurement": {"name": "count", "symbol": "people/h"},
+            "ObservedProperty": {"name": "Footfall"},
+            "Thing": {"name": "Market Square counter", "Locations": [
+                {"location": {"type": "Point", "coordinates": [-1.1505, 52.9533]}}
+            ]},
+            "Observations": [
+                {"phenomenonTime": "2024-03-04T12:00:00Z", "result": 400},
+                {"phenomenonTime": "2024-03-05T12:00:00Z", "result": 500},
+                {"phenomenonTime": "2024-03-09T12:00:00Z/2024-03-09T13:00:00Z", "result": "900"}
+            ]
+        });
+        let series = from_sensorthings_datastream(&datastream).unwrap();
+        let mut place = GeonPlace::default();
+        place.location = Some(Coordinate::new(52.9532, -1.1503));
+        assert!(summarise(&mut place, &series, 100.0));
+        assert_eq!(place.temporal["weekday_footfall"], "450 people/h");
+        assert_eq!(place.temporal["weekend_footfall"], "900 people/h");
+        assert_eq!(
+            place.temporal["footfall_measured"],
+            "mean 600 people/h (3 observations, 2024-03-04T12:00:00Z to 2024-03-09T12:00:00Z)"
+        );
+        assert_eq!(place.confidence["footfall_measured"], "low");
+        assert_eq!(place.source, vec!["Market Square counter (sensor)"]);
+        place.place = "Old Market Square".to_string();
+        assert_eq!(crate::parse(&crate::generate(&place)), place);
+
+        place.location = Some(Coordinate::new(53.0, -1.15));
+        assert!(!summarise(&mut place, &series, 100.0));
+    }
+
+    #[test]
+    fn test_csv_series() {
+        let series = from_csv_series("time,value\n2024-06-01T10:00,61.5\n2024-06-01T11:00,\n", "noise level", "NL-7").unwrap();
+        assert_eq!(series.observations, vec![("2024-06-01T10:00".to_string(), 61.5)]);
+        assert_eq!(weekday("2024-06-01"), Some(6));
+        let mut place = GeonPlace::default();
+        assert!(summarise(&mut place, &series, 50.0));
+        assert_eq!(place.temporal["noise_level_measured"], "mean 61.5 (1 observation)");
+    }
+}
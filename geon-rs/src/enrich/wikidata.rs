@@ -0,0 +1,233 @@
+use crate::models::GeonPlace;
+use serde_json::{Map, Value};
+
+/// Wikidata QID for a place: `extra["wikidata"]` (as set by the OSM
+/// converters) or an ID of the form `wikidata:Q123`.
+pub fn qid(place: &GeonPlace) -> Option<String> {
+    let candidate = place
+        .extra
+        .get("wikidata")
+        .and_then(|v| v.as_str())
+        .or_else(|| place.id.as_deref().and_then(|id| id.strip_prefix("wikidata:")))?;
+    let valid = candidate.starts_with('Q') && candidate.len() > 1 && candidate[1..].bytes().all(|b| b.is_ascii_digit());
+    valid.then(|| candidate.to_string())
+}
+
+// Main values of every statement for a property
+fn claim_values<'a>(entity: &'a Value, property: &str) -> Vec<&'a Value> {
+    entity
+        .pointer(&format!("/claims/{}", property))
+        .and_then(|v| v.as_array())
+        .map(|claims| claims.iter().filter_map(|c| c.pointer("/mainsnak/datavalue/value")).collect())
+        .unwrap_or_default()
+}
+
+// "+1189-00-00T00:00:00Z" with precision 9 (year) -> "1189"
+fn format_time(value: &Value) -> Option<String> {
+    let time = value.get("time")?.as_str()?;
+    let precision = value.get("precision").and_then(|v| v.as_u64()).unwrap_or(11);
+    let (sign, rest) = match time.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", time.trim_start_matches('+')),
+    };
+    let date = rest.split('T').next()?;
+    let len = match precision {
+        0..=9 => date.find('-').unwrap_or(date.len()),
+        10 => date.len().min(date.find('-')? + 3),
+        _ => date.len(),
+    };
+    Some(format!("{}{}", sign, date[..len].trim_start_matches('0')))
+}
+
+fn commons_file_url(file: &str) -> String {
+    format!("https://commons.wikimedia.org/wiki/Special:FilePath/{}", file.replace(' ', "_"))
+}
+
+/// Wikidata entity IDs of the heritage designations (P1435) on an entity,
+/// for resolving to labels.
+pub fn heritage_designation_ids(entity: &Value) -> Vec<String> {
+    claim_values(entity, "P1435")
+        .into_iter()
+        .filter_map(|v| v.get("id").and_then(|id| id.as_str()).map(str::to_string))
+        .collect()
+}
+
+/// Apply a Wikidata entity (as returned by `wbgetentities` or
+/// `Special:EntityData`) to a place:
+///
+/// - labels go to `extra["labels"]`, and the English label names an
+///   unnamed place
+/// - inception (P571) becomes `LIFESPAN.established`
+/// - heritage designations (P1435) go to `extra["heritage_designation"]`,
+///   using `designation_labels` where available and QIDs otherwise
+/// - image (P18) and Commons category (P373) become `extra` links
+/// - the entity is recorded in SOURCE and `extra["wikidata"]`
+pub fn apply_entity(place: &mut GeonPlace, entity: &Value, designation_labels: &Map<String, Value>) {
+    let qid = entity.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+    if let Some(labels) = entity.get("labels").and_then(|v| v.as_object()) {
+        let flat: Map<String, Value> = labels
+            .iter()
+            .filter_map(|(lang, l)| l.get("value").map(|v| (lang.clone(), v.clone())))
+            .collect();
+        if place.place.is_empty() || place.place == "Unnamed" {
+            if let Some(en) = flat.get("en").and_then(|v| v.as_str()) {
+                place.place = en.to_string();
+            }
+        }
+        if !flat.is_empty() {
+            place.extra.insert("labels".to_string(), Value::Object(flat));
+        }
+    }
+
+    if let Some(established) = claim_values(entity, "P571").into_iter().find_map(format_time) {
+        place.lifespan.entry("established".to_string()).or_insert(established);
+    }
+
+    let designations: Vec<Value> = heritage_designation_ids(entity)
+        .into_iter()
+        .map(|id| designation_labels.get(&id).cloned().unwrap_or(Value::String(id)))
+        .collect();
+    if !designations.is_empty() {
+        place.extra.insert("heritage_designation".to_string(), Value::Array(designations));
+    }
+
+    if let Some(file) = claim_values(entity, "P18").into_iter().find_map(|v| v.as_str()) {
+        place.extra.insert("image".to_string(), Value::String(commons_file_url(file)));
+    }
+    if let Some(category) = claim_values(entity, "P373").into_iter().find_map(|v| v.as_str()) {
+        let url = format!("https://commons.wikimedia.org/wiki/Category:{}", category.replace(' ', "_"));
+        place.extra.insert("commons".to_string(), Value::String(url));
+    }
+
+    if !qid.is_empty() {
+        place.extra.insert("wikidata".to_string(), Value::String(qid.clone()));
+        let source = format!("Wikidata ({})", qid);
+        if !place.source.contains(&source) {
+            place.source.push(source);
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+mod fetch {
+    use super::{apply_entity, heritage_designation_ids, qid};
+    use crate::geometry::haversine_m;
+    use crate::models::{Coordinate, GeonPlace};
+    use crate::parser::GeonError;
+    use serde_json::{Map, Value};
+
+    const API_URL: &str = "https://www.wikidata.org/w/api.php";
+
+    // Search hits further than this from LOCATION are not the same place
+    const MATCH_RADIUS_M: f64 = 1000.0;
+
+    async fn api(client: &reqwest::Client, params: &[(&str, &str)]) -> Result<Value, GeonError> {
+        let mut all = vec![("format", "json")];
+        all.extend_from_slice(params);
+        let url = reqwest::Url::parse_with_params(API_URL, &all)
+            .map_err(|e| GeonError::InvalidStructure(e.to_string()))?;
+        Ok(client.get(url).send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn entities(client: &reqwest::Client, ids: &[String], props: &str) -> Result<Map<String, Value>, GeonError> {
+        let ids = ids.join("|");
+        let resp = api(client, &[("action", "wbgetentities"), ("ids", &ids), ("props", props)]).await?;
+        Ok(resp.get("entities").and_then(|v| v.as_object()).cloned().unwrap_or_default())
+    }
+
+    fn entity_coordinate(entity: &Value) -> Option<Coordinate> {
+        let v = super::claim_values(entity, "P625").into_iter().next()?;
+        Some(Coordinate::new(v.get("latitude")?.as_f64()?, v.get("longitude")?.as_f64()?))
+    }
+
+    // Search by name and keep the first hit located near the place
+    async fn search(client: &reqwest::Client, place: &GeonPlace) -> Result<Option<Value>, GeonError> {
+        let Some(location) = &place.location else { return Ok(None) };
+        let resp = api(
+            client,
+            &[("action", "wbsearchentities"), ("search", &place.place), ("language", "en"), ("limit", "10")],
+        )
+        .await?;
+        let ids: Vec<String> = resp
+            .get("search")
+            .and_then(|v| v.as_array())
+            .map(|hits| hits.iter().filter_map(|h| h.get("id")?.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let found = entities(client, &ids, "labels|claims").await?;
+        Ok(ids.iter().filter_map(|id| found.get(id)).find_map(|entity| {
+            let coord = entity_coordinate(entity)?;
+            (haversine_m(&coord, location) <= MATCH_RADIUS_M).then(|| entity.clone())
+        }))
+    }
+
+    /// Look the place up on Wikidata, by QID or else by name near its
+    /// LOCATION, and apply the entity with [`apply_entity`]. Returns whether
+    /// a matching entity was found.
+    pub async fn enrich(place: &mut GeonPlace, client: &reqwest::Client) -> Result<bool, GeonError> {
+        let entity = match qid(place) {
+            Some(id) => entities(client, std::slice::from_ref(&id), "labels|claims").await?.remove(&id),
+            None => search(client, place).await?,
+        };
+        let Some(entity) = entity else { return Ok(false) };
+
+        let designation_ids = heritage_designation_ids(&entity);
+        let mut labels = Map::new();
+        if !designation_ids.is_empty() {
+            for (id, e) in entities(client, &designation_ids, "labels").await? {
+                if let Some(label) = e.pointer("/labels/en/value") {
+                    labels.insert(id, label.clone());
+                }
+            }
+        }
+        apply_entity(place, &entity, &labels);
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "http")]
+pub use fetch::enrich;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_entity() {
+        let entity = json!({
+            "id": "Q1419404",
+            "labels": {"en": {"language": "en", "value": "Nottingham Castle"}},
+            "claims": {
+                "P571": [{"mainsnak": {"datavalue": {"value": {"time": "+1068-00-00T00:00:00Z", "precision": 9}}}}],
+                "P1435": [{"mainsnak": {"datavalue": {"value": {"entity-type": "item", "id": "Q15700834"}}}}],
+                "P18": [{"mainsnak": {"datavalue": {"value": "Nottingham Castle 2.jpg"}}}]
+            }
+        });
+        let mut place = GeonPlace::default();
+        place.place = "Unnamed".to_string();
+        let mut labels = Map::new();
+        labels.insert("Q15700834".to_string(), json!("Grade I listed building"));
+        apply_entity(&mut place, &entity, &labels);
+
+        assert_eq!(place.place, "Nottingham Castle");
+        assert_eq!(place.lifespan["established"], "1068");
+        assert_eq!(place.extra["heritage_designation"], json!(["Grade I listed building"]));
+        assert_eq!(
+            place.extra["image"],
+            "https://commons.wikimedia.org/wiki/Special:FilePath/Nottingham_Castle_2.jpg"
+        );
+        assert_eq!(place.source, vec!["Wikidata (Q1419404)"]);
+        assert_eq!(qid(&place).as_deref(), Some("Q1419404"));
+    }
+
+    #[test]
+    fn test_format_time_precision() {
+        assert_eq!(format_time(&json!({"time": "+1999-03-21T00:00:00Z", "precision": 11})).as_deref(), Some("1999-03-21"));
+        assert_eq!(format_time(&json!({"time": "+1999-03-00T00:00:00Z", "precision": 10})).as_deref(), Some("1999-03"));
+        assert_eq!(format_time(&json!({"time": "-0500-00-00T00:00:00Z", "precision": 9})).as_deref(), Some("-500"));
+    }
+}
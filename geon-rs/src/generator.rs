@@ -1,5 +1,10 @@
-use crate::models::GeonPlace;
-use std::fmt::Write;
+use crate::lexer::key_separator;
+use crate::models::{is_empty_json_value, Coordinate, GeonPlace};
+use crate::parser::{parse_many, scalar_value, split_key_value, GeonError};
+use crate::vocabulary::SECTIONS;
+use alloc::{collections::BTreeMap, format, string::{String, ToString}, vec::Vec};
+use core::fmt::Write;
+use serde_json::Value;
 
 const INDENT: &str = "  ";
 
@@ -22,124 +27,290 @@ fn write_section(buf: &mut String, key: &str, depth: usize) {
 fn write_list(buf: &mut String, items: &[String], depth: usize) {
     for item in items {
         write_indent(buf, depth);
-        writeln!(buf, "- {}", item).unwrap();
+        if item.is_empty() {
+            buf.push_str("-\n");
+        } else {
+            writeln!(buf, "- {}", item).unwrap();
+        }
     }
 }
 
-fn write_dict(buf: &mut String, map: &std::collections::HashMap<String, String>, depth: usize) {
-    // Sort keys for deterministic output
-    let mut keys: Vec<&String> = map.keys().collect();
-    keys.sort();
-    
-    for key in keys {
+fn write_dict(buf: &mut String, map: &BTreeMap<String, String>, depth: usize) {
+    for (key, value) in map.iter().filter(|(k, _)| writable_key(k)) {
         write_indent(buf, depth);
-        writeln!(buf, "{}: {}", key, map[key]).unwrap();
+        if value.is_empty() {
+            writeln!(buf, "{}:", key).unwrap();
+        } else {
+            writeln!(buf, "{}: {}", key, value).unwrap();
+        }
     }
 }
 
-fn generate_nested(buf: &mut String, place: &GeonPlace, depth: usize) {
-    write_indent(buf, depth);
-    writeln!(buf, "- PLACE: {}", place.place).unwrap();
-    let inner = depth + 2;
-    
-    if !place.type_.is_empty() {
-        write_line(buf, "TYPE", &place.type_, inner);
+// Whether `key: value` reads back with `key` as its key
+fn writable_key(key: &str) -> bool {
+    !key.starts_with(['-', '#']) && split_key_value(&format!("{}: x", key)) == Some((key, "x"))
+}
+
+// Whether text reads back as itself after a colon or list dash
+fn plain(text: &str) -> bool {
+    text.trim() == text && !text.contains(['\n', '\r'])
+}
+
+// A list entry that reads back as text rather than a nested place
+fn item_text(v: &Value) -> Option<&str> {
+    v.as_str().filter(|s| plain(s) && !matches!(split_key_value(s), Some(("PLACE", _))))
+}
+
+// A VIEWSHEDS or `extra` value in the form the parser reads it back from:
+// lists and maps of plain text as blocks, other text as itself unless it
+// would read as JSON, and anything else as JSON
+fn write_value(buf: &mut String, key: &str, value: &Value, depth: usize) {
+    match value {
+        Value::String(s) if !s.is_empty() && plain(s) && scalar_value(s) == *value => write_line(buf, key, s, depth),
+        Value::Array(items) if !items.is_empty() && items.iter().all(|v| item_text(v).is_some()) => {
+            write_section(buf, key, depth);
+            write_list(buf, &items.iter().filter_map(item_text).map(String::from).collect::<Vec<_>>(), depth + 1);
+        }
+        Value::Object(map) if !map.is_empty() && map.iter().all(|(k, v)| writable_key(k) && v.as_str().is_some_and(plain)) => {
+            write_section(buf, key, depth);
+            let entries = map.iter().map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string())).collect();
+            write_dict(buf, &entries, depth + 1);
+        }
+        other => write_line(buf, key, &other.to_string(), depth),
     }
-    if let Some(loc) = &place.location {
-        write_line(buf, "LOCATION", &loc.to_string(), inner);
+}
+
+fn write_coordinates(buf: &mut String, key: &str, points: &[Coordinate], depth: usize) {
+    if !points.is_empty() {
+        write_section(buf, key, depth);
+        let items: Vec<String> = points.iter().map(|c| c.to_string()).collect();
+        write_list(buf, &items, depth + 1);
     }
-    if let Some(area) = &place.area {
-        write_line(buf, "AREA", area, inner);
+}
+
+fn write_items(buf: &mut String, key: &str, items: &[String], depth: usize) {
+    match items {
+        [] => {}
+        [one] if !one.is_empty() => write_line(buf, key, one, depth),
+        _ => {
+            write_section(buf, key, depth);
+            write_list(buf, items, depth + 1);
+        }
     }
-    // minimal subset for nested...
 }
 
-pub fn generate(place: &GeonPlace) -> String {
-    let mut buf = String::new();
-    
+fn write_map(buf: &mut String, key: &str, map: &BTreeMap<String, String>, depth: usize) {
+    if !map.is_empty() {
+        write_section(buf, key, depth);
+        write_dict(buf, map, depth + 1);
+    }
+}
+
+fn generate_nested(buf: &mut String, place: &GeonPlace, depth: usize) {
+    write_indent(buf, depth);
+    writeln!(buf, "- PLACE: {}", place.place).unwrap();
+    write_fields(buf, place, depth + 1);
+}
+
+// Every field but PLACE, with its key at `depth`
+fn write_fields(buf: &mut String, place: &GeonPlace, depth: usize) {
     // Identity
-    write_line(&mut buf, "PLACE", &place.place, 0);
     if !place.type_.is_empty() {
-        write_line(&mut buf, "TYPE", &place.type_, 0);
+        write_line(buf, "TYPE", &place.type_, depth);
     }
     if let Some(id) = &place.id {
-        write_line(&mut buf, "ID", id, 0);
+        write_line(buf, "ID", id, depth);
     }
-    
+
     // Geometry
     if let Some(loc) = &place.location {
-        write_line(&mut buf, "LOCATION", &loc.to_string(), 0);
+        write_line(buf, "LOCATION", &loc.to_string(), depth);
     }
-    
-    if !place.boundary.is_empty() {
-        write_section(&mut buf, "BOUNDARY", 0);
-        let items: Vec<String> = place.boundary.iter().map(|c| c.to_string()).collect();
-        write_list(&mut buf, &items, 1);
-    }
-    
+    write_coordinates(buf, "BOUNDARY", &place.boundary, depth);
+    write_coordinates(buf, "PATH", &place.path, depth);
     if let Some(ext) = &place.extent {
-        write_line(&mut buf, "EXTENT", &ext.to_string(), 0);
+        write_line(buf, "EXTENT", &ext.to_string(), depth);
     }
     if let Some(el) = &place.elevation {
-        write_line(&mut buf, "ELEVATION", el, 0);
+        write_line(buf, "ELEVATION", el, depth);
     }
     if let Some(area) = &place.area {
-        write_line(&mut buf, "AREA", area, 0);
+        write_line(buf, "AREA", area, depth);
     }
 
     // Semantic
-    if !place.purpose.is_empty() {
-        if place.purpose.len() == 1 {
-            write_line(&mut buf, "PURPOSE", &place.purpose[0], 0);
-        } else {
-            write_section(&mut buf, "PURPOSE", 0);
-            write_list(&mut buf, &place.purpose, 1);
-        }
-    }
-    
-    if !place.experience.is_empty() {
-        write_section(&mut buf, "EXPERIENCE", 0);
-        write_dict(&mut buf, &place.experience, 1);
-    }
-
+    write_items(buf, "PURPOSE", &place.purpose, depth);
+    write_map(buf, "EXPERIENCE", &place.experience, depth);
     if !place.character.is_empty() {
-        write_section(&mut buf, "CHARACTER", 0);
-        write_list(&mut buf, &place.character, 1);
+        write_section(buf, "CHARACTER", depth);
+        write_list(buf, &place.character, depth + 1);
     }
-    
+
     // Relational
     if !place.adjacencies.is_empty() {
-        write_section(&mut buf, "ADJACENCIES", 0);
-        write_list(&mut buf, &place.adjacencies, 1);
+        write_section(buf, "ADJACENCIES", depth);
+        write_list(buf, &place.adjacencies, depth + 1);
     }
-    
-    if !place.connectivity.is_empty() {
-        write_section(&mut buf, "CONNECTIVITY", 0);
-        write_dict(&mut buf, &place.connectivity, 1);
-    }
-    
+    write_map(buf, "CONNECTIVITY", &place.connectivity, depth);
     if !place.contains.is_empty() {
-        write_section(&mut buf, "CONTAINS", 0);
+        write_section(buf, "CONTAINS", depth);
         for child in &place.contains {
-            generate_nested(&mut buf, child, 1);
+            generate_nested(buf, child, depth + 1);
         }
     }
-    
     if let Some(part_of) = &place.part_of {
-        write_line(&mut buf, "PART_OF", part_of, 0);
+        write_line(buf, "PART_OF", part_of, depth);
+    }
+    if !is_empty_json_value(&place.viewsheds) {
+        write_value(buf, "VIEWSHEDS", &place.viewsheds, depth);
     }
-    
+
     // Temporal
-    if !place.temporal.is_empty() {
-        write_section(&mut buf, "TEMPORAL", 0);
-        write_dict(&mut buf, &place.temporal, 1);
+    write_map(buf, "TEMPORAL", &place.temporal, depth);
+    write_map(buf, "LIFESPAN", &place.lifespan, depth);
+
+    // Provenance
+    if !place.source.is_empty() {
+        write_section(buf, "SOURCE", depth);
+        write_list(buf, &place.source, depth + 1);
     }
-    if !place.lifespan.is_empty() {
-        write_section(&mut buf, "LIFESPAN", 0);
-        write_dict(&mut buf, &place.lifespan, 1);
+    write_map(buf, "CONFIDENCE", &place.confidence, depth);
+    if let Some(updated) = &place.updated {
+        write_line(buf, "UPDATED", updated, depth);
     }
 
-    // ... others ...
+    // Extended
+    write_map(buf, "BUILT_FORM", &place.built_form, depth);
+    write_map(buf, "ECOLOGY", &place.ecology, depth);
+    write_map(buf, "INFRASTRUCTURE", &place.infrastructure, depth);
+    write_map(buf, "DEMOGRAPHICS", &place.demographics, depth);
+    write_map(buf, "ECONOMY", &place.economy, depth);
+    write_map(buf, "VISUAL", &place.visual, depth);
+    write_map(buf, "VERTICAL_PROFILE", &place.vertical_profile, depth);
+    if !place.history.is_empty() {
+        write_section(buf, "HISTORY", depth);
+        // The first key on the entry's dash, the rest lined up below it
+        for entry in &place.history {
+            let mut lines = String::new();
+            write_dict(&mut lines, entry, depth + 2);
+            let first = lines.trim_start();
+            if first.is_empty() {
+                continue;
+            }
+            write_indent(buf, depth + 1);
+            buf.push_str("- ");
+            buf.push_str(first);
+        }
+    }
 
+    // Keys with no field of their own
+    for (key, value) in &place.extra {
+        if writable_key(key) && !SECTIONS.iter().any(|s| s.key == key) {
+            write_value(buf, key, value, depth);
+        }
+    }
+}
+
+/// GEON text for `place` and the places it contains, which [`parse`]
+/// reads back into an equal place. Text is written as given, so values
+/// holding line breaks or surrounding whitespace, and section keys that
+/// can't stand before a colon, don't survive the trip; empty entries in
+/// HISTORY are left out.
+///
+/// [`parse`]: crate::parse
+pub fn generate(place: &GeonPlace) -> String {
+    let mut buf = String::new();
+    write_line(&mut buf, "PLACE", &place.place, 0);
+    write_fields(&mut buf, place, 0);
     buf
 }
+
+// `KEY:  value` -> `KEY: value` and `-  item` -> `- item`. Keys are found
+// as the lexer finds them, so URLs, times and free text keep their colons.
+fn tidy_line(content: &str) -> String {
+    let (dash, rest) = match content.strip_prefix('-') {
+        Some(rest) if rest.starts_with([' ', '\t']) => ("- ", rest.trim_start()),
+        _ => ("", content),
+    };
+    match key_separator(rest) {
+        Some(i) => {
+            let (key, value) = (rest[..i].trim_end(), rest[i + 1..].trim());
+            if value.is_empty() { format!("{}{}:", dash, key) } else { format!("{}{}: {}", dash, key, value) }
+        }
+        _ => format!("{}{}", dash, rest),
+    }
+}
+
+// Canonical layout line by line, as `format` describes
+fn reindent(text: &str) -> String {
+    let mut out = String::new();
+    // Source indents of the enclosing levels, outermost first
+    let mut levels: Vec<usize> = Vec::new();
+    let mut comments: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let content = line.trim();
+        if content.is_empty() {
+            continue;
+        }
+        if content.starts_with('#') {
+            comments.push(content);
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while levels.last().is_some_and(|&l| l > indent) {
+            levels.pop();
+        }
+        if levels.last() != Some(&indent) {
+            levels.push(indent);
+        }
+        let depth = levels.len() - 1;
+        if depth == 0 && content.starts_with("PLACE:") && !out.is_empty() {
+            out.push('\n');
+        }
+        for comment in comments.drain(..) {
+            write_indent(&mut out, depth);
+            writeln!(out, "{}", comment).unwrap();
+        }
+        write_indent(&mut out, depth);
+        writeln!(out, "{}", tidy_line(content)).unwrap();
+    }
+    for comment in comments {
+        writeln!(out, "{}", comment).unwrap();
+    }
+    out
+}
+
+/// Re-indent GEON text in canonical style without changing what it says:
+/// two spaces per level of nesting, one space after a key's colon and a
+/// list item's dash, no trailing whitespace or blank lines within a
+/// document, and one blank line between documents. Keys, their order,
+/// values and `#` comments are kept as written, so sections the parser
+/// doesn't model survive. Text that is already formatted comes back
+/// unchanged.
+///
+/// Lines at an indent the parser skips, such as a map indented four spaces
+/// below its key, would be read once re-indented. Rather than change the
+/// text's meaning, that is an error naming the first such line.
+pub fn format(text: &str) -> Result<String, GeonError> {
+    let formatted = reindent(text);
+    if parse_many(&formatted) == parse_many(text) {
+        return Ok(formatted);
+    }
+    // The line whose re-indenting first changes what the text says
+    let lines: Vec<&str> = text.lines().collect();
+    let changes = |n: usize| {
+        let prefix = lines[..n].join("\n");
+        parse_many(&reindent(&prefix)) != parse_many(&prefix)
+    };
+    let (mut same, mut changed) = (0, lines.len());
+    while changed - same > 1 {
+        let mid = (same + changed) / 2;
+        if changes(mid) { changed = mid } else { same = mid }
+    }
+    let line = lines.get(changed - 1).copied().unwrap_or_default();
+    Err(GeonError::InvalidStructure(format!(
+        "line {} is indented {} spaces where the parser skips it; re-indenting would change what the text says",
+        changed,
+        line.len() - line.trim_start().len()
+    )))
+}
@@ -0,0 +1,1208 @@
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use std::fmt::{self, Write};
+
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Metres per degree of latitude (and of longitude at the equator) on the
+/// mean-radius sphere [`haversine_m`] measures on.
+pub const METRES_PER_DEGREE: f64 = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+
+/// Great-circle distance in metres between two coordinates (haversine).
+pub fn haversine_m(a: &Coordinate, b: &Coordinate) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.lon - a.lon).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+/// Distance in metres between two coordinates on the WGS84 ellipsoid
+/// (Vincenty's inverse formula), accurate to well under a millimetre where
+/// [`haversine_m`] can be out by up to 0.5%. Falls back to the haversine
+/// distance for nearly antipodal points, where the iteration may not settle.
+#[cfg(feature = "geodesic")]
+pub fn geodesic_m(a: &Coordinate, b: &Coordinate) -> f64 {
+    const A: f64 = 6_378_137.0;
+    const F: f64 = 1.0 / 298.257_223_563;
+    const B: f64 = A * (1.0 - F);
+
+    let l = (b.lon - a.lon).to_radians();
+    let (u1, u2) = (((1.0 - F) * a.lat.to_radians().tan()).atan(), ((1.0 - F) * b.lat.to_radians().tan()).atan());
+    let (sin_u1, cos_u1, sin_u2, cos_u2) = (u1.sin(), u1.cos(), u2.sin(), u2.cos());
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_l, cos_l) = (lambda.sin(), lambda.cos());
+        let sin_sigma = (cos_u2 * sin_l).hypot(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_l);
+        if sin_sigma == 0.0 {
+            return 0.0;
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_l;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_l / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // Both points on the equator
+        let cos_2sm = if cos2_alpha == 0.0 { 0.0 } else { cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha };
+        let c = F / 16.0 * cos2_alpha * (4.0 + F * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l + (1.0 - c) * F * sin_alpha * (sigma + c * sin_sigma * (cos_2sm + c * cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm)));
+        if (lambda - previous).abs() < 1e-12 {
+            let u_sq = cos2_alpha * (A * A - B * B) / (B * B);
+            let k1 = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let k2 = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = k2
+                * sin_sigma
+                * (cos_2sm
+                    + k2 / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm)
+                            - k2 / 6.0 * cos_2sm * (-3.0 + 4.0 * sin_sigma * sin_sigma) * (-3.0 + 4.0 * cos_2sm * cos_2sm)));
+            return B * k1 * (sigma - delta_sigma);
+        }
+    }
+    haversine_m(a, b)
+}
+
+// Point-to-point distance used by place distances: ellipsoidal with the
+// `geodesic` feature, spherical otherwise
+pub(crate) fn point_distance_m(a: &Coordinate, b: &Coordinate) -> f64 {
+    #[cfg(feature = "geodesic")]
+    return geodesic_m(a, b);
+    #[cfg(not(feature = "geodesic"))]
+    haversine_m(a, b)
+}
+
+/// Initial great-circle bearing from `a` to `b` in degrees clockwise from
+/// north, in `[0, 360)`.
+pub fn bearing(a: &Coordinate, b: &Coordinate) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlon = (b.lon - a.lon).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// The eight-point compass direction of `b` as seen from `a`, e.g.
+/// `"northeast"`, as used in ADJACENCIES and VIEWSHEDS annotations.
+pub fn compass_direction(a: &Coordinate, b: &Coordinate) -> &'static str {
+    const POINTS: [&str; 8] = ["north", "northeast", "east", "southeast", "south", "southwest", "west", "northwest"];
+    POINTS[((bearing(a, b) + 22.5) / 45.0) as usize % 8]
+}
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The geohash of `c` with `precision` characters (at most 12, about 4 cm;
+/// 6 is about 1 km and 8 about 40 m). Nearby points usually share a
+/// prefix, so hashes bucket places by proximity without a spatial index.
+pub fn geohash(c: &Coordinate, precision: usize) -> String {
+    let (mut lat, mut lon) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let (mut bits, mut n, mut even) = (0usize, 0, true);
+    while hash.len() < precision.min(12) {
+        let (range, value) = if even { (&mut lon, c.lon) } else { (&mut lat, c.lat) };
+        let mid = (range.0 + range.1) / 2.0;
+        bits <<= 1;
+        if value >= mid {
+            bits |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even = !even;
+        n += 1;
+        if n == 5 {
+            hash.push(GEOHASH_ALPHABET[bits] as char);
+            (bits, n) = (0, 0);
+        }
+    }
+    hash
+}
+
+/// The cell a geohash covers, or `None` if it has characters outside the
+/// geohash alphabet. Case is ignored.
+pub fn geohash_extent(hash: &str) -> Option<Extent> {
+    let (mut lat, mut lon) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut even = true;
+    for ch in hash.bytes() {
+        let bits = GEOHASH_ALPHABET.iter().position(|&a| a == ch.to_ascii_lowercase())?;
+        for shift in (0..5).rev() {
+            let range: &mut (f64, f64) = if even { &mut lon } else { &mut lat };
+            let mid = (range.0 + range.1) / 2.0;
+            if bits >> shift & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+    Some(Extent { north: lat.1, south: lat.0, east: lon.1, west: lon.0 })
+}
+
+// Planar shoelace area of a ring in degrees², used to rank rings.
+pub(crate) fn ring_area(ring: &[Coordinate]) -> f64 {
+    let mut sum = 0.0;
+    for i in 0..ring.len() {
+        let a = &ring[i];
+        let b = &ring[(i + 1) % ring.len()];
+        sum += a.lon * b.lat - b.lon * a.lat;
+    }
+    (sum / 2.0).abs()
+}
+
+/// Area-weighted centroid of a ring (planar in lon/lat), open or closed.
+/// Unlike a vertex mean it is not pulled towards densely digitised edges.
+/// Rings with no area (collinear or fewer than three points) fall back to
+/// the mean of their distinct vertices; an empty ring has no centroid.
+pub fn centroid(ring: &[Coordinate]) -> Option<Coordinate> {
+    let first = ring.first()?;
+    // Offset by the first vertex to keep the products well conditioned
+    let (mut area, mut lat, mut lon) = (0.0, 0.0, 0.0);
+    for i in 0..ring.len() {
+        let (a, b) = (&ring[i], &ring[(i + 1) % ring.len()]);
+        let (ax, ay) = (a.lon - first.lon, a.lat - first.lat);
+        let (bx, by) = (b.lon - first.lon, b.lat - first.lat);
+        let cross = ax * by - bx * ay;
+        area += cross;
+        lon += (ax + bx) * cross;
+        lat += (ay + by) * cross;
+    }
+    if area.abs() > f64::EPSILON * 1e3 {
+        return Some(Coordinate::new(first.lat + lat / (3.0 * area), first.lon + lon / (3.0 * area)));
+    }
+
+    let distinct = if ring.len() > 1 && ring.first() == ring.last() { &ring[..ring.len() - 1] } else { ring };
+    let n = distinct.len() as f64;
+    Some(Coordinate::new(
+        distinct.iter().map(|c| c.lat).sum::<f64>() / n,
+        distinct.iter().map(|c| c.lon).sum::<f64>() / n,
+    ))
+}
+
+/// A defect in a boundary ring, as found by [`validate_ring`]. Indices are
+/// positions in the ring as given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RingDefect {
+    /// Fewer than three distinct points, so the ring encloses nothing.
+    TooFewPoints(usize),
+    /// The point at `index` repeats the one before it.
+    RepeatedPoint { index: usize },
+    /// The ring runs out to `index` and straight back, enclosing no area.
+    Spike { index: usize },
+    /// The edges starting at `first` and `second` cross or touch.
+    SelfIntersection { first: usize, second: usize },
+}
+
+impl fmt::Display for RingDefect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingDefect::TooFewPoints(n) => write!(f, "ring has only {} distinct points", n),
+            RingDefect::RepeatedPoint { index } => write!(f, "point {} repeats the previous point", index),
+            RingDefect::Spike { index } => write!(f, "zero-area spike at point {}", index),
+            RingDefect::SelfIntersection { first, second } => {
+                write!(f, "edges from points {} and {} intersect", first, second)
+            }
+        }
+    }
+}
+
+// Twice the signed area of the triangle a, b, c
+fn cross(a: &Coordinate, b: &Coordinate, c: &Coordinate) -> f64 {
+    (b.lon - a.lon) * (c.lat - a.lat) - (b.lat - a.lat) * (c.lon - a.lon)
+}
+
+// Whether c, known to be collinear with a and b, lies within their bounding box
+fn on_segment(a: &Coordinate, b: &Coordinate, c: &Coordinate) -> bool {
+    c.lon >= a.lon.min(b.lon) && c.lon <= a.lon.max(b.lon) && c.lat >= a.lat.min(b.lat) && c.lat <= a.lat.max(b.lat)
+}
+
+fn segments_intersect(a: &Coordinate, b: &Coordinate, c: &Coordinate, d: &Coordinate) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(c, d, a))
+        || (d2 == 0.0 && on_segment(c, d, b))
+        || (d3 == 0.0 && on_segment(a, b, c))
+        || (d4 == 0.0 && on_segment(a, b, d))
+}
+
+/// Check a boundary ring, open or closed, for repeated consecutive points,
+/// zero-area spikes and self-intersections. These are common in imported
+/// OSM boundaries and upset area, centroid and containment calculations.
+/// An empty result means the ring is a simple polygon.
+pub fn validate_ring(ring: &[Coordinate]) -> Vec<RingDefect> {
+    let mut defects = Vec::new();
+    let open = if ring.len() > 1 && ring.first() == ring.last() { &ring[..ring.len() - 1] } else { ring };
+
+    // Distinct consecutive points, with their index in `ring`
+    let mut points: Vec<(usize, &Coordinate)> = Vec::with_capacity(open.len());
+    for (i, c) in open.iter().enumerate() {
+        if points.last().is_some_and(|(_, prev)| *prev == c) {
+            defects.push(RingDefect::RepeatedPoint { index: i });
+        } else {
+            points.push((i, c));
+        }
+    }
+    if points.len() > 1 && points[0].1 == points[points.len() - 1].1 {
+        defects.push(RingDefect::RepeatedPoint { index: points[points.len() - 1].0 });
+        points.pop();
+    }
+    let n = points.len();
+    if n < 3 {
+        defects.push(RingDefect::TooFewPoints(n));
+        return defects;
+    }
+
+    for i in 0..n {
+        let (a, (index, b), c) = (points[(i + n - 1) % n].1, points[i], points[(i + 1) % n].1);
+        let (ab, bc) = ((b.lon - a.lon, b.lat - a.lat), (c.lon - b.lon, c.lat - b.lat));
+        let scale = ab.0.hypot(ab.1) * bc.0.hypot(bc.1);
+        if cross(a, b, c).abs() <= 1e-9 * scale && ab.0 * bc.0 + ab.1 * bc.1 < 0.0 {
+            defects.push(RingDefect::Spike { index });
+        }
+    }
+
+    for i in 0..n {
+        let (a, b) = (points[i].1, points[(i + 1) % n].1);
+        let (west, east) = (a.lon.min(b.lon), a.lon.max(b.lon));
+        let (south, north) = (a.lat.min(b.lat), a.lat.max(b.lat));
+        // Edges sharing a point with edge i are adjacent and always touch
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (c, d) = (points[j].1, points[(j + 1) % n].1);
+            if c.lon.max(d.lon) < west || c.lon.min(d.lon) > east || c.lat.max(d.lat) < south || c.lat.min(d.lat) > north {
+                continue;
+            }
+            if segments_intersect(a, b, c, d) {
+                defects.push(RingDefect::SelfIntersection { first: points[i].0, second: points[j].0 });
+            }
+        }
+    }
+    defects
+}
+
+/// Whether `point` lies inside `ring` (even-odd rule, planar in lon/lat).
+/// The ring may be open or closed; points on an edge may fall either way.
+pub fn ring_contains(ring: &[Coordinate], point: &Coordinate) -> bool {
+    let mut inside = false;
+    let n = ring.len();
+    for i in 0..n {
+        let (a, b) = (&ring[i], &ring[(i + n - 1) % n]);
+        if (a.lat > point.lat) != (b.lat > point.lat)
+            && point.lon < (b.lon - a.lon) * (point.lat - a.lat) / (b.lat - a.lat) + a.lon
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Equirectangular projection to metres east and north of `origin`, accurate
+// enough for the extent of a single place.
+fn local_xy(origin: &Coordinate, c: &Coordinate) -> (f64, f64) {
+    ((c.lon - origin.lon) * METRES_PER_DEGREE * origin.lat.to_radians().cos(), (c.lat - origin.lat) * METRES_PER_DEGREE)
+}
+
+// Distance from p to the segment a-b, all in local metres
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 { 0.0 } else { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0) };
+    (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+/// Most points [`densify`] splits a single edge into.
+pub const MAX_DENSIFY_STEPS: usize = 10_000;
+
+/// Add points along every edge of a boundary ring longer than
+/// `max_segment_m` metres, including the edge back to the start of an
+/// unclosed ring, so no edge is longer. New points are spaced evenly on the
+/// straight lon/lat edge, so the shape is unchanged; they matter once it is
+/// reprojected, buffered or sampled along. Closedness is kept. An edge is
+/// split into at most [`MAX_DENSIFY_STEPS`] pieces, and `max_segment_m` must
+/// be finite and positive.
+pub fn densify(boundary: &mut Vec<Coordinate>, max_segment_m: f64) -> Result<(), GeonError> {
+    if !max_segment_m.is_finite() || max_segment_m <= 0.0 {
+        return Err(GeonError::InvalidStructure(format!("segment length must be positive, got {}", max_segment_m)));
+    }
+    if boundary.len() < 2 {
+        return Ok(());
+    }
+    let mut ring = std::mem::take(boundary);
+    let wrap = ring.len() >= 3 && ring.first() != ring.last();
+    if wrap {
+        ring.push(ring[0].clone());
+    }
+    let mut dense = vec![ring[0].clone()];
+    for w in ring.windows(2) {
+        let n = ((haversine_m(&w[0], &w[1]) / max_segment_m).ceil().max(1.0) as usize).min(MAX_DENSIFY_STEPS);
+        for i in 1..=n {
+            let t = i as f64 / n as f64;
+            dense.push(Coordinate::new(w[0].lat + (w[1].lat - w[0].lat) * t, w[0].lon + (w[1].lon - w[0].lon) * t));
+        }
+        // Land exactly on the original vertex
+        *dense.last_mut().unwrap() = w[1].clone();
+    }
+    if wrap {
+        dense.pop();
+    }
+    *boundary = dense;
+    Ok(())
+}
+
+/// Simplify a boundary ring in place with Douglas–Peucker, dropping points
+/// that lie within `tolerance_m` metres of the simplified outline. The ring
+/// keeps whether it was closed, and never drops below three distinct points,
+/// so a place smaller than the tolerance stays a polygon.
+pub fn simplify(boundary: &mut Vec<Coordinate>, tolerance_m: f64) {
+    let closed = boundary.len() > 1 && boundary.first() == boundary.last();
+    let n = boundary.len() - usize::from(closed);
+    if n <= 3 {
+        return;
+    }
+    let xy: Vec<(f64, f64)> = boundary[..n].iter().chain(&boundary[..1]).map(|c| local_xy(&boundary[0], c)).collect();
+    let farthest = |from: usize, to: usize, a: (f64, f64), b: (f64, f64)| {
+        (from + 1..to).map(|i| (i, segment_distance(xy[i], a, b))).max_by(|x, y| x.1.total_cmp(&y.1))
+    };
+
+    // Anchor the ring on its first point, the point farthest from it and the
+    // point farthest from the chord between them, then simplify each span
+    let (far, _) = farthest(0, n, xy[0], xy[0]).unwrap();
+    let (wide, _) = farthest(0, n, xy[0], xy[far]).filter(|(i, _)| *i != far).unwrap_or((if far > 1 { far - 1 } else { far + 1 }, 0.0));
+    let mut keep = vec![false; n + 1];
+    let mut spans = vec![(0, far.min(wide)), (far.min(wide), far.max(wide)), (far.max(wide), n)];
+    for i in [0, far, wide, n] {
+        keep[i] = true;
+    }
+    while let Some((from, to)) = spans.pop() {
+        if let Some((i, d)) = farthest(from, to, xy[from], xy[to]) {
+            if d > tolerance_m {
+                keep[i] = true;
+                spans.push((from, i));
+                spans.push((i, to));
+            }
+        }
+    }
+
+    let mut i = 0;
+    boundary.retain(|_| {
+        i += 1;
+        keep[i - 1]
+    });
+}
+
+/// Shape of the ring drawn around a set of points by [`hull`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hull {
+    Convex,
+    /// Start from the convex hull and dig into any edge longer than this
+    /// many metres, so an L-shaped campus does not claim the space between
+    /// its wings. Smaller values follow the points more closely.
+    Concave(f64),
+}
+
+// Andrew's monotone chain; indices of the hull in anticlockwise order
+fn convex_hull(xy: &[(f64, f64)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..xy.len()).collect();
+    order.sort_by(|&a, &b| xy[a].0.total_cmp(&xy[b].0).then(xy[a].1.total_cmp(&xy[b].1)));
+    order.dedup_by(|a, b| xy[*a] == xy[*b]);
+    if order.len() < 3 {
+        return order;
+    }
+    let mut hull: Vec<usize> = Vec::with_capacity(order.len() + 1);
+    for pass in [order.clone(), order.into_iter().rev().collect()] {
+        let base = hull.len();
+        for i in pass {
+            while hull.len() >= base + 2 && xy_cross(xy[hull[hull.len() - 2]], xy[hull[hull.len() - 1]], xy[i]) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(i);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+fn xy_cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+// Whether segments a-b and c-d cross away from their end points
+fn xy_crosses(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let (d1, d2) = (xy_cross(c, d, a), xy_cross(c, d, b));
+    let (d3, d4) = (xy_cross(a, b, c), xy_cross(a, b, d));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+// Dig into the edges of an anticlockwise convex hull longer than `max_edge`
+fn dig(xy: &[(f64, f64)], mut ring: Vec<usize>, max_edge: f64) -> Vec<usize> {
+    let dist = |a: usize, b: usize| (xy[a].0 - xy[b].0).hypot(xy[a].1 - xy[b].1);
+    let mut inside = vec![true; xy.len()];
+    for &i in &ring {
+        inside[i] = false;
+    }
+    let mut settled: Vec<(usize, usize)> = Vec::new();
+    loop {
+        let longest = (0..ring.len())
+            .map(|e| (ring[e], ring[(e + 1) % ring.len()], e))
+            .filter(|&(a, b, _)| dist(a, b) > max_edge && !settled.contains(&(a, b)))
+            .max_by(|x, y| dist(x.0, x.1).total_cmp(&dist(y.0, y.1)));
+        let Some((a, b, e)) = longest else { break };
+
+        // The nearest point that can join the ring without leaving another
+        // point outside it or crossing an existing edge
+        let fits = |p: usize| {
+            let (pa, pb, pp) = (xy[a], xy[b], xy[p]);
+            (0..xy.len()).all(|q| {
+                !inside[q] || q == p || {
+                    let t = xy[q];
+                    xy_cross(pa, pp, t) > 0.0 || xy_cross(pp, pb, t) > 0.0 || xy_cross(pb, pa, t) > 0.0
+                }
+            }) && (0..ring.len()).all(|f| {
+                let (c, d) = (xy[ring[f]], xy[ring[(f + 1) % ring.len()]]);
+                // A point already on the ring's outline would fold it back on itself
+                let on_edge = segment_distance(pp, c, d) <= 1e-9 * (d.0 - c.0).hypot(d.1 - c.1);
+                !on_edge && !xy_crosses(pa, pp, c, d) && !xy_crosses(pp, pb, c, d)
+            })
+        };
+        let reach = |p: usize| dist(a, p).max(dist(p, b));
+        let mut candidates: Vec<usize> = (0..xy.len())
+            .filter(|&p| inside[p] && reach(p) < dist(a, b) && xy_cross(xy[a], xy[b], xy[p]) > 0.0)
+            .collect();
+        candidates.sort_by(|&p, &q| reach(p).total_cmp(&reach(q)));
+        match candidates.into_iter().find(|&p| fits(p)) {
+            Some(p) => {
+                ring.insert(e + 1, p);
+                inside[p] = false;
+            }
+            None => settled.push((a, b)),
+        }
+    }
+    ring
+}
+
+/// A closed ring around `points`, or an empty one if they are fewer than
+/// three or all lie on a line. Lengths for [`Hull::Concave`] are measured in
+/// an equirectangular projection centred on the first point.
+pub fn hull(points: &[Coordinate], kind: Hull) -> Vec<Coordinate> {
+    let Some(origin) = points.first() else { return vec![] };
+    let xy: Vec<(f64, f64)> = points.iter().map(|c| local_xy(origin, c)).collect();
+    let mut ring = convex_hull(&xy);
+    if ring.len() < 3 {
+        return vec![];
+    }
+    if let Hull::Concave(max_edge) = kind {
+        ring = dig(&xy, ring, max_edge);
+    }
+    ring.push(ring[0]);
+    ring.into_iter().map(|i| points[i].clone()).collect()
+}
+
+// The point `distance_m` metres from `start` along the great circle leaving
+// on `bearing` degrees clockwise from north
+fn destination(start: &Coordinate, bearing: f64, distance_m: f64) -> Coordinate {
+    let (lat1, lon1) = (start.lat.to_radians(), start.lon.to_radians());
+    let (theta, delta) = (bearing.to_radians(), distance_m / EARTH_RADIUS_M);
+    let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * theta.cos()).asin();
+    let lon2 = lon1 + (theta.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+    Coordinate::new(lat2.to_degrees(), (lon2.to_degrees() + 540.0) % 360.0 - 180.0)
+}
+
+/// A closed, anticlockwise ring of `segments` points (at least three)
+/// `radius_m` metres from `center`, giving a point-only place an
+/// approximate footprint, e.g. `place.boundary = buffer(&location, 25.0, 16)`.
+pub fn buffer(center: &Coordinate, radius_m: f64, segments: usize) -> Vec<Coordinate> {
+    let segments = segments.max(3);
+    let mut ring: Vec<Coordinate> =
+        (0..segments).map(|i| destination(center, 360.0 - i as f64 * 360.0 / segments as f64, radius_m)).collect();
+    ring.push(ring[0].clone());
+    ring
+}
+
+/// Shortest distance in metres between two shapes, each a boundary ring
+/// (open or closed) or a single point: zero when they touch, overlap or one
+/// lies inside the other. Measured in an equirectangular projection around
+/// the first point of `a`, so it suits distances up to a few tens of
+/// kilometres.
+pub fn shape_distance_m(a: &[Coordinate], b: &[Coordinate]) -> Option<f64> {
+    outline_distance_m((a, a.len() >= 3), (b, b.len() >= 3))
+}
+
+// As `shape_distance_m`, for shapes that are each a ring (`true`) or a
+// line left open between its ends (`false`)
+pub(crate) fn outline_distance_m((a, a_ring): (&[Coordinate], bool), (b, b_ring): (&[Coordinate], bool)) -> Option<f64> {
+    let origin = a.first()?;
+    b.first()?;
+    let (a_ring, b_ring) = (a_ring && a.len() >= 3, b_ring && b.len() >= 3);
+    if (a_ring && b.iter().any(|c| ring_contains(a, c))) || (b_ring && a.iter().any(|c| ring_contains(b, c))) {
+        return Some(0.0);
+    }
+    // A single point is one degenerate edge
+    let edges = |n: usize, ring: bool| -> Vec<(usize, usize)> {
+        if ring || n == 1 { (0..n).map(|i| (i, (i + 1) % n)).collect() } else { (0..n - 1).map(|i| (i, i + 1)).collect() }
+    };
+    let (ea, eb) = (edges(a.len(), a_ring), edges(b.len(), b_ring));
+    for &(i, j) in &ea {
+        if eb.iter().any(|&(k, l)| segments_intersect(&a[i], &a[j], &b[k], &b[l])) {
+            return Some(0.0);
+        }
+    }
+
+    let (xa, xb): (Vec<_>, Vec<_>) =
+        (a.iter().map(|c| local_xy(origin, c)).collect(), b.iter().map(|c| local_xy(origin, c)).collect());
+    let one_way = |from: &[(f64, f64)], to: &[(f64, f64)], edges: &[(usize, usize)]| {
+        from.iter()
+            .flat_map(|&p| edges.iter().map(move |&(i, j)| segment_distance(p, to[i], to[j])))
+            .fold(f64::INFINITY, f64::min)
+    };
+    Some(one_way(&xa, &xb, &eb).min(one_way(&xb, &xa, &ea)))
+}
+
+// A ring in local metres, without its closing point and anticlockwise
+fn ring_xy(origin: &Coordinate, ring: &[Coordinate]) -> Vec<(f64, f64)> {
+    let open = if ring.len() > 1 && ring.first() == ring.last() { &ring[..ring.len() - 1] } else { ring };
+    let mut xy: Vec<(f64, f64)> = open.iter().map(|c| local_xy(origin, c)).collect();
+    let twice_area: f64 = (0..xy.len()).map(|i| xy_cross((0.0, 0.0), xy[i], xy[(i + 1) % xy.len()])).sum();
+    if twice_area < 0.0 {
+        xy.reverse();
+    }
+    xy
+}
+
+fn xy_contains(ring: &[(f64, f64)], p: (f64, f64)) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + ring.len() - 1) % ring.len()]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Green's theorem contribution of the parts of `a`'s edges lying inside
+// `b`. Parts running along an edge of `b` count only when `shared` is set
+// and both edges run the same way, so a shared edge is counted once.
+fn clipped_area(a: &[(f64, f64)], b: &[(f64, f64)], shared: bool) -> f64 {
+    const ON_EDGE_M: f64 = 1e-6;
+    let mut sum = 0.0;
+    for i in 0..a.len() {
+        let (p, q) = (a[i], a[(i + 1) % a.len()]);
+        let r = (q.0 - p.0, q.1 - p.1);
+        let len2 = r.0 * r.0 + r.1 * r.1;
+        if len2 == 0.0 {
+            continue;
+        }
+        // Where the edge meets `b`'s edges, as fractions along it
+        let mut cuts = vec![0.0, 1.0];
+        for j in 0..b.len() {
+            let (c, d) = (b[j], b[(j + 1) % b.len()]);
+            let s = (d.0 - c.0, d.1 - c.1);
+            let pc = (c.0 - p.0, c.1 - p.1);
+            let denom = r.0 * s.1 - r.1 * s.0;
+            if denom != 0.0 {
+                let t = (pc.0 * s.1 - pc.1 * s.0) / denom;
+                let u = (pc.0 * r.1 - pc.1 * r.0) / denom;
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                    cuts.push(t);
+                }
+            } else if pc.0 * r.1 - pc.1 * r.0 == 0.0 {
+                for end in [c, d] {
+                    cuts.push(((end.0 - p.0) * r.0 + (end.1 - p.1) * r.1) / len2);
+                }
+            }
+        }
+        cuts.retain(|t| (0.0..=1.0).contains(t));
+        cuts.sort_by(f64::total_cmp);
+
+        let at = |t: f64| (p.0 + r.0 * t, p.1 + r.1 * t);
+        for w in cuts.windows(2) {
+            let mid = at((w[0] + w[1]) / 2.0);
+            let along = (0..b.len()).find(|&j| segment_distance(mid, b[j], b[(j + 1) % b.len()]) <= ON_EDGE_M);
+            let counts = match along {
+                Some(j) => {
+                    let (c, d) = (b[j], b[(j + 1) % b.len()]);
+                    shared && r.0 * (d.0 - c.0) + r.1 * (d.1 - c.1) > 0.0
+                }
+                None => xy_contains(b, mid),
+            };
+            if counts {
+                sum += xy_cross((0.0, 0.0), at(w[0]), at(w[1])) / 2.0;
+            }
+        }
+    }
+    sum
+}
+
+/// Area in square metres enclosed by a boundary ring, open or closed,
+/// measured in an equirectangular projection around its first point.
+pub fn area_m2(ring: &[Coordinate]) -> f64 {
+    let Some(origin) = ring.first() else { return 0.0 };
+    let xy = ring_xy(origin, ring);
+    (0..xy.len()).map(|i| xy_cross((0.0, 0.0), xy[i], xy[(i + 1) % xy.len()])).sum::<f64>().abs() / 2.0
+}
+
+/// Area in square metres common to two boundary rings (open or closed,
+/// either winding), measured in an equirectangular projection around the
+/// first point of `a`. Rings need not be convex but should be simple; see
+/// [`validate_ring`].
+pub fn intersection_area_m2(a: &[Coordinate], b: &[Coordinate]) -> f64 {
+    let Some(origin) = a.first() else { return 0.0 };
+    if a.len() < 3 || b.len() < 3 {
+        return 0.0;
+    }
+    let (xa, xb) = (ring_xy(origin, a), ring_xy(origin, b));
+    (clipped_area(&xa, &xb, true) + clipped_area(&xb, &xa, false)).max(0.0)
+}
+
+// Apply `f` to every coordinate of a place and the places nested in it,
+// dropping boundary points that come to repeat the one before
+fn map_coords(place: &mut GeonPlace, f: &impl Fn(&mut Coordinate)) {
+    if let Some(c) = &mut place.location {
+        f(c);
+    }
+    place.boundary.iter_mut().for_each(f);
+    place.boundary.dedup();
+    place.path.iter_mut().for_each(f);
+    place.path.dedup();
+    if let Some(e) = &mut place.extent {
+        let (mut ne, mut sw) = (Coordinate::new(e.north, e.east), Coordinate::new(e.south, e.west));
+        f(&mut ne);
+        f(&mut sw);
+        *e = Extent { north: ne.lat, south: sw.lat, east: ne.lon, west: sw.lon };
+    }
+    for child in &mut place.contains {
+        map_coords(child, f);
+    }
+}
+
+/// Round every LOCATION, BOUNDARY, PATH and EXTENT coordinate in `place` and the
+/// places nested in it to `decimals` places (6 is about 10 cm), so source
+/// data with spurious precision gives smaller files and stable diffs.
+pub fn round_coords(place: &mut GeonPlace, decimals: u32) {
+    let scale = 10f64.powi(decimals.min(15) as i32);
+    map_coords(place, &|c: &mut Coordinate| {
+        c.lat = (c.lat * scale).round() / scale;
+        c.lon = (c.lon * scale).round() / scale;
+    });
+}
+
+/// Snap every coordinate in `place` and the places nested in it to a grid
+/// of `cell_m` metre squares: latitude in steps of `cell_m` metres, and
+/// longitude in steps of `cell_m` metres at the snapped latitude. The cell
+/// size must be finite and positive.
+pub fn snap_to_grid(place: &mut GeonPlace, cell_m: f64) -> Result<(), GeonError> {
+    if !cell_m.is_finite() || cell_m <= 0.0 {
+        return Err(GeonError::InvalidStructure(format!("grid cell size must be positive, got {}", cell_m)));
+    }
+    let lat_step = cell_m / METRES_PER_DEGREE;
+    map_coords(place, &|c: &mut Coordinate| {
+        c.lat = (c.lat / lat_step).round() * lat_step;
+        let lon_step = lat_step / c.lat.to_radians().cos().max(1e-6);
+        c.lon = (c.lon / lon_step).round() * lon_step;
+    });
+    Ok(())
+}
+
+// 64-bit FNV-1a, stable across platforms and Rust versions unlike `Hash`
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Degrees to the nearest 1e-7 (about 1 cm), so float noise and -0.0
+    // don't change the hash
+    fn degrees(&mut self, v: f64) {
+        self.write(&((v * 1e7).round() as i64).to_le_bytes());
+    }
+
+    fn points<'a>(&mut self, tag: &[u8], points: impl ExactSizeIterator<Item = &'a Coordinate>) {
+        self.write(tag);
+        self.write(&(points.len() as u64).to_le_bytes());
+        for c in points {
+            self.degrees(c.lat);
+            self.degrees(c.lon);
+        }
+    }
+}
+
+// A ring without its closing point or repeated points, anticlockwise,
+// starting from its south-westernmost point
+fn canonical_ring(ring: &[Coordinate]) -> Vec<Coordinate> {
+    let key = |c: &Coordinate| ((c.lat * 1e7).round() as i64, (c.lon * 1e7).round() as i64);
+    let mut points: Vec<Coordinate> = Vec::new();
+    for c in ring {
+        if points.last().is_none_or(|p| key(p) != key(c)) {
+            points.push(c.clone());
+        }
+    }
+    if points.len() > 1 && key(&points[0]) == key(&points[points.len() - 1]) {
+        points.pop();
+    }
+    let signed: f64 = (0..points.len())
+        .map(|i| {
+            let (a, b) = (&points[i], &points[(i + 1) % points.len()]);
+            a.lon * b.lat - b.lon * a.lat
+        })
+        .sum();
+    if signed < 0.0 {
+        points.reverse();
+    }
+    if let Some(start) = (0..points.len()).min_by_key(|i| key(&points[*i])) {
+        points.rotate_left(start);
+    }
+    points
+}
+
+/// A hash of a place's own LOCATION, BOUNDARY, PATH and EXTENT, to about a
+/// centimetre. Rings are compared whatever their starting point, winding
+/// or closing point, and paths whichever way they run, so re-exporting the
+/// same shape keeps the fingerprint. Nested places are not included.
+/// Stable across platforms and releases, for sync tools to store.
+pub fn fingerprint(place: &GeonPlace) -> u64 {
+    let mut h = Fnv::new();
+    if let Some(c) = &place.location {
+        h.points(b"L", std::iter::once(c));
+    }
+    h.points(b"B", canonical_ring(&place.boundary).iter());
+    let reversed: Vec<Coordinate> = place.path.iter().rev().cloned().collect();
+    let key = |line: &[Coordinate]| line.iter().map(|c| ((c.lat * 1e7).round() as i64, (c.lon * 1e7).round() as i64)).collect::<Vec<_>>();
+    let path = if key(&reversed) < key(&place.path) { &reversed } else { &place.path };
+    h.points(b"P", path.iter());
+    if let Some(e) = &place.extent {
+        h.write(b"E");
+        for v in [e.north, e.south, e.east, e.west] {
+            h.degrees(v);
+        }
+    }
+    h.0
+}
+
+/// A hash of everything about a place except its own geometry, so that
+/// alongside [`fingerprint`] a changed import can be told apart as "boundary
+/// edited" or "description edited". Map ordering doesn't affect it.
+pub fn attribute_fingerprint(place: &GeonPlace) -> u64 {
+    let mut rest = place.clone();
+    rest.location = None;
+    rest.boundary.clear();
+    rest.path.clear();
+    rest.extent = None;
+    let mut h = Fnv::new();
+    // Through `Value`, whose objects are sorted, rather than the HashMaps
+    let value = serde_json::to_value(&rest).unwrap_or_default();
+    h.write(value.to_string().as_bytes());
+    h.0
+}
+
+// WKT
+
+fn write_positions(buf: &mut String, coords: &[Coordinate], close: bool) {
+    buf.push('(');
+    for (i, c) in coords.iter().enumerate() {
+        if i > 0 {
+            buf.push_str(", ");
+        }
+        write!(buf, "{} {}", c.lon, c.lat).unwrap();
+    }
+    // WKT rings must be closed
+    if close && coords.first() != coords.last() {
+        write!(buf, ", {} {}", coords[0].lon, coords[0].lat).unwrap();
+    }
+    buf.push(')');
+}
+
+fn wkt_polygon(ring: &[Coordinate]) -> String {
+    let mut buf = String::from("POLYGON (");
+    write_positions(&mut buf, ring, true);
+    buf.push(')');
+    buf
+}
+
+fn wkt_line(path: &[Coordinate]) -> String {
+    let mut buf = String::from("LINESTRING ");
+    write_positions(&mut buf, path, false);
+    buf
+}
+
+/// Name of the simple-features geometry type [`to_wkt`] and [`to_wkb`]
+/// write for a place (`"Point"`, `"LineString"`, `"Polygon"` or
+/// `"GeometryCollection"`), or `None` where they write nothing.
+pub fn geometry_type(place: &GeonPlace) -> Option<&'static str> {
+    match (place.boundary.len() >= 3, place.path.len() >= 2) {
+        (true, true) => Some("GeometryCollection"),
+        (true, false) => Some("Polygon"),
+        (false, true) => Some("LineString"),
+        (false, false) => place.location.as_ref().map(|_| "Point"),
+    }
+}
+
+/// Render a place's geometry as WKT: `POLYGON` from BOUNDARY when it has at
+/// least three points and `LINESTRING` from PATH when it has at least two,
+/// as a `GEOMETRYCOLLECTION` of both when a place has both; otherwise
+/// `POINT` from LOCATION.
+pub fn to_wkt(place: &GeonPlace) -> Option<String> {
+    let polygon = (place.boundary.len() >= 3).then(|| wkt_polygon(&place.boundary));
+    let line = (place.path.len() >= 2).then(|| wkt_line(&place.path));
+    match (polygon, line) {
+        (Some(polygon), Some(line)) => Some(format!("GEOMETRYCOLLECTION ({}, {})", polygon, line)),
+        (Some(geometry), None) | (None, Some(geometry)) => Some(geometry),
+        (None, None) => place.location.as_ref().map(|c| format!("POINT ({} {})", c.lon, c.lat)),
+    }
+}
+
+// Nested coordinate lists as they appear between WKT parentheses
+#[derive(Debug)]
+enum WktNode {
+    Position(Coordinate),
+    List(Vec<WktNode>),
+}
+
+struct WktReader<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> WktReader<'a> {
+    fn skip_ws(&mut self) {
+        while self.pos < self.text.len() && self.text.as_bytes()[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, ch: u8) -> bool {
+        self.skip_ws();
+        if self.pos < self.text.len() && self.text.as_bytes()[self.pos] == ch {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self) -> String {
+        self.skip_ws();
+        let start = self.pos;
+        while self.pos < self.text.len() && self.text.as_bytes()[self.pos].is_ascii_alphabetic() {
+            self.pos += 1;
+        }
+        self.text[start..self.pos].to_ascii_uppercase()
+    }
+
+    fn position(&mut self) -> Result<Coordinate, GeonError> {
+        self.skip_ws();
+        let start = self.pos;
+        while self.pos < self.text.len() && !matches!(self.text.as_bytes()[self.pos], b',' | b')') {
+            self.pos += 1;
+        }
+        let parts: Vec<&str> = self.text[start..self.pos].split_whitespace().collect();
+        if parts.len() < 2 {
+            return Err(GeonError::InvalidWkt(format!("bad position at offset {}", start)));
+        }
+        Ok(Coordinate { lat: parts[1].parse()?, lon: parts[0].parse()? })
+    }
+
+    fn list(&mut self) -> Result<WktNode, GeonError> {
+        if !self.eat(b'(') {
+            return Err(GeonError::InvalidWkt(format!("expected '(' at offset {}", self.pos)));
+        }
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.text.as_bytes().get(self.pos) == Some(&b'(') {
+                items.push(self.list()?);
+            } else {
+                items.push(WktNode::Position(self.position()?));
+            }
+            if self.eat(b')') {
+                return Ok(WktNode::List(items));
+            }
+            if !self.eat(b',') {
+                return Err(GeonError::InvalidWkt(format!("expected ',' or ')' at offset {}", self.pos)));
+            }
+        }
+    }
+}
+
+fn node_positions(node: &WktNode) -> Vec<Coordinate> {
+    match node {
+        WktNode::Position(c) => vec![c.clone()],
+        WktNode::List(items) => items
+            .iter()
+            .filter_map(|n| match n {
+                WktNode::Position(c) => Some(c.clone()),
+                WktNode::List(_) => None,
+            })
+            .collect(),
+    }
+}
+
+// Outer ring of a POLYGON body: `((x y, ...), (hole), ...)`
+fn outer_ring(node: &WktNode) -> Vec<Coordinate> {
+    match node {
+        WktNode::List(rings) => rings.first().map(node_positions).unwrap_or_default(),
+        WktNode::Position(_) => vec![],
+    }
+}
+
+/// Geometry parsed from a WKT string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WktGeometry {
+    Point(Coordinate),
+    LineString(Vec<Coordinate>),
+    /// Outer ring only; holes are not represented in GEON.
+    Polygon(Vec<Coordinate>),
+    /// Members of a geometry collection, none of them collections.
+    Collection(Vec<WktGeometry>),
+}
+
+impl WktGeometry {
+    /// Set the section of `place` this geometry maps onto: LOCATION for a
+    /// point, PATH for a line, BOUNDARY for a polygon, and each in turn for
+    /// the members of a collection.
+    pub fn apply_to(self, place: &mut GeonPlace) {
+        match self {
+            WktGeometry::Point(c) => place.location = Some(c),
+            WktGeometry::LineString(line) => place.path = line,
+            WktGeometry::Polygon(ring) => place.boundary = ring,
+            WktGeometry::Collection(members) => members.into_iter().for_each(|m| m.apply_to(place)),
+        }
+    }
+}
+
+fn wkt_geometry(reader: &mut WktReader, nested: bool) -> Result<WktGeometry, GeonError> {
+    let kind = reader.keyword();
+    if reader.keyword() == "EMPTY" {
+        return Err(GeonError::InvalidWkt(format!("{} EMPTY has no geometry", kind)));
+    }
+    if kind == "GEOMETRYCOLLECTION" {
+        if nested {
+            return Err(GeonError::InvalidWkt("nested GEOMETRYCOLLECTION is not supported".to_string()));
+        }
+        if !reader.eat(b'(') {
+            return Err(GeonError::InvalidWkt(format!("expected '(' at offset {}", reader.pos)));
+        }
+        let mut members = Vec::new();
+        loop {
+            members.push(wkt_geometry(reader, true)?);
+            if reader.eat(b')') {
+                return Ok(WktGeometry::Collection(members));
+            }
+            if !reader.eat(b',') {
+                return Err(GeonError::InvalidWkt(format!("expected ',' or ')' at offset {}", reader.pos)));
+            }
+        }
+    }
+    let body = reader.list()?;
+
+    match kind.as_str() {
+        "POINT" => node_positions(&body)
+            .into_iter()
+            .next()
+            .map(WktGeometry::Point)
+            .ok_or_else(|| GeonError::InvalidWkt("POINT without coordinates".to_string())),
+        "LINESTRING" => Ok(WktGeometry::LineString(node_positions(&body))),
+        "POLYGON" => Ok(WktGeometry::Polygon(outer_ring(&body))),
+        "MULTIPOLYGON" => {
+            let polygons = match &body {
+                WktNode::List(items) => items.iter().map(outer_ring).collect::<Vec<_>>(),
+                WktNode::Position(_) => vec![],
+            };
+            polygons
+                .into_iter()
+                .max_by(|a, b| ring_area(a).total_cmp(&ring_area(b)))
+                .map(WktGeometry::Polygon)
+                .ok_or_else(|| GeonError::InvalidWkt("MULTIPOLYGON without polygons".to_string()))
+        }
+        other => Err(GeonError::InvalidWkt(format!("unsupported geometry type '{}'", other))),
+    }
+}
+
+/// Parse a WKT `POINT`, `LINESTRING`, `POLYGON` or `MULTIPOLYGON`, or a
+/// `GEOMETRYCOLLECTION` of them. A multipolygon is reduced to the outer ring
+/// of its largest member.
+pub fn from_wkt(text: &str) -> Result<WktGeometry, GeonError> {
+    let mut reader = WktReader { text: text.trim(), pos: 0 };
+    wkt_geometry(&mut reader, false)
+}
+
+// WKB (little-endian, 2D)
+
+const WKB_POINT: u32 = 1;
+const WKB_LINESTRING: u32 = 2;
+const WKB_POLYGON: u32 = 3;
+const WKB_MULTIPOLYGON: u32 = 6;
+const WKB_GEOMETRYCOLLECTION: u32 = 7;
+
+fn push_xy(buf: &mut Vec<u8>, c: &Coordinate) {
+    buf.extend(c.lon.to_le_bytes());
+    buf.extend(c.lat.to_le_bytes());
+}
+
+fn wkb_polygon(buf: &mut Vec<u8>, ring: &[Coordinate]) {
+    let closed = ring.first() == ring.last();
+    buf.push(1);
+    buf.extend(WKB_POLYGON.to_le_bytes());
+    buf.extend(1u32.to_le_bytes());
+    buf.extend((ring.len() as u32 + u32::from(!closed)).to_le_bytes());
+    for c in ring {
+        push_xy(buf, c);
+    }
+    if !closed {
+        push_xy(buf, &ring[0]);
+    }
+}
+
+fn wkb_line(buf: &mut Vec<u8>, path: &[Coordinate]) {
+    buf.push(1);
+    buf.extend(WKB_LINESTRING.to_le_bytes());
+    buf.extend((path.len() as u32).to_le_bytes());
+    for c in path {
+        push_xy(buf, c);
+    }
+}
+
+/// Encode a place's geometry as little-endian WKB, choosing POLYGON,
+/// LINESTRING, GEOMETRYCOLLECTION or POINT as [`to_wkt`] does.
+pub fn to_wkb(place: &GeonPlace) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    match (place.boundary.len() >= 3, place.path.len() >= 2) {
+        (true, true) => {
+            buf.push(1);
+            buf.extend(WKB_GEOMETRYCOLLECTION.to_le_bytes());
+            buf.extend(2u32.to_le_bytes());
+            wkb_polygon(&mut buf, &place.boundary);
+            wkb_line(&mut buf, &place.path);
+        }
+        (true, false) => wkb_polygon(&mut buf, &place.boundary),
+        (false, true) => wkb_line(&mut buf, &place.path),
+        (false, false) => {
+            buf.push(1);
+            buf.extend(WKB_POINT.to_le_bytes());
+            push_xy(&mut buf, place.location.as_ref()?);
+        }
+    }
+    Some(buf)
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    little: bool,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], GeonError> {
+        let chunk = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| GeonError::InvalidWkt("truncated WKB".to_string()))?;
+        self.pos += N;
+        Ok(chunk.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, GeonError> {
+        let b = self.take::<4>()?;
+        Ok(if self.little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64(&mut self) -> Result<f64, GeonError> {
+        let b = self.take::<8>()?;
+        Ok(if self.little { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    // Byte order and geometry type, with Z/M/ZM (ISO or EWKB) flagged by dimension count
+    fn header(&mut self) -> Result<(u32, usize), GeonError> {
+        self.little = self.take::<1>()?[0] == 1;
+        let raw = self.u32()?;
+        let mut dims = 2;
+        if raw & 0x8000_0000 != 0 {
+            dims += 1;
+        }
+        if raw & 0x4000_0000 != 0 {
+            dims += 1;
+        }
+        if raw & 0x2000_0000 != 0 {
+            self.u32()?; // EWKB SRID
+        }
+        let iso = raw & 0x0FFF_FFFF;
+        dims += match iso / 1000 {
+            1 | 2 => 1,
+            3 => 2,
+            _ => 0,
+        };
+        Ok((iso % 1000, dims))
+    }
+
+    fn position(&mut self, dims: usize) -> Result<Coordinate, GeonError> {
+        let x = self.f64()?;
+        let y = self.f64()?;
+        for _ in 2..dims {
+            self.f64()?;
+        }
+        Ok(Coordinate { lat: y, lon: x })
+    }
+
+    // Rings of a polygon body; returns the outer ring
+    fn polygon(&mut self, dims: usize) -> Result<Vec<Coordinate>, GeonError> {
+        let rings = self.u32()?;
+        let mut outer = Vec::new();
+        for r in 0..rings {
+            let n = self.u32()?;
+            let ring = (0..n).map(|_| self.position(dims)).collect::<Result<Vec<_>, _>>()?;
+            if r == 0 {
+                outer = ring;
+            }
+        }
+        Ok(outer)
+    }
+
+    fn geometry(&mut self, nested: bool) -> Result<WktGeometry, GeonError> {
+        let (kind, dims) = self.header()?;
+        match kind {
+            WKB_POINT => Ok(WktGeometry::Point(self.position(dims)?)),
+            WKB_LINESTRING => {
+                let n = self.u32()?;
+                Ok(WktGeometry::LineString((0..n).map(|_| self.position(dims)).collect::<Result<_, _>>()?))
+            }
+            WKB_POLYGON => Ok(WktGeometry::Polygon(self.polygon(dims)?)),
+            WKB_MULTIPOLYGON => {
+                let n = self.u32()?;
+                let mut largest: Vec<Coordinate> = Vec::new();
+                for _ in 0..n {
+                    let (_, dims) = self.header()?;
+                    let ring = self.polygon(dims)?;
+                    if ring_area(&ring) > ring_area(&largest) {
+                        largest = ring;
+                    }
+                }
+                Ok(WktGeometry::Polygon(largest))
+            }
+            WKB_GEOMETRYCOLLECTION if nested => {
+                Err(GeonError::InvalidWkt("nested WKB geometry collection is not supported".to_string()))
+            }
+            WKB_GEOMETRYCOLLECTION => {
+                let n = self.u32()?;
+                Ok(WktGeometry::Collection((0..n).map(|_| self.geometry(true)).collect::<Result<_, _>>()?))
+            }
+            other => Err(GeonError::InvalidWkt(format!("unsupported WKB geometry type {}", other))),
+        }
+    }
+}
+
+/// Decode WKB (either byte order, ISO or EWKB flags) for the same geometry
+/// types as [`from_wkt`].
+pub fn from_wkb(bytes: &[u8]) -> Result<WktGeometry, GeonError> {
+    let mut reader = WkbReader { bytes, pos: 0, little: true };
+    reader.geometry(false)
+}
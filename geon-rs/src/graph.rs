@@ -0,0 +1,431 @@
+use crate::collection::{GeonCollection, PlaceHandle};
+use crate::models::GeonPlace;
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+/// A place, or a name referred to by PART_OF or ADJACENCIES that no place
+/// in the collection carries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// ID where the place has one, otherwise its name.
+    pub key: String,
+    pub name: String,
+    pub type_: String,
+    /// Position of the place: its index in the collection, then indices
+    /// into successive CONTAINS lists. Empty for referenced names.
+    pub path: Vec<usize>,
+}
+
+impl Node {
+    pub fn is_external(&self) -> bool {
+        self.path.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// `from` contains `to`, from CONTAINS nesting or a PART_OF chain.
+    Contains,
+    /// `from` lists `to` in ADJACENCIES. Adjacency is symmetric for traversal.
+    Adjacent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+    /// The note after an adjacency, e.g. `immediate west`.
+    pub label: Option<String>,
+}
+
+/// Containment and adjacency between the places of a collection, as node
+/// and edge lists indexed by position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaceGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    // Edge indices leaving and entering each node
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    // IDs and lowercased names to nodes; the first place with a name wins
+    lookup: HashMap<String, usize>,
+}
+
+// `Council House (immediate west)` -> (`Council House`, `immediate west`)
+pub(crate) fn split_note(reference: &str) -> (&str, Option<&str>) {
+    let reference = reference.trim();
+    match reference.strip_suffix(')').and_then(|r| r.rsplit_once('(')) {
+        Some((name, note)) if !name.trim().is_empty() => (name.trim(), Some(note.trim())),
+        _ => (reference, None),
+    }
+}
+
+impl PlaceGraph {
+    /// Build the graph of every place in the collection, nested CONTAINS
+    /// included. PART_OF chains (smallest first) and ADJACENCIES entries are
+    /// resolved by ID or name, case-insensitively; names that match no place
+    /// become external nodes.
+    pub fn from_collection(collection: &GeonCollection) -> Self {
+        let mut graph = Self::default();
+        let mut pending = Vec::new();
+        for (i, place) in collection.places.iter().enumerate() {
+            graph.add_place(place, vec![i], None, &mut pending);
+        }
+
+        for (node, place) in pending {
+            if let Some(chain) = &place.part_of {
+                let mut child = node;
+                for name in chain.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    let parent = graph.resolve(name);
+                    graph.add_edge(parent, child, EdgeKind::Contains, None);
+                    child = parent;
+                }
+            }
+            for reference in &place.adjacencies {
+                let (name, note) = split_note(reference);
+                if !name.is_empty() {
+                    let other = graph.resolve(name);
+                    graph.add_edge(node, other, EdgeKind::Adjacent, note.map(str::to_string));
+                }
+            }
+        }
+        graph
+    }
+
+    fn add_place<'a>(
+        &mut self,
+        place: &'a GeonPlace,
+        path: Vec<usize>,
+        parent: Option<usize>,
+        pending: &mut Vec<(usize, &'a GeonPlace)>,
+    ) {
+        let node = self.add_node(Node {
+            key: place.id.clone().unwrap_or_else(|| place.place.clone()),
+            name: place.place.clone(),
+            type_: place.type_.clone(),
+            path: path.clone(),
+        });
+        if let Some(id) = &place.id {
+            self.lookup.insert(id.clone(), node);
+        }
+        self.lookup.entry(place.place.to_lowercase()).or_insert(node);
+        if let Some(parent) = parent {
+            self.add_edge(parent, node, EdgeKind::Contains, None);
+        }
+        pending.push((node, place));
+        for (j, child) in place.contains.iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(j);
+            self.add_place(child, child_path, Some(node), pending);
+        }
+    }
+
+    fn add_node(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.outgoing.push(Vec::new());
+        self.incoming.push(Vec::new());
+        self.nodes.len() - 1
+    }
+
+    // The node for an ID or name, adding an external one if there is none
+    fn resolve(&mut self, reference: &str) -> usize {
+        if let Some(node) = self.find(reference) {
+            return node;
+        }
+        let node = self.add_node(Node {
+            key: reference.to_string(),
+            name: reference.to_string(),
+            type_: String::new(),
+            path: Vec::new(),
+        });
+        self.lookup.insert(reference.to_lowercase(), node);
+        node
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, kind: EdgeKind, label: Option<String>) {
+        let exists = self.outgoing[from].iter().any(|e| self.edges[*e].to == to && self.edges[*e].kind == kind);
+        if from == to || exists {
+            return;
+        }
+        self.edges.push(Edge { from, to, kind, label });
+        self.outgoing[from].push(self.edges.len() - 1);
+        self.incoming[to].push(self.edges.len() - 1);
+    }
+
+    /// The node for a place ID or name.
+    pub fn find(&self, reference: &str) -> Option<usize> {
+        self.lookup.get(reference).or_else(|| self.lookup.get(&reference.to_lowercase())).copied()
+    }
+
+    /// The place a node stands for, or `None` for external nodes.
+    pub fn place<'a>(&self, node: usize, collection: &'a GeonCollection) -> Option<&'a GeonPlace> {
+        PlaceHandle(self.nodes.get(node)?.path.clone()).get(collection)
+    }
+
+    /// Edges leaving `node`.
+    pub fn outgoing(&self, node: usize) -> impl Iterator<Item = &Edge> {
+        self.outgoing[node].iter().map(|e| &self.edges[*e])
+    }
+
+    /// Edges entering `node`.
+    pub fn incoming(&self, node: usize) -> impl Iterator<Item = &Edge> {
+        self.incoming[node].iter().map(|e| &self.edges[*e])
+    }
+
+    /// Nodes directly contained by `node`.
+    pub fn children(&self, node: usize) -> Vec<usize> {
+        self.outgoing(node).filter(|e| e.kind == EdgeKind::Contains).map(|e| e.to).collect()
+    }
+
+    /// Nodes directly containing `node`.
+    pub fn parents(&self, node: usize) -> Vec<usize> {
+        self.incoming(node).filter(|e| e.kind == EdgeKind::Contains).map(|e| e.from).collect()
+    }
+
+    /// Nodes adjacent to `node`, in either direction.
+    pub fn adjacent(&self, node: usize) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .outgoing(node)
+            .filter(|e| e.kind == EdgeKind::Adjacent)
+            .map(|e| e.to)
+            .chain(self.incoming(node).filter(|e| e.kind == EdgeKind::Adjacent).map(|e| e.from))
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    // Breadth-first from `start` (excluded) via `next`, with the hop count
+    // of each node reached; safe on cyclic containment
+    fn walk(&self, start: usize, max_hops: usize, next: impl Fn(usize) -> Vec<usize>) -> Vec<(usize, usize)> {
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut found = Vec::new();
+        while let Some((node, hops)) = queue.pop_front() {
+            if hops == max_hops {
+                continue;
+            }
+            for n in next(node) {
+                if seen.insert(n) {
+                    found.push((n, hops + 1));
+                    queue.push_back((n, hops + 1));
+                }
+            }
+        }
+        found
+    }
+
+    fn nodes_of(&self, found: Vec<(usize, usize)>) -> Vec<&Node> {
+        found.into_iter().map(|(n, _)| &self.nodes[n]).collect()
+    }
+
+    /// Everything containing the place with this ID or name, nearest first,
+    /// so the last entry is its outermost container.
+    pub fn ancestors(&self, reference: &str) -> Vec<&Node> {
+        let Some(start) = self.find(reference) else { return vec![] };
+        self.nodes_of(self.walk(start, usize::MAX, |n| self.parents(n)))
+    }
+
+    /// Everything the place contains, directly or not, nearest first.
+    pub fn descendants(&self, reference: &str) -> Vec<&Node> {
+        let Some(start) = self.find(reference) else { return vec![] };
+        self.nodes_of(self.walk(start, usize::MAX, |n| self.children(n)))
+    }
+
+    /// Places reachable through at most `hops` ADJACENCIES links, with
+    /// their distance in hops, nearest first.
+    pub fn neighbours_within(&self, reference: &str, hops: usize) -> Vec<(&Node, usize)> {
+        let Some(start) = self.find(reference) else { return vec![] };
+        self.walk(start, hops, |n| self.adjacent(n)).into_iter().map(|(n, h)| (&self.nodes[n], h)).collect()
+    }
+
+    /// Containment cycles, each listed from its first node in graph order
+    /// around to the node that contains it again. Every place in a cycle of
+    /// PART_OF/CONTAINS links appears in exactly one reported cycle.
+    pub fn containment_cycles(&self) -> Vec<Vec<&Node>> {
+        let children: Vec<Vec<usize>> = (0..self.nodes.len()).map(|n| self.children(n)).collect();
+        let mut cycles = Vec::new();
+        for component in strongly_connected(&children).into_iter().filter(|c| c.len() > 1) {
+            // Shortest way round from the first node, within the component
+            let members: HashSet<usize> = component.iter().copied().collect();
+            let start = component[0];
+            let mut previous: HashMap<usize, usize> = HashMap::new();
+            let mut queue = VecDeque::from([start]);
+            'search: while let Some(node) = queue.pop_front() {
+                for &child in children[node].iter().filter(|c| members.contains(c)) {
+                    if child == start {
+                        let mut cycle = vec![node];
+                        while let Some(p) = previous.get(cycle.last().unwrap()) {
+                            cycle.push(*p);
+                        }
+                        cycles.push(cycle.into_iter().rev().map(|n| &self.nodes[n]).collect());
+                        break 'search;
+                    }
+                    if let Entry::Vacant(e) = previous.entry(child) {
+                        e.insert(node);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+        cycles
+    }
+
+    /// The shortest chain of containment links between two places, up and
+    /// down through their containers, both ends included. `None` when the
+    /// places share no container.
+    pub fn containment_path(&self, from: &str, to: &str) -> Option<Vec<&Node>> {
+        let (start, goal) = (self.find(from)?, self.find(to)?);
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        let mut seen = HashSet::from([start]);
+        while let Some(node) = queue.pop_front() {
+            if node == goal {
+                let mut path = vec![goal];
+                while let Some(p) = previous.get(path.last()?) {
+                    path.push(*p);
+                }
+                return Some(path.into_iter().rev().map(|n| &self.nodes[n]).collect());
+            }
+            for n in self.parents(node).into_iter().chain(self.children(node)) {
+                if seen.insert(n) {
+                    previous.insert(n, node);
+                    queue.push_back(n);
+                }
+            }
+        }
+        None
+    }
+}
+
+// Tarjan's strongly connected components over adjacency lists, iteratively
+// so deep containment chains can't overflow the stack. Components come out
+// with their nodes in ascending order.
+fn strongly_connected(next: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = next.len();
+    let (mut index, mut low) = (vec![usize::MAX; n], vec![0; n]);
+    let mut on_stack = vec![false; n];
+    let (mut stack, mut components, mut counter) = (Vec::new(), Vec::new(), 0);
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        let mut calls = vec![(root, 0)];
+        index[root] = counter;
+        low[root] = counter;
+        counter += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some((v, i)) = calls.pop() {
+            if let Some(&w) = next[v].get(i) {
+                calls.push((v, i + 1));
+                if index[w] == usize::MAX {
+                    index[w] = counter;
+                    low[w] = counter;
+                    counter += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+            // All successors done: fold into the caller, or close a component
+            if let Some(&(caller, _)) = calls.last() {
+                low[caller] = low[caller].min(low[v]);
+            }
+            if low[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+        }
+    }
+    components.sort();
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_from_collection() {
+        let mut square = GeonPlace::default();
+        square.place = "Old Market Square".to_string();
+        square.id = Some("osm:way/1".to_string());
+        square.part_of = Some("City Centre, Nottingham".to_string());
+        square.adjacencies = vec!["Council House (immediate east)".to_string(), "Long Row".to_string()];
+        let mut stall = GeonPlace::default();
+        stall.place = "Fountain".to_string();
+        square.contains = vec![stall];
+
+        let mut council = GeonPlace::default();
+        council.place = "Council House".to_string();
+        council.part_of = Some("city centre".to_string());
+        council.adjacencies = vec!["Old Market Square (west)".to_string()];
+
+        let collection = GeonCollection::new(vec![square, council]);
+        let graph = PlaceGraph::from_collection(&collection);
+        let names: Vec<&str> = graph.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["Old Market Square", "Fountain", "Council House", "City Centre", "Nottingham", "Long Row"]);
+
+        let square = graph.find("osm:way/1").unwrap();
+        let fountain = graph.find("fountain").unwrap();
+        let centre = graph.find("City Centre").unwrap();
+        assert_eq!(graph.nodes[square].key, "osm:way/1");
+        assert_eq!(graph.children(square), [fountain]);
+        assert_eq!(graph.parents(square), [centre]);
+        assert_eq!(graph.children(centre), [square, graph.find("Council House").unwrap()]);
+        assert_eq!(graph.parents(centre), [graph.find("Nottingham").unwrap()]);
+        assert!(graph.nodes[centre].is_external());
+        assert_eq!(graph.place(fountain, &collection).map(|p| p.place.as_str()), Some("Fountain"));
+
+        let council = graph.find("Council House").unwrap();
+        assert_eq!(graph.adjacent(council), [square]);
+        assert_eq!(graph.outgoing(square).find(|e| e.to == council).unwrap().label.as_deref(), Some("immediate east"));
+    }
+
+    #[test]
+    fn test_traversal() {
+        let place = |name: &str, part_of: &str, adjacencies: &[&str]| {
+            let mut p = GeonPlace::default();
+            p.place = name.to_string();
+            p.part_of = Some(part_of.to_string());
+            p.adjacencies = adjacencies.iter().map(|a| a.to_string()).collect();
+            p
+        };
+        let collection = GeonCollection::new(vec![
+            place("Fish Stall", "Victoria Market, Victoria Centre, City Centre", &["Cheese Stall"]),
+            place("Cheese Stall", "Victoria Market", &["Bakery Stall"]),
+            place("Bakery Stall", "Victoria Market", &[]),
+            place("Council House", "City Centre", &[]),
+        ]);
+        let graph = PlaceGraph::from_collection(&collection);
+        let names = |nodes: Vec<&Node>| nodes.iter().map(|n| n.name.clone()).collect::<Vec<_>>();
+
+        assert_eq!(names(graph.ancestors("Fish Stall")), ["Victoria Market", "Victoria Centre", "City Centre"]);
+        assert_eq!(graph.ancestors("Cheese Stall").last().unwrap().name, "City Centre");
+        assert_eq!(names(graph.descendants("Victoria Centre")), ["Victoria Market", "Fish Stall", "Cheese Stall", "Bakery Stall"]);
+        assert!(graph.ancestors("Nowhere").is_empty());
+
+        let near: Vec<(String, usize)> = graph
+            .neighbours_within("Fish Stall", 2)
+            .into_iter()
+            .map(|(n, hops)| (n.name.clone(), hops))
+            .collect();
+        assert_eq!(near, [("Cheese Stall".to_string(), 1), ("Bakery Stall".to_string(), 2)]);
+        assert_eq!(graph.neighbours_within("Fish Stall", 1).len(), 1);
+
+        let path = graph.containment_path("Bakery Stall", "Council House").unwrap();
+        assert_eq!(names(path), ["Bakery Stall", "Victoria Market", "Victoria Centre", "City Centre", "Council House"]);
+    }
+}
@@ -0,0 +1,15 @@
+//! Reading and writing GEON collections in external storage formats.
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+mod document;
+pub mod geoparquet;
+pub mod gpkg;
+pub mod mvt;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
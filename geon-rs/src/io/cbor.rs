@@ -0,0 +1,518 @@
+//! Compact binary serialisation (CBOR, RFC 8949).
+//!
+//! A payload is one version byte ([`VERSION`]) followed by a CBOR map from
+//! integer field tags to values. Tags are part of the format and never
+//! reused:
+//!
+//! | tag | field        | tag | field          | tag | field            |
+//! |-----|--------------|-----|----------------|-----|------------------|
+//! | 1   | place        | 20  | adjacencies    | 50  | built_form       |
+//! | 2   | type         | 21  | connectivity   | 51  | ecology          |
+//! | 3   | id           | 22  | contains       | 52  | infrastructure   |
+//! | 4   | location     | 23  | part_of        | 53  | demographics     |
+//! | 5   | boundary     | 24  | viewsheds      | 54  | economy          |
+//! | 6   | extent       | 30  | temporal       | 60  | visual           |
+//! | 7   | elevation    | 31  | lifespan       | 61  | history          |
+//! | 8   | area         | 40  | source         | 62  | vertical_profile |
+//! | 9   | path         | 41  | confidence     | 100 | extra            |
+//! | 10  | purpose      | 42  | updated        |     |                  |
+//! | 11  | experience   |     |                |     |                  |
+//! | 12  | character    |     |                |     |                  |
+//!
+//! LOCATION is `[lat, lon]`, BOUNDARY and PATH arrays of those, and EXTENT
+//! `[north, south, east, west]`. Empty fields are omitted. Decoders skip
+//! tags they do not know, so fields can be added without a version bump;
+//! the version changes only when an existing tag's meaning does.
+
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Format version written as the first byte of every payload.
+pub const VERSION: u8 = 1;
+
+const PLACE: u64 = 1;
+const TYPE: u64 = 2;
+const ID: u64 = 3;
+const LOCATION: u64 = 4;
+const BOUNDARY: u64 = 5;
+const EXTENT: u64 = 6;
+const ELEVATION: u64 = 7;
+const AREA: u64 = 8;
+const PATH: u64 = 9;
+const PURPOSE: u64 = 10;
+const EXPERIENCE: u64 = 11;
+const CHARACTER: u64 = 12;
+const ADJACENCIES: u64 = 20;
+const CONNECTIVITY: u64 = 21;
+const CONTAINS: u64 = 22;
+const PART_OF: u64 = 23;
+const VIEWSHEDS: u64 = 24;
+const TEMPORAL: u64 = 30;
+const LIFESPAN: u64 = 31;
+const SOURCE: u64 = 40;
+const CONFIDENCE: u64 = 41;
+const UPDATED: u64 = 42;
+const BUILT_FORM: u64 = 50;
+const ECOLOGY: u64 = 51;
+const INFRASTRUCTURE: u64 = 52;
+const DEMOGRAPHICS: u64 = 53;
+const ECONOMY: u64 = 54;
+const VISUAL: u64 = 60;
+const HISTORY: u64 = 61;
+const VERTICAL_PROFILE: u64 = 62;
+const EXTRA: u64 = 100;
+
+fn invalid(msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("CBOR: {}", msg))
+}
+
+// Encoding
+
+fn head(buf: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => buf.push(major | n as u8),
+        24..=0xff => buf.extend([major | 24, n as u8]),
+        0x100..=0xffff => {
+            buf.push(major | 25);
+            buf.extend((n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(major | 26);
+            buf.extend((n as u32).to_be_bytes());
+        }
+        _ => {
+            buf.push(major | 27);
+            buf.extend(n.to_be_bytes());
+        }
+    }
+}
+
+fn text(buf: &mut Vec<u8>, s: &str) {
+    head(buf, 3, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn float(buf: &mut Vec<u8>, f: f64) {
+    buf.push(0xfb);
+    buf.extend(f.to_be_bytes());
+}
+
+fn strings(buf: &mut Vec<u8>, items: &[String]) {
+    head(buf, 4, items.len() as u64);
+    for s in items {
+        text(buf, s);
+    }
+}
+
+fn string_map(buf: &mut Vec<u8>, m: &BTreeMap<String, String>) {
+    let mut entries: Vec<_> = m.iter().collect();
+    entries.sort();
+    head(buf, 5, entries.len() as u64);
+    for (k, v) in entries {
+        text(buf, k);
+        text(buf, v);
+    }
+}
+
+fn coordinate(buf: &mut Vec<u8>, c: &Coordinate) {
+    head(buf, 4, 2);
+    float(buf, c.lat);
+    float(buf, c.lon);
+}
+
+fn json(buf: &mut Vec<u8>, v: &Value) {
+    match v {
+        Value::Null => buf.push(0xf6),
+        Value::Bool(b) => buf.push(if *b { 0xf5 } else { 0xf4 }),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                head(buf, 0, u);
+            } else if let Some(i) = n.as_i64() {
+                head(buf, 1, (-1 - i) as u64);
+            } else {
+                float(buf, n.as_f64().unwrap_or(f64::NAN));
+            }
+        }
+        Value::String(s) => text(buf, s),
+        Value::Array(items) => {
+            head(buf, 4, items.len() as u64);
+            for item in items {
+                json(buf, item);
+            }
+        }
+        Value::Object(m) => {
+            head(buf, 5, m.len() as u64);
+            for (k, v) in m {
+                text(buf, k);
+                json(buf, v);
+            }
+        }
+    }
+}
+
+fn encode_place(buf: &mut Vec<u8>, p: &GeonPlace) {
+    let mut fields: Vec<(u64, Vec<u8>)> = Vec::new();
+    let mut field = |tag: u64, write: &dyn Fn(&mut Vec<u8>)| {
+        let mut value = Vec::new();
+        write(&mut value);
+        fields.push((tag, value));
+    };
+
+    field(PLACE, &|b| text(b, &p.place));
+    if !p.type_.is_empty() {
+        field(TYPE, &|b| text(b, &p.type_));
+    }
+    let optional = [(ID, &p.id), (ELEVATION, &p.elevation), (AREA, &p.area), (PART_OF, &p.part_of), (UPDATED, &p.updated)];
+    for (tag, value) in optional {
+        if let Some(v) = value {
+            field(tag, &|b| text(b, v));
+        }
+    }
+    if let Some(c) = &p.location {
+        field(LOCATION, &|b| coordinate(b, c));
+    }
+    if !p.boundary.is_empty() {
+        field(BOUNDARY, &|b| {
+            head(b, 4, p.boundary.len() as u64);
+            for c in &p.boundary {
+                coordinate(b, c);
+            }
+        });
+    }
+    if !p.path.is_empty() {
+        field(PATH, &|b| {
+            head(b, 4, p.path.len() as u64);
+            for c in &p.path {
+                coordinate(b, c);
+            }
+        });
+    }
+    if let Some(e) = &p.extent {
+        field(EXTENT, &|b| {
+            head(b, 4, 4);
+            for v in [e.north, e.south, e.east, e.west] {
+                float(b, v);
+            }
+        });
+    }
+    let lists = [(PURPOSE, &p.purpose), (CHARACTER, &p.character), (ADJACENCIES, &p.adjacencies), (SOURCE, &p.source)];
+    for (tag, list) in lists {
+        if !list.is_empty() {
+            field(tag, &|b| strings(b, list));
+        }
+    }
+    let maps = [
+        (EXPERIENCE, &p.experience),
+        (CONNECTIVITY, &p.connectivity),
+        (TEMPORAL, &p.temporal),
+        (LIFESPAN, &p.lifespan),
+        (CONFIDENCE, &p.confidence),
+        (BUILT_FORM, &p.built_form),
+        (ECOLOGY, &p.ecology),
+        (INFRASTRUCTURE, &p.infrastructure),
+        (DEMOGRAPHICS, &p.demographics),
+        (ECONOMY, &p.economy),
+        (VISUAL, &p.visual),
+        (VERTICAL_PROFILE, &p.vertical_profile),
+    ];
+    for (tag, m) in maps {
+        if !m.is_empty() {
+            field(tag, &|b| string_map(b, m));
+        }
+    }
+    if !p.contains.is_empty() {
+        field(CONTAINS, &|b| {
+            head(b, 4, p.contains.len() as u64);
+            for child in &p.contains {
+                encode_place(b, child);
+            }
+        });
+    }
+    if !p.viewsheds.is_null() {
+        field(VIEWSHEDS, &|b| json(b, &p.viewsheds));
+    }
+    if !p.history.is_empty() {
+        field(HISTORY, &|b| {
+            head(b, 4, p.history.len() as u64);
+            for entry in &p.history {
+                string_map(b, entry);
+            }
+        });
+    }
+    if !p.extra.is_empty() {
+        let extra: Map<String, Value> = p.extra.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        field(EXTRA, &|b| json(b, &Value::Object(extra.clone())));
+    }
+
+    fields.sort_by_key(|(tag, _)| *tag);
+    head(buf, 5, fields.len() as u64);
+    for (tag, value) in fields {
+        head(buf, 0, tag);
+        buf.extend(value);
+    }
+}
+
+/// Encode a place as a versioned CBOR payload.
+pub fn to_cbor(place: &GeonPlace) -> Vec<u8> {
+    let mut buf = vec![VERSION];
+    encode_place(&mut buf, place);
+    buf
+}
+
+// Decoding
+
+#[derive(Debug, Clone)]
+enum Item {
+    Int(i128),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Item>),
+    Map(Vec<(Item, Item)>),
+    Bool(bool),
+    Null,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], GeonError> {
+        let chunk = self.bytes.get(self.pos..self.pos + n).ok_or_else(|| invalid("unexpected end of data"))?;
+        self.pos += n;
+        Ok(chunk)
+    }
+
+    fn argument(&mut self, info: u8) -> Result<u64, GeonError> {
+        Ok(match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => return Err(invalid("indefinite lengths are not supported")),
+        })
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Item, GeonError> {
+        if depth > 64 {
+            return Err(invalid("nesting too deep"));
+        }
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return Ok(match info {
+                20 => Item::Bool(false),
+                21 => Item::Bool(true),
+                22 | 23 => Item::Null,
+                25 => Item::Float(half(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))),
+                26 => Item::Float(f32::from_be_bytes(self.take(4)?.try_into().unwrap()) as f64),
+                27 => Item::Float(f64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+                _ => return Err(invalid("unsupported simple value")),
+            });
+        }
+        let n = self.argument(info)?;
+        let len = |n: u64| usize::try_from(n).map_err(|_| invalid("length too large"));
+        Ok(match major {
+            0 => Item::Int(n as i128),
+            1 => Item::Int(-1 - n as i128),
+            2 => Item::Bytes(self.take(len(n)?)?.to_vec()),
+            3 => Item::Text(String::from_utf8(self.take(len(n)?)?.to_vec()).map_err(|_| invalid("invalid UTF-8"))?),
+            4 => Item::Array((0..n).map(|_| self.item(depth + 1)).collect::<Result<_, _>>()?),
+            5 => Item::Map((0..n).map(|_| Ok((self.item(depth + 1)?, self.item(depth + 1)?))).collect::<Result<_, GeonError>>()?),
+            // Tagged item: keep the content, drop the tag
+            _ => self.item(depth + 1)?,
+        })
+    }
+}
+
+fn half(bits: u16) -> f64 {
+    let exp = (bits >> 10) & 0x1f;
+    let mant = (bits & 0x3ff) as f64;
+    let value = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mant + 1024.0) * 2f64.powi(exp as i32 - 25),
+    };
+    if bits & 0x8000 != 0 { -value } else { value }
+}
+
+fn to_json(item: &Item) -> Value {
+    match item {
+        Item::Int(i) => i64::try_from(*i).map(Value::from).unwrap_or_else(|_| Value::from(*i as f64)),
+        Item::Float(f) => Value::from(*f),
+        Item::Bytes(b) => Value::Array(b.iter().map(|x| Value::from(*x)).collect()),
+        Item::Text(s) => Value::String(s.clone()),
+        Item::Array(items) => Value::Array(items.iter().map(to_json).collect()),
+        Item::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| {
+                    let key = match k {
+                        Item::Text(s) => s.clone(),
+                        other => to_json(other).to_string(),
+                    };
+                    (key, to_json(v))
+                })
+                .collect(),
+        ),
+        Item::Bool(b) => Value::Bool(*b),
+        Item::Null => Value::Null,
+    }
+}
+
+fn as_text(item: &Item) -> Result<String, GeonError> {
+    match item {
+        Item::Text(s) => Ok(s.clone()),
+        _ => Err(invalid("expected text")),
+    }
+}
+
+fn as_f64(item: &Item) -> Result<f64, GeonError> {
+    match item {
+        Item::Float(f) => Ok(*f),
+        Item::Int(i) => Ok(*i as f64),
+        _ => Err(invalid("expected a number")),
+    }
+}
+
+fn as_array(item: &Item) -> Result<&[Item], GeonError> {
+    match item {
+        Item::Array(items) => Ok(items),
+        _ => Err(invalid("expected an array")),
+    }
+}
+
+fn as_strings(item: &Item) -> Result<Vec<String>, GeonError> {
+    as_array(item)?.iter().map(as_text).collect()
+}
+
+fn as_string_map(item: &Item) -> Result<BTreeMap<String, String>, GeonError> {
+    match item {
+        Item::Map(entries) => entries.iter().map(|(k, v)| Ok((as_text(k)?, as_text(v)?))).collect(),
+        _ => Err(invalid("expected a map")),
+    }
+}
+
+fn as_coordinate(item: &Item) -> Result<Coordinate, GeonError> {
+    match as_array(item)? {
+        [lat, lon] => Ok(Coordinate::new(as_f64(lat)?, as_f64(lon)?)),
+        _ => Err(invalid("expected [lat, lon]")),
+    }
+}
+
+fn decode_place(item: &Item) -> Result<GeonPlace, GeonError> {
+    let Item::Map(entries) = item else {
+        return Err(invalid("a place must be a map"));
+    };
+    let mut p = GeonPlace::default();
+    for (tag, value) in entries {
+        let Item::Int(tag) = tag else { continue };
+        let tag = *tag as u64;
+        match tag {
+            PLACE => p.place = as_text(value)?,
+            TYPE => p.type_ = as_text(value)?,
+            ID => p.id = Some(as_text(value)?),
+            LOCATION => p.location = Some(as_coordinate(value)?),
+            BOUNDARY => p.boundary = as_array(value)?.iter().map(as_coordinate).collect::<Result<_, _>>()?,
+            PATH => p.path = as_array(value)?.iter().map(as_coordinate).collect::<Result<_, _>>()?,
+            EXTENT => match as_array(value)? {
+                [n, s, e, w] => {
+                    p.extent = Some(Extent { north: as_f64(n)?, south: as_f64(s)?, east: as_f64(e)?, west: as_f64(w)? })
+                }
+                _ => return Err(invalid("expected [north, south, east, west]")),
+            },
+            ELEVATION => p.elevation = Some(as_text(value)?),
+            AREA => p.area = Some(as_text(value)?),
+            PURPOSE => p.purpose = as_strings(value)?,
+            EXPERIENCE => p.experience = as_string_map(value)?,
+            CHARACTER => p.character = as_strings(value)?,
+            ADJACENCIES => p.adjacencies = as_strings(value)?,
+            CONNECTIVITY => p.connectivity = as_string_map(value)?,
+            CONTAINS => p.contains = as_array(value)?.iter().map(decode_place).collect::<Result<_, _>>()?,
+            PART_OF => p.part_of = Some(as_text(value)?),
+            VIEWSHEDS => p.viewsheds = to_json(value),
+            TEMPORAL => p.temporal = as_string_map(value)?,
+            LIFESPAN => p.lifespan = as_string_map(value)?,
+            SOURCE => p.source = as_strings(value)?,
+            CONFIDENCE => p.confidence = as_string_map(value)?,
+            UPDATED => p.updated = Some(as_text(value)?),
+            BUILT_FORM => p.built_form = as_string_map(value)?,
+            ECOLOGY => p.ecology = as_string_map(value)?,
+            INFRASTRUCTURE => p.infrastructure = as_string_map(value)?,
+            DEMOGRAPHICS => p.demographics = as_string_map(value)?,
+            ECONOMY => p.economy = as_string_map(value)?,
+            VISUAL => p.visual = as_string_map(value)?,
+            HISTORY => p.history = as_array(value)?.iter().map(as_string_map).collect::<Result<_, _>>()?,
+            VERTICAL_PROFILE => p.vertical_profile = as_string_map(value)?,
+            EXTRA => {
+                if let Value::Object(m) = to_json(value) {
+                    p.extra = m.into_iter().collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(p)
+}
+
+/// Decode a payload written by [`to_cbor`]. Payloads from a newer format
+/// version are rejected.
+pub fn from_cbor(bytes: &[u8]) -> Result<GeonPlace, GeonError> {
+    match bytes.first() {
+        Some(&VERSION) => {}
+        Some(v) => return Err(invalid(&format!("unsupported format version {}", v))),
+        None => return Err(invalid("empty payload")),
+    }
+    let mut decoder = Decoder { bytes, pos: 1 };
+    let item = decoder.item(0)?;
+    if decoder.pos != bytes.len() {
+        return Err(invalid("trailing bytes after place"));
+    }
+    decode_place(&item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_round_trip() {
+        let mut child = GeonPlace::default();
+        child.place = "Council House".to_string();
+        child.boundary = vec![Coordinate::new(1.0, 2.0), Coordinate::new(1.5, 2.0), Coordinate::new(1.5, 2.5)];
+
+        let mut place = GeonPlace::default();
+        place.place = "Old Market Square".to_string();
+        place.type_ = "public_space".to_string();
+        place.location = Some(Coordinate::new(52.953, -1.1497));
+        place.extent = Some(Extent { north: 53.0, south: 52.9, east: -1.1, west: -1.2 });
+        place.purpose = vec!["trade".to_string()];
+        place.experience.insert("noise".to_string(), "busy".to_string());
+        place.contains = vec![child];
+        place.viewsheds = serde_json::json!(["Castle", {"distance": -3}]);
+        place.history = vec![[("year".to_string(), "1929".to_string())].into()];
+        place.extra.insert("levels".to_string(), serde_json::json!([1, 2.5, null, true]));
+
+        let bytes = to_cbor(&place);
+        assert_eq!(bytes[0], VERSION);
+        assert_eq!(from_cbor(&bytes).unwrap(), place);
+        assert!(bytes.len() < serde_json::to_vec(&place).unwrap().len());
+    }
+
+    #[test]
+    fn test_cbor_versioning() {
+        // Unknown tag 999 is skipped; an unknown version is rejected
+        let mut payload = vec![VERSION, 0xa2, 0x01, 0x61, b'X', 0x19, 0x03, 0xe7, 0xf9, 0x3c, 0x00];
+        assert_eq!(from_cbor(&payload).unwrap().place, "X");
+        payload[0] = VERSION + 1;
+        assert!(from_cbor(&payload).is_err());
+        assert!(from_cbor(&[VERSION, 0xa1]).is_err());
+    }
+}
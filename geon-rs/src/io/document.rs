@@ -0,0 +1,278 @@
+//! Ordered key/value tree shared by the text serialisations (YAML, TOML).
+//!
+//! Places map onto it with GEON key names (`PLACE`, `TYPE`, `LOCATION`, ...)
+//! in the order the generator writes them, and with LOCATION, EXTENT and
+//! BOUNDARY points in their GEON text form (`"lat, lon"`), so a serialised
+//! place reads like the `.geon` document it came from.
+
+use crate::models::{is_empty_json_value, Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Scalar(Value),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn text(s: &str) -> Node {
+        Node::Scalar(Value::String(s.to_string()))
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Node {
+        match v {
+            Value::Array(items) => Node::List(items.iter().map(Node::from_value).collect()),
+            Value::Object(m) => Node::Map(m.iter().map(|(k, v)| (k.clone(), Node::from_value(v))).collect()),
+            other => Node::Scalar(other.clone()),
+        }
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        match self {
+            Node::Scalar(v) => v.clone(),
+            Node::List(items) => Value::Array(items.iter().map(Node::to_value).collect()),
+            Node::Map(entries) => Value::Object(entries.iter().map(|(k, v)| (k.clone(), v.to_value())).collect()),
+        }
+    }
+
+    // Scalars as GEON strings; numbers and booleans typed by the host format
+    // (`AREA: 1500`) are read back as their text
+    fn as_text(&self) -> Option<String> {
+        match self {
+            Node::Scalar(Value::String(s)) => Some(s.clone()),
+            Node::Scalar(Value::Null) => None,
+            Node::Scalar(v) => Some(v.to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn list(items: &[String]) -> Node {
+    Node::List(items.iter().map(|s| Node::text(s)).collect())
+}
+
+fn map(m: &BTreeMap<String, String>) -> Node {
+    let mut entries: Vec<(&String, &String)> = m.iter().collect();
+    entries.sort();
+    Node::Map(entries.into_iter().map(|(k, v)| (k.clone(), Node::text(v))).collect())
+}
+
+const LIST_SECTIONS: &[&str] = &["PURPOSE", "CHARACTER", "ADJACENCIES", "SOURCE"];
+const MAP_SECTIONS: &[&str] = &[
+    "EXPERIENCE", "CONNECTIVITY", "TEMPORAL", "LIFESPAN", "CONFIDENCE", "BUILT_FORM", "ECOLOGY",
+    "INFRASTRUCTURE", "DEMOGRAPHICS", "ECONOMY", "VISUAL", "VERTICAL_PROFILE",
+];
+
+fn list_field<'a>(p: &'a mut GeonPlace, key: &str) -> Option<&'a mut Vec<String>> {
+    Some(match key {
+        "PURPOSE" => &mut p.purpose,
+        "CHARACTER" => &mut p.character,
+        "ADJACENCIES" => &mut p.adjacencies,
+        "SOURCE" => &mut p.source,
+        _ => return None,
+    })
+}
+
+fn map_field<'a>(p: &'a mut GeonPlace, key: &str) -> Option<&'a mut BTreeMap<String, String>> {
+    Some(match key {
+        "EXPERIENCE" => &mut p.experience,
+        "CONNECTIVITY" => &mut p.connectivity,
+        "TEMPORAL" => &mut p.temporal,
+        "LIFESPAN" => &mut p.lifespan,
+        "CONFIDENCE" => &mut p.confidence,
+        "BUILT_FORM" => &mut p.built_form,
+        "ECOLOGY" => &mut p.ecology,
+        "INFRASTRUCTURE" => &mut p.infrastructure,
+        "DEMOGRAPHICS" => &mut p.demographics,
+        "ECONOMY" => &mut p.economy,
+        "VISUAL" => &mut p.visual,
+        "VERTICAL_PROFILE" => &mut p.vertical_profile,
+        _ => return None,
+    })
+}
+
+/// A place as GEON-keyed entries, omitting empty sections.
+pub(crate) fn to_node(place: &GeonPlace) -> Node {
+    let mut out: Vec<(String, Node)> = Vec::new();
+    let mut push = |key: &str, node: Node| out.push((key.to_string(), node));
+
+    push("PLACE", Node::text(&place.place));
+    if !place.type_.is_empty() {
+        push("TYPE", Node::text(&place.type_));
+    }
+    if let Some(id) = &place.id {
+        push("ID", Node::text(id));
+    }
+    if let Some(c) = &place.location {
+        push("LOCATION", Node::text(&c.to_string()));
+    }
+    if !place.boundary.is_empty() {
+        push("BOUNDARY", Node::List(place.boundary.iter().map(|c| Node::text(&c.to_string())).collect()));
+    }
+    if !place.path.is_empty() {
+        push("PATH", Node::List(place.path.iter().map(|c| Node::text(&c.to_string())).collect()));
+    }
+    if let Some(e) = &place.extent {
+        push("EXTENT", Node::text(&e.to_string()));
+    }
+    if let Some(v) = &place.elevation {
+        push("ELEVATION", Node::text(v));
+    }
+    if let Some(v) = &place.area {
+        push("AREA", Node::text(v));
+    }
+
+    let lists = [
+        ("PURPOSE", &place.purpose),
+        ("CHARACTER", &place.character),
+        ("ADJACENCIES", &place.adjacencies),
+    ];
+    for (key, items) in lists {
+        if !items.is_empty() {
+            push(key, list(items));
+        }
+    }
+    let maps = [("EXPERIENCE", &place.experience), ("CONNECTIVITY", &place.connectivity)];
+    for (key, m) in maps {
+        if !m.is_empty() {
+            push(key, map(m));
+        }
+    }
+    if !place.contains.is_empty() {
+        push("CONTAINS", Node::List(place.contains.iter().map(to_node).collect()));
+    }
+    if let Some(v) = &place.part_of {
+        push("PART_OF", Node::text(v));
+    }
+    if !is_empty_json_value(&place.viewsheds) {
+        push("VIEWSHEDS", Node::from_value(&place.viewsheds));
+    }
+    let maps = [("TEMPORAL", &place.temporal), ("LIFESPAN", &place.lifespan)];
+    for (key, m) in maps {
+        if !m.is_empty() {
+            push(key, map(m));
+        }
+    }
+    if !place.source.is_empty() {
+        push("SOURCE", list(&place.source));
+    }
+    if !place.confidence.is_empty() {
+        push("CONFIDENCE", map(&place.confidence));
+    }
+    if let Some(v) = &place.updated {
+        push("UPDATED", Node::text(v));
+    }
+    let maps = [
+        ("BUILT_FORM", &place.built_form),
+        ("ECOLOGY", &place.ecology),
+        ("INFRASTRUCTURE", &place.infrastructure),
+        ("DEMOGRAPHICS", &place.demographics),
+        ("ECONOMY", &place.economy),
+        ("VISUAL", &place.visual),
+        ("VERTICAL_PROFILE", &place.vertical_profile),
+    ];
+    for (key, m) in maps {
+        if !m.is_empty() {
+            push(key, map(m));
+        }
+    }
+    if !place.history.is_empty() {
+        push("HISTORY", Node::List(place.history.iter().map(map).collect()));
+    }
+
+    let mut extra: Vec<(&String, &Value)> = place.extra.iter().collect();
+    extra.sort_by(|a, b| a.0.cmp(b.0));
+    for (k, v) in extra {
+        out.push((k.clone(), Node::from_value(v)));
+    }
+    Node::Map(out)
+}
+
+fn coordinate(node: &Node) -> Result<Coordinate, GeonError> {
+    if let Node::Map(entries) = node {
+        let get = |k: &str| entries.iter().find(|(key, _)| key == k).and_then(|(_, v)| v.as_text());
+        if let (Some(lat), Some(lon)) = (get("lat"), get("lon")) {
+            return Ok(Coordinate::new(lat.parse()?, lon.parse()?));
+        }
+    }
+    let text = node.as_text().unwrap_or_default();
+    let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [lat, lon] => Ok(Coordinate::new(lat.parse()?, lon.parse()?)),
+        _ => Err(GeonError::InvalidStructure(format!("expected 'lat, lon', got '{}'", text))),
+    }
+}
+
+fn text_list(node: &Node) -> Vec<String> {
+    match node {
+        Node::List(items) => items.iter().filter_map(Node::as_text).collect(),
+        other => other.as_text().into_iter().collect(),
+    }
+}
+
+fn text_map(node: &Node) -> BTreeMap<String, String> {
+    match node {
+        Node::Map(entries) => entries.iter().filter_map(|(k, v)| Some((k.clone(), v.as_text()?))).collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Rebuild a place from GEON-keyed entries. Unknown keys go to `extra`.
+pub(crate) fn from_node(node: &Node) -> Result<GeonPlace, GeonError> {
+    let Node::Map(entries) = node else {
+        return Err(GeonError::InvalidStructure("a place must be a mapping".to_string()));
+    };
+    let mut p = GeonPlace::default();
+    for (key, value) in entries {
+        match key.as_str() {
+            "PLACE" => p.place = value.as_text().unwrap_or_default(),
+            "TYPE" => p.type_ = value.as_text().unwrap_or_default(),
+            "ID" => p.id = value.as_text(),
+            "LOCATION" => p.location = Some(coordinate(value)?),
+            "BOUNDARY" => {
+                if let Node::List(items) = value {
+                    p.boundary = items.iter().map(coordinate).collect::<Result<_, _>>()?;
+                }
+            }
+            "PATH" => {
+                if let Node::List(items) = value {
+                    p.path = items.iter().map(coordinate).collect::<Result<_, _>>()?;
+                }
+            }
+            "EXTENT" => {
+                let text = value.as_text().unwrap_or_default();
+                let parts = text.split(',').map(|s| s.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>()?;
+                match parts.as_slice() {
+                    [north, south, east, west] => {
+                        p.extent = Some(Extent { north: *north, south: *south, east: *east, west: *west })
+                    }
+                    _ => return Err(GeonError::InvalidStructure(format!("bad EXTENT '{}'", text))),
+                }
+            }
+            "ELEVATION" => p.elevation = value.as_text(),
+            "AREA" => p.area = value.as_text(),
+            "CONTAINS" => {
+                if let Node::List(items) = value {
+                    p.contains = items.iter().map(from_node).collect::<Result<_, _>>()?;
+                }
+            }
+            "PART_OF" => p.part_of = value.as_text(),
+            "VIEWSHEDS" => p.viewsheds = value.to_value(),
+            "UPDATED" => p.updated = value.as_text(),
+            "HISTORY" => {
+                if let Node::List(items) = value {
+                    p.history = items.iter().map(text_map).collect();
+                }
+            }
+            k if LIST_SECTIONS.contains(&k) => *list_field(&mut p, k).unwrap() = text_list(value),
+            k if MAP_SECTIONS.contains(&k) => *map_field(&mut p, k).unwrap() = text_map(value),
+            _ => {
+                p.extra.insert(key.clone(), value.to_value());
+            }
+        }
+    }
+    Ok(p)
+}
@@ -0,0 +1,404 @@
+//! GeoParquet export.
+//!
+//! Writes an uncompressed, single row group Parquet file with GeoParquet
+//! 1.0 metadata, readable by DuckDB, Spark, GeoPandas and friends. Columns:
+//!
+//! | column       | Parquet type                       |
+//! |--------------|------------------------------------|
+//! | `geometry`   | binary (WKB)                       |
+//! | `name`       | string                             |
+//! | `geon_type`  | string                             |
+//! | `id`         | string                             |
+//! | `purpose`    | list<string>                       |
+//! | `experience` | map<string, string>                |
+//! | `character`  | list<string>                       |
+//! | `area`       | string                             |
+//! | `part_of`    | string                             |
+//!
+//! Only top-level places are written; flatten CONTAINS first if needed.
+
+use crate::geometry::{geometry_type, to_wkb};
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde_json::json;
+use std::path::Path;
+
+// Thrift compact protocol, just enough for Parquet page headers and footer
+
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+#[derive(Default)]
+struct Thrift {
+    buf: Vec<u8>,
+    last: Vec<i16>,
+}
+
+impl Thrift {
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn zigzag(&mut self, v: i64) {
+        self.varint(((v << 1) ^ (v >> 63)) as u64);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last.last_mut().expect("field outside struct");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.zigzag(id as i64);
+        }
+    }
+
+    fn begin(&mut self) {
+        self.last.push(0);
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.last.pop();
+    }
+
+    fn i32(&mut self, id: i16, v: i32) {
+        self.field(id, T_I32);
+        self.zigzag(v as i64);
+    }
+
+    fn i64(&mut self, id: i16, v: i64) {
+        self.field(id, T_I64);
+        self.zigzag(v);
+    }
+
+    fn string(&mut self, id: i16, v: &str) {
+        self.field(id, T_BINARY);
+        self.varint(v.len() as u64);
+        self.buf.extend_from_slice(v.as_bytes());
+    }
+
+    fn struct_field(&mut self, id: i16) {
+        self.field(id, T_STRUCT);
+        self.begin();
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, T_LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | kind);
+        } else {
+            self.buf.push(0xF0 | kind);
+            self.varint(len as u64);
+        }
+    }
+}
+
+// Parquet enum values
+const BYTE_ARRAY: i32 = 6;
+const REQUIRED: i32 = 0;
+const OPTIONAL: i32 = 1;
+const REPEATED: i32 = 2;
+const UTF8: i32 = 0;
+const MAP: i32 = 1;
+const MAP_KEY_VALUE: i32 = 2;
+const LIST: i32 = 3;
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+
+// A leaf column: its levels and non-null values, in row order
+struct Column {
+    path: Vec<&'static str>,
+    max_rep: u8,
+    rep: Vec<u8>,
+    def: Vec<u8>,
+    values: Vec<Vec<u8>>,
+}
+
+impl Column {
+    fn new(path: &[&'static str], max_rep: u8) -> Self {
+        Column { path: path.to_vec(), max_rep, rep: vec![], def: vec![], values: vec![] }
+    }
+
+    fn push_optional(&mut self, value: Option<&[u8]>) {
+        match value {
+            Some(v) => {
+                self.def.push(1);
+                self.values.push(v.to_vec());
+            }
+            None => self.def.push(0),
+        }
+    }
+
+    // One row of a `list<string>` or one side of a `map<string, string>`;
+    // an empty collection is written as null
+    fn push_repeated<'a>(&mut self, items: impl IntoIterator<Item = &'a str>) {
+        let start = self.def.len();
+        for (i, item) in items.into_iter().enumerate() {
+            self.rep.push(u8::from(i > 0));
+            self.def.push(2);
+            self.values.push(item.as_bytes().to_vec());
+        }
+        if self.def.len() == start {
+            self.rep.push(0);
+            self.def.push(0);
+        }
+    }
+}
+
+// RLE/bit-packed hybrid, written as RLE runs only
+fn encode_levels(levels: &[u8]) -> Vec<u8> {
+    let mut runs = Thrift::default();
+    let mut i = 0;
+    while i < levels.len() {
+        let mut j = i;
+        while j < levels.len() && levels[j] == levels[i] {
+            j += 1;
+        }
+        runs.varint(((j - i) as u64) << 1);
+        runs.buf.push(levels[i]);
+        i = j;
+    }
+    let mut out = (runs.buf.len() as u32).to_le_bytes().to_vec();
+    out.extend(runs.buf);
+    out
+}
+
+fn page_data(column: &Column) -> Vec<u8> {
+    let mut data = Vec::new();
+    if column.max_rep > 0 {
+        data.extend(encode_levels(&column.rep));
+    }
+    data.extend(encode_levels(&column.def));
+    for v in &column.values {
+        data.extend((v.len() as u32).to_le_bytes());
+        data.extend_from_slice(v);
+    }
+    data
+}
+
+fn page_header(num_values: usize, size: usize) -> Vec<u8> {
+    let mut t = Thrift::default();
+    t.begin();
+    t.i32(1, 0); // DATA_PAGE
+    t.i32(2, size as i32);
+    t.i32(3, size as i32);
+    t.struct_field(5);
+    t.i32(1, num_values as i32);
+    t.i32(2, PLAIN);
+    t.i32(3, RLE);
+    t.i32(4, RLE);
+    t.end();
+    t.end();
+    t.buf
+}
+
+fn schema_element(t: &mut Thrift, name: &str, repetition: i32, leaf: bool, children: i32, converted: Option<i32>) {
+    t.begin();
+    if leaf {
+        t.i32(1, BYTE_ARRAY);
+    }
+    t.i32(3, repetition);
+    t.string(4, name);
+    if children > 0 {
+        t.i32(5, children);
+    }
+    if let Some(c) = converted {
+        t.i32(6, c);
+    }
+    t.end();
+}
+
+// Flat schema in depth-first order: (name, repetition, leaf, children, converted type)
+const SCHEMA: &[(&str, i32, bool, i32, Option<i32>)] = &[
+    ("geometry", OPTIONAL, true, 0, None),
+    ("name", OPTIONAL, true, 0, Some(UTF8)),
+    ("geon_type", OPTIONAL, true, 0, Some(UTF8)),
+    ("id", OPTIONAL, true, 0, Some(UTF8)),
+    ("purpose", OPTIONAL, false, 1, Some(LIST)),
+    ("list", REPEATED, false, 1, None),
+    ("element", REQUIRED, true, 0, Some(UTF8)),
+    ("experience", OPTIONAL, false, 1, Some(MAP)),
+    ("key_value", REPEATED, false, 2, Some(MAP_KEY_VALUE)),
+    ("key", REQUIRED, true, 0, Some(UTF8)),
+    ("value", REQUIRED, true, 0, Some(UTF8)),
+    ("character", OPTIONAL, false, 1, Some(LIST)),
+    ("list", REPEATED, false, 1, None),
+    ("element", REQUIRED, true, 0, Some(UTF8)),
+    ("area", OPTIONAL, true, 0, Some(UTF8)),
+    ("part_of", OPTIONAL, true, 0, Some(UTF8)),
+];
+
+const TOP_LEVEL_FIELDS: i32 = 9;
+
+fn columns(places: &[GeonPlace]) -> Vec<Column> {
+    let mut geometry = Column::new(&["geometry"], 0);
+    let mut name = Column::new(&["name"], 0);
+    let mut geon_type = Column::new(&["geon_type"], 0);
+    let mut id = Column::new(&["id"], 0);
+    let mut purpose = Column::new(&["purpose", "list", "element"], 1);
+    let mut exp_key = Column::new(&["experience", "key_value", "key"], 1);
+    let mut exp_value = Column::new(&["experience", "key_value", "value"], 1);
+    let mut character = Column::new(&["character", "list", "element"], 1);
+    let mut area = Column::new(&["area"], 0);
+    let mut part_of = Column::new(&["part_of"], 0);
+
+    for p in places {
+        geometry.push_optional(to_wkb(p).as_deref());
+        name.push_optional(Some(p.place.as_bytes()));
+        geon_type.push_optional(Some(p.type_.as_bytes()).filter(|t| !t.is_empty()));
+        id.push_optional(p.id.as_deref().map(str::as_bytes));
+        purpose.push_repeated(p.purpose.iter().map(String::as_str));
+        let mut experience: Vec<(&String, &String)> = p.experience.iter().collect();
+        experience.sort();
+        exp_key.push_repeated(experience.iter().map(|(k, _)| k.as_str()));
+        exp_value.push_repeated(experience.iter().map(|(_, v)| v.as_str()));
+        character.push_repeated(p.character.iter().map(String::as_str));
+        area.push_optional(p.area.as_deref().map(str::as_bytes));
+        part_of.push_optional(p.part_of.as_deref().map(str::as_bytes));
+    }
+    vec![geometry, name, geon_type, id, purpose, exp_key, exp_value, character, area, part_of]
+}
+
+fn geo_metadata(places: &[GeonPlace]) -> String {
+    // The types of the geometries actually in the WKB column
+    let written: Vec<&str> = places.iter().filter_map(geometry_type).collect();
+    let types: Vec<&str> = ["Point", "LineString", "Polygon", "GeometryCollection"]
+        .into_iter()
+        .filter(|t| written.contains(t))
+        .collect();
+    json!({
+        "version": "1.0.0",
+        "primary_column": "geometry",
+        "columns": {"geometry": {"encoding": "WKB", "geometry_types": types}},
+    })
+    .to_string()
+}
+
+/// Encode places as a GeoParquet file in memory.
+pub fn to_bytes(places: &[GeonPlace]) -> Vec<u8> {
+    let mut out = b"PAR1".to_vec();
+    // (column, data page offset, chunk size)
+    let mut chunks = Vec::new();
+    for column in columns(places) {
+        let data = page_data(&column);
+        let header = page_header(column.def.len(), data.len());
+        let offset = out.len();
+        out.extend(&header);
+        out.extend(&data);
+        chunks.push((column, offset, header.len() + data.len()));
+    }
+
+    let mut t = Thrift::default();
+    t.begin();
+    t.i32(1, 1);
+    t.list(2, T_STRUCT, SCHEMA.len() + 1);
+    schema_element(&mut t, "schema", REQUIRED, false, TOP_LEVEL_FIELDS, None);
+    for (name, repetition, leaf, children, converted) in SCHEMA {
+        schema_element(&mut t, name, *repetition, *leaf, *children, *converted);
+    }
+    t.i64(3, places.len() as i64);
+    t.list(4, T_STRUCT, 1);
+    t.begin();
+    t.list(1, T_STRUCT, chunks.len());
+    for (column, offset, size) in &chunks {
+        t.begin();
+        t.i64(2, *offset as i64);
+        t.struct_field(3);
+        t.i32(1, BYTE_ARRAY);
+        t.list(2, T_I32, 2);
+        t.zigzag(PLAIN as i64);
+        t.zigzag(RLE as i64);
+        t.list(3, T_BINARY, column.path.len());
+        for part in &column.path {
+            t.varint(part.len() as u64);
+            t.buf.extend_from_slice(part.as_bytes());
+        }
+        t.i32(4, 0); // UNCOMPRESSED
+        t.i64(5, column.def.len() as i64);
+        t.i64(6, *size as i64);
+        t.i64(7, *size as i64);
+        t.i64(9, *offset as i64);
+        t.end();
+        t.end();
+    }
+    t.i64(2, chunks.iter().map(|c| c.2 as i64).sum());
+    t.i64(3, places.len() as i64);
+    t.end();
+    t.list(5, T_STRUCT, 1);
+    t.begin();
+    t.string(1, "geo");
+    t.string(2, &geo_metadata(places));
+    t.end();
+    t.string(6, concat!("geon-rs ", env!("CARGO_PKG_VERSION")));
+    t.end();
+
+    out.extend(&t.buf);
+    out.extend((t.buf.len() as u32).to_le_bytes());
+    out.extend(b"PAR1");
+    out
+}
+
+/// Write places to a GeoParquet file at `path`.
+pub fn write(places: &[GeonPlace], path: impl AsRef<Path>) -> Result<(), GeonError> {
+    std::fs::write(path, to_bytes(places))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    #[test]
+    fn test_file_layout() {
+        let mut place = GeonPlace::default();
+        place.place = "Old Market Square".to_string();
+        place.type_ = "public_space".to_string();
+        place.location = Some(Coordinate::new(52.95, -1.15));
+        place.purpose = vec!["trade".to_string()];
+        place.experience.insert("sound".to_string(), "busy".to_string());
+
+        let bytes = to_bytes(&[place, GeonPlace::default()]);
+        assert_eq!(&bytes[..4], b"PAR1");
+        assert_eq!(&bytes[bytes.len() - 4..], b"PAR1");
+        let footer_len = u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+        let footer = String::from_utf8_lossy(&bytes[bytes.len() - 8 - footer_len..bytes.len() - 8]);
+        assert!(footer.contains("\"primary_column\":\"geometry\""));
+        assert!(footer.contains("\"geometry_types\":[\"Point\"]"));
+        assert!(footer.contains("key_value"));
+
+        let mut street = GeonPlace::default();
+        street.path = vec![Coordinate::new(52.95, -1.15), Coordinate::new(52.96, -1.15)];
+        let mut square = street.clone();
+        square.boundary = vec![Coordinate::new(52.95, -1.15), Coordinate::new(52.96, -1.15), Coordinate::new(52.96, -1.14)];
+        assert_eq!(
+            geo_metadata(&[street, square]),
+            json!({
+                "version": "1.0.0",
+                "primary_column": "geometry",
+                "columns": {"geometry": {"encoding": "WKB", "geometry_types": ["LineString", "GeometryCollection"]}},
+            })
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_levels_for_lists() {
+        let mut col = Column::new(&["purpose", "list", "element"], 1);
+        col.push_repeated(["a", "b"]);
+        col.push_repeated([]);
+        assert_eq!(col.rep, vec![0, 1, 0]);
+        assert_eq!(col.def, vec![2, 2, 0]);
+        // One run of two 2s, one run of a single 0
+        assert_eq!(encode_levels(&col.def), vec![4, 0, 0, 0, 4, 2, 2, 0]);
+    }
+}
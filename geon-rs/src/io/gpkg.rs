@@ -0,0 +1,322 @@
+//! GeoPackage layer support.
+//!
+//! GeoPackages are SQLite databases; this module provides the parts that
+//! are specific to GeoPackage so that any SQLite binding can read and write
+//! GEON layers: the schema for a feature table, the mapping between places
+//! and rows, and the GeoPackage geometry blob (a `GP` header followed by
+//! WKB). With the `gpkg` feature, [`read`] and [`write`] do the whole job
+//! over a bundled SQLite through `rusqlite`.
+
+use crate::geometry::{from_wkb, to_wkb, WktGeometry};
+use crate::models::{Coordinate, GeonPlace};
+use crate::parser::GeonError;
+
+/// WGS84, as registered in `gpkg_spatial_ref_sys` by every GeoPackage.
+pub const SRS_ID: i32 = 4326;
+
+/// Core attributes of a place as stored in a GeoPackage feature table.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GpkgRow {
+    pub geom: Option<Vec<u8>>,
+    pub name: String,
+    pub geon_type: String,
+    pub geon_id: Option<String>,
+    /// PURPOSE entries as a JSON array of strings.
+    pub purpose: Option<String>,
+    pub area: Option<String>,
+    pub part_of: Option<String>,
+}
+
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// The metadata tables every GeoPackage has, and the spatial reference
+// systems the specification requires in them
+const METADATA_SQL: &str = "\
+PRAGMA application_id = 1196444487;
+PRAGMA user_version = 10300;
+CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+  srs_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL PRIMARY KEY,
+  organization TEXT NOT NULL,
+  organization_coordsys_id INTEGER NOT NULL,
+  definition TEXT NOT NULL,
+  description TEXT
+);
+INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+  ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+  ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system'),
+  ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,\
+AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],\
+UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],AUTHORITY[\"EPSG\",\"4326\"]]',
+   'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+CREATE TABLE IF NOT EXISTS gpkg_contents (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  data_type TEXT NOT NULL,
+  identifier TEXT UNIQUE,
+  description TEXT DEFAULT '',
+  last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+  min_x DOUBLE,
+  min_y DOUBLE,
+  max_x DOUBLE,
+  max_y DOUBLE,
+  srs_id INTEGER REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+  table_name TEXT NOT NULL UNIQUE REFERENCES gpkg_contents (table_name),
+  column_name TEXT NOT NULL,
+  geometry_type_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL REFERENCES gpkg_spatial_ref_sys (srs_id),
+  z TINYINT NOT NULL,
+  m TINYINT NOT NULL,
+  PRIMARY KEY (table_name, column_name)
+);
+";
+
+/// SQL creating a feature table for GEON places and registering it in
+/// `gpkg_contents` and `gpkg_geometry_columns`. The GeoPackage metadata
+/// tables are created first where they don't exist yet, so the script
+/// works on an empty database as well as an existing GeoPackage.
+pub fn create_layer_sql(table: &str) -> String {
+    let t = quote_ident(table);
+    let lit = quote_literal(table);
+    format!(
+        "{METADATA_SQL}\
+         CREATE TABLE {t} (\n  \
+           fid INTEGER PRIMARY KEY AUTOINCREMENT,\n  \
+           geom GEOMETRY,\n  \
+           name TEXT NOT NULL,\n  \
+           geon_type TEXT,\n  \
+           geon_id TEXT,\n  \
+           purpose TEXT,\n  \
+           area TEXT,\n  \
+           part_of TEXT\n\
+         );\n\
+         INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id) \
+           VALUES ({lit}, 'features', {lit}, {SRS_ID});\n\
+         INSERT INTO gpkg_geometry_columns (table_name, column_name, geometry_type_name, srs_id, z, m) \
+           VALUES ({lit}, 'geom', 'GEOMETRY', {SRS_ID}, 0, 0);\n"
+    )
+}
+
+/// Parameterised insert matching the column order of [`GpkgRow`].
+pub fn insert_sql(table: &str) -> String {
+    format!(
+        "INSERT INTO {} (geom, name, geon_type, geon_id, purpose, area, part_of) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        quote_ident(table)
+    )
+}
+
+/// Select matching the column order of [`GpkgRow`].
+pub fn select_sql(table: &str) -> String {
+    format!("SELECT geom, name, geon_type, geon_id, purpose, area, part_of FROM {}", quote_ident(table))
+}
+
+/// Wrap WKB in a GeoPackage binary header (version 0, little-endian, with
+/// an XY envelope).
+pub fn encode_geometry(wkb: &[u8], envelope: [f64; 4]) -> Vec<u8> {
+    let mut buf = vec![b'G', b'P', 0, 0b0000_0011];
+    buf.extend(SRS_ID.to_le_bytes());
+    for v in envelope {
+        buf.extend(v.to_le_bytes());
+    }
+    buf.extend_from_slice(wkb);
+    buf
+}
+
+/// Strip the GeoPackage header and decode the WKB it wraps.
+pub fn decode_geometry(blob: &[u8]) -> Result<WktGeometry, GeonError> {
+    if blob.len() < 8 || &blob[..2] != b"GP" {
+        return Err(GeonError::InvalidStructure("not a GeoPackage geometry blob".to_string()));
+    }
+    let envelope_len = match (blob[3] >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        other => return Err(GeonError::InvalidStructure(format!("bad envelope indicator {}", other))),
+    };
+    let wkb = blob
+        .get(8 + envelope_len..)
+        .ok_or_else(|| GeonError::InvalidStructure("truncated GeoPackage geometry".to_string()))?;
+    from_wkb(wkb)
+}
+
+fn envelope(coords: &[&Coordinate]) -> [f64; 4] {
+    let mut env = [f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY];
+    for c in coords {
+        env = [env[0].min(c.lon), env[1].max(c.lon), env[2].min(c.lat), env[3].max(c.lat)];
+    }
+    env
+}
+
+/// Map a place to its feature-table row.
+pub fn to_row(place: &GeonPlace) -> GpkgRow {
+    let mut coords: Vec<&Coordinate> = Vec::new();
+    if place.boundary.len() >= 3 {
+        coords.extend(&place.boundary);
+    }
+    if place.path.len() >= 2 {
+        coords.extend(&place.path);
+    }
+    if coords.is_empty() {
+        coords.extend(&place.location);
+    }
+    GpkgRow {
+        geom: to_wkb(place).map(|wkb| encode_geometry(&wkb, envelope(&coords))),
+        name: place.place.clone(),
+        geon_type: place.type_.clone(),
+        geon_id: place.id.clone(),
+        purpose: (!place.purpose.is_empty()).then(|| serde_json::to_string(&place.purpose).unwrap()),
+        area: place.area.clone(),
+        part_of: place.part_of.clone(),
+    }
+}
+
+/// Rebuild a place from a feature-table row.
+pub fn from_row(row: &GpkgRow) -> Result<GeonPlace, GeonError> {
+    let mut p = GeonPlace::default();
+    p.place = row.name.clone();
+    p.type_ = row.geon_type.clone();
+    p.id = row.geon_id.clone();
+    p.purpose = match row.purpose.as_deref() {
+        // Text written by other tools is taken as a single entry
+        Some(text) => serde_json::from_str(text).unwrap_or_else(|_| vec![text.to_string()]),
+        None => Vec::new(),
+    };
+    p.area = row.area.clone();
+    p.part_of = row.part_of.clone();
+    if let Some(geometry) = row.geom.as_deref().map(decode_geometry).transpose()? {
+        geometry.apply_to(&mut p);
+    }
+    Ok(p)
+}
+
+#[cfg(feature = "gpkg")]
+fn sqlite_error(e: rusqlite::Error) -> GeonError {
+    GeonError::Store(e.to_string())
+}
+
+/// Write `places` to a feature table `layer` in the GeoPackage at `path`,
+/// creating the file and its metadata tables if need be and replacing any
+/// layer of that name. Each place is one row; nested places are written
+/// only if they are passed in themselves.
+#[cfg(feature = "gpkg")]
+pub fn write(path: impl AsRef<std::path::Path>, layer: &str, places: &[GeonPlace]) -> Result<(), GeonError> {
+    let mut conn = rusqlite::Connection::open(path).map_err(sqlite_error)?;
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    let lit = quote_literal(layer);
+    tx.execute_batch(&format!(
+        "{METADATA_SQL}\
+         DELETE FROM gpkg_geometry_columns WHERE table_name = {lit};\n\
+         DELETE FROM gpkg_contents WHERE table_name = {lit};\n\
+         DROP TABLE IF EXISTS {};\n",
+        quote_ident(layer)
+    ))
+    .map_err(sqlite_error)?;
+    tx.execute_batch(&create_layer_sql(layer)).map_err(sqlite_error)?;
+    {
+        let mut insert = tx.prepare(&insert_sql(layer)).map_err(sqlite_error)?;
+        for place in places {
+            let row = to_row(place);
+            insert
+                .execute(rusqlite::params![row.geom, row.name, row.geon_type, row.geon_id, row.purpose, row.area, row.part_of])
+                .map_err(sqlite_error)?;
+        }
+    }
+    tx.commit().map_err(sqlite_error)
+}
+
+/// Read every row of the feature table `layer` in the GeoPackage at `path`.
+#[cfg(feature = "gpkg")]
+pub fn read(path: impl AsRef<std::path::Path>, layer: &str) -> Result<Vec<GeonPlace>, GeonError> {
+    let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY;
+    let conn = rusqlite::Connection::open_with_flags(path, flags).map_err(sqlite_error)?;
+    let mut select = conn.prepare(&select_sql(layer)).map_err(sqlite_error)?;
+    let rows = select
+        .query_map([], |r| {
+            Ok(GpkgRow {
+                geom: r.get(0)?,
+                name: r.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                geon_type: r.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                geon_id: r.get(3)?,
+                purpose: r.get(4)?,
+                area: r.get(5)?,
+                part_of: r.get(6)?,
+            })
+        })
+        .map_err(sqlite_error)?;
+    rows.map(|row| from_row(&row.map_err(sqlite_error)?)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_round_trip() {
+        let mut place = GeonPlace::default();
+        place.place = "Market".to_string();
+        place.type_ = "public_space".to_string();
+        place.purpose = vec!["trade".to_string(), "events; markets".to_string()];
+        place.boundary = vec![Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.0), Coordinate::new(1.0, 1.0)];
+
+        let row = to_row(&place);
+        let blob = row.geom.as_ref().unwrap();
+        assert_eq!(&blob[..2], b"GP");
+        let back = from_row(&row).unwrap();
+        assert_eq!(back.purpose, place.purpose);
+        // Rings come back closed
+        assert_eq!(back.boundary.len(), 4);
+        assert_eq!(back.boundary[..3], place.boundary[..]);
+    }
+
+    #[test]
+    fn test_schema_quotes_names() {
+        assert!(create_layer_sql("o'clock").contains("VALUES ('o''clock', 'features'"));
+        assert!(insert_sql("places").starts_with("INSERT INTO \"places\""));
+    }
+
+    #[cfg(feature = "gpkg")]
+    #[test]
+    fn test_gpkg_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("geon-gpkg-{}.gpkg", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut market = GeonPlace::default();
+        market.place = "Market".to_string();
+        market.type_ = "public_space".to_string();
+        market.id = Some("osm:way/1".to_string());
+        market.purpose = vec!["trade; events".to_string()];
+        market.boundary = vec![Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.0), Coordinate::new(1.0, 1.0)];
+        let mut street = GeonPlace::default();
+        street.place = "High Street".to_string();
+        street.type_ = "street".to_string();
+        street.path = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.5, 0.5)];
+        street.part_of = Some("Market".to_string());
+
+        write(&path, "places", &[market.clone(), street.clone()]).unwrap();
+        // Writing again replaces the layer rather than failing or appending
+        write(&path, "places", &[market.clone(), street.clone()]).unwrap();
+        let back = read(&path, "places").unwrap();
+        assert_eq!(back.len(), 2);
+        assert_eq!(back[0].purpose, market.purpose);
+        assert_eq!(back[0].boundary[..3], market.boundary[..]);
+        assert_eq!(back[1].path, street.path);
+        assert_eq!(back[1].part_of, street.part_of);
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let app_id: i64 = conn.query_row("PRAGMA application_id", [], |r| r.get(0)).unwrap();
+        assert_eq!(app_id, 0x4750_4B47);
+        let srs: i64 = conn.query_row("SELECT srs_id FROM gpkg_geometry_columns WHERE table_name = 'places'", [], |r| r.get(0)).unwrap();
+        assert_eq!(srs, i64::from(SRS_ID));
+        drop(conn);
+        assert!(read(&path, "missing").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
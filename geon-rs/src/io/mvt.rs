@@ -0,0 +1,373 @@
+//! Mapbox Vector Tile export.
+//!
+//! Encodes places into MVT 2.1 tiles with two layers: `places` (a point at
+//! each LOCATION) and `boundaries` (each BOUNDARY as a polygon). Features
+//! carry a small attribute set: `name`, `type`, `id` and `purpose` (joined
+//! with `"; "`). Nested CONTAINS places are included alongside their
+//! parents.
+//!
+//! Polygons are quantised to the tile grid, which drops vertices closer
+//! together than one tile unit, but are not clipped: features overlapping a
+//! tile edge are written whole and left to the renderer to clip.
+
+use crate::models::{Coordinate, GeonPlace};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+/// Tile grid resolution.
+pub const EXTENT: u32 = 4096;
+
+/// `(z, x, y)` tile address in the XYZ scheme.
+pub type TileId = (u8, u32, u32);
+
+// Protobuf wire format
+
+fn varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn key(buf: &mut Vec<u8>, field: u32, wire: u8) {
+    varint(buf, ((field as u64) << 3) | wire as u64);
+}
+
+fn bytes_field(buf: &mut Vec<u8>, field: u32, data: &[u8]) {
+    key(buf, field, 2);
+    varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn uint_field(buf: &mut Vec<u8>, field: u32, v: u64) {
+    key(buf, field, 0);
+    varint(buf, v);
+}
+
+fn packed_field(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = Vec::new();
+    for v in values {
+        varint(&mut packed, *v as u64);
+    }
+    bytes_field(buf, field, &packed);
+}
+
+fn zigzag(v: i32) -> u32 {
+    ((v << 1) ^ (v >> 31)) as u32
+}
+
+// Web Mercator position in tile units at zoom `z` (whole world = 2^z tiles)
+fn project(c: &Coordinate, z: u8) -> (f64, f64) {
+    let n = f64::from(1u32 << z);
+    let lat = c.lat.clamp(-85.051_128_78, 85.051_128_78).to_radians();
+    let x = (c.lon + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+    (x, y)
+}
+
+fn tile_point(c: &Coordinate, tile: TileId) -> (i32, i32) {
+    let (x, y) = project(c, tile.0);
+    (((x - f64::from(tile.1)) * f64::from(EXTENT)).round() as i32, ((y - f64::from(tile.2)) * f64::from(EXTENT)).round() as i32)
+}
+
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+fn point_geometry(p: (i32, i32)) -> Vec<u32> {
+    vec![command(1, 1), zigzag(p.0), zigzag(p.1)]
+}
+
+// Exterior ring in tile coordinates: quantised, deduplicated, unclosed and
+// wound so its surveyor's-formula area is positive, as MVT requires
+fn polygon_geometry(ring: &[Coordinate], tile: TileId) -> Option<Vec<u32>> {
+    let mut pts: Vec<(i32, i32)> = Vec::with_capacity(ring.len());
+    for c in ring {
+        let p = tile_point(c, tile);
+        if pts.last() != Some(&p) {
+            pts.push(p);
+        }
+    }
+    if pts.len() > 1 && pts.first() == pts.last() {
+        pts.pop();
+    }
+    if pts.len() < 3 {
+        return None;
+    }
+    let area: i64 = (0..pts.len())
+        .map(|i| {
+            let (a, b) = (pts[i], pts[(i + 1) % pts.len()]);
+            i64::from(a.0) * i64::from(b.1) - i64::from(b.0) * i64::from(a.1)
+        })
+        .sum();
+    if area == 0 {
+        return None;
+    }
+    if area < 0 {
+        pts.reverse();
+    }
+
+    let mut geom = vec![command(1, 1), zigzag(pts[0].0), zigzag(pts[0].1), command(2, pts.len() as u32 - 1)];
+    for w in pts.windows(2) {
+        geom.push(zigzag(w[1].0 - w[0].0));
+        geom.push(zigzag(w[1].1 - w[0].1));
+    }
+    geom.push(command(7, 1));
+    Some(geom)
+}
+
+fn attributes(place: &GeonPlace) -> Vec<(&'static str, String)> {
+    let mut attrs = vec![("name", place.place.clone())];
+    if !place.type_.is_empty() {
+        attrs.push(("type", place.type_.clone()));
+    }
+    if let Some(id) = &place.id {
+        attrs.push(("id", id.clone()));
+    }
+    if !place.purpose.is_empty() {
+        attrs.push(("purpose", place.purpose.join("; ")));
+    }
+    attrs
+}
+
+struct LayerBuilder {
+    name: &'static str,
+    keys: Vec<&'static str>,
+    values: Vec<String>,
+    value_index: HashMap<String, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl LayerBuilder {
+    fn new(name: &'static str) -> Self {
+        LayerBuilder { name, keys: vec![], values: vec![], value_index: HashMap::new(), features: vec![] }
+    }
+
+    fn add(&mut self, geom_type: u64, geometry: &[u32], attrs: Vec<(&'static str, String)>) {
+        let mut tags = Vec::new();
+        for (k, v) in attrs {
+            let ki = match self.keys.iter().position(|x| *x == k) {
+                Some(i) => i,
+                None => {
+                    self.keys.push(k);
+                    self.keys.len() - 1
+                }
+            };
+            let next = self.values.len() as u32;
+            let vi = *self.value_index.entry(v.clone()).or_insert_with(|| {
+                self.values.push(v);
+                next
+            });
+            tags.extend([ki as u32, vi]);
+        }
+        let mut feature = Vec::new();
+        uint_field(&mut feature, 1, self.features.len() as u64 + 1);
+        packed_field(&mut feature, 2, &tags);
+        uint_field(&mut feature, 3, geom_type);
+        packed_field(&mut feature, 4, geometry);
+        self.features.push(feature);
+    }
+
+    fn encode(&self, tile: &mut Vec<u8>) {
+        if self.features.is_empty() {
+            return;
+        }
+        let mut layer = Vec::new();
+        uint_field(&mut layer, 15, 2);
+        bytes_field(&mut layer, 1, self.name.as_bytes());
+        for f in &self.features {
+            bytes_field(&mut layer, 2, f);
+        }
+        for k in &self.keys {
+            bytes_field(&mut layer, 3, k.as_bytes());
+        }
+        for v in &self.values {
+            let mut value = Vec::new();
+            bytes_field(&mut value, 1, v.as_bytes());
+            bytes_field(&mut layer, 4, &value);
+        }
+        uint_field(&mut layer, 5, EXTENT as u64);
+        bytes_field(tile, 3, &layer);
+    }
+}
+
+fn collect<'a>(places: &'a [GeonPlace], out: &mut Vec<&'a GeonPlace>) {
+    for p in places {
+        out.push(p);
+        collect(&p.contains, out);
+    }
+}
+
+// Range of tiles covered by a set of coordinates at zoom `z`
+/// The tile containing `c` at zoom `z`.
+pub fn tile_for(c: &Coordinate, z: u8) -> TileId {
+    let max = (1u32 << z) - 1;
+    let (x, y) = project(c, z);
+    (z, (x.floor().max(0.0) as u32).min(max), (y.floor().max(0.0) as u32).min(max))
+}
+
+fn tile_range(coords: &[&Coordinate], z: u8) -> Option<(u32, u32, u32, u32)> {
+    let mut range: Option<(u32, u32, u32, u32)> = None;
+    for c in coords {
+        let (_, tx, ty) = tile_for(c, z);
+        range = Some(match range {
+            None => (tx, ty, tx, ty),
+            Some((x0, y0, x1, y1)) => (x0.min(tx), y0.min(ty), x1.max(tx), y1.max(ty)),
+        });
+    }
+    range
+}
+
+fn place_coords(place: &GeonPlace) -> Vec<&Coordinate> {
+    place.location.iter().chain(&place.boundary).collect()
+}
+
+/// Encode a single tile. Places outside it are skipped.
+pub fn encode_tile(places: &[GeonPlace], tile: TileId) -> Vec<u8> {
+    let mut all = Vec::new();
+    collect(places, &mut all);
+    let visible: Vec<&GeonPlace> = all
+        .into_iter()
+        .filter(|p| {
+            tile_range(&place_coords(p), tile.0)
+                .is_some_and(|(x0, y0, x1, y1)| (x0..=x1).contains(&tile.1) && (y0..=y1).contains(&tile.2))
+        })
+        .collect();
+    encode_places(&visible, tile)
+}
+
+fn encode_places(places: &[&GeonPlace], tile: TileId) -> Vec<u8> {
+    let mut points = LayerBuilder::new("places");
+    let mut boundaries = LayerBuilder::new("boundaries");
+    for p in places {
+        if let Some(loc) = &p.location {
+            let (x, y) = tile_point(loc, tile);
+            let inside = 0..EXTENT as i32;
+            if inside.contains(&x) && inside.contains(&y) {
+                points.add(1, &point_geometry((x, y)), attributes(p));
+            }
+        }
+        if p.boundary.len() >= 3 {
+            if let Some(geom) = polygon_geometry(&p.boundary, tile) {
+                boundaries.add(3, &geom, attributes(p));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    points.encode(&mut out);
+    boundaries.encode(&mut out);
+    out
+}
+
+/// Encode every non-empty tile at each zoom level in `zooms`.
+pub fn encode_tiles(places: &[GeonPlace], zooms: std::ops::RangeInclusive<u8>) -> BTreeMap<TileId, Vec<u8>> {
+    let mut all = Vec::new();
+    collect(places, &mut all);
+
+    let mut tiles = BTreeMap::new();
+    for z in zooms {
+        let mut buckets: BTreeMap<(u32, u32), Vec<&GeonPlace>> = BTreeMap::new();
+        for p in &all {
+            if let Some((x0, y0, x1, y1)) = tile_range(&place_coords(p), z) {
+                for x in x0..=x1 {
+                    for y in y0..=y1 {
+                        buckets.entry((x, y)).or_default().push(p);
+                    }
+                }
+            }
+        }
+        for ((x, y), members) in buckets {
+            let data = encode_places(&members, (z, x, y));
+            if !data.is_empty() {
+                tiles.insert((z, x, y), data);
+            }
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_varint(buf: &mut &[u8]) -> u64 {
+        let mut v = 0;
+        for shift in (0..).step_by(7) {
+            let b = buf[0];
+            *buf = &buf[1..];
+            v |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                break;
+            }
+        }
+        v
+    }
+
+    fn unpack(mut buf: &[u8]) -> Vec<u64> {
+        let mut out = Vec::new();
+        while !buf.is_empty() {
+            out.push(read_varint(&mut buf));
+        }
+        out
+    }
+
+    // Minimal protobuf reader: (field, varint value, length-delimited bytes)
+    fn fields(mut buf: &[u8]) -> Vec<(u64, u64, Vec<u8>)> {
+        let mut out = Vec::new();
+        while !buf.is_empty() {
+            let k = read_varint(&mut buf);
+            if k & 7 == 0 {
+                out.push((k >> 3, read_varint(&mut buf), vec![]));
+            } else {
+                let len = read_varint(&mut buf) as usize;
+                out.push((k >> 3, 0, buf[..len].to_vec()));
+                buf = &buf[len..];
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_tile_layers() {
+        let mut square = GeonPlace::default();
+        square.place = "Old Market Square".to_string();
+        square.type_ = "public_space".to_string();
+        square.location = Some(Coordinate::new(52.9530, -1.1497));
+        square.boundary = vec![
+            Coordinate::new(52.9525, -1.1510),
+            Coordinate::new(52.9535, -1.1510),
+            Coordinate::new(52.9535, -1.1485),
+            Coordinate::new(52.9525, -1.1485),
+        ];
+
+        let tiles = encode_tiles(&[square], 14..=14);
+        assert_eq!(tiles.len(), 1);
+        let (&(z, x, y), data) = tiles.iter().next().unwrap();
+        assert_eq!((z, x, y), (14, 8139, 5340));
+
+        let layers: Vec<Vec<(u64, u64, Vec<u8>)>> = fields(data).into_iter().map(|(_, _, b)| fields(&b)).collect();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].iter().find(|f| f.0 == 1).unwrap().2, b"places");
+        assert_eq!(layers[1].iter().find(|f| f.0 == 1).unwrap().2, b"boundaries");
+
+        // Polygon: MoveTo(1), 2 params, LineTo(3), 6 params, ClosePath
+        let feature = fields(&layers[1].iter().find(|f| f.0 == 2).unwrap().2);
+        let geometry = unpack(&feature.iter().find(|f| f.0 == 4).unwrap().2);
+        assert_eq!(geometry[0], 9);
+        assert_eq!(geometry[3], 26);
+        assert_eq!(*geometry.last().unwrap(), 15);
+    }
+
+    #[test]
+    fn test_winding_is_positive() {
+        let ring = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0), Coordinate::new(1.0, 1.0)];
+        let a = polygon_geometry(&ring, (0, 0, 0)).unwrap();
+        let mut reversed = ring.to_vec();
+        reversed.reverse();
+        let b = polygon_geometry(&reversed, (0, 0, 0)).unwrap();
+        // Same ring either way round, starting from a different vertex
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a[3], command(2, 2));
+    }
+}
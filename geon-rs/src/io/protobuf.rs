@@ -0,0 +1,368 @@
+//! Protocol Buffers encoding of `proto/geon/v1/geon.proto`.
+//!
+//! [`to_protobuf`] and [`from_protobuf`] read and write `geon.v1.GeonPlace`
+//! messages on the wire, so services generated from the schema in any
+//! language (prost, protoc, grpc-*) exchange places with this crate
+//! directly. Unknown fields are skipped on decode.
+
+use crate::models::{is_empty_json_value, Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use std::collections::BTreeMap;
+
+fn invalid(msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("protobuf: {}", msg))
+}
+
+// Encoding
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+const FIXED32: u8 = 5;
+
+fn varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn key(buf: &mut Vec<u8>, field: u32, wire: u8) {
+    varint(buf, ((field as u64) << 3) | wire as u64);
+}
+
+fn bytes(buf: &mut Vec<u8>, field: u32, data: &[u8]) {
+    key(buf, field, LEN);
+    varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn double(buf: &mut Vec<u8>, field: u32, v: f64) {
+    // proto3 omits default values
+    if v != 0.0 || v.is_sign_negative() {
+        key(buf, field, FIXED64);
+        buf.extend(v.to_le_bytes());
+    }
+}
+
+fn string_map(buf: &mut Vec<u8>, field: u32, m: &BTreeMap<String, String>) {
+    let mut entries: Vec<_> = m.iter().collect();
+    entries.sort();
+    for (k, v) in entries {
+        let mut entry = Vec::new();
+        bytes(&mut entry, 1, k.as_bytes());
+        bytes(&mut entry, 2, v.as_bytes());
+        bytes(buf, field, &entry);
+    }
+}
+
+fn coordinate(c: &Coordinate) -> Vec<u8> {
+    let mut buf = Vec::new();
+    double(&mut buf, 1, c.lat);
+    double(&mut buf, 2, c.lon);
+    buf
+}
+
+fn encode_place(p: &GeonPlace) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if !p.place.is_empty() {
+        bytes(&mut buf, 1, p.place.as_bytes());
+    }
+    if !p.type_.is_empty() {
+        bytes(&mut buf, 2, p.type_.as_bytes());
+    }
+    if let Some(id) = &p.id {
+        bytes(&mut buf, 3, id.as_bytes());
+    }
+    if let Some(c) = &p.location {
+        bytes(&mut buf, 4, &coordinate(c));
+    }
+    for c in &p.boundary {
+        bytes(&mut buf, 5, &coordinate(c));
+    }
+    for c in &p.path {
+        bytes(&mut buf, 9, &coordinate(c));
+    }
+    if let Some(e) = &p.extent {
+        let mut ext = Vec::new();
+        for (field, v) in [(1, e.north), (2, e.south), (3, e.east), (4, e.west)] {
+            double(&mut ext, field, v);
+        }
+        bytes(&mut buf, 6, &ext);
+    }
+    let strings = [(7, &p.elevation), (8, &p.area), (23, &p.part_of), (42, &p.updated)];
+    for (field, value) in strings {
+        if let Some(v) = value {
+            bytes(&mut buf, field, v.as_bytes());
+        }
+    }
+    let lists = [(10, &p.purpose), (12, &p.character), (20, &p.adjacencies), (40, &p.source)];
+    for (field, list) in lists {
+        for s in list {
+            bytes(&mut buf, field, s.as_bytes());
+        }
+    }
+    let maps = [
+        (11, &p.experience),
+        (21, &p.connectivity),
+        (30, &p.temporal),
+        (31, &p.lifespan),
+        (41, &p.confidence),
+        (50, &p.built_form),
+        (51, &p.ecology),
+        (52, &p.infrastructure),
+        (53, &p.demographics),
+        (54, &p.economy),
+        (60, &p.visual),
+        (62, &p.vertical_profile),
+    ];
+    for (field, m) in maps {
+        string_map(&mut buf, field, m);
+    }
+    for child in &p.contains {
+        bytes(&mut buf, 22, &encode_place(child));
+    }
+    if !is_empty_json_value(&p.viewsheds) {
+        bytes(&mut buf, 24, p.viewsheds.to_string().as_bytes());
+    }
+    for entry in &p.history {
+        let mut msg = Vec::new();
+        string_map(&mut msg, 1, entry);
+        bytes(&mut buf, 61, &msg);
+    }
+    let extra: BTreeMap<String, String> = p.extra.iter().map(|(k, v)| (k.clone(), v.to_string())).collect();
+    string_map(&mut buf, 100, &extra);
+    buf
+}
+
+/// Encode a place as a `geon.v1.GeonPlace` message.
+pub fn to_protobuf(place: &GeonPlace) -> Vec<u8> {
+    encode_place(place)
+}
+
+// Decoding
+
+// Skipped fields only need their wire type; none of the schema's fields are varints
+enum Field<'a> {
+    Varint,
+    Fixed64([u8; 8]),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn varint(&mut self) -> Result<u64, GeonError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = *self.buf.get(self.pos).ok_or_else(|| invalid("truncated varint"))?;
+            self.pos += 1;
+            v |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                return Ok(v);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], GeonError> {
+        let chunk = self.buf.get(self.pos..self.pos + n).ok_or_else(|| invalid("truncated field"))?;
+        self.pos += n;
+        Ok(chunk)
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u32, Field<'a>)>, GeonError> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let k = self.varint()?;
+        let field = u32::try_from(k >> 3).map_err(|_| invalid("bad field number"))?;
+        let value = match (k & 7) as u8 {
+            VARINT => {
+                self.varint()?;
+                Field::Varint
+            }
+            FIXED64 => Field::Fixed64(self.take(8)?.try_into().unwrap()),
+            LEN => {
+                let len = usize::try_from(self.varint()?).map_err(|_| invalid("length too large"))?;
+                Field::Bytes(self.take(len)?)
+            }
+            FIXED32 => {
+                self.take(4)?;
+                Field::Fixed32
+            }
+            other => return Err(invalid(&format!("unsupported wire type {}", other))),
+        };
+        Ok(Some((field, value)))
+    }
+}
+
+fn fields(buf: &[u8]) -> impl Iterator<Item = Result<(u32, Field<'_>), GeonError>> {
+    let mut reader = Reader { buf, pos: 0 };
+    std::iter::from_fn(move || reader.next_field().transpose())
+}
+
+fn text(field: &Field) -> Result<String, GeonError> {
+    match field {
+        Field::Bytes(b) => String::from_utf8(b.to_vec()).map_err(|_| invalid("invalid UTF-8")),
+        _ => Err(invalid("expected a string")),
+    }
+}
+
+fn message<'a>(field: &Field<'a>) -> Result<&'a [u8], GeonError> {
+    match field {
+        Field::Bytes(b) => Ok(b),
+        _ => Err(invalid("expected a message")),
+    }
+}
+
+fn doubles<const N: usize>(buf: &[u8]) -> Result<[f64; N], GeonError> {
+    let mut out = [0.0; N];
+    for f in fields(buf) {
+        if let (n, Field::Fixed64(b)) = f? {
+            if (1..=N as u32).contains(&n) {
+                out[n as usize - 1] = f64::from_le_bytes(b);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn map_entry(buf: &[u8], map: &mut BTreeMap<String, String>) -> Result<(), GeonError> {
+    let (mut k, mut v) = (String::new(), String::new());
+    for f in fields(buf) {
+        match f? {
+            (1, field) => k = text(&field)?,
+            (2, field) => v = text(&field)?,
+            _ => {}
+        }
+    }
+    map.insert(k, v);
+    Ok(())
+}
+
+fn decode_place(buf: &[u8]) -> Result<GeonPlace, GeonError> {
+    let mut p = GeonPlace::default();
+    for f in fields(buf) {
+        let (number, field) = f?;
+        match number {
+            1 => p.place = text(&field)?,
+            2 => p.type_ = text(&field)?,
+            3 => p.id = Some(text(&field)?),
+            4 => {
+                let [lat, lon] = doubles(message(&field)?)?;
+                p.location = Some(Coordinate::new(lat, lon));
+            }
+            5 => {
+                let [lat, lon] = doubles(message(&field)?)?;
+                p.boundary.push(Coordinate::new(lat, lon));
+            }
+            6 => {
+                let [north, south, east, west] = doubles(message(&field)?)?;
+                p.extent = Some(Extent { north, south, east, west });
+            }
+            7 => p.elevation = Some(text(&field)?),
+            8 => p.area = Some(text(&field)?),
+            9 => {
+                let [lat, lon] = doubles(message(&field)?)?;
+                p.path.push(Coordinate::new(lat, lon));
+            }
+            10 => p.purpose.push(text(&field)?),
+            12 => p.character.push(text(&field)?),
+            20 => p.adjacencies.push(text(&field)?),
+            40 => p.source.push(text(&field)?),
+            22 => p.contains.push(decode_place(message(&field)?)?),
+            23 => p.part_of = Some(text(&field)?),
+            24 => p.viewsheds = serde_json::from_str(&text(&field)?).map_err(|_| invalid("bad viewsheds_json"))?,
+            42 => p.updated = Some(text(&field)?),
+            61 => {
+                let mut entry = BTreeMap::new();
+                for f in fields(message(&field)?) {
+                    if let (1, entry_field) = f? {
+                        map_entry(message(&entry_field)?, &mut entry)?;
+                    }
+                }
+                p.history.push(entry);
+            }
+            100 => {
+                let mut raw = BTreeMap::new();
+                map_entry(message(&field)?, &mut raw)?;
+                for (k, v) in raw {
+                    let value = serde_json::from_str(&v).map_err(|_| invalid("bad extra_json value"))?;
+                    p.extra.insert(k, value);
+                }
+            }
+            n => {
+                let target = match n {
+                    11 => &mut p.experience,
+                    21 => &mut p.connectivity,
+                    30 => &mut p.temporal,
+                    31 => &mut p.lifespan,
+                    41 => &mut p.confidence,
+                    50 => &mut p.built_form,
+                    51 => &mut p.ecology,
+                    52 => &mut p.infrastructure,
+                    53 => &mut p.demographics,
+                    54 => &mut p.economy,
+                    60 => &mut p.visual,
+                    62 => &mut p.vertical_profile,
+                    _ => continue,
+                };
+                map_entry(message(&field)?, target)?;
+            }
+        }
+    }
+    Ok(p)
+}
+
+/// Decode a `geon.v1.GeonPlace` message.
+pub fn from_protobuf(bytes: &[u8]) -> Result<GeonPlace, GeonError> {
+    decode_place(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protobuf_round_trip() {
+        let mut child = GeonPlace::default();
+        child.place = "Council House".to_string();
+        child.boundary = vec![Coordinate::new(0.0, 2.0), Coordinate::new(1.5, -0.0), Coordinate::new(1.5, 2.5)];
+
+        let mut place = GeonPlace::default();
+        place.place = "Old Market Square".to_string();
+        place.type_ = "public_space".to_string();
+        place.location = Some(Coordinate::new(52.953, -1.1497));
+        place.extent = Some(Extent { north: 53.0, south: 52.9, east: -1.1, west: -1.2 });
+        place.purpose = vec!["trade".to_string(), "events".to_string()];
+        place.experience.insert("noise".to_string(), "busy".to_string());
+        place.contains = vec![child];
+        place.viewsheds = serde_json::json!({"Castle": "north"});
+        place.history = vec![[("year".to_string(), "1929".to_string())].into()];
+        place.extra.insert("levels".to_string(), serde_json::json!([1, 2]));
+
+        assert_eq!(from_protobuf(&to_protobuf(&place)).unwrap(), place);
+    }
+
+    #[test]
+    fn test_protobuf_wire_format() {
+        let mut place = GeonPlace::default();
+        place.place = "A".to_string();
+        place.location = Some(Coordinate::new(1.0, 0.0));
+        let bytes = to_protobuf(&place);
+        // place = 1 (len "A"), location = 4 (message with only lat, since lon is the default)
+        assert_eq!(&bytes[..3], &[0x0a, 0x01, b'A']);
+        assert_eq!(&bytes[3..6], &[0x22, 0x09, 0x09]);
+        // Unknown fields (varint field 15) are skipped
+        let mut extended = bytes.clone();
+        extended.extend([0x78, 0x01]);
+        assert_eq!(from_protobuf(&extended).unwrap(), place);
+        assert!(from_protobuf(&[0x0a, 0x05, b'A']).is_err());
+    }
+}
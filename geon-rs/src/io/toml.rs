@@ -0,0 +1,523 @@
+//! TOML representation.
+//!
+//! [`to_toml`] writes a place with the same GEON key names as the YAML
+//! bridge. Scalars and string lists become top-level keys, map sections
+//! become tables, and the list-of-map sections become arrays of tables:
+//!
+//! ```toml
+//! PLACE = "Old Market Square"
+//! TYPE = "public_space"
+//! LOCATION = "52.953, -1.1497"
+//! PURPOSE = ["trade", "gathering"]
+//!
+//! [EXPERIENCE]
+//! noise = "busy"
+//!
+//! [[CONTAINS]]
+//! PLACE = "Council House"
+//! TYPE = "building"
+//!
+//! [CONTAINS.BUILT_FORM]
+//! roof_type = "dome"
+//!
+//! [[HISTORY]]
+//! event = "rebuilt"
+//! year = "1929"
+//! ```
+//!
+//! A child's own tables and nested CONTAINS follow it as
+//! `[CONTAINS.<SECTION>]` and `[[CONTAINS.CONTAINS]]`, which TOML attaches to
+//! the most recent `[[CONTAINS]]` entry. TOML has no null and no integers
+//! beyond 64-bit signed, so a place holding either in `extra` or VIEWSHEDS
+//! is an error rather than silently changed.
+//!
+//! [`from_toml`] reads TOML 1.0 apart from multi-line strings; dates and
+//! times are kept as their text.
+
+use super::document::{from_node, to_node, Node};
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde_json::Value;
+
+// Writing
+
+fn key(k: &str) -> String {
+    if !k.is_empty() && k.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-') {
+        k.to_string()
+    } else {
+        serde_json::to_string(k).unwrap()
+    }
+}
+
+fn inline(node: &Node) -> String {
+    match node {
+        Node::Scalar(v) => v.to_string(),
+        Node::List(items) => {
+            let items: Vec<String> = items.iter().map(inline).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Node::Map(entries) => {
+            let entries: Vec<String> =
+                entries.iter().map(|(k, v)| format!("{} = {}", key(k), inline(v))).collect();
+            if entries.is_empty() { "{}".to_string() } else { format!("{{ {} }}", entries.join(", ")) }
+        }
+    }
+}
+
+// Reject values TOML cannot hold, naming the key path they sit under
+fn check(node: &Node, path: &str) -> Result<(), GeonError> {
+    let unrepresentable = |what: &str| GeonError::InvalidStructure(format!("TOML has no {} (at {})", what, path));
+    match node {
+        Node::Scalar(Value::Null) => Err(unrepresentable("null")),
+        Node::Scalar(Value::Number(n)) if n.is_u64() && n.as_i64().is_none() => {
+            Err(unrepresentable("integer above 64-bit signed"))
+        }
+        Node::Scalar(_) => Ok(()),
+        Node::List(items) => items.iter().enumerate().try_for_each(|(i, n)| check(n, &format!("{}[{}]", path, i))),
+        Node::Map(entries) => entries.iter().try_for_each(|(k, v)| {
+            check(v, &if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) })
+        }),
+    }
+}
+
+fn is_table_array(node: &Node) -> bool {
+    matches!(node, Node::List(items) if !items.is_empty() && items.iter().all(|i| matches!(i, Node::Map(_))))
+}
+
+fn write_table(buf: &mut String, path: &[String], entries: &[(String, Node)]) {
+    // Plain keys first: after a header every key belongs to that table
+    for (k, v) in entries {
+        let plain = match v {
+            Node::Map(m) => m.is_empty(),
+            list => !is_table_array(list),
+        };
+        if plain {
+            buf.push_str(&format!("{} = {}\n", key(k), inline(v)));
+        }
+    }
+    for (k, v) in entries {
+        let mut child: Vec<String> = path.to_vec();
+        child.push(key(k));
+        match v {
+            Node::Map(m) if !m.is_empty() => {
+                buf.push_str(&format!("\n[{}]\n", child.join(".")));
+                write_table(buf, &child, m);
+            }
+            Node::List(items) if is_table_array(v) => {
+                for item in items {
+                    if let Node::Map(m) = item {
+                        buf.push_str(&format!("\n[[{}]]\n", child.join(".")));
+                        write_table(buf, &child, m);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Serialise a place as TOML using GEON key names. Fails on a null or an
+/// out-of-range integer, which TOML cannot represent.
+pub fn to_toml(place: &GeonPlace) -> Result<String, GeonError> {
+    let node = to_node(place);
+    check(&node, "")?;
+    let mut buf = String::new();
+    if let Node::Map(entries) = node {
+        write_table(&mut buf, &[], &entries);
+    }
+    Ok(buf)
+}
+
+// Reading
+
+type Table = Vec<(String, Node)>;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> GeonError {
+        let line = self.text.as_bytes()[..self.pos].iter().filter(|&&b| b == b'\n').count() + 1;
+        GeonError::InvalidStructure(format!("TOML line {}: {}", line, msg))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    // Step past the character at `pos`, however many bytes it takes
+    fn bump(&mut self) {
+        if let Some(ch) = self.peek() {
+            self.pos += ch.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.text[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_space(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    // Whitespace, newlines and comments (between statements and inside arrays)
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => self.bump(),
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), GeonError> {
+        self.skip_space();
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+        self.eat("\r");
+        if self.peek().is_none() || self.eat("\n") {
+            Ok(())
+        } else {
+            Err(self.error("expected end of line"))
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, GeonError> {
+        if self.eat("\"\"\"") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let ch = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += ch.len_utf8();
+            match ch {
+                '"' => return Ok(out),
+                '\n' => return Err(self.error("newline in string")),
+                '\\' => {
+                    let esc = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.bump();
+                    match esc {
+                        'b' => out.push('\u{8}'),
+                        't' => out.push('\t'),
+                        'n' => out.push('\n'),
+                        'f' => out.push('\u{c}'),
+                        'r' => out.push('\r'),
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        'u' | 'U' => {
+                            let len = if esc == 'u' { 4 } else { 8 };
+                            let hex = self.text.get(self.pos..self.pos + len).ok_or_else(|| self.error("bad escape"))?;
+                            let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+                            out.push(c.ok_or_else(|| self.error("bad unicode escape"))?);
+                            self.pos += len;
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    }
+                }
+                _ => out.push(ch),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, GeonError> {
+        if self.eat("'''") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        self.pos += 1;
+        let end = self.text[self.pos..].find(['\'', '\n']).ok_or_else(|| self.error("unterminated string"))?;
+        if self.text[self.pos + end..].starts_with('\n') {
+            return Err(self.error("newline in string"));
+        }
+        let s = self.text[self.pos..self.pos + end].to_string();
+        self.pos += end + 1;
+        Ok(s)
+    }
+
+    fn simple_key(&mut self) -> Result<String, GeonError> {
+        self.skip_space();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.text[start..self.pos].to_string())
+            }
+        }
+    }
+
+    fn dotted_key(&mut self) -> Result<Vec<String>, GeonError> {
+        let mut parts = vec![self.simple_key()?];
+        loop {
+            self.skip_space();
+            if !self.eat(".") {
+                return Ok(parts);
+            }
+            parts.push(self.simple_key()?);
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, GeonError> {
+        self.skip_space();
+        match self.peek() {
+            Some('"') => Ok(Node::Scalar(Value::String(self.basic_string()?))),
+            Some('\'') => Ok(Node::Scalar(Value::String(self.literal_string()?))),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.eat("]") {
+                        return Ok(Node::List(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip_blank();
+                    if !self.eat(",") {
+                        self.skip_blank();
+                        if self.eat("]") {
+                            return Ok(Node::List(items));
+                        }
+                        return Err(self.error("expected ',' or ']'"));
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut table = Table::new();
+                self.skip_space();
+                if self.eat("}") {
+                    return Ok(Node::Map(table));
+                }
+                loop {
+                    self.key_value(&mut table)?;
+                    self.skip_space();
+                    if self.eat("}") {
+                        return Ok(Node::Map(table));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("expected ',' or '}'"));
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r')) {
+                    self.bump();
+                }
+                let token = self.text[start..self.pos].trim_end();
+                self.pos = start + token.len();
+                scalar(token).ok_or_else(|| self.error(&format!("bad value '{}'", token)))
+            }
+        }
+    }
+
+    fn key_value(&mut self, table: &mut Table) -> Result<(), GeonError> {
+        let path = self.dotted_key()?;
+        self.skip_space();
+        if !self.eat("=") {
+            return Err(self.error("expected '='"));
+        }
+        let value = self.value()?;
+        let (last, parents) = path.split_last().unwrap();
+        let target = descend(table, parents, false).map_err(|m| self.error(&m))?;
+        if target.iter().any(|(k, _)| k == last) {
+            return Err(self.error(&format!("duplicate key '{}'", last)));
+        }
+        target.push((last.clone(), value));
+        Ok(())
+    }
+}
+
+fn scalar(token: &str) -> Option<Node> {
+    let value = match token {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "" => return None,
+        _ => {
+            let digits = token.replace('_', "");
+            if let Ok(i) = digits.parse::<i64>() {
+                Value::from(i)
+            } else if let Some(f) = parse_float(&digits) {
+                Value::from(f)
+            } else if token.starts_with(|c: char| c.is_ascii_digit()) && token.contains([':', '-']) {
+                // Offset/local date-times, dates and times
+                Value::String(token.to_string())
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(Node::Scalar(value))
+}
+
+fn parse_float(s: &str) -> Option<f64> {
+    let body = s.trim_start_matches(['+', '-']);
+    if matches!(body, "inf" | "nan") || !body.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// Walk (creating as needed) to the table at `path`; array-of-tables
+// segments resolve to their last element. With `push`, the final segment is
+// an array of tables that gains a fresh element.
+fn descend<'a>(mut table: &'a mut Table, path: &[String], push: bool) -> Result<&'a mut Table, String> {
+    for (i, part) in path.iter().enumerate() {
+        let last = i + 1 == path.len();
+        let idx = match table.iter().position(|(k, _)| k == part) {
+            Some(idx) => idx,
+            None => {
+                table.push((part.clone(), if push && last { Node::List(vec![]) } else { Node::Map(vec![]) }));
+                table.len() - 1
+            }
+        };
+        let node = &mut table[idx].1;
+        if push && last {
+            match node {
+                Node::List(items) => items.push(Node::Map(vec![])),
+                _ => return Err(format!("'{}' is already defined", part)),
+            }
+        }
+        table = match node {
+            Node::List(items) => match items.last_mut() {
+                Some(Node::Map(m)) => m,
+                _ => return Err(format!("'{}' is not a table", part)),
+            },
+            Node::Map(m) => m,
+            Node::Scalar(_) => return Err(format!("'{}' is already defined", part)),
+        };
+    }
+    Ok(table)
+}
+
+fn read_document(text: &str) -> Result<Node, GeonError> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut root = Table::new();
+    // Path of the table that key/value lines currently go into
+    let mut current: Vec<String> = vec![];
+
+    loop {
+        parser.skip_blank();
+        if parser.peek().is_none() {
+            break;
+        }
+        if parser.eat("[[") {
+            let path = parser.dotted_key()?;
+            parser.skip_space();
+            if !parser.eat("]]") {
+                return Err(parser.error("expected ']]'"));
+            }
+            descend(&mut root, &path, true).map_err(|m| parser.error(&m))?;
+            current = path;
+        } else if parser.eat("[") {
+            let path = parser.dotted_key()?;
+            parser.skip_space();
+            if !parser.eat("]") {
+                return Err(parser.error("expected ']'"));
+            }
+            descend(&mut root, &path, false).map_err(|m| parser.error(&m))?;
+            current = path;
+        } else {
+            let table = descend(&mut root, &current, false).map_err(|m| parser.error(&m))?;
+            parser.key_value(table)?;
+        }
+        parser.end_of_line()?;
+    }
+    Ok(Node::Map(root))
+}
+
+/// Read a place from TOML written with GEON key names.
+pub fn from_toml(text: &str) -> Result<GeonPlace, GeonError> {
+    from_node(&read_document(text)?)
+}
+
+// Any TOML document as JSON, for configuration files
+pub(crate) fn parse_value(text: &str) -> Result<Value, GeonError> {
+    Ok(read_document(text)?.to_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut child = GeonPlace::default();
+        child.place = "Council House".to_string();
+        child.type_ = "building".to_string();
+        child.built_form.insert("roof_type".to_string(), "dome".to_string());
+        let mut grandchild = GeonPlace::default();
+        grandchild.place = "Ballroom".to_string();
+        child.contains.push(grandchild);
+
+        let mut place = GeonPlace::default();
+        place.place = "Old Market Square".to_string();
+        place.type_ = "public_space".to_string();
+        place.location = Some(Coordinate::new(52.953, -1.1497));
+        place.purpose = vec!["trade".to_string(), "say \"hi\"".to_string()];
+        place.experience.insert("noise level".to_string(), "busy".to_string());
+        place.contains = vec![child.clone(), child];
+        place.history = vec![[("event".to_string(), "rebuilt".to_string())].into()];
+        place.extra.insert("levels".to_string(), serde_json::json!([1, 2.5]));
+
+        let text = to_toml(&place).unwrap();
+        assert!(text.contains("\n[[CONTAINS]]\nPLACE = \"Council House\"\n"));
+        assert!(text.contains("\n[CONTAINS.BUILT_FORM]\nroof_type = \"dome\"\n"));
+        assert!(text.contains("\n[[CONTAINS.CONTAINS]]\nPLACE = \"Ballroom\"\n"));
+        assert!(text.contains("\n[EXPERIENCE]\n\"noise level\" = \"busy\"\n"));
+        assert_eq!(from_toml(&text).unwrap(), place);
+
+        place.extra.insert("levels".to_string(), serde_json::json!([1, null]));
+        let err = to_toml(&place).unwrap_err().to_string();
+        assert!(err.contains("null") && err.contains("levels[1]"), "{}", err);
+        place.extra.insert("levels".to_string(), serde_json::json!({"max": u64::MAX}));
+        assert!(to_toml(&place).is_err());
+    }
+
+    #[test]
+    fn test_toml_syntax() {
+        let text = "# header\nPLACE = 'Green'   # trailing\nAREA = 1_500\nUPDATED = 2024-05-01\nPURPOSE = [\n  \"play\",\n  \"rest\", # comment\n]\nEXPERIENCE = { sound = \"birds\" }\nlocal.note = true\n";
+        let place = from_toml(text).unwrap();
+        assert_eq!(place.place, "Green");
+        assert_eq!(place.area.as_deref(), Some("1500"));
+        assert_eq!(place.updated.as_deref(), Some("2024-05-01"));
+        assert_eq!(place.purpose, vec!["play", "rest"]);
+        assert_eq!(place.experience["sound"], "birds");
+        assert_eq!(place.extra["local"], serde_json::json!({"note": true}));
+
+        assert!(from_toml("PLACE = \"a\"\nPLACE = \"b\"\n").is_err());
+        assert!(from_toml("PLACE = \"a\" junk\n").is_err());
+
+        let text = "# café — notes\nPLACE = \"Caf\\u00e9 \\\"Zoë\\\"\" # naïve\nPURPOSE = [ # déjà\n  \"rest\",\n]\n";
+        let place = from_toml(text).unwrap();
+        assert_eq!(place.place, "Café \"Zoë\"");
+        assert_eq!(place.purpose, vec!["rest"]);
+        assert!(from_toml("PLACE = \"a\\é\"\n").is_err());
+        let err = from_toml("# é\nPLACE = é\n").unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{}", err);
+    }
+}
@@ -0,0 +1,445 @@
+//! YAML interop.
+//!
+//! [`to_yaml`] writes a place with GEON key names, in generator order, as
+//! block-style YAML; [`from_yaml`] reads it back. For a typical document the
+//! output is line-for-line the `.geon` text, quoted where YAML needs it.
+//!
+//! # Where GEON syntax diverges from YAML
+//!
+//! GEON is YAML-shaped but is not YAML, and the differences are deliberate:
+//!
+//! - **Values are text.** GEON never types scalars: `ID: 12345` is a string
+//!   and `AREA: 1500 sqm` carries its units inline. YAML would read the
+//!   former as an integer and `open: yes` as a boolean (1.1). [`from_yaml`]
+//!   turns typed scalars back into their text for GEON sections.
+//! - **No quoting rules.** A GEON value runs to the end of the line, so
+//!   `- Market: Saturdays` is a list item in GEON but a mapping in YAML, and
+//!   `PLACE: #1 Club` is a name rather than a comment. [`to_yaml`] quotes such
+//!   values; hand-written YAML must do the same.
+//! - **Coordinates are strings.** `LOCATION: 52.95, -1.15` is a single
+//!   scalar in both, and stays in the GEON `lat, lon` order rather than
+//!   becoming a `[lon, lat]` sequence.
+//! - **Only block structure.** GEON has no flow collections, anchors,
+//!   aliases, tags, block scalars (`|`, `>`) or multi-document streams.
+//!   [`from_yaml`] accepts flow collections on a single line and rejects the
+//!   rest.
+
+use super::document::{from_node, to_node, Node};
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+use serde_json::Value;
+
+fn invalid(line: usize, msg: &str) -> GeonError {
+    GeonError::InvalidStructure(format!("YAML line {}: {}", line, msg))
+}
+
+// Writing
+
+// Strings that read back as themselves when written plain
+fn is_plain_safe(s: &str) -> bool {
+    !s.is_empty()
+        && s.trim() == s
+        && !s.starts_with(['-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`'])
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.chars().any(char::is_control)
+        && matches!(resolve_plain(s), Value::String(_))
+}
+
+fn scalar(v: &Value) -> String {
+    match v {
+        Value::String(s) if is_plain_safe(s) => s.clone(),
+        Value::String(s) => serde_json::to_string(s).unwrap(),
+        other => other.to_string(),
+    }
+}
+
+fn write_entries(buf: &mut String, entries: &[(String, Node)], indent: usize) {
+    let pad = " ".repeat(indent);
+    for (key, value) in entries {
+        let key = scalar(&Value::String(key.clone()));
+        match value {
+            Node::Scalar(v) => buf.push_str(&format!("{}{}: {}\n", pad, key, scalar(v))),
+            Node::List(items) if items.is_empty() => buf.push_str(&format!("{}{}: []\n", pad, key)),
+            Node::Map(m) if m.is_empty() => buf.push_str(&format!("{}{}: {{}}\n", pad, key)),
+            Node::List(items) => {
+                buf.push_str(&format!("{}{}:\n", pad, key));
+                write_items(buf, items, indent + 2);
+            }
+            Node::Map(m) => {
+                buf.push_str(&format!("{}{}:\n", pad, key));
+                write_entries(buf, m, indent + 2);
+            }
+        }
+    }
+}
+
+fn write_items(buf: &mut String, items: &[Node], indent: usize) {
+    let pad = " ".repeat(indent);
+    for item in items {
+        match item {
+            Node::Scalar(v) => buf.push_str(&format!("{}- {}\n", pad, scalar(v))),
+            Node::List(inner) if inner.is_empty() => buf.push_str(&format!("{}- []\n", pad)),
+            Node::Map(m) if m.is_empty() => buf.push_str(&format!("{}- {{}}\n", pad)),
+            // Nested blocks start on the dash line: "- PLACE: ..." / "- - a"
+            Node::List(inner) => {
+                let mut nested = String::new();
+                write_items(&mut nested, inner, indent + 2);
+                buf.push_str(&format!("{}- {}", pad, &nested[indent + 2..]));
+            }
+            Node::Map(m) => {
+                let mut nested = String::new();
+                write_entries(&mut nested, m, indent + 2);
+                buf.push_str(&format!("{}- {}", pad, &nested[indent + 2..]));
+            }
+        }
+    }
+}
+
+/// Serialise a place as YAML using GEON key names.
+pub fn to_yaml(place: &GeonPlace) -> String {
+    let mut buf = String::new();
+    if let Node::Map(entries) = to_node(place) {
+        write_entries(&mut buf, &entries, 0);
+    }
+    buf
+}
+
+// Reading
+
+struct Line {
+    number: usize,
+    indent: usize,
+    content: String,
+}
+
+// Remove a trailing `# comment` that is outside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, ch) in line.char_indices() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => {}
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None if ch == '#' && prev.is_whitespace() => return &line[..i],
+            None => {}
+        }
+        prev = ch;
+    }
+    line
+}
+
+fn read_lines(text: &str) -> Result<Vec<Line>, GeonError> {
+    let mut lines = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let content = strip_comment(raw).trim_end();
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed == "---" || trimmed == "..." || trimmed.starts_with('%') {
+            continue;
+        }
+        let lead = &content[..content.len() - trimmed.len()];
+        if lead.contains('\t') {
+            return Err(invalid(i + 1, "tabs are not allowed in indentation"));
+        }
+        lines.push(Line { number: i + 1, indent: lead.len(), content: trimmed.to_string() });
+    }
+    Ok(lines)
+}
+
+fn is_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+// Byte offset of the `:` ending a mapping key, outside quotes
+fn key_end(content: &str) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if (b == b'"' || b == b'\'') && i == 0 => quote = Some(b),
+            None if b == b':' && bytes.get(i + 1).is_none_or(|n| *n == b' ') => return Some(i),
+            None if i == 0 && matches!(b, b'[' | b'{') => return None,
+            None => {}
+        }
+    }
+    None
+}
+
+fn resolve_plain(s: &str) -> Value {
+    match s {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    let numeric = s.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))
+        && s.bytes().any(|b| b.is_ascii_digit());
+    if numeric {
+        if let Ok(i) = s.parse::<i64>() {
+            return Value::from(i);
+        }
+        if let Ok(f) = s.parse::<f64>() {
+            return Value::from(f);
+        }
+    }
+    Value::String(s.to_string())
+}
+
+fn parse_scalar(text: &str, line: usize) -> Result<Value, GeonError> {
+    if text.starts_with('"') {
+        return serde_json::from_str::<String>(text).map(Value::String).map_err(|_| invalid(line, "bad double-quoted string"));
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'').ok_or_else(|| invalid(line, "unterminated single-quoted string"))?;
+        return Ok(Value::String(inner.replace("''", "'")));
+    }
+    if text.starts_with(['&', '*', '!', '|', '>']) {
+        return Err(invalid(line, "anchors, aliases, tags and block scalars are not supported"));
+    }
+    Ok(resolve_plain(text))
+}
+
+// Single-line flow collections: `[a, b]`, `{k: v}`
+struct Flow<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl Flow<'_> {
+    fn skip_ws(&mut self) {
+        while self.text[self.pos..].starts_with(' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn node(&mut self) -> Result<Node, GeonError> {
+        self.skip_ws();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.collection(']', |flow| {
+                    items.push(flow.node()?);
+                    Ok(())
+                })?;
+                Ok(Node::List(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                self.collection('}', |flow| {
+                    let key = match flow.node()? {
+                        Node::Scalar(Value::String(s)) => s,
+                        Node::Scalar(v) => v.to_string(),
+                        _ => return Err(invalid(flow.line, "flow mapping keys must be scalars")),
+                    };
+                    flow.skip_ws();
+                    if flow.peek() != Some(':') {
+                        return Err(invalid(flow.line, "expected ':' in flow mapping"));
+                    }
+                    flow.pos += 1;
+                    entries.push((key, flow.node()?));
+                    Ok(())
+                })?;
+                Ok(Node::Map(entries))
+            }
+            Some('"') | Some('\'') => {
+                let q = self.peek().unwrap();
+                let start = self.pos;
+                self.pos += 1;
+                while let Some(ch) = self.peek() {
+                    self.pos += ch.len_utf8();
+                    if ch == '\\' && q == '"' {
+                        self.pos += self.peek().map_or(0, char::len_utf8);
+                    } else if ch == q {
+                        if q == '\'' && self.peek() == Some('\'') {
+                            self.pos += 1;
+                            continue;
+                        }
+                        break;
+                    }
+                }
+                Ok(Node::Scalar(parse_scalar(&self.text[start..self.pos], self.line)?))
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(ch) = self.peek() {
+                    if matches!(ch, ',' | ']' | '}') || (ch == ':' && self.text[self.pos + 1..].starts_with([' ', ',', '}'])) {
+                        break;
+                    }
+                    self.pos += ch.len_utf8();
+                }
+                Ok(Node::Scalar(parse_scalar(self.text[start..self.pos].trim(), self.line)?))
+            }
+        }
+    }
+
+    fn collection(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<(), GeonError>) -> Result<(), GeonError> {
+        self.skip_ws();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_ws();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(invalid(self.line, &format!("expected ',' or '{}'", close))),
+            }
+        }
+    }
+}
+
+fn parse_inline(text: &str, line: usize) -> Result<Node, GeonError> {
+    if text.starts_with(['[', '{']) {
+        let mut flow = Flow { text, pos: 0, line };
+        let node = flow.node()?;
+        flow.skip_ws();
+        if flow.pos != text.len() {
+            return Err(invalid(line, "unexpected text after flow collection"));
+        }
+        return Ok(node);
+    }
+    parse_scalar(text, line).map(Node::Scalar)
+}
+
+struct Reader {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Reader {
+    fn current(&self) -> Option<&Line> {
+        self.lines.get(self.pos)
+    }
+
+    fn block(&mut self, indent: usize) -> Result<Node, GeonError> {
+        match self.current() {
+            Some(line) if is_item(&line.content) => self.sequence(indent),
+            _ => self.mapping(indent),
+        }
+    }
+
+    // Value of a key or item whose inline part was empty
+    fn nested(&mut self, parent_indent: usize, allow_same_indent_items: bool) -> Result<Node, GeonError> {
+        match self.current() {
+            Some(next) if next.indent > parent_indent => {
+                let indent = next.indent;
+                self.block(indent)
+            }
+            Some(next) if allow_same_indent_items && next.indent == parent_indent && is_item(&next.content) => {
+                self.sequence(parent_indent)
+            }
+            _ => Ok(Node::Scalar(Value::Null)),
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Node, GeonError> {
+        let mut items = Vec::new();
+        while let Some(line) = self.current() {
+            if line.indent != indent || !is_item(&line.content) {
+                break;
+            }
+            let rest = line.content[1..].trim_start().to_string();
+            let number = line.number;
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, false)?);
+            } else if is_item(&rest) || key_end(&rest).is_some() {
+                // "- KEY: value" opens a block indented to where KEY starts
+                let offset = indent + line.content.len() - rest.len();
+                self.lines[self.pos] = Line { number, indent: offset, content: rest };
+                items.push(self.block(offset)?);
+            } else {
+                self.pos += 1;
+                items.push(parse_inline(&rest, number)?);
+            }
+        }
+        Ok(Node::List(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Node, GeonError> {
+        let mut entries: Vec<(String, Node)> = Vec::new();
+        while let Some(line) = self.current() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent || is_item(&line.content) {
+                return Err(invalid(line.number, "unexpected indentation"));
+            }
+            let number = line.number;
+            let end = key_end(&line.content).ok_or_else(|| invalid(number, "expected 'key: value'"))?;
+            let key = match parse_scalar(line.content[..end].trim(), number)? {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            let rest = line.content[end + 1..].trim().to_string();
+            self.pos += 1;
+            let value = if rest.is_empty() { self.nested(indent, true)? } else { parse_inline(&rest, number)? };
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(invalid(number, &format!("duplicate key '{}'", key)));
+            }
+            entries.push((key, value));
+        }
+        Ok(Node::Map(entries))
+    }
+}
+
+/// Read a place from YAML written with GEON key names (see the module docs
+/// for the supported subset).
+pub fn from_yaml(text: &str) -> Result<GeonPlace, GeonError> {
+    let mut reader = Reader { lines: read_lines(text)?, pos: 0 };
+    let indent = reader.current().map_or(0, |l| l.indent);
+    let node = reader.block(indent)?;
+    if let Some(line) = reader.current() {
+        return Err(invalid(line.number, "unexpected content after document"));
+    }
+    from_node(&node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let text = "PLACE: Old Market Square\nTYPE: public_space\nID: 12345\nLOCATION: 52.9530, -1.1497\nPURPOSE:\n  - trade\n  - \"gathering: civic\"\nEXPERIENCE:\n  noise: busy  # weekdays\nCONTAINS:\n  - PLACE: Council House\n    TYPE: building\n  - PLACE: Fountains\nHISTORY:\n- event: rebuilt\n  year: 1929\nwikidata: Q7084668\nlevels: [1, 2]\n";
+        let place = from_yaml(text).unwrap();
+        assert_eq!(place.id.as_deref(), Some("12345"));
+        assert_eq!(place.location, Some(Coordinate::new(52.953, -1.1497)));
+        assert_eq!(place.purpose, vec!["trade", "gathering: civic"]);
+        assert_eq!(place.experience["noise"], "busy");
+        assert_eq!(place.contains.len(), 2);
+        assert_eq!(place.contains[0].type_, "building");
+        assert_eq!(place.history[0]["year"], "1929");
+        assert_eq!(place.extra["levels"], serde_json::json!([1, 2]));
+
+        let yaml = to_yaml(&place);
+        assert!(yaml.starts_with("PLACE: Old Market Square\nTYPE: public_space\nID: \"12345\"\n"));
+        assert!(yaml.contains("CONTAINS:\n  - PLACE: Council House\n    TYPE: building\n"));
+        assert_eq!(from_yaml(&yaml).unwrap(), place);
+    }
+
+    #[test]
+    fn test_yaml_rejects_unsupported() {
+        assert!(from_yaml("PLACE: a\n  TYPE: b\n").is_err());
+        assert!(from_yaml("PLACE: &anchor a\n").is_err());
+        assert!(from_yaml("PLACE: a\nPLACE: b\n").is_err());
+        assert!(from_yaml("LOCATION: north\n").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Tokens of GEON text, for syntax highlighting and editor tooling.
+//!
+//! Each line lexes independently into, in order: an [`Indent`] of leading
+//! spaces, a [`ListMarker`] for a leading `- `, a [`Key`] and then a
+//! [`Value`] or [`Coordinate`]; or, for a line starting with `#`, a single
+//! [`Comment`]. Colons, the spaces around them and line endings belong to
+//! no token.
+//!
+//! A colon separates a key only when it follows a single word and comes
+//! before a space or the end of the line, the same rule
+//! [`format`](crate::generator::format) uses, so `https://...` and `09:00`
+//! stay values. A `#` later in a line is part of the value, as the parser
+//! reads it.
+//!
+//! [`Indent`]: TokenKind::Indent
+//! [`ListMarker`]: TokenKind::ListMarker
+//! [`Key`]: TokenKind::Key
+//! [`Value`]: TokenKind::Value
+//! [`Coordinate`]: TokenKind::Coordinate
+//! [`Comment`]: TokenKind::Comment
+
+use crate::parser::parse_coordinate;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Leading spaces, whose width gives the nesting.
+    Indent,
+    /// The `-` starting a list item.
+    ListMarker,
+    /// A key before its colon: a section (`EXPERIENCE`) or map key (`noise`).
+    Key,
+    /// Any other text after a key or list marker.
+    Value,
+    /// A `lat, lon` value.
+    Coordinate,
+    /// A line starting with `#`.
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offsets in the whole text.
+    pub span: Range<usize>,
+    /// Zero-based line number.
+    pub line: usize,
+}
+
+// Byte index of the colon ending a one-word key at the start of `rest`
+pub(crate) fn key_separator(rest: &str) -> Option<usize> {
+    let (i, _) = rest
+        .char_indices()
+        .find(|&(i, c)| c == ':' && rest[i + 1..].chars().next().is_none_or(char::is_whitespace))?;
+    let key = rest[..i].trim_end();
+    Some(i).filter(|_| !key.is_empty() && !key.contains(char::is_whitespace))
+}
+
+fn line_tokens(line: &str, start: usize, number: usize) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut push = |kind: TokenKind, from: usize, to: usize| {
+        if from < to {
+            tokens.push(Token { kind, text: &line[from..to], span: start + from..start + to, line: number });
+        }
+    };
+    let content_end = line.trim_end().len();
+    let mut at = line.len() - line.trim_start_matches(' ').len();
+    push(TokenKind::Indent, 0, at);
+    if at >= content_end {
+        return tokens;
+    }
+    if line[at..].starts_with('#') {
+        push(TokenKind::Comment, at, content_end);
+        return tokens;
+    }
+
+    let skip_space = |from: usize| from + (line[from..content_end].len() - line[from..content_end].trim_start().len());
+    let rest = &line[at..content_end];
+    if rest == "-" || rest.starts_with("- ") || rest.starts_with("-\t") {
+        push(TokenKind::ListMarker, at, at + 1);
+        at = skip_space(at + 1);
+    }
+    if let Some(colon) = key_separator(&line[at..content_end]) {
+        push(TokenKind::Key, at, at + line[at..at + colon].trim_end().len());
+        at = skip_space(at + colon + 1);
+    }
+    let value = &line[at..content_end];
+    let kind = if parse_coordinate(value).is_some() { TokenKind::Coordinate } else { TokenKind::Value };
+    push(kind, at, content_end);
+    tokens
+}
+
+/// The tokens of `text`, in order. Blank lines have none.
+pub fn tokens(text: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut offset = 0;
+    text.split_inclusive('\n').enumerate().flat_map(move |(number, raw)| {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        line_tokens(line.strip_suffix('\r').unwrap_or(line), start, number)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    fn kinds(text: &str) -> Vec<(TokenKind, &str)> {
+        tokens(text).map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(kinds("PLACE: Old Market Square\r\n"), [(Key, "PLACE"), (Value, "Old Market Square")]);
+        assert_eq!(kinds("LOCATION: 52.953, -1.1497"), [(Key, "LOCATION"), (Coordinate, "52.953, -1.1497")]);
+        assert_eq!(kinds("# survey, May\nEXPERIENCE:\n  noise:  loud  "), [
+            (Comment, "# survey, May"),
+            (Key, "EXPERIENCE"),
+            (Indent, "  "),
+            (Key, "noise"),
+            (Value, "loud"),
+        ]);
+        assert_eq!(kinds("  - PLACE: Bandstand"), [(Indent, "  "), (ListMarker, "-"), (Key, "PLACE"), (Value, "Bandstand")]);
+        assert_eq!(kinds("  - https://example.org/a:b"), [(Indent, "  "), (ListMarker, "-"), (Value, "https://example.org/a:b")]);
+        assert_eq!(kinds("  - 09:00-17:00 weekdays"), [(Indent, "  "), (ListMarker, "-"), (Value, "09:00-17:00 weekdays")]);
+        assert_eq!(kinds("  - 52.9, -1.1\n\n-"), [(Indent, "  "), (ListMarker, "-"), (Coordinate, "52.9, -1.1"), (ListMarker, "-")]);
+
+        let text = "PLACE: Café\nTYPE: plaza";
+        let tokens: Vec<Token> = tokens(text).collect();
+        assert_eq!(tokens[3].span.clone(), 19..24);
+        assert_eq!(&text[tokens[3].span.clone()], "plaza");
+        assert_eq!(tokens[3].line, 1);
+    }
+}
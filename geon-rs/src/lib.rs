@@ -1,15 +1,73 @@
+//! GEON place descriptions: parsing, generation, conversion and analysis.
+//!
+//! Without the default `std` feature only [`models`], [`parser`],
+//! [`lexer`], [`generator`] and [`vocabulary`] are built, for `no_std`
+//! targets with an allocator.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod models;
 pub mod parser;
+pub mod lexer;
 pub mod generator;
+pub mod vocabulary;
+#[cfg(feature = "std")]
 pub mod converter;
+#[cfg(feature = "std")]
+pub mod geometry;
+#[cfg(feature = "std")]
+pub mod enrich;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod collection;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod quantity;
+#[cfg(feature = "std")]
+pub mod units;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod patch;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "std")]
+pub mod changelog;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod tools;
+#[cfg(feature = "cells")]
+pub mod cells;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(any(all(test, feature = "std"), feature = "testing"))]
+pub mod testing;
 
 // Re-export core items
 pub use models::{GeonPlace, Coordinate, Extent};
-pub use parser::parse;
+pub use parser::{parse, GeonError};
 pub use generator::generate;
-pub use converter::from_geojson;
+#[cfg(feature = "std")]
+pub use collection::{GeonCollection, Level, Query, SpatialMatch};
+#[cfg(feature = "std")]
+pub use graph::PlaceGraph;
+#[cfg(feature = "std")]
+pub use patch::{diff_places, GeonPatch};
+#[cfg(feature = "std")]
+pub use merge::{merge3, MergeConflicts};
+#[cfg(feature = "std")]
+pub use changelog::ChangeLog;
+#[cfg(feature = "std")]
+pub use converter::{from_geojson, from_geojson_with, to_geojson, to_geojson_collection, TypeMapping};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -67,6 +125,113 @@ CONTAINS:
         assert_eq!(child.type_, "nested");
     }
 
+    #[test]
+    fn test_parse_bytes() {
+        let place = parser::parse_bytes(b"\xEF\xBB\xBFPLACE: Caf\xE9\r\nTYPE: hybrid\r\n").unwrap();
+        assert_eq!(place.place, "Caf\u{FFFD}");
+        assert_eq!(place.type_, "hybrid");
+        assert!(parser::parse_bytes(b"  PLACE: Indented\nTYPE: x").is_err());
+        assert!(parser::parse_bytes(&[0xFF, 0x00, 0x3A]).is_err());
+    }
+
+    #[test]
+    fn test_every_section() {
+        let text = r#"
+PLACE: Arboretum
+TYPE: park
+ID: osm:way/1
+LOCATION: 52.96, -1.16
+PATH:
+  - 52.96, -1.16
+  - 52.97, -1.16
+EXTENT: 52.97, 52.95, -1.15, -1.17
+ELEVATION: 60m
+AREA: 7 ha
+PURPOSE: recreation
+EXPERIENCE:
+  openness: high
+CHARACTER:
+  - Victorian
+ADJACENCIES:
+  - Waverley Street (west)
+CONNECTIVITY:
+  pedestrian_entries: 3
+CONTAINS:
+  - PLACE: Bandstand
+    SOURCE:
+      - survey
+PART_OF: Nottingham
+VIEWSHEDS:
+  castle: partial
+TEMPORAL:
+  opening: dawn to dusk
+LIFESPAN:
+  opened: 1852
+SOURCE:
+  - OpenStreetMap
+CONFIDENCE:
+  geometry: high
+UPDATED: 2025-05-01
+BUILT_FORM:
+  bandstand: cast iron
+ECOLOGY:
+  trees: 800
+INFRASTRUCTURE:
+  lighting: none
+DEMOGRAPHICS:
+  visitors: families
+ECONOMY:
+  entry: free
+VISUAL:
+  palette: green
+# Comments aren't fields: here: nothing
+HISTORY:
+  - date: 1852
+    event: opened
+  - date: 2002
+VERTICAL_PROFILE:
+  canopy_height: 25m
+WHEELCHAIR: yes
+addr:street: Waverley Street
+RATING: 4.5
+FACILITIES:
+  - toilets
+"#;
+        let place = parse(text);
+        assert_eq!(place.path.len(), 2);
+        assert_eq!(place.extent.as_ref().unwrap().west, -1.17);
+        assert_eq!(place.contains[0].source, ["survey"]);
+        assert_eq!(place.viewsheds, serde_json::json!({"castle": "partial"}));
+        assert_eq!(place.source, ["OpenStreetMap"]);
+        assert_eq!(place.confidence["geometry"], "high");
+        assert_eq!(place.updated.as_deref(), Some("2025-05-01"));
+        assert_eq!(place.built_form["bandstand"], "cast iron");
+        assert_eq!(place.ecology["trees"], "800");
+        assert_eq!(place.infrastructure["lighting"], "none");
+        assert_eq!(place.demographics["visitors"], "families");
+        assert_eq!(place.economy["entry"], "free");
+        assert_eq!(place.visual["palette"], "green");
+        assert_eq!(place.history.len(), 2);
+        assert_eq!((place.history[0]["date"].as_str(), place.history[0]["event"].as_str()), ("1852", "opened"));
+        assert_eq!(place.history[1].len(), 1);
+        assert_eq!(place.vertical_profile["canopy_height"], "25m");
+        assert_eq!(place.extra.len(), 4);
+        assert_eq!(place.extra["WHEELCHAIR"], "yes");
+        assert_eq!(place.extra["addr:street"], "Waverley Street");
+        assert_eq!(place.extra["RATING"], 4.5);
+        assert_eq!(place.extra["FACILITIES"], serde_json::json!(["toilets"]));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_many_parallel() {
+        let texts: Vec<String> = (0..100).map(|i| format!("PLACE: Place {i}\nLOCATION: {i}.0, 1.0")).collect();
+        let places = parser::parse_many_parallel(&texts);
+        assert_eq!(places.len(), 100);
+        assert_eq!(places[42].place, "Place 42");
+        assert_eq!(places[99].location.as_ref().unwrap().lat, 99.0);
+    }
+
     #[test]
     fn test_round_trip() {
         let mut place = GeonPlace::default();
@@ -87,4 +252,760 @@ CONTAINS:
         assert_eq!(place.location, parsed.location);
         assert_eq!(place.purpose, parsed.purpose);
     }
+
+    #[test]
+    fn test_to_geojson_point_and_sections() {
+        let text = "PLACE: Market\nTYPE: public_space\nID: m1\nLOCATION: 52.95, -1.15\nEXPERIENCE:\n  noise_level: loud";
+        let feature = to_geojson(&parse(text));
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["id"], "m1");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["geometry"]["coordinates"], serde_json::json!([-1.15, 52.95]));
+        assert_eq!(feature["properties"]["geon_type"], "public_space");
+        assert_eq!(feature["properties"]["experience"]["noise_level"], "loud");
+    }
+
+    #[test]
+    fn test_to_geojson_polygon_and_children() {
+        let text = r#"
+PLACE: Campus
+ID: c1
+BOUNDARY:
+  - 1.0, 1.0
+  - 1.0, 2.0
+  - 2.0, 2.0
+CONTAINS:
+  - PLACE: Library
+    TYPE: building
+    LOCATION: 1.5, 1.5
+"#;
+        let feature = to_geojson(&parse(text));
+        let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(feature["geometry"]["type"], "Polygon");
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.first(), ring.last());
+
+        let children = feature["properties"]["contains"].as_array().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0]["properties"]["name"], "Library");
+        assert_eq!(children[0]["properties"]["parent"], "c1");
+        assert_eq!(children[0]["geometry"]["type"], "Point");
+    }
+
+    #[test]
+    fn test_to_geojson_collection_modes() {
+        let text = r#"
+PLACE: Market Quarter
+CONTAINS:
+  - PLACE: Stall
+    LOCATION: 1.0, 1.0
+"#;
+        let places = vec![parse(text)];
+
+        let flat = to_geojson_collection(&places, &converter::CollectionOptions::default());
+        let features = flat["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[1]["properties"]["parent_id"], "Market Quarter");
+        assert_eq!(features[1]["properties"]["part_of"], "Market Quarter");
+        assert_eq!(features[1]["properties"]["depth"], 1);
+        assert!(features[0]["properties"].get("contains").is_none());
+
+        let opts = converter::CollectionOptions { hierarchy: converter::HierarchyMode::Embed };
+        let nested = to_geojson_collection(&places, &opts);
+        let features = nested["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["properties"]["contains"][0]["properties"]["name"], "Stall");
+
+        let text = "PLACE: Bandstand\nID: osm:node/9\nPLACE: Lake\nADJACENCIES:\n  - bandstand\n  - Nowhere\n";
+        let mut collection = GeonCollection::new(parser::parse_many(text));
+        collection.push(places[0].clone());
+        collection.places[2].contains[0].adjacencies = vec!["Lake".to_string()];
+        let flat = collection.to_geojson(&converter::CollectionOptions::default());
+        let features = flat["features"].as_array().unwrap();
+        assert_eq!(features[1]["properties"]["geon:adjacent_ids"], serde_json::json!(["osm:node/9"]));
+        assert!(features[2]["properties"].get("geon:adjacent_ids").is_none());
+        assert_eq!(features[3]["properties"]["geon:adjacent_ids"], serde_json::json!(["Lake"]));
+        assert!(!from_geojson(features[1].clone())[0].extra.contains_key("geon:adjacent_ids"));
+    }
+
+    #[test]
+    fn test_geojson_round_trip_sections() {
+        let text = r#"
+PLACE: Market Quarter
+TYPE: district
+ID: mq
+LOCATION: 52.95, -1.15
+BOUNDARY:
+  - 52.94, -1.16
+  - 52.96, -1.16
+  - 52.96, -1.14
+  - 52.94, -1.14
+PATH:
+  - 52.94, -1.15
+  - 52.96, -1.15
+EXPERIENCE:
+  noise_level: loud
+CHARACTER:
+  - historic
+CONTAINS:
+  - PLACE: Hall
+    TYPE: building
+    LOCATION: 52.951, -1.151
+    CONTAINS:
+      - PLACE: Stall
+        TYPE: building
+        LOCATION: 52.9511, -1.1511
+  - PLACE: Square
+    TYPE: public_space
+"#;
+        let mut place = parse(text);
+        place.history.push(std::collections::BTreeMap::from([("date".to_string(), "1928".to_string())]));
+        place.extra.insert("operator".to_string(), serde_json::json!({"name": "City Council"}));
+
+        let feature = to_geojson(&place);
+        assert_eq!(feature["geometry"]["type"], "GeometryCollection");
+        let back = from_geojson(feature).remove(0);
+        assert_eq!(back, place);
+        assert_eq!(back.contains[0].contains[0].place, "Stall");
+
+        let feature = serde_json::json!({
+            "type": "Feature",
+            "geometry": null,
+            "properties": {"name": "Bullring", "experience": {"openness": "medium", "crowding": 3}, "fsq_id": 42}
+        });
+        let p = from_geojson(feature).remove(0);
+        assert_eq!(p.experience["crowding"], "3");
+        assert_eq!(p.extra["fsq_id"], 42);
+        assert!(!p.extra.contains_key("experience"));
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn test_geojson_crate_feature() {
+        let mut place = parse("PLACE: Arboretum Walk\nTYPE: street\nID: osm:way/1\nPATH:\n  - 52.96, -1.17\n  - 52.97, -1.16\nCONFIDENCE:\n  geometry: high\n");
+        place.location = Some(Coordinate::new(52.96, -1.16));
+        let mut bandstand = GeonPlace::default();
+        bandstand.place = "Bandstand".to_string();
+        place.contains.push(bandstand);
+
+        let feature = geojson::Feature::from(&place);
+        assert_eq!(feature.geometry.as_ref().map(|g| g.value.type_name()), Some("LineString"));
+        assert_eq!(feature.property("name"), Some(&serde_json::json!("Arboretum Walk")));
+        assert_eq!(GeonPlace::try_from(feature).unwrap(), place);
+
+        let feature: geojson::Feature = r#"{"type": "Feature", "id": "n1", "geometry": {"type": "Point", "coordinates": [-1.15, 52.95]},
+            "properties": {"name": "Council House", "amenity": "townhall"}}"#.parse().unwrap();
+        let council = GeonPlace::try_from(feature).unwrap();
+        assert_eq!((council.place.as_str(), council.id.as_deref()), ("Council House", Some("n1")));
+        assert_eq!(council.location, Some(Coordinate::new(52.95, -1.15)));
+    }
+
+    #[test]
+    fn test_from_geojson_geometry_types() {
+        let feature = |geometry: serde_json::Value| {
+            from_geojson(serde_json::json!({"type": "Feature", "geometry": geometry, "properties": {}})).remove(0)
+        };
+
+        let line = feature(serde_json::json!({"type": "LineString", "coordinates": [[0.0, 0.0], [0.0, 1.0], [0.0, 3.0]]}));
+        assert_eq!(line.type_, "street");
+        let mid = line.location.unwrap();
+        assert!((mid.lat - 1.5).abs() < 1e-9 && mid.lon == 0.0);
+        assert_eq!(line.path.len(), 3);
+        assert!(line.boundary.is_empty());
+
+        let small = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]];
+        let large = [[10.0, 10.0], [14.0, 10.0], [14.0, 14.0], [10.0, 14.0], [10.0, 10.0]];
+        let multi = feature(serde_json::json!({"type": "MultiPolygon", "coordinates": [[small], [large]]}));
+        assert_eq!(multi.boundary.len(), 4);
+        assert_eq!(multi.boundary[0], Coordinate::new(10.0, 10.0));
+        assert_eq!(multi.type_, "hybrid");
+
+        let points = feature(serde_json::json!({"type": "MultiPoint", "coordinates": [[0.0, 0.0], [2.0, 4.0]]}));
+        assert_eq!(points.location, Some(Coordinate::new(2.0, 1.0)));
+
+        let collection = feature(serde_json::json!({"type": "GeometryCollection", "geometries": [
+            {"type": "Point", "coordinates": [5.0, 5.0]},
+            {"type": "Polygon", "coordinates": [small]}
+        ]}));
+        assert_eq!(collection.boundary.len(), 3);
+        let at = collection.location.unwrap();
+        assert!((at.lat - 1.0 / 3.0).abs() < 1e-9 && (at.lon - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_custom_type_mapping() {
+        let feature = serde_json::json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [-1.15, 52.95]},
+            "properties": {"landuse": "allotments", "amenity": "cafe", "nom": "Les Jardins"}
+        });
+        assert_eq!(from_geojson(feature.clone())[0].type_, "building");
+
+        let mapping = TypeMapping::from_json(
+            r#"{"types": {"public_space": ["allotments"]}, "type_keys": ["landuse", "amenity"], "name_keys": ["nom"]}"#,
+        )
+        .unwrap();
+        let place = from_geojson_with(feature, &mapping).remove(0);
+        assert_eq!(place.type_, "public_space");
+        assert_eq!(place.place, "Les Jardins");
+        assert_eq!(mapping.default_type, "hybrid");
+        assert!(TypeMapping::from_json(r#"{"type_keys": "landuse"}"#).is_err());
+
+        #[cfg(feature = "toml")]
+        {
+            let toml = "type_keys = [\"landuse\", \"amenity\"]\nname_keys = [\"nom\"]\n\n[types]\npublic_space = [\"allotments\"]\n";
+            assert_eq!(TypeMapping::from_toml(toml).unwrap(), mapping);
+        }
+    }
+
+    #[test]
+    fn test_xy_conversions() {
+        let c = Coordinate::new(52.95, -1.15);
+        let xy: (f64, f64) = (&c).into();
+        assert_eq!(xy, (-1.15, 52.95));
+        assert_eq!(Coordinate::from(xy), c);
+
+        let ext = Extent { north: 2.0, south: 1.0, east: 4.0, west: 3.0 };
+        let (min, max) = ext.to_min_max();
+        assert_eq!(Extent::from_min_max(min, max), ext);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_geo_types_conversions() {
+        let c = Coordinate::new(52.95, -1.15);
+        let point = geo_types::Point::from(&c);
+        assert_eq!((point.x(), point.y()), (-1.15, 52.95));
+        assert_eq!(Coordinate::from(point), c);
+        assert_eq!(Coordinate::from(geo_types::Coord::from(&c)), c);
+
+        let ext = Extent { north: 2.0, south: 1.0, east: 4.0, west: 3.0 };
+        let rect = geo_types::Rect::try_from(&ext).unwrap();
+        assert_eq!(rect.min(), geo_types::Coord { x: 3.0, y: 1.0 });
+        assert_eq!(Extent::from(rect), ext);
+        let fiji = Extent { north: -16.0, south: -19.0, east: -179.0, west: 177.0 };
+        assert!(geo_types::Rect::try_from(&fiji).is_err());
+
+        let mut place = parse("PLACE: Arboretum\nBOUNDARY:\n  - 52.96, -1.17\n  - 52.97, -1.16\n  - 52.95, -1.15\n");
+        let polygon = place.boundary_polygon().unwrap();
+        assert_eq!(polygon.exterior().0.len(), 4);
+        assert_eq!(polygon.exterior().0[1], geo_types::Coord { x: -1.16, y: 52.97 });
+        let before = place.boundary.clone();
+        place.set_boundary_polygon(&polygon);
+        assert_eq!(place.boundary, before);
+        assert!(GeonPlace::default().boundary_polygon().is_none());
+    }
+
+    #[test]
+    fn test_wkt_round_trip() {
+        let mut place = GeonPlace::default();
+        place.set_geometry_from_wkt("POLYGON ((-1.0 52.0, -1.0 53.0, 0.0 53.0, -1.0 52.0), (-0.9 52.1, -0.9 52.2, -0.8 52.1, -0.9 52.1))").unwrap();
+        assert_eq!(place.boundary.len(), 4);
+        assert_eq!(place.boundary[1], Coordinate::new(53.0, -1.0));
+        assert_eq!(geometry::to_wkt(&place).unwrap(), "POLYGON ((-1 52, -1 53, 0 53, -1 52))");
+
+        place.set_geometry_from_wkt("point(-1.15 52.95)").unwrap();
+        assert_eq!(place.location, Some(Coordinate::new(52.95, -1.15)));
+
+        place.set_geometry_from_wkt("MULTIPOLYGON (((0 0, 0 1, 1 1, 0 0)), ((0 0, 0 5, 5 5, 0 0)))").unwrap();
+        assert_eq!(place.boundary[2], Coordinate::new(5.0, 5.0));
+
+        assert!(place.set_geometry_from_wkt("MULTIPOINT ((0 0), (1 1))").is_err());
+        assert!(place.set_geometry_from_wkt("POINT (1)").is_err());
+
+        let mut walk = GeonPlace::default();
+        walk.set_geometry_from_wkt("GEOMETRYCOLLECTION (POLYGON ((0 0, 0 1, 1 1, 0 0)), LINESTRING (0 0, 1 1))").unwrap();
+        assert_eq!(walk.boundary.len(), 4);
+        assert_eq!(walk.path, vec![Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)]);
+        assert_eq!(geometry::to_wkt(&walk).unwrap(), "GEOMETRYCOLLECTION (POLYGON ((0 0, 0 1, 1 1, 0 0)), LINESTRING (0 0, 1 1))");
+        walk.boundary.clear();
+        assert_eq!(geometry::to_wkt(&walk).unwrap(), "LINESTRING (0 0, 1 1)");
+        assert!(geometry::from_wkt("GEOMETRYCOLLECTION (GEOMETRYCOLLECTION (POINT (0 0)))").is_err());
+    }
+
+    #[test]
+    fn test_polygon_centroid() {
+        // An L-shape whose long arm is densely digitised: the vertex mean drifts
+        // along the arm, the area-weighted centroid does not
+        let mut ring = vec![Coordinate::new(0.0, 0.0)];
+        ring.extend((1..=20).map(|i| Coordinate::new(0.0, i as f64 * 0.2)));
+        ring.extend([Coordinate::new(1.0, 4.0), Coordinate::new(1.0, 1.0), Coordinate::new(3.0, 1.0), Coordinate::new(3.0, 0.0)]);
+        let c = geometry::centroid(&ring).unwrap();
+        assert!((c.lon - 1.5).abs() < 1e-9 && (c.lat - 1.0).abs() < 1e-9);
+
+        let mut closed = ring.clone();
+        closed.push(ring[0].clone());
+        assert_eq!(geometry::centroid(&closed), Some(c));
+
+        let line = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 2.0), Coordinate::new(0.0, 0.0)];
+        assert_eq!(geometry::centroid(&line), Some(Coordinate::new(0.0, 1.0)));
+        assert_eq!(geometry::centroid(&[]), None);
+    }
+
+    #[test]
+    fn test_simplify_boundary() {
+        // A ~1 km square with a point every few metres along each side and a
+        // 2 m kink halfway along the south edge
+        let mut ring = Vec::new();
+        for i in 0..100 {
+            let t = i as f64 * 0.0001;
+            ring.push(Coordinate::new(if i == 50 { 0.00002 } else { 0.0 }, t));
+        }
+        ring.extend((0..100).map(|i| Coordinate::new(i as f64 * 0.0001, 0.01)));
+        ring.extend((0..100).map(|i| Coordinate::new(0.01, 0.01 - i as f64 * 0.0001)));
+        ring.extend((0..100).map(|i| Coordinate::new(0.01 - i as f64 * 0.0001, 0.0)));
+        ring.push(ring[0].clone());
+
+        let mut coarse = ring.clone();
+        geometry::simplify(&mut coarse, 5.0);
+        assert_eq!(coarse.len(), 5);
+        assert_eq!(coarse.first(), coarse.last());
+        assert!(coarse.contains(&Coordinate::new(0.01, 0.01)));
+
+        let mut fine = ring.clone();
+        geometry::simplify(&mut fine, 1.0);
+        assert!(fine.contains(&Coordinate::new(0.00002, 0.005)));
+
+        // Open rings stay open and keep a polygon's worth of points
+        let mut tiny = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1e-6), Coordinate::new(1e-6, 1e-6), Coordinate::new(1e-6, 0.0)];
+        geometry::simplify(&mut tiny, 10.0);
+        assert_eq!(tiny.len(), 3);
+        assert_ne!(tiny.first(), tiny.last());
+    }
+
+    #[test]
+    fn test_densify_boundary() {
+        // ~1.1 km square
+        let square = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 0.01),
+            Coordinate::new(0.01, 0.01),
+            Coordinate::new(0.01, 0.0),
+        ];
+        let mut open = square.clone();
+        geometry::densify(&mut open, 250.0).unwrap();
+        assert_eq!(open.len(), 20);
+        assert_eq!(open[5], square[1]);
+        assert_ne!(open.first(), open.last());
+        assert!(open.windows(2).all(|w| geometry::haversine_m(&w[0], &w[1]) <= 250.0));
+        assert!((geometry::area_m2(&open) - geometry::area_m2(&square)).abs() < 1.0);
+
+        let mut closed = square.clone();
+        closed.push(square[0].clone());
+        geometry::densify(&mut closed, 250.0).unwrap();
+        assert_eq!(closed.len(), 21);
+        assert_eq!(closed.first(), closed.last());
+
+        let mut short = square.clone();
+        geometry::densify(&mut short, 5_000.0).unwrap();
+        assert_eq!(short, square);
+
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(geometry::densify(&mut short, bad).is_err());
+        }
+        assert_eq!(short, square);
+        // A 1 mm limit on a ~1.1 km edge is capped rather than allocating a million points
+        let mut edge = square[..2].to_vec();
+        geometry::densify(&mut edge, 0.001).unwrap();
+        assert_eq!(edge.len(), geometry::MAX_DENSIFY_STEPS + 1);
+        assert_eq!(edge.last(), square.get(1));
+    }
+
+    #[test]
+    fn test_hull_from_children() {
+        // Buildings along the two wings of an L-shaped campus, ~100 m apart
+        let mut campus = GeonPlace::default();
+        for (lat, lon) in [(0.0, 0.0), (0.0, 0.001), (0.0, 0.002), (0.0, 0.003), (0.001, 0.0), (0.002, 0.0), (0.003, 0.0)] {
+            let mut building = GeonPlace::default();
+            building.location = Some(Coordinate::new(lat, lon));
+            campus.contains.push(building);
+        }
+        campus.contains[1].boundary = vec![Coordinate::new(0.0005, 0.001), Coordinate::new(0.0005, 0.0011), Coordinate::new(0.0006, 0.001)];
+
+        assert!(campus.derive_boundary_from_children(geometry::Hull::Convex));
+        assert_eq!(campus.boundary.len(), 4);
+        assert_eq!(campus.boundary.first(), campus.boundary.last());
+        assert!(geometry::ring_contains(&campus.boundary, &Coordinate::new(0.0012, 0.0012)));
+
+        assert!(campus.derive_boundary_from_children(geometry::Hull::Concave(150.0)));
+        assert!(!geometry::ring_contains(&campus.boundary, &Coordinate::new(0.0012, 0.0012)));
+        assert!(geometry::ring_contains(&campus.boundary, &Coordinate::new(0.0003, 0.0003)));
+        assert!(geometry::validate_ring(&campus.boundary).is_empty());
+
+        let mut street = GeonPlace::default();
+        street.contains = campus.contains[..4].to_vec();
+        street.contains[1].boundary.clear();
+        assert!(!street.derive_boundary_from_children(geometry::Hull::Convex));
+        assert!(street.boundary.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_point() {
+        let kiosk = Coordinate::new(52.95, -1.15);
+        let ring = geometry::buffer(&kiosk, 50.0, 32);
+        assert_eq!(ring.len(), 33);
+        assert_eq!(ring.first(), ring.last());
+        assert!(ring.iter().all(|c| (geometry::haversine_m(&kiosk, c) - 50.0).abs() < 1e-6));
+        assert!(geometry::ring_contains(&ring, &kiosk));
+        assert!(geometry::validate_ring(&ring).is_empty());
+        assert!((ring[0].lat - kiosk.lat - 50.0 / geometry::METRES_PER_DEGREE).abs() < 1e-6);
+
+        assert_eq!(geometry::buffer(&kiosk, 10.0, 1).len(), 4);
+        let across = geometry::buffer(&Coordinate::new(0.0, 179.9999), 100.0, 8);
+        assert!(across.iter().all(|c| (-180.0..=180.0).contains(&c.lon)));
+    }
+
+    #[test]
+    fn test_distance_between_places() {
+        let square = |lat: f64, lon: f64, side: f64| {
+            vec![Coordinate::new(lat, lon), Coordinate::new(lat, lon + side), Coordinate::new(lat + side, lon + side), Coordinate::new(lat + side, lon)]
+        };
+        let (mut park, mut lake, mut cafe) = (GeonPlace::default(), GeonPlace::default(), GeonPlace::default());
+        park.boundary = square(0.0, 0.0, 0.01);
+        park.location = Some(Coordinate::new(0.005, 0.005));
+        lake.boundary = square(0.0, 0.02, 0.01);
+        cafe.location = Some(Coordinate::new(0.005, 0.015));
+
+        // Boundary to boundary, not between LOCATIONs
+        let gap = park.distance_to(&lake).unwrap();
+        assert!((gap - 1_112.0).abs() < 2.0, "{gap}");
+        assert!((park.distance_to(&cafe).unwrap() - 556.0).abs() < 1.0);
+        assert!((cafe.distance_to(&lake).unwrap() - 556.0).abs() < 1.0);
+
+        let mut pond = GeonPlace::default();
+        pond.boundary = square(0.009, 0.009, 0.002);
+        assert_eq!(park.distance_to(&pond), Some(0.0));
+        cafe.location = Some(Coordinate::new(0.001, 0.001));
+        assert_eq!(park.distance_to(&cafe), Some(0.0));
+
+        let mut kiosk = GeonPlace::default();
+        kiosk.location = Some(Coordinate::new(0.0, 0.01));
+        assert!((cafe.distance_to(&kiosk).unwrap() - geometry::haversine_m(&Coordinate::new(0.001, 0.001), &Coordinate::new(0.0, 0.01))).abs() < 5.0);
+        assert_eq!(kiosk.distance_to(&GeonPlace::default()), None);
+
+        // A PATH-only street running north along lon 0.012, between park and lake
+        let mut street = GeonPlace::default();
+        street.path = vec![Coordinate::new(-0.01, 0.012), Coordinate::new(0.02, 0.012)];
+        assert!((park.distance_to(&street).unwrap() - 222.4).abs() < 1.0);
+        assert!((street.distance_to(&lake).unwrap() - 889.6).abs() < 1.0);
+        assert!((street.distance_to(&kiosk).unwrap() - 222.4).abs() < 1.0);
+        street.path.push(Coordinate::new(0.005, 0.025));
+        assert_eq!(street.distance_to(&lake), Some(0.0));
+
+        #[cfg(feature = "geodesic")]
+        {
+            // Flinders Peak to Buninyong (Vincenty's own test case)
+            let flinders = Coordinate::new(-37.951_033_416_7, 144.424_867_888_9);
+            let buninyong = Coordinate::new(-37.652_821_138_9, 143.926_495_527_8);
+            assert!((geometry::geodesic_m(&flinders, &buninyong) - 54_972.271).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_derive_extent() {
+        let text = r#"
+PLACE: Campus
+LOCATION: 52.0, -1.0
+CONTAINS:
+  - PLACE: Library
+    BOUNDARY:
+      - 52.1, -1.2
+      - 52.2, -1.2
+      - 52.2, -1.1
+  - PLACE: Pond
+    LOCATION: 51.9, -0.9
+    AREA: 3.14159 ha
+PLACE: Bench
+LOCATION: 51.5, 0.0
+PLACE: Unplaced
+PLACE: Lane
+PATH:
+  - 51.0, -1.0
+  - 51.1, -0.9
+"#;
+        let mut collection = GeonCollection::new(parser::parse_many(text));
+        let window = Extent { north: 51.2, south: 51.05, east: -0.8, west: -1.2 };
+        assert!(collection.query_bbox(&window).is_empty());
+        assert_eq!(collection.query_bbox_with(&window, SpatialMatch::Intersects).len(), 1);
+        let near = collection.query_radius_with(&Coordinate::new(51.05, -0.95), 10.0, SpatialMatch::Intersects);
+        assert_eq!(near.len(), 1);
+        assert_eq!(collection.fill_extents(), 5);
+        let campus = &collection.places[0];
+        assert_eq!(campus.contains[0].extent, Some(Extent { north: 52.2, south: 52.1, east: -1.1, west: -1.2 }));
+        let pond = campus.contains[1].extent.as_ref().unwrap();
+        assert!((pond.north - 51.9 - 100.0 / geometry::METRES_PER_DEGREE).abs() < 1e-9);
+        let extent = campus.extent.as_ref().unwrap();
+        assert_eq!((extent.north, extent.west), (52.2, -1.2));
+        assert!(extent.south < 51.9 && extent.east > -0.9);
+        assert_eq!(collection.places[1].extent, Some(Extent { north: 51.5, south: 51.5, east: 0.0, west: 0.0 }));
+        assert!(collection.places[2].extent.is_none());
+        assert_eq!(collection.places[3].extent, Some(Extent { north: 51.1, south: 51.0, east: -0.9, west: -1.0 }));
+        assert_eq!(collection.fill_extents(), 0);
+    }
+
+    #[test]
+    fn test_overlapping_footprints() {
+        let ring = |points: &[(f64, f64)]| points.iter().map(|&(lat, lon)| Coordinate::new(lat, lon)).collect::<Vec<_>>();
+        let (mut osm, mut os, mut next_door, mut annex) =
+            (GeonPlace::default(), GeonPlace::default(), GeonPlace::default(), GeonPlace::default());
+        // A 0.001° square and the same building shifted by half its width
+        osm.boundary = ring(&[(0.0, 0.0), (0.0, 0.001), (0.001, 0.001), (0.001, 0.0), (0.0, 0.0)]);
+        os.boundary = ring(&[(0.0, 0.0005), (0.001, 0.0005), (0.001, 0.0015), (0.0, 0.0015)]);
+        next_door.boundary = ring(&[(0.0, 0.001), (0.0, 0.002), (0.001, 0.002), (0.001, 0.001)]);
+        // An L-shape wrapping round a corner of the square
+        annex.boundary = ring(&[(-0.001, -0.001), (-0.001, 0.0005), (0.0005, 0.0005), (0.0005, 0.0), (0.0, 0.0), (0.0, -0.001)]);
+
+        let side = 0.001 * geometry::METRES_PER_DEGREE;
+        let shared = osm.overlap_area_m2(&os).unwrap();
+        assert!((shared - side * side / 2.0).abs() < 1.0, "{shared}");
+        assert!((os.overlap_area_m2(&osm).unwrap() - shared).abs() < 1e-6);
+        assert!((osm.overlap_area_m2(&osm).unwrap() - side * side).abs() < 1.0);
+        assert!((osm.overlap_area_m2(&annex).unwrap() - side * side / 4.0).abs() < 1.0);
+        assert!(osm.overlaps(&os));
+        assert!(!osm.overlaps(&next_door));
+
+        let mut far = GeonPlace::default();
+        far.boundary = ring(&[(1.0, 1.0), (1.0, 1.001), (1.001, 1.001)]);
+        assert_eq!(osm.overlap_area_m2(&far), Some(0.0));
+        far.boundary.clear();
+        far.location = Some(Coordinate::new(0.0005, 0.0005));
+        assert_eq!(osm.overlap_area_m2(&far), None);
+    }
+
+    #[test]
+    fn test_bearing_and_adjacency() {
+        let origin = Coordinate::new(52.0, -1.0);
+        let east = Coordinate::new(52.0, -0.997);
+        assert!((geometry::bearing(&origin, &Coordinate::new(53.0, -1.0))).abs() < 1e-9);
+        assert!((geometry::bearing(&origin, &east) - 90.0).abs() < 0.1);
+        assert_eq!(geometry::compass_direction(&origin, &east), "east");
+        assert_eq!(geometry::compass_direction(&origin, &Coordinate::new(51.9, -1.1)), "southwest");
+        assert_eq!(geometry::compass_direction(&origin, &Coordinate::new(52.1, -1.01)), "north");
+
+        let mut square = GeonPlace::default();
+        square.location = Some(origin);
+        let mut council = GeonPlace::default();
+        council.place = "Council House".to_string();
+        council.location = Some(east);
+        assert_eq!(square.adjacency_to(&council).as_deref(), Some("Council House (210m east)"));
+        council.location = Some(Coordinate::new(52.02, -1.0));
+        assert_eq!(square.adjacency_to(&council).as_deref(), Some("Council House (2.2km north)"));
+        council.location = None;
+        assert_eq!(square.adjacency_to(&council), None);
+    }
+
+    #[test]
+    fn test_midpoint_and_center() {
+        let close = |a: &Coordinate, lat: f64, lon: f64| (a.lat - lat).abs() < 1e-9 && (a.lon - lon).abs() < 1e-9;
+        let mid = Coordinate::new(0.0, 179.0).midpoint(&Coordinate::new(0.0, -179.0));
+        assert!(close(&mid, 0.0, -180.0) || close(&mid, 0.0, 180.0), "{}", mid);
+        let over_pole = Coordinate::new(80.0, 0.0).midpoint(&Coordinate::new(80.0, 180.0));
+        assert!((over_pole.lat - 90.0).abs() < 1e-9);
+        let equator = Coordinate::new(0.0, 10.0).midpoint(&Coordinate::new(0.0, 20.0));
+        assert!(close(&equator, 0.0, 15.0));
+
+        let fiji = Extent { north: -15.0, south: -21.0, east: -178.0, west: 177.0 };
+        assert!(close(&fiji.center(), -18.0, 179.5));
+        let across = Extent { north: 1.0, south: -1.0, east: -170.0, west: 170.0 };
+        assert!(close(&across.center(), 0.0, 180.0));
+        let plain = Extent { north: 53.0, south: 52.0, east: -1.0, west: -2.0 };
+        assert!(close(&plain.center(), 52.5, -1.5));
+    }
+
+    #[test]
+    fn test_geometry_fingerprint() {
+        let text = r#"
+PLACE: Square
+LOCATION: 52.9534, -1.1502
+BOUNDARY:
+  - 52.9530, -1.1510
+  - 52.9530, -1.1490
+  - 52.9540, -1.1490
+  - 52.9540, -1.1510
+EXPERIENCE:
+  noise: moderate
+"#;
+        let place = parse(text);
+        let shape = geometry::fingerprint(&place);
+        let attributes = geometry::attribute_fingerprint(&place);
+
+        // Same ring, reversed, rotated and closed, with float noise
+        let mut redrawn = place.clone();
+        redrawn.boundary.reverse();
+        redrawn.boundary.rotate_left(2);
+        redrawn.boundary.push(redrawn.boundary[0].clone());
+        redrawn.location.as_mut().unwrap().lat += 1e-10;
+        assert_eq!(geometry::fingerprint(&redrawn), shape);
+        assert_eq!(geometry::attribute_fingerprint(&redrawn), attributes);
+
+        let mut described = place.clone();
+        described.experience.insert("noise".to_string(), "loud".to_string());
+        assert_eq!(geometry::fingerprint(&described), shape);
+        assert_ne!(geometry::attribute_fingerprint(&described), attributes);
+
+        let mut moved = place.clone();
+        moved.boundary[2].lat += 0.0001;
+        assert_ne!(geometry::fingerprint(&moved), shape);
+        assert_eq!(geometry::attribute_fingerprint(&moved), attributes);
+
+        let mut street = GeonPlace::default();
+        street.path = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.001), Coordinate::new(0.001, 0.001)];
+        let forward = geometry::fingerprint(&street);
+        street.path.reverse();
+        assert_eq!(geometry::fingerprint(&street), forward);
+        assert_ne!(forward, geometry::fingerprint(&GeonPlace::default()));
+    }
+
+    #[test]
+    fn test_format_text() {
+        let messy = "PLACE:   Old Market Square   \n\nTYPE: public_space\nEXPERIENCE:\n  noise:moderate\n  opening:  09:00-17:00\n# Long Row is the north side\nADJACENCIES:\n  -   Long Row (north)\nCONTAINS:\n  - PLACE: Fountain\n      SOURCE: https://example.org/fountain\nPLACE: Council House\n";
+        let formatted = generator::format(messy).unwrap();
+        assert_eq!(
+            formatted,
+            "PLACE: Old Market Square\nTYPE: public_space\nEXPERIENCE:\n  noise:moderate\n  opening: 09:00-17:00\n# Long Row is the north side\nADJACENCIES:\n  - Long Row (north)\nCONTAINS:\n  - PLACE: Fountain\n    SOURCE: https://example.org/fountain\n\nPLACE: Council House\n"
+        );
+        assert_eq!(generator::format(&formatted).unwrap(), formatted);
+
+        let fixtures = [include_str!("../benches/appendix_a.geon"), include_str!("../../geon-vscode/test.geon"), messy];
+        for text in fixtures {
+            assert_eq!(parser::parse_many(&generator::format(text).unwrap()), parser::parse_many(text));
+        }
+
+        // Indented four spaces, the parser skips EXPERIENCE's entries, so
+        // re-indenting them would add a section
+        let wide = "PLACE: Square\nEXPERIENCE:\n    noise: moderate\n";
+        assert!(parse(wide).experience.is_empty());
+        let err = generator::format(wide).unwrap_err().to_string();
+        assert!(err.contains("line 3 is indented 4 spaces"), "{}", err);
+        let odd = "PLACE: Square\nTYPE: park\n   PURPOSE: play\nCHARACTER:\n - green\n";
+        assert!(generator::format(odd).unwrap_err().to_string().contains("line 5"));
+        // Lines the parser skips either way are re-indented freely
+        let skipped = "PLACE: Square\nLOCATION: 1.0, 2.0\n     note: ignored\n";
+        assert_eq!(generator::format(skipped).unwrap(), "PLACE: Square\nLOCATION: 1.0, 2.0\n  note: ignored\n");
+    }
+
+    #[test]
+    fn test_street_path() {
+        let text = r#"
+PLACE: Long Row
+TYPE: street
+LOCATION: 52.9535, -1.1500
+PATH:
+  - 52.9534, -1.1510
+  - 52.9535, -1.1500
+  - 52.9537, -1.1490
+"#;
+        let street = parse(text);
+        assert_eq!(street.path.len(), 3);
+        assert!(street.boundary.is_empty());
+        assert!(generate(&street).contains("PATH:\n  - 52.9534, -1.151\n"));
+        assert_eq!(parse(&generate(&street)), street);
+
+        let feature = to_geojson(&street);
+        assert_eq!(feature["geometry"]["type"], "LineString");
+        assert_eq!(feature["geometry"]["coordinates"][0], serde_json::json!([-1.151, 52.9534]));
+        let back = from_geojson(feature).remove(0);
+        assert_eq!(back.path, street.path);
+        assert_eq!(back.location, street.location);
+    }
+
+    #[test]
+    fn test_round_and_snap_coords() {
+        let text = r#"
+PLACE: Square
+LOCATION: 52.953421987, -1.150123456
+BOUNDARY:
+  - 52.9534211, -1.1501231
+  - 52.9534214, -1.1501234
+  - 52.9536, -1.1503
+  - 52.9532, -1.1505
+CONTAINS:
+  - PLACE: Fountain
+    LOCATION: 52.95341119, -1.15012345
+"#;
+        let mut place = parse(text);
+        place.extent = Some(Extent { north: 52.95361234, south: 52.95321234, east: -1.15012345, west: -1.15051234 });
+        geometry::round_coords(&mut place, 5);
+        assert_eq!(place.location, Some(Coordinate::new(52.95342, -1.15012)));
+        assert_eq!(place.boundary.len(), 3);
+        assert_eq!(place.contains[0].location, Some(Coordinate::new(52.95341, -1.15012)));
+        assert_eq!(place.extent.as_ref().unwrap().west, -1.15051);
+        assert!(generate(&place).contains("LOCATION: 52.95342, -1.15012"));
+
+        let mut snapped = parse(text);
+        assert!(geometry::snap_to_grid(&mut snapped, f64::NAN).is_err());
+        assert!(geometry::snap_to_grid(&mut snapped, 0.0).is_err());
+        assert_eq!(snapped, parse(text));
+        geometry::snap_to_grid(&mut snapped, 10.0).unwrap();
+        let at = snapped.location.unwrap();
+        assert!(geometry::haversine_m(&at, parse(text).location.as_ref().unwrap()) <= 10.0);
+        let fountain = snapped.contains[0].location.clone().unwrap();
+        // Points in the same 10 m cell land on the same grid point
+        assert_eq!(fountain, at);
+    }
+
+    #[test]
+    fn test_to_kml_folders() {
+        let text = r#"
+PLACE: Parks & Gardens
+PURPOSE: recreation
+CONTAINS:
+  - PLACE: Rose Garden
+    LOCATION: 52.9, -1.1
+"#;
+        let kml = converter::to_kml(&[parse(text)]);
+        assert!(kml.contains("<Folder>\n    <name>Parks &amp; Gardens</name>"));
+        assert!(kml.contains("<description>Purpose: recreation</description>"));
+        assert!(kml.contains("<Point><coordinates>-1.1,52.9</coordinates></Point>"));
+        assert_eq!(kml.matches("<Placemark>").count(), 2);
+    }
+
+    #[test]
+    fn test_to_gpx_waypoints() {
+        let text = "PLACE: Trig Point\nTYPE: landmark\nLOCATION: 53.1, -1.8\nELEVATION: 100 ft\nCONTAINS:\n  - PLACE: Cairn\n    LOCATION: 53.2, -1.9";
+        let gpx = converter::to_gpx(&[parse(text)]);
+        assert!(gpx.contains("<wpt lat=\"53.1\" lon=\"-1.8\">"));
+        assert!(gpx.contains("<ele>30.48</ele>"));
+        assert!(gpx.contains("<name>Cairn</name>"));
+        assert_eq!(gpx.matches("<wpt ").count(), 2);
+    }
+
+    #[test]
+    fn test_to_jsonld() {
+        let text = "PLACE: Arboretum\nID: osm:way/9\nTYPE: public_space\nLOCATION: 52.96, -1.16\nCONTAINS:\n  - PLACE: Bandstand\n    LOCATION: 52.961, -1.161";
+        let mut place = parse(text);
+        place.source = vec!["https://www.wikidata.org/wiki/Q4785".to_string(), "survey".to_string()];
+        let doc = converter::to_jsonld(&[place]);
+        let arboretum = &doc["@graph"][0];
+        assert_eq!(doc["@context"], "https://schema.org");
+        assert_eq!(arboretum["@id"], "osm:way/9");
+        assert_eq!(arboretum["additionalType"], "public_space");
+        assert_eq!(arboretum["geo"]["latitude"], 52.96);
+        assert_eq!(arboretum["sameAs"], serde_json::json!(["https://www.wikidata.org/wiki/Q4785"]));
+        assert_eq!(arboretum["containsPlace"][0]["name"], "Bandstand");
+    }
+
+    #[test]
+    fn test_wkb_round_trip() {
+        let mut place = GeonPlace::default();
+        place.location = Some(Coordinate::new(52.95, -1.15));
+        let wkb = geometry::to_wkb(&place).unwrap();
+        assert_eq!(wkb.len(), 21);
+        assert_eq!(geometry::from_wkb(&wkb).unwrap(), geometry::WktGeometry::Point(Coordinate::new(52.95, -1.15)));
+
+        // Big-endian POINT Z (ISO type 1001)
+        let mut be = vec![0u8];
+        be.extend(1001u32.to_be_bytes());
+        for v in [1.0f64, 2.0, 3.0] {
+            be.extend(v.to_be_bytes());
+        }
+        assert_eq!(geometry::from_wkb(&be).unwrap(), geometry::WktGeometry::Point(Coordinate::new(2.0, 1.0)));
+
+        place.path = vec![Coordinate::new(52.95, -1.15), Coordinate::new(52.96, -1.14)];
+        place.boundary = vec![Coordinate::new(52.9, -1.2), Coordinate::new(53.0, -1.2), Coordinate::new(53.0, -1.1)];
+        let mut back = GeonPlace::default();
+        geometry::from_wkb(&geometry::to_wkb(&place).unwrap()).unwrap().apply_to(&mut back);
+        assert_eq!(back.path, place.path);
+        assert_eq!(back.boundary[..3], place.boundary[..]);
+    }
 }
@@ -0,0 +1,201 @@
+//! Three-way merge of concurrent edits to a place, e.g. two branches of a
+//! `.geon` file in git.
+
+use crate::collection::{field_changes, pair_children, FieldChange};
+use crate::models::GeonPlace;
+use crate::patch::{is_list, op_for, split_path, GeonPatch};
+use std::fmt;
+
+/// A field both sides changed differently. Values are as in
+/// [`FieldChange`]; a side is `None` where it removed the field, or removed
+/// the nested place the field belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub field: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "(removed)".to_string());
+        write!(f, "{}: ours {}, theirs {} (was {})", self.field, show(&self.ours), show(&self.theirs), show(&self.base))
+    }
+}
+
+/// The conflicts found by [`merge3`], with the merge taking our side of
+/// each, for resolving by hand.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{} conflicting field(s): {}", .conflicts.len(), .conflicts.iter().map(|c| c.field.as_str()).collect::<Vec<_>>().join(", "))]
+pub struct MergeConflicts {
+    pub conflicts: Vec<Conflict>,
+    pub merged: Box<GeonPlace>,
+}
+
+// Name of the nested place a CONTAINS entry holds
+fn child_name(json: &str) -> Option<String> {
+    serde_json::from_str::<GeonPlace>(json).ok().map(|p| p.place.to_lowercase())
+}
+
+// A field path into `base` as the same path into `ours`, whose nested
+// places may have moved; `None` if `ours` removed one on the way
+fn rebase(field: &str, base: &GeonPlace, ours: &GeonPlace) -> Option<String> {
+    let (indices, leaf) = split_path(field);
+    let (mut from, mut to) = (base, ours);
+    let mut path = String::new();
+    for i in indices {
+        let j = pair_children(&from.contains, &to.contains).get(i).copied().flatten()?;
+        path.push_str(&format!("CONTAINS[{}].", j));
+        (from, to) = (&from.contains[i], &to.contains[j]);
+    }
+    Some(path + leaf)
+}
+
+fn conflicts(ours: &FieldChange, theirs: &FieldChange) -> bool {
+    if ours.field != theirs.field {
+        return false;
+    }
+    if !is_list(&ours.field) {
+        return ours.after != theirs.after;
+    }
+    // List entries come and go independently, except that two nested
+    // places added under one name must agree
+    ours.field.ends_with("CONTAINS")
+        && ours.after.is_some()
+        && ours.after != theirs.after
+        && ours.after.as_deref().and_then(child_name) == theirs.after.as_deref().and_then(child_name)
+}
+
+/// Merge `ours` and `theirs`, both edited from `base`, field by field.
+///
+/// Changes to different fields, map keys or list entries combine; the same
+/// change made on both sides is applied once. A field, map key or nested
+/// place changed differently on each side, or edited on one side inside a
+/// nested place the other removed, is a conflict.
+pub fn merge3(base: &GeonPlace, ours: &GeonPlace, theirs: &GeonPlace) -> Result<GeonPlace, MergeConflicts> {
+    let our_changes = field_changes(base, ours);
+    let their_changes = field_changes(base, theirs);
+
+    let mut merged = ours.clone();
+    let mut found = Vec::new();
+    for change in their_changes {
+        if our_changes.contains(&change) {
+            continue;
+        }
+        if let Some(clash) = our_changes.iter().find(|ours| conflicts(ours, &change)) {
+            let ours = if clash.field == change.field { clash.after.clone() } else { None };
+            found.push(Conflict { field: change.field.clone(), base: change.before.clone(), ours, theirs: change.after });
+            continue;
+        }
+        // A nested place we removed, or a list entry we changed, can't take
+        // their edit
+        let rebased = rebase(&change.field, base, ours).map(|field| FieldChange { field, ..change.clone() });
+        let applied = rebased.is_some_and(|c| GeonPatch { place: String::new(), ops: vec![op_for(c)] }.apply(&mut merged).is_ok());
+        if !applied {
+            found.push(Conflict { field: change.field.clone(), base: change.before.clone(), ours: None, theirs: change.after });
+        }
+    }
+
+    if found.is_empty() { Ok(merged) } else { Err(MergeConflicts { conflicts: found, merged: Box::new(merged) }) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge3() {
+        let mut base = GeonPlace::default();
+        base.place = "Old Market Square".to_string();
+        base.type_ = "plaza".to_string();
+        base.purpose = strings(&["market", "parking"]);
+        base.experience.insert("noise".to_string(), "moderate".to_string());
+        let mut fountain = GeonPlace::default();
+        fountain.place = "Fountain".to_string();
+        base.contains = vec![fountain];
+
+        let mut ours = base.clone();
+        ours.type_ = "square".to_string();
+        ours.purpose = strings(&["market", "events"]);
+        ours.experience.insert("crowding".to_string(), "busy".to_string());
+        let mut theirs = base.clone();
+        theirs.purpose = strings(&["market", "parking", "events", "protest"]);
+        theirs.experience.insert("noise".to_string(), "loud".to_string());
+        theirs.contains[0].type_ = "landmark".to_string();
+
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.type_, "square");
+        assert_eq!(merged.purpose, strings(&["market", "events", "protest"]));
+        assert_eq!(merged.experience["noise"], "loud");
+        assert_eq!(merged.experience["crowding"], "busy");
+        assert_eq!(merged.contains[0].type_, "landmark");
+
+        // Both retype the square, and we remove the fountain they edited
+        ours.type_ = "public_space".to_string();
+        ours.contains.clear();
+        theirs.type_ = "market".to_string();
+        let err = merge3(&base, &ours, &theirs).unwrap_err();
+        let fields: Vec<&str> = err.conflicts.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["TYPE", "CONTAINS[0].TYPE"]);
+        assert_eq!(err.conflicts[0].to_string(), "TYPE: ours public_space, theirs market (was plaza)");
+        assert_eq!(err.merged.type_, "public_space");
+        assert_eq!(err.merged.experience["noise"], "loud");
+    }
+
+    #[test]
+    fn test_merge3_moved_children() {
+        let mut base = GeonPlace::default();
+        base.place = "Arcade".to_string();
+        for name in ["Kiosk", "Stall", "Stall"] {
+            let mut child = GeonPlace::default();
+            child.place = name.to_string();
+            base.contains.push(child);
+        }
+
+        // We drop the kiosk, so their second stall is our first
+        let mut ours = base.clone();
+        ours.contains.remove(0);
+        let mut theirs = base.clone();
+        theirs.contains[2].type_ = "market_stall".to_string();
+
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.contains.len(), 2);
+        assert_eq!((merged.contains[0].type_.as_str(), merged.contains[1].type_.as_str()), ("", "market_stall"));
+    }
+
+    #[test]
+    fn test_merge3_multiline_values() {
+        let mut base = GeonPlace::default();
+        base.place = "Arboretum".to_string();
+        base.character = strings(&["Victorian", "  walled  "]);
+        base.temporal.insert("opening".to_string(), "dawn to dusk".to_string());
+        let mut glasshouse = GeonPlace::default();
+        glasshouse.place = "Glasshouse".to_string();
+        base.contains = vec![glasshouse];
+
+        let mut ours = base.clone();
+        ours.temporal.insert("opening".to_string(), "summer: 8am\nwinter: 9am".to_string());
+        ours.character.push("tree collection\n(est. 1852)".to_string());
+        let mut theirs = base.clone();
+        theirs.temporal.insert("notes".to_string(), "  closed  on\tfestival days ".to_string());
+        theirs.character.retain(|c| c != "  walled  ");
+        theirs.contains[0].visual.insert("glazing".to_string(), "".to_string());
+
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.temporal["opening"], "summer: 8am\nwinter: 9am");
+        assert_eq!(merged.temporal["notes"], "  closed  on\tfestival days ");
+        assert_eq!(merged.character, strings(&["Victorian", "tree collection\n(est. 1852)"]));
+        assert_eq!(merged.contains[0].visual["glazing"], "");
+
+        // Edits to one value that differ only in whitespace still clash
+        theirs.temporal.insert("opening".to_string(), "summer: 8am\nwinter:  9am".to_string());
+        let err = merge3(&base, &ours, &theirs).unwrap_err();
+        assert_eq!(err.conflicts.len(), 1);
+        assert_eq!(err.conflicts[0].theirs.as_deref(), Some("summer: 8am\nwinter:  9am"));
+    }
+}
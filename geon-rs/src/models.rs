@@ -1,6 +1,10 @@
+#[cfg(feature = "std")]
+use crate::geometry::{self, Hull};
+#[cfg(feature = "std")]
+use crate::parser::GeonError;
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::fmt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fmt;
 
 /// A WGS84 coordinate pair (latitude, longitude).
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,6 +18,27 @@ impl Coordinate {
         Self { lat, lon }
     }
 
+    /// See [`geometry::geohash`].
+    #[cfg(feature = "std")]
+    pub fn geohash(&self, precision: usize) -> String {
+        geometry::geohash(self, precision)
+    }
+
+    /// The point halfway along the great circle to `other`, so the midpoint
+    /// of two points either side of the antimeridian stays near it and one
+    /// between points on either side of a pole lies towards the pole. For
+    /// antipodal points, where every great circle qualifies, the result is
+    /// one of them.
+    #[cfg(feature = "std")]
+    pub fn midpoint(&self, other: &Coordinate) -> Coordinate {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlon = (other.lon - self.lon).to_radians();
+        let (bx, by) = (lat2.cos() * dlon.cos(), lat2.cos() * dlon.sin());
+        let lat = (lat1.sin() + lat2.sin()).atan2(((lat1.cos() + bx).powi(2) + by * by).sqrt());
+        let lon = self.lon.to_radians() + by.atan2(lat1.cos() + bx);
+        Coordinate::new(lat.to_degrees(), wrap_lon(lon.to_degrees()))
+    }
+
     pub fn to_geojson_position(&self) -> Vec<f64> {
         vec![self.lon, self.lat]
     }
@@ -30,6 +55,53 @@ impl Coordinate {
     }
 }
 
+// Longitude in [-180, 180), as `rem_euclid` would give (which needs std)
+fn wrap_lon(lon: f64) -> f64 {
+    let r = (lon + 180.0) % 360.0;
+    if r < 0.0 { r + 180.0 } else { r - 180.0 }
+}
+
+/// `(x, y)` order, i.e. `(lon, lat)`, as used by `geo_types` and most GIS libraries.
+impl From<&Coordinate> for (f64, f64) {
+    fn from(c: &Coordinate) -> Self {
+        (c.lon, c.lat)
+    }
+}
+
+impl From<(f64, f64)> for Coordinate {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self { lat: y, lon: x }
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<&Coordinate> for geo_types::Coord<f64> {
+    fn from(c: &Coordinate) -> Self {
+        geo_types::Coord { x: c.lon, y: c.lat }
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<geo_types::Coord<f64>> for Coordinate {
+    fn from(c: geo_types::Coord<f64>) -> Self {
+        Self { lat: c.y, lon: c.x }
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<&Coordinate> for geo_types::Point<f64> {
+    fn from(c: &Coordinate) -> Self {
+        geo_types::Point::new(c.lon, c.lat)
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<geo_types::Point<f64>> for Coordinate {
+    fn from(p: geo_types::Point<f64>) -> Self {
+        Self { lat: p.y(), lon: p.x() }
+    }
+}
+
 impl fmt::Display for Coordinate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}, {}", self.lat, self.lon)
@@ -45,6 +117,50 @@ pub struct Extent {
     pub west: f64,
 }
 
+impl Extent {
+    /// Lower-left `(min_x, min_y)` and upper-right `(max_x, max_y)` corners.
+    pub fn to_min_max(&self) -> ((f64, f64), (f64, f64)) {
+        ((self.west, self.south), (self.east, self.north))
+    }
+
+    pub fn from_min_max(min: (f64, f64), max: (f64, f64)) -> Self {
+        Self { north: max.1, south: min.1, east: max.0, west: min.0 }
+    }
+
+    /// The middle of the box. An extent whose `west` is greater than its
+    /// `east` crosses the antimeridian, and is centred across it rather
+    /// than around the other side of the world.
+    pub fn center(&self) -> Coordinate {
+        let mut width = self.east - self.west;
+        if width < 0.0 {
+            width += 360.0;
+        }
+        let lon = self.west + width / 2.0;
+        Coordinate::new((self.north + self.south) / 2.0, if lon.abs() > 180.0 { wrap_lon(lon) } else { lon })
+    }
+}
+
+/// Fails for an extent that crosses the antimeridian (`west` greater than
+/// `east`), which a `Rect` can't hold.
+#[cfg(feature = "geo")]
+impl TryFrom<&Extent> for geo_types::Rect<f64> {
+    type Error = crate::parser::GeonError;
+
+    fn try_from(e: &Extent) -> Result<Self, Self::Error> {
+        if e.west > e.east {
+            return Err(crate::parser::GeonError::InvalidStructure(alloc::format!("extent {} crosses the antimeridian", e)));
+        }
+        Ok(geo_types::Rect::new(geo_types::Coord { x: e.west, y: e.south }, geo_types::Coord { x: e.east, y: e.north }))
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<geo_types::Rect<f64>> for Extent {
+    fn from(r: geo_types::Rect<f64>) -> Self {
+        Self { north: r.max().y, south: r.min().y, east: r.max().x, west: r.min().x }
+    }
+}
+
 impl fmt::Display for Extent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}, {}, {}, {}", self.north, self.south, self.east, self.west)
@@ -67,6 +183,9 @@ pub struct GeonPlace {
     pub location: Option<Coordinate>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub boundary: Vec<Coordinate>,
+    /// An open line such as a street centreline or route, first to last.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Coordinate>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extent: Option<Extent>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -77,16 +196,16 @@ pub struct GeonPlace {
     // --- Semantic (2.2.3) ---
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub purpose: Vec<String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub experience: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub experience: BTreeMap<String, String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub character: Vec<String>,
 
     // --- Relational (2.2.4) ---
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub adjacencies: Vec<String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub connectivity: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub connectivity: BTreeMap<String, String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub contains: Vec<GeonPlace>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -101,44 +220,250 @@ pub struct GeonPlace {
     pub viewsheds: serde_json::Value,
 
     // --- Temporal (2.2.5) ---
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub temporal: HashMap<String, String>, // Python had Any, but usage suggests str/str mostly.
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub lifespan: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub temporal: BTreeMap<String, String>, // Python had Any, but usage suggests str/str mostly.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lifespan: BTreeMap<String, String>,
 
     // --- Data provenance (2.2.6) ---
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub source: Vec<String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub confidence: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub confidence: BTreeMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated: Option<String>,
 
     // --- Extended / domain-specific (2.3) ---
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub built_form: HashMap<String, String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub ecology: HashMap<String, String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub infrastructure: HashMap<String, String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub demographics: HashMap<String, String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub economy: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub built_form: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ecology: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub infrastructure: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub demographics: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub economy: BTreeMap<String, String>,
 
     // --- Extensions (section 9) ---
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub visual: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub visual: BTreeMap<String, String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub history: Vec<HashMap<String, String>>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub vertical_profile: HashMap<String, String>,
+    pub history: Vec<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vertical_profile: BTreeMap<String, String>,
 
     // --- Catch-all for unknown / user-defined fields ---
     #[serde(flatten)]
-    pub extra: HashMap<String, serde_json::Value>,
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl GeonPlace {
+    /// The key-value section named by a GEON key such as `EXPERIENCE`.
+    pub(crate) fn section_mut(&mut self, key: &str) -> Option<&mut BTreeMap<String, String>> {
+        Some(match key {
+            "EXPERIENCE" => &mut self.experience,
+            "CONNECTIVITY" => &mut self.connectivity,
+            "TEMPORAL" => &mut self.temporal,
+            "LIFESPAN" => &mut self.lifespan,
+            "CONFIDENCE" => &mut self.confidence,
+            "BUILT_FORM" => &mut self.built_form,
+            "ECOLOGY" => &mut self.ecology,
+            "INFRASTRUCTURE" => &mut self.infrastructure,
+            "DEMOGRAPHICS" => &mut self.demographics,
+            "ECONOMY" => &mut self.economy,
+            "VISUAL" => &mut self.visual,
+            "VERTICAL_PROFILE" => &mut self.vertical_profile,
+            _ => return None,
+        })
+    }
+
+    /// The list-of-text section named by a GEON key such as `PURPOSE`.
+    pub(crate) fn list_mut(&mut self, key: &str) -> Option<&mut Vec<String>> {
+        Some(match key {
+            "PURPOSE" => &mut self.purpose,
+            "CHARACTER" => &mut self.character,
+            "ADJACENCIES" => &mut self.adjacencies,
+            "SOURCE" => &mut self.source,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "geo")]
+impl GeonPlace {
+    /// BOUNDARY as a polygon without holes, its ring closed as `geo_types`
+    /// closes it; `None` for fewer than three points.
+    pub fn boundary_polygon(&self) -> Option<geo_types::Polygon<f64>> {
+        if self.boundary.len() < 3 {
+            return None;
+        }
+        let ring: Vec<geo_types::Coord<f64>> = self.boundary.iter().map(Into::into).collect();
+        Some(geo_types::Polygon::new(ring.into(), Vec::new()))
+    }
+
+    /// Set BOUNDARY to the exterior ring of `polygon`, without the closing
+    /// point GEON leaves implicit. Holes have no place in BOUNDARY and are
+    /// dropped.
+    pub fn set_boundary_polygon(&mut self, polygon: &geo_types::Polygon<f64>) {
+        let ring = &polygon.exterior().0;
+        let open = match (ring.first(), ring.last()) {
+            (Some(first), Some(last)) if ring.len() > 1 && first == last => &ring[..ring.len() - 1],
+            _ => &ring[..],
+        };
+        self.boundary = open.iter().map(|&c| c.into()).collect();
+    }
+}
+
+#[cfg(feature = "std")]
+impl GeonPlace {
+    /// Set LOCATION from a WKT `POINT`, PATH from a `LINESTRING`, or
+    /// BOUNDARY from a `POLYGON` / `MULTIPOLYGON`, or each from the members
+    /// of a `GEOMETRYCOLLECTION` (see [`geometry::from_wkt`](crate::geometry::from_wkt)).
+    pub fn set_geometry_from_wkt(&mut self, wkt: &str) -> Result<(), GeonError> {
+        geometry::from_wkt(wkt)?.apply_to(self);
+        Ok(())
+    }
+
+    /// Store the geohash of LOCATION in `extra` as `geohash`, so JSON and
+    /// GeoJSON exports carry it, and return it. Places without a LOCATION
+    /// are left alone; for an ID, use e.g. `format!("geohash:{}", hash)`.
+    pub fn set_geohash(&mut self, precision: usize) -> Option<String> {
+        let hash = self.location.as_ref()?.geohash(precision);
+        self.extra.insert("geohash".to_string(), serde_json::Value::String(hash.clone()));
+        Some(hash)
+    }
+
+    /// Distance in metres to `other`, between the nearest parts of their
+    /// shapes (zero if they touch or overlap): each place's BOUNDARY, else
+    /// its PATH, else its LOCATION. Between two LOCATIONs it is great-circle,
+    /// or on the WGS84 ellipsoid with the `geodesic` feature. `None` if
+    /// either has no geometry.
+    pub fn distance_to(&self, other: &GeonPlace) -> Option<f64> {
+        fn shape(p: &GeonPlace) -> Option<(&[Coordinate], bool)> {
+            if p.boundary.len() >= 3 {
+                Some((&p.boundary, true))
+            } else if p.path.len() >= 2 {
+                Some((&p.path, false))
+            } else {
+                p.location.as_ref().map(|c| (std::slice::from_ref(c), false))
+            }
+        }
+        match (shape(self)?, shape(other)?) {
+            (([a], _), ([b], _)) => Some(geometry::point_distance_m(a, b)),
+            (a, b) => geometry::outline_distance_m(a, b),
+        }
+    }
+
+    /// An ADJACENCIES entry describing `other` from here, such as
+    /// `"Council House (200m east)"`: [`distance_to`](Self::distance_to)
+    /// to the nearest 10 m (or km past 1 km, "immediate" under 10 m) and the
+    /// compass direction between LOCATIONs. `None` unless both have one.
+    pub fn adjacency_to(&self, other: &GeonPlace) -> Option<String> {
+        let direction = geometry::compass_direction(self.location.as_ref()?, other.location.as_ref()?);
+        let metres = self.distance_to(other)?;
+        let distance = if metres < 10.0 {
+            "immediate".to_string()
+        } else if metres < 1000.0 {
+            format!("{}m", (metres / 10.0).round() * 10.0)
+        } else {
+            format!("{:.1}km", metres / 1000.0)
+        };
+        Some(format!("{} ({} {})", other.place, distance, direction))
+    }
+
+    /// Whether this place's BOUNDARY and `other`'s share some area; places
+    /// that only touch along an edge do not overlap. `false` unless both
+    /// have a BOUNDARY.
+    pub fn overlaps(&self, other: &GeonPlace) -> bool {
+        self.overlap_area_m2(other).is_some_and(|area| area > 0.0)
+    }
+
+    /// Area in square metres shared by this place's BOUNDARY and `other`'s
+    /// (see [`geometry::intersection_area_m2`]), for spotting the same
+    /// footprint imported from two sources. `None` unless both have a
+    /// BOUNDARY of at least three points.
+    pub fn overlap_area_m2(&self, other: &GeonPlace) -> Option<f64> {
+        let (a, b) = (&self.boundary, &other.boundary);
+        if a.len() < 3 || b.len() < 3 {
+            return None;
+        }
+        let bounds = |ring: &[Coordinate]| {
+            ring.iter().fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(w, s, e, n), c| {
+                (w.min(c.lon), s.min(c.lat), e.max(c.lon), n.max(c.lat))
+            })
+        };
+        let ((aw, as_, ae, an), (bw, bs, be, bn)) = (bounds(a), bounds(b));
+        if aw > be || bw > ae || as_ > bn || bs > an {
+            return Some(0.0);
+        }
+        Some(geometry::intersection_area_m2(a, b))
+    }
+
+    /// Set EXTENT to the bounding box of BOUNDARY and PATH; failing that, of LOCATION
+    /// and the EXTENTs of nested places (derived the same way where they
+    /// have none); failing that, of a circle at LOCATION covering AREA, or
+    /// of LOCATION alone. Returns `false`, leaving EXTENT alone, when there
+    /// is no geometry to go on.
+    pub fn derive_extent(&mut self) -> bool {
+        match self.derived_extent() {
+            Some(extent) => {
+                self.extent = Some(extent);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn derived_extent(&self) -> Option<Extent> {
+        let bounds = |points: &mut dyn Iterator<Item = (f64, f64)>| {
+            let first = points.next()?;
+            let (w, s, e, n) = points.fold((first.0, first.1, first.0, first.1), |(w, s, e, n), (x, y)| {
+                (w.min(x), s.min(y), e.max(x), n.max(y))
+            });
+            Some(Extent { north: n, south: s, east: e, west: w })
+        };
+        if !self.boundary.is_empty() || !self.path.is_empty() {
+            return bounds(&mut self.boundary.iter().chain(&self.path).map(|c| (c.lon, c.lat)));
+        }
+
+        let children: Vec<Extent> =
+            self.contains.iter().filter_map(|c| c.extent.clone().or_else(|| c.derived_extent())).collect();
+        if !children.is_empty() {
+            let corners = children.iter().flat_map(|e| [(e.west, e.south), (e.east, e.north)]);
+            return bounds(&mut corners.chain(self.location.iter().map(|c| (c.lon, c.lat))));
+        }
+
+        let at = self.location.as_ref()?;
+        let radius_m = self.area.as_deref().and_then(crate::quantity::parse_area).map_or(0.0, |a| (a / std::f64::consts::PI).sqrt());
+        let dlat = radius_m / geometry::METRES_PER_DEGREE;
+        let dlon = dlat / at.lat.to_radians().cos().max(1e-6);
+        Some(Extent { north: at.lat + dlat, south: at.lat - dlat, east: at.lon + dlon, west: at.lon - dlon })
+    }
+
+    /// Set BOUNDARY to a hull around the LOCATIONs and BOUNDARY points of
+    /// every place nested under this one, for districts, campuses and the
+    /// like that have members but no geometry of their own. Returns `false`,
+    /// leaving BOUNDARY alone, when the members span fewer than three points
+    /// not on a line.
+    pub fn derive_boundary_from_children(&mut self, kind: Hull) -> bool {
+        fn collect(place: &GeonPlace, points: &mut Vec<Coordinate>) {
+            for child in &place.contains {
+                points.extend(child.location.iter().chain(&child.boundary).cloned());
+                collect(child, points);
+            }
+        }
+        let mut points = Vec::new();
+        collect(self, &mut points);
+        let ring = geometry::hull(&points, kind);
+        if ring.is_empty() {
+            return false;
+        }
+        self.boundary = ring;
+        true
+    }
 }
 
-fn is_empty_json_value(v: &serde_json::Value) -> bool {
+pub(crate) fn is_empty_json_value(v: &serde_json::Value) -> bool {
     v.is_null() || (v.is_array() && v.as_array().unwrap().is_empty()) || (v.is_object() && v.as_object().unwrap().is_empty())
 }
@@ -1,7 +1,8 @@
 use crate::models::{Coordinate, Extent, GeonPlace};
+use alloc::{collections::BTreeMap, string::{String, ToString}, vec, vec::Vec};
+use core::num::ParseFloatError;
+use serde_json::Value;
 use thiserror::Error;
-use std::num::ParseFloatError;
-use std::collections::HashMap;
 
 #[derive(Error, Debug)]
 pub enum GeonError {
@@ -9,311 +10,437 @@ pub enum GeonError {
     ParseFloat(#[from] ParseFloatError),
     #[error("Invalid structure: {0}")]
     InvalidStructure(String),
+    #[error("Invalid WKT: {0}")]
+    InvalidWkt(String),
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Store error: {0}")]
+    Store(String),
+    #[cfg(feature = "http")]
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
 }
 
 // Low-level helpers
 
-fn indent_level(line: &str) -> usize {
-    line.len() - line.trim_start().len()
+// Byte index of the colon ending the key of a `key: value` line. A key may
+// itself hold colons, as in `addr:street: ...`, so long as each is followed
+// by more of the key; otherwise the key ends at the first colon.
+fn key_end(line: &str) -> Option<usize> {
+    let first = line.find(':')?;
+    let bytes = line.as_bytes();
+    let mut at = first;
+    loop {
+        match bytes.get(at + 1) {
+            None | Some(b' ' | b'\t') => return Some(at),
+            _ => {}
+        }
+        if bytes[..first].iter().any(u8::is_ascii_whitespace) {
+            return Some(first);
+        }
+        match bytes[at + 1..].iter().position(|&b| b == b':' || b.is_ascii_whitespace()) {
+            Some(n) if bytes[at + 1 + n] == b':' => at += 1 + n,
+            _ => return Some(first),
+        }
+    }
 }
 
-fn split_key_value(line: &str) -> Option<(String, String)> {
-    let stripped = line.trim();
-    if let Some(idx) = stripped.find(':') {
-        let key = stripped[..idx].trim().to_string();
-        let value = stripped[idx + 1..].trim().to_string();
-        Some((key, value))
-    } else {
-        None
+// `key: value` of a trimmed line
+pub(crate) fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let at = key_end(line)?;
+    Some((line[..at].trim_end(), line[at + 1..].trim_start()))
+}
+
+pub(crate) fn parse_coordinate(text: &str) -> Option<Coordinate> {
+    let (lat, lon) = text.split_once(',')?;
+    if lon.contains(',') {
+        return None;
     }
+    Some(Coordinate { lat: lat.trim().parse().ok()?, lon: lon.trim().parse().ok()? })
 }
 
-fn parse_coordinate(text: &str) -> Option<Coordinate> {
-    let parts: Vec<&str> = text.split(',').map(|s| s.trim()).collect();
-    if parts.len() == 2 {
-        if let (Ok(lat), Ok(lon)) = (parts[0].parse::<f64>(), parts[1].parse::<f64>()) {
-            return Some(Coordinate { lat, lon });
+/// A value of VIEWSHEDS or an unknown key given after its colon: JSON when
+/// it reads as JSON (`42`, `true`, `["a", "b"]`, `"quoted"`), and
+/// otherwise the text itself. [`generate`](crate::generate) writes values
+/// so that they read back the same way.
+pub(crate) fn scalar_value(text: &str) -> Value {
+    match text.as_bytes().first() {
+        Some(b'"' | b'[' | b'{' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n') => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
         }
+        _ => Value::String(text.to_string()),
     }
-    None
 }
 
 // Block parser implementation
-
-#[derive(Debug, Clone)]
-enum Node {
-    Value(String),
-    List(Vec<Node>),
-    Map(HashMap<String, Node>),
-}
-
-// Simplified approach: recursive parsing based on indentation is tricky with iterators.
-// We'll use a traditional procedural approach with an index pointer.
+//
+// Lines are read straight into the place's fields, in one pass with an
+// index pointer. A key's value is either the text after its colon or the
+// block of lines indented below it: a list when the block's first line
+// starts with `- `, a map otherwise. A key given twice takes its last
+// value. Keys the place has no field for are kept in `extra`.
 
 struct Line<'a> {
     indent: usize,
     content: &'a str,
 }
 
-fn tokenize_lines(text: &str) -> Vec<Line> {
-    text.lines()
-        .filter(|line| !line.trim().is_empty())
-        .map(|line| Line {
-            indent: indent_level(line),
-            content: line.trim(),
-        })
-        .collect()
+// The indent and content of a line, trimmed byte by byte while the
+// whitespace is ASCII
+fn trim_line(line: &str) -> (usize, &str) {
+    let space = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\x0B' | b'\x0C' | b'\r');
+    let bytes = line.as_bytes();
+    let start = bytes.iter().position(|b| !space(b)).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !space(b)).map_or(start, |i| i + 1);
+    if bytes.get(start).is_some_and(|b| !b.is_ascii()) || (end > start && !bytes[end - 1].is_ascii()) {
+        // Other Unicode whitespace
+        let rest = line.trim_start();
+        return (line.len() - rest.len(), rest.trim_end());
+    }
+    (start, &line[start..end])
 }
 
-// Parse a block of lines into a HashMap representing the fields
-fn parse_block(lines: &[Line], start: usize, base_indent: usize) -> (HashMap<String, Node>, usize) {
-    let mut result = HashMap::new();
-    let mut i = start;
-
-    while i < lines.len() {
-        let line = &lines[i];
-        
-        if line.indent < base_indent {
-            break;
-        }
-
-        // If indent > base_indent, it belongs to previous key.
-        // If we are here, we expect a key at `base_indent`.
-        if line.indent > base_indent {
-            // Unexpected indentation or continuation of previous?
-            // For this simple parser, assume we process correctly and shouldn't hit this
-            // unless previous key logic failed to consume children.
-            // skips...
-            i += 1;
-            continue;
-        }
-
-        if let Some((key, value)) = split_key_value(line.content) {
-            if !value.is_empty() {
-                // Simple key: value
-                result.insert(key.clone(), Node::Value(value));
-                i += 1;
-            } else {
-                // Key with children
-                let (children, next_i) = collect_children(lines, i + 1, base_indent + 2);
-                result.insert(key.clone(), children);
-                i = next_i;
-            }
-        } else {
-            // Not a key-value line (maybe a list item marker? handled in collect_children)
-            i += 1;
+// The non-blank lines that aren't `#` comments
+fn tokenize_lines(text: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::with_capacity(text.len() / 24);
+    for line in text.lines() {
+        let (indent, content) = trim_line(line);
+        if !content.is_empty() && !content.starts_with('#') {
+            lines.push(Line { indent, content });
         }
     }
+    lines
+}
 
-    (result, i)
+// Index of the first line from `start` indented less than `indent`: the
+// end of a block whose lines are indented at least that much
+fn block_end(lines: &[Line], start: usize, indent: usize) -> usize {
+    lines[start..].iter().position(|l| l.indent < indent).map_or(lines.len(), |n| start + n)
 }
 
-fn collect_children(lines: &[Line], start: usize, child_indent: usize) -> (Node, usize) {
-    if start >= lines.len() {
-        return (Node::List(vec![]), start);
-    } // Should be empty list or map
-    
-    // Check if first child is list item or key-value
-    let first_line = &lines[start];
-    if first_line.indent < child_indent {
-         return (Node::List(vec![]), start); 
+// The text of a `- item` line; a lone `-` is an empty item
+fn item_text(content: &str) -> Option<&str> {
+    match content.strip_prefix('-')? {
+        "" => Some(""),
+        rest if rest.starts_with(' ') => Some(rest.trim_start()),
+        _ => None,
     }
+}
 
-    let is_list = first_line.content.starts_with("- ");
+// A `- ` entry of a list block
+enum Item<'a> {
+    // `- text` with nothing indented below it
+    Scalar(&'a str),
+    // `- PLACE: name`, with the rest of the place's fields below it
+    Place(&'a str, usize, usize),
+    // Any other entry with lines below it, read as a map whose fields
+    // start at the given line and indent; the entry's own text is a
+    // `key: value` of it (or, without a colon, ignored)
+    Map(&'a str, usize, usize),
+}
 
-    if is_list {
-        let mut items = Vec::new();
-        let mut i = start;
-        
-        while i < lines.len() {
-            let line = &lines[i];
-            if line.indent < child_indent {
-                break;
+// The entries of the list block `start..end` indented by `indent`
+fn list_items<'a, 'l>(lines: &'l [Line<'a>], start: usize, end: usize, indent: usize) -> impl Iterator<Item = Item<'a>> + 'l {
+    (start..end).filter(move |&i| lines[i].indent == indent).filter_map(move |i| {
+        let text = item_text(lines[i].content)?;
+        let below = i + 1 < lines.len() && lines[i + 1].indent > indent;
+        Some(match split_key_value(text) {
+            Some(("PLACE", name)) => {
+                Item::Place(name, i + 1, if below { lines[i + 1].indent } else { indent + 2 })
             }
-            if line.indent == child_indent && line.content.starts_with("- ") {
-                let item_text = line.content[2..].trim();
-                
-                // Check if nested PLACE
-                let kv = split_key_value(item_text);
-                if let Some((k, _)) = kv {
-                    if k == "PLACE" {
-                         // Nested GEON block
-                         // Calculate range of this block
-                         let mut j = i + 1;
-                         while j < lines.len() && lines[j].indent > child_indent {
-                             j += 1;
-                         }
-                         
-                         // Create a virtual block for the nested place
-                         // The "PLACE: name" line serves as the header, subsequent lines are children
-                         // But `parse_block` expects keys at `base_indent`.
-                         // We need to fuse the current line text into the block or handle it specially.
-                         // Easier: parse children as map, then insert PLACE key manually.
-                         
-                         // Determine field indent
-                         let field_indent = if j > i + 1 { lines[i+1].indent } else { child_indent + 2 };
-                         
-                         let (mut sub_map, _) = parse_block(lines, i + 1, field_indent);
-                         
-                         // Insert the PLACE/ID/TYPE from the item_text line if present
-                         if let Some((k, v)) = split_key_value(item_text) {
-                            sub_map.insert(k, Node::Value(v));
-                         }
-
-                         items.push(Node::Map(sub_map));
-                         i = j;
-                         continue;
-                    }
-                }
-                
-                // Regular list item (scalar or object?)
-                // If next line is indented further, it's an object/map attached to this item
-                let mut j = i + 1;
-                while j < lines.len() && lines[j].indent > child_indent {
-                    j += 1;
-                }
-                
-                if j > i + 1 {
-                    // Has children
-                    let field_indent = lines[i+1].indent;
-                     let (mut sub_map, _) = parse_block(lines, i + 1, field_indent);
-                     
-                     // If item_text was "Key: Value", insert it. If just "Value", ...
-                     if let Some((k, v)) = split_key_value(item_text) {
-                         sub_map.insert(k, Node::Value(v));
-                     } else {
-                         // Handle scalar with attached map? obscure case for GEON.
-                         // Usually - Value
-                         //           Attr: Val
-                         sub_map.insert("_value".to_string(), Node::Value(item_text.to_string()));
-                     }
-                     items.push(Node::Map(sub_map));
-                     i = j;
-                } else {
-                    // Scalar list item
-                    items.push(Node::Value(item_text.to_string()));
-                    i += 1;
-                }
-                
+            _ if below => Item::Map(text, i + 1, lines[i + 1].indent),
+            _ => Item::Scalar(text),
+        })
+    })
+}
+
+fn parse_extent(text: &str) -> Option<Extent> {
+    let mut parts = text.split(',').map(|s| s.trim().parse::<f64>());
+    let mut next = || parts.next()?.ok();
+    let extent = Extent { north: next()?, south: next()?, east: next()?, west: next()? };
+    parts.next().is_none().then_some(extent)
+}
+
+// Set the field for `key` from the text after its colon
+fn set_value(p: &mut GeonPlace, key: &str, v: &str) {
+    match key {
+        "PLACE" => p.place = v.to_string(),
+        "TYPE" => p.type_ = v.to_string(),
+        "ID" => p.id = Some(v.to_string()),
+        "LOCATION" => p.location = parse_coordinate(v),
+        "EXTENT" => p.extent = parse_extent(v),
+        "ELEVATION" => p.elevation = Some(v.to_string()),
+        "AREA" => p.area = Some(v.to_string()),
+        "PART_OF" => p.part_of = Some(v.to_string()),
+        "UPDATED" => p.updated = Some(v.to_string()),
+        "VIEWSHEDS" => p.viewsheds = scalar_value(v),
+        "BOUNDARY" => p.boundary.clear(),
+        "PATH" => p.path.clear(),
+        "CONTAINS" => p.contains.clear(),
+        "HISTORY" => p.history.clear(),
+        _ => {
+            if let Some(list) = p.list_mut(key) {
+                *list = vec![v.to_string()];
+            } else if let Some(map) = p.section_mut(key) {
+                map.clear();
             } else {
-                // Indent match but no "- ", weird
-                i += 1; 
+                p.extra.insert(key.to_string(), scalar_value(v));
             }
         }
-        (Node::List(items), i)
-    } else {
-        // Map of sub-keys
-        let (map, i) = parse_block(lines, start, child_indent);
-        (Node::Map(map), i)
     }
 }
 
+// Entries of a list block as text; entries with lines below them read as ""
+fn string_items(lines: &[Line], start: usize, end: usize, indent: usize) -> Vec<String> {
+    list_items(lines, start, end, indent)
+        .map(|item| match item {
+            Item::Scalar(text) => text.to_string(),
+            _ => String::new(),
+        })
+        .collect()
+}
 
-// Converter from Node -> GeonPlace
-
-fn node_to_string(n: &Node) -> String {
-    match n {
-        Node::Value(s) => s.clone(),
-        Node::List(_) => "".to_string(),
-        Node::Map(_) => "".to_string(),
+// `key: value` lines of a map block; a key with a block below it reads as ""
+fn map_entries(lines: &[Line], start: usize, indent: usize) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    let mut i = start;
+    while i < lines.len() && lines[i].indent >= indent {
+        if lines[i].indent > indent {
+            i += 1;
+            continue;
+        }
+        match split_key_value(lines[i].content) {
+            Some((key, value)) => {
+                map.insert(key.to_string(), value.to_string());
+                i = if value.is_empty() { block_end(lines, i + 1, indent + 2) } else { i + 1 };
+            }
+            None => i += 1,
+        }
     }
+    map
 }
 
-fn node_to_vec_string(n: &Node) -> Vec<String> {
-    match n {
-        Node::List(list) => list.iter().map(node_to_string).collect(),
-        Node::Value(s) => vec![s.clone()],
-        _ => vec![],
-    }
+// HISTORY entries: `- key: value` with any further keys indented below it
+fn history_entries(lines: &[Line], start: usize, end: usize, indent: usize) -> Vec<BTreeMap<String, String>> {
+    list_items(lines, start, end, indent)
+        .filter_map(|item| {
+            let (text, below) = match item {
+                Item::Scalar(text) => (text, BTreeMap::new()),
+                Item::Place(name, from, field_indent) => {
+                    let mut entry = map_entries(lines, from, field_indent);
+                    entry.insert("PLACE".to_string(), name.to_string());
+                    return Some(entry);
+                }
+                Item::Map(text, from, field_indent) => (text, map_entries(lines, from, field_indent)),
+            };
+            let mut entry = below;
+            if let Some((key, value)) = split_key_value(text) {
+                entry.insert(key.to_string(), value.to_string());
+            }
+            Some(entry).filter(|e| !e.is_empty())
+        })
+        .collect()
 }
 
-fn node_to_map_string(n: &Node) -> HashMap<String, String> {
-    match n {
-        Node::Map(m) => {
-             let mut res = HashMap::new();
-             for (k, v) in m {
-                 res.insert(k.clone(), node_to_string(v));
-             }
-             res
-        },
-        _ => HashMap::new(),
+// VIEWSHEDS or an unknown key's block: a list of text, a map of text, or
+// nothing
+fn block_value(lines: &[Line], start: usize, end: usize, indent: usize, is_list: bool) -> Value {
+    if start == end {
+        Value::Null
+    } else if is_list {
+        Value::Array(string_items(lines, start, end, indent).into_iter().map(Value::String).collect())
+    } else {
+        Value::Object(map_entries(lines, start, indent).into_iter().map(|(k, v)| (k, Value::String(v))).collect())
     }
 }
 
-fn raw_to_place(raw: HashMap<String, Node>) -> GeonPlace {
-    let mut p = GeonPlace::default();
-    
-    if let Some(Node::Value(v)) = raw.get("PLACE") { p.place = v.clone(); }
-    if let Some(Node::Value(v)) = raw.get("TYPE") { p.type_ = v.clone(); }
-    if let Some(Node::Value(v)) = raw.get("ID") { p.id = Some(v.clone()); }
-    
-    if let Some(Node::Value(v)) = raw.get("LOCATION") { 
-        p.location = parse_coordinate(v);
+fn child_place(lines: &[Line], item: Item) -> GeonPlace {
+    let mut child = GeonPlace::default();
+    match item {
+        Item::Scalar(name) => {
+            // A bare `- name` entry
+            child.place = name.to_string();
+        }
+        Item::Place(name, from, field_indent) => {
+            parse_fields(&mut child, lines, from, field_indent);
+            child.place = name.to_string();
+        }
+        Item::Map(text, from, field_indent) => {
+            parse_fields(&mut child, lines, from, field_indent);
+            if let Some((key, value)) = split_key_value(text) {
+                set_value(&mut child, key, value);
+            }
+        }
     }
-    
-    if let Some(Node::Value(v)) = raw.get("EXTENT") {
-        let parts: Vec<&str> = v.split(',').map(|s| s.trim()).collect();
-        if parts.len() == 4 {
-             if let (Ok(n), Ok(s), Ok(e), Ok(w)) = (
-                 parts[0].parse(), parts[1].parse(), parts[2].parse(), parts[3].parse()
-             ) {
-                 p.extent = Some(Extent { north: n, south: s, east: e, west: w });
-             }
+    child
+}
+
+// Set the field for `key` from the block of lines from `start` indented by
+// at least `indent`, returning the index after it
+fn set_block(p: &mut GeonPlace, key: &str, lines: &[Line], start: usize, indent: usize) -> usize {
+    let end = block_end(lines, start, indent);
+    let is_list = start == end || item_text(lines[start].content).is_some();
+    let coordinates = || -> Vec<Coordinate> {
+        list_items(lines, start, end, indent)
+            .filter_map(|item| match item {
+                Item::Scalar(text) => parse_coordinate(text),
+                _ => None,
+            })
+            .collect()
+    };
+    match key {
+        "PLACE" => p.place = String::new(),
+        "TYPE" => p.type_ = String::new(),
+        "ID" => p.id = None,
+        "LOCATION" => p.location = None,
+        "EXTENT" => p.extent = None,
+        "ELEVATION" => p.elevation = None,
+        "AREA" => p.area = None,
+        "PART_OF" => p.part_of = None,
+        "UPDATED" => p.updated = None,
+        "BOUNDARY" => p.boundary = if is_list { coordinates() } else { Vec::new() },
+        "PATH" => p.path = if is_list { coordinates() } else { Vec::new() },
+        "CONTAINS" if is_list => p.contains = list_items(lines, start, end, indent).map(|item| child_place(lines, item)).collect(),
+        "CONTAINS" => p.contains.clear(),
+        "HISTORY" => p.history = if is_list { history_entries(lines, start, end, indent) } else { Vec::new() },
+        "VIEWSHEDS" => p.viewsheds = block_value(lines, start, end, indent, is_list),
+        _ => {
+            if let Some(list) = p.list_mut(key) {
+                *list = if is_list { string_items(lines, start, end, indent) } else { Vec::new() };
+            } else if let Some(map) = p.section_mut(key) {
+                *map = if is_list { BTreeMap::new() } else { map_entries(lines, start, indent) };
+            } else {
+                p.extra.insert(key.to_string(), block_value(lines, start, end, indent, is_list));
+            }
         }
     }
+    end
+}
 
-    if let Some(Node::Value(v)) = raw.get("ELEVATION") { p.elevation = Some(v.clone()); }
-    if let Some(Node::Value(v)) = raw.get("AREA") { p.area = Some(v.clone()); }
-
-    if let Some(n) = raw.get("PURPOSE") { p.purpose = node_to_vec_string(n); }
-    if let Some(n) = raw.get("EXPERIENCE") { p.experience = node_to_map_string(n); }
-    if let Some(n) = raw.get("CHARACTER") { p.character = node_to_vec_string(n); }
-    
-    if let Some(n) = raw.get("ADJACENCIES") { p.adjacencies = node_to_vec_string(n); }
-    if let Some(n) = raw.get("CONNECTIVITY") { p.connectivity = node_to_map_string(n); }
-    
-    if let Some(Node::List(list)) = raw.get("CONTAINS") {
-        for item in list {
-            if let Node::Map(m) = item {
-                p.contains.push(raw_to_place(m.clone()));
-            } else if let Node::Value(s) = item {
-                 // Inline string place? " - PLACE: foo" was parsed above as Map if correct.
-                 // But if just string " - park", treat as bare place
-                 let mut child = GeonPlace::default();
-                 child.place = s.clone();
-                 p.contains.push(child);
+// Read the `key: value` lines from `start` indented by exactly `indent`, and
+// the blocks below them, into `p`; returns the index of the first line
+// indented less
+fn parse_fields(p: &mut GeonPlace, lines: &[Line], start: usize, indent: usize) -> usize {
+    let mut i = start;
+    while i < lines.len() && lines[i].indent >= indent {
+        if lines[i].indent > indent {
+            i += 1;
+            continue;
+        }
+        match split_key_value(lines[i].content) {
+            Some((key, "")) => i = set_block(p, key, lines, i + 1, indent + 2),
+            Some((key, value)) => {
+                set_value(p, key, value);
+                i += 1;
             }
+            None => i += 1,
         }
     }
-    
-    if let Some(Node::Value(v)) = raw.get("PART_OF") { p.part_of = Some(v.clone()); }
-    
-    // Viewsheds, Temporal, Lifespan, Source, Confidence, Updated...
-    // Only implementing a subset for brevity as per plan, but complete enough for basic usage.
-    // For full compliance, repeat pattern above.
-    
-    if let Some(n) = raw.get("TEMPORAL") { p.temporal = node_to_map_string(n); }
-    if let Some(n) = raw.get("LIFESPAN") { p.lifespan = node_to_map_string(n); }
-    
-    // Boundary...
-    if let Some(Node::List(list)) = raw.get("BOUNDARY") {
-        for item in list {
-            if let Node::Value(v) = item {
-                if let Some(c) = parse_coordinate(v) {
-                    p.boundary.push(c);
+    i
+}
+
+pub fn parse(text: &str) -> GeonPlace {
+    let mut place = GeonPlace::default();
+    parse_fields(&mut place, &tokenize_lines(text), 0, 0);
+    place
+}
+
+/// Parse GEON from raw bytes, such as a file or request body: a UTF-8 byte
+/// order mark is skipped and invalid UTF-8 read as U+FFFD. Unlike
+/// [`parse`], input without an unindented `PLACE:` line is an error rather
+/// than an empty place.
+pub fn parse_bytes(bytes: &[u8]) -> Result<GeonPlace, GeonError> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let text = String::from_utf8_lossy(bytes);
+    if !text.lines().any(|line| line.starts_with("PLACE:")) {
+        return Err(GeonError::InvalidStructure("no PLACE line".to_string()));
+    }
+    Ok(parse(&text))
+}
+
+/// The documents of a multi-document GEON text, each starting at an
+/// unindented `PLACE:` line. See [`documents`].
+#[derive(Debug, Clone)]
+pub struct Documents<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Documents<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut seen_place = false;
+        let mut end = 0;
+        for line in self.rest.split_inclusive('\n') {
+            if line.starts_with("PLACE:") {
+                if seen_place {
+                    break;
                 }
+                seen_place = true;
             }
+            end += line.len();
         }
+        let (doc, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(doc).filter(|d| !d.trim().is_empty())
     }
+}
 
-    p
+/// Split a multi-document GEON text into its documents without parsing
+/// them. A new document starts at each unindented `PLACE:` line; anything
+/// before the first belongs to it.
+pub fn documents(text: &str) -> Documents<'_> {
+    Documents { rest: text }
 }
 
-pub fn parse(text: &str) -> GeonPlace {
-    let tokens = tokenize_lines(text);
-    if tokens.is_empty() {
-        return GeonPlace::default();
+/// Parse a multi-document GEON text into one place per document.
+pub fn parse_many(text: &str) -> Vec<GeonPlace> {
+    // Split the lines rather than the text, so each is read once
+    let lines = tokenize_lines(text);
+    if lines.is_empty() {
+        // Nothing but comments is still a (blank) document
+        return documents(text).map(parse).collect();
+    }
+    let starts_place = |l: &Line| l.indent == 0 && l.content.starts_with("PLACE:");
+    let mut places = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        // Lines before the first PLACE belong to the first document
+        let first = lines[start..].iter().position(starts_place).map_or(lines.len(), |n| start + n);
+        let next = lines.get(first + 1..).and_then(|rest| rest.iter().position(starts_place)).map_or(lines.len(), |n| first + 1 + n);
+        let mut place = GeonPlace::default();
+        parse_fields(&mut place, &lines[..next], start, 0);
+        places.push(place);
+        start = next;
+    }
+    places
+}
+
+/// Parse many GEON texts across all available cores, keeping their order.
+#[cfg(feature = "parallel")]
+pub fn parse_many_parallel<S: AsRef<str> + Sync>(texts: &[S]) -> Vec<GeonPlace> {
+    par_map(texts, |text| parse(text.as_ref()))
+}
+
+// Map `f` over `items` on a scoped thread per core, each taking one
+// contiguous chunk, and collect the results in order
+#[cfg(feature = "parallel")]
+pub(crate) fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads < 2 || items.len() < 2 {
+        return items.iter().map(f).collect();
     }
-    let (raw, _) = parse_block(&tokens, 0, 0);
-    raw_to_place(raw)
+    let chunk = items.len().div_ceil(threads);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|part| scope.spawn(move || part.iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
 }
@@ -0,0 +1,395 @@
+//! Field-level patches between versions of a place, for syncing and
+//! reviewing edits without shipping whole documents.
+//!
+//! A patch names the place it applies to, then lists one operation per
+//! line on the field paths used by [`field_changes`]:
+//!
+//! ```text
+//! PATCH: osm:way/1
+//! set TYPE = "square"
+//! add PURPOSE = "events"
+//! remove PURPOSE = "parking"
+//! set EXPERIENCE.noise = "quiet"
+//! remove ELEVATION
+//! set CONTAINS[0].TYPE = "room"
+//! ```
+//!
+//! `add` and `remove` with a value edit list fields (PURPOSE, CHARACTER,
+//! ADJACENCIES, SOURCE, HISTORY, CONTAINS) one entry at a time; `set` and
+//! bare `remove` edit everything else. Values are JSON string literals, so
+//! any text, empty or spanning lines, fits on one line; a field that is
+//! empty, has ` = ` in it or starts or ends with whitespace is quoted the
+//! same way. Nested places are addressed by position, from 0.
+
+use crate::collection::{field_changes, FieldChange, LIST_FIELDS};
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::{parse_coordinate, GeonError};
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    Set { field: String, value: String },
+    /// Append an entry to a list field.
+    Add { field: String, value: String },
+    /// Remove a list entry, or with no value, clear the field.
+    Remove { field: String, value: Option<String> },
+}
+
+impl PatchOp {
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Set { field, .. } | Self::Add { field, .. } | Self::Remove { field, .. } => field,
+        }
+    }
+}
+
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).expect("strings serialise")
+}
+
+// A field as written: bare unless reading it back would go wrong
+fn field_text(field: &str) -> Cow<'_, str> {
+    let bare = field.trim() == field
+        && !field.starts_with('"')
+        && !field.contains(" = ")
+        && !field.contains(char::is_control);
+    if bare { Cow::Borrowed(field) } else { Cow::Owned(quote(field)) }
+}
+
+// A JSON string literal at the start of `text`, decoded, and what follows it
+fn read_string(text: &str) -> Option<(String, &str)> {
+    let mut strings = serde_json::Deserializer::from_str(text).into_iter::<String>();
+    let value = strings.next()?.ok()?;
+    Some((value, &text[strings.byte_offset()..]))
+}
+
+impl fmt::Display for PatchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Set { field, value } => write!(f, "set {} = {}", field_text(field), quote(value)),
+            Self::Add { field, value } => write!(f, "add {} = {}", field_text(field), quote(value)),
+            Self::Remove { field, value: Some(value) } => write!(f, "remove {} = {}", field_text(field), quote(value)),
+            Self::Remove { field, value: None } => write!(f, "remove {}", field_text(field)),
+        }
+    }
+}
+
+/// Edits to one place, by its ID or name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeonPatch {
+    pub place: String,
+    pub ops: Vec<PatchOp>,
+}
+
+impl fmt::Display for GeonPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PATCH: {}", self.place)?;
+        for op in &self.ops {
+            writeln!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, message: impl fmt::Display) -> GeonError {
+    GeonError::InvalidStructure(format!("{}: {}", field, message))
+}
+
+// `CONTAINS[1].CONTAINS[0].TYPE` -> ([1, 0], "TYPE")
+pub(crate) fn split_path(path: &str) -> (Vec<usize>, &str) {
+    let mut indices = Vec::new();
+    let mut rest = path;
+    while let Some((i, after)) = rest
+        .strip_prefix("CONTAINS[")
+        .and_then(|r| r.split_once("]."))
+        .and_then(|(i, after)| Some((i.parse().ok()?, after)))
+    {
+        indices.push(i);
+        rest = after;
+    }
+    (indices, rest)
+}
+
+pub(crate) fn is_list(path: &str) -> bool {
+    LIST_FIELDS.contains(&split_path(path).1)
+}
+
+pub(crate) fn op_for(change: FieldChange) -> PatchOp {
+    match (change.before, change.after) {
+        (None, Some(value)) if is_list(&change.field) => PatchOp::Add { field: change.field, value },
+        (_, Some(value)) => PatchOp::Set { field: change.field, value },
+        (before, None) if is_list(&change.field) => PatchOp::Remove { field: change.field, value: before },
+        (_, None) => PatchOp::Remove { field: change.field, value: None },
+    }
+}
+
+/// The patch that turns `a` into `b`, addressed to `a` by ID or name.
+pub fn diff_places(a: &GeonPlace, b: &GeonPlace) -> GeonPatch {
+    let ops = field_changes(a, b).into_iter().map(op_for).collect();
+    GeonPatch { place: a.id.clone().unwrap_or_else(|| a.place.clone()), ops }
+}
+
+impl GeonPatch {
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Read a patch in the text form above. Blank lines and lines starting
+    /// `#` are skipped; the `PATCH:` line is optional.
+    pub fn parse(text: &str) -> Result<Self, GeonError> {
+        let mut patch = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(place) = line.strip_prefix("PATCH:") {
+                patch.place = place.trim().to_string();
+                continue;
+            }
+            let bad = || GeonError::InvalidStructure(format!("line {}: cannot read '{}'", n + 1, line));
+            let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim_start();
+            let (field, rest) = if rest.starts_with('"') {
+                read_string(rest).ok_or_else(bad)?
+            } else {
+                let end = rest.find(" = ").unwrap_or(rest.len());
+                (rest[..end].trim_end().to_string(), &rest[end..])
+            };
+            let value = match rest.trim() {
+                "" => None,
+                rest => match rest.strip_prefix('=').and_then(|v| read_string(v.trim_start())) {
+                    Some((value, tail)) if tail.trim().is_empty() => Some(value),
+                    _ => return Err(bad()),
+                },
+            };
+            if field.is_empty() {
+                return Err(bad());
+            }
+            patch.ops.push(match (verb, value) {
+                ("set", Some(value)) => PatchOp::Set { field, value },
+                ("add", Some(value)) => PatchOp::Add { field, value },
+                ("remove", value) => PatchOp::Remove { field, value },
+                _ => return Err(bad()),
+            });
+        }
+        Ok(patch)
+    }
+
+    /// Apply every operation in order. Either all apply or, on the first
+    /// that cannot (a nested place or list entry that isn't there, a value
+    /// that doesn't parse), none do and the error says why.
+    pub fn apply(&self, place: &mut GeonPlace) -> Result<(), GeonError> {
+        let mut patched = place.clone();
+        for op in &self.ops {
+            let (indices, leaf) = split_path(op.field());
+            let mut target = &mut patched;
+            for i in indices {
+                target = target.contains.get_mut(i).ok_or_else(|| invalid(op.field(), format!("no nested place {}", i)))?;
+            }
+            apply_op(target, leaf, op)?;
+        }
+        *place = patched;
+        Ok(())
+    }
+}
+
+fn coordinate(field: &str, text: &str) -> Result<Coordinate, GeonError> {
+    parse_coordinate(text).ok_or_else(|| invalid(field, format!("'{}' is not a coordinate", text)))
+}
+
+fn extent(field: &str, text: &str) -> Result<Extent, GeonError> {
+    let parts: Result<Vec<f64>, _> = text.split(',').map(|s| s.trim().parse()).collect();
+    match parts.ok().as_deref() {
+        Some(&[north, south, east, west]) => Ok(Extent { north, south, east, west }),
+        _ => Err(invalid(field, format!("'{}' is not an extent", text))),
+    }
+}
+
+fn json<T: serde::de::DeserializeOwned>(field: &str, text: &str) -> Result<T, GeonError> {
+    serde_json::from_str(text).map_err(|e| invalid(field, e))
+}
+
+fn edit_list<T>(
+    list: &mut Vec<T>,
+    op: &PatchOp,
+    read: impl Fn(&str) -> Result<T, GeonError>,
+    same: impl Fn(&T, &T) -> bool,
+) -> Result<(), GeonError> {
+    match op {
+        PatchOp::Add { value, .. } => list.push(read(value)?),
+        PatchOp::Remove { value: None, .. } => list.clear(),
+        PatchOp::Remove { field, value: Some(value) } => {
+            let entry = read(value)?;
+            let i = list.iter().position(|x| same(x, &entry)).ok_or_else(|| invalid(field, format!("no entry '{}'", value)))?;
+            list.remove(i);
+        }
+        PatchOp::Set { field, .. } => return Err(invalid(field, "list fields take add and remove")),
+    }
+    Ok(())
+}
+
+fn apply_op(place: &mut GeonPlace, leaf: &str, op: &PatchOp) -> Result<(), GeonError> {
+    let field = op.field();
+    let text = |s: &str| Ok(s.to_string());
+
+    match leaf {
+        "PURPOSE" => return edit_list(&mut place.purpose, op, text, PartialEq::eq),
+        "CHARACTER" => return edit_list(&mut place.character, op, text, PartialEq::eq),
+        "ADJACENCIES" => return edit_list(&mut place.adjacencies, op, text, PartialEq::eq),
+        "SOURCE" => return edit_list(&mut place.source, op, text, PartialEq::eq),
+        "HISTORY" => return edit_list(&mut place.history, op, |s| json(field, s), PartialEq::eq),
+        // A nested place is JSON, but can be removed by name alone
+        "CONTAINS" => {
+            let read = |s: &str| {
+                if s.starts_with('{') {
+                    return json(field, s);
+                }
+                let mut child = GeonPlace::default();
+                child.place = s.to_string();
+                Ok(child)
+            };
+            let by_name = matches!(op, PatchOp::Remove { value: Some(v), .. } if !v.starts_with('{'));
+            let same = |a: &GeonPlace, b: &GeonPlace| if by_name { a.place.eq_ignore_ascii_case(&b.place) } else { a == b };
+            return edit_list(&mut place.contains, op, read, same);
+        }
+        _ if leaf.starts_with("CONTAINS[") => return Err(invalid(field, "nested places are addressed by position, e.g. CONTAINS[0]")),
+        _ => {}
+    }
+
+    let value = match op {
+        PatchOp::Set { value, .. } => Some(value.as_str()),
+        PatchOp::Remove { value: None, .. } => None,
+        _ => return Err(invalid(field, "only list fields take add and remove with a value")),
+    };
+    if let Some((key, sub)) = leaf.split_once('.') {
+        if let Some(section) = place.section_mut(key) {
+            match value {
+                Some(value) => {
+                    section.insert(sub.to_string(), value.to_string());
+                }
+                None => {
+                    section.remove(sub).ok_or_else(|| invalid(field, "no such key"))?;
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    let owned = value.map(str::to_string);
+    match leaf {
+        "PLACE" => place.place = owned.unwrap_or_default(),
+        "TYPE" => place.type_ = owned.unwrap_or_default(),
+        "ID" => place.id = owned,
+        "LOCATION" => place.location = value.map(|v| coordinate(field, v)).transpose()?,
+        "BOUNDARY" => {
+            place.boundary = value.unwrap_or_default().split(';').filter(|s| !s.trim().is_empty()).map(|v| coordinate(field, v)).collect::<Result<_, _>>()?
+        }
+        "PATH" => {
+            place.path = value.unwrap_or_default().split(';').filter(|s| !s.trim().is_empty()).map(|v| coordinate(field, v)).collect::<Result<_, _>>()?
+        }
+        "EXTENT" => place.extent = value.map(|v| extent(field, v)).transpose()?,
+        "ELEVATION" => place.elevation = owned,
+        "AREA" => place.area = owned,
+        "PART_OF" => place.part_of = owned,
+        "UPDATED" => place.updated = owned,
+        "VIEWSHEDS" => place.viewsheds = value.map(|v| json(field, v)).transpose()?.unwrap_or_default(),
+        // Anything else is an `extra` field; values that aren't JSON are text
+        _ => match value {
+            Some(v) => {
+                let v = serde_json::from_str(v).unwrap_or_else(|_| serde_json::Value::String(v.to_string()));
+                place.extra.insert(leaf.to_string(), v);
+            }
+            None => {
+                place.extra.remove(leaf).ok_or_else(|| invalid(field, "no such field"))?;
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_round_trip() {
+        let mut before = GeonPlace::default();
+        before.place = "Council House".to_string();
+        before.id = Some("osm:way/7".to_string());
+        before.elevation = Some("56 m".to_string());
+        before.purpose = vec!["civic".to_string(), "offices".to_string()];
+        let mut ballroom = GeonPlace::default();
+        ballroom.place = "Ballroom".to_string();
+        before.contains = vec![ballroom];
+
+        let mut after = before.clone();
+        after.location = Some(Coordinate::new(52.9532, -1.1497));
+        after.elevation = None;
+        after.purpose = vec!["civic".to_string(), "events".to_string()];
+        after.experience.insert("noise".to_string(), "quiet".to_string());
+        after.contains[0].type_ = "room".to_string();
+        after.extra.insert("wheelchair".to_string(), serde_json::json!("yes"));
+
+        let patch = diff_places(&before, &after);
+        let text = patch.to_string();
+        assert_eq!(
+            text,
+            r#"PATCH: osm:way/7
+set LOCATION = "52.9532, -1.1497"
+remove ELEVATION
+remove PURPOSE = "offices"
+add PURPOSE = "events"
+set EXPERIENCE.noise = "quiet"
+set wheelchair = "\"yes\""
+set CONTAINS[0].TYPE = "room"
+"#
+        );
+        assert_eq!(GeonPatch::parse(&text).unwrap(), patch);
+
+        let mut place = before.clone();
+        patch.apply(&mut place).unwrap();
+        assert_eq!(place, after);
+        assert!(diff_places(&after, &place).is_empty());
+
+        // A patch that doesn't fit leaves the place alone
+        let stale = GeonPatch::parse("set TYPE = \"hall\"\nremove PURPOSE = \"parking\"").unwrap();
+        assert!(stale.apply(&mut place).is_err());
+        assert_eq!(place, after);
+        assert!(GeonPatch::parse("rename PLACE = \"Hall\"").is_err());
+        assert!(GeonPatch::parse("set TYPE = hall").is_err());
+    }
+
+    #[test]
+    fn test_patch_text_round_trip() {
+        let mut before = GeonPlace::default();
+        before.place = "Arcade".to_string();
+        before.purpose = vec!["".to_string(), "shopping".to_string()];
+        for name in ["Stall ]. 1", "Stall ]. 1"] {
+            let mut stall = GeonPlace::default();
+            stall.place = name.to_string();
+            before.contains.push(stall);
+        }
+
+        let mut after = before.clone();
+        after.purpose = vec!["".to_string(), "  shopping  ".to_string(), "line one\nline two".to_string()];
+        after.experience.insert("noise".to_string(), "".to_string());
+        after.experience.insert("a = b".to_string(), " padded\t".to_string());
+        after.elevation = Some("".to_string());
+        after.contains[1].type_ = "kiosk\r\nstand".to_string();
+        after.extra.insert("NOTE".to_string(), serde_json::json!("  x = \"y\"  "));
+
+        let patch = diff_places(&before, &after);
+        let text = patch.to_string();
+        assert_eq!(text.lines().count(), patch.ops.len() + 1);
+        assert!(text.contains("set CONTAINS[1].TYPE = \"kiosk\\r\\nstand\"\n"), "{}", text);
+        let read = GeonPatch::parse(&text).unwrap();
+        assert_eq!(read, patch);
+
+        let mut place = before.clone();
+        read.apply(&mut place).unwrap();
+        assert_eq!(place, after);
+        assert_eq!(place.contains[0].type_, "");
+    }
+}
@@ -0,0 +1,83 @@
+//! Reading the free-text quantities used in AREA, ELEVATION and similar
+//! fields, e.g. `12500 sqm`, `1.2 ha`, `56 m`, `~ 3,000 sq ft`.
+
+use crate::units::{AreaUnit, LengthUnit};
+
+// Unit spellings
+const AREA_UNITS: &[(&[&str], AreaUnit)] = &[
+    (&["sqm", "m2", "m²", "sq m", "square metres", "square meters", "sq metres"], AreaUnit::SquareMetres),
+    (&["ha", "hectare", "hectares"], AreaUnit::Hectares),
+    (&["km2", "km²", "sq km", "square kilometres", "square kilometers"], AreaUnit::SquareKilometres),
+    (&["acre", "acres", "ac"], AreaUnit::Acres),
+    (&["sq ft", "sqft", "ft2", "ft²", "square feet"], AreaUnit::SquareFeet),
+];
+
+const LENGTH_UNITS: &[(&[&str], LengthUnit)] = &[
+    (&["m", "metre", "metres", "meter", "meters"], LengthUnit::Metres),
+    (&["km", "kilometre", "kilometres", "kilometer", "kilometers"], LengthUnit::Kilometres),
+    (&["ft", "foot", "feet", "'"], LengthUnit::Feet),
+    (&["mi", "mile", "miles"], LengthUnit::Miles),
+];
+
+/// The leading number of a quantity and the rest of the text, ignoring
+/// approximation marks and thousands separators.
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim().trim_start_matches(['~', '≈']).trim_start();
+    let text = text.strip_prefix("ca.").or(text.strip_prefix("c.")).unwrap_or(text).trim_start();
+    let end = text
+        .char_indices()
+        .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+')))
+        .map_or(text.len(), |(i, _)| i);
+    let number: String = text[..end].chars().filter(|c| *c != ',').collect();
+    Some((number.parse().ok()?, text[end..].trim()))
+}
+
+// The number and unit of a quantity, `default` when no unit is given
+fn read<U: Copy>(text: &str, units: &[(&[&str], U)], default: U) -> Option<(f64, U)> {
+    let (number, rest) = split_number(text)?;
+    // Only the unit itself, not any trailing note such as `(approx.)`
+    let unit = rest.split(['(', ';']).next().unwrap_or_default().trim().trim_end_matches('.').to_lowercase();
+    if unit.is_empty() {
+        return Some((number, default));
+    }
+    units.iter().find(|(names, _)| names.contains(&unit.as_str())).map(|(_, u)| (number, *u))
+}
+
+/// Square metres in an AREA value; a bare number is taken as square metres.
+pub fn parse_area(text: &str) -> Option<f64> {
+    read(text, AREA_UNITS, AreaUnit::SquareMetres).map(|(n, unit)| n * unit.square_metres())
+}
+
+/// Metres in an ELEVATION or length value; a bare number is taken as metres.
+pub fn parse_length(text: &str) -> Option<f64> {
+    read(text, LENGTH_UNITS, LengthUnit::Metres).map(|(n, unit)| n * unit.metres())
+}
+
+/// The unit an AREA value is written in.
+pub fn area_unit(text: &str) -> Option<AreaUnit> {
+    read(text, AREA_UNITS, AreaUnit::SquareMetres).map(|(_, unit)| unit)
+}
+
+/// The unit an ELEVATION or length value is written in.
+pub fn length_unit(text: &str) -> Option<LengthUnit> {
+    read(text, LENGTH_UNITS, LengthUnit::Metres).map(|(_, unit)| unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantities() {
+        assert_eq!(parse_area("12500 sqm"), Some(12500.0));
+        assert_eq!(parse_area("~1.5 ha"), Some(15_000.0));
+        assert_eq!(parse_area("3,000 m² (approx.)"), Some(3000.0));
+        assert_eq!(parse_area("2 km2"), Some(2_000_000.0));
+        assert_eq!(parse_area("40"), Some(40.0));
+        assert_eq!(parse_area("c. .5 ha"), Some(5_000.0));
+        assert_eq!(parse_area("large"), None);
+        assert_eq!(parse_area("5 parsecs"), None);
+        assert_eq!(parse_length("56 m"), Some(56.0));
+        assert!((parse_length("100 ft").unwrap() - 30.48).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,351 @@
+//! An HTTP service over a [`GeonCollection`]: the thin REST layer put in
+//! front of a corpus.
+//!
+//! - `GET /places`: every place, or with `bbox=west,south,east,north` (the
+//!   OGC API order) those in the box, and with `type=` those of one TYPE.
+//! - `GET /places/{id}`: the place with that ID, nested places included.
+//! - `POST /places`: add a place, as GEON text or, with
+//!   `Content-Type: application/json`, place JSON. It is linted first, and
+//!   one with errors is refused with `422` and the issues.
+//!
+//! Places are sent as place JSON, as GeoJSON when `Accept` lists
+//! `application/geo+json` first, or as GEON text for `text/plain`. Errors
+//! are JSON objects with an `error` message.
+//!
+//! [`GeonService::handle`] takes and returns [`http`] types, so the routes
+//! can be mounted in another framework's router; [`serve`] runs them on
+//! hyper.
+
+use crate::collection::{GeonCollection, Registry};
+use crate::converter::{to_geojson, to_geojson_collection, CollectionOptions};
+use crate::generator::generate;
+use crate::models::{Extent, GeonPlace};
+use crate::parser::parse;
+use crate::validate::{lint_collection, LintConfig, Severity};
+use http::{header, Method, Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Value};
+use std::ops::Deref;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+/// Largest request body [`serve`] reads.
+pub const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    GeoJson,
+    Geon,
+}
+
+// The first of the formats `accept` lists, place JSON if none
+fn negotiate(accept: &str) -> Format {
+    for media in accept.split(',') {
+        match media.split(';').next().unwrap_or_default().trim() {
+            "application/json" => return Format::Json,
+            "application/geo+json" => return Format::GeoJson,
+            "text/plain" => return Format::Geon,
+            _ => {}
+        }
+    }
+    Format::Json
+}
+
+fn respond(status: StatusCode, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response.headers_mut().insert(header::CONTENT_TYPE, content_type.parse().expect("valid media type"));
+    response
+}
+
+fn respond_json(status: StatusCode, content_type: &str, value: &Value) -> Response<Vec<u8>> {
+    respond(status, content_type, value.to_string().into_bytes())
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    respond_json(status, "application/json", &json!({ "error": message }))
+}
+
+fn respond_places(status: StatusCode, places: &[GeonPlace], one: bool, format: Format) -> Response<Vec<u8>> {
+    match format {
+        Format::Json if one => respond_json(status, "application/json", &json!(places[0])),
+        Format::Json => respond_json(status, "application/json", &json!(places)),
+        Format::GeoJson if one => respond_json(status, "application/geo+json", &to_geojson(&places[0])),
+        Format::GeoJson => {
+            respond_json(status, "application/geo+json", &to_geojson_collection(places, &CollectionOptions::default()))
+        }
+        Format::Geon => {
+            let text = places.iter().map(generate).collect::<Vec<_>>().join("\n");
+            respond(status, "text/plain; charset=utf-8", text.into_bytes())
+        }
+    }
+}
+
+// Undo %-escapes, leaving malformed ones as they are
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// %-escape everything but unreserved characters and ':', for a path segment
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b':') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+// `west,south,east,north`
+fn parse_bbox(text: &str) -> Option<Extent> {
+    let n: Vec<f64> = text.split(',').map(|s| s.trim().parse().ok()).collect::<Option<_>>()?;
+    match n[..] {
+        [west, south, east, north] => Some(Extent { north, south, east, west }),
+        _ => None,
+    }
+}
+
+// The collection with its IDs and names, kept in step as places are added
+#[derive(Debug, Default)]
+struct Corpus {
+    collection: GeonCollection,
+    registry: Registry,
+}
+
+/// Read access to the collection behind a [`GeonService`], holding off
+/// POSTs while it lives.
+pub struct CollectionRef<'a>(RwLockReadGuard<'a, Corpus>);
+
+impl Deref for CollectionRef<'_> {
+    type Target = GeonCollection;
+
+    fn deref(&self) -> &GeonCollection {
+        &self.0.collection
+    }
+}
+
+/// The routes over a shared collection. Clones share the collection, so
+/// places POSTed through one are seen by all.
+#[derive(Debug, Clone, Default)]
+pub struct GeonService {
+    corpus: Arc<RwLock<Corpus>>,
+    config: LintConfig,
+}
+
+impl GeonService {
+    pub fn new(collection: GeonCollection) -> Self {
+        let registry = collection.registry();
+        Self { corpus: Arc::new(RwLock::new(Corpus { collection, registry })), config: LintConfig::default() }
+    }
+
+    /// Lint POSTed places with `config` instead of the recommended profile.
+    pub fn with_lint_config(mut self, config: LintConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The collection the routes read and add to.
+    pub fn collection(&self) -> CollectionRef<'_> {
+        CollectionRef(self.corpus.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Answer one request.
+    pub fn handle(&self, request: &Request<impl AsRef<[u8]>>) -> Response<Vec<u8>> {
+        let accept = request.headers().get(header::ACCEPT).and_then(|v| v.to_str().ok());
+        let format = negotiate(accept.unwrap_or_default());
+        let path = request.uri().path().trim_end_matches('/');
+        match (request.method(), path.strip_prefix("/places")) {
+            (&Method::GET, Some("")) => self.list(request.uri().query().unwrap_or_default(), format),
+            (&Method::POST, Some("")) => self.create(request, format),
+            (&Method::GET, Some(id)) if id.starts_with('/') => self.get(&percent_decode(&id[1..]), format),
+            (_, Some("")) => error(StatusCode::METHOD_NOT_ALLOWED, "use GET or POST"),
+            (_, Some(id)) if id.starts_with('/') => error(StatusCode::METHOD_NOT_ALLOWED, "use GET"),
+            _ => error(StatusCode::NOT_FOUND, "no such route"),
+        }
+    }
+
+    fn list(&self, query: &str, format: Format) -> Response<Vec<u8>> {
+        let (mut bbox, mut type_) = (None, None);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(&value.replace('+', " "));
+            match key {
+                "bbox" => match parse_bbox(&value) {
+                    Some(extent) => bbox = Some(extent),
+                    None => return error(StatusCode::BAD_REQUEST, "bbox must be west,south,east,north"),
+                },
+                "type" => type_ = Some(value),
+                other => return error(StatusCode::BAD_REQUEST, &format!("unknown parameter {}", other)),
+            }
+        }
+        let collection = self.collection();
+        let found = match &bbox {
+            Some(extent) => collection.query_bbox(extent),
+            None => collection.iter().collect(),
+        };
+        let places: Vec<GeonPlace> =
+            found.into_iter().filter(|p| type_.as_ref().is_none_or(|t| p.type_ == *t)).cloned().collect();
+        respond_places(StatusCode::OK, &places, false, format)
+    }
+
+    fn get(&self, id: &str, format: Format) -> Response<Vec<u8>> {
+        let corpus = self.corpus.read().unwrap_or_else(PoisonError::into_inner);
+        match corpus.registry.by_id(id).and_then(|handle| handle.get(&corpus.collection)) {
+            Some(place) => respond_places(StatusCode::OK, std::slice::from_ref(place), true, format),
+            None => error(StatusCode::NOT_FOUND, &format!("no place with ID {}", id)),
+        }
+    }
+
+    fn create(&self, request: &Request<impl AsRef<[u8]>>, format: Format) -> Response<Vec<u8>> {
+        let Ok(body) = std::str::from_utf8(request.body().as_ref()) else {
+            return error(StatusCode::BAD_REQUEST, "body is not UTF-8");
+        };
+        let content_type = request.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        let place = if content_type.is_some_and(|t| t.starts_with("application/json")) {
+            match serde_json::from_str::<GeonPlace>(body) {
+                Ok(place) => place,
+                Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        } else {
+            parse(body)
+        };
+        if place.place.is_empty() {
+            return error(StatusCode::BAD_REQUEST, "place has no PLACE");
+        }
+
+        let issues = lint_collection(&GeonCollection::new(vec![place.clone()]), &self.config);
+        if issues.iter().any(|i| i.severity == Severity::Error) {
+            return respond_json(StatusCode::UNPROCESSABLE_ENTITY, "application/json", &json!({ "issues": issues }));
+        }
+        let mut corpus = self.corpus.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(id) = &place.id {
+            if corpus.registry.by_id(id).is_some() {
+                return error(StatusCode::CONFLICT, &format!("a place with ID {} exists", id));
+            }
+        }
+        let Corpus { collection, registry } = &mut *corpus;
+        registry.insert(collection.len(), &place);
+        collection.push(place.clone());
+        drop(corpus);
+
+        let mut response = respond_places(StatusCode::CREATED, std::slice::from_ref(&place), true, format);
+        if let Some(location) = place.id.as_ref().and_then(|id| format!("/places/{}", percent_encode(id)).parse().ok()) {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+        response
+    }
+}
+
+/// Serve `service` over HTTP/1.1 on `listener`, a task per connection,
+/// until accepting a connection fails.
+pub async fn serve(listener: tokio::net::TcpListener, service: GeonService) -> std::io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let service = service.clone();
+        let handler = hyper::service::service_fn(move |request: Request<Incoming>| {
+            let service = service.clone();
+            async move {
+                let (parts, body) = request.into_parts();
+                let response = match Limited::new(body, MAX_BODY_BYTES).collect().await {
+                    Ok(body) => service.handle(&Request::from_parts(parts, body.to_bytes())),
+                    Err(e) if e.is::<LengthLimitError>() => error(StatusCode::PAYLOAD_TOO_LARGE, "body too large"),
+                    Err(_) => error(StatusCode::BAD_REQUEST, "body could not be read"),
+                };
+                Ok::<_, hyper::Error>(response.map(|body| Full::new(Bytes::from(body))))
+            }
+        });
+        tokio::spawn(async move {
+            // A client dropping its connection is no reason to stop serving
+            let _ = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), handler).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> GeonService {
+        GeonService::new(GeonCollection::new(vec![
+            parse("PLACE: Old Market Square\nID: osm:way/1\nTYPE: public_space\nLOCATION: 52.9533, -1.1500"),
+            parse("PLACE: Council House\nID: osm:way/2\nTYPE: building\nLOCATION: 52.9536, -1.1492"),
+            parse("PLACE: Trent Bridge\nID: osm:way/3\nTYPE: infrastructure\nLOCATION: 52.9387, -1.1342"),
+        ]))
+    }
+
+    fn request(service: &GeonService, method: Method, uri: &str, accept: &str, body: &str) -> Response<Vec<u8>> {
+        let request = Request::builder().method(method).uri(uri).header(header::ACCEPT, accept);
+        service.handle(&request.body(body.as_bytes()).unwrap())
+    }
+
+    fn json_body(response: &Response<Vec<u8>>) -> Value {
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    #[test]
+    fn test_routes() {
+        let service = service();
+        let get = |uri: &str, accept: &str| request(&service, Method::GET, uri, accept, "");
+        let all = get("/places", "");
+        assert_eq!(all.status(), StatusCode::OK);
+        assert_eq!(json_body(&all).as_array().unwrap().len(), 3);
+
+        let boxed = get("/places?bbox=-1.16,52.95,-1.14,52.96&type=building", "application/geo+json");
+        assert_eq!(boxed.headers()[header::CONTENT_TYPE], "application/geo+json");
+        let features = json_body(&boxed)["features"].clone();
+        assert_eq!(features.as_array().unwrap().len(), 1);
+        assert_eq!(features[0]["properties"]["name"], "Council House");
+        assert_eq!(get("/places?bbox=1,2,3", "").status(), StatusCode::BAD_REQUEST);
+
+        let one = get("/places/osm:way%2F3", "text/plain, application/json");
+        assert!(String::from_utf8_lossy(one.body()).starts_with("PLACE: Trent Bridge\n"));
+        assert_eq!(get("/places/osm:way/3", "").status(), StatusCode::OK);
+        assert_eq!(get("/places/osm:way/9", "").status(), StatusCode::NOT_FOUND);
+        assert_eq!(get("/", "").status(), StatusCode::NOT_FOUND);
+        assert_eq!(request(&service, Method::DELETE, "/places/osm:way/3", "", "").status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_post() {
+        let service = service();
+        let post = |body: &str| request(&service, Method::POST, "/places", "", body);
+        let created = post("PLACE: Bandstand\nID: local:1\nLOCATION: 52.95, -1.15");
+        assert_eq!(created.status(), StatusCode::CREATED);
+        assert_eq!(created.headers()[header::LOCATION], "/places/local:1");
+        assert_eq!(json_body(&created)["place"], "Bandstand");
+        let shared = service.clone();
+        assert_eq!(request(&shared, Method::GET, "/places/local:1", "", "").status(), StatusCode::OK);
+
+        assert_eq!(post("PLACE: Square\nID: osm:way/1").status(), StatusCode::CONFLICT);
+        let spaced = post("PLACE: Kiosk\nID: local/kiosk 2\nLOCATION: 52.95, -1.15");
+        assert_eq!(spaced.headers()[header::LOCATION], "/places/local%2Fkiosk%202");
+        assert_eq!(request(&service, Method::GET, "/places/local%2Fkiosk%202", "", "").status(), StatusCode::OK);
+        assert_eq!(post("PLACE: Kiosk\nID: local/kiosk 2").status(), StatusCode::CONFLICT);
+        assert_eq!(service.collection().len(), 5);
+        let invalid = post("PLACE: Plot\nBOUNDARY:\n  - 52.9, -1.1\n  - 52.9, -1.2");
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(&invalid)["issues"][0]["rule"], "too-few-points");
+        assert_eq!(post("TYPE: street").status(), StatusCode::BAD_REQUEST);
+    }
+}
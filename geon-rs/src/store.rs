@@ -0,0 +1,53 @@
+//! Keeping places in a database rather than in memory.
+//!
+//! As with the SQL helpers in [`io::gpkg`](crate::io::gpkg), this crate
+//! doesn't link a database driver here. Stores issue plain SQL through [`SqlConnection`], which
+//! takes a few lines to implement over whichever binding an application
+//! already uses (`rusqlite`, `sqlx`, ...).
+
+#[cfg(feature = "postgis")]
+pub mod postgis;
+mod sql;
+
+pub use sql::{SqlStore, SqliteStore};
+
+use crate::parser::GeonError;
+
+/// A value bound to, or read back from, an SQL statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<Option<String>> for SqlValue {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(SqlValue::Null, SqlValue::Text)
+    }
+}
+
+impl From<Option<f64>> for SqlValue {
+    fn from(value: Option<f64>) -> Self {
+        value.map_or(SqlValue::Null, SqlValue::Real)
+    }
+}
+
+/// The part of a database binding a store needs. Parameters are bound
+/// positionally, to `?` placeholders for SQLite and `$1`, `$2`, ... for
+/// PostgreSQL.
+pub trait SqlConnection {
+    type Error: std::fmt::Display;
+
+    /// Run a statement, returning the number of rows changed.
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<usize, Self::Error>;
+
+    /// Run a query, returning its rows in order.
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> Result<Vec<Vec<SqlValue>>, Self::Error>;
+}
+
+fn store_error(e: impl std::fmt::Display) -> GeonError {
+    GeonError::Store(e.to_string())
+}
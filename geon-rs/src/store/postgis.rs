@@ -0,0 +1,181 @@
+//! Collections in a PostGIS table, with LOCATION and BOUNDARY as real
+//! geometry columns (SRID 4326) so they join other spatial tables, and the
+//! whole place as `jsonb` alongside.
+//!
+//! Statements use `$1`-style placeholders, for a PostgreSQL binding behind
+//! [`SqlConnection`].
+
+use super::{store_error, SqlConnection, SqlValue};
+use crate::collection::GeonCollection;
+use crate::geometry::{from_wkt, to_wkt, WktGeometry};
+use crate::io::gpkg::quote_ident;
+use crate::models::GeonPlace;
+use crate::parser::GeonError;
+
+/// SQL creating `table` with geometry columns and GiST indexes, and the
+/// PostGIS extension if it isn't installed yet.
+pub fn create_table_sql(table: &str) -> String {
+    let t = quote_ident(table);
+    let index = |suffix: &str| quote_ident(&format!("{}_{}", table, suffix));
+    format!(
+        "CREATE EXTENSION IF NOT EXISTS postgis;\n\
+         CREATE TABLE IF NOT EXISTS {t} (\n  \
+           key text PRIMARY KEY,\n  \
+           geon_id text,\n  \
+           name text NOT NULL,\n  \
+           geon_type text,\n  \
+           location geometry(Point, 4326),\n  \
+           boundary geometry(Polygon, 4326),\n  \
+           properties jsonb NOT NULL\n\
+         );\n\
+         CREATE INDEX IF NOT EXISTS {} ON {t} USING GIST (location);\n\
+         CREATE INDEX IF NOT EXISTS {} ON {t} USING GIST (boundary);\n\
+         CREATE INDEX IF NOT EXISTS {} ON {t} (geon_type);\n",
+        index("location"),
+        index("boundary"),
+        index("type"),
+    )
+}
+
+/// Insert or replace a place, by ID or else name; parameters as [`to_params`].
+pub fn upsert_sql(table: &str) -> String {
+    format!(
+        "INSERT INTO {} (key, geon_id, name, geon_type, location, boundary, properties) \
+         VALUES ($1, $2, $3, $4, ST_GeomFromText($5, 4326), ST_GeomFromText($6, 4326), $7::jsonb) \
+         ON CONFLICT (key) DO UPDATE SET geon_id = EXCLUDED.geon_id, name = EXCLUDED.name, \
+         geon_type = EXCLUDED.geon_type, location = EXCLUDED.location, boundary = EXCLUDED.boundary, \
+         properties = EXCLUDED.properties",
+        quote_ident(table)
+    )
+}
+
+/// Select the columns [`from_row`] reads, in key order.
+pub fn select_sql(table: &str) -> String {
+    format!(
+        "SELECT properties::text, ST_AsText(location), ST_AsText(boundary) FROM {} ORDER BY key",
+        quote_ident(table)
+    )
+}
+
+/// Parameters for [`upsert_sql`].
+pub fn to_params(place: &GeonPlace) -> Result<Vec<SqlValue>, GeonError> {
+    let mut outline = GeonPlace::default();
+    outline.boundary = place.boundary.clone();
+    Ok(vec![
+        SqlValue::Text(place.id.clone().unwrap_or_else(|| place.place.clone())),
+        place.id.clone().into(),
+        SqlValue::Text(place.place.clone()),
+        SqlValue::Text(place.type_.clone()),
+        place.location.as_ref().map(|c| format!("POINT ({} {})", c.lon, c.lat)).into(),
+        to_wkt(&outline).into(),
+        SqlValue::Text(serde_json::to_string(place).map_err(store_error)?),
+    ])
+}
+
+/// Rebuild a place from a row of [`select_sql`]. The geometry columns win
+/// over the JSON, so edits made to them in a GIS are kept.
+pub fn from_row(row: &[SqlValue]) -> Result<GeonPlace, GeonError> {
+    let text = |i: usize| match row.get(i) {
+        Some(SqlValue::Text(t)) => Some(t.as_str()),
+        _ => None,
+    };
+    let properties = text(0).ok_or_else(|| GeonError::Store("expected a properties column".to_string()))?;
+    let mut place: GeonPlace = serde_json::from_str(properties).map_err(store_error)?;
+    place.location = match text(1).map(from_wkt).transpose()? {
+        Some(WktGeometry::Point(c)) => Some(c),
+        _ => None,
+    };
+    if let Some(WktGeometry::Polygon(mut ring)) = text(2).map(from_wkt).transpose()? {
+        // Rings come back closed; keep one stored open that way
+        if place.boundary.first() != place.boundary.last() && ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        place.boundary = ring;
+    }
+    Ok(place)
+}
+
+/// Run [`create_table_sql`].
+pub fn create_table<C: SqlConnection>(conn: &mut C, table: &str) -> Result<(), GeonError> {
+    for statement in create_table_sql(table).split_terminator(";\n") {
+        conn.execute(statement, &[]).map_err(store_error)?;
+    }
+    Ok(())
+}
+
+/// Write every place in `collection` to `table` in one transaction,
+/// replacing rows with the same key, and return how many were written.
+pub fn write<C: SqlConnection>(conn: &mut C, table: &str, collection: &GeonCollection) -> Result<usize, GeonError> {
+    let sql = upsert_sql(table);
+    conn.execute("BEGIN", &[]).map_err(store_error)?;
+    for place in collection {
+        let written = to_params(place).and_then(|params| conn.execute(&sql, &params).map_err(store_error));
+        if let Err(e) = written {
+            let _ = conn.execute("ROLLBACK", &[]);
+            return Err(e);
+        }
+    }
+    conn.execute("COMMIT", &[]).map_err(store_error)?;
+    Ok(collection.len())
+}
+
+/// Read every place in `table`.
+pub fn read<C: SqlConnection>(conn: &mut C, table: &str) -> Result<GeonCollection, GeonError> {
+    let rows = conn.query(&select_sql(table), &[]).map_err(store_error)?;
+    let places = rows.iter().map(|r| from_row(r)).collect::<Result<Vec<_>, _>>()?;
+    Ok(GeonCollection::new(places))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+
+    // Keeps rows as PostGIS would hand them back; WKT rings are closed
+    #[derive(Default)]
+    struct Fake {
+        statements: Vec<String>,
+        rows: Vec<Vec<SqlValue>>,
+    }
+
+    impl SqlConnection for Fake {
+        type Error = String;
+
+        fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<usize, String> {
+            self.statements.push(sql.split_whitespace().take(3).collect::<Vec<_>>().join(" "));
+            if sql.starts_with("INSERT") {
+                self.rows.push(vec![params[6].clone(), params[4].clone(), params[5].clone()]);
+            }
+            Ok(1)
+        }
+
+        fn query(&mut self, _sql: &str, _params: &[SqlValue]) -> Result<Vec<Vec<SqlValue>>, String> {
+            Ok(self.rows.clone())
+        }
+    }
+
+    #[test]
+    fn test_postgis_round_trip() {
+        let sql = create_table_sql("geon places");
+        assert!(sql.contains("CREATE TABLE IF NOT EXISTS \"geon places\""));
+        assert!(sql.contains("\"geon places_location\" ON \"geon places\" USING GIST (location)"));
+        assert!(upsert_sql("places").contains("ST_GeomFromText($5, 4326)"));
+
+        let mut square = GeonPlace::default();
+        square.place = "Old Market Square".to_string();
+        square.location = Some(Coordinate::new(52.9529, -1.1505));
+        square.boundary = vec![Coordinate::new(52.953, -1.151), Coordinate::new(52.953, -1.150), Coordinate::new(52.952, -1.150)];
+        square.experience.insert("noise".to_string(), "loud".to_string());
+        let mut hall = GeonPlace::default();
+        hall.place = "Council House".to_string();
+        hall.id = Some("osm:way/7".to_string());
+        let collection = GeonCollection::new(vec![square, hall]);
+
+        let mut conn = Fake::default();
+        create_table(&mut conn, "places").unwrap();
+        assert_eq!(write(&mut conn, "places", &collection).unwrap(), 2);
+        assert_eq!(conn.statements[0], "CREATE EXTENSION IF");
+        assert_eq!(conn.statements.last().unwrap(), "COMMIT");
+        assert_eq!(read(&mut conn, "places").unwrap(), collection);
+    }
+}
@@ -0,0 +1,349 @@
+use super::{store_error, SqlConnection, SqlValue};
+use crate::collection::{place_bounds, GeonCollection, Query, SpatialMatch};
+use crate::geometry::METRES_PER_DEGREE;
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use std::collections::HashSet;
+
+// Half the Earth's circumference: no two places are further apart
+const MAX_DISTANCE_M: f64 = 20_040_000.0;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS geon_places (\n  \
+       key TEXT PRIMARY KEY,\n  \
+       geon_id TEXT,\n  \
+       name TEXT NOT NULL,\n  \
+       geon_type TEXT,\n  \
+       lat REAL,\n  \
+       lon REAL,\n  \
+       west REAL,\n  \
+       south REAL,\n  \
+       east REAL,\n  \
+       north REAL,\n  \
+       body TEXT NOT NULL\n\
+     )",
+    "CREATE INDEX IF NOT EXISTS geon_places_id ON geon_places (geon_id)",
+    "CREATE INDEX IF NOT EXISTS geon_places_name ON geon_places (name COLLATE NOCASE)",
+    "CREATE INDEX IF NOT EXISTS geon_places_type ON geon_places (geon_type COLLATE NOCASE)",
+    "CREATE INDEX IF NOT EXISTS geon_places_bbox ON geon_places (west, east, south, north)",
+];
+
+const UPSERT: &str = "INSERT INTO geon_places (key, geon_id, name, geon_type, lat, lon, west, south, east, north, body) \
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+     ON CONFLICT (key) DO UPDATE SET geon_id = excluded.geon_id, name = excluded.name, geon_type = excluded.geon_type, \
+     lat = excluded.lat, lon = excluded.lon, west = excluded.west, south = excluded.south, east = excluded.east, \
+     north = excluded.north, body = excluded.body";
+
+const SELECT_ALL: &str = "SELECT body FROM geon_places ORDER BY rowid";
+const SELECT_KEY: &str = "SELECT body FROM geon_places WHERE key = ?";
+const SELECT_TYPE: &str = "SELECT body FROM geon_places WHERE geon_type = ? COLLATE NOCASE ORDER BY rowid";
+const SELECT_WINDOW: &str =
+    "SELECT key, body FROM geon_places WHERE east >= ? AND west <= ? AND north >= ? AND south <= ? ORDER BY rowid";
+
+/// Places kept in a database table, one JSON record per place alongside
+/// indexed columns for ID, name, TYPE, LOCATION and bounding box, so that
+/// spatial and type queries read only the rows they need. Statements are
+/// written for SQLite, over whichever driver `C` wraps.
+///
+/// Places are keyed by ID, or name where they have none; storing a place
+/// under an existing key replaces it. Queries narrow the rows in SQL and
+/// then match exactly as [`GeonCollection`] does.
+#[derive(Debug)]
+pub struct SqlStore<C> {
+    conn: C,
+}
+
+/// [`SqlStore`] under the name it is usually reached by, since its
+/// statements are written for SQLite.
+pub type SqliteStore<C> = SqlStore<C>;
+
+fn key(place: &GeonPlace) -> String {
+    place.id.clone().unwrap_or_else(|| place.place.clone())
+}
+
+fn row(place: &GeonPlace) -> Result<Vec<SqlValue>, GeonError> {
+    let body = serde_json::to_string(place).map_err(store_error)?;
+    let bounds = place_bounds(place);
+    Ok(vec![
+        SqlValue::Text(key(place)),
+        place.id.clone().into(),
+        SqlValue::Text(place.place.clone()),
+        SqlValue::Text(place.type_.clone()),
+        place.location.as_ref().map(|c| c.lat).into(),
+        place.location.as_ref().map(|c| c.lon).into(),
+        bounds.map(|b| b.0).into(),
+        bounds.map(|b| b.1).into(),
+        bounds.map(|b| b.2).into(),
+        bounds.map(|b| b.3).into(),
+        SqlValue::Text(body),
+    ])
+}
+
+// The place in a row whose last column is its JSON body
+fn place(row: &[SqlValue]) -> Result<GeonPlace, GeonError> {
+    match row.last() {
+        Some(SqlValue::Text(body)) => serde_json::from_str(body).map_err(store_error),
+        _ => Err(GeonError::Store("expected a JSON body column".to_string())),
+    }
+}
+
+// The TYPE a query requires, if any, to narrow the rows read
+fn required_type(query: &Query) -> Option<&str> {
+    match query {
+        Query::TypeIs(t) => Some(t),
+        Query::And(a, b) => required_type(a).or_else(|| required_type(b)),
+        _ => None,
+    }
+}
+
+// Query windows (west, south, east, north), two for an extent crossing the
+// antimeridian
+fn windows(extent: &Extent) -> Vec<[f64; 4]> {
+    if extent.west > extent.east {
+        vec![[extent.west, extent.south, 180.0, extent.north], [-180.0, extent.south, extent.east, extent.north]]
+    } else {
+        vec![[extent.west, extent.south, extent.east, extent.north]]
+    }
+}
+
+// An extent covering everything within `metres` of `center`
+fn radius_extent(center: &Coordinate, metres: f64) -> Extent {
+    let dlat = metres / METRES_PER_DEGREE;
+    let dlon = dlat / center.lat.to_radians().cos().max(1e-6);
+    let (south, north) = ((center.lat - dlat).max(-90.0), (center.lat + dlat).min(90.0));
+    if dlon >= 180.0 || north == 90.0 || south == -90.0 {
+        return Extent { north, south, east: 180.0, west: -180.0 };
+    }
+    let wrap = |lon: f64| if lon > 180.0 { lon - 360.0 } else if lon < -180.0 { lon + 360.0 } else { lon };
+    Extent { north, south, east: wrap(center.lon + dlon), west: wrap(center.lon - dlon) }
+}
+
+impl<C: SqlConnection> SqlStore<C> {
+    /// Use `conn`, creating the `geon_places` table and its indexes if they
+    /// don't exist yet.
+    pub fn new(mut conn: C) -> Result<Self, GeonError> {
+        for sql in SCHEMA {
+            conn.execute(sql, &[]).map_err(store_error)?;
+        }
+        Ok(Self { conn })
+    }
+
+    pub fn into_inner(self) -> C {
+        self.conn
+    }
+
+    fn rows(&mut self, sql: &str, params: &[SqlValue]) -> Result<Vec<GeonPlace>, GeonError> {
+        let rows = self.conn.query(sql, params).map_err(store_error)?;
+        rows.iter().map(|r| place(r)).collect()
+    }
+
+    /// Add a place, or replace the one stored under its key.
+    pub fn insert(&mut self, place: &GeonPlace) -> Result<(), GeonError> {
+        self.conn.execute(UPSERT, &row(place)?).map_err(store_error)?;
+        Ok(())
+    }
+
+    /// Add or replace many places in one transaction, returning how many.
+    pub fn insert_all<'a>(&mut self, places: impl IntoIterator<Item = &'a GeonPlace>) -> Result<usize, GeonError> {
+        self.conn.execute("BEGIN", &[]).map_err(store_error)?;
+        let mut count = 0;
+        for place in places {
+            if let Err(e) = self.insert(place) {
+                let _ = self.conn.execute("ROLLBACK", &[]);
+                return Err(e);
+            }
+            count += 1;
+        }
+        self.conn.execute("COMMIT", &[]).map_err(store_error)?;
+        Ok(count)
+    }
+
+    /// Remove the place with this ID (or name, if it has no ID), returning
+    /// whether there was one.
+    pub fn remove(&mut self, key: &str) -> Result<bool, GeonError> {
+        let removed = self.conn.execute("DELETE FROM geon_places WHERE key = ?", &[SqlValue::Text(key.to_string())]);
+        Ok(removed.map_err(store_error)? > 0)
+    }
+
+    pub fn get(&mut self, key: &str) -> Result<Option<GeonPlace>, GeonError> {
+        Ok(self.rows(SELECT_KEY, &[SqlValue::Text(key.to_string())])?.pop())
+    }
+
+    pub fn len(&mut self) -> Result<usize, GeonError> {
+        let rows = self.conn.query("SELECT COUNT(*) FROM geon_places", &[]).map_err(store_error)?;
+        match rows.first().and_then(|r| r.first()) {
+            Some(SqlValue::Integer(n)) => Ok(*n as usize),
+            _ => Err(GeonError::Store("expected a row count".to_string())),
+        }
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool, GeonError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Every stored place, in the order first stored.
+    pub fn load(&mut self) -> Result<GeonCollection, GeonError> {
+        Ok(GeonCollection::new(self.rows(SELECT_ALL, &[])?))
+    }
+
+    /// Places matching `query`, as [`GeonCollection::filter`]. Only a
+    /// query requiring a TYPE avoids reading every row.
+    pub fn filter(&mut self, query: &Query) -> Result<Vec<GeonPlace>, GeonError> {
+        let rows = match required_type(query) {
+            Some(t) => self.rows(SELECT_TYPE, &[SqlValue::Text(t.to_string())])?,
+            None => self.rows(SELECT_ALL, &[])?,
+        };
+        Ok(rows.into_iter().filter(|p| query.matches(p)).collect())
+    }
+
+    // Places whose bounding boxes overlap `extent`, in store order
+    fn candidates(&mut self, extent: &Extent) -> Result<GeonCollection, GeonError> {
+        let mut found = Vec::new();
+        let mut seen = HashSet::new();
+        for [w, s, e, n] in windows(extent) {
+            let params = [SqlValue::Real(w), SqlValue::Real(e), SqlValue::Real(s), SqlValue::Real(n)];
+            for row in self.conn.query(SELECT_WINDOW, &params).map_err(store_error)? {
+                if let Some(SqlValue::Text(key)) = row.first() {
+                    if seen.insert(key.clone()) {
+                        found.push(place(&row)?);
+                    }
+                }
+            }
+        }
+        Ok(GeonCollection::new(found))
+    }
+
+    /// As [`GeonCollection::query_bbox`].
+    pub fn query_bbox(&mut self, extent: &Extent) -> Result<Vec<GeonPlace>, GeonError> {
+        self.query_bbox_with(extent, SpatialMatch::Location)
+    }
+
+    pub fn query_bbox_with(&mut self, extent: &Extent, mode: SpatialMatch) -> Result<Vec<GeonPlace>, GeonError> {
+        let candidates = self.candidates(extent)?;
+        Ok(candidates.query_bbox_with(extent, mode).into_iter().cloned().collect())
+    }
+
+    /// As [`GeonCollection::query_radius`].
+    pub fn query_radius(&mut self, center: &Coordinate, metres: f64) -> Result<Vec<GeonPlace>, GeonError> {
+        self.query_radius_with(center, metres, SpatialMatch::Location)
+    }
+
+    pub fn query_radius_with(&mut self, center: &Coordinate, metres: f64, mode: SpatialMatch) -> Result<Vec<GeonPlace>, GeonError> {
+        let candidates = self.candidates(&radius_extent(center, metres))?;
+        Ok(candidates.query_radius_with(center, metres, mode).into_iter().cloned().collect())
+    }
+
+    /// As [`GeonCollection::nearest`]. Searches ever wider circles, from
+    /// 1 km, until one holds `k` places.
+    pub fn nearest(&mut self, center: &Coordinate, k: usize) -> Result<Vec<(GeonPlace, f64)>, GeonError> {
+        let mut metres: f64 = 1_000.0;
+        loop {
+            let candidates = GeonCollection::new(self.query_radius(center, metres)?);
+            if candidates.len() >= k || metres >= MAX_DISTANCE_M {
+                return Ok(candidates.nearest(center, k).into_iter().map(|(p, d)| (p.clone(), d)).collect());
+            }
+            metres = (metres * 4.0).min(MAX_DISTANCE_M);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SQLite itself, through rusqlite
+    struct Sqlite(rusqlite::Connection);
+
+    fn bind(value: &SqlValue) -> rusqlite::types::Value {
+        use rusqlite::types::Value;
+        match value {
+            SqlValue::Null => Value::Null,
+            SqlValue::Integer(n) => Value::Integer(*n),
+            SqlValue::Real(x) => Value::Real(*x),
+            SqlValue::Text(s) => Value::Text(s.clone()),
+            SqlValue::Blob(b) => Value::Blob(b.clone()),
+        }
+    }
+
+    impl SqlConnection for Sqlite {
+        type Error = rusqlite::Error;
+
+        fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<usize, rusqlite::Error> {
+            self.0.execute(sql, rusqlite::params_from_iter(params.iter().map(bind)))
+        }
+
+        fn query(&mut self, sql: &str, params: &[SqlValue]) -> Result<Vec<Vec<SqlValue>>, rusqlite::Error> {
+            let mut statement = self.0.prepare(sql)?;
+            let columns = statement.column_count();
+            let rows = statement.query_map(rusqlite::params_from_iter(params.iter().map(bind)), |row| {
+                (0..columns)
+                    .map(|i| {
+                        Ok(match row.get::<_, rusqlite::types::Value>(i)? {
+                            rusqlite::types::Value::Null => SqlValue::Null,
+                            rusqlite::types::Value::Integer(n) => SqlValue::Integer(n),
+                            rusqlite::types::Value::Real(x) => SqlValue::Real(x),
+                            rusqlite::types::Value::Text(s) => SqlValue::Text(s),
+                            rusqlite::types::Value::Blob(b) => SqlValue::Blob(b),
+                        })
+                    })
+                    .collect()
+            })?;
+            rows.collect()
+        }
+    }
+
+    fn store() -> SqliteStore<Sqlite> {
+        SqliteStore::new(Sqlite(rusqlite::Connection::open_in_memory().unwrap())).unwrap()
+    }
+
+    fn place(name: &str, type_: &str, lat: f64, lon: f64) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.type_ = type_.to_string();
+        p.location = Some(Coordinate::new(lat, lon));
+        p
+    }
+
+    #[test]
+    fn test_sql_store() {
+        let mut store = store();
+        let places = [
+            place("Old Market Square", "public_space", 52.9529, -1.1505),
+            place("Council House", "building", 52.9531, -1.1497),
+            place("Arboretum", "public_space", 52.9625, -1.1550),
+            place("Trafalgar Square", "public_space", 51.5080, -0.1281),
+        ];
+        assert_eq!(store.insert_all(&places).unwrap(), 4);
+
+        let mut hall = places[1].clone();
+        hall.area = Some("2400 sqm".to_string());
+        store.insert(&hall).unwrap();
+        assert_eq!(store.len().unwrap(), 4);
+        assert_eq!(store.get("Council House").unwrap().unwrap().area.as_deref(), Some("2400 sqm"));
+
+        let nottingham = Extent { north: 52.96, south: 52.95, east: -1.14, west: -1.16 };
+        let names = |found: Vec<GeonPlace>| found.into_iter().map(|p| p.place).collect::<Vec<_>>();
+        assert_eq!(names(store.query_bbox(&nottingham).unwrap()), ["Old Market Square", "Council House"]);
+        let spaces = store.filter(&Query::type_is("public_space").and(Query::name_contains("square"))).unwrap();
+        assert_eq!(names(spaces), ["Old Market Square", "Trafalgar Square"]);
+
+        let centre = Coordinate::new(52.9530, -1.1500);
+        assert_eq!(names(store.query_radius(&centre, 100.0).unwrap()), ["Old Market Square", "Council House"]);
+        let nearest = store.nearest(&centre, 4).unwrap();
+        assert_eq!(nearest[3].0.place, "Trafalgar Square");
+        assert!(nearest[3].1 > 150_000.0);
+
+        // A path along the 52nd parallel overlaps both halves of a window
+        // across the antimeridian, and is read once
+        let mut parallel = place("52nd parallel", "street", 52.0, 0.0);
+        parallel.path = vec![Coordinate::new(52.0, -179.5), Coordinate::new(52.0, 179.5)];
+        store.insert(&parallel).unwrap();
+        let crossing = Extent { north: 53.0, south: 51.0, east: -179.0, west: 179.0 };
+        assert_eq!(names(store.candidates(&crossing).unwrap().places), ["52nd parallel"]);
+        assert!(store.remove("52nd parallel").unwrap());
+
+        assert!(store.remove("Arboretum").unwrap());
+        assert!(!store.remove("Arboretum").unwrap());
+        assert_eq!(store.load().unwrap().len(), 3);
+    }
+}
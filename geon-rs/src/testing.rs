@@ -0,0 +1,328 @@
+//! Random places for property tests: [`Arbitrary`] values of [`Coordinate`],
+//! [`Extent`] and [`GeonPlace`] built from raw bytes, and [`check`] to run
+//! a property over many of them.
+//!
+//! Values are read from an [`Unstructured`] byte source in the way the
+//! `arbitrary` crate's are: every choice consumes bytes, and a source that
+//! has run out reads as zeros, which give the smallest choice (no optional
+//! field, an empty list, coordinate 0). Shrinking a failing case is then a
+//! matter of shrinking its bytes, which [`find_failure`] does, and any byte
+//! string is a valid input, so fuzzers can drive the same code.
+//!
+//! Places fill every field, nested places included, with VIEWSHEDS and
+//! extra fields holding strings, numbers, lists and maps. Text is made of
+//! plain words, so for any place `parse(&generate(&place)) == place`.
+
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::vocabulary::{scale_for, EXPERIENCE_KEYS, TYPES};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+// Words names, list entries and map values are made of
+const WORDS: &[&str] = &[
+    "market", "square", "green", "old", "north", "bridge", "lane", "hall", "river", "quiet", "café", "trees",
+    "station", "stone", "open", "busy", "garden", "tower", "east", "walk",
+];
+
+const CONFIDENCE: &[&str] = &["low", "medium", "high"];
+
+// Longest list (and most map entries) a place gets
+const MAX_ITEMS: u32 = 6;
+// Most places nested in one place
+const MAX_CHILDREN: u32 = 3;
+// How deep places nest inside each other
+const MAX_DEPTH: u32 = 2;
+
+/// Raw bytes read as a sequence of choices; zeros once they run out.
+#[derive(Debug, Clone)]
+pub struct Unstructured<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Unstructured<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The next byte, or 0 once the data has run out.
+    pub fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&b, rest)) => {
+                self.data = rest;
+                b
+            }
+            None => 0,
+        }
+    }
+
+    /// An integer in `0..=max`, from as few bytes as `max` needs.
+    pub fn int_to(&mut self, max: u32) -> u32 {
+        let (mut n, mut range) = (0u64, max as u64);
+        while range > 0 {
+            n = (n << 8) | self.byte() as u64;
+            range >>= 8;
+        }
+        (n % (max as u64 + 1)) as u32
+    }
+
+    /// Whether to add an optional field or another list item.
+    pub fn more(&mut self) -> bool {
+        self.byte() & 1 == 1
+    }
+
+    pub fn choose<'b, T>(&mut self, items: &'b [T]) -> &'b T {
+        &items[self.int_to(items.len() as u32 - 1) as usize]
+    }
+
+    pub fn arbitrary<T: Arbitrary>(&mut self) -> T {
+        T::arbitrary(self)
+    }
+
+    // Up to `max` items from `item`, as long as `more` says so
+    fn list<T>(&mut self, max: u32, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        let mut items = Vec::new();
+        while items.len() < max as usize && self.more() {
+            items.push(item(self));
+        }
+        items
+    }
+
+    fn optional<T>(&mut self, value: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        if self.more() { Some(value(self)) } else { None }
+    }
+
+    // One to three words joined by `separator`
+    fn words(&mut self, separator: &str) -> String {
+        let count = 1 + self.int_to(2) as usize;
+        (0..count).map(|_| *self.choose(WORDS)).collect::<Vec<_>>().join(separator)
+    }
+
+    // Millionths of a degree in `-max..=max`, zigzagged so 0 comes first
+    fn degrees(&mut self, max: u32) -> f64 {
+        let n = self.int_to(max * 2_000_000) as i64;
+        let micro = if n % 2 == 0 { n / 2 } else { -(n + 1) / 2 };
+        micro as f64 / 1e6
+    }
+
+    fn map(&mut self, value: impl Fn(&mut Self, &str) -> String) -> BTreeMap<String, String> {
+        self.list(MAX_ITEMS, |u| {
+            let key = u.words("_");
+            let v = value(u, &key);
+            (key, v)
+        })
+        .into_iter()
+        .collect()
+    }
+
+    fn words_map(&mut self) -> BTreeMap<String, String> {
+        self.map(|u, _| u.words(" "))
+    }
+
+    // A JSON value of the kinds GEON text reads back: words, a whole
+    // number, a list of words or of numbers, or a map of words
+    fn json(&mut self) -> Value {
+        let words = |u: &mut Self| Value::String(u.words(" "));
+        match self.int_to(4) {
+            0 => words(self),
+            1 => Value::from(self.int_to(99_999)),
+            2 => Value::Array((0..=self.int_to(2)).map(|_| words(self)).collect()),
+            3 => Value::Array((0..=self.int_to(2)).map(|_| Value::from(self.int_to(999))).collect()),
+            _ => {
+                let key = self.words("_");
+                let mut map: serde_json::Map<String, Value> = self.words_map().into_iter().map(|(k, v)| (k, v.into())).collect();
+                map.insert(key, words(self));
+                Value::Object(map)
+            }
+        }
+    }
+
+    fn place(&mut self, depth: u32) -> GeonPlace {
+        let mut place = GeonPlace::default();
+        place.place = self.words(" ");
+        place.type_ = self.optional(|u| u.choose(TYPES).to_string()).unwrap_or_default();
+        place.id = self.optional(|u| format!("test:{}", u.int_to(9999)));
+        place.location = self.optional(Unstructured::arbitrary);
+        place.boundary = self.list(MAX_ITEMS, Unstructured::arbitrary);
+        place.path = self.list(MAX_ITEMS, Unstructured::arbitrary);
+        place.extent = self.optional(Unstructured::arbitrary);
+        place.elevation = self.optional(|u| format!("{} m", u.int_to(4000)));
+        place.area = self.optional(|u| format!("{} ha", u.int_to(500)));
+        place.purpose = self.list(MAX_ITEMS, |u| u.words(" "));
+        place.experience = self
+            .list(MAX_ITEMS, |u| {
+                let (key, _) = *u.choose(EXPERIENCE_KEYS);
+                (key.to_string(), u.choose(scale_for(key)).to_string())
+            })
+            .into_iter()
+            .collect();
+        place.character = self.list(MAX_ITEMS, |u| u.words(" "));
+        place.adjacencies = self.list(MAX_ITEMS, |u| u.words(" "));
+        place.connectivity = self.words_map();
+        if depth < MAX_DEPTH {
+            place.contains = self.list(MAX_CHILDREN, |u| u.place(depth + 1));
+        }
+        place.part_of = self.optional(|u| u.words(" "));
+        let viewsheds = self.words_map();
+        if !viewsheds.is_empty() {
+            place.viewsheds = Value::Object(viewsheds.into_iter().map(|(k, v)| (k, v.into())).collect());
+        }
+        place.temporal = self.words_map();
+        place.lifespan = self.map(|u, _| u.int_to(2025).to_string());
+        place.source = self.list(MAX_ITEMS, |u| u.words(" "));
+        place.confidence = self.map(|u, _| u.choose(CONFIDENCE).to_string());
+        place.updated = self.optional(|u| format!("{}-{:02}-{:02}", 1900 + u.int_to(125), 1 + u.int_to(11), 1 + u.int_to(27)));
+        place.built_form = self.words_map();
+        place.ecology = self.words_map();
+        place.infrastructure = self.words_map();
+        place.demographics = self.words_map();
+        place.economy = self.words_map();
+        place.visual = self.words_map();
+        place.history = self.list(MAX_ITEMS, |u| {
+            let mut entry = u.words_map();
+            entry.insert("date".to_string(), (1000 + u.int_to(1025)).to_string());
+            entry
+        });
+        place.vertical_profile = self.words_map();
+        // Upper-case keys and `addr:` tags, neither of which is a section
+        place.extra = self
+            .list(MAX_ITEMS, |u| {
+                let key = if u.more() { format!("addr:{}", u.words("_")) } else { u.words("_").to_uppercase() };
+                (key, u.json())
+            })
+            .into_iter()
+            .collect();
+        place
+    }
+}
+
+/// A type that can be built from any [`Unstructured`] bytes.
+pub trait Arbitrary: Sized {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self;
+}
+
+/// A point to a millionth of a degree.
+impl Arbitrary for Coordinate {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        Coordinate::new(u.degrees(90), u.degrees(180))
+    }
+}
+
+/// North at or above south; west past east crosses the antimeridian.
+impl Arbitrary for Extent {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let (a, b) = (u.degrees(90), u.degrees(90));
+        Extent { north: a.max(b), south: a.min(b), east: u.degrees(180), west: u.degrees(180) }
+    }
+}
+
+/// Every field may be filled, and places nest up to two levels deep.
+impl Arbitrary for GeonPlace {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.place(0)
+    }
+}
+
+// splitmix64, for test data that is the same on every run
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn holds<T: Arbitrary>(data: &[u8], property: &impl Fn(&T) -> bool) -> bool {
+    property(&T::arbitrary(&mut Unstructured::new(data)))
+}
+
+// Smaller data that still fails: cut chunks out, then lower bytes, until
+// no single step helps
+fn shrink<T: Arbitrary>(mut data: Vec<u8>, property: &impl Fn(&T) -> bool) -> Vec<u8> {
+    loop {
+        let before = data.clone();
+        let mut chunk = data.len().max(1);
+        while chunk > 0 {
+            let mut at = 0;
+            while at + chunk <= data.len() {
+                let mut candidate = data.clone();
+                candidate.drain(at..at + chunk);
+                if holds(&candidate, property) { at += chunk } else { data = candidate }
+            }
+            chunk /= 2;
+        }
+        for i in 0..data.len() {
+            for smaller in [0, data[i] / 2, data[i].saturating_sub(1)] {
+                if smaller < data[i] {
+                    let mut candidate = data.clone();
+                    candidate[i] = smaller;
+                    if !holds(&candidate, property) {
+                        data = candidate;
+                        break;
+                    }
+                }
+            }
+        }
+        if data == before {
+            return data;
+        }
+    }
+}
+
+/// The smallest value found among `cases` random values of `T` for which
+/// `property` is false, with the bytes that build it, or `None` if it held
+/// for all of them. The same cases are tried on every run.
+pub fn find_failure<T: Arbitrary>(cases: usize, property: impl Fn(&T) -> bool) -> Option<(T, Vec<u8>)> {
+    let mut state = 0x6765_6f6e;
+    for _ in 0..cases {
+        let len = next_random(&mut state) % 512;
+        let data: Vec<u8> = (0..len).map(|_| next_random(&mut state) as u8).collect();
+        if !holds(&data, &property) {
+            let data = shrink(data, &property);
+            return Some((T::arbitrary(&mut Unstructured::new(&data)), data));
+        }
+    }
+    None
+}
+
+/// Panic with the smallest counterexample if `property` fails for any of
+/// `cases` random values of `T`.
+pub fn check<T: Arbitrary + Debug>(cases: usize, property: impl Fn(&T) -> bool) {
+    if let Some((value, data)) = find_failure(cases, property) {
+        panic!("property failed for {:#?}\n(built from bytes {:?})", value, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_round_trips() {
+        check(300, |place: &GeonPlace| parse(&generate(place)) == *place);
+        check(300, |place: &GeonPlace| {
+            serde_json::from_value::<GeonPlace>(serde_json::to_value(place).unwrap()).unwrap() == *place
+        });
+        check(300, |e: &Extent| e.north >= e.south && (-180.0..=180.0).contains(&e.west));
+
+        // Some cases nest places two deep and fill the provenance, HISTORY and extra fields
+        assert!(find_failure(300, |p: &GeonPlace| p.contains.iter().all(|c| c.contains.is_empty())).is_some());
+        assert!(find_failure(300, |p: &GeonPlace| p.history.is_empty() || p.confidence.is_empty() || p.extra.is_empty()).is_some());
+    }
+
+    #[test]
+    fn test_shrinking() {
+        assert_eq!(GeonPlace::arbitrary(&mut Unstructured::new(&[])).place, "market");
+        let (place, data) = find_failure(300, |p: &GeonPlace| p.contains.len() < 2).unwrap();
+        assert_eq!(place.contains.len(), 2);
+        assert!(place.location.is_none() && place.purpose.is_empty() && place.contains[0].location.is_none());
+        // Each field of the first child takes a byte, zero for "none"
+        assert!(data.len() < 80, "{:?}", data);
+    }
+}
@@ -0,0 +1,299 @@
+//! A collection's query API as tools a language model can call: the
+//! definitions to offer it ([`definitions`]) and a dispatcher for its calls
+//! ([`call`]).
+//!
+//! The tools only read the collection. Arguments are checked against each
+//! tool's schema, unknown ones included, and results are capped at
+//! [`MAX_RESULTS`] places, so a model can be handed a corpus without being
+//! able to change it or flood its own context.
+
+use crate::collection::{GeonCollection, Query, Resolution};
+use crate::generator::generate;
+use crate::models::{Coordinate, Extent, GeonPlace};
+use crate::parser::GeonError;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+/// Most places one call returns.
+pub const MAX_RESULTS: usize = 50;
+// Results when the model gives no `limit`
+const DEFAULT_LIMIT: usize = 10;
+// Widest `places_near` search, in metres
+const MAX_RADIUS_M: f64 = 50_000.0;
+
+/// A tool as MCP's `tools/list` describes one: a name, what it does, and a
+/// JSON Schema of its arguments. Other function-calling APIs take the same
+/// three fields under their own names.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(rename = "inputSchema")]
+    pub input_schema: Value,
+}
+
+/// The tools [`call`] answers: `find_places`, `describe_place` and
+/// `places_near`.
+pub fn definitions() -> Vec<ToolDefinition> {
+    let limit = json!({
+        "type": "integer", "minimum": 1, "maximum": MAX_RESULTS,
+        "description": format!("Most places to return (default {}).", DEFAULT_LIMIT),
+    });
+    vec![
+        ToolDefinition {
+            name: "find_places",
+            description: "Search the places in the corpus by words in their names, purposes and character, \
+                          by TYPE, by what they are part of, or by bounding box. Returns short summaries; \
+                          use describe_place for the full description.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {"type": "string", "description": "Words to look for, e.g. \"quiet green space\"."},
+                    "type": {"type": "string", "description": "GEON TYPE, e.g. public_space, building, street."},
+                    "part_of": {"type": "string", "description": "Name or ID of a place the results are part of."},
+                    "bbox": {
+                        "type": "array", "items": {"type": "number"}, "minItems": 4, "maxItems": 4,
+                        "description": "[west, south, east, north] in degrees.",
+                    },
+                    "limit": limit,
+                },
+                "additionalProperties": false,
+            }),
+        },
+        ToolDefinition {
+            name: "describe_place",
+            description: "The full GEON description of one place: its geometry, purposes, experience, \
+                          connections and history.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "place": {"type": "string", "description": "The place's ID, or its name if unique."},
+                },
+                "required": ["place"],
+                "additionalProperties": false,
+            }),
+        },
+        ToolDefinition {
+            name: "places_near",
+            description: "Places within a distance of a point, nearest first, with their distances in metres.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "lat": {"type": "number", "minimum": -90, "maximum": 90},
+                    "lon": {"type": "number", "minimum": -180, "maximum": 180},
+                    "radius_m": {
+                        "type": "number", "exclusiveMinimum": 0, "maximum": MAX_RADIUS_M,
+                        "description": "Search radius in metres (default 500).",
+                    },
+                    "type": {"type": "string", "description": "Only places of this GEON TYPE."},
+                    "limit": limit,
+                },
+                "required": ["lat", "lon"],
+                "additionalProperties": false,
+            }),
+        },
+    ]
+}
+
+fn invalid(message: String) -> GeonError {
+    GeonError::InvalidStructure(message)
+}
+
+// A call's arguments, checked against its tool's schema
+struct Args {
+    map: Map<String, Value>,
+}
+
+impl Args {
+    fn new(tool: &ToolDefinition, arguments: &Value) -> Result<Self, GeonError> {
+        let map = match arguments {
+            Value::Object(map) => map.clone(),
+            Value::Null => Map::new(),
+            _ => return Err(invalid(format!("{}: arguments must be an object", tool.name))),
+        };
+        let schema = &tool.input_schema;
+        if let Some(key) = map.keys().find(|k| schema["properties"].get(k.as_str()).is_none()) {
+            return Err(invalid(format!("{}: unknown argument {}", tool.name, key)));
+        }
+        let required = schema["required"].as_array().map(Vec::as_slice).unwrap_or_default();
+        if let Some(key) = required.iter().filter_map(Value::as_str).find(|k| !map.contains_key(*k)) {
+            return Err(invalid(format!("{}: missing argument {}", tool.name, key)));
+        }
+        Ok(Self { map })
+    }
+
+    fn str(&self, key: &str) -> Result<Option<&str>, GeonError> {
+        match self.map.get(key) {
+            None => Ok(None),
+            Some(v) => v.as_str().map(Some).ok_or_else(|| invalid(format!("{} must be a string", key))),
+        }
+    }
+
+    // A number within `min..=max`
+    fn number(&self, key: &str, min: f64, max: f64) -> Result<Option<f64>, GeonError> {
+        match self.map.get(key) {
+            None => Ok(None),
+            Some(v) => match v.as_f64() {
+                Some(n) if (min..=max).contains(&n) => Ok(Some(n)),
+                _ => Err(invalid(format!("{} must be a number from {} to {}", key, min, max))),
+            },
+        }
+    }
+
+    fn limit(&self) -> Result<usize, GeonError> {
+        Ok(self.number("limit", 1.0, MAX_RESULTS as f64)?.map_or(DEFAULT_LIMIT, |n| n as usize))
+    }
+
+    fn bbox(&self) -> Result<Option<Extent>, GeonError> {
+        let Some(v) = self.map.get("bbox") else { return Ok(None) };
+        let numbers: Option<Vec<f64>> = v.as_array().and_then(|a| a.iter().map(Value::as_f64).collect());
+        match numbers.as_deref() {
+            Some(&[west, south, east, north]) => Ok(Some(Extent { north, south, east, west })),
+            _ => Err(invalid("bbox must be [west, south, east, north]".to_string())),
+        }
+    }
+}
+
+// What a model needs to pick a place out of a list
+fn summary(place: &GeonPlace) -> Value {
+    let mut s = json!({ "name": place.place });
+    if let Some(id) = &place.id {
+        s["id"] = json!(id);
+    }
+    if !place.type_.is_empty() {
+        s["type"] = json!(place.type_);
+    }
+    if let Some(location) = &place.location {
+        s["location"] = json!(location);
+    }
+    if let Some(part_of) = &place.part_of {
+        s["part_of"] = json!(part_of);
+    }
+    s
+}
+
+fn find_places(collection: &GeonCollection, args: &Args) -> Result<Value, GeonError> {
+    let mut query = Query::All;
+    if let Some(type_) = args.str("type")? {
+        query = query.and(Query::type_is(type_));
+    }
+    if let Some(name) = args.str("part_of")? {
+        query = query.and(Query::part_of(name));
+    }
+    let mut found: Vec<&GeonPlace> = match args.str("text")? {
+        Some(text) => collection.search(text).into_iter().map(|(p, _)| p).collect(),
+        None => collection.iter().collect(),
+    };
+    if let Some(extent) = args.bbox()? {
+        let inside: HashSet<*const GeonPlace> = collection.query_bbox(&extent).into_iter().map(|p| p as *const _).collect();
+        found.retain(|p| inside.contains(&(*p as *const _)));
+    }
+    found.retain(|p| query.matches(p));
+    let places: Vec<Value> = found.iter().take(args.limit()?).map(|p| summary(p)).collect();
+    Ok(json!({ "total": found.len(), "places": places }))
+}
+
+fn describe_place(collection: &GeonCollection, args: &Args) -> Result<Value, GeonError> {
+    let reference = args.str("place")?.unwrap_or_default();
+    let registry = collection.registry();
+    match registry.resolve(reference) {
+        Resolution::Resolved(handle) => {
+            let place = handle.get(collection).expect("registry handles point into the collection");
+            Ok(json!({ "name": place.place, "geon": generate(place) }))
+        }
+        Resolution::Ambiguous(handles) => {
+            let matches: Vec<Value> = handles.iter().filter_map(|h| h.get(collection)).map(summary).collect();
+            Err(invalid(format!("{} names {} places; use one of their IDs: {}", reference, matches.len(), json!(matches))))
+        }
+        Resolution::Unresolved => Err(invalid(format!("no place called {}", reference))),
+    }
+}
+
+fn places_near(collection: &GeonCollection, args: &Args) -> Result<Value, GeonError> {
+    let lat = args.number("lat", -90.0, 90.0)?.unwrap_or_default();
+    let lon = args.number("lon", -180.0, 180.0)?.unwrap_or_default();
+    let radius = args.number("radius_m", f64::MIN_POSITIVE, MAX_RADIUS_M)?.unwrap_or(500.0);
+    let query = args.str("type")?.map_or(Query::All, Query::type_is);
+    let places: Vec<Value> = collection
+        .nearest_matching(&Coordinate::new(lat, lon), &query, args.limit()?)
+        .into_iter()
+        .take_while(|(_, d)| *d <= radius)
+        .map(|(p, d)| {
+            let mut s = summary(p);
+            s["distance_m"] = json!(d.round());
+            s
+        })
+        .collect();
+    Ok(json!({ "places": places }))
+}
+
+/// Run the tool `name` over `collection` with the model's `arguments`. An
+/// error (a tool that doesn't exist, bad arguments, a place that can't be
+/// found) is meant to go back to the model, which can correct its call.
+pub fn call(collection: &GeonCollection, name: &str, arguments: &Value) -> Result<Value, GeonError> {
+    let tools = definitions();
+    let tool = tools.iter().find(|t| t.name == name).ok_or_else(|| invalid(format!("no tool called {}", name)))?;
+    let args = Args::new(tool, arguments)?;
+    match name {
+        "find_places" => find_places(collection, &args),
+        "describe_place" => describe_place(collection, &args),
+        "places_near" => places_near(collection, &args),
+        _ => unreachable!("every definition has a handler"),
+    }
+}
+
+/// Answer MCP's `tools/call` with `params` of `{"name", "arguments"}`: the
+/// result of [`call`] as a text content block, or its error with `isError`.
+pub fn mcp_call(collection: &GeonCollection, params: &Value) -> Value {
+    let name = params["name"].as_str().unwrap_or_default();
+    match call(collection, name, &params["arguments"]) {
+        Ok(result) => json!({ "content": [{ "type": "text", "text": result.to_string() }], "isError": false }),
+        Err(e) => json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_tools() {
+        let collection = GeonCollection::new(vec![
+            parse("PLACE: Old Market Square\nID: osm:way/1\nTYPE: public_space\nLOCATION: 52.9533, -1.1500\nPURPOSE:\n  - markets\n  - gathering"),
+            parse("PLACE: Council House\nID: osm:way/2\nTYPE: building\nLOCATION: 52.9536, -1.1492\nPART_OF: Old Market Square"),
+            parse("PLACE: Arboretum\nTYPE: public_space\nLOCATION: 52.9610, -1.1560"),
+        ]);
+        let names = |v: &Value| v["places"].as_array().unwrap().iter().map(|p| p["name"].clone()).collect::<Vec<_>>();
+
+        assert_eq!(definitions().iter().map(|t| t.name).collect::<Vec<_>>(), ["find_places", "describe_place", "places_near"]);
+        let found = call(&collection, "find_places", &json!({"type": "public_space", "limit": 1})).unwrap();
+        assert_eq!((found["total"].clone(), names(&found)), (json!(2), vec![json!("Old Market Square")]));
+        let found = call(&collection, "find_places", &json!({"text": "market"})).unwrap();
+        assert_eq!(names(&found), [json!("Old Market Square")]);
+        let found = call(&collection, "find_places", &json!({"part_of": "old market square"})).unwrap();
+        assert_eq!(found["places"][0]["id"], "osm:way/2");
+        let found = call(&collection, "find_places", &json!({"type": "public_space", "bbox": [-1.157, 52.955, -1.155, 52.962]})).unwrap();
+        assert_eq!(names(&found), [json!("Arboretum")]);
+
+        let described = call(&collection, "describe_place", &json!({"place": "osm:way/1"})).unwrap();
+        assert!(described["geon"].as_str().unwrap().contains("- markets"));
+        let near = call(&collection, "places_near", &json!({"lat": 52.9536, "lon": -1.1493, "radius_m": 200})).unwrap();
+        assert_eq!(names(&near), [json!("Council House"), json!("Old Market Square")]);
+        assert!(near["places"][0]["distance_m"].as_f64().unwrap() < 10.0);
+
+        for (name, arguments) in [
+            ("drop_table", json!({})),
+            ("find_places", json!({"sql": "1=1"})),
+            ("find_places", json!({"limit": 500})),
+            ("describe_place", json!({})),
+            ("describe_place", json!({"place": "Bandstand"})),
+            ("places_near", json!({"lat": 95, "lon": 0})),
+        ] {
+            assert!(call(&collection, name, &arguments).is_err(), "{} {}", name, arguments);
+        }
+        let result = mcp_call(&collection, &json!({"name": "places_near", "arguments": {"lat": "north"}}));
+        assert_eq!(result["isError"], true);
+    }
+}
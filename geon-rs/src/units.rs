@@ -0,0 +1,227 @@
+//! Converting and formatting areas and lengths, so computed quantities can
+//! be written in whichever unit a corpus already uses (`1.2 ha`, `56 m`,
+//! `3 acres`). Reading them back is [`quantity`](crate::quantity)'s job.
+
+use crate::quantity::{area_unit, length_unit, parse_area, parse_length};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// A unit of area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AreaUnit {
+    SquareMetres,
+    Hectares,
+    SquareKilometres,
+    Acres,
+    SquareFeet,
+}
+
+impl AreaUnit {
+    /// Size of one unit in square metres.
+    pub const fn square_metres(self) -> f64 {
+        match self {
+            Self::SquareMetres => 1.0,
+            Self::Hectares => 10_000.0,
+            Self::SquareKilometres => 1_000_000.0,
+            Self::Acres => 4_046.856_422_4,
+            Self::SquareFeet => 0.092_903_04,
+        }
+    }
+
+    /// The spelling used when formatting, e.g. `ha`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::SquareMetres => "sqm",
+            Self::Hectares => "ha",
+            Self::SquareKilometres => "km²",
+            Self::Acres => "acres",
+            Self::SquareFeet => "sq ft",
+        }
+    }
+}
+
+/// A unit of length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    Metres,
+    Kilometres,
+    Feet,
+    Miles,
+}
+
+impl LengthUnit {
+    /// Size of one unit in metres.
+    pub const fn metres(self) -> f64 {
+        match self {
+            Self::Metres => 1.0,
+            Self::Kilometres => 1_000.0,
+            Self::Feet => 0.3048,
+            Self::Miles => 1_609.344,
+        }
+    }
+
+    /// The spelling used when formatting, e.g. `ft`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Metres => "m",
+            Self::Kilometres => "km",
+            Self::Feet => "ft",
+            Self::Miles => "mi",
+        }
+    }
+}
+
+/// `value` in `from` units, expressed in `to` units.
+pub fn convert_area(value: f64, from: AreaUnit, to: AreaUnit) -> f64 {
+    value * from.square_metres() / to.square_metres()
+}
+
+/// `value` in `from` units, expressed in `to` units.
+pub fn convert_length(value: f64, from: LengthUnit, to: LengthUnit) -> f64 {
+    value * from.metres() / to.metres()
+}
+
+// `value` rounded to `decimals` places without trailing zeros, then the unit
+fn format_quantity(value: f64, decimals: usize, symbol: &str) -> String {
+    let mut number = format!("{:.*}", decimals, value);
+    if number.contains('.') {
+        number = number.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+    if number == "-0" {
+        number = "0".to_string();
+    }
+    format!("{} {}", number, symbol)
+}
+
+/// An area, held in square metres.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Area(pub f64);
+
+impl Area {
+    pub fn new(value: f64, unit: AreaUnit) -> Self {
+        Self(value * unit.square_metres())
+    }
+
+    /// Read an AREA value such as `1.2 ha` (see
+    /// [`parse_area`](crate::quantity::parse_area)).
+    pub fn parse(text: &str) -> Option<Self> {
+        parse_area(text).map(Self)
+    }
+
+    pub fn square_metres(self) -> f64 {
+        self.0
+    }
+
+    pub fn to(self, unit: AreaUnit) -> f64 {
+        self.0 / unit.square_metres()
+    }
+
+    /// The area in `unit`, e.g. `1.25 ha` with two decimals.
+    pub fn format(self, unit: AreaUnit, decimals: usize) -> String {
+        format_quantity(self.to(unit), decimals, unit.symbol())
+    }
+}
+
+/// Square metres below a hectare, hectares below a square kilometre, and
+/// square kilometres beyond, to one decimal.
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.0.abs() {
+            a if a < 10_000.0 => AreaUnit::SquareMetres,
+            a if a < 1_000_000.0 => AreaUnit::Hectares,
+            _ => AreaUnit::SquareKilometres,
+        };
+        let decimals = if unit == AreaUnit::SquareMetres { 0 } else { 1 };
+        f.write_str(&self.format(unit, decimals))
+    }
+}
+
+/// A height or length, held in metres.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Elevation(pub f64);
+
+impl Elevation {
+    pub fn new(value: f64, unit: LengthUnit) -> Self {
+        Self(value * unit.metres())
+    }
+
+    /// Read an ELEVATION value such as `142 m` (see
+    /// [`parse_length`](crate::quantity::parse_length)).
+    pub fn parse(text: &str) -> Option<Self> {
+        parse_length(text).map(Self)
+    }
+
+    pub fn metres(self) -> f64 {
+        self.0
+    }
+
+    pub fn to(self, unit: LengthUnit) -> f64 {
+        self.0 / unit.metres()
+    }
+
+    /// The length in `unit`, e.g. `466 ft` with no decimals.
+    pub fn format(self, unit: LengthUnit, decimals: usize) -> String {
+        format_quantity(self.to(unit), decimals, unit.symbol())
+    }
+}
+
+/// Whole metres, e.g. `142 m`.
+impl fmt::Display for Elevation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(LengthUnit::Metres, 0))
+    }
+}
+
+// The most common item, earliest first among ties
+fn most_common<U: Copy + Eq + Hash>(units: impl Iterator<Item = U>) -> Option<U> {
+    let mut counts: HashMap<U, (usize, usize)> = HashMap::new();
+    for (i, unit) in units.enumerate() {
+        counts.entry(unit).or_insert((0, i)).0 += 1;
+    }
+    counts.into_iter().max_by(|a, b| a.1.0.cmp(&b.1.0).then(b.1.1.cmp(&a.1.1))).map(|(u, _)| u)
+}
+
+/// The unit most of `values` (AREA texts) are written in; bare numbers count
+/// as square metres and unreadable values are ignored.
+pub fn preferred_area_unit<'a>(values: impl IntoIterator<Item = &'a str>) -> Option<AreaUnit> {
+    most_common(values.into_iter().filter_map(area_unit))
+}
+
+/// The unit most of `values` (ELEVATION or length texts) are written in.
+pub fn preferred_length_unit<'a>(values: impl IntoIterator<Item = &'a str>) -> Option<LengthUnit> {
+    most_common(values.into_iter().filter_map(length_unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_and_format() {
+        assert_eq!(convert_area(1.0, AreaUnit::SquareKilometres, AreaUnit::Hectares), 100.0);
+        assert!((convert_area(1.0, AreaUnit::Hectares, AreaUnit::Acres) - 2.471).abs() < 1e-3);
+        assert!((convert_length(1.0, LengthUnit::Miles, LengthUnit::Feet) - 5280.0).abs() < 1e-9);
+
+        let square = Area::parse("12,600 sqm").unwrap();
+        assert_eq!(square.format(AreaUnit::Hectares, 2), "1.26 ha");
+        assert_eq!(square.format(AreaUnit::Acres, 1), "3.1 acres");
+        assert_eq!(square.to_string(), "1.3 ha");
+        assert_eq!(Area(420.4).to_string(), "420 sqm");
+        assert_eq!(Area::new(2.5, AreaUnit::SquareKilometres).to_string(), "2.5 km²");
+
+        let peak = Elevation::parse("142 m").unwrap();
+        assert_eq!(peak.to_string(), "142 m");
+        assert_eq!(peak.format(LengthUnit::Feet, 0), "466 ft");
+        assert_eq!(Elevation::new(3.0, LengthUnit::Kilometres).format(LengthUnit::Kilometres, 2), "3 km");
+    }
+
+    #[test]
+    fn test_preferred_unit() {
+        let areas = ["2 acres", "40", "1.5 acres", "3 ha", "large"];
+        assert_eq!(preferred_area_unit(areas), Some(AreaUnit::Acres));
+        assert_eq!(preferred_area_unit(["12 sqm", "1 ha"]), Some(AreaUnit::SquareMetres));
+        assert_eq!(preferred_length_unit(["30 ft", "1,200 feet", "56 m"]), Some(LengthUnit::Feet));
+        assert_eq!(preferred_area_unit([]), None);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::collection::{GeonCollection, Resolution};
+use crate::geometry::{validate_ring, RingDefect};
+use crate::graph::PlaceGraph;
+use crate::parser::GeonError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        })
+    }
+}
+
+/// A problem found in a place or collection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    /// Name of the [`Rule`] that found it, e.g. `duplicate-id`.
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Place the issue is about, by ID or name.
+    pub place: String,
+    /// GEON key the issue is about, e.g. `PART_OF`.
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}: {}", self.severity, self.place, self.field, self.message)
+    }
+}
+
+/// How much [`lint_collection`] checks. Each profile runs the rules of the
+/// ones before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Structural problems only: what [`validate_collection`] reports.
+    Minimal,
+    /// Also places missing the fields most uses of a corpus need.
+    #[default]
+    Recommended,
+    /// Also places without provenance.
+    Strict,
+}
+
+/// A named check, with its severity and the profile it starts in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    pub name: &'static str,
+    pub severity: Severity,
+    pub profile: Profile,
+    pub description: &'static str,
+}
+
+const fn rule(name: &'static str, severity: Severity, profile: Profile, description: &'static str) -> Rule {
+    Rule { name, severity, profile, description }
+}
+
+/// Every rule [`lint_collection`] knows.
+pub const RULES: &[Rule] = &[
+    rule("too-few-points", Severity::Error, Profile::Minimal, "BOUNDARY has fewer than three distinct points"),
+    rule("self-intersection", Severity::Error, Profile::Minimal, "BOUNDARY edges cross"),
+    rule("repeated-point", Severity::Warning, Profile::Minimal, "BOUNDARY repeats a point"),
+    rule("spike", Severity::Warning, Profile::Minimal, "BOUNDARY doubles back on itself"),
+    rule("part-of-self", Severity::Error, Profile::Minimal, "PART_OF names the place itself"),
+    rule("containment-cycle", Severity::Error, Profile::Minimal, "PART_OF and CONTAINS form a loop"),
+    rule("duplicate-id", Severity::Error, Profile::Minimal, "ID is used by more than one place"),
+    rule("ambiguous-reference", Severity::Warning, Profile::Minimal, "a reference matches more than one place"),
+    rule("missing-type", Severity::Warning, Profile::Recommended, "place has no TYPE"),
+    rule("missing-location", Severity::Warning, Profile::Recommended, "place has no LOCATION or BOUNDARY"),
+    rule("missing-source", Severity::Info, Profile::Strict, "place has no SOURCE"),
+];
+
+/// The rule called `name`.
+pub fn find_rule(name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.name == name)
+}
+
+/// A configured rule: `off`, `on` at its own severity whatever the profile,
+/// or on at the given severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    On,
+    Error,
+    Warning,
+    Info,
+}
+
+/// Which rules [`lint_collection`] runs, as read from a `.geonlint.toml`:
+///
+/// ```toml
+/// profile = "strict"
+///
+/// [rules]
+/// missing-source = "off"
+/// spike = "error"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub profile: Profile,
+    /// Settings by rule name, overriding the profile.
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+impl LintConfig {
+    pub fn new(profile: Profile) -> Self {
+        Self { profile, rules: BTreeMap::new() }
+    }
+
+    /// Read a config from JSON.
+    pub fn from_json(text: &str) -> Result<Self, GeonError> {
+        Self::from_value(serde_json::from_str(text).map_err(|e| GeonError::InvalidStructure(format!("lint config: {}", e)))?)
+    }
+
+    /// Read a config from TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, GeonError> {
+        Self::from_value(crate::io::toml::parse_value(text)?)
+    }
+
+    // Deserialise, rejecting rules that don't exist so typos don't pass silently
+    fn from_value(value: serde_json::Value) -> Result<Self, GeonError> {
+        let config: Self =
+            serde_json::from_value(value).map_err(|e| GeonError::InvalidStructure(format!("lint config: {}", e)))?;
+        match config.rules.keys().find(|name| find_rule(name).is_none()) {
+            Some(name) => Err(GeonError::InvalidStructure(format!("lint config: unknown rule '{}'", name))),
+            None => Ok(config),
+        }
+    }
+
+    /// The severity `rule` reports at, or `None` if it is off.
+    pub fn severity(&self, rule: &Rule) -> Option<Severity> {
+        match self.rules.get(rule.name) {
+            Some(RuleLevel::Off) => None,
+            Some(RuleLevel::On) => Some(rule.severity),
+            Some(RuleLevel::Error) => Some(Severity::Error),
+            Some(RuleLevel::Warning) => Some(Severity::Warning),
+            Some(RuleLevel::Info) => Some(Severity::Info),
+            None => Some(rule.severity).filter(|_| rule.profile <= self.profile),
+        }
+    }
+}
+
+// An issue at its rule's own severity; configuration adjusts it afterwards
+fn issue(rule: &'static str, place: &str, field: &str, message: String) -> Issue {
+    let severity = find_rule(rule).expect("rule is in RULES").severity;
+    Issue { rule, severity, place: place.to_string(), field: field.to_string(), message }
+}
+
+/// Check a collection as a whole. Reports, as errors, places that are
+/// PART_OF themselves, cycles of containment through PART_OF and CONTAINS
+/// (A contains B, B is part of A's child, ...), which would send any
+/// recursive walk of the hierarchy round forever, and IDs used by more than
+/// one place. Self-intersecting BOUNDARY rings are errors, as are rings
+/// with fewer than three distinct points; repeated points and zero-area
+/// spikes (see [`validate_ring`]) are warnings, as are PART_OF and
+/// ADJACENCIES entries naming more than one place.
+pub fn validate_collection(collection: &GeonCollection) -> Vec<Issue> {
+    lint_collection(collection, &LintConfig::new(Profile::Minimal))
+}
+
+/// Run the rules `config` turns on (see [`RULES`]) over a collection.
+pub fn lint_collection(collection: &GeonCollection, config: &LintConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let graph = PlaceGraph::from_collection(collection);
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let Some(place) = graph.place(i, collection) else { continue };
+        if !place.boundary.is_empty() {
+            for defect in validate_ring(&place.boundary) {
+                let rule = match defect {
+                    RingDefect::TooFewPoints(_) => "too-few-points",
+                    RingDefect::SelfIntersection { .. } => "self-intersection",
+                    RingDefect::RepeatedPoint { .. } => "repeated-point",
+                    RingDefect::Spike { .. } => "spike",
+                };
+                issues.push(issue(rule, &node.key, "BOUNDARY", defect.to_string()));
+            }
+        }
+        let own = |name: &str| name.eq_ignore_ascii_case(&place.place) || place.id.as_deref() == Some(name);
+        if place.part_of.as_deref().is_some_and(|chain| chain.split(',').any(|name| own(name.trim()))) {
+            issues.push(issue("part-of-self", &node.key, "PART_OF", "place is part of itself".to_string()));
+        }
+    }
+
+    for cycle in graph.containment_cycles() {
+        let mut names: Vec<&str> = cycle.iter().map(|n| n.key.as_str()).collect();
+        names.push(names[0]);
+        let message = format!("containment cycle: {}", names.join(" contains "));
+        issues.push(issue("containment-cycle", &cycle[0].key, "PART_OF", message));
+    }
+
+    let registry = collection.registry();
+    for (id, handles) in registry.duplicate_ids() {
+        issues.push(issue("duplicate-id", id, "ID", format!("ID is used by {} places", handles.len())));
+    }
+    for reference in registry.references(collection) {
+        if let Resolution::Ambiguous(handles) = &reference.target {
+            let from = reference.from.get(collection).expect("reference from a registered place");
+            let message = format!("'{}' could be any of {} places", reference.name, handles.len());
+            issues.push(issue("ambiguous-reference", from.id.as_deref().unwrap_or(&from.place), reference.field, message));
+        }
+    }
+
+    // Completeness of top-level places; nested ones take these from their parent
+    for place in &collection.places {
+        let key = place.id.as_deref().unwrap_or(&place.place);
+        if place.type_.is_empty() {
+            issues.push(issue("missing-type", key, "TYPE", "place has no TYPE".to_string()));
+        }
+        if place.location.is_none() && place.boundary.is_empty() {
+            issues.push(issue("missing-location", key, "LOCATION", "place has no LOCATION or BOUNDARY".to_string()));
+        }
+        if place.source.is_empty() {
+            issues.push(issue("missing-source", key, "SOURCE", "place has no SOURCE".to_string()));
+        }
+    }
+
+    issues.retain_mut(|issue| {
+        let rule = find_rule(issue.rule).expect("rule is in RULES");
+        config.severity(rule).map(|severity| issue.severity = severity).is_some()
+    });
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Coordinate, GeonPlace};
+
+    fn place(name: &str, part_of: &str) -> GeonPlace {
+        let mut p = GeonPlace::default();
+        p.place = name.to_string();
+        p.part_of = Some(part_of.to_string()).filter(|s| !s.is_empty());
+        p
+    }
+
+    #[test]
+    fn test_containment_cycles() {
+        // Market contains Stall; Stall's Corner claims to contain Market
+        let mut market = place("Market", "Corner");
+        let mut stall = place("Stall", "");
+        stall.contains = vec![place("Corner", "")];
+        market.contains = vec![stall];
+        let collection = GeonCollection::new(vec![market, place("Hall", "Hall"), place("Square", "City")]);
+
+        let issues = validate_collection(&collection);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].to_string(), "[error] Hall: PART_OF: place is part of itself");
+        assert_eq!(issues[1].message, "containment cycle: Market contains Stall contains Corner contains Market");
+        assert!(validate_collection(&GeonCollection::new(vec![place("Square", "City")])).is_empty());
+
+        let streets = GeonCollection::new(vec![place("Square", "High Street"), place("High Street", ""), place("High Street", "")]);
+        let issues = validate_collection(&streets);
+        assert_eq!(issues[0].to_string(), "[warning] Square: PART_OF: 'High Street' could be any of 2 places");
+    }
+
+    #[test]
+    fn test_boundary_defects() {
+        let ring = |points: &[(f64, f64)]| points.iter().map(|&(lat, lon)| Coordinate::new(lat, lon)).collect::<Vec<_>>();
+        let square = ring(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+        assert!(validate_ring(&square).is_empty());
+
+        // Bow-tie: edges 0 -> 1 and 2 -> 3 cross
+        let bowtie = ring(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        assert_eq!(validate_ring(&bowtie), vec![RingDefect::SelfIntersection { first: 0, second: 2 }]);
+
+        let spiky = ring(&[(0.0, 0.0), (0.0, 1.0), (0.0, 1.0), (0.5, 1.0), (0.5, 3.0), (0.5, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+        let defects = validate_ring(&spiky);
+        assert_eq!(defects[0], RingDefect::RepeatedPoint { index: 2 });
+        assert!(defects.contains(&RingDefect::Spike { index: 4 }));
+        assert_eq!(validate_ring(&ring(&[(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)])), vec![RingDefect::TooFewPoints(2)]);
+
+        let mut park = place("Park", "");
+        park.boundary = bowtie;
+        let issues = validate_collection(&GeonCollection::new(vec![park]));
+        assert_eq!(issues[0].to_string(), "[error] Park: BOUNDARY: edges from points 0 and 2 intersect");
+    }
+
+    #[test]
+    fn test_lint_config() {
+        let mut kiosk = place("Kiosk", "");
+        kiosk.type_ = "building".to_string();
+        let collection = GeonCollection::new(vec![kiosk, place("Hall", "Hall")]);
+        let rules = |config: &LintConfig| -> Vec<String> {
+            lint_collection(&collection, config).iter().map(|i| format!("{} {} {}", i.severity, i.rule, i.place)).collect()
+        };
+
+        assert_eq!(
+            rules(&LintConfig::default()),
+            [
+                "error part-of-self Hall",
+                "warning missing-location Kiosk",
+                "warning missing-type Hall",
+                "warning missing-location Hall",
+            ]
+        );
+        let config = LintConfig::from_json(r#"{"profile": "strict", "rules": {"missing-location": "off", "part-of-self": "warning"}}"#)
+            .unwrap();
+        assert_eq!(
+            rules(&config),
+            ["warning part-of-self Hall", "info missing-source Kiosk", "warning missing-type Hall", "info missing-source Hall"]
+        );
+        let config = LintConfig::from_json(r#"{"profile": "minimal", "rules": {"missing-type": "on"}}"#).unwrap();
+        assert_eq!(rules(&config), ["error part-of-self Hall", "warning missing-type Hall"]);
+        assert!(LintConfig::from_json(r#"{"rules": {"no-such-rule": "off"}}"#).is_err());
+        assert!(LintConfig::from_json(r#"{"severity": "high"}"#).is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_lint_config_toml() {
+        let config = LintConfig::from_toml("# Strict — for café data\nprofile = \"strict\"\n\n[rules]\nspike = \"error\"\n").unwrap();
+        assert_eq!(config.profile, Profile::Strict);
+        assert_eq!(config.severity(find_rule("spike").unwrap()), Some(Severity::Error));
+    }
+}
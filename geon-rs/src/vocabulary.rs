@@ -0,0 +1,141 @@
+//! The words GEON documents are written with: the sections a place can
+//! have, the usual TYPE values and the EXPERIENCE scales. Editor tooling
+//! uses them for completion and hover text.
+
+use alloc::vec::Vec;
+
+/// How a section's value is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    /// `KEY: text` on one line.
+    Text,
+    /// `KEY: lat, lon`.
+    Coordinate,
+    /// A list of `- lat, lon` points.
+    Coordinates,
+    /// A list of `- text` entries.
+    List,
+    /// Indented `key: value` lines.
+    Map,
+    /// A list of nested places, each starting `- PLACE:`.
+    Places,
+    /// A list of entries that are each a map, like HISTORY events.
+    Entries,
+}
+
+/// A top-level GEON key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    pub key: &'static str,
+    pub kind: SectionKind,
+    pub summary: &'static str,
+}
+
+const fn section(key: &'static str, kind: SectionKind, summary: &'static str) -> Section {
+    Section { key, kind, summary }
+}
+
+/// Every GEON section, in specification order.
+pub const SECTIONS: &[Section] = &[
+    section("PLACE", SectionKind::Text, "Name of the place. Starts every document."),
+    section("TYPE", SectionKind::Text, "Kind of place, e.g. `public_space`, `building` or `street`."),
+    section("ID", SectionKind::Text, "Stable identifier, usually `source:id` such as `osm:way/123`."),
+    section("LOCATION", SectionKind::Coordinate, "Representative point as `lat, lon` in WGS 84."),
+    section("BOUNDARY", SectionKind::Coordinates, "Outline ring as a list of `lat, lon` points."),
+    section("PATH", SectionKind::Coordinates, "Centre line of a linear place, as a list of `lat, lon` points."),
+    section("EXTENT", SectionKind::Text, "Bounding box as `north, south, east, west`."),
+    section("ELEVATION", SectionKind::Text, "Height above sea level with a unit, e.g. `142 m`."),
+    section("AREA", SectionKind::Text, "Size with a unit, e.g. `1.2 ha` or `850 sqm`."),
+    section("PURPOSE", SectionKind::List, "What the place is for: one use per entry."),
+    section("EXPERIENCE", SectionKind::Map, "How the place feels, as `quality: level` on the GEON scales."),
+    section("CHARACTER", SectionKind::List, "Descriptive traits, one per entry."),
+    section("ADJACENCIES", SectionKind::List, "Neighbouring places, e.g. `Council House (210m east)`."),
+    section("CONNECTIVITY", SectionKind::Map, "How the place is reached: modes, routes and walking times."),
+    section("CONTAINS", SectionKind::Places, "Places inside this one, each a nested document."),
+    section("PART_OF", SectionKind::Text, "Name or ID of the place this one belongs to."),
+    section("VIEWSHEDS", SectionKind::Entries, "What can be seen from the place, and from where."),
+    section("TEMPORAL", SectionKind::Map, "How the place changes through the day, week or year."),
+    section("LIFESPAN", SectionKind::Map, "When the place was established, rebuilt or closed."),
+    section("SOURCE", SectionKind::List, "Where the description comes from, one source per entry."),
+    section("CONFIDENCE", SectionKind::Map, "How reliable each part is: `high`, `medium` or `low`."),
+    section("UPDATED", SectionKind::Text, "Date of the last revision, ISO 8601."),
+    section("BUILT_FORM", SectionKind::Map, "Buildings and structures: height, storeys, materials."),
+    section("ECOLOGY", SectionKind::Map, "Habitats, canopy cover and species."),
+    section("INFRASTRUCTURE", SectionKind::Map, "Utilities, lighting, seating and other services."),
+    section("DEMOGRAPHICS", SectionKind::Map, "Who lives, works or visits here."),
+    section("ECONOMY", SectionKind::Map, "Businesses, rents and footfall."),
+    section("VISUAL", SectionKind::Map, "Views, colours and visual landmarks."),
+    section("HISTORY", SectionKind::Entries, "Dated events, each with at least `event` and `year`."),
+    section("VERTICAL_PROFILE", SectionKind::Map, "Layers above and below ground, by height or level."),
+];
+
+/// The section called `key`, ignoring case.
+pub fn find_section(key: &str) -> Option<&'static Section> {
+    SECTIONS.iter().find(|s| s.key.eq_ignore_ascii_case(key))
+}
+
+/// TYPE values the converters produce; others are allowed.
+pub const TYPES: &[&str] = &[
+    "public_space", "street", "transport_hub", "building", "landmark", "threshold", "infrastructure",
+    "natural_feature", "district", "hybrid",
+];
+
+/// The EXPERIENCE scales, lowest step first. The first is the general
+/// scale for qualities without their own words.
+pub const SCALES: &[[&str; 5]] = &[
+    ["very_low", "low", "medium", "high", "very_high"],
+    ["very_quiet", "quiet", "moderate", "loud", "very_loud"],
+    ["very_simple", "simple", "moderate", "complex", "very_complex"],
+    ["very_poor", "poor", "moderate", "good", "very_good"],
+    ["deserted", "sparse", "moderate", "busy", "crowded"],
+    ["very_unsafe", "unsafe", "neutral", "safe", "very_safe"],
+    ["very_private", "semi_private", "semi_public", "public", "very_public"],
+    ["very_slow", "slow", "moderate", "fast", "very_fast"],
+    ["very_transient", "transient", "stable", "permanent", "very_permanent"],
+];
+
+/// Common EXPERIENCE qualities, each with the index of its scale in
+/// [`SCALES`].
+pub const EXPERIENCE_KEYS: &[(&str, usize)] = &[
+    ("noise", 1),
+    ("noise_level", 1),
+    ("complexity", 2),
+    ("walkability", 3),
+    ("crowding", 4),
+    ("activity_density", 4),
+    ("safety", 5),
+    ("publicness", 6),
+    ("pace", 7),
+    ("permanence", 8),
+    ("openness", 0),
+    ("shade", 0),
+    ("greenery", 0),
+];
+
+/// The scale an EXPERIENCE quality is rated on: its own if it is one of
+/// [`EXPERIENCE_KEYS`], else the general one.
+pub fn scale_for(key: &str) -> &'static [&'static str; 5] {
+    let index = EXPERIENCE_KEYS.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map_or(0, |(_, i)| *i);
+    &SCALES[index]
+}
+
+/// Every scale containing `word`, with its step (0 to 4) on that scale.
+pub fn scales_with(word: &str) -> Vec<(&'static [&'static str; 5], usize)> {
+    SCALES.iter().filter_map(|scale| Some((scale, scale.iter().position(|w| *w == word)?))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vocabulary() {
+        assert_eq!(find_section("experience").unwrap().kind, SectionKind::Map);
+        assert!(find_section("SMELL").is_none());
+        assert_eq!(scale_for("Noise")[4], "very_loud");
+        assert_eq!(scale_for("birdsong"), &SCALES[0]);
+        let moderate = scales_with("moderate");
+        assert_eq!(moderate.len(), 5);
+        assert!(moderate.iter().all(|(_, step)| *step == 2));
+    }
+}
@@ -0,0 +1,126 @@
+//! The `geon` binary, run on the files under tests/fixtures.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+// Exit status, standard output and standard error of `geon args...`, run in
+// the fixtures directory with `stdin` as its input
+fn geon(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_geon"))
+        .args(args)
+        .current_dir(FIXTURES)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("geon runs");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let text = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap();
+    (output.status.code().unwrap(), text(output.stdout), text(output.stderr))
+}
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(Path::new(FIXTURES).join(name)).unwrap()
+}
+
+#[test]
+fn test_parse_and_generate() {
+    let (code, json, _) = geon(&["parse", "square.geon"], "");
+    assert_eq!(code, 0);
+    let place: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!((place["place"].as_str(), place["id"].as_str()), (Some("Old Market Square"), Some("osm:way/1")));
+    assert_eq!(place["purpose"], serde_json::json!(["markets", "civic events"]));
+
+    let (code, text, _) = geon(&["generate"], &json);
+    assert_eq!(code, 0);
+    let (_, again, _) = geon(&["parse"], &text);
+    assert_eq!(again, json);
+
+    let (code, _, err) = geon(&["generate"], "{\"place\": ");
+    assert_eq!(code, 2);
+    assert!(err.starts_with("geon: invalid JSON"), "{}", err);
+}
+
+#[test]
+fn test_validate_and_lint() {
+    let (code, out, err) = geon(&["validate", "square.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str(), err.as_str()), (0, "", "2 place(s), 0 error(s), 0 other issue(s)\n"));
+
+    // Without a config, a missing TYPE is a warning and lint passes
+    let (code, out, _) = geon(&["lint", "square.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str()), (0, "kiosk.geon: [warning] Kiosk: TYPE: place has no TYPE (missing-type)\n"));
+
+    // lint.toml (with a non-ASCII comment) makes it an error
+    let (code, out, err) = geon(&["lint", "--config", "lint.toml", "square.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str(), err.as_str()), (1, "kiosk.geon: [error] Kiosk: TYPE: place has no TYPE (missing-type)\n", ""));
+    let (code, out, _) = geon(&["lint", "--config", "lint.toml", "--format", "json", "kiosk.geon"], "");
+    let issues: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!((code, issues[0]["rule"].as_str(), issues[0]["file"].as_str()), (1, Some("missing-type"), Some("kiosk.geon")));
+
+    let (code, _, err) = geon(&["lint", "--config", "missing.toml", "kiosk.geon"], "");
+    assert_eq!(code, 2);
+    assert!(err.starts_with("geon: missing.toml: "), "{}", err);
+}
+
+#[test]
+fn test_convert_and_stats() {
+    let (code, out, _) = geon(&["convert", "square.geon", "--to", "geojson"], "");
+    let geojson: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(geojson["features"][0]["geometry"], serde_json::json!({"type": "Point", "coordinates": [-1.15, 52.953]}));
+    let (code, _, err) = geon(&["convert", "square.geon", "--to", "shp"], "");
+    assert_eq!((code, err.as_str()), (2, "geon: cannot write 'shp'\n"));
+
+    let (code, out, _) = geon(&["stats", "--json", "square.geon", "kiosk.geon"], "");
+    let stats: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(stats["places"], 2);
+    assert_eq!(stats["by_type"], serde_json::json!({"public_space": 1, "unknown": 1}));
+    assert_eq!(stats["field_coverage"]["TYPE"], 50.0);
+}
+
+#[test]
+fn test_diff() {
+    let (code, out, _) = geon(&["diff", "square.geon", "square.geon"], "");
+    assert_eq!((code, out.as_str()), (0, ""));
+    // Like diff(1), status 1 when the files differ
+    let (code, out, _) = geon(&["diff", "square.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str()), (1, "+ Kiosk\n- Old Market Square (osm:way/1)\n"));
+    let (code, _, _) = geon(&["diff", "square.geon"], "");
+    assert_eq!(code, 2);
+}
+
+#[test]
+fn test_fmt() {
+    let (code, out, _) = geon(&["fmt"], &fixture("messy.geon"));
+    assert_eq!((code, out), (0, fixture("square.geon")));
+    let (code, out, _) = geon(&["fmt", "--check", "square.geon", "messy.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str()), (1, "messy.geon\n"));
+    let (code, out, _) = geon(&["fmt", "--check", "square.geon", "kiosk.geon"], "");
+    assert_eq!((code, out.as_str()), (0, ""));
+
+    // Rewriting in place, after which the file checks clean
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fmt");
+    std::fs::create_dir_all(&dir).unwrap();
+    let messy = dir.join("messy.geon");
+    std::fs::write(&messy, fixture("messy.geon")).unwrap();
+    let path = messy.to_str().unwrap();
+    assert_eq!(geon(&["fmt", path], "").0, 0);
+    assert_eq!(std::fs::read_to_string(&messy).unwrap(), fixture("square.geon"));
+    assert_eq!(geon(&["fmt", "--check", path], ""), (0, String::new(), String::new()));
+
+    // EXPERIENCE's entries are indented past where the parser reads them;
+    // fmt refuses rather than turning them into a section, and leaves the file alone
+    let wide = dir.join("wide.geon");
+    std::fs::write(&wide, fixture("wide.geon")).unwrap();
+    let (code, _, err) = geon(&["fmt", wide.to_str().unwrap()], "");
+    assert_eq!(code, 2);
+    assert!(err.contains("wide.geon: Invalid structure: line 4 is indented 4 spaces"), "{}", err);
+    assert_eq!(std::fs::read_to_string(&wide).unwrap(), fixture("wide.geon"));
+    let (code, out, _) = geon(&["fmt", "--check"], &fixture("wide.geon"));
+    assert_eq!((code, out.as_str()), (2, ""));
+}